@@ -15,6 +15,7 @@ use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracer_backend::TracerController;
 
+use crate::exit_code::CliError;
 use crate::session_state::{self, SessionState, SessionStatus};
 
 #[derive(Subcommand)]
@@ -514,9 +515,9 @@ fn stop_ada_recorder(child: &mut Child) -> anyhow::Result<()> {
 fn stop_capture(session_id: Option<String>) -> anyhow::Result<()> {
     // Find the session to stop
     let session = if let Some(id) = session_id {
-        session_state::get(&id)?.ok_or_else(|| anyhow::anyhow!("Session {} not found", id))?
+        session_state::get(&id)?.ok_or_else(|| CliError::not_found(format!("Session {} not found", id)))?
     } else {
-        session_state::latest_running()?.ok_or_else(|| anyhow::anyhow!("No running sessions found"))?
+        session_state::latest_running()?.ok_or_else(|| CliError::not_found("No running sessions found"))?
     };
 
     if session.status != SessionStatus::Running {
@@ -557,7 +558,7 @@ fn map_tracer_result<T, E>(result: Result<T, E>) -> anyhow::Result<T>
 where
     E: std::fmt::Display,
 {
-    result.map_err(|err| anyhow::anyhow!(err.to_string()))
+    result.map_err(|err| CliError::backend(err.to_string()))
 }
 
 #[cfg(test)]
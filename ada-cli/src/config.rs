@@ -0,0 +1,161 @@
+//! Defaults for CLI options read from `ada.toml`.
+//!
+//! The file is taken from `--config` when given, otherwise from `./ada.toml`,
+//! otherwise from `$XDG_CONFIG_HOME/ada/ada.toml` (`~/.config/ada/ada.toml`
+//! when `XDG_CONFIG_HOME` is unset). Flags on the command line override the
+//! file, which overrides built-in defaults:
+//!
+//! ```toml
+//! output_dir = "./traces"      # trace start/attach --output
+//! trace_root = "./traces"      # trace list directory
+//! tracer_path = "/opt/ada/bin/tracer"
+//! output_format = "json"       # --output-format
+//! ```
+//!
+//! Unknown keys are reported as warnings so older CLIs accept newer files.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::output::OutputFormat;
+
+const CONFIG_FILE: &str = "ada.toml";
+const KNOWN_KEYS: [&str; 4] = ["output_dir", "trace_root", "tracer_path", "output_format"];
+
+/// Values from `ada.toml`; unset keys fall back to built-in defaults
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct Config {
+    pub output_dir: Option<PathBuf>,
+    pub trace_root: Option<PathBuf>,
+    pub tracer_path: Option<PathBuf>,
+    pub output_format: Option<OutputFormat>,
+}
+
+impl Config {
+    /// Load the config file, or defaults when no file is found
+    ///
+    /// An explicit path must exist; discovered files are optional.
+    pub fn load(explicit: Option<&Path>) -> Result<Self> {
+        let path = match explicit {
+            Some(path) => path.to_path_buf(),
+            None => match discover(Path::new("."), config_home()) {
+                Some(path) => path,
+                None => return Ok(Config::default()),
+            },
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {:?}", path))?;
+        let (config, unknown) =
+            Self::parse(&content).with_context(|| format!("Invalid config file {:?}", path))?;
+        // stderr keeps warnings out of JSON/YAML written to stdout
+        for key in unknown {
+            eprintln!(
+                "warning: ignoring unknown key '{}' in {}",
+                key,
+                path.display()
+            );
+        }
+        Ok(config)
+    }
+
+    /// Parse config text, returning the config and any unknown keys
+    fn parse(content: &str) -> Result<(Self, Vec<String>)> {
+        let table: toml::Table = toml::from_str(content)?;
+        let unknown = table
+            .keys()
+            .filter(|key| !KNOWN_KEYS.contains(&key.as_str()))
+            .cloned()
+            .collect();
+        let config = table.try_into()?;
+        Ok((config, unknown))
+    }
+}
+
+/// `$XDG_CONFIG_HOME`, falling back to `~/.config`
+fn config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Find `ada.toml` in `cwd`, then in `<config_home>/ada/`
+fn discover(cwd: &Path, config_home: Option<PathBuf>) -> Option<PathBuf> {
+    let local = cwd.join(CONFIG_FILE);
+    if local.is_file() {
+        return Some(local);
+    }
+    config_home
+        .map(|dir| dir.join("ada").join(CONFIG_FILE))
+        .filter(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_config__parse_known_keys__then_all_set() {
+        let (config, unknown) = Config::parse(
+            r#"
+            output_dir = "/tmp/traces"
+            trace_root = "/tmp/root"
+            tracer_path = "/opt/tracer"
+            output_format = "yaml"
+            "#,
+        )
+        .unwrap();
+
+        assert!(unknown.is_empty());
+        assert_eq!(
+            config,
+            Config {
+                output_dir: Some(PathBuf::from("/tmp/traces")),
+                trace_root: Some(PathBuf::from("/tmp/root")),
+                tracer_path: Some(PathBuf::from("/opt/tracer")),
+                output_format: Some(OutputFormat::Yaml),
+            }
+        );
+    }
+
+    #[test]
+    fn test_config__parse_unknown_key__then_reported_not_rejected() {
+        let (config, unknown) = Config::parse("output_dir = \"x\"\nfuture_option = 1\n").unwrap();
+        assert_eq!(config.output_dir, Some(PathBuf::from("x")));
+        assert_eq!(unknown, vec!["future_option".to_string()]);
+    }
+
+    #[test]
+    fn test_config__parse_bad_value__then_error() {
+        assert!(Config::parse("output_format = \"xml\"").is_err());
+        assert!(Config::parse("output_dir = ").is_err());
+    }
+
+    #[test]
+    fn test_config__discover__then_cwd_before_config_home() {
+        let cwd = TempDir::new().unwrap();
+        let home = TempDir::new().unwrap();
+        let home_config = home.path().join("ada").join(CONFIG_FILE);
+        std::fs::create_dir_all(home_config.parent().unwrap()).unwrap();
+        std::fs::write(&home_config, "").unwrap();
+
+        let found = discover(cwd.path(), Some(home.path().to_path_buf()));
+        assert_eq!(found, Some(home_config));
+
+        std::fs::write(cwd.path().join(CONFIG_FILE), "").unwrap();
+        let found = discover(cwd.path(), Some(home.path().to_path_buf()));
+        assert_eq!(found, Some(cwd.path().join(CONFIG_FILE)));
+
+        assert_eq!(discover(home.path(), None), None);
+    }
+
+    #[test]
+    fn test_config__load_missing_explicit__then_error() {
+        let dir = TempDir::new().unwrap();
+        assert!(Config::load(Some(&dir.path().join("missing.toml"))).is_err());
+    }
+}
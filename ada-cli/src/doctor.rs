@@ -6,6 +6,8 @@ use clap::Subcommand;
 use serde::Serialize;
 use std::path::PathBuf;
 
+use crate::exit_code::ExitCode;
+
 #[derive(Subcommand)]
 pub enum DoctorCommands {
     /// Run all health checks
@@ -41,13 +43,17 @@ struct CheckResults {
     ffmpeg: CheckResult,
 }
 
-pub fn run(cmd: DoctorCommands) -> anyhow::Result<()> {
+/// Runs a doctor command and returns the exit code it calls for
+///
+/// Failed checks are part of the printed report rather than an error, so they
+/// come back as `ExitCode::Failure` for `main` to exit with.
+pub fn run(cmd: DoctorCommands) -> anyhow::Result<ExitCode> {
     match cmd {
         DoctorCommands::Check { format } => run_checks(&format),
     }
 }
 
-fn run_checks(format: &str) -> anyhow::Result<()> {
+fn run_checks(format: &str) -> anyhow::Result<ExitCode> {
     let frida_agent = check_frida_agent();
     let whisper = check_whisper();
     let ffmpeg = check_ffmpeg();
@@ -79,10 +85,10 @@ fn run_checks(format: &str) -> anyhow::Result<()> {
     }
 
     if issues_count > 0 {
-        std::process::exit(1);
+        return Ok(ExitCode::Failure);
     }
 
-    Ok(())
+    Ok(ExitCode::Success)
 }
 
 fn print_text_report(
@@ -544,6 +550,13 @@ mod tests {
     // Report Generation Tests
     // =========================================================================
 
+    #[test]
+    fn run_checks__tools_missing__then_failure_exit_code() {
+        let result = with_envs(&[("PATH", Some(""))], || run_checks("json"));
+
+        assert_eq!(result.unwrap(), ExitCode::Failure);
+    }
+
     #[test]
     fn issues_count__all_pass__then_zero() {
         let checks = [
@@ -0,0 +1,116 @@
+//! Process exit codes for scripting.
+//!
+//! | Code | Meaning                                                  |
+//! |------|----------------------------------------------------------|
+//! | 0    | Success                                                  |
+//! | 1    | Other failure, e.g. `doctor` found issues                |
+//! | 2    | Not found: session, bundle, symbol, dSYM, binary or file |
+//! | 3    | Invalid arguments, options or config file                |
+//! | 4    | Backend error: tracer, symbol resolver or external tool  |
+//! | 5    | I/O error                                                |
+//!
+//! Commands attach a code by returning a [`CliError`]; errors without one
+//! are classified by [`for_error`] from the `std::io::Error` in their chain.
+
+/// Exit code of the `ada` process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    Failure = 1,
+    NotFound = 2,
+    InvalidArgs = 3,
+    Backend = 4,
+    Io = 5,
+}
+
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        std::process::ExitCode::from(code as u8)
+    }
+}
+
+/// An error that exits the process with a specific code
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct CliError {
+    pub code: ExitCode,
+    message: String,
+}
+
+impl CliError {
+    pub fn with_code(code: ExitCode, message: impl Into<String>) -> anyhow::Error {
+        CliError {
+            code,
+            message: message.into(),
+        }
+        .into()
+    }
+
+    pub fn not_found(message: impl Into<String>) -> anyhow::Error {
+        Self::with_code(ExitCode::NotFound, message)
+    }
+
+    pub fn invalid_args(message: impl Into<String>) -> anyhow::Error {
+        Self::with_code(ExitCode::InvalidArgs, message)
+    }
+
+    pub fn backend(message: impl Into<String>) -> anyhow::Error {
+        Self::with_code(ExitCode::Backend, message)
+    }
+}
+
+/// Pick the exit code for an error returned by a command
+pub fn for_error(err: &anyhow::Error) -> ExitCode {
+    for cause in err.chain() {
+        if let Some(cli_error) = cause.downcast_ref::<CliError>() {
+            return cli_error.code;
+        }
+        if let Some(io_error) = cause.downcast_ref::<std::io::Error>() {
+            return match io_error.kind() {
+                std::io::ErrorKind::NotFound => ExitCode::NotFound,
+                _ => ExitCode::Io,
+            };
+        }
+        if cause.downcast_ref::<toml::de::Error>().is_some() {
+            return ExitCode::InvalidArgs;
+        }
+    }
+    ExitCode::Failure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_for_error__cli_error_under_context__then_its_code() {
+        let err = Err::<(), _>(CliError::not_found("Session x not found"))
+            .context("Failed to stop session")
+            .unwrap_err();
+        assert_eq!(for_error(&err), ExitCode::NotFound);
+        assert_eq!(
+            for_error(&CliError::backend("tracer crashed")),
+            ExitCode::Backend
+        );
+    }
+
+    #[test]
+    fn test_for_error__io_errors__then_not_found_or_io() {
+        let missing = std::fs::read("/nonexistent/ada/manifest.json")
+            .context("Failed to read manifest")
+            .unwrap_err();
+        assert_eq!(for_error(&missing), ExitCode::NotFound);
+
+        let denied = anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert_eq!(for_error(&denied), ExitCode::Io);
+    }
+
+    #[test]
+    fn test_for_error__untyped_error__then_failure() {
+        assert_eq!(
+            for_error(&anyhow::anyhow!("something else")),
+            ExitCode::Failure
+        );
+    }
+}
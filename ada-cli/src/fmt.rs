@@ -0,0 +1,82 @@
+//! Human-readable formatting for durations and sizes in CLI output.
+
+/// Format a nanosecond duration with an automatically chosen unit
+///
+/// Values below one second keep up to two decimals (`999ns`, `1.23ms`,
+/// `1.5s`); longer values are split into minutes and hours (`2m 05s`).
+pub fn duration_ns(ns: u64) -> String {
+    const UNITS: [(&str, f64); 3] = [("us", 1e3), ("ms", 1e6), ("s", 1e9)];
+
+    if ns < 1_000 {
+        return format!("{}ns", ns);
+    }
+
+    let secs = ns / 1_000_000_000;
+    if secs >= 3600 {
+        return format!("{}h {:02}m", secs / 3600, secs % 3600 / 60);
+    }
+    if secs >= 60 {
+        return format!("{}m {:02}s", secs / 60, secs % 60);
+    }
+
+    for (i, (unit, scale)) in UNITS.iter().enumerate() {
+        let value = (ns as f64 / scale * 100.0).round() / 100.0;
+        // Move up a unit when rounding would print e.g. "1000us"
+        if value < 1000.0 || i == UNITS.len() - 1 {
+            return format!("{}{}", trim_decimals(value), unit);
+        }
+    }
+    unreachable!("the last unit always returns")
+}
+
+/// Format a byte count with binary units (`512 B`, `4.2 MiB`)
+pub fn bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if n < 1024 {
+        return format!("{} B", n);
+    }
+
+    let mut value = n as f64 / 1024.0;
+    let mut unit = 0;
+    // Compare the rounded value so 1023.96 KiB prints as 1.0 MiB
+    while (value * 10.0).round() / 10.0 >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Print a value rounded to two decimals without trailing zeros
+fn trim_decimals(value: f64) -> String {
+    let s = format!("{:.2}", value);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_ns__unit_boundaries__then_expected_unit() {
+        assert_eq!(duration_ns(0), "0ns");
+        assert_eq!(duration_ns(999), "999ns");
+        assert_eq!(duration_ns(1_000), "1us");
+        assert_eq!(duration_ns(1_234_567), "1.23ms");
+        assert_eq!(duration_ns(999_999), "1ms");
+        assert_eq!(duration_ns(1_500_000_000), "1.5s");
+        assert_eq!(duration_ns(59_999_999_999), "60s");
+        assert_eq!(duration_ns(125_000_000_000), "2m 05s");
+        assert_eq!(duration_ns(3_720_000_000_000), "1h 02m");
+    }
+
+    #[test]
+    fn test_bytes__unit_boundaries__then_expected_unit() {
+        assert_eq!(bytes(0), "0 B");
+        assert_eq!(bytes(1023), "1023 B");
+        assert_eq!(bytes(1024), "1.0 KiB");
+        assert_eq!(bytes(4_404_019), "4.2 MiB");
+        assert_eq!(bytes(1024 * 1024 - 1), "1.0 MiB");
+        assert_eq!(bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
@@ -7,19 +7,30 @@
 //! - `ada trace` - Manage tracing sessions
 //! - `ada symbols` - Symbol resolution and dSYM management
 //! - `ada query` - Query trace data
+//!
+//! Exit codes are listed in the `exit_code` module.
 
 mod capture;
+mod config;
 mod doctor;
+mod exit_code;
 mod ffi;
+mod fmt;
+mod output;
 mod query;
 mod session_state;
+mod snapshot;
 mod symbols;
+mod table;
 mod trace;
+mod validate;
 
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
-use tracing_subscriber::{fmt, EnvFilter};
+use clap::{CommandFactory, Parser, Subcommand};
+use exit_code::ExitCode;
+use output::OutputFormat;
+use tracing_subscriber::EnvFilter;
 
 /// ADA - Application Dynamic Analysis
 ///
@@ -29,9 +40,21 @@ use tracing_subscriber::{fmt, EnvFilter};
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
-    /// Enable verbose output
-    #[arg(short, long, global = true)]
-    verbose: bool,
+    /// Log more: -v info, -vv debug, -vvv trace [default: warnings only]
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Log errors only
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Output format for command results [default: text]
+    #[arg(long, global = true, value_enum)]
+    output_format: Option<OutputFormat>,
+
+    /// Config file with option defaults [default: ./ada.toml or $XDG_CONFIG_HOME/ada/ada.toml]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
 
     #[command(subcommand)]
     command: Commands,
@@ -59,6 +82,23 @@ enum Commands {
     #[command(subcommand)]
     Doctor(doctor::DoctorCommands),
 
+    /// Generate shell completion scripts
+    ///
+    /// Example: ada completions zsh > ~/.zfunc/_ada
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+
+    /// Generate roff man pages for ada and every subcommand
+    #[command(hide = true)]
+    Man {
+        /// Directory to write the pages to (created if missing)
+        #[arg(short, long, default_value = "./man")]
+        output: PathBuf,
+    },
+
     // LCOV_EXCL_START - Struct field definitions
     /// Query trace data from a bundle
     ///
@@ -72,6 +112,7 @@ enum Commands {
     ///   ada query session_2026_01_24_14_56_19_a1b2c3 events --limit 100
     ///   ada query ~/.ada/sessions/session_xxx/ events --thread 0 --limit 50
     ///   ada query /path/to/bundle.adabundle functions
+    ///   ada query @latest events --start +500ms --end +2s
     Query {
         /// Bundle path: @latest, session ID, or directory path
         bundle: PathBuf,
@@ -119,6 +160,14 @@ pub enum QueryCommands {
         #[arg(long)]
         until_ns: Option<u64>,
 
+        /// Filter events at or after this time: 1500000 (ns), 2.5s, or +500ms from trace start
+        #[arg(long, conflicts_with = "since_ns")]
+        start: Option<query::TimeSpec>,
+
+        /// Filter events at or before this time: 1500000 (ns), 2.5s, or +500ms from trace start
+        #[arg(long, conflicts_with = "until_ns")]
+        end: Option<query::TimeSpec>,
+
         /// Output format (text, json, or line)
         #[arg(short = 'f', long, default_value = "text")]
         format: String,
@@ -147,6 +196,14 @@ pub enum QueryCommands {
         #[arg(short, long, default_value = "1000")]
         limit: usize,
 
+        /// Only calls at or after this time: 1500000 (ns), 2.5s, or +500ms from trace start
+        #[arg(long)]
+        start: Option<query::TimeSpec>,
+
+        /// Only calls at or before this time: 1500000 (ns), 2.5s, or +500ms from trace start
+        #[arg(long)]
+        end: Option<query::TimeSpec>,
+
         /// Output format (text, json, or line)
         #[arg(short = 'f', long, default_value = "text")]
         format: String,
@@ -221,28 +278,80 @@ pub enum TranscribeCommands {
 }
 // LCOV_EXCL_STOP
 
-fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    fmt::Subscriber::builder()
+fn main() -> std::process::ExitCode {
+    // Usage errors exit with InvalidArgs instead of clap's default of 2,
+    // which the exit code table reserves for NotFound
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            let _ = err.print();
+            return match err.use_stderr() {
+                true => ExitCode::InvalidArgs.into(),
+                false => ExitCode::Success.into(),
+            };
+        }
+    };
+
+    init_logging(cli.verbose, cli.quiet);
+
+    match run(cli) {
+        Ok(code) => code.into(),
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            exit_code::for_error(&err).into()
+        }
+    }
+}
+
+/// Initialize logging to stderr so logs never mix with command output
+///
+/// `RUST_LOG` is honored only when neither `-v` nor `-q` is given.
+fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => "error",
+        (false, 0) => "warn",
+        (false, 1) => "info",
+        (false, 2) => "debug",
+        (false, _) => "trace",
+    };
+    let filter = match (quiet, verbose) {
+        (false, 0) => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level)),
+        _ => EnvFilter::new(level),
+    };
+    tracing_subscriber::fmt::Subscriber::builder()
         .with_env_filter(filter)
         .with_target(false)
+        .with_writer(std::io::stderr)
         .init();
+}
 
-    let cli = Cli::parse();
-
-    if cli.verbose {
-        tracing::info!("Verbose mode enabled");
-    }
+fn run(cli: Cli) -> anyhow::Result<ExitCode> {
+    // Flags override the config file, which overrides built-in defaults
+    let config = config::Config::load(cli.config.as_deref())?;
+    let output_format = cli
+        .output_format
+        .or(config.output_format)
+        .unwrap_or_default();
 
     // LCOV_EXCL_START - CLI entry point, tested via integration
     match cli.command {
-        Commands::Trace(cmd) => trace::run(cmd),
-        Commands::Symbols(cmd) => symbols::run(cmd),
-        Commands::Capture(cmd) => capture::run(cmd),
-        Commands::Session(cmd) => session_state::run(cmd),
-        Commands::Doctor(cmd) => doctor::run(cmd),
-        Commands::Query { bundle, command } => query::run(&bundle, command),
+        Commands::Trace(cmd) => trace::run(cmd, output_format, &config)?,
+        Commands::Symbols(cmd) => symbols::run(cmd, output_format)?,
+        Commands::Capture(cmd) => capture::run(cmd)?,
+        Commands::Session(cmd) => session_state::run(cmd)?,
+        // Doctor prints its report and picks the exit code itself
+        Commands::Doctor(cmd) => return doctor::run(cmd),
+        Commands::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "ada", &mut std::io::stdout());
+        }
+        Commands::Man { output } => {
+            std::fs::create_dir_all(&output)?;
+            clap_mangen::generate_to(Cli::command(), &output)?;
+            println!("Man pages written to: {}", output.display());
+        }
+        Commands::Query { bundle, command } => query::run(&bundle, command, output_format)?,
     }
     // LCOV_EXCL_STOP
+
+    Ok(ExitCode::Success)
 }
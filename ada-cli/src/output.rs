@@ -0,0 +1,78 @@
+//! Output format selected by the global `--output-format` flag.
+//!
+//! Commands build a serializable result and hand it to [`emit`] together
+//! with their text renderer, so JSON and YAML output always match the fields
+//! the text view is built from.
+
+use serde::{Deserialize, Serialize};
+
+/// Output format for command results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+/// Render a command result in the selected format
+pub fn render<T: Serialize>(
+    result: &T,
+    format: OutputFormat,
+    text: impl FnOnce(&T) -> String,
+) -> anyhow::Result<String> {
+    Ok(match format {
+        OutputFormat::Text => text(result),
+        OutputFormat::Json => format!("{}\n", serde_json::to_string_pretty(result)?),
+        OutputFormat::Yaml => serde_yaml::to_string(result)?,
+    })
+}
+
+/// Print a command result in the selected format
+pub fn emit<T: Serialize>(
+    result: &T,
+    format: OutputFormat,
+    text: impl FnOnce(&T) -> String,
+) -> anyhow::Result<()> {
+    print!("{}", render(result, format, text)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Info {
+        name: String,
+        count: u32,
+    }
+
+    fn info() -> Info {
+        Info {
+            name: "main".to_string(),
+            count: 2,
+        }
+    }
+
+    #[test]
+    fn test_render__text__then_uses_text_renderer() {
+        let output = render(&info(), OutputFormat::Text, |i| {
+            format!("{} x{}\n", i.name, i.count)
+        });
+        assert_eq!(output.unwrap(), "main x2\n");
+    }
+
+    #[test]
+    fn test_render__json__then_pretty_json() {
+        let output = render(&info(), OutputFormat::Json, |_| unreachable!()).unwrap();
+        assert_eq!(output, "{\n  \"name\": \"main\",\n  \"count\": 2\n}\n");
+    }
+
+    #[test]
+    fn test_render__yaml__then_yaml_document() {
+        let output = render(&info(), OutputFormat::Yaml, |_| unreachable!()).unwrap();
+        assert_eq!(output, "name: main\ncount: 2\n");
+    }
+}
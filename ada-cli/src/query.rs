@@ -9,27 +9,31 @@ mod events;
 mod output;
 mod screenshot;
 mod session;
+mod time_spec;
 mod transcribe;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 
+use crate::exit_code::CliError;
+use crate::output::OutputFormat as GlobalFormat;
 use crate::{QueryCommands, TranscribeCommands};
 use bundle::Bundle;
 use output::OutputFormat;
+pub use time_spec::TimeSpec;
 
 /// Run a query against a bundle
 ///
 /// Layer 1: Open and validate the bundle manifest
 /// Layer 2: Dispatch to appropriate data source based on query type
 // LCOV_EXCL_START - Integration function requires real session files
-pub fn run(bundle_path: &Path, cmd: QueryCommands) -> Result<()> {
+pub fn run(bundle_path: &Path, cmd: QueryCommands, global: GlobalFormat) -> Result<()> {
     // Handle capabilities query first - doesn't need bundle
     if let QueryCommands::Capabilities { format } = &cmd {
-        let fmt = parse_format(format)?;
+        let fmt = parse_format(format, global)?;
         let caps = capabilities::Capabilities::detect();
-        println!("{}", capabilities::format_capabilities(&caps, fmt));
+        print_output(capabilities::format_capabilities(&caps, fmt), global)?;
         return Ok(());
     }
 
@@ -39,12 +43,12 @@ pub fn run(bundle_path: &Path, cmd: QueryCommands) -> Result<()> {
     // Handle media queries that don't need trace session
     match &cmd {
         QueryCommands::Transcribe(transcribe_cmd) => {
-            return execute_transcribe_query(&bundle, transcribe_cmd);
+            return execute_transcribe_query(&bundle, transcribe_cmd, global);
         }
         QueryCommands::Screenshot { time, output, format } => {
-            let fmt = parse_format(format)?;
+            let fmt = parse_format(format, global)?;
             let result = screenshot::extract_screenshot(&bundle, *time, output.as_deref())?;
-            println!("{}", screenshot::format_screenshot(&result, fmt));
+            print_output(screenshot::format_screenshot(&result, fmt), global)?;
             return Ok(());
         }
         _ => {}
@@ -54,16 +58,45 @@ pub fn run(bundle_path: &Path, cmd: QueryCommands) -> Result<()> {
     // All current queries are trace queries - need ATF data
     let session = session::Session::open(&bundle.trace_path())?;
 
-    execute_trace_query(&session, cmd)
+    execute_trace_query(&session, cmd, global)
+}
+
+/// Trace session directory of a bundle
+///
+/// A trace directory, recognized by its `thread_*` directories, is used
+/// as-is, so it resolves even when its manifest is missing or damaged.
+pub fn trace_path(bundle_path: &Path) -> Result<PathBuf> {
+    let is_trace_dir = std::fs::read_dir(bundle_path).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("thread_"))
+    });
+    if is_trace_dir {
+        return Ok(bundle_path.to_path_buf());
+    }
+    Ok(Bundle::open(bundle_path)?.trace_path())
+}
+
+/// Print every trace event of a bundle in the canonical dump format
+pub fn dump(bundle_path: &Path) -> Result<()> {
+    let bundle = Bundle::open(bundle_path)?;
+    let session = session::Session::open(&bundle.trace_path())?;
+    let events = session.all_events()?;
+    print!("{}", output::format_dump(&events, &session));
+    Ok(())
 }
 
 /// Execute a transcribe query
-fn execute_transcribe_query(bundle: &Bundle, cmd: &TranscribeCommands) -> Result<()> {
+fn execute_transcribe_query(
+    bundle: &Bundle,
+    cmd: &TranscribeCommands,
+    global: GlobalFormat,
+) -> Result<()> {
     match cmd {
         TranscribeCommands::Info { format } => {
-            let fmt = parse_format(format)?;
+            let fmt = parse_format(format, global)?;
             let info = transcribe::get_info(bundle)?;
-            println!("{}", transcribe::format_info(&info, fmt));
+            print_output(transcribe::format_info(&info, fmt), global)?;
         }
         TranscribeCommands::Segments {
             offset,
@@ -72,21 +105,25 @@ fn execute_transcribe_query(bundle: &Bundle, cmd: &TranscribeCommands) -> Result
             until,
             format,
         } => {
-            let fmt = parse_format(format)?;
+            let fmt = parse_format(format, global)?;
             let result = transcribe::get_segments(bundle, *offset, *limit, *since, *until)?;
-            println!("{}", transcribe::format_segments(&result, fmt));
+            print_output(transcribe::format_segments(&result, fmt), global)?;
         }
     }
     Ok(())
 }
 
 /// Execute a trace query against an opened session
-fn execute_trace_query(session: &session::Session, cmd: QueryCommands) -> Result<()> {
+fn execute_trace_query(
+    session: &session::Session,
+    cmd: QueryCommands,
+    global: GlobalFormat,
+) -> Result<()> {
     match cmd {
         QueryCommands::Summary { format } => {
-            let fmt = parse_format(&format)?;
+            let fmt = parse_format(&format, global)?;
             let summary = session.summary()?;
-            println!("{}", output::format_summary(&summary, fmt));
+            print_output(output::format_summary(&summary, fmt), global)?;
         }
         QueryCommands::Events {
             thread,
@@ -95,9 +132,14 @@ fn execute_trace_query(session: &session::Session, cmd: QueryCommands) -> Result
             offset,
             since_ns,
             until_ns,
+            start,
+            end,
             format,
         } => {
-            let fmt = parse_format(&format)?;
+            let fmt = parse_format(&format, global)?;
+            let time_start_ns = session.manifest.time_start_ns;
+            let since_ns = start.map(|t| t.resolve(time_start_ns)).or(since_ns);
+            let until_ns = end.map(|t| t.resolve(time_start_ns)).or(until_ns);
             let events = session.query_events(
                 thread,
                 function.as_deref(),
@@ -106,32 +148,41 @@ fn execute_trace_query(session: &session::Session, cmd: QueryCommands) -> Result
                 since_ns,
                 until_ns,
             )?;
-            println!("{}", output::format_events(&events, session, fmt));
+            print_output(output::format_events(&events, session, fmt), global)?;
         }
         QueryCommands::Functions { format } => {
-            let fmt = parse_format(&format)?;
+            let fmt = parse_format(&format, global)?;
             let symbols = session.list_symbols();
-            println!("{}", output::format_functions(&symbols, fmt));
+            print_output(output::format_functions(&symbols, fmt), global)?;
         }
         QueryCommands::Threads { format } => {
-            let fmt = parse_format(&format)?;
+            let fmt = parse_format(&format, global)?;
             let threads = session.list_threads();
-            println!("{}", output::format_threads(&threads, fmt));
+            print_output(output::format_threads(&threads, fmt), global)?;
         }
         QueryCommands::Calls {
             function,
             limit,
+            start,
+            end,
             format,
         } => {
-            let fmt = parse_format(&format)?;
-            let events =
-                session.query_events(None, Some(&function), Some(limit), Some(0), None, None)?;
-            println!("{}", output::format_events(&events, session, fmt));
+            let fmt = parse_format(&format, global)?;
+            let time_start_ns = session.manifest.time_start_ns;
+            let events = session.query_events(
+                None,
+                Some(&function),
+                Some(limit),
+                Some(0),
+                start.map(|t| t.resolve(time_start_ns)),
+                end.map(|t| t.resolve(time_start_ns)),
+            )?;
+            print_output(output::format_events(&events, session, fmt), global)?;
         }
         QueryCommands::TimeInfo { format } => {
-            let fmt = parse_format(&format)?;
+            let fmt = parse_format(&format, global)?;
             let time_info = session.time_info();
-            println!("{}", output::format_time_info(&time_info, fmt));
+            print_output(output::format_time_info(&time_info, fmt), global)?;
         }
         QueryCommands::Capabilities { .. } => {
             // Already handled above before opening bundle
@@ -151,9 +202,26 @@ fn execute_trace_query(session: &session::Session, cmd: QueryCommands) -> Result
 }
 
 /// Parse format string to OutputFormat
-fn parse_format(format: &str) -> Result<OutputFormat> {
+///
+/// A JSON or YAML `--output-format` overrides the per-command `--format`;
+/// YAML is rendered as JSON here and converted by `print_output`.
+fn parse_format(format: &str, global: GlobalFormat) -> Result<OutputFormat> {
+    if global != GlobalFormat::Text {
+        return Ok(OutputFormat::Json);
+    }
     format
         .parse()
-        .map_err(|e: String| anyhow::anyhow!("{}", e))
+        .map_err(CliError::invalid_args)
+}
+
+/// Print formatted query output, converting JSON to YAML when requested
+fn print_output(formatted: String, global: GlobalFormat) -> Result<()> {
+    if global == GlobalFormat::Yaml {
+        let value: serde_json::Value = serde_json::from_str(&formatted)?;
+        print!("{}", serde_yaml::to_string(&value)?);
+    } else {
+        println!("{}", formatted);
+    }
+    Ok(())
 }
 // LCOV_EXCL_STOP
@@ -13,9 +13,10 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use serde::Deserialize;
 
+use crate::exit_code::CliError;
 use crate::session_state;
 
 /// Bundle manifest structure from manifest.json
@@ -55,10 +56,10 @@ pub fn resolve_bundle_path(input: &Path) -> Result<PathBuf> {
         return match token {
             "latest" => {
                 let session = session_state::latest()?
-                    .ok_or_else(|| anyhow::anyhow!("No sessions found"))?;
+                    .ok_or_else(|| CliError::not_found("No sessions found"))?;
                 Ok(session.session_path)
             }
-            _ => bail!("Unknown token: @{}", token),
+            _ => Err(CliError::invalid_args(format!("Unknown token: @{}", token))),
         };
     }
     // LCOV_EXCL_STOP
@@ -85,18 +86,18 @@ pub fn resolve_bundle_path(input: &Path) -> Result<PathBuf> {
 
     // If input exists as a directory but has no manifest, give a helpful error
     if input.exists() && input.is_dir() {
-        bail!(
+        return Err(CliError::not_found(format!(
             "Directory exists but contains no manifest.json: {:?}\n\
              A valid bundle directory must contain manifest.json",
             input
-        );
+        )));
     }
 
-    bail!(
+    Err(CliError::not_found(format!(
         "Bundle not found: {}\n\
          Expected: @latest, session ID (session_...), or path to directory with manifest.json",
         input.display()
-    )
+    )))
 }
 
 /// An opened ADA bundle with validated manifest
@@ -122,7 +123,10 @@ impl Bundle {
         // 2. Read manifest
         let manifest_path = resolved.join("manifest.json");
         if !manifest_path.exists() {
-            bail!("No manifest.json found in bundle: {:?}", resolved);
+            return Err(CliError::not_found(format!(
+                "No manifest.json found in bundle: {:?}",
+                resolved
+            )));
         }
 
         let content = fs::read_to_string(&manifest_path)
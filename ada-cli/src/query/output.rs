@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use serde::Serialize;
 
 use super::events::{Event, EventKind};
-use super::session::{Session, SessionSummary, ThreadInfo, TimeInfo};
+use super::session::{ChildSession, DropPolicy, Session, SessionSummary, ThreadInfo, TimeInfo};
 
 /// Output format
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -160,6 +160,15 @@ fn format_summary_text(summary: &SessionSummary) -> String {
     output.push_str(&format!("Threads: {}\n", summary.thread_count));
     output.push_str(&format!("Symbols: {}\n", summary.symbol_count));
     output.push_str(&format!("Events:  {:>6}\n", format_number(summary.total_events)));
+    output.push_str(&format!(
+        "Drops:   {} ({})\n",
+        summary.drop_policy.as_str(),
+        summary.drop_policy.description()
+    ));
+
+    for child in &summary.children {
+        output.push_str(&format!("Child:   pid {} ({})\n", child.pid, child.dir));
+    }
 
     if !summary.thread_event_counts.is_empty() {
         output.push('\n');
@@ -186,6 +195,8 @@ fn format_summary_json(summary: &SessionSummary) -> String {
         thread_count: usize,
         symbol_count: usize,
         total_events: usize,
+        drop_policy: DropPolicy,
+        children: Vec<ChildSession>,
         threads: Vec<JsonThreadCount>,
     }
 
@@ -202,6 +213,8 @@ fn format_summary_json(summary: &SessionSummary) -> String {
         thread_count: summary.thread_count,
         symbol_count: summary.symbol_count,
         total_events: summary.total_events,
+        drop_policy: summary.drop_policy,
+        children: summary.children.clone(),
         threads: summary
             .thread_event_counts
             .iter()
@@ -228,7 +241,11 @@ fn format_time_info_text(time_info: &TimeInfo) -> String {
     let mut output = String::new();
     output.push_str(&format!("Time Start:  {} ns\n", time_info.time_start_ns));
     output.push_str(&format!("Time End:    {} ns\n", time_info.time_end_ns));
-    output.push_str(&format!("Duration:    {} ns ({:.3} s)\n", time_info.duration_ns, time_info.duration_secs));
+    output.push_str(&format!(
+        "Duration:    {} ns ({})\n",
+        time_info.duration_ns,
+        crate::fmt::duration_ns(time_info.duration_ns)
+    ));
     output
 }
 
@@ -449,6 +466,29 @@ fn format_events_json(events: &[Event], session: &Session) -> String {
 }
 // LCOV_EXCL_STOP
 
+/// Format events as a canonical dump, one event per line
+///
+/// Unlike the other formats there is no header, footer or truncation, so the
+/// output of two traces can be compared with `diff`:
+/// ```text
+/// 949066051830500 thread=0 CALL depth=1 fn=0x7b00000001 main
+/// ```
+pub fn format_dump(events: &[Event], session: &Session) -> String {
+    let mut output = String::new();
+    for event in events {
+        output.push_str(&format!(
+            "{} thread={} {} depth={} fn=0x{:x} {}\n",
+            event.timestamp_ns,
+            event.thread_id,
+            event.kind,
+            event.depth,
+            event.function_id,
+            session.resolve_symbol(event.function_id).unwrap_or("<unknown>")
+        ));
+    }
+    output
+}
+
 /// Format number with thousands separators
 fn format_number(n: usize) -> String {
     let s = n.to_string();
@@ -512,12 +552,19 @@ mod tests {
             symbol_count: 10,
             total_events: 100,
             thread_event_counts: vec![(0, 60), (1, 40)],
+            drop_policy: DropPolicy::DropNewest,
+            children: vec![ChildSession {
+                pid: 4242,
+                dir: "children/pid_4242".to_string(),
+            }],
         };
 
         let output = format_summary(&summary, OutputFormat::Text);
         assert!(output.contains("Session: test_session"));
         assert!(output.contains("Threads: 2"));
         assert!(output.contains("Thread 0:") && output.contains("60 events"));
+        assert!(output.contains("Drops:   drop_newest (new events were discarded"));
+        assert!(output.contains("Child:   pid 4242 (children/pid_4242)"));
     }
 
     #[test]
@@ -530,6 +577,8 @@ mod tests {
             symbol_count: 5,
             total_events: 50,
             thread_event_counts: vec![(0, 50)],
+            drop_policy: DropPolicy::DropOldest,
+            children: Vec::new(),
         };
 
         let output = format_summary(&summary, OutputFormat::Json);
@@ -537,6 +586,8 @@ mod tests {
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
         assert_eq!(parsed["session_name"], "test_session");
         assert_eq!(parsed["total_events"], 50);
+        assert_eq!(parsed["drop_policy"], "drop_oldest");
+        assert_eq!(parsed["children"], serde_json::json!([]));
     }
 
     #[test]
@@ -770,7 +821,7 @@ mod tests {
         let output = super::format_time_info(&time_info, OutputFormat::Text);
         assert!(output.contains("Time Start:  1000000000 ns"));
         assert!(output.contains("Time End:    2000000000 ns"));
-        assert!(output.contains("Duration:    1000000000 ns (1.000 s)"));
+        assert!(output.contains("Duration:    1000000000 ns (1s)"));
     }
 
     #[test]
@@ -789,4 +840,39 @@ mod tests {
         assert_eq!(parsed["duration_ns"], 1000000000u64);
         assert_eq!(parsed["duration_secs"], 1.0);
     }
+
+    #[test]
+    fn test_format_dump__events__then_one_line_each_without_header() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manifest = r#"{
+            "threads": [{"id": 0}],
+            "symbols": [{"function_id": "0x7b00000001", "module_id": 123, "name": "main"}]
+        }"#;
+        std::fs::write(temp_dir.path().join("manifest.json"), manifest).unwrap();
+        let session = Session::open(temp_dir.path()).unwrap();
+
+        let events = vec![
+            Event {
+                timestamp_ns: 1000,
+                function_id: 0x7b00000001,
+                thread_id: 0,
+                kind: EventKind::Call,
+                depth: 1,
+            },
+            Event {
+                timestamp_ns: 1500,
+                function_id: 0x7b00000002,
+                thread_id: 0,
+                kind: EventKind::Return,
+                depth: 1,
+            },
+        ];
+
+        assert_eq!(
+            format_dump(&events, &session),
+            "1000 thread=0 CALL depth=1 fn=0x7b00000001 main\n\
+             1500 thread=0 RETURN depth=1 fn=0x7b00000002 <unknown>\n"
+        );
+        assert_eq!(format_dump(&[], &session), "");
+    }
 }
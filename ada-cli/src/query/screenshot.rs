@@ -10,6 +10,7 @@ use anyhow::{bail, Context, Result};
 use serde::Serialize;
 
 use super::bundle::Bundle;
+use crate::exit_code::CliError;
 use super::output::OutputFormat;
 
 /// Screenshot extraction result
@@ -35,20 +36,22 @@ pub fn extract_screenshot(
     output_path: Option<&Path>,
 ) -> Result<ScreenshotResult> {
     let screen_path = bundle.screen_path().ok_or_else(|| {
-        anyhow::anyhow!("Session has no screen recording. Use --screen flag during capture.")
+        CliError::not_found("Session has no screen recording. Use --screen flag during capture.")
     })?;
 
     if !screen_path.exists() {
-        bail!(
+        return Err(CliError::not_found(format!(
             "Screen recording not found at {:?}. Use --screen flag during capture.",
             screen_path
-        );
+        )));
     }
 
     // Check if ffmpeg is available
     let which_result = Command::new("which").arg("ffmpeg").output();
     if !which_result.map(|o| o.status.success()).unwrap_or(false) {
-        bail!("FFmpeg not available. Install with: brew install ffmpeg");
+        return Err(CliError::backend(
+            "FFmpeg not available. Install with: brew install ffmpeg",
+        ));
     }
 
     // Determine output path
@@ -83,7 +86,7 @@ pub fn extract_screenshot(
 
     if !ffmpeg_output.status.success() {
         let stderr = String::from_utf8_lossy(&ffmpeg_output.stderr);
-        bail!("FFmpeg failed: {}", stderr);
+        return Err(CliError::backend(format!("FFmpeg failed: {}", stderr)));
     }
 
     if !output.exists() {
@@ -4,11 +4,12 @@
 //! Use Bundle::open() first to resolve the trace path from a bundle.
 
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File, TryLockError};
+use std::io;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
-use serde::Deserialize;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
 
 use super::events::{Event, EventKind, EventReader};
 
@@ -37,6 +38,49 @@ pub struct Manifest {
     pub modules: Vec<ModuleInfo>,
     #[serde(default)]
     pub symbols: Vec<SymbolInfo>,
+    #[serde(default)]
+    pub drop_policy: DropPolicy,
+    #[serde(default)]
+    pub children: Vec<ChildSession>,
+}
+
+/// How the tracer handled events once a thread's ring buffers were full.
+/// Manifests written before the field existed used `DropOldest`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    #[default]
+    DropOldest,
+    DropNewest,
+    BlockProducer,
+}
+
+impl DropPolicy {
+    /// Manifest spelling of the policy
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DropPolicy::DropOldest => "drop_oldest",
+            DropPolicy::DropNewest => "drop_newest",
+            DropPolicy::BlockProducer => "block_producer",
+        }
+    }
+
+    /// What overflow did to the captured events
+    pub fn description(self) -> &'static str {
+        match self {
+            DropPolicy::DropOldest => "oldest undrained events were discarded to make room",
+            DropPolicy::DropNewest => "new events were discarded while buffers were full",
+            DropPolicy::BlockProducer => "traced threads waited for buffer space",
+        }
+    }
+}
+
+/// A traced child process whose session lives inside this one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildSession {
+    pub pid: u32,
+    /// Session directory relative to this session's, e.g. `children/pid_1234`
+    pub dir: String,
 }
 
 /// Thread information from manifest
@@ -81,6 +125,8 @@ pub struct SessionSummary {
     pub symbol_count: usize,
     pub total_events: usize,
     pub thread_event_counts: Vec<(u32, usize)>,
+    pub drop_policy: DropPolicy,
+    pub children: Vec<ChildSession>,
 }
 
 /// Time information for a session
@@ -101,6 +147,14 @@ impl Session {
     /// This expects a direct path to a trace directory containing manifest.json.
     /// Use Bundle::open() first to resolve the trace path from a bundle.
     pub fn open(trace_path: &Path) -> Result<Self> {
+        // The tracer holds .lock exclusively until every file is finalized
+        if is_being_written(trace_path)? {
+            bail!(
+                "Trace at {} is being written; query it after the capture stops",
+                trace_path.display()
+            );
+        }
+
         // Read manifest
         let manifest_path = trace_path.join("manifest.json");
         let manifest_content = fs::read_to_string(&manifest_path)
@@ -173,6 +227,8 @@ impl Session {
             symbol_count: self.manifest.symbols.len(),
             total_events,
             thread_event_counts,
+            drop_policy: self.manifest.drop_policy,
+            children: self.manifest.children.clone(),
         })
     }
     // LCOV_EXCL_STOP
@@ -305,11 +361,56 @@ impl Session {
         Ok(events)
     }
     // LCOV_EXCL_STOP
+
+    /// Read every event of every thread in a deterministic order
+    ///
+    /// Events are ordered by timestamp, then thread ID, then position in the
+    /// thread's index file, so reading the same trace twice yields the same
+    /// sequence. Unlike `query_events`, events of unknown kinds are kept.
+    pub fn all_events(&self) -> Result<Vec<Event>> {
+        let mut thread_ids: Vec<u32> = self.manifest.threads.iter().map(|t| t.id).collect();
+        thread_ids.sort_unstable();
+        thread_ids.dedup();
+
+        let mut events = Vec::new();
+        for thread_id in thread_ids {
+            let index_path = self
+                .path
+                .join(format!("thread_{}", thread_id))
+                .join("index.atf");
+            if !index_path.exists() {
+                continue;
+            }
+            events.extend(EventReader::open(&index_path)?.iter());
+        }
+
+        // Stable sort keeps file order for events sharing a timestamp and thread
+        events.sort_by_key(|e| (e.timestamp_ns, e.thread_id));
+        Ok(events)
+    }
+}
+
+/// Whether the tracer still holds the session lock in `trace_path`
+fn is_being_written(trace_path: &Path) -> Result<bool> {
+    let lock_path = trace_path.join(".lock");
+    let file = match File::open(&lock_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e).with_context(|| format!("Failed to open {:?}", lock_path)),
+    };
+    match file.try_lock_shared() {
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(e)) => {
+            Err(e).with_context(|| format!("Failed to check {:?}", lock_path))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::query::events::{AtfIndexFooter, AtfIndexHeader, IndexEventRaw};
     use std::io::Write;
     use tempfile::TempDir;
 
@@ -356,6 +457,20 @@ mod tests {
         assert_eq!(session.manifest.symbols.len(), 1);
     }
 
+    #[test]
+    fn test_session__capture_holds_lock__then_refused_until_released() {
+        let temp_dir = create_test_session();
+        let trace_dir = temp_dir.path().join("trace");
+        let writer_lock = fs::File::create(trace_dir.join(".lock")).unwrap();
+        writer_lock.lock().unwrap();
+
+        let err = Session::open(&trace_dir).err().unwrap();
+        assert!(err.to_string().contains("is being written"));
+
+        writer_lock.unlock().unwrap();
+        assert!(Session::open(&trace_dir).is_ok());
+    }
+
     #[test]
     fn test_session__resolve_symbol__then_found() {
         let temp_dir = create_test_session();
@@ -401,4 +516,86 @@ mod tests {
         assert_eq!(time_info.duration_ns, 1000000);
         assert!((time_info.duration_secs - 0.001).abs() < 1e-9);
     }
+
+    /// Write thread_<id>/index.atf holding `(timestamp_ns, event_kind)` events
+    fn write_index(trace_dir: &Path, thread_id: u32, events: &[(u64, u32)]) {
+        let thread_dir = trace_dir.join(format!("thread_{}", thread_id));
+        fs::create_dir_all(&thread_dir).unwrap();
+        let mut f = fs::File::create(thread_dir.join("index.atf")).unwrap();
+
+        let header = AtfIndexHeader {
+            magic: *b"ATI2",
+            endian: 0x01,
+            version: 1,
+            arch: 1,
+            os: 3,
+            flags: 0,
+            thread_id,
+            clock_type: 1,
+            _reserved1: [0; 3],
+            _reserved2: 0,
+            event_size: 32,
+            event_count: events.len() as u32,
+            events_offset: 64,
+            footer_offset: 64 + events.len() as u64 * 32,
+            time_start_ns: 0,
+            time_end_ns: 0,
+        };
+        f.write_all(unsafe {
+            std::slice::from_raw_parts(&header as *const AtfIndexHeader as *const u8, 64)
+        })
+        .unwrap();
+
+        for (i, &(timestamp_ns, event_kind)) in events.iter().enumerate() {
+            let event = IndexEventRaw {
+                timestamp_ns,
+                function_id: 0x7b00000001,
+                thread_id,
+                event_kind,
+                call_depth: i as u32,
+                detail_seq: u32::MAX,
+            };
+            f.write_all(unsafe {
+                std::slice::from_raw_parts(&event as *const IndexEventRaw as *const u8, 32)
+            })
+            .unwrap();
+        }
+
+        let footer = AtfIndexFooter {
+            magic: *b"2ITA",
+            checksum: 0,
+            event_count: events.len() as u64,
+            time_start_ns: 0,
+            time_end_ns: 0,
+            bytes_written: events.len() as u64 * 32,
+            reserved: [0; 24],
+        };
+        f.write_all(unsafe {
+            std::slice::from_raw_parts(&footer as *const AtfIndexFooter as *const u8, 64)
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_session__all_events__then_ordered_by_time_thread_and_position() {
+        let temp_dir = TempDir::new().unwrap();
+        let trace_dir = temp_dir.path();
+        let manifest = r#"{"threads": [{"id": 7}, {"id": 3}], "symbols": []}"#;
+        fs::write(trace_dir.join("manifest.json"), manifest).unwrap();
+        write_index(trace_dir, 7, &[(100, 1), (200, 1), (200, 2)]);
+        write_index(trace_dir, 3, &[(200, 1), (300, 9)]);
+
+        let session = Session::open(trace_dir).unwrap();
+        let order: Vec<_> = session
+            .all_events()
+            .unwrap()
+            .iter()
+            .map(|e| (e.timestamp_ns, e.thread_id, e.depth))
+            .collect();
+
+        assert_eq!(
+            order,
+            vec![(100, 7, 0), (200, 3, 0), (200, 7, 1), (200, 7, 2), (300, 3, 1)]
+        );
+    }
 }
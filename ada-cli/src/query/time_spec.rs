@@ -0,0 +1,132 @@
+//! Human-readable time arguments for trace queries
+//!
+//! Accepted forms:
+//! - `1500000` - absolute timestamp in nanoseconds
+//! - `100ms`, `2.5s`, `1m` - absolute timestamp with a unit (ns, us, ms, s, m, h)
+//! - `+500ms` - offset from the trace start time in the session manifest
+
+use std::str::FromStr;
+
+/// A point in time given on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSpec {
+    /// Absolute timestamp in nanoseconds
+    Absolute(u64),
+    /// Nanoseconds after the trace start
+    FromStart(u64),
+}
+
+impl TimeSpec {
+    /// Convert to an absolute timestamp given the trace start time
+    pub fn resolve(self, time_start_ns: u64) -> u64 {
+        match self {
+            TimeSpec::Absolute(ns) => ns,
+            TimeSpec::FromStart(offset_ns) => time_start_ns.saturating_add(offset_ns),
+        }
+    }
+}
+
+impl FromStr for TimeSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid time '{}': expected nanoseconds (1500000), a duration \
+                 (100ms, 2.5s, 1m) or an offset from trace start (+500ms)",
+                s
+            )
+        };
+
+        let trimmed = s.trim();
+        let (relative, value) = match trimmed.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let ns = parse_duration_ns(value).ok_or_else(invalid)?;
+
+        Ok(if relative {
+            TimeSpec::FromStart(ns)
+        } else {
+            TimeSpec::Absolute(ns)
+        })
+    }
+}
+
+/// Parse `<number>[unit]` into nanoseconds; a bare number is nanoseconds
+fn parse_duration_ns(s: &str) -> Option<u64> {
+    let unit_start = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(unit_start);
+
+    let scale: u128 = match unit {
+        "" | "ns" => 1,
+        "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        "m" => 60 * 1_000_000_000,
+        "h" => 60 * 60 * 1_000_000_000,
+        _ => return None,
+    };
+
+    // Scale the integer and fractional digits separately so large values
+    // keep full nanosecond precision.
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    let whole: u128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let mut ns = whole.checked_mul(scale)?;
+    if !fraction.is_empty() {
+        let digits: u128 = fraction.parse().ok()?;
+        let divisor = 10u128.checked_pow(fraction.len() as u32)?;
+        ns = ns.checked_add(digits.checked_mul(scale)? / divisor)?;
+    }
+
+    u64::try_from(ns).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_spec__parse_forms__then_expected_values() {
+        assert_eq!("1500000".parse(), Ok(TimeSpec::Absolute(1_500_000)));
+        assert_eq!("250ns".parse(), Ok(TimeSpec::Absolute(250)));
+        assert_eq!("3us".parse(), Ok(TimeSpec::Absolute(3_000)));
+        assert_eq!("100ms".parse(), Ok(TimeSpec::Absolute(100_000_000)));
+        assert_eq!("2.5s".parse(), Ok(TimeSpec::Absolute(2_500_000_000)));
+        assert_eq!("1m".parse(), Ok(TimeSpec::Absolute(60_000_000_000)));
+        assert_eq!(".5ms".parse(), Ok(TimeSpec::Absolute(500_000)));
+        assert_eq!("+500ms".parse(), Ok(TimeSpec::FromStart(500_000_000)));
+    }
+
+    #[test]
+    fn test_time_spec__parse_invalid__then_error_with_examples() {
+        for input in [
+            "",
+            "+",
+            "ms",
+            "1.2.3s",
+            "10 days",
+            "-5s",
+            "1e3ns",
+            "99999999999h",
+        ] {
+            let err = input.parse::<TimeSpec>().unwrap_err();
+            assert!(err.contains("+500ms"), "{}: {}", input, err);
+        }
+    }
+
+    #[test]
+    fn test_time_spec__resolve__then_offset_added_to_start() {
+        assert_eq!(TimeSpec::Absolute(42).resolve(1_000), 42);
+        assert_eq!(TimeSpec::FromStart(500).resolve(1_000), 1_500);
+    }
+}
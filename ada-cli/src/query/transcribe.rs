@@ -10,6 +10,7 @@ use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 
 use super::bundle::Bundle;
+use crate::exit_code::CliError;
 use super::output::OutputFormat;
 
 /// A transcript segment with timing information
@@ -74,13 +75,13 @@ pub struct TimeRange {
 pub fn get_or_create_transcript(bundle: &Bundle) -> Result<Transcript> {
     let voice_path = bundle
         .voice_path()
-        .ok_or_else(|| anyhow::anyhow!("Session has no voice recording. Use --voice flag during capture."))?;
+        .ok_or_else(|| CliError::not_found("Session has no voice recording. Use --voice flag during capture."))?;
 
     if !voice_path.exists() {
-        bail!(
+        return Err(CliError::not_found(format!(
             "Voice recording not found at {:?}. Use --voice flag during capture.",
             voice_path
-        );
+        )));
     }
 
     let cache_path = bundle.path.join("transcript.json");
@@ -139,7 +140,9 @@ fn run_whisper(voice_path: &Path, bundle: &Bundle) -> Result<Transcript> {
     // Check if whisper is available
     let which_result = Command::new("which").arg("whisper").output();
     if !which_result.map(|o| o.status.success()).unwrap_or(false) {
-        bail!("Whisper not available. Install with: pip install openai-whisper");
+        return Err(CliError::backend(
+            "Whisper not available. Install with: pip install openai-whisper",
+        ));
     }
 
     // Create temp directory for whisper output
@@ -160,7 +163,7 @@ fn run_whisper(voice_path: &Path, bundle: &Bundle) -> Result<Transcript> {
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Whisper failed: {}", stderr);
+        return Err(CliError::backend(format!("Whisper failed: {}", stderr)));
     }
 
     // Find the output JSON file
@@ -10,13 +10,15 @@
 //!
 //! The session directory IS the bundle - no nested `.adabundle` needed.
 
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use clap::Subcommand;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::exit_code::CliError;
+
 /// Sessions directory path relative to home: ~/.ada/sessions/
 pub const SESSIONS_DIR: &str = ".ada/sessions";
 
@@ -161,9 +163,9 @@ fn cmd_latest(running_only: bool) -> Result<()> {
         }
         None => {
             if running_only {
-                bail!("No running sessions found");
+                return Err(CliError::not_found("No running sessions found"));
             } else {
-                bail!("No sessions found");
+                return Err(CliError::not_found("No sessions found"));
             }
         }
     }
@@ -251,7 +253,7 @@ pub fn update(session_id: &str, session: &SessionState) -> Result<()> {
     let file_path = dir.join("session.json");
 
     if !file_path.exists() {
-        bail!("Session {} not found", session_id);
+        return Err(CliError::not_found(format!("Session {} not found", session_id)));
     }
 
     let json = serde_json::to_string_pretty(session)?;
@@ -0,0 +1,488 @@
+//! Snapshots of a capture in progress.
+//!
+//! A session stays locked and manifest-less until the tracer stops, so it
+//! cannot be queried while it runs. A snapshot copies the events the tracer
+//! has written to disk so far into a new directory, finalized the way the
+//! tracer would on stop: whole events only, index and detail files trimmed
+//! to a prefix that links up, headers and footers recomputed, and a
+//! manifest listing the copied threads. The live session is only read.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::exit_code::CliError;
+
+const HEADER_SIZE: usize = 64;
+const FOOTER_SIZE: usize = 64;
+const INDEX_EVENT_SIZE: usize = 32;
+const DETAIL_EVENT_HEADER_SIZE: usize = 24;
+const NO_DETAIL_SEQ: u32 = u32::MAX;
+const INDEX_FLAG_HAS_DETAIL_FILE: u32 = 1;
+
+/// What a snapshot copied
+#[derive(Debug, Serialize)]
+pub struct SnapshotSummary {
+    pub session: PathBuf,
+    pub output: PathBuf,
+    pub threads: Vec<ThreadSnapshot>,
+    pub total_events: u64,
+}
+
+/// Events copied for one thread
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ThreadSnapshot {
+    pub id: u32,
+    pub events: u64,
+    pub detail_events: u64,
+}
+
+/// Copy the flushed prefix of `session` into `output` as a finalized trace
+pub fn snapshot_session(session: &Path, output: &Path) -> Result<SnapshotSummary> {
+    if !session.is_dir() {
+        return Err(CliError::not_found(format!(
+            "Session directory not found: {}",
+            session.display()
+        )));
+    }
+    if output.join("manifest.json").exists() {
+        return Err(CliError::invalid_args(format!(
+            "{} already holds a trace",
+            output.display()
+        )));
+    }
+    fs::create_dir_all(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    if fs::canonicalize(output)? == fs::canonicalize(session)? {
+        return Err(CliError::invalid_args(
+            "Snapshot directory must differ from the session directory",
+        ));
+    }
+
+    let mut thread_ids: Vec<u32> = fs::read_dir(session)?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            name.to_str()?.strip_prefix("thread_")?.parse().ok()
+        })
+        .collect();
+    thread_ids.sort_unstable();
+
+    let mut threads = Vec::new();
+    let mut manifest_threads = Vec::new();
+    let mut time_range: Option<(u64, u64)> = None;
+    let mut clock_type = 1;
+    for id in thread_ids {
+        let source = session.join(format!("thread_{}", id));
+        let Some(copy) = ThreadCopy::read(&source)? else {
+            continue;
+        };
+        if threads.is_empty() {
+            clock_type = copy.index_header[16];
+        }
+        if let Some((start, end)) = copy.time_range() {
+            time_range = Some(match time_range {
+                Some((s, e)) => (s.min(start), e.max(end)),
+                None => (start, end),
+            });
+        }
+
+        copy.write(&output.join(format!("thread_{}", id)))?;
+        manifest_threads.push(serde_json::json!({
+            "id": id,
+            "has_detail": copy.detail.is_some(),
+        }));
+        threads.push(ThreadSnapshot {
+            id,
+            events: copy.index_events.len() as u64 / INDEX_EVENT_SIZE as u64,
+            detail_events: copy.detail.as_ref().map_or(0, |d| d.count),
+        });
+    }
+
+    let (time_start_ns, time_end_ns) = time_range.unwrap_or((0, 0));
+    let manifest = serde_json::json!({
+        "threads": manifest_threads,
+        "time_start_ns": time_start_ns,
+        "time_end_ns": time_end_ns,
+        "clock_type": clock_type,
+        "endianness": "little",
+        "format_version": "2.0",
+    });
+    // Published last, and atomically, so the copy only looks like a trace
+    // once every thread file is in place
+    let manifest_path = output.join("manifest.json");
+    let manifest_tmp = output.join("manifest.json.tmp");
+    fs::write(&manifest_tmp, serde_json::to_vec_pretty(&manifest)?)?;
+    fs::rename(&manifest_tmp, &manifest_path)?;
+
+    let total_events = threads.iter().map(|t| t.events).sum();
+    Ok(SnapshotSummary {
+        session: session.to_path_buf(),
+        output: output.to_path_buf(),
+        threads,
+        total_events,
+    })
+}
+
+pub fn format_summary_text(summary: &SnapshotSummary) -> String {
+    format!(
+        "Snapshot of {} written to {}\n{} events across {} threads\n",
+        summary.session.display(),
+        summary.output.display(),
+        summary.total_events,
+        summary.threads.len()
+    )
+}
+
+/// One thread's files, trimmed to whole events that link up
+struct ThreadCopy {
+    index_header: [u8; HEADER_SIZE],
+    index_events: Vec<u8>,
+    detail: Option<DetailCopy>,
+}
+
+struct DetailCopy {
+    header: [u8; HEADER_SIZE],
+    events: Vec<u8>,
+    count: u64,
+    index_seq_start: u32,
+    index_seq_end: u32,
+    time_start_ns: u64,
+    time_end_ns: u64,
+}
+
+impl ThreadCopy {
+    /// Read a thread directory; `None` if it has no index file yet
+    fn read(dir: &Path) -> Result<Option<Self>> {
+        let index_path = dir.join("index.atf");
+        if !index_path.exists() {
+            return Ok(None);
+        }
+        let index = fs::read(&index_path)
+            .with_context(|| format!("Failed to read {}", index_path.display()))?;
+        if index.len() < HEADER_SIZE || &index[..4] != b"ATI2" {
+            bail!("Not an ATF index file: {}", index_path.display());
+        }
+
+        // A finalized file says how many events it holds; a live one holds
+        // however many whole events have reached the disk
+        let mut index_count = (index.len() - HEADER_SIZE) / INDEX_EVENT_SIZE;
+        let footer_offset = read_u64(&index, 40) as usize;
+        if footer_offset > HEADER_SIZE
+            && index.len() >= footer_offset + FOOTER_SIZE
+            && &index[footer_offset..footer_offset + 4] == b"2ITA"
+        {
+            index_count = index_count.min(read_u32(&index, 28) as usize);
+        }
+
+        let detail = match fs::read(dir.join("detail.atf")) {
+            Ok(bytes) if bytes.len() >= HEADER_SIZE && &bytes[..4] == b"ATD2" => Some(bytes),
+            _ => None,
+        };
+
+        // Records of the detail file, as (offset, length, index_seq)
+        let mut records = Vec::new();
+        if let Some(detail) = &detail {
+            let mut end = detail.len();
+            let bytes_length = read_u64(detail, 36) as usize;
+            if bytes_length > 0 && HEADER_SIZE + bytes_length <= end {
+                end = HEADER_SIZE + bytes_length;
+            }
+            let mut offset = HEADER_SIZE;
+            while offset + DETAIL_EVENT_HEADER_SIZE <= end {
+                let length = read_u32(detail, offset) as usize;
+                if length < DETAIL_EVENT_HEADER_SIZE || offset + length > end {
+                    break;
+                }
+                records.push((offset, length, read_u32(detail, offset + 8)));
+                offset += length;
+            }
+        }
+
+        // Stop before the first event linking to a detail record that has
+        // not been written yet, then drop detail records past that point
+        let event = |seq: usize| HEADER_SIZE + seq * INDEX_EVENT_SIZE;
+        let index_count = (0..index_count)
+            .find(|&seq| {
+                let detail_seq = read_u32(&index, event(seq) + 28);
+                detail_seq != NO_DETAIL_SEQ && detail_seq as usize >= records.len()
+            })
+            .unwrap_or(index_count);
+        records.retain(|&(_, _, index_seq)| (index_seq as usize) < index_count);
+
+        let detail = detail.map(|bytes| {
+            let mut copy = DetailCopy {
+                header: bytes[..HEADER_SIZE].try_into().unwrap(),
+                events: Vec::new(),
+                count: records.len() as u64,
+                index_seq_start: u32::MAX,
+                index_seq_end: 0,
+                time_start_ns: 0,
+                time_end_ns: 0,
+            };
+            for (i, &(offset, length, index_seq)) in records.iter().enumerate() {
+                let timestamp = read_u64(&bytes, offset + 16);
+                if i == 0 {
+                    copy.time_start_ns = timestamp;
+                }
+                copy.time_end_ns = timestamp;
+                copy.index_seq_start = copy.index_seq_start.min(index_seq);
+                copy.index_seq_end = copy.index_seq_end.max(index_seq);
+                copy.events
+                    .extend_from_slice(&bytes[offset..offset + length]);
+            }
+            copy
+        });
+
+        Ok(Some(ThreadCopy {
+            index_header: index[..HEADER_SIZE].try_into().unwrap(),
+            index_events: index[HEADER_SIZE..event(index_count)].to_vec(),
+            detail,
+        }))
+    }
+
+    /// First and last index timestamps, if any event was copied
+    fn time_range(&self) -> Option<(u64, u64)> {
+        if self.index_events.is_empty() {
+            return None;
+        }
+        let last = self.index_events.len() - INDEX_EVENT_SIZE;
+        Some((
+            read_u64(&self.index_events, 0),
+            read_u64(&self.index_events, last),
+        ))
+    }
+
+    fn write(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+
+        let count = (self.index_events.len() / INDEX_EVENT_SIZE) as u64;
+        let (time_start_ns, time_end_ns) = self.time_range().unwrap_or((0, 0));
+        let mut header = self.index_header;
+        let mut flags = read_u32(&header, 8);
+        if self.detail.is_some() {
+            flags |= INDEX_FLAG_HAS_DETAIL_FILE;
+        }
+        write_u32(&mut header, 8, flags);
+        write_u32(&mut header, 28, count as u32);
+        write_u64(&mut header, 32, HEADER_SIZE as u64);
+        write_u64(
+            &mut header,
+            40,
+            (HEADER_SIZE + self.index_events.len()) as u64,
+        );
+        write_u64(&mut header, 48, time_start_ns);
+        write_u64(&mut header, 56, time_end_ns);
+
+        let mut footer = [0u8; FOOTER_SIZE];
+        footer[..4].copy_from_slice(b"2ITA");
+        write_u64(&mut footer, 8, count);
+        write_u64(&mut footer, 16, time_start_ns);
+        write_u64(&mut footer, 24, time_end_ns);
+        write_u64(&mut footer, 32, self.index_events.len() as u64);
+
+        fs::write(
+            dir.join("index.atf"),
+            [&header[..], &self.index_events, &footer[..]].concat(),
+        )?;
+
+        if let Some(detail) = &self.detail {
+            let mut header = detail.header;
+            write_u64(&mut header, 20, HEADER_SIZE as u64);
+            write_u64(&mut header, 28, detail.count);
+            write_u64(&mut header, 36, detail.events.len() as u64);
+            write_u64(&mut header, 44, detail.index_seq_start as u64);
+            write_u64(&mut header, 52, detail.index_seq_end as u64);
+
+            let mut footer = [0u8; FOOTER_SIZE];
+            footer[..4].copy_from_slice(b"2DTA");
+            write_u64(&mut footer, 8, detail.count);
+            write_u64(&mut footer, 16, detail.events.len() as u64);
+            write_u64(&mut footer, 24, detail.time_start_ns);
+            write_u64(&mut footer, 32, detail.time_end_ns);
+
+            fs::write(
+                dir.join("detail.atf"),
+                [&header[..], &detail.events, &footer[..]].concat(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u64(bytes: &mut [u8], offset: usize, value: u64) {
+    bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use tempfile::TempDir;
+
+    fn index_event(timestamp_ns: u64, detail_seq: u32) -> Vec<u8> {
+        let mut event = vec![0u8; INDEX_EVENT_SIZE];
+        write_u64(&mut event, 0, timestamp_ns);
+        write_u64(&mut event, 8, 0x1_0000_0001);
+        write_u32(&mut event, 20, 1);
+        write_u32(&mut event, 28, detail_seq);
+        event
+    }
+
+    fn detail_event(index_seq: u32, timestamp_ns: u64) -> Vec<u8> {
+        let length = DETAIL_EVENT_HEADER_SIZE + 8;
+        let mut event = vec![0u8; length];
+        write_u32(&mut event, 0, length as u32);
+        write_u32(&mut event, 8, index_seq);
+        write_u64(&mut event, 16, timestamp_ns);
+        event
+    }
+
+    /// A thread directory as a live tracer leaves it: placeholder headers
+    /// and no footers
+    fn write_live_thread(session: &Path, id: u32, index: &[Vec<u8>], detail: &[Vec<u8>]) {
+        let dir = session.join(format!("thread_{}", id));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut header = vec![0u8; HEADER_SIZE];
+        header[..4].copy_from_slice(b"ATI2");
+        header[4] = 1;
+        header[5] = 1;
+        header[16] = 1;
+        write_u32(&mut header, 12, id);
+        write_u32(&mut header, 24, INDEX_EVENT_SIZE as u32);
+        write_u64(&mut header, 32, HEADER_SIZE as u64);
+        write_u64(&mut header, 40, HEADER_SIZE as u64);
+        fs::write(dir.join("index.atf"), [header, index.concat()].concat()).unwrap();
+
+        if !detail.is_empty() {
+            let mut header = vec![0u8; HEADER_SIZE];
+            header[..4].copy_from_slice(b"ATD2");
+            header[4] = 1;
+            header[5] = 1;
+            write_u64(&mut header, 20, HEADER_SIZE as u64);
+            fs::write(dir.join("detail.atf"), [header, detail.concat()].concat()).unwrap();
+        }
+    }
+
+    #[test]
+    fn snapshot_session__live_session__then_finalized_copy() {
+        let root = TempDir::new().unwrap();
+        let session = root.path().join("session");
+        let output = root.path().join("snapshot");
+        // A torn trailing event is not copied
+        let mut torn = index_event(400, NO_DETAIL_SEQ);
+        torn.truncate(10);
+        write_live_thread(
+            &session,
+            0,
+            &[
+                index_event(100, NO_DETAIL_SEQ),
+                index_event(300, NO_DETAIL_SEQ),
+                torn,
+            ],
+            &[],
+        );
+        write_live_thread(&session, 3, &[index_event(200, NO_DETAIL_SEQ)], &[]);
+
+        let summary = snapshot_session(&session, &output).unwrap();
+
+        assert_eq!(summary.total_events, 3);
+        assert_eq!(
+            summary.threads,
+            vec![
+                ThreadSnapshot {
+                    id: 0,
+                    events: 2,
+                    detail_events: 0
+                },
+                ThreadSnapshot {
+                    id: 3,
+                    events: 1,
+                    detail_events: 0
+                },
+            ]
+        );
+        let index = fs::read(output.join("thread_0/index.atf")).unwrap();
+        assert_eq!(
+            index.len(),
+            HEADER_SIZE + 2 * INDEX_EVENT_SIZE + FOOTER_SIZE
+        );
+        assert_eq!(read_u32(&index, 28), 2);
+        assert_eq!(
+            read_u64(&index, 40),
+            (HEADER_SIZE + 2 * INDEX_EVENT_SIZE) as u64
+        );
+        assert_eq!(&index[read_u64(&index, 40) as usize..][..4], b"2ITA");
+
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&fs::read(output.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(manifest["time_start_ns"], 100);
+        assert_eq!(manifest["time_end_ns"], 300);
+        assert_eq!(manifest["threads"][1]["id"], 3);
+        // The live session is left as it was
+        assert!(!session.join("manifest.json").exists());
+    }
+
+    #[test]
+    fn snapshot_session__detail_behind_index__then_trimmed_to_linked_prefix() {
+        let root = TempDir::new().unwrap();
+        let session = root.path().join("session");
+        let output = root.path().join("snapshot");
+        // Event 2 links to detail record 1, which has not reached the disk
+        write_live_thread(
+            &session,
+            0,
+            &[
+                index_event(100, 0),
+                index_event(200, NO_DETAIL_SEQ),
+                index_event(300, 1),
+            ],
+            &[detail_event(0, 100)],
+        );
+
+        let summary = snapshot_session(&session, &output).unwrap();
+
+        assert_eq!(
+            summary.threads,
+            vec![ThreadSnapshot {
+                id: 0,
+                events: 2,
+                detail_events: 1
+            }]
+        );
+        let index = fs::read(output.join("thread_0/index.atf")).unwrap();
+        assert_eq!(read_u32(&index, 8) & INDEX_FLAG_HAS_DETAIL_FILE, 1);
+        let detail = fs::read(output.join("thread_0/detail.atf")).unwrap();
+        assert_eq!(read_u64(&detail, 28), 1);
+        assert_eq!(&detail[detail.len() - FOOTER_SIZE..][..4], b"2DTA");
+    }
+
+    #[test]
+    fn snapshot_session__existing_trace_or_same_dir__then_refused() {
+        let root = TempDir::new().unwrap();
+        let session = root.path().join("session");
+        write_live_thread(&session, 0, &[index_event(100, NO_DETAIL_SEQ)], &[]);
+        let output = root.path().join("snapshot");
+        snapshot_session(&session, &output).unwrap();
+
+        assert!(snapshot_session(&session, &output).is_err());
+        assert!(snapshot_session(&session, &session).is_err());
+        assert!(snapshot_session(&root.path().join("missing"), &output).is_err());
+    }
+}
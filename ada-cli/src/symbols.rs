@@ -5,8 +5,13 @@
 //! - Locating dSYM bundles
 //! - Dumping symbol tables
 
+use crate::exit_code::CliError;
 use crate::ffi::{self, SymbolResolver};
+use crate::output::{self, OutputFormat};
+use crate::table::Table;
 use clap::Subcommand;
+use serde::Serialize;
+use anyhow::Context;
 use std::path::Path;
 
 #[derive(Subcommand)]
@@ -38,7 +43,7 @@ pub enum SymbolsCommands {
         /// Path to session directory
         session: String,
 
-        /// Output format (text, json)
+        /// Output format (text, json); prefer the global --output-format
         #[arg(short, long, default_value = "text")]
         format: String,
     },
@@ -57,141 +62,225 @@ fn parse_function_id(s: &str) -> Result<u64, String> {
     u64::from_str_radix(s, 16).map_err(|e| format!("Invalid function_id: {}", e))
 }
 
-pub fn run(cmd: SymbolsCommands) -> anyhow::Result<()> {
+pub fn run(cmd: SymbolsCommands, output_format: OutputFormat) -> anyhow::Result<()> {
     match cmd {
         SymbolsCommands::Resolve { session, function_id } => {
-            resolve_symbol(&session, function_id)
+            resolve_symbol(&session, function_id, output_format)
         }
         SymbolsCommands::LocateDsym { uuid } => {
-            locate_dsym(&uuid)
+            locate_dsym(&uuid, output_format)
         }
         SymbolsCommands::Demangle { name } => {
-            demangle_symbol(&name)
+            demangle_symbol(&name, output_format)
         }
         SymbolsCommands::Dump { session, format } => {
-            dump_symbols(&session, &format)
+            // --format json predates the global flag and still selects JSON
+            let output_format = match format.as_str() {
+                "json" if output_format == OutputFormat::Text => OutputFormat::Json,
+                _ => output_format,
+            };
+            dump_symbols(&session, output_format)
         }
         SymbolsCommands::Info { session } => {
-            show_info(&session)
+            show_info(&session, output_format)
         }
     }
 }
 
-fn resolve_symbol(session: &str, function_id: u64) -> anyhow::Result<()> {
+/// A resolved symbol
+#[derive(Serialize)]
+struct SymbolResult {
+    function_id: String,
+    name: String,
+    mangled: String,
+    module: Option<String>,
+    source_file: Option<String>,
+    source_line: u32,
+    source_column: u32,
+}
+
+fn resolve_symbol(session: &str, function_id: u64, format: OutputFormat) -> anyhow::Result<()> {
     let resolver = SymbolResolver::new(session)
-        .ok_or_else(|| anyhow::anyhow!("Failed to open session: {}", session))?;
+        .ok_or_else(|| CliError::backend(format!("Failed to open session: {}", session)))?;
 
     match resolver.resolve(function_id) {
         Ok(symbol) => {
-            println!("Function ID: 0x{:016x}", symbol.function_id);
-            println!("Name:        {}", symbol.name_demangled);
-            if symbol.name_mangled != symbol.name_demangled {
-                println!("Mangled:     {}", symbol.name_mangled);
-            }
-            if let Some(module) = &symbol.module_path {
-                println!("Module:      {}", module);
-            }
-            if let Some(file) = &symbol.source_file {
-                print!("Source:      {}", file);
-                if symbol.source_line > 0 {
-                    print!(":{}", symbol.source_line);
-                    if symbol.source_column > 0 {
-                        print!(":{}", symbol.source_column);
-                    }
-                }
-                println!();
-            }
+            let result = SymbolResult {
+                function_id: format!("0x{:016x}", symbol.function_id),
+                name: symbol.name_demangled,
+                mangled: symbol.name_mangled,
+                module: symbol.module_path,
+                source_file: symbol.source_file,
+                source_line: symbol.source_line,
+                source_column: symbol.source_column,
+            };
+            output::emit(&result, format, format_symbol_text)?;
         }
         Err(ffi::SymbolResolveResult::NotFound) => {
-            eprintln!("Symbol not found for function_id: 0x{:016x}", function_id);
-            std::process::exit(1);
+            return Err(CliError::not_found(format!(
+                "Symbol not found for function_id: 0x{:016x}",
+                function_id
+            )));
         }
         Err(e) => {
-            anyhow::bail!("Resolution failed: {:?}", e);
+            return Err(CliError::backend(format!("Resolution failed: {:?}", e)));
         }
     }
 
     Ok(())
 }
 
-fn locate_dsym(uuid: &str) -> anyhow::Result<()> {
+fn format_symbol_text(symbol: &SymbolResult) -> String {
+    let mut output = String::new();
+    output.push_str(&format!("Function ID: {}\n", symbol.function_id));
+    output.push_str(&format!("Name:        {}\n", symbol.name));
+    if symbol.mangled != symbol.name {
+        output.push_str(&format!("Mangled:     {}\n", symbol.mangled));
+    }
+    if let Some(module) = &symbol.module {
+        output.push_str(&format!("Module:      {}\n", module));
+    }
+    if let Some(file) = &symbol.source_file {
+        output.push_str(&format!("Source:      {}", file));
+        if symbol.source_line > 0 {
+            output.push_str(&format!(":{}", symbol.source_line));
+            if symbol.source_column > 0 {
+                output.push_str(&format!(":{}", symbol.source_column));
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Location of a dSYM bundle
+#[derive(Serialize)]
+struct DsymResult {
+    uuid: String,
+    path: String,
+}
+
+fn locate_dsym(uuid: &str, format: OutputFormat) -> anyhow::Result<()> {
     match ffi::locate_dsym(uuid) {
         Some(path) => {
-            println!("{}", path);
+            let result = DsymResult {
+                uuid: uuid.to_string(),
+                path,
+            };
+            output::emit(&result, format, |r| format!("{}\n", r.path))?;
         }
         None => {
-            eprintln!("dSYM not found for UUID: {}", uuid);
-            std::process::exit(1);
+            return Err(CliError::not_found(format!("dSYM not found for UUID: {}", uuid)));
         }
     }
     Ok(())
 }
 
-fn demangle_symbol(name: &str) -> anyhow::Result<()> {
-    let demangled = ffi::demangle(name);
-    println!("{}", demangled);
-    Ok(())
+/// A demangled symbol name
+#[derive(Serialize)]
+struct DemangleResult {
+    mangled: String,
+    demangled: String,
+}
+
+fn demangle_symbol(name: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let result = DemangleResult {
+        mangled: name.to_string(),
+        demangled: ffi::demangle(name),
+    };
+    output::emit(&result, format, |r| format!("{}\n", r.demangled))
+}
+
+/// Module and symbol tables as stored in the session manifest
+#[derive(Serialize)]
+struct SymbolDump {
+    modules: Option<serde_json::Value>,
+    symbols: Option<serde_json::Value>,
+    format_version: Option<serde_json::Value>,
 }
 
-fn dump_symbols(session: &str, format: &str) -> anyhow::Result<()> {
+fn dump_symbols(session: &str, format: OutputFormat) -> anyhow::Result<()> {
     // Read the manifest.json directly for full dump
     let manifest_path = Path::new(session).join("manifest.json");
     let content = std::fs::read_to_string(&manifest_path)
-        .map_err(|e| anyhow::anyhow!("Failed to read manifest: {}", e))?;
-
-    if format == "json" {
-        // Just output the relevant parts of the manifest
-        let json: serde_json::Value = serde_json::from_str(&content)?;
-
-        let output = serde_json::json!({
-            "modules": json.get("modules"),
-            "symbols": json.get("symbols"),
-            "format_version": json.get("format_version"),
-        });
-
-        println!("{}", serde_json::to_string_pretty(&output)?);
-    } else {
-        // Text format
-        let json: serde_json::Value = serde_json::from_str(&content)?;
-
-        // Print modules
-        if let Some(modules) = json.get("modules").and_then(|m| m.as_array()) {
-            println!("=== Modules ({}) ===\n", modules.len());
-            for module in modules {
-                let id = module.get("module_id").and_then(|v| v.as_u64()).unwrap_or(0);
-                let path = module.get("path").and_then(|v| v.as_str()).unwrap_or("?");
-                let uuid = module.get("uuid").and_then(|v| v.as_str()).unwrap_or("");
-                println!("  [{:08x}] {}", id, path);
-                if !uuid.is_empty() {
-                    println!("             UUID: {}", uuid);
-                }
-            }
-            println!();
+        .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+    let mut json: serde_json::Value = serde_json::from_str(&content)?;
+
+    let dump = SymbolDump {
+        modules: json.get_mut("modules").map(serde_json::Value::take),
+        symbols: json.get_mut("symbols").map(serde_json::Value::take),
+        format_version: json.get_mut("format_version").map(serde_json::Value::take),
+    };
+    output::emit(&dump, format, format_dump_text)
+}
+
+fn format_dump_text(dump: &SymbolDump) -> String {
+    let mut output = String::new();
+
+    if let Some(modules) = dump.modules.as_ref().and_then(|m| m.as_array()) {
+        output.push_str(&format!("=== Modules ({}) ===\n\n", modules.len()));
+        let mut table = Table::new(["ID", "PATH", "UUID"]).highlight(0);
+        for module in modules {
+            let id = module.get("module_id").and_then(|v| v.as_u64()).unwrap_or(0);
+            let path = module.get("path").and_then(|v| v.as_str()).unwrap_or("?");
+            let uuid = module.get("uuid").and_then(|v| v.as_str()).unwrap_or("");
+            table.add_row([format!("{:08x}", id), path.to_string(), uuid.to_string()]);
         }
+        output.push_str(&table.render_stdout());
+        output.push('\n');
+    }
 
-        // Print symbols
-        if let Some(symbols) = json.get("symbols").and_then(|s| s.as_array()) {
-            println!("=== Symbols ({}) ===\n", symbols.len());
-            for symbol in symbols {
-                let fid = symbol.get("function_id").and_then(|v| v.as_str()).unwrap_or("0");
-                let name = symbol.get("name").and_then(|v| v.as_str()).unwrap_or("?");
-                let demangled = ffi::demangle(name);
-                println!("  {} {}", fid, demangled);
-            }
+    if let Some(symbols) = dump.symbols.as_ref().and_then(|s| s.as_array()) {
+        output.push_str(&format!("=== Symbols ({}) ===\n\n", symbols.len()));
+        let mut table = Table::new(["FUNCTION ID", "NAME"]).highlight(0);
+        for symbol in symbols {
+            let fid = symbol.get("function_id").and_then(|v| v.as_str()).unwrap_or("0");
+            let name = symbol.get("name").and_then(|v| v.as_str()).unwrap_or("?");
+            table.add_row([fid.to_string(), ffi::demangle(name)]);
         }
+        output.push_str(&table.render_stdout());
     }
 
-    Ok(())
+    output
+}
+
+/// Symbol table statistics for a session
+#[derive(Serialize)]
+struct SymbolInfo {
+    session: String,
+    format_version: Option<String>,
+    module_count: usize,
+    symbol_count: usize,
+    manifest_bytes: Option<u64>,
 }
 
-fn show_info(session: &str) -> anyhow::Result<()> {
+fn show_info(session: &str, format: OutputFormat) -> anyhow::Result<()> {
     let resolver = SymbolResolver::new(session)
-        .ok_or_else(|| anyhow::anyhow!("Failed to open session: {}", session))?;
+        .ok_or_else(|| CliError::backend(format!("Failed to open session: {}", session)))?;
 
-    println!("Session: {}", session);
-    println!("Format:  {}", resolver.format_version().unwrap_or_else(|| "unknown".to_string()));
-    println!("Modules: {}", resolver.module_count());
-    println!("Symbols: {}", resolver.symbol_count());
+    let info = SymbolInfo {
+        session: session.to_string(),
+        format_version: resolver.format_version(),
+        module_count: resolver.module_count(),
+        symbol_count: resolver.symbol_count(),
+        manifest_bytes: std::fs::metadata(Path::new(session).join("manifest.json"))
+            .ok()
+            .map(|metadata| metadata.len()),
+    };
+    output::emit(&info, format, format_info_text)
+}
 
-    Ok(())
+fn format_info_text(info: &SymbolInfo) -> String {
+    let mut output = String::new();
+    output.push_str(&format!("Session: {}\n", info.session));
+    output.push_str(&format!(
+        "Format:  {}\n",
+        info.format_version.as_deref().unwrap_or("unknown")
+    ));
+    output.push_str(&format!("Modules: {}\n", info.module_count));
+    output.push_str(&format!("Symbols: {}\n", info.symbol_count));
+    if let Some(bytes) = info.manifest_bytes {
+        output.push_str(&format!("Size:    {}\n", crate::fmt::bytes(bytes)));
+    }
+    output
 }
@@ -0,0 +1,153 @@
+//! Aligned table output for CLI listings.
+//!
+//! Columns are sized to their widest cell so long names never shift the
+//! columns after them. Headers are bold when writing to a terminal and plain
+//! when piped or when `NO_COLOR` is set.
+
+use std::io::IsTerminal;
+
+const BOLD: &str = "\x1b[1m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Column alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// A table rendered with auto-sized columns
+pub struct Table {
+    headers: Vec<String>,
+    align: Vec<Align>,
+    highlight: Option<usize>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Create a table with the given column headers, all left-aligned
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        let headers: Vec<String> = headers.into_iter().map(Into::into).collect();
+        let align = vec![Align::Left; headers.len()];
+        Table {
+            headers,
+            align,
+            highlight: None,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Set the alignment of a column
+    pub fn align(mut self, column: usize, align: Align) -> Self {
+        self.align[column] = align;
+        self
+    }
+
+    /// Color the cells of a column when colors are enabled
+    pub fn highlight(mut self, column: usize) -> Self {
+        self.highlight = Some(column);
+        self
+    }
+
+    /// Append a row; missing cells are left empty
+    pub fn add_row<S: Into<String>>(&mut self, cells: impl IntoIterator<Item = S>) {
+        let mut row: Vec<String> = cells.into_iter().map(Into::into).collect();
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+    }
+
+    /// Render for stdout, with colors only when stdout is a terminal
+    pub fn render_stdout(&self) -> String {
+        let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        self.render(color)
+    }
+
+    /// Render the table, one line per row with a trailing newline
+    pub fn render(&self, color: bool) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut output = self.render_line(&self.headers, &widths, color.then_some(BOLD), None);
+        for row in &self.rows {
+            let highlight = self.highlight.filter(|_| color);
+            output.push_str(&self.render_line(row, &widths, None, highlight));
+        }
+        output
+    }
+
+    fn render_line(
+        &self,
+        cells: &[String],
+        widths: &[usize],
+        style: Option<&str>,
+        highlight: Option<usize>,
+    ) -> String {
+        let last = cells.len().saturating_sub(1);
+        let mut line = String::from("  ");
+        for (i, cell) in cells.iter().enumerate() {
+            let padding = " ".repeat(widths[i] - cell.chars().count());
+            let (before, after) = match self.align[i] {
+                Align::Left => ("", padding.as_str()),
+                Align::Right => (padding.as_str(), ""),
+            };
+            let style = style.or((highlight == Some(i)).then_some(CYAN));
+
+            line.push_str(before);
+            match style {
+                Some(style) => line.push_str(&format!("{}{}{}", style, cell, RESET)),
+                None => line.push_str(cell),
+            }
+            line.push_str(after);
+            if i != last {
+                line.push_str("  ");
+            }
+        }
+        // Empty or left-aligned last cells would otherwise leave trailing spaces
+        line.truncate(line.trim_end().len());
+        line.push('\n');
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table__render_plain__then_columns_aligned() {
+        let mut table = Table::new(["NAME", "SIZE"]).align(1, Align::Right);
+        table.add_row(["a_very_long_session_name", "1.0 KiB"]);
+        table.add_row(["short", "12 B"]);
+
+        assert_eq!(
+            table.render(false),
+            "  NAME                         SIZE\n\
+             \x20 a_very_long_session_name  1.0 KiB\n\
+             \x20 short                        12 B\n"
+        );
+    }
+
+    #[test]
+    fn test_table__render_color__then_styles_wrap_cells_only() {
+        let mut table = Table::new(["ID", "NAME"]).highlight(0);
+        table.add_row(["0x1", "main"]);
+
+        let output = table.render(true);
+        assert_eq!(
+            output,
+            "  \x1b[1mID\x1b[0m   \x1b[1mNAME\x1b[0m\n  \x1b[36m0x1\x1b[0m  main\n"
+        );
+    }
+
+    #[test]
+    fn test_table__short_row__then_padded_with_empty_cells() {
+        let mut table = Table::new(["A", "B"]);
+        table.add_row(["x"]);
+        assert_eq!(table.render(false), "  A  B\n  x\n");
+    }
+}
@@ -4,10 +4,26 @@
 //! - Starting trace sessions
 //! - Stopping trace sessions
 //! - Listing sessions
+//! - Snapshotting a session while it is captured
+//! - Dumping a session's events as diffable text
+//! - Validating that a recorded session is well-formed
+//! - Measuring tracing overhead for a workload
 
 use clap::Subcommand;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracer_backend::{TraceSession, TracerController};
+
+use crate::config::Config;
+use crate::exit_code::CliError;
+use crate::output::{self, OutputFormat};
+use crate::table::{Align, Table};
+
+/// Trace directory used when neither a flag nor ada.toml sets one
+const DEFAULT_TRACE_DIR: &str = "./traces";
 
 #[derive(Subcommand)]
 pub enum TraceCommands {
@@ -16,9 +32,13 @@ pub enum TraceCommands {
         /// Path to the binary to trace
         binary: String,
 
-        /// Output directory for trace files
-        #[arg(short, long, default_value = "./traces")]
-        output: PathBuf,
+        /// Output directory for trace files [default: ./traces]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Path to the tracer binary [default: searched next to ada and in PATH]
+        #[arg(long)]
+        tracer: Option<PathBuf>,
 
         /// Arguments to pass to the binary
         #[arg(trailing_var_arg = true)]
@@ -35,9 +55,13 @@ pub enum TraceCommands {
         #[arg(short, long)]
         scheme: String,
 
-        /// Output directory for trace files
-        #[arg(short, long, default_value = "./traces")]
-        output: PathBuf,
+        /// Output directory for trace files [default: ./traces]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Path to the tracer binary [default: searched next to ada and in PATH]
+        #[arg(long)]
+        tracer: Option<PathBuf>,
     },
 
     /// Attach to a running process
@@ -45,9 +69,13 @@ pub enum TraceCommands {
         /// Process ID to attach to
         pid: u32,
 
-        /// Output directory for trace files
-        #[arg(short, long, default_value = "./traces")]
-        output: PathBuf,
+        /// Output directory for trace files [default: ./traces]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Path to the tracer binary [default: searched next to ada and in PATH]
+        #[arg(long)]
+        tracer: Option<PathBuf>,
     },
 
     /// Stop the current trace session
@@ -55,35 +83,131 @@ pub enum TraceCommands {
 
     /// List trace sessions
     List {
-        /// Directory containing trace sessions
-        #[arg(default_value = "./traces")]
-        directory: PathBuf,
+        /// Directory containing trace sessions [default: ./traces]
+        directory: Option<PathBuf>,
+    },
+
+    /// Print every event one per line in a stable, diffable form
+    Dump {
+        /// Session: @latest, session ID, or directory path
+        session: PathBuf,
+    },
+
+    /// Check that a recorded trace is well-formed; exits nonzero on any failure
+    Validate {
+        /// Session: @latest, session ID, or directory path
+        session: PathBuf,
+
+        /// Skip the manifest integrity check
+        #[arg(long)]
+        skip_manifest: bool,
+
+        /// Skip comparing header and footer event counts with the events on disk
+        #[arg(long)]
+        skip_event_count: bool,
+
+        /// Skip checking that timestamps never go backwards within a thread
+        #[arg(long)]
+        skip_monotonic: bool,
+
+        /// Skip checking that calls and returns pair up within a thread
+        #[arg(long)]
+        skip_span_balance: bool,
+    },
+
+    /// Run a binary untraced, then traced, and report the slowdown
+    ///
+    /// The untraced run's standard output is discarded; the traced run is
+    /// spawned by the tracer controller and writes to ada's standard output.
+    Overhead {
+        /// Path to the binary to measure
+        binary: String,
+
+        /// Keep the traced run's session in this directory [default: a temporary directory]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Arguments to pass to the binary
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Copy what a running capture has written so far into a queryable trace
+    Snapshot {
+        /// Directory of the session being captured
+        session: PathBuf,
+
+        /// Directory for the snapshot [default: <session>_snapshot_<timestamp>]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 }
 
-pub fn run(cmd: TraceCommands) -> anyhow::Result<()> {
+pub fn run(cmd: TraceCommands, output_format: OutputFormat, config: &Config) -> anyhow::Result<()> {
+    // Flags override ada.toml, which overrides the built-in defaults
+    let output_dir = |flag: Option<PathBuf>| {
+        flag.or_else(|| config.output_dir.clone())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_TRACE_DIR))
+    };
+    let tracer_path = |flag: Option<PathBuf>| flag.or_else(|| config.tracer_path.clone());
+
     match cmd {
-        TraceCommands::Start { binary, output, args } => {
-            start_trace(&binary, &output, &args)
+        TraceCommands::Start { binary, output, tracer, args } => {
+            let tracer = tracer_path(tracer);
+            start_trace(&binary, &output_dir(output), tracer.as_deref(), &args)
         }
-        TraceCommands::StartXcode { project, scheme, output } => {
-            start_xcode_trace(&project, &scheme, &output)
+        TraceCommands::StartXcode { project, scheme, output, tracer } => {
+            let tracer = tracer_path(tracer);
+            start_xcode_trace(&project, &scheme, &output_dir(output), tracer.as_deref())
         }
-        TraceCommands::Attach { pid, output } => {
-            attach_trace(pid, &output)
+        TraceCommands::Attach { pid, output, tracer } => {
+            let tracer = tracer_path(tracer);
+            attach_trace(pid, &output_dir(output), tracer.as_deref())
         }
         TraceCommands::Stop => {
             stop_trace()
         }
         TraceCommands::List { directory } => {
-            list_sessions(&directory)
+            let directory = directory
+                .or_else(|| config.trace_root.clone())
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_TRACE_DIR));
+            list_sessions(&directory, output_format)
+        }
+        TraceCommands::Dump { session } => {
+            crate::query::dump(&session)
+        }
+        TraceCommands::Validate {
+            session,
+            skip_manifest,
+            skip_event_count,
+            skip_monotonic,
+            skip_span_balance,
+        } => {
+            let skip = crate::validate::SkipChecks {
+                manifest: skip_manifest,
+                event_count: skip_event_count,
+                monotonic: skip_monotonic,
+                span_balance: skip_span_balance,
+            };
+            validate_trace(&session, skip, output_format)
+        }
+        TraceCommands::Overhead { binary, output, args } => {
+            overhead_report(&binary, output, &args, output_format)
+        }
+        TraceCommands::Snapshot { session, output } => {
+            snapshot_trace(&session, output, output_format)
         }
     }
 }
 
-fn start_trace(binary: &str, output: &PathBuf, args: &[String]) -> anyhow::Result<()> {
+fn start_trace(
+    binary: &str,
+    output: &PathBuf,
+    tracer: Option<&Path>,
+    args: &[String],
+) -> anyhow::Result<()> {
     // Use the existing tracer binary
-    let tracer_path = find_tracer()?;
+    let tracer_path = find_tracer(tracer)?;
 
     let session_name = format!(
         "session_{}_{}",
@@ -112,16 +236,22 @@ fn start_trace(binary: &str, output: &PathBuf, args: &[String]) -> anyhow::Resul
     cmd.args(args);
 
     // Run tracer
+    tracing::info!("Running {:?}", cmd);
     let status = cmd.status()?;
     if !status.success() {
-        anyhow::bail!("Tracer exited with status: {}", status);
+        return Err(CliError::backend(format!("Tracer exited with status: {}", status)));
     }
 
     println!("\nTrace complete. Session saved to: {}", session_dir.display());
     Ok(())
 }
 
-fn start_xcode_trace(project: &str, scheme: &str, output: &PathBuf) -> anyhow::Result<()> {
+fn start_xcode_trace(
+    project: &str,
+    scheme: &str,
+    output: &PathBuf,
+    tracer: Option<&Path>,
+) -> anyhow::Result<()> {
     println!("Building Xcode project: {}", project);
     println!("Scheme: {}", scheme);
 
@@ -137,7 +267,7 @@ fn start_xcode_trace(project: &str, scheme: &str, output: &PathBuf) -> anyhow::R
         .status()?;
 
     if !build_status.success() {
-        anyhow::bail!("xcodebuild failed");
+        return Err(CliError::backend("xcodebuild failed"));
     }
 
     // Find the built binary
@@ -171,11 +301,11 @@ fn start_xcode_trace(project: &str, scheme: &str, output: &PathBuf) -> anyhow::R
     println!("Built binary: {}", binary_path);
 
     // Start trace with the built binary
-    start_trace(&binary_path, output, &[])
+    start_trace(&binary_path, output, tracer, &[])
 }
 
-fn attach_trace(pid: u32, output: &PathBuf) -> anyhow::Result<()> {
-    let tracer_path = find_tracer()?;
+fn attach_trace(pid: u32, output: &PathBuf, tracer: Option<&Path>) -> anyhow::Result<()> {
+    let tracer_path = find_tracer(tracer)?;
 
     let session_name = format!("session_{}_pid_{}", chrono_lite_timestamp(), pid);
     let session_dir = output.join(&session_name);
@@ -193,7 +323,7 @@ fn attach_trace(pid: u32, output: &PathBuf) -> anyhow::Result<()> {
         .status()?;
 
     if !status.success() {
-        anyhow::bail!("Tracer exited with status: {}", status);
+        return Err(CliError::backend(format!("Tracer exited with status: {}", status)));
     }
 
     println!("\nTrace complete. Session saved to: {}", session_dir.display());
@@ -208,44 +338,335 @@ fn stop_trace() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn list_sessions(directory: &PathBuf) -> anyhow::Result<()> {
-    if !directory.exists() {
-        println!("No sessions found in: {}", directory.display());
-        return Ok(());
+fn validate_trace(
+    session: &Path,
+    skip: crate::validate::SkipChecks,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let trace_path = crate::query::trace_path(session)?;
+    let report = crate::validate::validate_session(&trace_path, skip)?;
+    crate::validate::emit_report(&report, format)
+}
+
+/// Cost of tracing one run of a workload
+#[derive(Debug, Serialize)]
+struct OverheadReport {
+    binary: String,
+    args: Vec<String>,
+    untraced_ns: u64,
+    traced_ns: u64,
+    /// Traced wall-clock time divided by untraced
+    slowdown: f64,
+    /// Extra CPU time of the traced run, target and tracer together, as a
+    /// percentage of the untraced run's. `None` when the traced target was
+    /// not a child of ada, so its CPU time could not be collected.
+    cpu_overhead_percent: Option<f64>,
+    /// Peak resident memory of the tracer side: ada with the controller,
+    /// its shared memory and drain thread
+    memory_usage_mb: f64,
+    untraced_exit_code: Option<i32>,
+    traced_exit_code: Option<i32>,
+    trace_size_bytes: u64,
+    /// Where the traced run's session was kept, if it was
+    session: Option<PathBuf>,
+}
+
+fn overhead_report(
+    binary: &str,
+    output: Option<PathBuf>,
+    args: &[String],
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    // Untraced first: whatever the first run warms up then favors the
+    // traced run, so the slowdown is never overstated
+    let children_before = Usage::of(libc::RUSAGE_CHILDREN);
+    let (untraced_status, untraced_ns) = timed_run(Command::new(binary).args(args))?;
+    let untraced_cpu_ns = Usage::of(libc::RUSAGE_CHILDREN).cpu_ns - children_before.cpu_ns;
+
+    let temp_dir;
+    let session_dir = match &output {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            dir.clone()
+        }
+        None => {
+            temp_dir = tempfile::tempdir()?;
+            temp_dir.path().to_path_buf()
+        }
+    };
+    let mut controller =
+        TracerController::new(&session_dir).map_err(|err| CliError::backend(format!("{err:#}")))?;
+    let mut spawn_args = vec![binary.to_string()];
+    spawn_args.extend_from_slice(args);
+
+    let children_before = Usage::of(libc::RUSAGE_CHILDREN);
+    let self_before = Usage::of(libc::RUSAGE_SELF);
+    let started = Instant::now();
+    let session = TraceSession::spawn(&mut controller, binary, &spawn_args)
+        .map_err(|err| CliError::backend(format!("{err:#}")))?;
+    let traced_exit = wait_for_exit(session.pid());
+    let traced_ns = started.elapsed().as_nanos() as u64;
+    session
+        .finish()
+        .map_err(|err| CliError::backend(format!("{err:#}")))?;
+    let self_after = Usage::of(libc::RUSAGE_SELF);
+
+    let (traced_exit_code, cpu_overhead_percent) = match traced_exit {
+        TargetExit::Reaped(code) => {
+            let target_cpu_ns = Usage::of(libc::RUSAGE_CHILDREN).cpu_ns - children_before.cpu_ns;
+            let tracer_cpu_ns = self_after.cpu_ns - self_before.cpu_ns;
+            (
+                code,
+                Some(cpu_overhead_percent(
+                    untraced_cpu_ns,
+                    target_cpu_ns + tracer_cpu_ns,
+                )),
+            )
+        }
+        TargetExit::Gone => (None, None),
+    };
+
+    let report = OverheadReport {
+        binary: binary.to_string(),
+        args: args.to_vec(),
+        untraced_ns,
+        traced_ns,
+        slowdown: traced_ns as f64 / untraced_ns.max(1) as f64,
+        cpu_overhead_percent,
+        memory_usage_mb: self_after.max_rss_bytes as f64 / (1024.0 * 1024.0),
+        untraced_exit_code: untraced_status.code(),
+        traced_exit_code,
+        trace_size_bytes: dir_size(&session_dir),
+        session: output,
+    };
+    output::emit(&report, format, format_overhead_text)
+}
+
+/// Run `cmd` to completion with its standard output discarded
+fn timed_run(cmd: &mut Command) -> anyhow::Result<(ExitStatus, u64)> {
+    tracing::info!("Running {:?}", cmd);
+    let started = Instant::now();
+    let status = cmd.stdout(Stdio::null()).status()?;
+    Ok((status, started.elapsed().as_nanos() as u64))
+}
+
+/// How a traced target ended, as seen by `wait_for_exit`
+enum TargetExit {
+    /// Reaped by ada, with its exit code (`None` when killed by a signal)
+    Reaped(Option<i32>),
+    /// Not a child of ada, so only its disappearance was seen
+    Gone,
+}
+
+/// Block until the process `pid` exits
+fn wait_for_exit(pid: u32) -> TargetExit {
+    let mut status: i32 = 0;
+    if unsafe { libc::waitpid(pid as i32, &mut status, 0) } == pid as i32 {
+        return TargetExit::Reaped(libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status)));
     }
 
-    let mut sessions = Vec::new();
+    // Not our child to wait on, so poll until it is gone
+    while unsafe { libc::kill(pid as i32, 0) } == 0 {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    TargetExit::Gone
+}
 
-    for entry in std::fs::read_dir(directory)? {
-        let entry = entry?;
-        let path = entry.path();
+/// CPU time and peak resident memory from `getrusage`
+struct Usage {
+    cpu_ns: u64,
+    max_rss_bytes: u64,
+}
 
-        if path.is_dir() {
-            let manifest = path.join("manifest.json");
-            if manifest.exists() {
-                sessions.push(path);
-            }
+impl Usage {
+    /// Usage of `who`, `libc::RUSAGE_SELF` or `libc::RUSAGE_CHILDREN`
+    fn of(who: libc::c_int) -> Self {
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        unsafe { libc::getrusage(who, &mut usage) };
+
+        let ns =
+            |time: libc::timeval| time.tv_sec as u64 * 1_000_000_000 + time.tv_usec as u64 * 1_000;
+        // ru_maxrss is in bytes on macOS and in kilobytes elsewhere
+        let max_rss_bytes = if cfg!(target_os = "macos") {
+            usage.ru_maxrss as u64
+        } else {
+            usage.ru_maxrss as u64 * 1024
+        };
+        Usage {
+            cpu_ns: ns(usage.ru_utime) + ns(usage.ru_stime),
+            max_rss_bytes,
         }
     }
+}
+
+/// Extra CPU time of `traced_cpu_ns` over `untraced_cpu_ns`, in percent
+fn cpu_overhead_percent(untraced_cpu_ns: u64, traced_cpu_ns: u64) -> f64 {
+    (traced_cpu_ns as f64 - untraced_cpu_ns as f64) * 100.0 / untraced_cpu_ns.max(1) as f64
+}
+
+fn format_overhead_text(report: &OverheadReport) -> String {
+    let mut text = format!("Tracing overhead for {}\n\n", report.binary);
+    let mut row =
+        |label: &str, value: String| text.push_str(&format!("  {:<14}{}\n", label, value));
+    row("untraced", crate::fmt::duration_ns(report.untraced_ns));
+    row("traced", crate::fmt::duration_ns(report.traced_ns));
+    row("slowdown", format!("{:.2}x", report.slowdown));
+    row(
+        "cpu overhead",
+        report
+            .cpu_overhead_percent
+            .map_or("unknown".to_string(), |percent| format!("{:+.1}%", percent)),
+    );
+    row("tracer memory", format!("{:.1} MB", report.memory_usage_mb));
+    row("trace size", crate::fmt::bytes(report.trace_size_bytes));
+    if report.untraced_exit_code != report.traced_exit_code {
+        let code = |code: Option<i32>| code.map_or("signal".to_string(), |c| c.to_string());
+        text.push_str(&format!(
+            "\nWarning: exit codes differ (untraced {}, traced {}), so the runs may not be comparable\n",
+            code(report.untraced_exit_code),
+            code(report.traced_exit_code)
+        ));
+    }
+    if let Some(session) = &report.session {
+        text.push_str(&format!("\nSession saved to: {}\n", session.display()));
+    }
+    text
+}
 
-    if sessions.is_empty() {
-        println!("No trace sessions found in: {}", directory.display());
-        return Ok(());
+fn snapshot_trace(
+    session: &Path,
+    output: Option<PathBuf>,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let output = output.unwrap_or_else(|| {
+        let name = session
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("session");
+        session.with_file_name(format!("{}_snapshot_{}", name, chrono_lite_timestamp()))
+    });
+    let summary = crate::snapshot::snapshot_session(session, &output)?;
+    output::emit(&summary, format, crate::snapshot::format_summary_text)
+}
+
+/// Trace sessions found in a directory
+#[derive(Serialize)]
+struct SessionListing {
+    directory: PathBuf,
+    sessions: Vec<SessionEntry>,
+}
+
+#[derive(Serialize)]
+struct SessionEntry {
+    name: String,
+    duration_ns: Option<u64>,
+    size_bytes: u64,
+}
+
+fn list_sessions(directory: &PathBuf, format: OutputFormat) -> anyhow::Result<()> {
+    let mut sessions = Vec::new();
+
+    if directory.exists() {
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if path.is_dir() {
+                let manifest = path.join("manifest.json");
+                if manifest.exists() {
+                    sessions.push(path);
+                }
+            }
+        }
     }
 
     sessions.sort();
-    println!("Trace sessions in {}:\n", directory.display());
+    let listing = SessionListing {
+        directory: directory.clone(),
+        sessions: sessions
+            .iter()
+            .map(|session| SessionEntry {
+                name: session
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("?")
+                    .to_string(),
+                duration_ns: session_duration_ns(session),
+                size_bytes: dir_size(session),
+            })
+            .collect(),
+    };
+
+    output::emit(&listing, format, format_listing_text)
+}
 
-    for session in sessions {
-        let name = session.file_name().and_then(|n| n.to_str()).unwrap_or("?");
-        println!("  {}", name);
+fn format_listing_text(listing: &SessionListing) -> String {
+    if listing.sessions.is_empty() {
+        return format!("No trace sessions found in: {}\n", listing.directory.display());
     }
 
-    Ok(())
+    let mut table = Table::new(["SESSION", "DURATION", "SIZE"])
+        .align(1, Align::Right)
+        .align(2, Align::Right)
+        .highlight(0);
+    for session in &listing.sessions {
+        let duration = session
+            .duration_ns
+            .map(crate::fmt::duration_ns)
+            .unwrap_or_else(|| "-".to_string());
+        table.add_row([
+            session.name.clone(),
+            duration,
+            crate::fmt::bytes(session.size_bytes),
+        ]);
+    }
+
+    format!(
+        "Trace sessions in {}:\n\n{}",
+        listing.directory.display(),
+        table.render_stdout()
+    )
+}
+
+/// Duration recorded in a session's manifest, if it has time bounds
+fn session_duration_ns(session: &Path) -> Option<u64> {
+    let content = std::fs::read_to_string(session.join("manifest.json")).ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let start = manifest.get("time_start_ns")?.as_u64()?;
+    let end = manifest.get("time_end_ns")?.as_u64()?;
+    Some(end.saturating_sub(start))
+}
+
+/// Total size of the files under a directory
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
 }
 
 /// Find the tracer binary
-fn find_tracer() -> anyhow::Result<PathBuf> {
+///
+/// A configured path is used as-is and must exist.
+fn find_tracer(configured: Option<&Path>) -> anyhow::Result<PathBuf> {
+    if let Some(path) = configured {
+        if !path.exists() {
+            return Err(CliError::not_found(format!(
+                "Tracer binary not found at {}",
+                path.display()
+            )));
+        }
+        tracing::debug!("Using configured tracer: {}", path.display());
+        return Ok(path.to_path_buf());
+    }
+
     // Try common locations
     let candidates = [
         // Relative to ada binary
@@ -261,14 +682,16 @@ fn find_tracer() -> anyhow::Result<PathBuf> {
 
     for candidate in candidates.iter().flatten() {
         if candidate.exists() {
+            tracing::debug!("Using tracer: {}", candidate.display());
             return Ok(candidate.clone());
         }
+        tracing::debug!("No tracer at {}", candidate.display());
     }
 
-    anyhow::bail!(
+    Err(CliError::not_found(
         "Could not find tracer binary. Please ensure it's built and in PATH, \
-         or run from the project root."
-    )
+         or run from the project root.",
+    ))
 }
 
 /// Simple timestamp without chrono dependency
@@ -279,3 +702,61 @@ fn chrono_lite_timestamp() -> String {
         .unwrap_or_default();
     format!("{}", duration.as_secs())
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+
+    fn overhead_report() -> OverheadReport {
+        OverheadReport {
+            binary: "./workload".to_string(),
+            args: vec!["--fast".to_string()],
+            untraced_ns: 1_000_000_000,
+            traced_ns: 1_500_000_000,
+            slowdown: 1.5,
+            cpu_overhead_percent: Some(42.5),
+            memory_usage_mb: 64.0,
+            untraced_exit_code: Some(0),
+            traced_exit_code: Some(0),
+            trace_size_bytes: 2048,
+            session: None,
+        }
+    }
+
+    #[test]
+    fn overhead_report__json__then_reports_cpu_and_memory() {
+        let json =
+            output::render(&overhead_report(), OutputFormat::Json, format_overhead_text).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["slowdown"], 1.5);
+        assert_eq!(value["cpu_overhead_percent"], 42.5);
+        assert_eq!(value["memory_usage_mb"], 64.0);
+        assert_eq!(value["untraced_ns"], 1_000_000_000u64);
+        assert_eq!(value["traced_ns"], 1_500_000_000u64);
+        assert_eq!(value["trace_size_bytes"], 2048);
+        assert!(value["session"].is_null());
+    }
+
+    #[test]
+    fn overhead_report__text__then_one_row_per_measure() {
+        let mut report = overhead_report();
+        let text = format_overhead_text(&report);
+        assert!(text.contains("  slowdown      1.50x\n"), "{text}");
+        assert!(text.contains("  cpu overhead  +42.5%\n"), "{text}");
+        assert!(text.contains("  tracer memory 64.0 MB\n"), "{text}");
+        assert!(!text.contains("Warning"), "{text}");
+
+        report.cpu_overhead_percent = None;
+        assert!(format_overhead_text(&report).contains("  cpu overhead  unknown\n"));
+    }
+
+    #[test]
+    fn cpu_overhead_percent__traced_slower__then_extra_share_of_untraced() {
+        assert_eq!(cpu_overhead_percent(200, 300), 50.0);
+        assert_eq!(cpu_overhead_percent(200, 200), 0.0);
+        assert_eq!(cpu_overhead_percent(0, 0), 0.0);
+    }
+}
@@ -0,0 +1,527 @@
+//! Well-formedness checks for a recorded trace.
+//!
+//! `ada trace validate` runs every check and fails when any of them finds a
+//! problem, so CI can gate archiving a trace on a single command:
+//!
+//! - `manifest`: manifest.json parses, lists each thread once, lists every
+//!   thread directory on disk, and its time bounds are ordered
+//! - `event_count`: each index file has a valid header and footer whose
+//!   event counts agree with the size of its events section
+//! - `monotonic`: timestamps never go backwards within a thread
+//! - `span_balance`: within a thread every return closes a call and every
+//!   call is closed by the end of the trace
+//!
+//! Files are read as raw bytes rather than through the query readers, which
+//! fall back to best-effort values exactly where these checks must not.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::exit_code::{CliError, ExitCode};
+
+const HEADER_SIZE: usize = 64;
+const FOOTER_SIZE: usize = 64;
+const INDEX_EVENT_SIZE: usize = 32;
+const EVENT_KIND_CALL: u32 = 1;
+const EVENT_KIND_RETURN: u32 = 2;
+
+/// Checks to leave out of a validation run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SkipChecks {
+    pub manifest: bool,
+    pub event_count: bool,
+    pub monotonic: bool,
+    pub span_balance: bool,
+}
+
+/// Outcome of every check for one trace
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    pub session: PathBuf,
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// One line per problem found; empty unless the check failed
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skipped,
+}
+
+/// One thread's index file, as far as it could be read
+struct ThreadIndex {
+    id: u32,
+    bytes: Vec<u8>,
+}
+
+impl ThreadIndex {
+    fn header_u32(&self, offset: usize) -> u32 {
+        read_u32(&self.bytes, offset)
+    }
+
+    fn header_u64(&self, offset: usize) -> u64 {
+        read_u64(&self.bytes, offset)
+    }
+
+    /// Problems with the header, or `None` if events can be read
+    fn header_problem(&self) -> Option<String> {
+        if self.bytes.len() < HEADER_SIZE {
+            return Some(format!(
+                "thread {}: index.atf is {} bytes, shorter than its header",
+                self.id,
+                self.bytes.len()
+            ));
+        }
+        if &self.bytes[..4] != b"ATI2" || self.bytes[5] != 1 || self.bytes[4] != 1 {
+            return Some(format!(
+                "thread {}: index.atf header is not ATF v2",
+                self.id
+            ));
+        }
+        if self.header_u32(24) != INDEX_EVENT_SIZE as u32 {
+            return Some(format!(
+                "thread {}: index event size is {}, expected {}",
+                self.id,
+                self.header_u32(24),
+                INDEX_EVENT_SIZE
+            ));
+        }
+        None
+    }
+
+    /// The events section, ending at the footer or, without a readable
+    /// footer, at the last whole event in the file
+    fn events(&self) -> &[u8] {
+        if self.header_problem().is_some() {
+            return &[];
+        }
+        let start = (self.header_u64(32) as usize).min(self.bytes.len());
+        let end = match self.footer() {
+            Some(_) => self.header_u64(40) as usize,
+            None => self.bytes.len(),
+        };
+        let section = &self.bytes[start..end.max(start)];
+        &section[..section.len() - section.len() % INDEX_EVENT_SIZE]
+    }
+
+    fn footer(&self) -> Option<&[u8]> {
+        let offset = self.header_u64(40) as usize;
+        let footer = self.bytes.get(offset..offset.checked_add(FOOTER_SIZE)?)?;
+        (&footer[..4] == b"2ITA").then_some(footer)
+    }
+
+    /// (timestamp, kind) of each event, in file order
+    fn event_kinds(&self) -> impl Iterator<Item = (u64, u32)> + '_ {
+        self.events()
+            .chunks_exact(INDEX_EVENT_SIZE)
+            .map(|event| (read_u64(event, 0), read_u32(event, 20)))
+    }
+}
+
+/// Run the checks not skipped against the trace in `session`
+pub fn validate_session(session: &Path, skip: SkipChecks) -> Result<ValidationReport> {
+    if !session.is_dir() {
+        return Err(CliError::not_found(format!(
+            "Session directory not found: {}",
+            session.display()
+        )));
+    }
+
+    let mut thread_ids: Vec<u32> = fs::read_dir(session)?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            name.to_str()?.strip_prefix("thread_")?.parse().ok()
+        })
+        .collect();
+    thread_ids.sort_unstable();
+
+    let mut threads = Vec::new();
+    for id in &thread_ids {
+        let path = session.join(format!("thread_{}", id)).join("index.atf");
+        // A missing index is reported by the manifest check
+        if let Ok(bytes) = fs::read(&path) {
+            threads.push(ThreadIndex { id: *id, bytes });
+        }
+    }
+
+    let checks = vec![
+        run_check("manifest", skip.manifest, || {
+            check_manifest(session, &thread_ids)
+        }),
+        run_check("event_count", skip.event_count, || {
+            check_event_count(&threads)
+        }),
+        run_check("monotonic", skip.monotonic, || check_monotonic(&threads)),
+        run_check("span_balance", skip.span_balance, || {
+            check_span_balance(&threads)
+        }),
+    ];
+
+    Ok(ValidationReport {
+        session: session.to_path_buf(),
+        passed: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        checks,
+    })
+}
+
+fn run_check(name: &'static str, skipped: bool, run: impl FnOnce() -> Vec<String>) -> CheckResult {
+    if skipped {
+        return CheckResult {
+            name,
+            status: CheckStatus::Skipped,
+            problems: Vec::new(),
+        };
+    }
+    let problems = run();
+    CheckResult {
+        name,
+        status: match problems.is_empty() {
+            true => CheckStatus::Pass,
+            false => CheckStatus::Fail,
+        },
+        problems,
+    }
+}
+
+/// Print the report and turn a failed validation into a nonzero exit
+pub fn emit_report(report: &ValidationReport, format: crate::output::OutputFormat) -> Result<()> {
+    crate::output::emit(report, format, format_report_text)?;
+    if !report.passed {
+        return Err(CliError::with_code(
+            ExitCode::Failure,
+            format!("Trace validation failed: {}", report.session.display()),
+        ));
+    }
+    Ok(())
+}
+
+pub fn format_report_text(report: &ValidationReport) -> String {
+    let mut text = format!("Validating {}\n", report.session.display());
+    for check in &report.checks {
+        let status = match check.status {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skipped => "SKIP",
+        };
+        text.push_str(&format!("  {:<4}  {}\n", status, check.name));
+        for problem in &check.problems {
+            text.push_str(&format!("          {}\n", problem));
+        }
+    }
+    let verdict = if report.passed { "passed" } else { "FAILED" };
+    text.push_str(&format!("Validation {}\n", verdict));
+    text
+}
+
+fn check_manifest(session: &Path, thread_dirs: &[u32]) -> Vec<String> {
+    let manifest: serde_json::Value = match fs::read(session.join("manifest.json"))
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|err| err.to_string()))
+    {
+        Ok(manifest) => manifest,
+        Err(err) => return vec![format!("manifest.json unreadable: {}", err)],
+    };
+
+    let mut problems = Vec::new();
+    let Some(threads) = manifest.get("threads").and_then(|t| t.as_array()) else {
+        return vec!["manifest.json has no threads list".to_string()];
+    };
+    let mut listed = BTreeSet::new();
+    for thread in threads {
+        let Some(id) = thread.get("id").and_then(|id| id.as_u64()) else {
+            problems.push(format!("thread entry without an id: {}", thread));
+            continue;
+        };
+        if !listed.insert(id as u32) {
+            problems.push(format!("thread {} listed more than once", id));
+        }
+        let dir = session.join(format!("thread_{}", id));
+        if !dir.join("index.atf").is_file() {
+            problems.push(format!("thread {}: index.atf missing", id));
+        }
+        let has_detail = thread.get("has_detail").and_then(|d| d.as_bool());
+        if has_detail == Some(true) && !dir.join("detail.atf").is_file() {
+            problems.push(format!(
+                "thread {}: listed with detail but detail.atf missing",
+                id
+            ));
+        }
+    }
+    for id in thread_dirs {
+        if !listed.contains(id) {
+            problems.push(format!("thread_{} is not listed in the manifest", id));
+        }
+    }
+
+    let bound = |key: &str| manifest.get(key).and_then(|v| v.as_u64());
+    if let (Some(start), Some(end)) = (bound("time_start_ns"), bound("time_end_ns")) {
+        if start > end {
+            problems.push(format!(
+                "time_start_ns {} is after time_end_ns {}",
+                start, end
+            ));
+        }
+    }
+    problems
+}
+
+fn check_event_count(threads: &[ThreadIndex]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for thread in threads {
+        if let Some(problem) = thread.header_problem() {
+            problems.push(problem);
+            continue;
+        }
+        let Some(footer) = thread.footer() else {
+            problems.push(format!("thread {}: index.atf has no footer", thread.id));
+            continue;
+        };
+        let events_offset = thread.header_u64(32);
+        let section = thread.header_u64(40).saturating_sub(events_offset);
+        if thread.header_u64(40) < events_offset || section % INDEX_EVENT_SIZE as u64 != 0 {
+            problems.push(format!(
+                "thread {}: events section of {} bytes is not whole events",
+                thread.id, section
+            ));
+            continue;
+        }
+        let actual = section / INDEX_EVENT_SIZE as u64;
+        let header_count = thread.header_u32(28) as u64;
+        let footer_count = read_u64(footer, 8);
+        let footer_bytes = read_u64(footer, 32);
+        if header_count != actual || footer_count != actual || footer_bytes != section {
+            problems.push(format!(
+                "thread {}: {} events on disk, header says {}, footer says {} ({} bytes)",
+                thread.id, actual, header_count, footer_count, footer_bytes
+            ));
+        }
+    }
+    problems
+}
+
+fn check_monotonic(threads: &[ThreadIndex]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for thread in threads {
+        let mut previous = None;
+        let mut backwards = 0u64;
+        let mut first = None;
+        for (seq, (timestamp_ns, _)) in thread.event_kinds().enumerate() {
+            if let Some(previous) = previous.filter(|&previous| timestamp_ns < previous) {
+                backwards += 1;
+                first.get_or_insert((seq, timestamp_ns, previous));
+            }
+            previous = Some(timestamp_ns);
+        }
+        if let Some((seq, timestamp_ns, previous)) = first {
+            problems.push(format!(
+                "thread {}: {} timestamps go backwards, first at event {} ({} < {})",
+                thread.id, backwards, seq, timestamp_ns, previous
+            ));
+        }
+    }
+    problems
+}
+
+fn check_span_balance(threads: &[ThreadIndex]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for thread in threads {
+        let mut open = 0u64;
+        let mut unmatched = 0u64;
+        let mut first_unmatched = None;
+        for (seq, (_, kind)) in thread.event_kinds().enumerate() {
+            match kind {
+                EVENT_KIND_CALL => open += 1,
+                EVENT_KIND_RETURN if open > 0 => open -= 1,
+                EVENT_KIND_RETURN => {
+                    unmatched += 1;
+                    first_unmatched.get_or_insert(seq);
+                }
+                _ => {}
+            }
+        }
+        if let Some(seq) = first_unmatched {
+            problems.push(format!(
+                "thread {}: {} returns without a call, first at event {}",
+                thread.id, unmatched, seq
+            ));
+        }
+        if open > 0 {
+            problems.push(format!("thread {}: {} calls never return", thread.id, open));
+        }
+    }
+    problems
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use tempfile::TempDir;
+
+    fn index_event(timestamp_ns: u64, kind: u32) -> Vec<u8> {
+        let mut event = vec![0u8; INDEX_EVENT_SIZE];
+        event[..8].copy_from_slice(&timestamp_ns.to_le_bytes());
+        event[20..24].copy_from_slice(&kind.to_le_bytes());
+        event
+    }
+
+    /// A finalized thread whose header and footer agree with `events`
+    fn write_thread(session: &Path, id: u32, events: &[Vec<u8>]) {
+        let dir = session.join(format!("thread_{}", id));
+        fs::create_dir_all(&dir).unwrap();
+        let events = events.concat();
+        let count = (events.len() / INDEX_EVENT_SIZE) as u64;
+
+        let mut header = vec![0u8; HEADER_SIZE];
+        header[..4].copy_from_slice(b"ATI2");
+        header[4] = 1;
+        header[5] = 1;
+        header[12..16].copy_from_slice(&id.to_le_bytes());
+        header[24..28].copy_from_slice(&(INDEX_EVENT_SIZE as u32).to_le_bytes());
+        header[28..32].copy_from_slice(&(count as u32).to_le_bytes());
+        header[32..40].copy_from_slice(&(HEADER_SIZE as u64).to_le_bytes());
+        header[40..48].copy_from_slice(&((HEADER_SIZE + events.len()) as u64).to_le_bytes());
+
+        let mut footer = vec![0u8; FOOTER_SIZE];
+        footer[..4].copy_from_slice(b"2ITA");
+        footer[8..16].copy_from_slice(&count.to_le_bytes());
+        footer[32..40].copy_from_slice(&(events.len() as u64).to_le_bytes());
+
+        fs::write(dir.join("index.atf"), [header, events, footer].concat()).unwrap();
+    }
+
+    fn write_manifest(session: &Path, ids: &[u32]) {
+        let threads: Vec<_> = ids
+            .iter()
+            .map(|id| serde_json::json!({ "id": id }))
+            .collect();
+        let manifest = serde_json::json!({
+            "threads": threads,
+            "time_start_ns": 100,
+            "time_end_ns": 400,
+        });
+        fs::write(session.join("manifest.json"), manifest.to_string()).unwrap();
+    }
+
+    fn status(report: &ValidationReport, name: &str) -> CheckStatus {
+        report
+            .checks
+            .iter()
+            .find(|check| check.name == name)
+            .unwrap()
+            .status
+    }
+
+    #[test]
+    fn validate_session__well_formed_trace__then_every_check_passes() {
+        let root = TempDir::new().unwrap();
+        write_thread(
+            root.path(),
+            1,
+            &[
+                index_event(100, EVENT_KIND_CALL),
+                index_event(200, EVENT_KIND_CALL),
+                index_event(300, EVENT_KIND_RETURN),
+                index_event(400, EVENT_KIND_RETURN),
+            ],
+        );
+        write_manifest(root.path(), &[1]);
+
+        let report = validate_session(root.path(), SkipChecks::default()).unwrap();
+
+        assert!(report.passed);
+        assert!(report.checks.iter().all(|c| c.status == CheckStatus::Pass));
+    }
+
+    #[test]
+    fn validate_session__broken_trace__then_each_problem_reported() {
+        let root = TempDir::new().unwrap();
+        write_thread(
+            root.path(),
+            1,
+            &[
+                index_event(200, EVENT_KIND_RETURN),
+                index_event(100, EVENT_KIND_CALL),
+            ],
+        );
+        // Thread 2 lost its footer and is missing from the manifest
+        write_thread(root.path(), 2, &[index_event(100, EVENT_KIND_CALL)]);
+        let index = root.path().join("thread_2/index.atf");
+        let bytes = fs::read(&index).unwrap();
+        fs::write(&index, &bytes[..bytes.len() - FOOTER_SIZE]).unwrap();
+        write_manifest(root.path(), &[1]);
+
+        let report = validate_session(root.path(), SkipChecks::default()).unwrap();
+
+        assert!(!report.passed);
+        let problems = |name: &str| {
+            &report
+                .checks
+                .iter()
+                .find(|check| check.name == name)
+                .unwrap()
+                .problems
+        };
+        assert_eq!(
+            problems("manifest"),
+            &vec!["thread_2 is not listed in the manifest".to_string()]
+        );
+        assert_eq!(
+            problems("event_count"),
+            &vec!["thread 2: index.atf has no footer".to_string()]
+        );
+        assert_eq!(
+            problems("monotonic"),
+            &vec!["thread 1: 1 timestamps go backwards, first at event 1 (100 < 200)".to_string()]
+        );
+        assert_eq!(
+            problems("span_balance"),
+            &vec![
+                "thread 1: 1 returns without a call, first at event 0".to_string(),
+                "thread 1: 1 calls never return".to_string(),
+                "thread 2: 1 calls never return".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_session__failing_checks_skipped__then_passes() {
+        let root = TempDir::new().unwrap();
+        write_thread(root.path(), 1, &[index_event(100, EVENT_KIND_CALL)]);
+        write_manifest(root.path(), &[1]);
+        let skip = SkipChecks {
+            span_balance: true,
+            ..SkipChecks::default()
+        };
+
+        let report = validate_session(root.path(), skip).unwrap();
+
+        assert!(report.passed);
+        assert_eq!(status(&report, "span_balance"), CheckStatus::Skipped);
+        assert_eq!(status(&report, "event_count"), CheckStatus::Pass);
+    }
+}
@@ -0,0 +1,53 @@
+//! Feeds arbitrary bytes to the ATF v2 index and detail readers.
+//!
+//! The first input byte selects the reader (even: index, odd: detail) and the
+//! rest is written out as the file it opens. Malformed files must come back
+//! as errors or short reads, never as panics or out-of-bounds reads.
+
+#![no_main]
+
+use std::io::Write;
+
+use libfuzzer_sys::fuzz_target;
+use query_engine::atf::v2::{DetailEvent, DetailReader, IndexReader};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, body)) = data.split_first() else {
+        return;
+    };
+
+    let _ = DetailEvent::from_bytes(body);
+
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(body).unwrap();
+    file.flush().unwrap();
+
+    if selector % 2 == 0 {
+        fuzz_index(file.path());
+    } else {
+        fuzz_detail(file.path());
+    }
+});
+
+fn fuzz_index(path: &std::path::Path) {
+    let Ok(reader) = IndexReader::open(path) else {
+        return;
+    };
+    let _ = reader.time_range();
+    let _ = reader.has_detail();
+    assert_eq!(reader.iter().len(), reader.len() as usize);
+    for event in reader.iter() {
+        let _ = (event.timestamp_ns, event.function_id, event.detail_seq);
+    }
+}
+
+fn fuzz_detail(path: &std::path::Path) {
+    let Ok(reader) = DetailReader::open(path) else {
+        return;
+    };
+    for event in reader.iter() {
+        let _ = event.header();
+        let _ = event.payload().len();
+    }
+    let _ = reader.get_by_index_seq(0);
+}
@@ -0,0 +1,20 @@
+//! Feeds arbitrary bytes to the session manifest parser.
+//!
+//! Parsing must never panic, and every manifest it accepts must have a time
+//! range that does not run backwards.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use query_engine::atf::v2::Manifest;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(manifest) = Manifest::from_bytes(data) else {
+        return;
+    };
+    assert!(manifest.time_end_ns >= manifest.time_start_ns);
+    assert_eq!(
+        manifest.duration_ns(),
+        manifest.time_end_ns - manifest.time_start_ns
+    );
+});
@@ -0,0 +1,63 @@
+#!/usr/bin/env python3
+"""Write well-formed ATF v2 seed inputs for the atf_v2_reader fuzz target."""
+
+import struct
+from pathlib import Path
+
+CORPUS = Path(__file__).parent / "corpus" / "atf_v2_reader"
+INDEX_SELECTOR = b"\x00"
+DETAIL_SELECTOR = b"\x01"
+
+
+def index_file(event_count):
+    footer_offset = 64 + event_count * 32
+    header = struct.pack(
+        "<4sBBBBIIB3sIIIQQQQ",
+        b"ATI2", 1, 1, 1, 3, 1, 0, 1, b"\0" * 3, 0, 32, event_count,
+        64, footer_offset, 1000, 1000 + event_count * 100,
+    )
+    events = b"".join(
+        struct.pack("<QQIIII", 1000 + i * 100, 0x100000001 + i, 0,
+                    1 if i % 2 == 0 else 2, i % 10, i)
+        for i in range(event_count)
+    )
+    footer = struct.pack(
+        "<4sIQQQQ24s", b"2ITA", 0, event_count, 1000,
+        1000 + event_count * 100, event_count * 32, b"\0" * 24,
+    )
+    return header + events + footer
+
+
+def detail_file(event_count, payload_len):
+    total_length = 24 + payload_len
+    header = struct.pack(
+        "<4sBBBBIIIQQQQQ4s",
+        b"ATD2", 1, 1, 1, 3, 0, 0, 0, 64, event_count,
+        event_count * total_length, 0, max(event_count - 1, 0), b"\0" * 4,
+    )
+    events = b"".join(
+        struct.pack("<IHHIIQ", total_length, 3 + i % 2, 0, i, 0, 1000 + i * 100)
+        + bytes(range(payload_len))
+        for i in range(event_count)
+    )
+    footer = struct.pack(
+        "<4sIQQQQ24s", b"2DTA", 0, event_count, event_count * total_length,
+        1000, 1000 + event_count * 100, b"\0" * 24,
+    )
+    return header + events + footer
+
+
+def main():
+    CORPUS.mkdir(parents=True, exist_ok=True)
+    seeds = {
+        "seed_index_empty": INDEX_SELECTOR + index_file(0),
+        "seed_index_4_events": INDEX_SELECTOR + index_file(4),
+        "seed_detail_empty": DETAIL_SELECTOR + detail_file(0, 0),
+        "seed_detail_3_events": DETAIL_SELECTOR + detail_file(3, 16),
+    }
+    for name, data in seeds.items():
+        (CORPUS / name).write_bytes(data)
+
+
+if __name__ == "__main__":
+    main()
@@ -11,9 +11,16 @@ use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
 use crate::{
-    // TODO: Re-enable handlers after updating to ATF V2 API
-    // handlers::{EventsGetHandler, SpansListHandler, TraceInfoHandler},
-    server::{JsonRpcServer, ServerError},
+    atf::v2::is_remote_url,
+    handlers::{
+        CallGraphHandler, EventsCountHandler, EventsGetHandler, FunctionsTimingHandler,
+        SessionVerifyHandler, SignalsSummaryHandler, SpansAtTimeHandler, SpansGetHandler,
+        SpansListHandler, StacksGetHandler, SystemSchemaHandler, ThreadsCpuTimeHandler,
+        TimelineHandler, TraceAnomaliesHandler, TraceDecimateHandler, TraceFingerprintHandler,
+        TraceInfoHandler, TraceIngestHandler, TraceQuickCheckHandler, TraceSliceHandler,
+        TraceStripRegistersHandler,
+    },
+    server::{JsonRpcServer, JsonRpcServerConfig, ServerError},
 };
 
 #[derive(Parser, Debug, Clone)]
@@ -29,7 +36,8 @@ pub struct Args {
     #[arg(long, default_value = "127.0.0.1:9090")]
     pub address: SocketAddr,
 
-    /// Root directory containing trace artifacts
+    /// Root directory containing trace artifacts, or an `http://`,
+    /// `https://` or `s3://` URL serving them
     #[arg(long, value_name = "PATH", default_value = "./traces")]
     pub trace_root: PathBuf,
 
@@ -40,6 +48,10 @@ pub struct Args {
     /// Cache time-to-live in seconds
     #[arg(long, default_value_t = 300)]
     pub cache_ttl: u64,
+
+    /// Megabytes all caches may hold together; unlimited when omitted
+    #[arg(long, value_name = "MB")]
+    pub memory_budget_mb: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -48,6 +60,7 @@ pub struct AppConfig {
     pub trace_root: PathBuf,
     pub cache_size: usize,
     pub cache_ttl: Duration,
+    pub memory_budget_bytes: Option<usize>,
 }
 
 impl From<Args> for AppConfig {
@@ -57,6 +70,9 @@ impl From<Args> for AppConfig {
             trace_root: value.trace_root,
             cache_size: value.cache_size,
             cache_ttl: Duration::from_secs(value.cache_ttl),
+            memory_budget_bytes: value
+                .memory_budget_mb
+                .map(|mb| mb.saturating_mul(1024 * 1024)),
         }
     }
 }
@@ -72,21 +88,12 @@ pub fn init_tracing() {
 pub async fn run(config: AppConfig) -> Result<()> {
     ensure_trace_root(&config.trace_root).await?;
 
-    let server = JsonRpcServer::new();
-
-    // TODO: Re-enable handlers after updating to ATF V2 API
-    // let handler = TraceInfoHandler::new(
-    //     config.trace_root.clone(),
-    //     config.cache_size,
-    //     config.cache_ttl,
-    // );
-    // handler.register(&server);
-    //
-    // let events_handler = EventsGetHandler::new(config.trace_root.clone());
-    // events_handler.register(&server);
-    //
-    // let spans_handler = SpansListHandler::new(config.trace_root.clone());
-    // spans_handler.register(&server);
+    let server = JsonRpcServer::with_config(JsonRpcServerConfig {
+        memory_budget_bytes: config.memory_budget_bytes,
+        ..JsonRpcServerConfig::default()
+    });
+
+    register_handlers(&server, &config);
 
     info!(
         address = %config.address,
@@ -107,7 +114,86 @@ pub async fn run(config: AppConfig) -> Result<()> {
     Ok(())
 }
 
+/// Registers the query handlers, all reading traces under `config.trace_root`.
+pub fn register_handlers(server: &JsonRpcServer, config: &AppConfig) {
+    let handler = TraceInfoHandler::new(
+        config.trace_root.clone(),
+        config.cache_size,
+        config.cache_ttl,
+    );
+    handler.register(server);
+
+    let events_handler = EventsGetHandler::new(config.trace_root.clone());
+    events_handler.register(server);
+
+    let events_count_handler = EventsCountHandler::new(config.trace_root.clone());
+    events_count_handler.register(server);
+
+    let spans_handler = SpansListHandler::new(config.trace_root.clone());
+    spans_handler.register(server);
+
+    let spans_get_handler = SpansGetHandler::new(config.trace_root.clone());
+    spans_get_handler.register(server);
+
+    let stacks_handler = StacksGetHandler::new(config.trace_root.clone());
+    stacks_handler.register(server);
+
+    let timeline_handler = TimelineHandler::new(config.trace_root.clone());
+    timeline_handler.register(server);
+
+    let functions_timing_handler = FunctionsTimingHandler::new(config.trace_root.clone());
+    functions_timing_handler.register(server);
+
+    let call_graph_handler = CallGraphHandler::new(config.trace_root.clone());
+    call_graph_handler.register(server);
+
+    let spans_at_time_handler = SpansAtTimeHandler::new(
+        config.trace_root.clone(),
+        config.cache_size,
+        config.cache_ttl,
+    );
+    spans_at_time_handler.register(server);
+
+    let fingerprint_handler = TraceFingerprintHandler::new(config.trace_root.clone());
+    fingerprint_handler.register(server);
+
+    let anomalies_handler = TraceAnomaliesHandler::new(config.trace_root.clone());
+    anomalies_handler.register(server);
+
+    let schema_handler = SystemSchemaHandler::new();
+    schema_handler.register(server);
+
+    let cpu_time_handler = ThreadsCpuTimeHandler::new(config.trace_root.clone());
+    cpu_time_handler.register(server);
+
+    let session_verify_handler = SessionVerifyHandler::new(config.trace_root.clone());
+    session_verify_handler.register(server);
+
+    let quick_check_handler = TraceQuickCheckHandler::new(config.trace_root.clone());
+    quick_check_handler.register(server);
+
+    let signals_handler = SignalsSummaryHandler::new(config.trace_root.clone());
+    signals_handler.register(server);
+
+    let slice_handler = TraceSliceHandler::new(config.trace_root.clone());
+    slice_handler.register(server);
+
+    let decimate_handler = TraceDecimateHandler::new(config.trace_root.clone());
+    decimate_handler.register(server);
+
+    let ingest_handler = TraceIngestHandler::new(config.trace_root.clone());
+    ingest_handler.register(server);
+
+    let strip_handler = TraceStripRegistersHandler::new(config.trace_root.clone());
+    strip_handler.register(server);
+}
+
 pub async fn ensure_trace_root(path: &Path) -> Result<()> {
+    // Traces under a URL root are fetched on demand; there is nothing to create.
+    if path.to_str().is_some_and(is_remote_url) {
+        return Ok(());
+    }
+
     match tokio::fs::metadata(path).await {
         Ok(metadata) => {
             if !metadata.is_dir() {
@@ -223,6 +309,7 @@ mod tests {
         assert_eq!(config.trace_root, PathBuf::from("./traces"));
         assert_eq!(config.cache_size, 100);
         assert_eq!(config.cache_ttl, Duration::from_secs(300));
+        assert_eq!(config.memory_budget_bytes, None);
     }
 
     #[test]
@@ -237,6 +324,8 @@ mod tests {
             "250",
             "--cache-ttl",
             "60",
+            "--memory-budget-mb",
+            "64",
         ])
         .expect("custom args parse");
 
@@ -248,6 +337,7 @@ mod tests {
         assert_eq!(config.trace_root, PathBuf::from("/tmp/custom"));
         assert_eq!(config.cache_size, 250);
         assert_eq!(config.cache_ttl, Duration::from_secs(60));
+        assert_eq!(config.memory_budget_bytes, Some(64 * 1024 * 1024));
     }
 
     #[tokio::test]
@@ -262,6 +352,15 @@ mod tests {
         assert!(new_dir.is_dir());
     }
 
+    #[tokio::test]
+    async fn ensure_trace_root__url__then_nothing_created() {
+        ensure_trace_root(Path::new("https://traces.example.com/root"))
+            .await
+            .expect("url root");
+
+        assert!(!Path::new("https:").exists());
+    }
+
     #[tokio::test]
     async fn ensure_trace_root__path_is_file__then_returns_error() {
         let root = tempdir().expect("tempdir");
@@ -319,6 +418,7 @@ mod tests {
             trace_root: trace_root.path().to_path_buf(),
             cache_size: 8,
             cache_ttl: Duration::from_secs(1),
+            memory_budget_bytes: None,
         };
 
         let result = run(config).await;
@@ -336,6 +436,7 @@ mod tests {
             trace_root: trace_root.path().to_path_buf(),
             cache_size: 8,
             cache_ttl: Duration::from_secs(1),
+            memory_budget_bytes: None,
         };
 
         let server_task = tokio::spawn(run(config));
@@ -428,6 +529,7 @@ mod tests {
             trace_root: PathBuf::from("/custom/trace/path"),
             cache_size: 512,
             cache_ttl: 900,
+            memory_budget_mb: Some(64),
         };
 
         let config = AppConfig::from(args);
@@ -436,6 +538,48 @@ mod tests {
         assert_eq!(config.trace_root, PathBuf::from("/custom/trace/path"));
         assert_eq!(config.cache_size, 512);
         assert_eq!(config.cache_ttl, Duration::from_secs(900));
+        assert_eq!(config.memory_budget_bytes, Some(64 * 1024 * 1024));
+    }
+
+    #[test]
+    fn register_handlers__then_query_methods_registered() {
+        let server = JsonRpcServer::new();
+        let config = AppConfig {
+            address: "127.0.0.1:0".parse().expect("parse address"),
+            trace_root: PathBuf::from("/tmp/traces"),
+            cache_size: 4,
+            cache_ttl: Duration::from_secs(60),
+            memory_budget_bytes: None,
+        };
+
+        register_handlers(&server, &config);
+
+        let registry = server.handler_registry();
+        for method in [
+            "trace.info",
+            "events.get",
+            "events.count",
+            "spans.list",
+            "spans.get",
+            "stacks.get",
+            "trace.timeline",
+            "functions.timing",
+            "functions.callGraph",
+            "spans.atTime",
+            "trace.fingerprint",
+            "trace.anomalies",
+            "system.schema",
+            "threads.cpuTime",
+            "session.verify",
+            "trace.quickCheck",
+            "signals.summary",
+            "trace.slice",
+            "trace.decimate",
+            "trace.ingest",
+            "trace.stripRegisters",
+        ] {
+            assert!(registry.contains(method), "{method} not registered");
+        }
     }
 
     /// Direct unit test for init_tracing function coverage
@@ -473,6 +617,7 @@ mod tests {
             trace_root: file_path,
             cache_size: 10,
             cache_ttl: Duration::from_secs(30),
+            memory_budget_bytes: None,
         };
 
         let result = run(config).await;
@@ -492,6 +637,7 @@ mod tests {
             trace_root: trace_path,
             cache_size: 25,
             cache_ttl: Duration::from_secs(60),
+            memory_budget_bytes: None,
         };
 
         // Run for a very short time to exercise initialization but not full serving
@@ -1,12 +1,23 @@
 // ATF V2 is now the primary format
+pub mod parsed;
 pub mod v2;
 
+pub use parsed::{arch_name, os_name, AtfError, ManifestInfo, ParsedEvent, ParsedEventKind};
+
 // Re-export V2 types as top-level for convenience
 pub use v2::{
     error::{AtfV2Error, Result as AtfV2Result},
     types::{IndexEvent, DetailEvent},
-    session::{SessionReader, Manifest, ThreadInfo},
+    session::{
+        format_wall_clock_ns, SessionReader, Manifest, ThreadInfo, Endianness, DropPolicy,
+        ChildSession, ClockMapping,
+    },
     thread::ThreadReader,
     index::IndexReader,
     detail::DetailReader,
+    remote::{is_remote_url, Fetcher, HttpFetcher},
+    verify::{
+        QuickCheckReport, QuickCheckThread, VerifyIssue, VerifyIssueKind, VerifyReport,
+        QUICK_CHECK_SAMPLE_SIZE,
+    },
 };
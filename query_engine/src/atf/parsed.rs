@@ -0,0 +1,221 @@
+// Decoded event model the query handlers work on
+//
+// Handlers see a trace as a summary manifest plus a stream of decoded
+// events, independent of the on-disk layout. V2 sessions are adapted to it by
+// `handlers::source::V2EventSource`.
+
+use std::{fmt, path::PathBuf};
+
+use thiserror::Error;
+
+use super::v2::AtfV2Error;
+
+/// Errors surfaced to handlers while opening or reading a trace
+#[derive(Debug, Error)]
+pub enum AtfError {
+    #[error("trace not found: {}", .0.display())]
+    TraceNotFound(PathBuf),
+
+    #[error("manifest not found: {}", .0.display())]
+    ManifestNotFound(PathBuf),
+
+    #[error("invalid manifest: {0}")]
+    Manifest(String),
+
+    #[error("failed to decode events: {0}")]
+    Decode(String),
+
+    #[error("I/O error at {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to fetch {url}: {source}")]
+    Fetch {
+        url: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("blocking task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl AtfError {
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        AtfError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Maps a V2 reader error for the session in `session_dir`
+    pub fn from_v2(session_dir: impl Into<PathBuf>, err: AtfV2Error) -> Self {
+        let session_dir = session_dir.into();
+        match err {
+            AtfV2Error::Io { path, source } if source.kind() == std::io::ErrorKind::NotFound => {
+                if !session_dir.is_dir() {
+                    AtfError::TraceNotFound(session_dir)
+                } else if path == session_dir.join("manifest.json") {
+                    AtfError::ManifestNotFound(path)
+                } else {
+                    AtfError::io(path, source)
+                }
+            }
+            AtfV2Error::Io { path, source } => AtfError::io(path, source),
+            err @ (AtfV2Error::ManifestJson(_) | AtfV2Error::InvalidManifest(_)) => {
+                AtfError::Manifest(err.to_string())
+            }
+            AtfV2Error::Fetch { url, source } => AtfError::Fetch { url, source },
+            other => AtfError::Decode(format!("failed to read V2 session: {other}")),
+        }
+    }
+}
+
+/// Summary of a trace, as handlers report it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestInfo {
+    pub os: String,
+    pub arch: String,
+    pub time_start_ns: u64,
+    pub time_end_ns: u64,
+    pub event_count: u64,
+}
+
+impl ManifestInfo {
+    pub fn duration_ns(&self) -> u64 {
+        self.time_end_ns.saturating_sub(self.time_start_ns)
+    }
+}
+
+/// Name of an index header `os` code
+pub fn os_name(code: u8) -> &'static str {
+    match code {
+        1 => "ios",
+        2 => "android",
+        3 => "macos",
+        4 => "linux",
+        5 => "windows",
+        _ => "unknown",
+    }
+}
+
+/// Name of an index header `arch` code
+pub fn arch_name(code: u8) -> &'static str {
+    match code {
+        1 => "x86_64",
+        2 => "arm64",
+        _ => "unknown",
+    }
+}
+
+/// A decoded event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedEvent {
+    pub timestamp_ns: u64,
+    pub thread_id: u32,
+    pub kind: ParsedEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedEventKind {
+    FunctionCall { symbol: Option<String> },
+    FunctionReturn { symbol: Option<String> },
+    Unknown,
+}
+
+impl ParsedEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParsedEventKind::FunctionCall { .. } => "FunctionCall",
+            ParsedEventKind::FunctionReturn { .. } => "FunctionReturn",
+            ParsedEventKind::Unknown => "Unknown",
+        }
+    }
+
+    /// Symbol of a call or return
+    pub fn function_symbol(&self) -> Option<&str> {
+        match self {
+            ParsedEventKind::FunctionCall { symbol }
+            | ParsedEventKind::FunctionReturn { symbol } => symbol.as_deref(),
+            ParsedEventKind::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for ParsedEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn from_v2__missing_manifest__then_manifest_not_found() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let manifest_path = dir.path().join("manifest.json");
+        let err = AtfError::from_v2(
+            dir.path(),
+            AtfV2Error::io(&manifest_path, io::Error::from(io::ErrorKind::NotFound)),
+        );
+        assert!(matches!(err, AtfError::ManifestNotFound(path) if path == manifest_path));
+
+        let missing = dir.path().join("missing");
+        let err = AtfError::from_v2(
+            &missing,
+            AtfV2Error::io(
+                missing.join("manifest.json"),
+                io::Error::from(io::ErrorKind::NotFound),
+            ),
+        );
+        assert!(matches!(err, AtfError::TraceNotFound(path) if path == missing));
+    }
+
+    #[test]
+    fn from_v2__malformed_manifest__then_manifest_error() {
+        let err = AtfError::from_v2(
+            "/tmp",
+            AtfV2Error::InvalidManifest("time_end_ns 1 is before time_start_ns 2".into()),
+        );
+        assert!(matches!(err, AtfError::Manifest(message) if message.contains("time_end_ns")));
+
+        let err = AtfError::from_v2("/tmp", AtfV2Error::UnsupportedVersion(9));
+        assert!(matches!(err, AtfError::Decode(_)));
+    }
+
+    #[test]
+    fn from_v2__fetch_failure__then_fetch_error_with_url() {
+        let err = AtfError::from_v2(
+            "http://host/trace",
+            AtfV2Error::fetch(
+                "http://host/trace/manifest.json",
+                io::Error::from(io::ErrorKind::TimedOut),
+            ),
+        );
+        assert!(matches!(
+            err,
+            AtfError::Fetch { url, source }
+                if url == "http://host/trace/manifest.json"
+                    && source.kind() == io::ErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    fn parsed_event_kind__as_str_and_symbol__then_expected() {
+        let call = ParsedEventKind::FunctionCall {
+            symbol: Some("0x10".into()),
+        };
+        assert_eq!(call.as_str(), "FunctionCall");
+        assert_eq!(call.function_symbol(), Some("0x10"));
+        assert_eq!(ParsedEventKind::Unknown.to_string(), "Unknown");
+        assert_eq!(ParsedEventKind::Unknown.function_symbol(), None);
+    }
+}
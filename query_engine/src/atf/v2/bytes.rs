@@ -0,0 +1,64 @@
+// Backing storage shared by the index and detail readers
+
+use memmap2::Mmap;
+use std::ops::Deref;
+
+/// Contents of a session file, mapped from disk or fetched from a server
+pub(crate) enum FileBytes {
+    Mapped(Mmap),
+    /// Stored as `u64` words so records read in place are as aligned as in a
+    /// page-aligned map
+    Fetched {
+        words: Vec<u64>,
+        len: usize,
+    },
+}
+
+impl FileBytes {
+    pub(crate) fn fetched(bytes: &[u8]) -> Self {
+        let mut words = vec![0u64; bytes.len().div_ceil(8)];
+        // SAFETY: `words` spans at least `bytes.len()` bytes and the two
+        // buffers are distinct allocations
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                words.as_mut_ptr() as *mut u8,
+                bytes.len(),
+            );
+        }
+        FileBytes::Fetched {
+            words,
+            len: bytes.len(),
+        }
+    }
+}
+
+impl Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Mapped(mmap) => mmap,
+            // SAFETY: the first `len` bytes of `words` were initialised from
+            // the fetched bytes, and the slice borrows `self`
+            FileBytes::Fetched { words, len } => unsafe {
+                std::slice::from_raw_parts(words.as_ptr() as *const u8, *len)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_bytes__fetched__then_contents_kept_and_aligned() {
+        let bytes: Vec<u8> = (0..13).collect();
+
+        let fetched = FileBytes::fetched(&bytes);
+
+        assert_eq!(&*fetched, &bytes[..]);
+        assert_eq!(fetched.as_ptr() as usize % 8, 0);
+    }
+}
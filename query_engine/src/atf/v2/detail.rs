@@ -1,6 +1,7 @@
 // User Story: M1_E5_I2 - ATF V2 Detail Reader
 // Tech Spec: M1_E5_I2_TECH_DESIGN.md - Memory-mapped reader for variable-length detail events
 
+use super::bytes::FileBytes;
 use super::error::{AtfV2Error, Result};
 use super::types::{AtfDetailFooter, AtfDetailHeader, DetailEvent};
 use memmap2::Mmap;
@@ -9,7 +10,7 @@ use std::path::Path;
 
 /// Memory-mapped reader for ATF v2 detail files
 pub struct DetailReader {
-    mmap: Mmap,
+    mmap: FileBytes,
     header: AtfDetailHeader,
     footer: Option<AtfDetailFooter>,
     events_offset: usize,
@@ -21,9 +22,13 @@ pub struct DetailReader {
 impl DetailReader {
     /// Open and memory-map a detail file, building the event index
     pub fn open(path: &Path) -> Result<Self> {
-        let file = File::open(path)?;
-        let mmap = unsafe { Mmap::map(&file)? };
+        let file = File::open(path).map_err(|e| AtfV2Error::io(path, e))?;
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| AtfV2Error::io(path, e))?;
+        Self::from_bytes(FileBytes::Mapped(mmap))
+    }
 
+    /// Read a detail file already held in memory, building the event index
+    pub(crate) fn from_bytes(mmap: FileBytes) -> Result<Self> {
         // Validate file size (at least header)
         if mmap.len() < 64 {
             return Err(AtfV2Error::FileTooSmall {
@@ -65,7 +70,7 @@ impl DetailReader {
     }
 
     /// Validate detail header
-    fn validate_header(header: &AtfDetailHeader) -> Result<()> {
+    pub(super) fn validate_header(header: &AtfDetailHeader) -> Result<()> {
         // Check magic bytes
         if &header.magic != b"ATD2" {
             return Err(AtfV2Error::InvalidMagic {
@@ -88,7 +93,7 @@ impl DetailReader {
     }
 
     /// Read footer if present
-    fn read_footer(mmap: &Mmap) -> Option<AtfDetailFooter> {
+    fn read_footer(mmap: &[u8]) -> Option<AtfDetailFooter> {
         if mmap.len() < 128 {
             return None; // Need at least header + footer
         }
@@ -109,7 +114,7 @@ impl DetailReader {
     }
 
     /// Build index of detail events for O(1) access
-    fn build_event_index(mmap: &Mmap, header: &AtfDetailHeader) -> Result<Vec<usize>> {
+    fn build_event_index(mmap: &[u8], header: &AtfDetailHeader) -> Result<Vec<usize>> {
         let mut index = Vec::new();
         let mut offset = header.events_offset as usize;
         let end_offset = mmap.len().saturating_sub(64); // Leave room for footer
@@ -175,6 +180,13 @@ impl DetailReader {
         self.event_index.is_empty()
     }
 
+    /// Event count recorded in the footer, or `None` without a valid footer
+    ///
+    /// Differs from `len` when records are missing or cut short.
+    pub fn footer_event_count(&self) -> Option<u64> {
+        self.footer.map(|footer| footer.event_count)
+    }
+
     /// Get thread ID
     pub fn thread_id(&self) -> u32 {
         self.header.thread_id
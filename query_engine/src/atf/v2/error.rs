@@ -1,12 +1,19 @@
 // User Story: M1_E5_I2 - ATF V2 Reader error handling
 // Tech Spec: M1_E5_I2_TECH_DESIGN.md - Error types for reader operations
 
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum AtfV2Error {
-    #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
+    /// Opening or mapping a session file failed; `source` is the OS error
+    #[error("I/O error at {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
 
     #[error("Invalid magic bytes: expected {expected:?}, got {got:?}")]
     InvalidMagic {
@@ -37,6 +44,42 @@ pub enum AtfV2Error {
 
     #[error("Invalid offset: {offset} out of bounds (file size: {file_size})")]
     InvalidOffset { offset: usize, file_size: usize },
+
+    /// `manifest.json` is not valid JSON or does not match the schema
+    #[error("Malformed manifest: {0}")]
+    ManifestJson(#[from] serde_json::Error),
+
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
+
+    /// The tracer still holds the session lock and is writing the files
+    #[error("Trace is being written: {}", .0.display())]
+    SessionInProgress(PathBuf),
+
+    /// Fetching a session file from a remote store failed; `source` is the
+    /// network or HTTP error
+    #[error("Fetch error at {url}: {source}")]
+    Fetch {
+        url: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl AtfV2Error {
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        AtfV2Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn fetch(url: impl Into<String>, source: std::io::Error) -> Self {
+        AtfV2Error::Fetch {
+            url: url.into(),
+            source,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, AtfV2Error>;
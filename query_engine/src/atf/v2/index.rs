@@ -1,6 +1,7 @@
 // User Story: M1_E5_I2 - ATF V2 Index Reader
 // Tech Spec: M1_E5_I2_TECH_DESIGN.md - Memory-mapped reader for index files with O(1) access
 
+use super::bytes::FileBytes;
 use super::error::{AtfV2Error, Result};
 use super::types::{AtfIndexFooter, AtfIndexHeader, IndexEvent, ATF_INDEX_FLAG_HAS_DETAIL_FILE};
 use memmap2::Mmap;
@@ -9,7 +10,7 @@ use std::path::Path;
 
 /// Memory-mapped reader for ATF v2 index files
 pub struct IndexReader {
-    _mmap: Mmap,
+    _bytes: FileBytes,
     header: AtfIndexHeader,
     footer: Option<AtfIndexFooter>,
     events_offset: usize,
@@ -18,8 +19,8 @@ pub struct IndexReader {
 }
 
 // SAFETY: IndexReader is Send + Sync because:
-// - Mmap is Send + Sync
-// - events_ptr points to memory-mapped read-only data
+// - FileBytes is Send + Sync
+// - events_ptr points into the read-only bytes it owns
 // - No interior mutability
 unsafe impl Send for IndexReader {}
 unsafe impl Sync for IndexReader {}
@@ -27,8 +28,14 @@ unsafe impl Sync for IndexReader {}
 impl IndexReader {
     /// Open and memory-map an index file
     pub fn open(path: &Path) -> Result<Self> {
-        let file = File::open(path)?;
-        let mmap = unsafe { Mmap::map(&file)? };
+        let file = File::open(path).map_err(|e| AtfV2Error::io(path, e))?;
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| AtfV2Error::io(path, e))?;
+        Self::from_bytes(FileBytes::Mapped(mmap))
+    }
+
+    /// Read an index file already held in memory
+    pub(crate) fn from_bytes(bytes: FileBytes) -> Result<Self> {
+        let mmap = &*bytes;
 
         // Validate file size (at least header)
         if mmap.len() < 64 {
@@ -45,7 +52,7 @@ impl IndexReader {
         Self::validate_header(&header)?;
 
         // Try to read footer (authoritative for event count)
-        let (footer, event_count) = Self::read_footer(&mmap, &header);
+        let (footer, event_count) = Self::read_footer(mmap, &header);
 
         let events_offset = header.events_offset as usize;
 
@@ -58,11 +65,19 @@ impl IndexReader {
             });
         }
 
+        // Never trust a footer count beyond the events section: with a valid
+        // footer the events end where the footer starts, otherwise at EOF
+        let events_end = match footer {
+            Some(_) => header.footer_offset as usize,
+            None => mmap.len(),
+        };
+        let event_count = event_count.min((events_end.saturating_sub(events_offset) / 32) as u32);
+
         // Calculate pointer to events array
         let events_ptr = unsafe { mmap.as_ptr().add(events_offset) as *const IndexEvent };
 
         Ok(IndexReader {
-            _mmap: mmap,
+            _bytes: bytes,
             header,
             footer,
             events_offset,
@@ -72,7 +87,7 @@ impl IndexReader {
     }
 
     /// Validate index header
-    fn validate_header(header: &AtfIndexHeader) -> Result<()> {
+    pub(super) fn validate_header(header: &AtfIndexHeader) -> Result<()> {
         // Check magic bytes
         if &header.magic != b"ATI2" {
             return Err(AtfV2Error::InvalidMagic {
@@ -100,11 +115,11 @@ impl IndexReader {
     }
 
     /// Read footer and determine authoritative event count
-    fn read_footer(mmap: &Mmap, header: &AtfIndexHeader) -> (Option<AtfIndexFooter>, u32) {
+    fn read_footer(mmap: &[u8], header: &AtfIndexHeader) -> (Option<AtfIndexFooter>, u32) {
         let footer_offset = header.footer_offset as usize;
 
         // Try to read footer
-        if footer_offset + 64 <= mmap.len() {
+        if footer_offset.checked_add(64).is_some_and(|end| end <= mmap.len()) {
             let footer = unsafe {
                 std::ptr::read_unaligned(
                     mmap.as_ptr().add(footer_offset) as *const AtfIndexFooter
@@ -145,6 +160,13 @@ impl IndexReader {
         self.event_count == 0
     }
 
+    /// Event count recorded in the footer, or `None` without a valid footer
+    ///
+    /// Exceeds `len` when the events section is shorter than the footer says.
+    pub fn footer_event_count(&self) -> Option<u64> {
+        self.footer.map(|footer| footer.event_count)
+    }
+
     /// Check if detail file exists
     pub fn has_detail(&self) -> bool {
         (self.header.flags & ATF_INDEX_FLAG_HAS_DETAIL_FILE) != 0
@@ -165,6 +187,22 @@ impl IndexReader {
         self.header.thread_id
     }
 
+    /// Architecture code from the header (1=x86_64, 2=arm64)
+    pub fn arch(&self) -> u8 {
+        self.header.arch
+    }
+
+    /// Operating system code from the header
+    pub fn os(&self) -> u8 {
+        self.header.os
+    }
+
+    /// Clock the timestamps were read from (1=mach_continuous, 2=qpc,
+    /// 3=boottime)
+    pub fn clock_type(&self) -> u8 {
+        self.header.clock_type
+    }
+
     /// Iterate all events
     pub fn iter(&self) -> IndexEventIter {
         IndexEventIter {
@@ -891,4 +929,32 @@ mod tests {
         assert!(reader.is_empty());
         assert_eq!(reader.len(), 0);
     }
+
+    #[test]
+    fn test_index_reader__footer_count_beyond_file__then_clamped() {
+        // Test Plan: Edge Case Tests - Corrupted index files
+        let file = create_test_index_file(10);
+        let mut bytes = std::fs::read(file.path()).unwrap();
+        let footer_offset = 64 + 10 * 32;
+        bytes[footer_offset + 8..footer_offset + 16].copy_from_slice(&1_000u64.to_le_bytes());
+        std::fs::write(file.path(), &bytes).unwrap();
+
+        let reader = IndexReader::open(file.path()).unwrap();
+        // Only the 10 events before the footer are readable
+        assert_eq!(reader.len(), 10);
+        assert!(reader.get(10).is_none());
+    }
+
+    #[test]
+    fn test_index_reader__footer_offset_overflows__then_calculated_count() {
+        // Test Plan: Edge Case Tests - Corrupted index files
+        let file = create_test_index_file(2);
+        let mut bytes = std::fs::read(file.path()).unwrap();
+        bytes[40..48].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(file.path(), &bytes).unwrap();
+
+        let reader = IndexReader::open(file.path()).unwrap();
+        assert!(reader.footer.is_none());
+        assert_eq!(reader.len(), 4);
+    }
 }
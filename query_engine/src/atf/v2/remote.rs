@@ -0,0 +1,612 @@
+// Reading sessions from HTTP(S) servers and S3 buckets
+
+use super::bytes::FileBytes;
+use super::detail::DetailReader;
+use super::error::{AtfV2Error, Result};
+use super::index::IndexReader;
+use super::thread::ThreadReader;
+use hyper::body::{self, Bytes};
+use hyper::client::HttpConnector;
+use hyper::{header, Body, Client, Request, StatusCode};
+use hyper_rustls::HttpsConnector;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::ops::Range;
+use std::thread;
+use std::time::Duration;
+
+/// Bytes requested per range request while downloading a session file
+pub const REMOTE_CHUNK_SIZE: u64 = 8 << 20;
+
+/// Range requests kept in flight while downloading a session file whose
+/// length is known
+pub const REMOTE_READ_AHEAD: usize = 4;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Fetches objects from remote storage for `SessionReader::open_url_with`
+///
+/// A missing object must fail with `io::ErrorKind::NotFound`, which marks
+/// optional session files as absent rather than failing the open.
+pub trait Fetcher: Send + Sync {
+    /// The whole object at `url`
+    fn fetch(&self, url: &str) -> io::Result<Vec<u8>>;
+
+    /// The bytes of the object at `url` within `range`. Fewer bytes come
+    /// back when the object ends inside the range, none when it ends before.
+    fn fetch_range(&self, url: &str, range: Range<u64>) -> io::Result<Vec<u8>>;
+
+    /// Like `fetch_range`, but also the length of the whole object when the
+    /// response tells it. When the whole object arrived anyway, the bytes
+    /// from `range.start` to its end may all come back.
+    ///
+    /// Knowing the length lets `fetch_file` request the remaining ranges
+    /// ahead of time instead of one after another.
+    fn fetch_range_sized(
+        &self,
+        url: &str,
+        range: Range<u64>,
+    ) -> io::Result<(Vec<u8>, Option<u64>)> {
+        Ok((self.fetch_range(url, range)?, None))
+    }
+}
+
+/// Whether `location` names a remote session rather than a local path
+pub fn is_remote_url(location: &str) -> bool {
+    ["http://", "https://", "s3://"]
+        .iter()
+        .any(|scheme| location.starts_with(scheme))
+}
+
+/// `relative` resolved against the session or directory URL `base`
+pub(crate) fn join_url(base: &str, relative: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), relative)
+}
+
+/// Downloads a file with range requests of `chunk_size` bytes
+///
+/// When the first response tells the file's length, the remaining ranges are
+/// requested with up to `REMOTE_READ_AHEAD` of them in flight, so that their
+/// round trips overlap. Otherwise each range is requested once the one
+/// before it came back full.
+pub(crate) fn fetch_file(fetcher: &dyn Fetcher, url: &str, chunk_size: u64) -> Result<FileBytes> {
+    let (mut contents, len) = fetcher
+        .fetch_range_sized(url, 0..chunk_size)
+        .map_err(|e| AtfV2Error::fetch(url, e))?;
+    match len {
+        Some(len) => {
+            contents.truncate(usize::try_from(len).unwrap_or(usize::MAX));
+            let ranges = (contents.len() as u64..len)
+                .step_by(chunk_size as usize)
+                .map(|start| start..(start + chunk_size).min(len));
+            fetch_ahead(fetcher, url, ranges, &mut contents)
+                .map_err(|e| AtfV2Error::fetch(url, e))?;
+        }
+        None => {
+            let mut last = (contents.len() as u64) < chunk_size;
+            while !last {
+                let start = contents.len() as u64;
+                let chunk = fetcher
+                    .fetch_range(url, start..start + chunk_size)
+                    .map_err(|e| AtfV2Error::fetch(url, e))?;
+                last = (chunk.len() as u64) < chunk_size;
+                contents.extend_from_slice(&chunk);
+            }
+        }
+    }
+    Ok(FileBytes::fetched(&contents))
+}
+
+/// Appends the `ranges` of `url` to `contents` in order, each fetched on a
+/// thread of its own with up to `REMOTE_READ_AHEAD` requests in flight
+fn fetch_ahead(
+    fetcher: &dyn Fetcher,
+    url: &str,
+    ranges: impl Iterator<Item = Range<u64>>,
+    contents: &mut Vec<u8>,
+) -> io::Result<()> {
+    thread::scope(|scope| {
+        let mut ranges = ranges.peekable();
+        let mut in_flight = VecDeque::new();
+        while ranges.peek().is_some() || !in_flight.is_empty() {
+            while in_flight.len() < REMOTE_READ_AHEAD {
+                let Some(range) = ranges.next() else { break };
+                let expected = range.end - range.start;
+                let request = scope.spawn(move || fetcher.fetch_range(url, range));
+                in_flight.push_back((expected, request));
+            }
+            let Some((expected, request)) = in_flight.pop_front() else {
+                break;
+            };
+            let chunk = request
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("fetch thread panicked")))?;
+            if chunk.len() as u64 != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "object changed length while it was fetched",
+                ));
+            }
+            contents.extend_from_slice(&chunk);
+        }
+        Ok(())
+    })
+}
+
+/// Like `fetch_file`, but `None` when the object does not exist
+fn fetch_optional_file(fetcher: &dyn Fetcher, url: &str) -> Result<Option<FileBytes>> {
+    match fetch_file(fetcher, url, REMOTE_CHUNK_SIZE) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(AtfV2Error::Fetch { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Remote counterpart of `ThreadReader::open`; `None` when the thread has no
+/// index file, as for a missing local thread directory
+pub(crate) fn open_thread(fetcher: &dyn Fetcher, thread_url: &str) -> Result<Option<ThreadReader>> {
+    let Some(index) = fetch_optional_file(fetcher, &join_url(thread_url, "index.atf"))? else {
+        return Ok(None);
+    };
+    let index = IndexReader::from_bytes(index)?;
+    let detail = fetch_optional_file(fetcher, &join_url(thread_url, "detail.atf"))?
+        .map(DetailReader::from_bytes)
+        .transpose()?;
+    Ok(Some(ThreadReader { index, detail }))
+}
+
+/// Fetches `http://`, `https://` and `s3://` URLs with hyper
+///
+/// `s3://bucket/key` is read anonymously from the bucket's virtual-hosted
+/// endpoint, so private buckets need a `Fetcher` that signs its requests.
+///
+/// A server that ignores `Range` answers a range request with the whole
+/// object. That body is kept and later ranges of the object are sliced from
+/// it, until a range reaches the end of the object.
+pub struct HttpFetcher {
+    runtime: tokio::runtime::Runtime,
+    client: Client<HttpsConnector<HttpConnector>>,
+    s3_endpoint: Option<String>,
+    full_bodies: Mutex<HashMap<String, Bytes>>,
+}
+
+impl HttpFetcher {
+    pub fn new() -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Ok(Self {
+            runtime,
+            client: Client::builder().build(connector),
+            s3_endpoint: None,
+            full_bodies: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Serves `s3://` URLs from `endpoint` with path-style addressing, for
+    /// S3-compatible stores such as MinIO
+    pub fn with_s3_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.s3_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// The HTTP(S) URL serving `url`
+    fn resolve(&self, url: &str) -> String {
+        let Some(path) = url.strip_prefix("s3://") else {
+            return url.to_string();
+        };
+        let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+        match &self.s3_endpoint {
+            Some(endpoint) => format!("{}/{bucket}/{key}", endpoint.trim_end_matches('/')),
+            None => format!("https://{bucket}.s3.amazonaws.com/{key}"),
+        }
+    }
+
+    /// Sends a GET on a thread of its own, since `block_on` panics on a
+    /// thread that is already driving a runtime, such as a handler's task
+    fn get(&self, url: &str, range: Option<Range<u64>>) -> io::Result<Fetched> {
+        let url = self.resolve(url);
+        thread::scope(|scope| {
+            scope
+                .spawn(|| self.runtime.block_on(self.send(&url, range)))
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("fetch thread panicked")))
+        })
+    }
+
+    async fn send(&self, url: &str, range: Option<Range<u64>>) -> io::Result<Fetched> {
+        let mut request = Request::get(url);
+        if let Some(range) = range {
+            request = request.header(
+                header::RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1),
+            );
+        }
+        let request = request
+            .body(Body::empty())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let exchange = async {
+            let response = self
+                .client
+                .request(request)
+                .await
+                .map_err(io::Error::other)?;
+            let status = response.status();
+            let object_len = response
+                .headers()
+                .get(header::CONTENT_RANGE)
+                .and_then(|value| content_range_len(value.to_str().ok()?));
+            let body = body::to_bytes(response.into_body())
+                .await
+                .map_err(io::Error::other)?;
+            Ok(Fetched {
+                status,
+                object_len,
+                body,
+            })
+        };
+        tokio::time::timeout(REQUEST_TIMEOUT, exchange)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "request timed out"))?
+    }
+}
+
+/// A response of `HttpFetcher`
+struct Fetched {
+    status: StatusCode,
+    /// Object length given by a `Content-Range` header
+    object_len: Option<u64>,
+    body: Bytes,
+}
+
+/// Object length of a `Content-Range` value such as `bytes 0-9/25` or
+/// `bytes */25`; `None` when the server did not know it
+fn content_range_len(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.parse().ok()
+}
+
+fn status_error(status: StatusCode) -> io::Error {
+    let kind = match status {
+        StatusCode::NOT_FOUND => io::ErrorKind::NotFound,
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => io::ErrorKind::PermissionDenied,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, format!("HTTP {status}"))
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, url: &str) -> io::Result<Vec<u8>> {
+        match self.get(url, None)? {
+            Fetched {
+                status: StatusCode::OK,
+                body,
+                ..
+            } => Ok(body.to_vec()),
+            Fetched { status, .. } => Err(status_error(status)),
+        }
+    }
+
+    fn fetch_range(&self, url: &str, range: Range<u64>) -> io::Result<Vec<u8>> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let cached = self.full_bodies.lock().get(url).cloned();
+        let body = match cached {
+            Some(body) => body,
+            None => {
+                let Fetched { status, body, .. } = self.get(url, Some(range.clone()))?;
+                match status {
+                    StatusCode::PARTIAL_CONTENT => return Ok(body.to_vec()),
+                    // The server ignored the Range header and sent the whole object
+                    StatusCode::OK => {
+                        self.full_bodies
+                            .lock()
+                            .insert(url.to_string(), body.clone());
+                        body
+                    }
+                    StatusCode::RANGE_NOT_SATISFIABLE => return Ok(Vec::new()),
+                    status => return Err(status_error(status)),
+                }
+            }
+        };
+
+        let clamp = |offset: u64| usize::try_from(offset).map_or(body.len(), |o| o.min(body.len()));
+        let end = clamp(range.end);
+        if end == body.len() {
+            // Nothing past this range is left to read
+            self.full_bodies.lock().remove(url);
+        }
+        Ok(body[clamp(range.start)..end].to_vec())
+    }
+
+    /// A server that ignores `Range` sends the whole object, which is
+    /// returned from `range.start` on rather than kept for later ranges
+    fn fetch_range_sized(
+        &self,
+        url: &str,
+        range: Range<u64>,
+    ) -> io::Result<(Vec<u8>, Option<u64>)> {
+        if range.is_empty() || self.full_bodies.lock().contains_key(url) {
+            return Ok((self.fetch_range(url, range)?, None));
+        }
+        let fetched = self.get(url, Some(range.clone()))?;
+        match fetched.status {
+            StatusCode::PARTIAL_CONTENT => Ok((fetched.body.to_vec(), fetched.object_len)),
+            StatusCode::OK => {
+                let body = fetched.body;
+                let start = usize::try_from(range.start).map_or(body.len(), |s| s.min(body.len()));
+                Ok((body[start..].to_vec(), Some(body.len() as u64)))
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok((Vec::new(), fetched.object_len)),
+            status => Err(status_error(status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Response, Server};
+    use std::convert::Infallible;
+    use std::error::Error;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves objects from memory and records the ranges requested. With
+    /// `sized`, responses tell the object length; with `latency`, each one
+    /// takes that long and the most requests seen in flight are counted.
+    #[derive(Default)]
+    struct MockFetcher {
+        objects: HashMap<String, Vec<u8>>,
+        ranges: Mutex<Vec<Range<u64>>>,
+        sized: bool,
+        latency: Duration,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    impl Fetcher for MockFetcher {
+        fn fetch(&self, url: &str) -> io::Result<Vec<u8>> {
+            self.fetch_range(url, 0..u64::MAX)
+        }
+
+        fn fetch_range(&self, url: &str, range: Range<u64>) -> io::Result<Vec<u8>> {
+            self.ranges.lock().push(range.clone());
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            thread::sleep(self.latency);
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            let object = self
+                .objects
+                .get(url)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such object"))?;
+            let clamp = |offset: u64| (offset as usize).min(object.len());
+            Ok(object[clamp(range.start)..clamp(range.end)].to_vec())
+        }
+
+        fn fetch_range_sized(
+            &self,
+            url: &str,
+            range: Range<u64>,
+        ) -> io::Result<(Vec<u8>, Option<u64>)> {
+            let chunk = self.fetch_range(url, range)?;
+            let len = self.objects[url].len() as u64;
+            Ok((chunk, self.sized.then_some(len)))
+        }
+    }
+
+    #[test]
+    fn test_fetch_file__larger_than_chunk__then_fetched_in_ranges() {
+        let mut fetcher = MockFetcher::default();
+        let contents: Vec<u8> = (0..25).collect();
+        fetcher.objects.insert("s3://b/f".into(), contents.clone());
+
+        let bytes = fetch_file(&fetcher, "s3://b/f", 10).unwrap();
+
+        assert_eq!(&*bytes, &contents[..]);
+        assert_eq!(*fetcher.ranges.lock(), vec![0..10, 10..20, 20..30]);
+    }
+
+    #[test]
+    fn test_fetch_file__length_known__then_ranges_fetched_ahead_in_order() {
+        let mut fetcher = MockFetcher {
+            sized: true,
+            latency: Duration::from_millis(50),
+            ..MockFetcher::default()
+        };
+        let contents: Vec<u8> = (0..55).collect();
+        fetcher.objects.insert("s3://b/f".into(), contents.clone());
+
+        let bytes = fetch_file(&fetcher, "s3://b/f", 10).unwrap();
+
+        assert_eq!(&*bytes, &contents[..]);
+        let mut ranges = fetcher.ranges.lock().clone();
+        ranges.sort_by_key(|range| range.start);
+        assert_eq!(ranges, vec![0..10, 10..20, 20..30, 30..40, 40..50, 50..55]);
+        assert!(fetcher.max_in_flight.load(Ordering::SeqCst) > 1);
+        assert!(fetcher.max_in_flight.load(Ordering::SeqCst) <= REMOTE_READ_AHEAD);
+    }
+
+    #[test]
+    fn test_fetch_file__object_shrinks_while_fetched__then_error() {
+        struct ShrinkingFetcher;
+        impl Fetcher for ShrinkingFetcher {
+            fn fetch(&self, _url: &str) -> io::Result<Vec<u8>> {
+                unreachable!()
+            }
+            fn fetch_range(&self, _url: &str, range: Range<u64>) -> io::Result<Vec<u8>> {
+                Ok(vec![
+                    0;
+                    range.end.min(15).saturating_sub(range.start) as usize
+                ])
+            }
+            fn fetch_range_sized(
+                &self,
+                url: &str,
+                range: Range<u64>,
+            ) -> io::Result<(Vec<u8>, Option<u64>)> {
+                Ok((self.fetch_range(url, range)?, Some(30)))
+            }
+        }
+
+        let err = match fetch_file(&ShrinkingFetcher, "s3://b/f", 10) {
+            Err(err) => err,
+            Ok(_) => panic!("expected fetch error"),
+        };
+
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_fetch_file__missing_object__then_fetch_error_chains_source() {
+        let fetcher = MockFetcher::default();
+
+        let err = match fetch_file(&fetcher, "https://host/missing", 10) {
+            Err(err) => err,
+            Ok(_) => panic!("expected fetch error"),
+        };
+
+        assert!(matches!(&err, AtfV2Error::Fetch { url, .. } if url == "https://host/missing"));
+        let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_open_thread__no_index__then_none() {
+        let fetcher = MockFetcher::default();
+
+        assert!(open_thread(&fetcher, "s3://b/session/thread_0")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_http_fetcher__s3_url__then_resolved_to_endpoint() {
+        let fetcher = HttpFetcher::new().unwrap();
+        assert_eq!(
+            fetcher.resolve("s3://traces/run/manifest.json"),
+            "https://traces.s3.amazonaws.com/run/manifest.json"
+        );
+
+        let fetcher = fetcher.with_s3_endpoint("http://127.0.0.1:9000/");
+        assert_eq!(
+            fetcher.resolve("s3://traces/run/manifest.json"),
+            "http://127.0.0.1:9000/traces/run/manifest.json"
+        );
+        assert_eq!(fetcher.resolve("http://host/a"), "http://host/a");
+    }
+
+    /// Serves `/object`, with byte-range support when `honor_ranges`, and
+    /// 404s everything else. The counter tracks the requests for `/object`.
+    fn serve(contents: &'static [u8], honor_ranges: bool) -> (SocketAddr, &'static AtomicUsize) {
+        let (addr_tx, addr_rx) = std::sync::mpsc::channel();
+        let requests: &'static AtomicUsize = Box::leak(Box::default());
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let make_service = make_service_fn(move |_| async move {
+                    Ok::<_, Infallible>(service_fn(move |request: Request<Body>| async move {
+                        if request.uri().path() != "/object" {
+                            let mut response = Response::new(Body::empty());
+                            *response.status_mut() = StatusCode::NOT_FOUND;
+                            return Ok::<_, Infallible>(response);
+                        }
+                        requests.fetch_add(1, Ordering::SeqCst);
+                        let range = request
+                            .headers()
+                            .get(header::RANGE)
+                            .filter(|_| honor_ranges)
+                            .and_then(|value| value.to_str().ok()?.strip_prefix("bytes="))
+                            .and_then(|range| range.split_once('-'))
+                            .map(|(start, end)| {
+                                (
+                                    start.parse::<usize>().unwrap(),
+                                    end.parse::<usize>().unwrap(),
+                                )
+                            });
+                        let response = match range {
+                            None => Response::new(Body::from(contents)),
+                            Some((start, _)) if start >= contents.len() => {
+                                let mut response = Response::new(Body::empty());
+                                *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+                                response.headers_mut().insert(
+                                    header::CONTENT_RANGE,
+                                    format!("bytes */{}", contents.len()).parse().unwrap(),
+                                );
+                                response
+                            }
+                            Some((start, end)) => {
+                                let end = (end + 1).min(contents.len());
+                                let mut response = Response::new(Body::from(&contents[start..end]));
+                                *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+                                response.headers_mut().insert(
+                                    header::CONTENT_RANGE,
+                                    format!("bytes {start}-{}/{}", end - 1, contents.len())
+                                        .parse()
+                                        .unwrap(),
+                                );
+                                response
+                            }
+                        };
+                        Ok(response)
+                    }))
+                });
+                let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+                addr_tx.send(server.local_addr()).unwrap();
+                server.await.unwrap();
+            });
+        });
+        (addr_rx.recv().unwrap(), requests)
+    }
+
+    #[test]
+    fn test_http_fetcher__range_requests__then_partial_contents() {
+        let (addr, _) = serve(b"0123456789", true);
+        let fetcher = HttpFetcher::new().unwrap();
+        let url = format!("http://{addr}/object");
+
+        assert_eq!(fetcher.fetch(&url).unwrap(), b"0123456789");
+        assert_eq!(fetcher.fetch_range(&url, 2..5).unwrap(), b"234");
+        assert_eq!(fetcher.fetch_range(&url, 8..20).unwrap(), b"89");
+        assert!(fetcher.fetch_range(&url, 10..20).unwrap().is_empty());
+        assert_eq!(&*fetch_file(&fetcher, &url, 4).unwrap(), b"0123456789");
+        assert_eq!(
+            fetcher.fetch_range_sized(&url, 0..4).unwrap(),
+            (b"0123".to_vec(), Some(10))
+        );
+
+        let missing = fetcher
+            .fetch(&format!("http://{addr}/missing"))
+            .unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_http_fetcher__server_ignores_ranges__then_later_ranges_served_from_memory() {
+        let (addr, requests) = serve(b"0123456789", false);
+        let fetcher = HttpFetcher::new().unwrap();
+        let url = format!("http://{addr}/object");
+
+        assert_eq!(&*fetch_file(&fetcher, &url, 4).unwrap(), b"0123456789");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(fetcher.full_bodies.lock().is_empty());
+
+        assert_eq!(fetcher.fetch_range(&url, 2..5).unwrap(), b"234");
+        assert_eq!(fetcher.fetch_range(&url, 5..8).unwrap(), b"567");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,14 +1,58 @@
 // User Story: M1_E5_I2 - ATF V2 Session Reader
 // Tech Spec: M1_E5_I2_TECH_DESIGN.md - Cross-thread merge-sort iterator
 
-use super::error::Result;
+use super::error::{AtfV2Error, Result};
+use super::remote::{self, is_remote_url, Fetcher, HttpFetcher};
 use super::thread::ThreadReader;
 use super::types::IndexEvent;
 use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
-use std::fs;
-use std::path::Path;
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Advisory lock file the tracer holds exclusively while writing a session
+pub const SESSION_LOCK_FILE: &str = ".lock";
+
+/// Reads of a manifest that is empty or cut short before `open` gives up
+const MANIFEST_READ_ATTEMPTS: u32 = 5;
+const MANIFEST_RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// Byte order of raw multi-byte values captured by the tracer, such as
+/// stack copies. Manifests written before the field existed are little-endian.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// How the tracer handled events once a thread's ring buffers were full.
+/// Manifests written before the field existed used `DropOldest`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    #[default]
+    DropOldest,
+    DropNewest,
+    BlockProducer,
+}
+
+impl DropPolicy {
+    /// One-line account of what overflow did to the captured events
+    pub fn description(self) -> &'static str {
+        match self {
+            DropPolicy::DropOldest => "oldest undrained events were discarded to make room",
+            DropPolicy::DropNewest => "new events were discarded while buffers were full",
+            DropPolicy::BlockProducer => "traced threads waited for buffer space",
+        }
+    }
+}
 
 /// Manifest describing the session
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +62,125 @@ pub struct Manifest {
     pub time_start_ns: u64,
     #[serde(default)]
     pub time_end_ns: u64,
+    #[serde(default)]
+    pub endianness: Endianness,
+    #[serde(default)]
+    pub drop_policy: DropPolicy,
+    /// Wall-clock time (ns since the Unix epoch) when the capture started.
+    /// Absent in manifests written before the tracer recorded it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wall_clock_start_ns: Option<u64>,
+    /// Event clock reading taken together with `wall_clock_start_ns`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monotonic_start_ns: Option<u64>,
+    /// Wall-clock time when the capture stopped; absent for snapshots and
+    /// older manifests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wall_clock_end_ns: Option<u64>,
+    /// Event clock reading taken together with `wall_clock_end_ns`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monotonic_end_ns: Option<u64>,
+    /// Sub-sessions of traced child processes, in the order they started
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ChildSession>,
+    /// Set when calls were decimated on export: one call/return pair in
+    /// this many was kept, so call counts are scaled down by it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimation_factor: Option<u32>,
+    /// Set when the register values of function call and return records
+    /// were zeroed on export
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub registers_stripped: bool,
+}
+
+impl Manifest {
+    /// Parse `manifest.json` contents
+    ///
+    /// Rejects manifests whose time range ends before it starts, so
+    /// `duration_ns` never underflows.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let manifest: Manifest = serde_json::from_slice(bytes)?;
+        if manifest.time_end_ns < manifest.time_start_ns {
+            return Err(AtfV2Error::InvalidManifest(format!(
+                "time_end_ns {} is before time_start_ns {}",
+                manifest.time_end_ns, manifest.time_start_ns
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Session duration from the manifest time range
+    pub fn duration_ns(&self) -> u64 {
+        self.time_end_ns.saturating_sub(self.time_start_ns)
+    }
+
+    /// Mapping from the event clock to the wall clock, if the manifest
+    /// records the clock readings
+    pub fn clock_mapping(&self) -> Option<ClockMapping> {
+        let start = (self.monotonic_start_ns?, self.wall_clock_start_ns?);
+        let end = self.monotonic_end_ns.zip(self.wall_clock_end_ns);
+        Some(ClockMapping::new(start, end))
+    }
+
+    /// Wall-clock time of an event timestamp, in ns since the Unix epoch
+    ///
+    /// `None` when the manifest carries no wall-clock anchor.
+    pub fn monotonic_to_realtime(&self, timestamp_ns: u64) -> Option<u64> {
+        self.clock_mapping()?.to_realtime(timestamp_ns)
+    }
+
+    /// Wall-clock time of an event timestamp as an ISO-8601 UTC string
+    pub fn wall_clock_iso8601(&self, timestamp_ns: u64) -> Option<String> {
+        self.monotonic_to_realtime(timestamp_ns)
+            .map(format_wall_clock_ns)
+    }
+}
+
+/// Linear map from event clock (monotonic) readings to wall-clock ns
+///
+/// Built from `(monotonic_ns, realtime_ns)` pairs sampled at capture start
+/// and, when available, at capture end. Two pairs correct for the wall clock
+/// running faster or slower than the event clock over the capture; with only
+/// the start pair, or a zero or negative monotonic span between the pairs,
+/// timestamps are offset from the start pair alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockMapping {
+    start: (u64, u64),
+    end: Option<(u64, u64)>,
+}
+
+impl ClockMapping {
+    pub fn new(start: (u64, u64), end: Option<(u64, u64)>) -> Self {
+        let end = end.filter(|&(monotonic_end_ns, _)| monotonic_end_ns > start.0);
+        Self { start, end }
+    }
+
+    /// Wall-clock ns for a monotonic timestamp, or `None` if it falls outside
+    /// the representable range
+    pub fn to_realtime(&self, monotonic_ns: u64) -> Option<u64> {
+        let (monotonic_start_ns, realtime_start_ns) = self.start;
+        let offset = i128::from(monotonic_ns) - i128::from(monotonic_start_ns);
+        let scaled = match self.end {
+            Some((monotonic_end_ns, realtime_end_ns)) => {
+                let monotonic_span = i128::from(monotonic_end_ns) - i128::from(monotonic_start_ns);
+                let realtime_span = i128::from(realtime_end_ns) - i128::from(realtime_start_ns);
+                offset.checked_mul(realtime_span)? / monotonic_span
+            }
+            None => offset,
+        };
+        u64::try_from(i128::from(realtime_start_ns) + scaled).ok()
+    }
+}
+
+/// Format ns since the Unix epoch as ISO-8601 UTC with nanosecond precision,
+/// e.g. `2024-05-01T12:00:00.000000042Z`
+pub fn format_wall_clock_ns(wall_clock_ns: u64) -> String {
+    let secs = (wall_clock_ns / 1_000_000_000) as i64;
+    let nanos = (wall_clock_ns % 1_000_000_000) as u32;
+    match chrono::DateTime::from_timestamp(secs, nanos) {
+        Some(datetime) => datetime.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+        None => wall_clock_ns.to_string(),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,31 +190,143 @@ pub struct ThreadInfo {
     pub has_detail: bool,
 }
 
+/// A child process traced into its own session inside the parent's
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildSession {
+    pub pid: u32,
+    /// Session directory relative to the parent's, e.g. `children/pid_1234`
+    pub dir: String,
+}
+
+/// Where a session's files are read from
+enum SessionLocation {
+    Local(PathBuf),
+    Remote {
+        url: String,
+        fetcher: Arc<dyn Fetcher>,
+    },
+}
+
 /// Session reader with multi-thread support
 pub struct SessionReader {
+    location: SessionLocation,
     manifest: Manifest,
     threads: Vec<ThreadReader>,
+    /// Manifest thread ids whose stream could not be found
+    missing_threads: Vec<u32>,
 }
 
 impl SessionReader {
     /// Open session directory and load all thread readers
+    ///
+    /// Fails with `SessionInProgress` while the tracer holds the session
+    /// lock, since its files are still being appended to.
+    ///
+    /// The tracer publishes `manifest.json` atomically by renaming a fully
+    /// written temporary file into place. Writers that do not, or that
+    /// replace the file in place, can expose an empty or truncated manifest
+    /// for a moment, so such reads are retried briefly before failing.
     pub fn open(session_dir: &Path) -> Result<Self> {
-        // Read manifest.json
-        let manifest_path = session_dir.join("manifest.json");
-        let manifest_str = fs::read_to_string(&manifest_path)?;
-        let manifest: Manifest = serde_json::from_str(&manifest_str)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if is_being_written(session_dir)? {
+            return Err(AtfV2Error::SessionInProgress(session_dir.to_path_buf()));
+        }
+        Self::open_unlocked(session_dir)
+    }
+
+    /// Open a session without checking the session lock
+    ///
+    /// For readers that follow a capture while it is written and cope with
+    /// files that grow underneath them.
+    pub fn open_unlocked(session_dir: &Path) -> Result<Self> {
+        let manifest = read_manifest(&session_dir.join("manifest.json"))?;
 
         // Load thread readers
         let mut threads = Vec::new();
+        let mut missing_threads = Vec::new();
         for thread_info in &manifest.threads {
             let thread_dir = session_dir.join(format!("thread_{}", thread_info.id));
             if thread_dir.exists() {
                 threads.push(ThreadReader::open(&thread_dir)?);
+            } else {
+                missing_threads.push(thread_info.id);
+            }
+        }
+
+        Ok(SessionReader {
+            location: SessionLocation::Local(session_dir.to_path_buf()),
+            manifest,
+            threads,
+            missing_threads,
+        })
+    }
+
+    /// Open a session by URL
+    ///
+    /// `http://`, `https://` and `s3://` URLs are read with `HttpFetcher`;
+    /// anything else is a local path and goes through `open`.
+    pub fn open_url(url: &str) -> Result<Self> {
+        if !is_remote_url(url) {
+            return Self::open(Path::new(url));
+        }
+        let fetcher = HttpFetcher::new().map_err(|e| AtfV2Error::fetch(url, e))?;
+        Self::open_url_with(url, Arc::new(fetcher))
+    }
+
+    /// Open the session at `url` with `fetcher`
+    ///
+    /// Thread files are downloaded with range requests and read from
+    /// memory. The session lock cannot be checked remotely, so the session
+    /// must have been uploaded after the tracer finished writing it.
+    pub fn open_url_with(url: &str, fetcher: Arc<dyn Fetcher>) -> Result<Self> {
+        let manifest_url = remote::join_url(url, "manifest.json");
+        let bytes = fetcher
+            .fetch(&manifest_url)
+            .map_err(|e| AtfV2Error::fetch(manifest_url, e))?;
+        let manifest = Manifest::from_bytes(&bytes)?;
+
+        let mut threads = Vec::new();
+        let mut missing_threads = Vec::new();
+        for thread_info in &manifest.threads {
+            let thread_url = remote::join_url(url, &format!("thread_{}", thread_info.id));
+            match remote::open_thread(fetcher.as_ref(), &thread_url)? {
+                Some(thread) => threads.push(thread),
+                None => missing_threads.push(thread_info.id),
             }
         }
 
-        Ok(SessionReader { manifest, threads })
+        Ok(SessionReader {
+            location: SessionLocation::Remote {
+                url: url.to_string(),
+                fetcher,
+            },
+            manifest,
+            threads,
+            missing_threads,
+        })
+    }
+
+    /// Open the session of a child listed in this manifest
+    ///
+    /// Children form a tree: each child manifest lists its own children.
+    /// Directories that would leave this session are rejected.
+    pub fn open_child(&self, child: &ChildSession) -> Result<SessionReader> {
+        let relative = Path::new(&child.dir);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(AtfV2Error::InvalidManifest(format!(
+                "child {} directory {:?} is outside the session",
+                child.pid, child.dir
+            )));
+        }
+        match &self.location {
+            SessionLocation::Local(session_dir) => SessionReader::open(&session_dir.join(relative)),
+            SessionLocation::Remote { url, fetcher } => SessionReader::open_url_with(
+                &remote::join_url(url, &child.dir),
+                Arc::clone(fetcher),
+            ),
+        }
     }
 
     /// Get all thread readers
@@ -64,6 +339,20 @@ impl SessionReader {
         &self.manifest
     }
 
+    /// Ids of manifest threads skipped on open because their stream is
+    /// missing; `threads` holds the remaining manifest threads in order
+    pub fn missing_threads(&self) -> &[u32] {
+        &self.missing_threads
+    }
+
+    /// Session directory, or `None` for a session opened by URL
+    pub fn session_dir(&self) -> Option<&Path> {
+        match &self.location {
+            SessionLocation::Local(session_dir) => Some(session_dir),
+            SessionLocation::Remote { .. } => None,
+        }
+    }
+
     /// Time range across all threads
     pub fn time_range(&self) -> (u64, u64) {
         if self.threads.is_empty() {
@@ -89,7 +378,15 @@ impl SessionReader {
 
     /// Merge-sort iterator across all threads by timestamp_ns
     pub fn merged_iter(&self) -> MergedEventIter {
-        MergedEventIter::new(&self.threads)
+        MergedEventIter::new(&self.threads, None)
+    }
+
+    /// Merge-sort iterator over only the events of `event_kind`
+    ///
+    /// Other events are skipped within each thread before the merge, so the
+    /// heap only ever holds matching events.
+    pub fn merged_iter_of_kind(&self, event_kind: u32) -> MergedEventIter<'_> {
+        MergedEventIter::new(&self.threads, Some(event_kind))
     }
 }
 
@@ -97,20 +394,37 @@ impl SessionReader {
 pub struct MergedEventIter<'a> {
     heap: BinaryHeap<Reverse<(u64, usize, u32)>>, // (timestamp, thread_idx, seq)
     threads: &'a [ThreadReader],
+    event_kind: Option<u32>,
 }
 
 impl<'a> MergedEventIter<'a> {
-    fn new(threads: &'a [ThreadReader]) -> Self {
-        let mut heap = BinaryHeap::new();
+    fn new(threads: &'a [ThreadReader], event_kind: Option<u32>) -> Self {
+        let mut iter = Self {
+            heap: BinaryHeap::new(),
+            threads,
+            event_kind,
+        };
 
         // Seed heap with first event from each thread
-        for (idx, thread) in threads.iter().enumerate() {
-            if let Some(event) = thread.index.get(0) {
-                heap.push(Reverse((event.timestamp_ns, idx, 0)));
-            }
+        for idx in 0..threads.len() {
+            iter.push_from(idx, 0);
         }
 
-        Self { heap, threads }
+        iter
+    }
+
+    /// Queue the first event of `thread_idx` at or after `seq` that passes
+    /// the kind filter
+    fn push_from(&mut self, thread_idx: usize, mut seq: u32) {
+        let index = &self.threads[thread_idx].index;
+        while let Some(event) = index.get(seq) {
+            if self.event_kind.is_none_or(|kind| event.event_kind == kind) {
+                self.heap
+                    .push(Reverse((event.timestamp_ns, thread_idx, seq)));
+                return;
+            }
+            seq += 1;
+        }
     }
 }
 
@@ -124,18 +438,54 @@ impl<'a> Iterator for MergedEventIter<'a> {
         let event = self.threads[thread_idx].index.get(seq)?;
 
         // Push next event from same thread if available
-        if let Some(next_event) = self.threads[thread_idx].index.get(seq + 1) {
-            self.heap
-                .push(Reverse((next_event.timestamp_ns, thread_idx, seq + 1)));
-        }
+        self.push_from(thread_idx, seq + 1);
 
         Some((thread_idx, event))
     }
 }
 
+/// Whether a tracer currently holds the session lock in `session_dir`
+///
+/// Sessions without a lock file, such as those written before the lock
+/// existed, are never reported as in progress.
+pub fn is_being_written(session_dir: &Path) -> Result<bool> {
+    let lock_path = session_dir.join(SESSION_LOCK_FILE);
+    let file = match File::open(&lock_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(AtfV2Error::io(lock_path, e)),
+    };
+    match file.try_lock_shared() {
+        Ok(()) => {
+            let _ = file.unlock();
+            Ok(false)
+        }
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(e)) => Err(AtfV2Error::io(lock_path, e)),
+    }
+}
+
+/// Reads and parses `manifest.json`, retrying while it looks partially written
+pub(super) fn read_manifest(path: &Path) -> Result<Manifest> {
+    let mut attempt = 1;
+    loop {
+        let bytes = fs::read(path).map_err(|e| AtfV2Error::io(path, e))?;
+        match Manifest::from_bytes(&bytes) {
+            Err(AtfV2Error::ManifestJson(err))
+                if err.is_eof() && attempt < MANIFEST_READ_ATTEMPTS =>
+            {
+                attempt += 1;
+                thread::sleep(MANIFEST_RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::atf::v2::types::ATF_EVENT_KIND_RETURN;
     use std::fs;
     use std::io::Write;
     use tempfile::TempDir;
@@ -156,6 +506,15 @@ mod tests {
             threads: thread_infos,
             time_start_ns: 1000,
             time_end_ns: 1000 + events_per_thread as u64 * 100 * thread_count as u64,
+            endianness: Endianness::Little,
+            drop_policy: DropPolicy::DropOldest,
+            wall_clock_start_ns: None,
+            monotonic_start_ns: None,
+            wall_clock_end_ns: None,
+            monotonic_end_ns: None,
+            children: Vec::new(),
+            decimation_factor: None,
+            registers_stripped: false,
         };
 
         let manifest_str = serde_json::to_string_pretty(&manifest).unwrap();
@@ -296,6 +655,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_merge_sort_of_kind__multiple_threads__then_only_kind_in_order() {
+        // Even-numbered events are calls, odd-numbered ones returns
+        let dir = create_test_session(3, 100);
+        let session = SessionReader::open(dir.path()).unwrap();
+
+        let returns: Vec<_> = session
+            .merged_iter_of_kind(ATF_EVENT_KIND_RETURN)
+            .map(|(_, event)| (event.timestamp_ns, event.event_kind))
+            .collect();
+
+        assert_eq!(returns.len(), 150);
+        assert!(returns
+            .iter()
+            .all(|&(_, kind)| kind == ATF_EVENT_KIND_RETURN));
+        assert!(returns.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        assert_eq!(session.merged_iter_of_kind(3).count(), 0);
+    }
+
     #[test]
     fn test_merge_sort__empty_session__then_no_events() {
         // User Story: M1_E5_I2 - Handle empty session
@@ -306,6 +684,15 @@ mod tests {
             threads: vec![],
             time_start_ns: 0,
             time_end_ns: 0,
+            endianness: Endianness::Little,
+            drop_policy: DropPolicy::DropOldest,
+            wall_clock_start_ns: None,
+            monotonic_start_ns: None,
+            wall_clock_end_ns: None,
+            monotonic_end_ns: None,
+            children: Vec::new(),
+            decimation_factor: None,
+            registers_stripped: false,
         };
 
         let manifest_str = serde_json::to_string_pretty(&manifest).unwrap();
@@ -329,6 +716,15 @@ mod tests {
             threads: vec![],
             time_start_ns: 0,
             time_end_ns: 0,
+            endianness: Endianness::Little,
+            drop_policy: DropPolicy::DropOldest,
+            wall_clock_start_ns: None,
+            monotonic_start_ns: None,
+            wall_clock_end_ns: None,
+            monotonic_end_ns: None,
+            children: Vec::new(),
+            decimation_factor: None,
+            registers_stripped: false,
         };
 
         let manifest_str = serde_json::to_string_pretty(&manifest).unwrap();
@@ -368,6 +764,15 @@ mod tests {
             ],
             time_start_ns: 1000,
             time_end_ns: 2000,
+            endianness: Endianness::Little,
+            drop_policy: DropPolicy::DropOldest,
+            wall_clock_start_ns: None,
+            monotonic_start_ns: None,
+            wall_clock_end_ns: None,
+            monotonic_end_ns: None,
+            children: Vec::new(),
+            decimation_factor: None,
+            registers_stripped: false,
         };
 
         let manifest_str = serde_json::to_string_pretty(&manifest).unwrap();
@@ -441,4 +846,272 @@ mod tests {
         assert_eq!(session.threads().len(), 1);
         assert_eq!(session.threads()[0].thread_id(), 0);
     }
+
+    #[test]
+    fn test_manifest__endianness_field__then_parsed_with_little_default() {
+        let legacy: Manifest = serde_json::from_str(r#"{"threads": []}"#).unwrap();
+        assert_eq!(legacy.endianness, Endianness::Little);
+
+        let big: Manifest =
+            serde_json::from_str(r#"{"threads": [], "endianness": "big"}"#).unwrap();
+        assert_eq!(big.endianness, Endianness::Big);
+    }
+
+    #[test]
+    fn test_manifest__drop_policy_field__then_parsed_with_drop_oldest_default() {
+        let legacy: Manifest = serde_json::from_str(r#"{"threads": []}"#).unwrap();
+        assert_eq!(legacy.drop_policy, DropPolicy::DropOldest);
+
+        let blocking: Manifest =
+            serde_json::from_str(r#"{"threads": [], "drop_policy": "block_producer"}"#).unwrap();
+        assert_eq!(blocking.drop_policy, DropPolicy::BlockProducer);
+    }
+
+    #[test]
+    fn test_manifest__wall_clock_anchor__then_event_timestamps_resolve() {
+        let legacy: Manifest = serde_json::from_str(r#"{"threads": []}"#).unwrap();
+        assert_eq!(legacy.monotonic_to_realtime(5_000), None);
+        assert_eq!(legacy.wall_clock_iso8601(5_000), None);
+
+        let anchored: Manifest = serde_json::from_str(
+            r#"{"threads": [], "wall_clock_start_ns": 1714564800000000000,
+                "monotonic_start_ns": 5000}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            anchored.monotonic_to_realtime(5_042),
+            Some(1_714_564_800_000_000_042)
+        );
+        assert_eq!(
+            anchored.wall_clock_iso8601(5_042).as_deref(),
+            Some("2024-05-01T12:00:00.000000042Z")
+        );
+        // Events recorded before the anchor still resolve
+        assert_eq!(
+            anchored.monotonic_to_realtime(4_000),
+            Some(1_714_564_799_999_999_000)
+        );
+    }
+
+    #[test]
+    fn test_manifest__start_and_end_readings__then_mapped_linearly() {
+        // The wall clock advanced 2_000 ns while the event clock advanced 1_000
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"threads": [],
+                "monotonic_start_ns": 1000, "wall_clock_start_ns": 50000,
+                "monotonic_end_ns": 2000, "wall_clock_end_ns": 52000}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.monotonic_to_realtime(1_000), Some(50_000));
+        assert_eq!(manifest.monotonic_to_realtime(1_500), Some(51_000));
+        assert_eq!(manifest.monotonic_to_realtime(2_000), Some(52_000));
+        assert_eq!(manifest.monotonic_to_realtime(500), Some(49_000));
+    }
+
+    #[test]
+    fn test_clock_mapping__zero_span__then_offset_from_start() {
+        let mapping = ClockMapping::new((1_000, 50_000), Some((1_000, 90_000)));
+        assert_eq!(mapping.to_realtime(1_250), Some(50_250));
+
+        let backwards = ClockMapping::new((1_000, 50_000), Some((900, 49_900)));
+        assert_eq!(backwards.to_realtime(1_250), Some(50_250));
+
+        let before_epoch = ClockMapping::new((1_000, 100), None);
+        assert_eq!(before_epoch.to_realtime(0), None);
+    }
+
+    #[test]
+    fn test_session_reader__open_child__then_child_session_opened() {
+        let parent = TempDir::new().unwrap();
+        let child_dir = parent.path().join("children/pid_42");
+        fs::create_dir_all(&child_dir).unwrap();
+        fs::write(child_dir.join("manifest.json"), r#"{"threads": []}"#).unwrap();
+        fs::write(
+            parent.path().join("manifest.json"),
+            r#"{"threads": [], "children": [{"pid": 42, "dir": "children/pid_42"}]}"#,
+        )
+        .unwrap();
+
+        let reader = SessionReader::open(parent.path()).unwrap();
+        let children = &reader.manifest().children;
+        assert_eq!(
+            children,
+            &vec![ChildSession {
+                pid: 42,
+                dir: "children/pid_42".into()
+            }]
+        );
+        let child = reader.open_child(&children[0]).unwrap();
+        assert!(child.manifest().children.is_empty());
+
+        let escaping = ChildSession {
+            pid: 7,
+            dir: "../elsewhere".into(),
+        };
+        assert!(matches!(
+            reader.open_child(&escaping),
+            Err(AtfV2Error::InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn test_manifest_from_bytes__valid__then_duration_from_range() {
+        let manifest = Manifest::from_bytes(
+            br#"{"threads": [{"id": 0}], "time_start_ns": 1000, "time_end_ns": 6000}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.threads.len(), 1);
+        assert_eq!(manifest.duration_ns(), 5000);
+    }
+
+    #[test]
+    fn test_manifest_from_bytes__end_before_start__then_error() {
+        let result = Manifest::from_bytes(
+            br#"{"threads": [], "time_start_ns": 6000, "time_end_ns": 1000}"#,
+        );
+        assert!(matches!(result, Err(AtfV2Error::InvalidManifest(_))));
+    }
+
+    #[test]
+    fn test_manifest_from_bytes__not_json__then_error() {
+        assert!(matches!(
+            Manifest::from_bytes(b"\xff{threads"),
+            Err(AtfV2Error::ManifestJson(_))
+        ));
+        assert!(matches!(
+            Manifest::from_bytes(br#"{"time_start_ns": 0}"#),
+            Err(AtfV2Error::ManifestJson(_))
+        ));
+    }
+
+    #[test]
+    fn test_session_reader__manifest_missing__then_io_error_chains_source() {
+        use std::error::Error;
+
+        let temp_dir = TempDir::new().unwrap();
+        let err = SessionReader::open(temp_dir.path()).err().unwrap();
+
+        let manifest_path = temp_dir.path().join("manifest.json");
+        assert!(err.to_string().contains(manifest_path.to_str().unwrap()));
+        let source = err.source().unwrap().downcast_ref::<std::io::Error>();
+        assert_eq!(source.unwrap().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_manifest_from_bytes__not_json__then_error_chains_serde_source() {
+        use std::error::Error;
+
+        let err = Manifest::from_bytes(b"{").unwrap_err();
+        assert!(err.to_string().starts_with("Malformed manifest: "));
+        assert!(err.source().unwrap().is::<serde_json::Error>());
+    }
+
+    #[test]
+    fn test_session_reader__manifest_completed_during_open__then_retries() {
+        let temp_dir = TempDir::new().unwrap();
+        let manifest_path = temp_dir.path().join("manifest.json");
+        fs::write(&manifest_path, br#"{"threads": ["#).unwrap();
+
+        let writer = {
+            let manifest_path = manifest_path.clone();
+            thread::spawn(move || {
+                thread::sleep(MANIFEST_RETRY_DELAY);
+                let tmp_path = manifest_path.with_extension("json.tmp");
+                fs::write(&tmp_path, br#"{"threads": [], "time_end_ns": 10}"#).unwrap();
+                fs::rename(&tmp_path, &manifest_path).unwrap();
+            })
+        };
+
+        let reader = SessionReader::open(temp_dir.path()).unwrap();
+        writer.join().unwrap();
+        assert_eq!(reader.manifest().time_end_ns, 10);
+    }
+
+    #[test]
+    fn test_session_reader__manifest_stays_truncated__then_error() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("manifest.json"), b"").unwrap();
+
+        let result = SessionReader::open(temp_dir.path());
+        assert!(matches!(result, Err(AtfV2Error::ManifestJson(_))));
+    }
+
+    #[test]
+    fn test_session_reader__session_locked__then_in_progress_until_released() {
+        let temp_dir = create_test_session(1, 2);
+        let writer_lock = File::create(temp_dir.path().join(SESSION_LOCK_FILE)).unwrap();
+        writer_lock.lock().unwrap();
+
+        assert!(is_being_written(temp_dir.path()).unwrap());
+        let result = SessionReader::open(temp_dir.path());
+        assert!(matches!(result, Err(AtfV2Error::SessionInProgress(_))));
+        assert!(SessionReader::open_unlocked(temp_dir.path()).is_ok());
+
+        writer_lock.unlock().unwrap();
+        assert!(!is_being_written(temp_dir.path()).unwrap());
+        assert!(SessionReader::open(temp_dir.path()).is_ok());
+    }
+
+    /// Serves the files under `root` as the objects below `s3://bucket/`
+    struct DirFetcher {
+        root: PathBuf,
+    }
+
+    impl Fetcher for DirFetcher {
+        fn fetch(&self, url: &str) -> io::Result<Vec<u8>> {
+            fs::read(self.root.join(url.strip_prefix("s3://bucket/").unwrap()))
+        }
+
+        fn fetch_range(&self, url: &str, range: std::ops::Range<u64>) -> io::Result<Vec<u8>> {
+            let object = self.fetch(url)?;
+            let clamp = |offset: u64| (offset as usize).min(object.len());
+            Ok(object[clamp(range.start)..clamp(range.end)].to_vec())
+        }
+    }
+
+    #[test]
+    fn test_session_reader__open_url_with__then_matches_local_session() {
+        let temp_dir = create_test_session(3, 10);
+        fs::remove_dir_all(temp_dir.path().join("thread_2")).unwrap();
+        let local = SessionReader::open(temp_dir.path()).unwrap();
+        let fetcher = Arc::new(DirFetcher {
+            root: temp_dir.path().to_path_buf(),
+        });
+
+        let remote = SessionReader::open_url_with("s3://bucket/", fetcher).unwrap();
+
+        assert_eq!(remote.threads().len(), 2);
+        assert_eq!(remote.time_range(), local.time_range());
+        let events = |reader: &SessionReader| -> Vec<(usize, u64, u64)> {
+            reader
+                .merged_iter()
+                .map(|(thread, event)| (thread, event.timestamp_ns, event.function_id))
+                .collect()
+        };
+        assert_eq!(events(&remote), events(&local));
+    }
+
+    #[test]
+    fn test_session_reader__open_url_local_path__then_opened_from_disk() {
+        let temp_dir = create_test_session(1, 4);
+
+        let reader = SessionReader::open_url(temp_dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(reader.event_count(), 4);
+    }
+
+    #[test]
+    fn test_session_reader__open_url_manifest_missing__then_fetch_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let fetcher = Arc::new(DirFetcher {
+            root: temp_dir.path().to_path_buf(),
+        });
+
+        let result = SessionReader::open_url_with("s3://bucket", fetcher);
+
+        assert!(matches!(
+            result,
+            Err(AtfV2Error::Fetch { url, .. }) if url == "s3://bucket/manifest.json"
+        ));
+    }
 }
@@ -158,6 +158,16 @@ impl<'a> DetailEvent<'a> {
 
         Some(DetailEvent { header, payload })
     }
+
+    /// Stack bytes captured by a function call or return record, cut to
+    /// the bytes present; `None` when the payload is shorter than its fixed
+    /// part
+    pub fn stack_snapshot(&self) -> Option<&'a [u8]> {
+        let fixed = self.payload.get(..ATF_DETAIL_FUNCTION_PAYLOAD_SIZE)?;
+        let stack_size = u16::from_le_bytes([fixed[96], fixed[97]]) as usize;
+        let stack = &self.payload[ATF_DETAIL_FUNCTION_PAYLOAD_SIZE..];
+        Some(&stack[..stack_size.min(stack.len())])
+    }
 }
 
 impl<'a> fmt::Debug for DetailEvent<'a> {
@@ -182,6 +192,11 @@ pub const ATF_EVENT_KIND_EXCEPTION: u32 = 3;
 pub const ATF_DETAIL_EVENT_FUNCTION_CALL: u16 = 3;
 pub const ATF_DETAIL_EVENT_FUNCTION_RETURN: u16 = 4;
 
+// Function call/return payload: function_id, x0-x7, lr, fp, sp, then a u16
+// stack_size and u16 padding ahead of the stack snapshot
+pub const ATF_DETAIL_FUNCTION_PAYLOAD_SIZE: usize = 100;
+pub const ATF_DETAIL_FUNCTION_REGISTER_BYTES: usize = 88;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,6 +246,23 @@ mod tests {
         assert_eq!(event.payload().len(), 76); // 100 - 24
     }
 
+    #[test]
+    fn test_detail_event__stack_snapshot__then_cut_to_present_bytes() {
+        let mut data = vec![0u8; 24 + ATF_DETAIL_FUNCTION_PAYLOAD_SIZE + 8];
+        let total_length = data.len() as u32;
+        data[0..4].copy_from_slice(&total_length.to_le_bytes());
+        data[24 + 96..24 + 98].copy_from_slice(&16u16.to_le_bytes());
+        data[24 + ATF_DETAIL_FUNCTION_PAYLOAD_SIZE..].fill(0xAB);
+
+        let event = DetailEvent::from_bytes(&data).unwrap();
+        assert_eq!(event.stack_snapshot(), Some(&[0xAB; 8][..]));
+
+        let mut short = vec![0u8; 24 + 50];
+        short[0..4].copy_from_slice(&74u32.to_le_bytes());
+        let event = DetailEvent::from_bytes(&short).unwrap();
+        assert_eq!(event.stack_snapshot(), None);
+    }
+
     #[test]
     fn test_detail_event__too_short__then_none() {
         // User Story: M1_E5_I2 - Handle truncated data
@@ -0,0 +1,841 @@
+// ATF V2 session consistency checks
+//
+// Cross-checks the manifest thread list against the per-thread streams, and
+// each index against its detail file, so that corrupt or partially written
+// sessions are reported rather than silently read short. Quick checks do the
+// same from the file headers, footers and sizes alone.
+
+use super::detail::DetailReader;
+use super::error::{AtfV2Error, Result};
+use super::index::IndexReader;
+use super::session::{is_being_written, read_manifest, SessionReader};
+use super::thread::ThreadReader;
+use super::types::{
+    AtfDetailFooter, AtfDetailHeader, AtfIndexFooter, AtfIndexHeader, IndexEvent,
+    ATF_INDEX_FLAG_HAS_DETAIL_FILE, ATF_NO_DETAIL_SEQ,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Events a quick check reads from each end of every index
+pub const QUICK_CHECK_SAMPLE_SIZE: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VerifyIssueKind {
+    /// The manifest lists a thread whose stream does not exist
+    MissingThread,
+    /// A thread directory exists that the manifest does not list
+    UnlistedThread,
+    /// The index header names a different thread than the manifest
+    ThreadIdMismatch,
+    /// The manifest, the index flags and the detail file disagree on
+    /// whether the thread has details
+    DetailPresenceMismatch,
+    /// The index has no valid footer, as when the writer stopped early
+    IndexFooterMissing,
+    /// The index footer counts more events than the file holds
+    IndexCountMismatch,
+    /// The detail footer count differs from the records in the file
+    DetailCountMismatch,
+    /// Index events link to detail records that do not exist
+    DanglingDetailLink,
+    /// Detail records link back to a different index event
+    BrokenBackLink,
+    /// The detail file is shorter or longer than its footer says, or has
+    /// no valid footer; reported by quick checks
+    DetailSizeMismatch,
+    /// An index covers time outside the manifest's time range; reported by
+    /// quick checks
+    TimeRangeMismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyIssue {
+    pub kind: VerifyIssueKind,
+    pub thread_id: u32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyReport {
+    pub threads_checked: usize,
+    pub events_checked: u64,
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    /// Whether every check passed
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(&mut self, kind: VerifyIssueKind, thread_id: u32, message: String) {
+        self.issues.push(VerifyIssue {
+            kind,
+            thread_id,
+            message,
+        });
+    }
+}
+
+impl SessionReader {
+    /// Cross-check the manifest, index and detail files of the session
+    ///
+    /// Every index event's detail link is followed, so this reads the whole
+    /// session. Link failures are reported once per thread with a count
+    /// rather than once per event. Unlisted thread directories are only
+    /// found for sessions opened from a local directory.
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+        let manifest = self.manifest();
+
+        for &thread_id in self.missing_threads() {
+            report.push(
+                VerifyIssueKind::MissingThread,
+                thread_id,
+                format!("manifest lists thread {thread_id} but thread_{thread_id} is missing"),
+            );
+        }
+
+        let listed = manifest
+            .threads
+            .iter()
+            .filter(|info| !self.missing_threads().contains(&info.id));
+        for (info, thread) in listed.zip(self.threads()) {
+            verify_thread(&mut report, info.id, info.has_detail, thread);
+        }
+
+        if let Some(session_dir) = self.session_dir() {
+            let listed: BTreeSet<u32> = manifest.threads.iter().map(|info| info.id).collect();
+            for thread_id in unlisted_threads(session_dir, &listed)? {
+                report.push(
+                    VerifyIssueKind::UnlistedThread,
+                    thread_id,
+                    format!("thread_{thread_id} is not listed in the manifest"),
+                );
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Check a local session from its manifest and the headers, footers and
+    /// sizes of its files, without opening it
+    ///
+    /// Reads a fixed number of bytes per thread whatever the trace size, so
+    /// it catches truncated or mismatched files but not broken detail links;
+    /// use [`SessionReader::verify`] for those. Fails like
+    /// [`SessionReader::open`] on a locked session, a bad manifest, or a file
+    /// header that is not ATF V2.
+    pub fn quick_check(session_dir: &Path) -> Result<QuickCheckReport> {
+        if is_being_written(session_dir)? {
+            return Err(AtfV2Error::SessionInProgress(session_dir.to_path_buf()));
+        }
+        let manifest = read_manifest(&session_dir.join("manifest.json"))?;
+        let mut report = QuickCheckReport::default();
+
+        for info in &manifest.threads {
+            let thread_dir = session_dir.join(format!("thread_{}", info.id));
+            let index_path = thread_dir.join("index.atf");
+            if !index_path.is_file() {
+                report.push(
+                    VerifyIssueKind::MissingThread,
+                    info.id,
+                    format!(
+                        "manifest lists thread {} but thread_{}/index.atf is missing",
+                        info.id, info.id
+                    ),
+                );
+                continue;
+            }
+
+            let thread = quick_check_index(&mut report, info.id, &index_path)?;
+            let flagged_detail = thread.flags & ATF_INDEX_FLAG_HAS_DETAIL_FILE != 0;
+            let detail_path = thread_dir.join("detail.atf");
+            let present_detail = detail_path.is_file();
+            if info.has_detail != present_detail || flagged_detail != present_detail {
+                report.push(
+                    VerifyIssueKind::DetailPresenceMismatch,
+                    info.id,
+                    format!(
+                        "manifest has_detail={}, index flag={flagged_detail}, \
+                         detail file present={present_detail}",
+                        info.has_detail
+                    ),
+                );
+            }
+            if present_detail {
+                quick_check_detail(&mut report, info.id, &detail_path)?;
+            }
+
+            let covered = (manifest.time_start_ns..=manifest.time_end_ns)
+                .contains(&thread.first_ns)
+                && (manifest.time_start_ns..=manifest.time_end_ns).contains(&thread.last_ns);
+            if thread.event_count > 0 && manifest.time_end_ns > 0 && !covered {
+                report.push(
+                    VerifyIssueKind::TimeRangeMismatch,
+                    info.id,
+                    format!(
+                        "index events span {}..={} ns outside the manifest range {}..={} ns",
+                        thread.first_ns,
+                        thread.last_ns,
+                        manifest.time_start_ns,
+                        manifest.time_end_ns
+                    ),
+                );
+            }
+
+            report.threads_checked += 1;
+            report.event_count += thread.event_count;
+            report.threads.push(thread.sample);
+        }
+
+        let listed: BTreeSet<u32> = manifest.threads.iter().map(|info| info.id).collect();
+        for thread_id in unlisted_threads(session_dir, &listed)? {
+            report.push(
+                VerifyIssueKind::UnlistedThread,
+                thread_id,
+                format!("thread_{thread_id} is not listed in the manifest"),
+            );
+        }
+
+        Ok(report)
+    }
+}
+
+/// The events a quick check read from either end of a thread's index
+#[derive(Debug, Clone)]
+pub struct QuickCheckThread {
+    pub thread_id: u32,
+    /// Events the index holds, as far as the file size allows
+    pub event_count: u64,
+    /// Up to [`QUICK_CHECK_SAMPLE_SIZE`] events from the start of the index
+    pub first_events: Vec<IndexEvent>,
+    /// Up to [`QUICK_CHECK_SAMPLE_SIZE`] events from the end of the index,
+    /// overlapping `first_events` in short indexes
+    pub last_events: Vec<IndexEvent>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QuickCheckReport {
+    pub threads_checked: usize,
+    /// Events the checked indexes hold
+    pub event_count: u64,
+    pub threads: Vec<QuickCheckThread>,
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl QuickCheckReport {
+    /// Whether every check passed
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(&mut self, kind: VerifyIssueKind, thread_id: u32, message: String) {
+        self.issues.push(VerifyIssue {
+            kind,
+            thread_id,
+            message,
+        });
+    }
+}
+
+/// What a quick check learned from one index file
+struct CheckedIndex {
+    flags: u32,
+    event_count: u64,
+    first_ns: u64,
+    last_ns: u64,
+    sample: QuickCheckThread,
+}
+
+/// Thread ids of the `thread_<id>` directories in `session_dir` that are not
+/// in `listed`
+fn unlisted_threads(session_dir: &Path, listed: &BTreeSet<u32>) -> Result<BTreeSet<u32>> {
+    let entries = fs::read_dir(session_dir).map_err(|e| AtfV2Error::io(session_dir, e))?;
+    let mut unlisted = BTreeSet::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| AtfV2Error::io(session_dir, e))?;
+        let name = entry.file_name();
+        let thread_id = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("thread_"))
+            .and_then(|id| id.parse::<u32>().ok());
+        if let Some(thread_id) = thread_id.filter(|id| !listed.contains(id)) {
+            unlisted.insert(thread_id);
+        }
+    }
+    Ok(unlisted)
+}
+
+/// Read the `T` stored at `offset`, or `None` when the file ends before it
+///
+/// `T` must be one of the packed on-disk structs, valid for any bytes.
+fn read_struct_at<T: Copy>(file: &mut File, path: &Path, offset: u64) -> Result<Option<T>> {
+    let mut bytes = vec![0u8; std::mem::size_of::<T>()];
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| AtfV2Error::io(path, e))?;
+    match file.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(unsafe {
+            std::ptr::read_unaligned(bytes.as_ptr() as *const T)
+        })),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(AtfV2Error::io(path, e)),
+    }
+}
+
+fn quick_check_index(
+    report: &mut QuickCheckReport,
+    thread_id: u32,
+    path: &Path,
+) -> Result<CheckedIndex> {
+    let mut file = File::open(path).map_err(|e| AtfV2Error::io(path, e))?;
+    let file_len = file.metadata().map_err(|e| AtfV2Error::io(path, e))?.len();
+    let header: AtfIndexHeader =
+        read_struct_at(&mut file, path, 0)?.ok_or(AtfV2Error::FileTooSmall {
+            expected: 64,
+            actual: file_len as usize,
+        })?;
+    IndexReader::validate_header(&header)?;
+    let events_offset = header.events_offset;
+    if events_offset > file_len {
+        return Err(AtfV2Error::InvalidOffset {
+            offset: events_offset as usize,
+            file_size: file_len as usize,
+        });
+    }
+
+    let header_thread_id = header.thread_id;
+    if header_thread_id != thread_id {
+        report.push(
+            VerifyIssueKind::ThreadIdMismatch,
+            thread_id,
+            format!("index header names thread {header_thread_id}"),
+        );
+    }
+
+    let footer_offset = header.footer_offset;
+    let footer = read_struct_at::<AtfIndexFooter>(&mut file, path, footer_offset)?
+        .filter(|footer| &footer.magic == b"2ITA" && footer_offset >= events_offset);
+    let events_end = footer.map_or(file_len, |_| footer_offset);
+    let held = (events_end - events_offset) / 32;
+    let event_count = match footer {
+        None => {
+            report.push(
+                VerifyIssueKind::IndexFooterMissing,
+                thread_id,
+                "index footer is missing or invalid".to_string(),
+            );
+            held
+        }
+        Some(footer) => {
+            let declared = footer.event_count;
+            let bytes_written = footer.bytes_written;
+            if declared != held {
+                report.push(
+                    VerifyIssueKind::IndexCountMismatch,
+                    thread_id,
+                    format!("index footer counts {declared} events but the file holds {held}"),
+                );
+            } else if events_offset + bytes_written + 64 != file_len {
+                report.push(
+                    VerifyIssueKind::IndexCountMismatch,
+                    thread_id,
+                    format!(
+                        "index footer records {bytes_written} bytes of events but the file \
+                         is {file_len} bytes"
+                    ),
+                );
+            }
+            declared.min(held)
+        }
+    };
+
+    let mut read_events = |seqs: std::ops::Range<u64>| -> Result<Vec<IndexEvent>> {
+        let mut events = Vec::new();
+        for seq in seqs {
+            if let Some(event) = read_struct_at(&mut file, path, events_offset + seq * 32)? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    };
+    let first_events = read_events(0..event_count.min(QUICK_CHECK_SAMPLE_SIZE))?;
+    let last_events =
+        read_events(event_count.saturating_sub(QUICK_CHECK_SAMPLE_SIZE)..event_count)?;
+
+    Ok(CheckedIndex {
+        flags: header.flags,
+        event_count,
+        first_ns: first_events.first().map_or(0, |event| event.timestamp_ns),
+        last_ns: last_events.last().map_or(0, |event| event.timestamp_ns),
+        sample: QuickCheckThread {
+            thread_id,
+            event_count,
+            first_events,
+            last_events,
+        },
+    })
+}
+
+fn quick_check_detail(report: &mut QuickCheckReport, thread_id: u32, path: &Path) -> Result<()> {
+    let mut file = File::open(path).map_err(|e| AtfV2Error::io(path, e))?;
+    let file_len = file.metadata().map_err(|e| AtfV2Error::io(path, e))?.len();
+    let header: AtfDetailHeader =
+        read_struct_at(&mut file, path, 0)?.ok_or(AtfV2Error::FileTooSmall {
+            expected: 64,
+            actual: file_len as usize,
+        })?;
+    DetailReader::validate_header(&header)?;
+
+    let footer = match file_len.checked_sub(64).filter(|&offset| offset >= 64) {
+        Some(offset) => read_struct_at::<AtfDetailFooter>(&mut file, path, offset)?
+            .filter(|footer| &footer.magic == b"2DTA"),
+        None => None,
+    };
+    let Some(footer) = footer else {
+        report.push(
+            VerifyIssueKind::DetailSizeMismatch,
+            thread_id,
+            "detail footer is missing or invalid".to_string(),
+        );
+        return Ok(());
+    };
+
+    let expected_len = header.events_offset.saturating_add(footer.bytes_length) + 64;
+    if expected_len != file_len {
+        let bytes_length = footer.bytes_length;
+        report.push(
+            VerifyIssueKind::DetailSizeMismatch,
+            thread_id,
+            format!(
+                "detail footer records {bytes_length} bytes of events but the file is \
+                 {file_len} bytes"
+            ),
+        );
+    }
+    Ok(())
+}
+
+fn verify_thread(
+    report: &mut VerifyReport,
+    thread_id: u32,
+    listed_detail: bool,
+    thread: &ThreadReader,
+) {
+    report.threads_checked += 1;
+    report.events_checked += thread.index.len() as u64;
+
+    let header_thread_id = thread.thread_id();
+    if header_thread_id != thread_id {
+        report.push(
+            VerifyIssueKind::ThreadIdMismatch,
+            thread_id,
+            format!("index header names thread {header_thread_id}"),
+        );
+    }
+
+    let flagged_detail = thread.index.has_detail();
+    let present_detail = thread.detail.is_some();
+    if listed_detail != present_detail || flagged_detail != present_detail {
+        report.push(
+            VerifyIssueKind::DetailPresenceMismatch,
+            thread_id,
+            format!(
+                "manifest has_detail={listed_detail}, index flag={flagged_detail}, \
+                 detail file present={present_detail}"
+            ),
+        );
+    }
+
+    match thread.index.footer_event_count() {
+        None => report.push(
+            VerifyIssueKind::IndexFooterMissing,
+            thread_id,
+            "index footer is missing or invalid".to_string(),
+        ),
+        Some(declared) if declared != thread.index.len() as u64 => report.push(
+            VerifyIssueKind::IndexCountMismatch,
+            thread_id,
+            format!(
+                "index footer counts {declared} events but the file holds {}",
+                thread.index.len()
+            ),
+        ),
+        Some(_) => {}
+    }
+
+    if let Some(detail) = &thread.detail {
+        if let Some(declared) = detail.footer_event_count() {
+            if declared != detail.len() as u64 {
+                report.push(
+                    VerifyIssueKind::DetailCountMismatch,
+                    thread_id,
+                    format!(
+                        "detail footer counts {declared} events but the file holds {}",
+                        detail.len()
+                    ),
+                );
+            }
+        }
+    }
+
+    let (mut dangling, mut first_dangling) = (0u64, None);
+    let (mut broken, mut first_broken) = (0u64, None);
+    for (index_seq, event) in thread.index.iter().enumerate() {
+        let index_seq = index_seq as u32;
+        let detail_seq = event.detail_seq;
+        if detail_seq == ATF_NO_DETAIL_SEQ {
+            continue;
+        }
+        match thread.get_detail_for(event) {
+            None => {
+                dangling += 1;
+                first_dangling.get_or_insert(index_seq);
+            }
+            Some(detail) if detail.header().index_seq != index_seq => {
+                broken += 1;
+                first_broken.get_or_insert(index_seq);
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(first) = first_dangling {
+        report.push(
+            VerifyIssueKind::DanglingDetailLink,
+            thread_id,
+            format!(
+                "{dangling} index events link to missing detail records, first at index {first}"
+            ),
+        );
+    }
+    if let Some(first) = first_broken {
+        report.push(
+            VerifyIssueKind::BrokenBackLink,
+            thread_id,
+            format!(
+                "{broken} detail records link back to the wrong index event, first at index {first}"
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::session::{DropPolicy, Endianness, Manifest, ThreadInfo};
+    use super::super::types::{
+        AtfDetailFooter, AtfDetailHeader, AtfIndexFooter, AtfIndexHeader, IndexEvent,
+    };
+    use super::*;
+    use std::io::Write;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn as_bytes<T>(value: &T) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>())
+        }
+    }
+
+    fn write_manifest(dir: &Path, threads: Vec<ThreadInfo>) {
+        let manifest = Manifest {
+            threads,
+            time_start_ns: 1000,
+            time_end_ns: 2000,
+            endianness: Endianness::Little,
+            drop_policy: DropPolicy::DropOldest,
+            wall_clock_start_ns: None,
+            monotonic_start_ns: None,
+            wall_clock_end_ns: None,
+            monotonic_end_ns: None,
+            children: Vec::new(),
+            decimation_factor: None,
+            registers_stripped: false,
+        };
+        fs::write(
+            dir.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .unwrap();
+    }
+
+    /// Write thread_<id> with one index event per entry of `detail_seqs` and,
+    /// when `back_links` is given, one detail record per entry linking back
+    /// to that index sequence.
+    fn write_thread(
+        dir: &Path,
+        id: u32,
+        footer_event_count: u64,
+        detail_seqs: &[u32],
+        back_links: Option<&[u32]>,
+    ) {
+        let thread_dir = dir.join(format!("thread_{id}"));
+        fs::create_dir(&thread_dir).unwrap();
+
+        let event_count = detail_seqs.len() as u32;
+        let mut index = fs::File::create(thread_dir.join("index.atf")).unwrap();
+        let header = AtfIndexHeader {
+            magic: *b"ATI2",
+            endian: 0x01,
+            version: 1,
+            arch: 1,
+            os: 3,
+            flags: back_links.is_some() as u32,
+            thread_id: id,
+            clock_type: 1,
+            _reserved1: [0; 3],
+            _reserved2: 0,
+            event_size: 32,
+            event_count,
+            events_offset: 64,
+            footer_offset: 64 + event_count as u64 * 32,
+            time_start_ns: 1000,
+            time_end_ns: 1000 + event_count as u64 * 100,
+        };
+        index.write_all(as_bytes(&header)).unwrap();
+        for (i, &detail_seq) in detail_seqs.iter().enumerate() {
+            let event = IndexEvent {
+                timestamp_ns: 1000 + i as u64 * 100,
+                function_id: 0x100000001,
+                thread_id: id,
+                event_kind: 1,
+                call_depth: 0,
+                detail_seq,
+            };
+            index.write_all(as_bytes(&event)).unwrap();
+        }
+        let footer = AtfIndexFooter {
+            magic: *b"2ITA",
+            checksum: 0,
+            event_count: footer_event_count,
+            time_start_ns: 1000,
+            time_end_ns: 1000 + event_count as u64 * 100,
+            bytes_written: event_count as u64 * 32,
+            reserved: [0; 24],
+        };
+        index.write_all(as_bytes(&footer)).unwrap();
+
+        let Some(back_links) = back_links else {
+            return;
+        };
+        let mut detail = fs::File::create(thread_dir.join("detail.atf")).unwrap();
+        let bytes_length = back_links.len() as u64 * 24;
+        let header = AtfDetailHeader {
+            magic: *b"ATD2",
+            endian: 0x01,
+            version: 1,
+            arch: 1,
+            os: 3,
+            flags: 0,
+            thread_id: id,
+            _reserved1: 0,
+            events_offset: 64,
+            event_count: back_links.len() as u64,
+            bytes_length,
+            index_seq_start: 0,
+            index_seq_end: event_count as u64,
+            _reserved2: [0; 4],
+        };
+        detail.write_all(as_bytes(&header)).unwrap();
+        for &index_seq in back_links {
+            detail.write_all(&24u32.to_le_bytes()).unwrap();
+            detail.write_all(&3u16.to_le_bytes()).unwrap();
+            detail.write_all(&0u16.to_le_bytes()).unwrap();
+            detail.write_all(&index_seq.to_le_bytes()).unwrap();
+            detail.write_all(&id.to_le_bytes()).unwrap();
+            detail.write_all(&1000u64.to_le_bytes()).unwrap();
+        }
+        let footer = AtfDetailFooter {
+            magic: *b"2DTA",
+            checksum: 0,
+            event_count: back_links.len() as u64,
+            bytes_length,
+            time_start_ns: 1000,
+            time_end_ns: 1000,
+            reserved: [0; 24],
+        };
+        detail.write_all(as_bytes(&footer)).unwrap();
+    }
+
+    fn kinds(report: &VerifyReport) -> Vec<(VerifyIssueKind, u32)> {
+        report
+            .issues
+            .iter()
+            .map(|issue| (issue.kind, issue.thread_id))
+            .collect()
+    }
+
+    #[test]
+    fn test_verify__consistent_session__then_no_issues() {
+        let dir = TempDir::new().unwrap();
+        write_manifest(
+            dir.path(),
+            vec![
+                ThreadInfo {
+                    id: 0,
+                    has_detail: true,
+                },
+                ThreadInfo {
+                    id: 1,
+                    has_detail: false,
+                },
+            ],
+        );
+        write_thread(dir.path(), 0, 3, &[0, ATF_NO_DETAIL_SEQ, 1], Some(&[0, 2]));
+        write_thread(dir.path(), 1, 2, &[ATF_NO_DETAIL_SEQ; 2], None);
+
+        let report = SessionReader::open(dir.path()).unwrap().verify().unwrap();
+
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert_eq!(report.threads_checked, 2);
+        assert_eq!(report.events_checked, 5);
+    }
+
+    #[test]
+    fn test_verify__inconsistent_session__then_each_discrepancy_reported() {
+        let dir = TempDir::new().unwrap();
+        write_manifest(
+            dir.path(),
+            vec![
+                ThreadInfo {
+                    id: 0,
+                    has_detail: true,
+                },
+                ThreadInfo {
+                    id: 1,
+                    has_detail: true,
+                },
+                ThreadInfo {
+                    id: 2,
+                    has_detail: false,
+                },
+            ],
+        );
+        // Thread 0: second link points past the detail file, third links to a
+        // record that claims a different index event
+        write_thread(dir.path(), 0, 3, &[0, 5, 1], Some(&[0, 0]));
+        // Thread 1: the manifest claims details that were never written
+        write_thread(dir.path(), 1, 2, &[ATF_NO_DETAIL_SEQ; 2], None);
+        // Thread 2 is missing, thread 7 is not in the manifest
+        write_thread(dir.path(), 7, 1, &[ATF_NO_DETAIL_SEQ], None);
+
+        let session = SessionReader::open(dir.path()).unwrap();
+        assert_eq!(session.missing_threads(), &[2]);
+        let report = session.verify().unwrap();
+
+        assert_eq!(
+            kinds(&report),
+            vec![
+                (VerifyIssueKind::MissingThread, 2),
+                (VerifyIssueKind::DanglingDetailLink, 0),
+                (VerifyIssueKind::BrokenBackLink, 0),
+                (VerifyIssueKind::DetailPresenceMismatch, 1),
+                (VerifyIssueKind::UnlistedThread, 7),
+            ]
+        );
+        assert!(!report.is_consistent());
+    }
+
+    #[test]
+    fn test_quick_check__consistent_session__then_no_issues_and_end_samples() {
+        let dir = TempDir::new().unwrap();
+        write_manifest(
+            dir.path(),
+            vec![
+                ThreadInfo {
+                    id: 0,
+                    has_detail: true,
+                },
+                ThreadInfo {
+                    id: 1,
+                    has_detail: false,
+                },
+            ],
+        );
+        write_thread(dir.path(), 0, 3, &[0, ATF_NO_DETAIL_SEQ, 1], Some(&[0, 2]));
+        write_thread(dir.path(), 1, 8, &[ATF_NO_DETAIL_SEQ; 8], None);
+
+        let report = SessionReader::quick_check(dir.path()).unwrap();
+
+        assert!(report.is_consistent(), "{:?}", report.issues);
+        assert_eq!(report.threads_checked, 2);
+        assert_eq!(report.event_count, 11);
+        let timestamps = |events: &[IndexEvent]| -> Vec<u64> {
+            events.iter().map(|event| event.timestamp_ns).collect()
+        };
+        let short = &report.threads[0];
+        assert_eq!(timestamps(&short.first_events), vec![1000, 1100, 1200]);
+        assert_eq!(timestamps(&short.last_events), vec![1000, 1100, 1200]);
+        let long = &report.threads[1];
+        assert_eq!(long.event_count, 8);
+        assert_eq!(timestamps(&long.first_events), vec![1000, 1100, 1200]);
+        assert_eq!(timestamps(&long.last_events), vec![1500, 1600, 1700]);
+    }
+
+    #[test]
+    fn test_quick_check__truncated_and_mismatched_files__then_each_reported() {
+        let dir = TempDir::new().unwrap();
+        write_manifest(
+            dir.path(),
+            (0..4)
+                .map(|id| ThreadInfo {
+                    id,
+                    has_detail: id == 1,
+                })
+                .chain(std::iter::once(ThreadInfo {
+                    id: 9,
+                    has_detail: false,
+                }))
+                .collect(),
+        );
+        // Thread 0: the writer stopped halfway through the fourth event
+        write_thread(dir.path(), 0, 4, &[ATF_NO_DETAIL_SEQ; 4], None);
+        let index = dir.path().join("thread_0/index.atf");
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&index)
+            .unwrap()
+            .set_len(64 + 3 * 32 + 16)
+            .unwrap();
+        // Thread 1: the detail file lost its last record and footer
+        write_thread(dir.path(), 1, 2, &[0, 1], Some(&[0, 1]));
+        let detail = dir.path().join("thread_1/detail.atf");
+        let detail_len = fs::metadata(&detail).unwrap().len();
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&detail)
+            .unwrap()
+            .set_len(detail_len - 64 - 24)
+            .unwrap();
+        // Thread 2: the footer counts more events than were written
+        write_thread(dir.path(), 2, 5, &[ATF_NO_DETAIL_SEQ; 2], None);
+        // Thread 3: events run past the manifest's end at 2000
+        write_thread(dir.path(), 3, 12, &[ATF_NO_DETAIL_SEQ; 12], None);
+
+        let report = SessionReader::quick_check(dir.path()).unwrap();
+
+        assert_eq!(
+            report
+                .issues
+                .iter()
+                .map(|issue| (issue.kind, issue.thread_id))
+                .collect::<Vec<_>>(),
+            vec![
+                (VerifyIssueKind::IndexFooterMissing, 0),
+                (VerifyIssueKind::DetailSizeMismatch, 1),
+                (VerifyIssueKind::IndexCountMismatch, 2),
+                (VerifyIssueKind::TimeRangeMismatch, 3),
+                (VerifyIssueKind::MissingThread, 9),
+            ]
+        );
+        assert_eq!(report.threads[0].event_count, 3);
+        assert_eq!(report.threads[2].event_count, 2);
+        assert_eq!(report.threads_checked, 4);
+    }
+}
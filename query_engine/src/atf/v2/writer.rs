@@ -0,0 +1,614 @@
+// Writing ATF V2 sessions
+//
+// Exports build a new session out of an existing one. Each thread stream is
+// written through a `ThreadWriter`, which numbers the index and detail
+// records afresh so that dropping events keeps the links between the two
+// files intact. The manifest is published last, and atomically, so an export
+// that stops early never looks like a trace. Checksums are left at zero, as
+// the tracer writes them.
+
+use super::error::{AtfV2Error, Result};
+use super::index::IndexReader;
+use super::session::SessionReader;
+use super::thread::ThreadReader;
+use super::types::{
+    AtfDetailFooter, AtfDetailHeader, AtfIndexFooter, AtfIndexHeader, DetailEventHeader,
+    IndexEvent, ATF_INDEX_FLAG_HAS_DETAIL_FILE, ATF_NO_DETAIL_SEQ,
+};
+use serde_json::{json, Map, Value};
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const HEADER_SIZE: u64 = 64;
+const INDEX_EVENT_SIZE: u64 = 32;
+const DETAIL_EVENT_HEADER_SIZE: usize = 24;
+
+/// Index header fields a written stream carries over from its source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadLayout {
+    pub thread_id: u32,
+    pub arch: u8,
+    pub os: u8,
+    pub clock_type: u8,
+}
+
+impl ThreadLayout {
+    /// Layout of the stream `index` was read from
+    pub fn of(index: &IndexReader) -> Self {
+        ThreadLayout {
+            thread_id: index.thread_id(),
+            arch: index.arch(),
+            os: index.os(),
+            clock_type: index.clock_type(),
+        }
+    }
+}
+
+/// What a `ThreadWriter` wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrittenThread {
+    pub thread_id: u32,
+    pub event_count: u64,
+    pub detail_count: u64,
+    /// Earliest and latest event timestamps; `None` for an empty stream
+    pub time_range: Option<(u64, u64)>,
+}
+
+/// Detail file of a `ThreadWriter`, opened with its first record
+struct DetailFile {
+    file: BufWriter<File>,
+    path: PathBuf,
+    count: u64,
+    bytes_length: u64,
+    index_seq_range: (u32, u32),
+    time_range: (u64, u64),
+}
+
+/// Writes one thread's `index.atf` and, once a detail record arrives, its
+/// `detail.atf`
+pub struct ThreadWriter {
+    dir: PathBuf,
+    layout: ThreadLayout,
+    index: BufWriter<File>,
+    index_path: PathBuf,
+    event_count: u32,
+    time_range: Option<(u64, u64)>,
+    detail: Option<DetailFile>,
+}
+
+impl ThreadWriter {
+    /// Create `thread_dir` and start its index file
+    pub fn create(thread_dir: &Path, layout: ThreadLayout) -> Result<Self> {
+        fs::create_dir_all(thread_dir).map_err(|e| AtfV2Error::io(thread_dir, e))?;
+        let index_path = thread_dir.join("index.atf");
+        let index = create_with_header_space(&index_path)?;
+        Ok(ThreadWriter {
+            dir: thread_dir.to_path_buf(),
+            layout,
+            index,
+            index_path,
+            event_count: 0,
+            time_range: None,
+            detail: None,
+        })
+    }
+
+    /// Append `event`, linked to a detail record built from the given
+    /// header and payload
+    ///
+    /// The record length and the links in both directions are rewritten to
+    /// the positions the event and record take in the new files.
+    pub fn push(
+        &mut self,
+        event: &IndexEvent,
+        detail: Option<(DetailEventHeader, &[u8])>,
+    ) -> Result<()> {
+        let index_seq = self.event_count;
+        let mut event = *event;
+        event.detail_seq = match detail {
+            Some((header, payload)) => self.push_detail(index_seq, header, payload)?,
+            None => ATF_NO_DETAIL_SEQ,
+        };
+        write_record(&mut self.index, &self.index_path, &event)?;
+
+        self.event_count += 1;
+        let timestamp_ns = event.timestamp_ns;
+        self.time_range = Some(match self.time_range {
+            Some((start, end)) => (start.min(timestamp_ns), end.max(timestamp_ns)),
+            None => (timestamp_ns, timestamp_ns),
+        });
+        Ok(())
+    }
+
+    /// Append `event` of `source` together with its detail record, if any
+    pub fn copy_event(&mut self, source: &ThreadReader, event: &IndexEvent) -> Result<()> {
+        let detail = source.get_detail_for(event);
+        self.push(
+            event,
+            detail
+                .as_ref()
+                .map(|detail| (detail.header(), detail.payload())),
+        )
+    }
+
+    fn push_detail(
+        &mut self,
+        index_seq: u32,
+        header: DetailEventHeader,
+        payload: &[u8],
+    ) -> Result<u32> {
+        let timestamp_ns = header.timestamp;
+        let detail = match &mut self.detail {
+            Some(detail) => detail,
+            None => {
+                let path = self.dir.join("detail.atf");
+                let file = create_with_header_space(&path)?;
+                self.detail.insert(DetailFile {
+                    file,
+                    path,
+                    count: 0,
+                    bytes_length: 0,
+                    index_seq_range: (index_seq, index_seq),
+                    time_range: (timestamp_ns, timestamp_ns),
+                })
+            }
+        };
+
+        let detail_seq = detail.count as u32;
+        let total_length = DETAIL_EVENT_HEADER_SIZE + payload.len();
+        let header = DetailEventHeader {
+            total_length: total_length as u32,
+            index_seq,
+            ..header
+        };
+        write_record(&mut detail.file, &detail.path, &header)?;
+        detail
+            .file
+            .write_all(payload)
+            .map_err(|e| AtfV2Error::io(&detail.path, e))?;
+
+        detail.count += 1;
+        detail.bytes_length += total_length as u64;
+        detail.index_seq_range.1 = index_seq;
+        detail.time_range = (
+            detail.time_range.0.min(timestamp_ns),
+            detail.time_range.1.max(timestamp_ns),
+        );
+        Ok(detail_seq)
+    }
+
+    /// Write the footers, fill in the headers and close the files
+    pub fn finish(self) -> Result<WrittenThread> {
+        let ThreadWriter {
+            layout,
+            mut index,
+            index_path,
+            event_count,
+            time_range,
+            detail,
+            ..
+        } = self;
+        let (time_start_ns, time_end_ns) = time_range.unwrap_or((0, 0));
+        let events_bytes = event_count as u64 * INDEX_EVENT_SIZE;
+
+        let footer = AtfIndexFooter {
+            magic: *b"2ITA",
+            checksum: 0,
+            event_count: event_count as u64,
+            time_start_ns,
+            time_end_ns,
+            bytes_written: events_bytes,
+            reserved: [0; 24],
+        };
+        write_record(&mut index, &index_path, &footer)?;
+        let header = AtfIndexHeader {
+            magic: *b"ATI2",
+            endian: 0x01,
+            version: 1,
+            arch: layout.arch,
+            os: layout.os,
+            flags: if detail.is_some() {
+                ATF_INDEX_FLAG_HAS_DETAIL_FILE
+            } else {
+                0
+            },
+            thread_id: layout.thread_id,
+            clock_type: layout.clock_type,
+            _reserved1: [0; 3],
+            _reserved2: 0,
+            event_size: INDEX_EVENT_SIZE as u32,
+            event_count,
+            events_offset: HEADER_SIZE,
+            footer_offset: HEADER_SIZE + events_bytes,
+            time_start_ns,
+            time_end_ns,
+        };
+        finish_file(index, &index_path, &header)?;
+
+        let detail_count = match detail {
+            Some(detail) => finish_detail(detail, layout)?,
+            None => 0,
+        };
+
+        Ok(WrittenThread {
+            thread_id: layout.thread_id,
+            event_count: event_count as u64,
+            detail_count,
+            time_range,
+        })
+    }
+}
+
+fn finish_detail(mut detail: DetailFile, layout: ThreadLayout) -> Result<u64> {
+    let footer = AtfDetailFooter {
+        magic: *b"2DTA",
+        checksum: 0,
+        event_count: detail.count,
+        bytes_length: detail.bytes_length,
+        time_start_ns: detail.time_range.0,
+        time_end_ns: detail.time_range.1,
+        reserved: [0; 24],
+    };
+    write_record(&mut detail.file, &detail.path, &footer)?;
+    let header = AtfDetailHeader {
+        magic: *b"ATD2",
+        endian: 0x01,
+        version: 1,
+        arch: layout.arch,
+        os: layout.os,
+        flags: 0,
+        thread_id: layout.thread_id,
+        _reserved1: 0,
+        events_offset: HEADER_SIZE,
+        event_count: detail.count,
+        bytes_length: detail.bytes_length,
+        index_seq_start: detail.index_seq_range.0 as u64,
+        index_seq_end: detail.index_seq_range.1 as u64,
+        _reserved2: [0; 4],
+    };
+    finish_file(detail.file, &detail.path, &header)?;
+    Ok(detail.count)
+}
+
+/// Create `path` with room for a header, written once the counts are known
+fn create_with_header_space(path: &Path) -> Result<BufWriter<File>> {
+    let file = File::create(path).map_err(|e| AtfV2Error::io(path, e))?;
+    let mut file = BufWriter::new(file);
+    file.write_all(&[0; HEADER_SIZE as usize])
+        .map_err(|e| AtfV2Error::io(path, e))?;
+    Ok(file)
+}
+
+fn finish_file<T: Copy>(mut file: BufWriter<File>, path: &Path, header: &T) -> Result<()> {
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| AtfV2Error::io(path, e))?;
+    write_record(&mut file, path, header)?;
+    file.flush().map_err(|e| AtfV2Error::io(path, e))
+}
+
+fn write_record<T: Copy>(file: &mut BufWriter<File>, path: &Path, record: &T) -> Result<()> {
+    // SAFETY: the V2 records are `repr(C, packed)` plain data
+    let bytes = unsafe {
+        std::slice::from_raw_parts(record as *const T as *const u8, std::mem::size_of::<T>())
+    };
+    file.write_all(bytes).map_err(|e| AtfV2Error::io(path, e))
+}
+
+/// Fields of the `manifest.json` in `session_dir`, kept as JSON so that a
+/// rewritten manifest carries over the fields `Manifest` does not model
+pub fn read_manifest_fields(session_dir: &Path) -> Result<Map<String, Value>> {
+    let path = session_dir.join("manifest.json");
+    let bytes = fs::read(&path).map_err(|e| AtfV2Error::io(&path, e))?;
+    match serde_json::from_slice(&bytes)? {
+        Value::Object(fields) => Ok(fields),
+        _ => Err(AtfV2Error::InvalidManifest(
+            "manifest is not a JSON object".to_string(),
+        )),
+    }
+}
+
+/// Publish the manifest of a session holding `threads`
+///
+/// `fields` supplies everything but the thread list and the time range,
+/// which are taken from what was written. Child sessions are not copied, so
+/// links to them are dropped.
+pub fn write_manifest(
+    session_dir: &Path,
+    mut fields: Map<String, Value>,
+    threads: &[WrittenThread],
+) -> Result<()> {
+    let ranges = || threads.iter().filter_map(|thread| thread.time_range);
+    let time_start_ns = ranges().map(|(start, _)| start).min().unwrap_or(0);
+    let time_end_ns = ranges().map(|(_, end)| end).max().unwrap_or(0);
+    let thread_list = threads
+        .iter()
+        .map(|thread| json!({ "id": thread.thread_id, "has_detail": thread.detail_count > 0 }))
+        .collect();
+    fields.insert("threads".to_string(), Value::Array(thread_list));
+    fields.insert("time_start_ns".to_string(), time_start_ns.into());
+    fields.insert("time_end_ns".to_string(), time_end_ns.into());
+    fields.remove("children");
+
+    let path = session_dir.join("manifest.json");
+    let tmp_path = session_dir.join("manifest.json.tmp");
+    let bytes = serde_json::to_vec_pretty(&Value::Object(fields))?;
+    fs::write(&tmp_path, bytes).map_err(|e| AtfV2Error::io(&tmp_path, e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| AtfV2Error::io(&path, e))
+}
+
+/// Write `threads` of a session to `output_dir` as a new session, passing
+/// each stream through `rewrite`, which pushes the events to keep
+///
+/// `manifest` supplies the manifest fields, as for `write_manifest`.
+pub fn rewrite_threads<'a, I, F>(
+    threads: I,
+    output_dir: &Path,
+    manifest: Map<String, Value>,
+    mut rewrite: F,
+) -> Result<Vec<WrittenThread>>
+where
+    I: IntoIterator<Item = &'a ThreadReader>,
+    F: FnMut(&ThreadReader, &mut ThreadWriter) -> Result<()>,
+{
+    let mut written = Vec::new();
+    for thread in threads {
+        let thread_dir = output_dir.join(format!("thread_{}", thread.thread_id()));
+        let mut writer = ThreadWriter::create(&thread_dir, ThreadLayout::of(&thread.index))?;
+        rewrite(thread, &mut writer)?;
+        written.push(writer.finish()?);
+    }
+    write_manifest(output_dir, manifest, &written)?;
+    Ok(written)
+}
+
+impl SessionReader {
+    /// Write every event of the session, with its detail record, back out
+    /// through `ThreadWriter` as a new session in `output_dir`
+    ///
+    /// The copy holds the same events but freshly numbered records, headers,
+    /// footers and manifest time range, which normalizes sessions written by
+    /// older or partial writers. `output_dir` must not exist yet. Child
+    /// sessions are not copied.
+    pub fn reencode(&self, output_dir: &Path) -> Result<Vec<WrittenThread>> {
+        let manifest = match self.session_dir() {
+            Some(session_dir) => read_manifest_fields(session_dir)?,
+            // Remote sessions keep no raw manifest, so only the modelled
+            // fields are carried over
+            None => match serde_json::to_value(self.manifest())? {
+                Value::Object(fields) => fields,
+                _ => Map::new(),
+            },
+        };
+        fs::create_dir(output_dir).map_err(|e| AtfV2Error::io(output_dir, e))?;
+        rewrite_threads(self.threads(), output_dir, manifest, |thread, writer| {
+            for event in thread.index.iter() {
+                writer.copy_event(thread, event)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atf::v2::types::{ATF_DETAIL_EVENT_FUNCTION_CALL, ATF_EVENT_KIND_CALL};
+    use tempfile::TempDir;
+
+    const LAYOUT: ThreadLayout = ThreadLayout {
+        thread_id: 7,
+        arch: 2,
+        os: 3,
+        clock_type: 1,
+    };
+
+    fn event(seq: u64) -> IndexEvent {
+        IndexEvent {
+            timestamp_ns: 1000 + seq * 10,
+            function_id: 0x100 + seq,
+            thread_id: 7,
+            event_kind: ATF_EVENT_KIND_CALL,
+            call_depth: 0,
+            detail_seq: 99,
+        }
+    }
+
+    fn detail_header(seq: u64) -> DetailEventHeader {
+        DetailEventHeader {
+            total_length: 0,
+            event_type: ATF_DETAIL_EVENT_FUNCTION_CALL,
+            flags: 0,
+            index_seq: 99,
+            thread_id: 7,
+            timestamp: 1000 + seq * 10,
+        }
+    }
+
+    #[test]
+    fn test_thread_writer__mixed_details__then_links_renumbered() {
+        let dir = TempDir::new().unwrap();
+        let thread_dir = dir.path().join("thread_7");
+
+        let mut writer = ThreadWriter::create(&thread_dir, LAYOUT).unwrap();
+        writer.push(&event(0), None).unwrap();
+        writer
+            .push(&event(1), Some((detail_header(1), &[1, 2, 3][..])))
+            .unwrap();
+        writer
+            .push(&event(2), Some((detail_header(2), &[4][..])))
+            .unwrap();
+        let written = writer.finish().unwrap();
+
+        assert_eq!(
+            written,
+            WrittenThread {
+                thread_id: 7,
+                event_count: 3,
+                detail_count: 2,
+                time_range: Some((1000, 1020)),
+            }
+        );
+
+        let thread = ThreadReader::open(&thread_dir).unwrap();
+        assert_eq!(ThreadLayout::of(&thread.index), LAYOUT);
+        assert!(thread.index.has_detail());
+        assert_eq!(thread.index.footer_event_count(), Some(3));
+        assert_eq!(thread.time_range(), (1000, 1020));
+
+        assert!(thread
+            .get_detail_for(thread.index.get(0).unwrap())
+            .is_none());
+        let second = thread.index.get(1).unwrap();
+        let detail = thread.get_detail_for(second).unwrap();
+        assert_eq!(detail.payload(), &[1, 2, 3]);
+        assert_eq!({ detail.header().total_length }, 27);
+        assert_eq!(
+            { thread.get_index_for(&detail).unwrap().timestamp_ns },
+            1010
+        );
+        let third = thread.index.get(2).unwrap();
+        assert_eq!({ third.detail_seq }, 1);
+        assert_eq!(thread.get_detail_for(third).unwrap().payload(), &[4]);
+    }
+
+    #[test]
+    fn test_thread_writer__no_details__then_index_only() {
+        let dir = TempDir::new().unwrap();
+        let thread_dir = dir.path().join("thread_7");
+
+        let writer = ThreadWriter::create(&thread_dir, LAYOUT).unwrap();
+        let written = writer.finish().unwrap();
+
+        assert_eq!(written.event_count, 0);
+        assert_eq!(written.time_range, None);
+        assert!(!thread_dir.join("detail.atf").exists());
+        let thread = ThreadReader::open(&thread_dir).unwrap();
+        assert!(thread.index.is_empty());
+        assert!(!thread.index.has_detail());
+    }
+
+    #[test]
+    fn test_rewrite_threads__filtered_events__then_manifest_regenerated() {
+        let source = TempDir::new().unwrap();
+        let mut writer = ThreadWriter::create(&source.path().join("thread_7"), LAYOUT).unwrap();
+        for seq in 0..4 {
+            writer
+                .push(&event(seq), Some((detail_header(seq), &[seq as u8][..])))
+                .unwrap();
+        }
+        let written = writer.finish().unwrap();
+        let mut fields = Map::new();
+        fields.insert("clock_type".to_string(), json!(1));
+        fields.insert(
+            "children".to_string(),
+            json!([{ "pid": 2, "dir": "children/pid_2" }]),
+        );
+        write_manifest(source.path(), fields, &[written]).unwrap();
+
+        let session = SessionReader::open(source.path()).unwrap();
+        let output = TempDir::new().unwrap();
+        let manifest = read_manifest_fields(source.path()).unwrap();
+        let written = rewrite_threads(
+            session.threads(),
+            output.path(),
+            manifest,
+            |thread, writer| {
+                for event in thread
+                    .index
+                    .iter()
+                    .filter(|event| event.timestamp_ns >= 1020)
+                {
+                    writer.copy_event(thread, event)?;
+                }
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].event_count, 2);
+        let fields = read_manifest_fields(output.path()).unwrap();
+        assert_eq!(fields["clock_type"], 1);
+        assert!(!fields.contains_key("children"));
+        assert!(!output.path().join("manifest.json.tmp").exists());
+
+        let sliced = SessionReader::open(output.path()).unwrap();
+        assert_eq!(sliced.manifest().time_start_ns, 1020);
+        assert_eq!(sliced.manifest().time_end_ns, 1030);
+        assert!(sliced.manifest().threads[0].has_detail);
+        assert!(sliced.verify().unwrap().is_consistent());
+        let thread = &sliced.threads()[0];
+        let payloads: Vec<_> = thread
+            .index
+            .iter()
+            .map(|event| thread.get_detail_for(event).unwrap().payload().to_vec())
+            .collect();
+        assert_eq!(payloads, vec![vec![2], vec![3]]);
+    }
+
+    #[test]
+    fn test_reencode__stale_manifest__then_same_events_and_fresh_manifest() {
+        let source = TempDir::new().unwrap();
+        let mut writer = ThreadWriter::create(&source.path().join("thread_7"), LAYOUT).unwrap();
+        for seq in 0..3 {
+            let payload = [seq as u8, 0xff];
+            let detail = (seq != 1).then_some((detail_header(seq), &payload[..]));
+            writer.push(&event(seq), detail).unwrap();
+        }
+        let written = writer.finish().unwrap();
+        let mut fields = Map::new();
+        fields.insert("format_version".to_string(), json!("2.0"));
+        write_manifest(source.path(), fields, &[written]).unwrap();
+        let mut fields = read_manifest_fields(source.path()).unwrap();
+        fields.insert("time_start_ns".to_string(), json!(0));
+        fields.insert("time_end_ns".to_string(), json!(5000));
+        fs::write(
+            source.path().join("manifest.json"),
+            serde_json::to_vec(&fields).unwrap(),
+        )
+        .unwrap();
+
+        let session = SessionReader::open(source.path()).unwrap();
+        let output_root = TempDir::new().unwrap();
+        let output = output_root.path().join("reencoded");
+        let written = session.reencode(&output).unwrap();
+
+        assert_eq!(written[0].event_count, 3);
+        let reencoded = SessionReader::open(&output).unwrap();
+        assert!(reencoded.verify().unwrap().is_consistent());
+        assert_eq!(reencoded.time_range(), (1000, 1020));
+        assert_eq!(
+            read_manifest_fields(&output).unwrap()["format_version"],
+            "2.0"
+        );
+        let events = |session: &SessionReader| -> Vec<_> {
+            session
+                .merged_iter()
+                .map(|(thread, event)| {
+                    let payload = session.threads()[thread]
+                        .get_detail_for(event)
+                        .map(|detail| detail.payload().to_vec());
+                    let IndexEvent {
+                        timestamp_ns,
+                        function_id,
+                        event_kind,
+                        call_depth,
+                        detail_seq,
+                        ..
+                    } = *event;
+                    (
+                        timestamp_ns,
+                        function_id,
+                        event_kind,
+                        call_depth,
+                        detail_seq,
+                        payload,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(events(&reencoded), events(&session));
+        assert!(session.reencode(&output).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task;
+
+use crate::{
+    atf::{AtfError, ParsedEventKind},
+    handlers::{
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, EventSource, SourceProvider},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+    },
+};
+
+const DEFAULT_DEEP_STACK_DEPTH: u32 = 8;
+const DEFAULT_MAX_SAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceAnomaliesParams {
+    pub trace_id: String,
+    /// Calls still open at the end of the trace from which a thread's stack
+    /// counts as deep.
+    #[serde(default = "default_deep_stack_depth")]
+    pub deep_stack_depth: u32,
+    /// Unmatched returns listed per thread; all of them are counted.
+    #[serde(default = "default_max_samples")]
+    pub max_samples: usize,
+    /// Leave out threads whose calls and returns all match.
+    #[serde(default)]
+    pub only_anomalous: bool,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+fn default_deep_stack_depth() -> u32 {
+    DEFAULT_DEEP_STACK_DEPTH
+}
+
+fn default_max_samples() -> usize {
+    DEFAULT_MAX_SAMPLES
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceAnomaliesResponse {
+    /// Every thread that made or returned from a call, most severe first.
+    pub threads: Vec<ThreadBalance>,
+    /// Threads with open calls at the end or unmatched returns.
+    pub anomalous_thread_count: u64,
+    pub deep_stack_thread_count: u64,
+    pub unmatched_return_count: u64,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadBalance {
+    pub thread_id: u32,
+    pub call_count: u64,
+    pub return_count: u64,
+    /// Calls minus returns; positive when calls were left open.
+    pub imbalance: i64,
+    /// Calls still open at the end of the trace.
+    pub open_depth: u32,
+    /// Whether `open_depth` reached the requested deep stack depth.
+    pub deep_stack: bool,
+    /// Symbols of the open calls, outermost first.
+    pub open_stack: Vec<Option<String>>,
+    /// Returns that arrived while the thread had no open call.
+    pub unmatched_return_count: u64,
+    /// The first unmatched returns, in stream order.
+    pub unmatched_returns: Vec<UnmatchedReturn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedReturn {
+    pub timestamp_ns: u64,
+    pub symbol: Option<String>,
+}
+
+impl ThreadBalance {
+    fn is_anomalous(&self) -> bool {
+        self.open_depth > 0 || self.unmatched_return_count > 0
+    }
+}
+
+/// Call/return balance of every thread in `source`.
+///
+/// Each thread keeps a stack of open calls, as span reconstruction does: a
+/// return closes the innermost open call, and a return with nothing open is
+/// unmatched. Threads are sorted by the magnitude of their imbalance, then
+/// by the number of anomalous events (open calls plus unmatched returns),
+/// so a thread whose stray returns cancel out its open calls still ranks
+/// above a clean one. Ties go by thread id.
+pub(crate) fn trace_anomalies(
+    source: &dyn EventSource,
+    deep_stack_depth: u32,
+    max_samples: usize,
+) -> Result<Vec<ThreadBalance>, AtfError> {
+    let mut threads: BTreeMap<u32, (ThreadBalance, Vec<Option<String>>)> = BTreeMap::new();
+
+    for item in source.events()? {
+        let event = item?;
+        let (is_call, symbol) = match &event.kind {
+            ParsedEventKind::FunctionCall { symbol } => (true, symbol),
+            ParsedEventKind::FunctionReturn { symbol } => (false, symbol),
+            _ => continue,
+        };
+        let (balance, stack) = threads.entry(event.thread_id).or_insert_with(|| {
+            let balance = ThreadBalance {
+                thread_id: event.thread_id,
+                call_count: 0,
+                return_count: 0,
+                imbalance: 0,
+                open_depth: 0,
+                deep_stack: false,
+                open_stack: Vec::new(),
+                unmatched_return_count: 0,
+                unmatched_returns: Vec::new(),
+            };
+            (balance, Vec::new())
+        });
+        if is_call {
+            balance.call_count += 1;
+            stack.push(symbol.clone());
+            continue;
+        }
+        balance.return_count += 1;
+        if stack.pop().is_none() {
+            balance.unmatched_return_count += 1;
+            if balance.unmatched_returns.len() < max_samples {
+                balance.unmatched_returns.push(UnmatchedReturn {
+                    timestamp_ns: event.timestamp_ns,
+                    symbol: symbol.clone(),
+                });
+            }
+        }
+    }
+
+    let mut balances: Vec<ThreadBalance> = threads
+        .into_values()
+        .map(|(mut balance, stack)| {
+            balance.imbalance = balance.call_count as i64 - balance.return_count as i64;
+            balance.open_depth = stack.len() as u32;
+            balance.deep_stack = balance.open_depth > 0 && balance.open_depth >= deep_stack_depth;
+            balance.open_stack = stack;
+            balance
+        })
+        .collect();
+    // Stable, so ties stay in thread id order.
+    balances.sort_by_key(|balance| {
+        let anomalous_events = u64::from(balance.open_depth) + balance.unmatched_return_count;
+        std::cmp::Reverse((balance.imbalance.unsigned_abs(), anomalous_events))
+    });
+    Ok(balances)
+}
+
+#[derive(Clone)]
+pub struct TraceAnomaliesHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
+}
+
+impl TraceAnomaliesHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.anomalies", self);
+    }
+
+    fn map_atf_error(err: AtfError) -> JsonRpcError {
+        match err {
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to load trace: {other}")),
+        }
+    }
+
+    pub async fn get_anomalies(
+        &self,
+        params: TraceAnomaliesParams,
+    ) -> Result<TraceAnomaliesResponse, JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        let source = Arc::clone(&self.source);
+        let (deep_stack_depth, max_samples) = (params.deep_stack_depth, params.max_samples);
+        let mut threads = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            trace_anomalies(events.as_ref(), deep_stack_depth, max_samples)
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("anomaly detection task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
+
+        let anomalous_thread_count = threads.iter().filter(|t| t.is_anomalous()).count() as u64;
+        let deep_stack_thread_count = threads.iter().filter(|t| t.deep_stack).count() as u64;
+        let unmatched_return_count = threads.iter().map(|t| t.unmatched_return_count).sum();
+        if params.only_anomalous {
+            threads.retain(ThreadBalance::is_anomalous);
+        }
+
+        Ok(TraceAnomaliesResponse {
+            threads,
+            anomalous_thread_count,
+            deep_stack_thread_count,
+            unmatched_return_count,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TraceAnomaliesHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TraceAnomaliesParams =
+            TraceAnomaliesParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid trace.anomalies params: {err}"))
+            })?;
+
+        let response = self.get_anomalies(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::ParsedEvent;
+    use crate::handlers::source::{MemoryEventSource, MemorySourceProvider};
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+
+    fn call(timestamp_ns: u64, thread_id: u32, symbol: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: ParsedEventKind::FunctionCall {
+                symbol: Some(symbol.into()),
+            },
+        }
+    }
+
+    fn ret(timestamp_ns: u64, thread_id: u32, symbol: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: ParsedEventKind::FunctionReturn {
+                symbol: Some(symbol.into()),
+            },
+        }
+    }
+
+    #[test]
+    fn trace_anomalies__mixed_threads__then_sorted_by_imbalance() {
+        let events = vec![
+            // Thread 1 is balanced
+            call(1, 1, "main"),
+            ret(2, 1, "main"),
+            // Thread 2 exits three calls deep
+            call(3, 2, "worker"),
+            call(4, 2, "loop"),
+            call(5, 2, "read"),
+            // Thread 3 returns from a call recorded before tracing began
+            ret(6, 3, "started_early"),
+        ];
+
+        let threads = trace_anomalies(&MemoryEventSource::new(events), 3, 5).expect("anomalies");
+
+        let order: Vec<u32> = threads.iter().map(|thread| thread.thread_id).collect();
+        assert_eq!(order, vec![2, 3, 1]);
+        assert_eq!(threads[0].imbalance, 3);
+        assert_eq!(threads[0].open_depth, 3);
+        assert!(threads[0].deep_stack);
+        assert_eq!(
+            threads[0].open_stack,
+            vec![
+                Some("worker".to_string()),
+                Some("loop".to_string()),
+                Some("read".to_string())
+            ]
+        );
+        assert_eq!(threads[1].imbalance, -1);
+        assert_eq!(
+            threads[1].unmatched_returns,
+            vec![UnmatchedReturn {
+                timestamp_ns: 6,
+                symbol: Some("started_early".into()),
+            }]
+        );
+        assert!(!threads[2].is_anomalous());
+    }
+
+    #[test]
+    fn trace_anomalies__stray_return_cancels_open_call__then_still_ranked_anomalous() {
+        let events = vec![
+            call(1, 1, "a"),
+            ret(2, 1, "a"),
+            ret(3, 2, "stray"),
+            call(4, 2, "open"),
+        ];
+
+        let threads = trace_anomalies(&MemoryEventSource::new(events), 8, 5).expect("anomalies");
+
+        assert_eq!(threads[0].thread_id, 2);
+        assert_eq!(threads[0].imbalance, 0);
+        assert_eq!(threads[0].open_depth, 1);
+        assert!(!threads[0].deep_stack);
+        assert_eq!(threads[0].unmatched_return_count, 1);
+    }
+
+    #[tokio::test]
+    async fn trace_anomalies__only_anomalous__then_balanced_threads_dropped() {
+        let provider = MemorySourceProvider::new().with_trace(
+            "run",
+            MemoryEventSource::new(vec![
+                call(1, 1, "main"),
+                ret(2, 1, "main"),
+                ret(3, 2, "x"),
+                ret(4, 2, "y"),
+            ]),
+        );
+        let handler =
+            TraceAnomaliesHandler::new(PathBuf::from("/virtual")).with_source(Arc::new(provider));
+
+        let result = handler
+            .call(Some(json!({
+                "traceId": "run",
+                "onlyAnomalous": true,
+                "maxSamples": 1
+            })))
+            .await
+            .expect("anomalies");
+
+        assert_eq!(result["anomalousThreadCount"], 1);
+        assert_eq!(result["unmatchedReturnCount"], 2);
+        assert_eq!(result["threads"].as_array().expect("threads").len(), 1);
+        assert_eq!(result["threads"][0]["threadId"], 2);
+        assert_eq!(result["threads"][0]["unmatchedReturnCount"], 2);
+        assert_eq!(
+            result["threads"][0]["unmatchedReturns"]
+                .as_array()
+                .expect("samples")
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn trace_anomalies__v2_thread_exits_mid_call__then_open_stack_reported() {
+        let fixture = TraceFixture::new("exited");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x100),
+            function_return_event(200, 1, 0x100),
+            function_call_event(150, 2, 0x200),
+            function_call_event(160, 2, 0x300),
+        ]);
+        let handler = TraceAnomaliesHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({ "traceId": "exited" })))
+            .await
+            .expect("anomalies");
+
+        assert_eq!(result["anomalousThreadCount"], 1);
+        assert_eq!(result["threads"][0]["threadId"], 2);
+        assert_eq!(result["threads"][0]["openStack"], json!(["0x200", "0x300"]));
+        assert_eq!(result["threads"][1]["imbalance"], 0);
+    }
+}
@@ -0,0 +1,256 @@
+//! Typed query API for embedding the query engine as a library.
+//!
+//! The JSON-RPC handlers parse `serde_json::Value` params and delegate to the
+//! same typed methods this facade calls, so Rust callers get identical results
+//! without serializing through JSON.
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    handlers::{
+        events::{EventsGetParams, EventsGetResponse},
+        spans::{SpansListParams, SpansListResponse},
+        trace_info::{TraceInfoParams, TraceInfoResponse},
+        source::SourceProvider,
+        EventsGetHandler, SpansListHandler, TraceInfoHandler,
+    },
+    server::types::JsonRpcError,
+};
+
+const DEFAULT_CACHE_CAPACITY: usize = 100;
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum QueryError {
+    #[error("invalid parameters: {0}")]
+    InvalidParams(String),
+    #[error("trace not found")]
+    TraceNotFound,
+    #[error("query failed: {0}")]
+    Internal(String),
+}
+
+impl From<JsonRpcError> for QueryError {
+    fn from(err: JsonRpcError) -> Self {
+        let detail = match err.data {
+            Some(Value::String(detail)) => detail,
+            Some(other) => other.to_string(),
+            None => err.message,
+        };
+        match err.code {
+            -32602 => QueryError::InvalidParams(detail),
+            -32000 => QueryError::TraceNotFound,
+            _ => QueryError::Internal(detail),
+        }
+    }
+}
+
+impl From<QueryError> for JsonRpcError {
+    fn from(err: QueryError) -> Self {
+        match err {
+            QueryError::InvalidParams(detail) => JsonRpcError::invalid_params(detail),
+            QueryError::TraceNotFound => JsonRpcError::trace_not_found(),
+            QueryError::Internal(detail) => JsonRpcError::internal(detail),
+        }
+    }
+}
+
+/// Query entry point over a single trace root, mirroring the RPC methods.
+#[derive(Clone)]
+pub struct QueryApi {
+    events: EventsGetHandler,
+    spans: SpansListHandler,
+    trace_info: TraceInfoHandler,
+}
+
+impl QueryApi {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self::with_cache(trace_root_dir, DEFAULT_CACHE_CAPACITY, DEFAULT_CACHE_TTL)
+    }
+
+    pub fn with_cache(trace_root_dir: PathBuf, cache_capacity: usize, cache_ttl: Duration) -> Self {
+        Self {
+            events: EventsGetHandler::new(trace_root_dir.clone()),
+            spans: SpansListHandler::new(trace_root_dir.clone()),
+            trace_info: TraceInfoHandler::new(trace_root_dir, cache_capacity, cache_ttl),
+        }
+    }
+
+    /// Reads `events.get` and `spans.list` traces through `source`, e.g. an
+    /// in-memory [`MemorySourceProvider`](crate::handlers::MemorySourceProvider),
+    /// instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.events = self.events.with_source(Arc::clone(&source));
+        self.spans = self.spans.with_source(source);
+        self
+    }
+
+    /// Typed equivalent of `events.get`.
+    pub async fn get_events(
+        &self,
+        params: EventsGetParams,
+    ) -> Result<EventsGetResponse, QueryError> {
+        Ok(self.events.get_events(params).await?)
+    }
+
+    /// Typed equivalent of `spans.list`.
+    pub async fn list_spans(
+        &self,
+        params: SpansListParams,
+    ) -> Result<SpansListResponse, QueryError> {
+        Ok(self.spans.list_spans(params).await?)
+    }
+
+    /// Typed equivalent of `trace.info`.
+    pub async fn get_trace_info(
+        &self,
+        params: TraceInfoParams,
+    ) -> Result<TraceInfoResponse, QueryError> {
+        Ok(self.trace_info.get_trace_info(params).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    const MAIN: u64 = 0x1000;
+
+    fn write_trace(trace_id: &str) -> TraceFixture {
+        let fixture = TraceFixture::new(trace_id);
+        fixture.write_events(&[
+            function_call_event(100, 1, MAIN),
+            function_return_event(200, 1, MAIN),
+        ]);
+        fixture
+    }
+
+    fn events_params(trace_id: &str) -> EventsGetParams {
+        serde_json::from_value(json!({ "traceId": trace_id })).expect("params")
+    }
+
+    #[tokio::test]
+    async fn query_api__get_events__then_typed_response() {
+        let fixture = write_trace("trace");
+        let api = QueryApi::new(fixture.trace_root());
+
+        let response = api
+            .get_events(events_params("trace"))
+            .await
+            .expect("events");
+        assert_eq!(response.metadata.total_count, 2);
+        assert_eq!(response.events[0].timestamp_ns, Some(100));
+
+        let spans = api
+            .list_spans(serde_json::from_value(json!({ "traceId": "trace" })).expect("params"))
+            .await
+            .expect("spans");
+        assert_eq!(spans.metadata.total_count, 1);
+        assert_eq!(spans.spans[0].duration_ns, Some(100));
+
+        let info = api
+            .get_trace_info(TraceInfoParams {
+                trace_id: "trace".into(),
+                ..Default::default()
+            })
+            .await
+            .expect("trace info");
+        assert_eq!(info.event_count, 2);
+        assert_eq!(info.span_count, 1);
+    }
+
+    #[tokio::test]
+    async fn query_api__memory_source__then_served_without_files() {
+        use crate::{
+            atf::{ParsedEvent, ParsedEventKind},
+            handlers::{MemoryEventSource, MemorySourceProvider},
+        };
+
+        let symbol = Some("main".to_string());
+        let events = vec![
+            ParsedEvent {
+                timestamp_ns: 100,
+                thread_id: 7,
+                kind: ParsedEventKind::FunctionCall { symbol: symbol.clone() },
+            },
+            ParsedEvent {
+                timestamp_ns: 250,
+                thread_id: 7,
+                kind: ParsedEventKind::FunctionReturn { symbol },
+            },
+        ];
+        let provider =
+            MemorySourceProvider::new().with_trace("in_memory", MemoryEventSource::new(events));
+        let api = QueryApi::new(PathBuf::from("/nonexistent/trace/root"))
+            .with_source(Arc::new(provider));
+
+        let response = api
+            .get_events(events_params("in_memory"))
+            .await
+            .expect("events");
+        assert_eq!(response.metadata.total_count, 2);
+        assert_eq!(response.events[1].thread_id, Some(7));
+
+        let spans = api
+            .list_spans(serde_json::from_value(json!({ "traceId": "in_memory" })).expect("params"))
+            .await
+            .expect("spans");
+        assert_eq!(spans.spans[0].duration_ns, Some(150));
+
+        let err = api
+            .get_events(events_params("other"))
+            .await
+            .expect_err("unknown trace");
+        assert_eq!(err, QueryError::TraceNotFound);
+    }
+
+    #[tokio::test]
+    async fn query_api__missing_trace__then_trace_not_found() {
+        let root = TempDir::new().expect("tempdir");
+        let api = QueryApi::new(root.path().to_path_buf());
+
+        let err = api
+            .get_trace_info(TraceInfoParams {
+                trace_id: "missing".into(),
+                ..Default::default()
+            })
+            .await
+            .expect_err("expected error");
+        assert_eq!(err, QueryError::TraceNotFound);
+    }
+
+    #[tokio::test]
+    async fn query_api__empty_trace_id__then_invalid_params() {
+        let root = TempDir::new().expect("tempdir");
+        let api = QueryApi::new(root.path().to_path_buf());
+
+        let err = api
+            .get_events(events_params("  "))
+            .await
+            .expect_err("expected error");
+        assert_eq!(
+            err,
+            QueryError::InvalidParams("traceId must not be empty".into())
+        );
+    }
+
+    #[test]
+    fn query_error__json_rpc_round_trip__then_codes_preserved() {
+        for err in [
+            QueryError::InvalidParams("bad".into()),
+            QueryError::TraceNotFound,
+            QueryError::Internal("boom".into()),
+        ] {
+            let rpc: JsonRpcError = err.clone().into();
+            assert_eq!(QueryError::from(rpc), err);
+        }
+    }
+}
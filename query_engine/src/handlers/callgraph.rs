@@ -0,0 +1,410 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task;
+
+use crate::{
+    atf::AtfError,
+    handlers::{
+        functions::FunctionTimingFilters,
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, SourceProvider},
+        spans::{for_each_nested, reconstruct_spans, SpanCandidate},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+    },
+};
+
+fn default_min_call_count() -> u64 {
+    1
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallGraphParams {
+    pub trace_id: String,
+    /// Thread and time filters, applied to the callee of each call.
+    #[serde(default)]
+    pub filters: FunctionTimingFilters,
+    /// Edges with fewer calls than this are left out of the graph.
+    #[serde(default = "default_min_call_count")]
+    pub min_call_count: u64,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallGraphResponse {
+    /// Graphviz source of the caller-to-callee graph.
+    pub dot: String,
+    pub node_count: u64,
+    pub edge_count: u64,
+    /// Edges dropped for falling below `minCallCount`.
+    pub pruned_edge_count: u64,
+    pub execution_time_ms: u64,
+}
+
+/// Caller-to-callee graph of named functions.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CallGraph {
+    /// Self time per function.
+    pub(crate) self_ns: BTreeMap<String, u64>,
+    /// Call count per `(caller, callee)`.
+    pub(crate) edges: BTreeMap<(String, String), u64>,
+    /// Functions called with no named caller, such as thread entry points.
+    pub(crate) roots: BTreeSet<String>,
+}
+
+impl CallGraph {
+    /// Builds the graph from reconstructed spans; the direct caller of a span
+    /// is the innermost span enclosing it.
+    pub(crate) fn build(spans: &[SpanCandidate], filters: &FunctionTimingFilters) -> Self {
+        let mut graph = CallGraph::default();
+        for_each_nested(spans, |span, enclosing| {
+            let Some(callee) = span.function_name.as_deref() else {
+                return;
+            };
+            if !filters.matches(span) {
+                return;
+            }
+            let self_ns = graph.self_ns.entry(callee.to_string()).or_default();
+            *self_ns = self_ns.saturating_add(span.self_duration_ns);
+            match enclosing
+                .last()
+                .and_then(|caller| caller.function_name.as_deref())
+            {
+                Some(caller) => {
+                    *graph
+                        .edges
+                        .entry((caller.to_string(), callee.to_string()))
+                        .or_default() += 1;
+                }
+                None => {
+                    graph.roots.insert(callee.to_string());
+                }
+            }
+        });
+        graph
+    }
+
+    /// Renders the graph as DOT, dropping edges called fewer than
+    /// `min_call_count` times and the functions left with no edge. Roots are
+    /// always kept so the entry points stay visible.
+    pub(crate) fn to_dot(&self, min_call_count: u64) -> DotGraph {
+        let (kept, pruned): (Vec<_>, Vec<_>) = self
+            .edges
+            .iter()
+            .partition(|(_, &count)| count >= min_call_count);
+
+        let mut nodes: BTreeSet<&str> = self.roots.iter().map(String::as_str).collect();
+        for ((caller, callee), _) in &kept {
+            nodes.insert(caller);
+            nodes.insert(callee);
+        }
+
+        let mut dot = String::from("digraph callgraph {\n    node [shape=box];\n");
+        for name in &nodes {
+            let self_ns = self.self_ns.get(*name).copied().unwrap_or(0);
+            let _ = writeln!(
+                dot,
+                "    {} [label=\"{}\\nself {} ns\"];",
+                dot_id(name),
+                dot_escape(name),
+                self_ns
+            );
+        }
+        for ((caller, callee), count) in &kept {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label=\"{count}\", weight={count}];",
+                dot_id(caller),
+                dot_id(callee)
+            );
+        }
+        dot.push_str("}\n");
+
+        DotGraph {
+            dot,
+            node_count: nodes.len() as u64,
+            edge_count: kept.len() as u64,
+            pruned_edge_count: pruned.len() as u64,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct DotGraph {
+    pub(crate) dot: String,
+    pub(crate) node_count: u64,
+    pub(crate) edge_count: u64,
+    pub(crate) pruned_edge_count: u64,
+}
+
+fn dot_escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", dot_escape(name))
+}
+
+#[derive(Clone)]
+pub struct CallGraphHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
+}
+
+impl CallGraphHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("functions.callGraph", self);
+    }
+
+    fn validate_params(params: &CallGraphParams) -> Result<(), JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+        if let (Some(start), Some(end)) = (params.filters.time_start_ns, params.filters.time_end_ns)
+        {
+            if start >= end {
+                return Err(JsonRpcError::invalid_params(
+                    "timeStartNs must be less than timeEndNs",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn map_atf_error(err: AtfError) -> JsonRpcError {
+        match err {
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to load trace: {other}")),
+        }
+    }
+
+    pub async fn get_call_graph(
+        &self,
+        params: CallGraphParams,
+    ) -> Result<CallGraphResponse, JsonRpcError> {
+        Self::validate_params(&params)?;
+
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        // Span reconstruction walks the whole event stream with blocking IO.
+        let source = Arc::clone(&self.source);
+        let spans = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            reconstruct_spans(events.as_ref())
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("span reconstruction task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
+
+        let graph = CallGraph::build(&spans, &params.filters).to_dot(params.min_call_count);
+
+        Ok(CallGraphResponse {
+            dot: graph.dot,
+            node_count: graph.node_count,
+            edge_count: graph.edge_count,
+            pruned_edge_count: graph.pruned_edge_count,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for CallGraphHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: CallGraphParams = CallGraphParams::deserialize(params.unwrap_or(&json!({})))
+            .map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid functions.callGraph params: {err}"))
+            })?;
+
+        let response = self.get_call_graph(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    use super::*;
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+
+    const MAIN: u64 = 0x100;
+    const WORK: u64 = 0x200;
+
+    fn candidate(thread_id: u32, start: u64, end: u64, depth: u32, name: &str) -> SpanCandidate {
+        SpanCandidate {
+            span_id: format!("{thread_id}:{start}"),
+            function_name: Some(name.into()),
+            start_time_ns: start,
+            end_time_ns: end,
+            duration_ns: end - start,
+            self_duration_ns: end - start,
+            thread_id,
+            depth,
+            child_count: 0,
+            merged_count: 1,
+        }
+    }
+
+    /// `main` calls `parse` three times and `log` once.
+    fn spans() -> Vec<SpanCandidate> {
+        vec![
+            candidate(1, 0, 1_000, 0, "main"),
+            candidate(1, 100, 200, 1, "parse"),
+            candidate(1, 300, 400, 1, "parse"),
+            candidate(1, 500, 600, 1, "parse"),
+            candidate(1, 700, 710, 1, "log"),
+        ]
+    }
+
+    #[test]
+    fn call_graph__nested_spans__then_edges_count_calls() {
+        let graph = CallGraph::build(&spans(), &FunctionTimingFilters::default());
+
+        assert_eq!(graph.edges[&("main".into(), "parse".into())], 3);
+        assert_eq!(graph.edges[&("main".into(), "log".into())], 1);
+        assert_eq!(graph.self_ns["parse"], 300);
+        assert_eq!(graph.roots, BTreeSet::from(["main".to_string()]));
+    }
+
+    #[test]
+    fn call_graph__min_call_count__then_light_edges_and_orphans_pruned() {
+        let graph = CallGraph::build(&spans(), &FunctionTimingFilters::default());
+
+        let rendered = graph.to_dot(2);
+        assert_eq!(
+            (
+                rendered.node_count,
+                rendered.edge_count,
+                rendered.pruned_edge_count
+            ),
+            (2, 1, 1)
+        );
+        let dot = rendered.dot;
+        assert!(dot.contains("\"main\" -> \"parse\" [label=\"3\", weight=3];"));
+        assert!(!dot.contains("\"log\""));
+    }
+
+    #[test]
+    fn call_graph__quoted_names__then_escaped() {
+        let graph = CallGraph::build(
+            &[candidate(1, 0, 10, 0, "operator\"\"_x\\")],
+            &FunctionTimingFilters::default(),
+        );
+
+        let dot = graph.to_dot(1).dot;
+        assert!(dot.contains("\"operator\\\"\\\"_x\\\\\" [label="));
+    }
+
+    /// Feeds the DOT through Graphviz when it is installed, so a syntax error
+    /// fails the test instead of the user's renderer.
+    #[test]
+    fn call_graph__dot_output__then_graphviz_round_trips() {
+        let graph = CallGraph::build(&spans(), &FunctionTimingFilters::default());
+        let dot = graph.to_dot(1).dot;
+
+        let child = Command::new("dot")
+            .arg("-Tcanon")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let Ok(mut child) = child else {
+            eprintln!("graphviz `dot` not found; skipping round trip");
+            return;
+        };
+        child
+            .stdin
+            .take()
+            .expect("stdin")
+            .write_all(dot.as_bytes())
+            .expect("write dot");
+        let output = child.wait_with_output().expect("dot output");
+        assert!(
+            output.status.success(),
+            "dot rejected the graph: {}\n{dot}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let canonical = String::from_utf8_lossy(&output.stdout);
+        assert!(canonical.contains("main -> parse"), "{canonical}");
+        assert!(canonical.contains("main -> log"), "{canonical}");
+    }
+
+    #[tokio::test]
+    async fn call_graph_handler__trace__then_dot_with_counts() {
+        let fixture = TraceFixture::new("callgraph");
+        let events = vec![
+            function_call_event(100, 1, MAIN),
+            function_call_event(110, 1, WORK),
+            function_return_event(200, 1, WORK),
+            function_call_event(210, 1, WORK),
+            function_return_event(300, 1, WORK),
+            function_return_event(400, 1, MAIN),
+        ];
+        fixture.write_events(&events);
+        let handler = CallGraphHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({ "traceId": "callgraph" })))
+            .await
+            .expect("call graph");
+
+        assert_eq!(result["nodeCount"], 2);
+        assert_eq!(result["edgeCount"], 1);
+        assert_eq!(result["prunedEdgeCount"], 0);
+        let dot = result["dot"].as_str().expect("dot");
+        assert!(dot.contains("\"0x200\" [label=\"0x200\\nself 180 ns\"];"));
+        assert!(dot.contains("\"0x100\" -> \"0x200\" [label=\"2\", weight=2];"));
+    }
+}
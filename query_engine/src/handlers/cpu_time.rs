@@ -0,0 +1,374 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task;
+
+use crate::{
+    atf::{AtfError, ParsedEventKind},
+    handlers::{
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, EventSource, SourceProvider},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+    },
+};
+
+const DEFAULT_GAP_THRESHOLD_NS: u64 = 1_000_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadsCpuTimeParams {
+    pub trace_id: String,
+    /// Gaps between consecutive events of a thread longer than this count as
+    /// time the thread was blocked rather than running.
+    #[serde(default = "default_gap_threshold_ns")]
+    pub gap_threshold_ns: u64,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+fn default_gap_threshold_ns() -> u64 {
+    DEFAULT_GAP_THRESHOLD_NS
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadsCpuTimeResponse {
+    /// Every thread that made a call, busiest first.
+    pub threads: Vec<ThreadCpuTime>,
+    pub gap_threshold_ns: u64,
+    /// Sum of `cpuTimeNs` over all threads.
+    pub total_cpu_time_ns: u64,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadCpuTime {
+    pub thread_id: u32,
+    /// Time the thread had at least one call open, i.e. the summed duration
+    /// of its outermost spans.
+    pub span_time_ns: u64,
+    /// Part of `spanTimeNs` spent in gaps longer than the threshold.
+    pub idle_time_ns: u64,
+    pub idle_gap_count: u64,
+    /// Estimated time on CPU: `spanTimeNs` minus `idleTimeNs`.
+    pub cpu_time_ns: u64,
+    /// `cpuTimeNs` over `spanTimeNs`; zero when the thread has no span time.
+    pub busy_ratio: f64,
+}
+
+/// Per-thread state while the stream is scanned.
+#[derive(Default)]
+struct ThreadClock {
+    depth: u32,
+    last_timestamp_ns: u64,
+    span_time_ns: u64,
+    idle_time_ns: u64,
+    idle_gap_count: u64,
+}
+
+/// Estimates the CPU time of every thread in `source`.
+///
+/// Traces carry no scheduling events, so blocking is inferred from silence:
+/// while a thread has a call open, each gap between two of its consecutive
+/// events adds to its span time, and a gap longer than `gap_threshold_ns`
+/// counts as idle. The estimate is rough in both directions. Long stretches
+/// of uninstrumented computation look idle, and blocking shorter than the
+/// threshold looks busy. Returns without a matching call are ignored, and
+/// calls still open at the end of the trace stop counting at the thread's
+/// last event.
+pub(crate) fn thread_cpu_times(
+    source: &dyn EventSource,
+    gap_threshold_ns: u64,
+) -> Result<Vec<ThreadCpuTime>, AtfError> {
+    let mut clocks: BTreeMap<u32, ThreadClock> = BTreeMap::new();
+
+    for item in source.events()? {
+        let event = item?;
+        let is_call = match event.kind {
+            ParsedEventKind::FunctionCall { .. } => true,
+            ParsedEventKind::FunctionReturn { .. } => false,
+            _ => continue,
+        };
+        let clock = clocks.entry(event.thread_id).or_default();
+        if clock.depth > 0 {
+            let gap = event.timestamp_ns.saturating_sub(clock.last_timestamp_ns);
+            clock.span_time_ns += gap;
+            if gap > gap_threshold_ns {
+                clock.idle_time_ns += gap;
+                clock.idle_gap_count += 1;
+            }
+        }
+        clock.last_timestamp_ns = event.timestamp_ns;
+        if is_call {
+            clock.depth += 1;
+        } else {
+            clock.depth = clock.depth.saturating_sub(1);
+        }
+    }
+
+    let mut threads: Vec<ThreadCpuTime> = clocks
+        .into_iter()
+        .map(|(thread_id, clock)| {
+            let cpu_time_ns = clock.span_time_ns - clock.idle_time_ns;
+            ThreadCpuTime {
+                thread_id,
+                span_time_ns: clock.span_time_ns,
+                idle_time_ns: clock.idle_time_ns,
+                idle_gap_count: clock.idle_gap_count,
+                cpu_time_ns,
+                busy_ratio: if clock.span_time_ns == 0 {
+                    0.0
+                } else {
+                    cpu_time_ns as f64 / clock.span_time_ns as f64
+                },
+            }
+        })
+        .collect();
+    // Stable, so ties stay in thread id order.
+    threads.sort_by_key(|thread| std::cmp::Reverse(thread.cpu_time_ns));
+    Ok(threads)
+}
+
+#[derive(Clone)]
+pub struct ThreadsCpuTimeHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
+}
+
+impl ThreadsCpuTimeHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("threads.cpuTime", self);
+    }
+
+    fn map_atf_error(err: AtfError) -> JsonRpcError {
+        match err {
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to load trace: {other}")),
+        }
+    }
+
+    pub async fn get_cpu_time(
+        &self,
+        params: ThreadsCpuTimeParams,
+    ) -> Result<ThreadsCpuTimeResponse, JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+        if params.gap_threshold_ns == 0 {
+            return Err(JsonRpcError::invalid_params(
+                "gapThresholdNs must be greater than zero",
+            ));
+        }
+
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        let source = Arc::clone(&self.source);
+        let gap_threshold_ns = params.gap_threshold_ns;
+        let threads = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            thread_cpu_times(events.as_ref(), gap_threshold_ns)
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("cpu time task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
+
+        Ok(ThreadsCpuTimeResponse {
+            total_cpu_time_ns: threads.iter().map(|thread| thread.cpu_time_ns).sum(),
+            threads,
+            gap_threshold_ns,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for ThreadsCpuTimeHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: ThreadsCpuTimeParams =
+            ThreadsCpuTimeParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid threads.cpuTime params: {err}"))
+            })?;
+
+        let response = self.get_cpu_time(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::ParsedEvent;
+    use crate::handlers::source::{MemoryEventSource, MemorySourceProvider};
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+
+    fn call(timestamp_ns: u64, thread_id: u32, symbol: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: ParsedEventKind::FunctionCall {
+                symbol: Some(symbol.into()),
+            },
+        }
+    }
+
+    fn ret(timestamp_ns: u64, thread_id: u32, symbol: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: ParsedEventKind::FunctionReturn {
+                symbol: Some(symbol.into()),
+            },
+        }
+    }
+
+    #[test]
+    fn thread_cpu_times__blocked_thread__then_long_gaps_excluded() {
+        let events = vec![
+            // Thread 1 computes steadily for 400ns
+            call(0, 1, "compute"),
+            call(100, 1, "step"),
+            ret(200, 1, "step"),
+            ret(400, 1, "compute"),
+            // Thread 2 spends most of its 1000ns span waiting in `read`
+            call(0, 2, "serve"),
+            call(50, 2, "read"),
+            ret(950, 2, "read"),
+            ret(1000, 2, "serve"),
+            // Time between outermost spans is not counted
+            call(5000, 2, "serve"),
+            ret(5100, 2, "serve"),
+        ];
+
+        let threads = thread_cpu_times(&MemoryEventSource::new(events), 500).expect("cpu time");
+
+        assert_eq!(
+            threads[0],
+            ThreadCpuTime {
+                thread_id: 1,
+                span_time_ns: 400,
+                idle_time_ns: 0,
+                idle_gap_count: 0,
+                cpu_time_ns: 400,
+                busy_ratio: 1.0,
+            }
+        );
+        assert_eq!(threads[1].thread_id, 2);
+        assert_eq!(threads[1].span_time_ns, 1100);
+        assert_eq!(threads[1].idle_time_ns, 900);
+        assert_eq!(threads[1].idle_gap_count, 1);
+        assert_eq!(threads[1].cpu_time_ns, 200);
+    }
+
+    #[test]
+    fn thread_cpu_times__stray_return_and_open_call__then_counted_to_last_event() {
+        let events = vec![
+            ret(10, 3, "early"),
+            call(20, 3, "open"),
+            call(70, 3, "inner"),
+        ];
+
+        let threads = thread_cpu_times(&MemoryEventSource::new(events), 1_000).expect("cpu time");
+
+        assert_eq!(threads[0].span_time_ns, 50);
+        assert_eq!(threads[0].cpu_time_ns, 50);
+    }
+
+    #[tokio::test]
+    async fn threads_cpu_time__handler__then_totals_and_threshold_validated() {
+        let provider = MemorySourceProvider::new().with_trace(
+            "run",
+            MemoryEventSource::new(vec![
+                call(0, 1, "main"),
+                ret(300, 1, "main"),
+                call(0, 2, "worker"),
+                ret(5_000_000, 2, "worker"),
+            ]),
+        );
+        let handler =
+            ThreadsCpuTimeHandler::new(PathBuf::from("/virtual")).with_source(Arc::new(provider));
+
+        let result = handler
+            .call(Some(json!({ "traceId": "run" })))
+            .await
+            .expect("cpu time");
+        assert_eq!(result["gapThresholdNs"], DEFAULT_GAP_THRESHOLD_NS);
+        assert_eq!(result["totalCpuTimeNs"], 300);
+        assert_eq!(result["threads"][0]["threadId"], 1);
+        assert_eq!(result["threads"][1]["idleTimeNs"], 5_000_000);
+        assert_eq!(result["threads"][1]["busyRatio"], 0.0);
+
+        let err = handler
+            .call(Some(json!({ "traceId": "run", "gapThresholdNs": 0 })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, -32602);
+    }
+
+    #[tokio::test]
+    async fn threads_cpu_time__v2_session__then_threads_clocked_apart() {
+        let fixture = TraceFixture::new("session");
+        fixture.write_events(&[
+            function_call_event(0, 1, 0x100),
+            function_return_event(400, 1, 0x100),
+            function_call_event(100, 2, 0x200),
+            function_call_event(150, 2, 0x300),
+            function_return_event(2_150, 2, 0x300),
+            function_return_event(2_200, 2, 0x200),
+        ]);
+        let handler = ThreadsCpuTimeHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({
+                "traceId": "session",
+                "gapThresholdNs": 1_000,
+            })))
+            .await
+            .expect("cpu time");
+
+        assert_eq!(result["threads"][0]["threadId"], 1);
+        assert_eq!(result["threads"][0]["cpuTimeNs"], 400);
+        assert_eq!(result["threads"][1]["spanTimeNs"], 2_100);
+        assert_eq!(result["threads"][1]["idleTimeNs"], 2_000);
+        assert_eq!(result["totalCpuTimeNs"], 500);
+    }
+}
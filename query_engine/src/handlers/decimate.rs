@@ -0,0 +1,265 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    atf::{
+        v2::{read_manifest_fields, rewrite_threads, ATF_EVENT_KIND_CALL, ATF_EVENT_KIND_RETURN},
+        AtfV2Error, SessionReader,
+    },
+    handlers::export::run_export,
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceDecimateParams {
+    pub trace_id: String,
+    /// Trace id of the decimated copy, created next to the source trace.
+    pub output_trace_id: String,
+    /// Keep one call/return pair in this many, per thread; at least 1.
+    pub factor: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceDecimateResponse {
+    pub trace_id: String,
+    /// Factor recorded in the new manifest, compounded with any factor the
+    /// source trace was already decimated by.
+    pub decimation_factor: u32,
+    pub event_count: u64,
+    pub source_event_count: u64,
+}
+
+/// Copies every `factor`th call of each thread, with its matching return,
+/// into a new session at `output`. A return is kept exactly when the call it
+/// closes was, so the kept spans stay balanced; returns with no call in the
+/// trace and all other events are always kept.
+fn decimate_session(
+    source: &Path,
+    output: &Path,
+    factor: u32,
+) -> Result<TraceDecimateResponse, AtfV2Error> {
+    let session = SessionReader::open(source)?;
+    let decimation_factor = session
+        .manifest()
+        .decimation_factor
+        .unwrap_or(1)
+        .saturating_mul(factor);
+    let mut manifest = read_manifest_fields(source)?;
+    manifest.insert("decimation_factor".to_string(), decimation_factor.into());
+
+    let written = rewrite_threads(session.threads(), output, manifest, |thread, writer| {
+        let mut calls_seen = 0u64;
+        // Whether each call still open was kept, innermost last
+        let mut open_calls = Vec::new();
+        for event in thread.index.iter() {
+            let keep = match event.event_kind {
+                ATF_EVENT_KIND_CALL => {
+                    let keep = calls_seen.is_multiple_of(u64::from(factor));
+                    calls_seen += 1;
+                    open_calls.push(keep);
+                    keep
+                }
+                ATF_EVENT_KIND_RETURN => open_calls.pop().unwrap_or(true),
+                _ => true,
+            };
+            if keep {
+                writer.copy_event(thread, event)?;
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(TraceDecimateResponse {
+        trace_id: String::new(),
+        decimation_factor,
+        event_count: written.iter().map(|thread| thread.event_count).sum(),
+        source_event_count: session.event_count(),
+    })
+}
+
+#[derive(Clone)]
+pub struct TraceDecimateHandler {
+    trace_root_dir: PathBuf,
+}
+
+impl TraceDecimateHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self { trace_root_dir }
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.decimate", self);
+    }
+
+    pub async fn decimate(
+        &self,
+        params: TraceDecimateParams,
+    ) -> Result<TraceDecimateResponse, JsonRpcError> {
+        if params.factor == 0 {
+            return Err(JsonRpcError::invalid_params("factor must be at least 1"));
+        }
+
+        let factor = params.factor;
+        let response = run_export(
+            &self.trace_root_dir,
+            &params.trace_id,
+            &params.output_trace_id,
+            move |source, output| decimate_session(source, output, factor),
+        )
+        .await?;
+
+        Ok(TraceDecimateResponse {
+            trace_id: params.output_trace_id.trim().to_string(),
+            ..response
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TraceDecimateHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TraceDecimateParams = match params {
+            Some(value) => TraceDecimateParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid trace.decimate parameters: {err}"))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing trace.decimate parameters",
+                ))
+            }
+        };
+
+        let response = self.decimate(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::v2::ATF_EVENT_KIND_EXCEPTION;
+    use crate::handlers::{
+        test_support::{exception_event, function_call_event, function_return_event, TraceFixture},
+        TraceInfoHandler,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn kinds_and_functions(session: &SessionReader) -> Vec<(u32, u64)> {
+        session
+            .merged_iter()
+            .map(|(_, event)| (event.event_kind, event.function_id))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn trace_decimate__nested_calls__then_kept_pairs_balanced() {
+        let fixture = TraceFixture::new("full");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0xa),
+            function_call_event(110, 1, 0xb),
+            function_return_event(120, 1, 0xb),
+            function_call_event(130, 1, 0xc),
+            exception_event(135, 1),
+            function_return_event(140, 1, 0xc),
+            function_return_event(150, 1, 0xa),
+            function_call_event(160, 1, 0xd),
+            function_return_event(170, 1, 0xd),
+        ]);
+        let handler = TraceDecimateHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "half",
+                "factor": 2,
+            })))
+            .await
+            .expect("decimate");
+
+        assert_eq!(
+            response,
+            json!({
+                "traceId": "half",
+                "decimationFactor": 2,
+                "eventCount": 5,
+                "sourceEventCount": 9,
+            })
+        );
+        let session =
+            SessionReader::open(&fixture.trace_root().join("half")).expect("open decimated");
+        assert_eq!(
+            kinds_and_functions(&session),
+            vec![
+                (ATF_EVENT_KIND_CALL, 0xa),
+                (ATF_EVENT_KIND_CALL, 0xc),
+                (ATF_EVENT_KIND_EXCEPTION, 0),
+                (ATF_EVENT_KIND_RETURN, 0xc),
+                (ATF_EVENT_KIND_RETURN, 0xa),
+            ]
+        );
+        assert_eq!(session.manifest().decimation_factor, Some(2));
+    }
+
+    #[tokio::test]
+    async fn trace_decimate__already_decimated__then_factors_compound_and_trace_info_reports() {
+        let fixture = TraceFixture::new("full");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0xa),
+            function_return_event(110, 1, 0xa),
+        ]);
+        fixture.edit_manifest(|manifest| manifest["decimation_factor"] = json!(3));
+        let handler = TraceDecimateHandler::new(fixture.trace_root());
+
+        handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "sparse",
+                "factor": 4,
+            })))
+            .await
+            .expect("decimate");
+
+        let info = TraceInfoHandler::new(fixture.trace_root(), 0, Duration::from_secs(60))
+            .call(Some(json!({ "traceId": "sparse" })))
+            .await
+            .expect("trace info");
+        assert_eq!(info["decimationFactor"], 12);
+    }
+
+    #[tokio::test]
+    async fn trace_decimate__zero_factor__then_invalid_params() {
+        let fixture = TraceFixture::new("full");
+        let handler = TraceDecimateHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "none",
+                "factor": 0,
+            })))
+            .await
+            .expect_err("expected error");
+
+        assert_eq!(err.code, -32602);
+        assert!(!fixture.trace_root().join("none").exists());
+    }
+}
@@ -0,0 +1,76 @@
+//! Versioned envelope for handler responses.
+//!
+//! `schemaVersion` and `generatedAt` are added as siblings of the response
+//! fields rather than moving them under a `data` key, so clients that ignore
+//! unknown fields keep working. Bump a method's version whenever the meaning
+//! of one of its existing fields changes; adding a field does not need a bump.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atf::format_wall_clock_ns,
+    server::{handler::JsonRpcResult, types::JsonRpcError},
+};
+
+/// Response schema version of `events.get`.
+pub const EVENTS_GET_SCHEMA_VERSION: u32 = 1;
+
+/// Response schema version of `spans.list`.
+pub const SPANS_LIST_SCHEMA_VERSION: u32 = 1;
+
+/// Response schema version of `trace.info`.
+pub const TRACE_INFO_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseEnvelope<T> {
+    pub schema_version: u32,
+    /// ISO-8601 UTC time the response was produced.
+    pub generated_at: String,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T> ResponseEnvelope<T> {
+    pub fn new(schema_version: u32, data: T) -> Self {
+        let now_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            schema_version,
+            generated_at: format_wall_clock_ns(now_ns),
+            data,
+        }
+    }
+}
+
+/// Serializes `data` wrapped in an envelope of `schema_version`.
+pub(crate) fn envelope_result<T: Serialize>(schema_version: u32, data: T) -> JsonRpcResult {
+    serde_json::to_value(ResponseEnvelope::new(schema_version, data))
+        .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn envelope_result__object__then_version_beside_fields() {
+        let result = envelope_result(3, json!({ "traceId": "t", "count": 2 })).expect("envelope");
+
+        assert_eq!(result["schemaVersion"], 3);
+        assert_eq!(result["traceId"], "t");
+        assert_eq!(result["count"], 2);
+        assert!(result["generatedAt"]
+            .as_str()
+            .is_some_and(|at| at.ends_with('Z')));
+        assert!(result.get("data").is_none());
+    }
+}
@@ -1,11 +1,25 @@
-use std::{path::PathBuf, time::Instant};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+};
 
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use tokio::task;
 
 use crate::{
-    atf::{AtfError, AtfReader, ParsedEvent, ParsedEventKind},
+    atf::{AtfError, ParsedEvent, ParsedEventKind},
+    handlers::{
+        envelope::{envelope_result, EVENTS_GET_SCHEMA_VERSION},
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, SourceProvider},
+        wall_clock::WallClockAnchor,
+    },
     server::{
         handler::{JsonRpcHandler, JsonRpcResult},
         types::JsonRpcError,
@@ -14,6 +28,7 @@ use crate::{
 
 const DEFAULT_LIMIT: u64 = 1000;
 const MAX_LIMIT: u64 = 10_000;
+const MAX_CONTEXT: u64 = 100;
 
 fn default_limit() -> u64 {
     DEFAULT_LIMIT
@@ -23,13 +38,16 @@ fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EventsGetParams {
     #[serde(rename = "traceId")]
     pub trace_id: String,
     #[serde(default)]
     pub filters: EventFilters,
+    /// Nested and/or/not groups, ANDed with `filters`.
+    #[serde(default)]
+    pub filter_expr: Option<FilterExpr>,
     #[serde(default)]
     pub projection: EventProjection,
     #[serde(default)]
@@ -40,9 +58,57 @@ pub struct EventsGetParams {
     pub order_by: EventOrderBy,
     #[serde(default = "default_true")]
     pub ascending: bool,
+    /// Returns the page as `eventsByThread` instead of `events`; offset and
+    /// limit still apply to the whole ordered result, not to each thread.
+    #[serde(default)]
+    pub group_by_thread: bool,
+    /// Adds each event's ISO-8601 wall-clock time as `wallClock`, null when
+    /// the trace recorded no wall-clock anchor.
+    #[serde(default)]
+    pub include_wall_clock: bool,
+    /// Adds up to this many events before and after each event in the page,
+    /// like `grep -C`, whether or not they match the filters.
+    #[serde(default)]
+    pub context: u64,
+    #[serde(default)]
+    pub context_scope: ContextScope,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+/// Which events count as neighbours for `context`
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ContextScope {
+    /// Neighbours on the event's own thread.
+    #[default]
+    Thread,
+    /// Neighbours across all threads.
+    Global,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsCountParams {
+    pub trace_id: String,
+    #[serde(default)]
+    pub filters: EventFilters,
+    /// Nested and/or/not groups, ANDed with `filters`.
+    #[serde(default)]
+    pub filter_expr: Option<FilterExpr>,
+    /// Also report counts keyed by event type.
+    #[serde(default)]
+    pub by_type: bool,
+    /// Also report counts keyed by thread id.
+    #[serde(default)]
+    pub by_thread: bool,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EventFilters {
     #[serde(rename = "timeStartNs")]
@@ -57,45 +123,193 @@ pub struct EventFilters {
     pub function_names: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
+/// A boolean combination of filters, e.g.
+/// `{"and": [{"or": [{"threadIds": [1]}, {"threadIds": [2]}]}, {"functionNames": ["f"]}]}`.
+///
+/// A group is an object with a single `and`, `or` or `not` key. Any other
+/// object is a leaf whose fields are those of `filters`, ANDed the same way.
+/// An empty `and` matches every event and an empty `or` matches none.
+#[derive(Debug, Clone)]
+pub enum FilterExpr {
+    And(Vec<FilterExpr>),
+    Or(Vec<FilterExpr>),
+    Not(Box<FilterExpr>),
+    Match(EventFilters),
+}
+
+const FILTER_FIELDS: [&str; 5] = [
+    "timeStartNs",
+    "timeEndNs",
+    "threadIds",
+    "eventTypes",
+    "functionNames",
+];
+
+impl<'de> Deserialize<'de> for FilterExpr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        enum Group {
+            And(Vec<FilterExpr>),
+            Or(Vec<FilterExpr>),
+            Not(Box<FilterExpr>),
+        }
+
+        // Leaves are checked by hand: `EventFilters` tolerates unknown
+        // fields, and a misspelled one would otherwise match every event
+        let value = Value::deserialize(deserializer)?;
+        let Some(map) = value.as_object() else {
+            return Err(D::Error::custom("filterExpr entries must be objects"));
+        };
+        let is_group = map.len() == 1
+            && ["and", "or", "not"]
+                .iter()
+                .any(|key| map.contains_key(*key));
+        if is_group {
+            return Ok(match Group::deserialize(value).map_err(D::Error::custom)? {
+                Group::And(exprs) => FilterExpr::And(exprs),
+                Group::Or(exprs) => FilterExpr::Or(exprs),
+                Group::Not(expr) => FilterExpr::Not(expr),
+            });
+        }
+        if let Some(key) = map
+            .keys()
+            .find(|key| !FILTER_FIELDS.contains(&key.as_str()))
+        {
+            return Err(D::Error::custom(format!("unknown filter field `{key}`")));
+        }
+        EventFilters::deserialize(value)
+            .map(FilterExpr::Match)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Mirrors the hand-written `Deserialize`: a single-key group or a leaf.
+impl JsonSchema for FilterExpr {
+    fn schema_name() -> String {
+        "FilterExpr".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let expr =
+            serde_json::to_value(gen.subschema_for::<FilterExpr>()).expect("schema serializes");
+        let filters =
+            serde_json::to_value(gen.subschema_for::<EventFilters>()).expect("schema serializes");
+        let group = |key: &str, schema: Value| {
+            json!({
+                "type": "object",
+                "properties": { key: schema },
+                "required": [key],
+                "additionalProperties": false,
+            })
+        };
+        let schema = json!({
+            "anyOf": [
+                group("and", json!({ "type": "array", "items": expr })),
+                group("or", json!({ "type": "array", "items": expr })),
+                group("not", expr),
+                filters,
+            ]
+        });
+        serde_json::from_value(schema).expect("valid schema")
+    }
+}
+
+impl FilterExpr {
+    fn matches(&self, event: &ParsedEvent) -> bool {
+        match self {
+            FilterExpr::And(exprs) => exprs.iter().all(|expr| expr.matches(event)),
+            FilterExpr::Or(exprs) => exprs.iter().any(|expr| expr.matches(event)),
+            FilterExpr::Not(expr) => !expr.matches(event),
+            FilterExpr::Match(filters) => filters.matches(event),
+        }
+    }
+
+    fn validate(&self) -> Result<(), JsonRpcError> {
+        match self {
+            FilterExpr::And(exprs) | FilterExpr::Or(exprs) => {
+                exprs.iter().try_for_each(FilterExpr::validate)
+            }
+            FilterExpr::Not(expr) => expr.validate(),
+            FilterExpr::Match(filters) => validate_filters(filters),
+        }
+    }
+}
+
+impl EventFilters {
+    fn matches(&self, event: &ParsedEvent) -> bool {
+        if let Some(start) = self.time_start_ns {
+            if event.timestamp_ns < start {
+                return false;
+            }
+        }
+        if let Some(end) = self.time_end_ns {
+            if event.timestamp_ns > end {
+                return false;
+            }
+        }
+        if let Some(thread_ids) = self.thread_ids.as_ref() {
+            if !thread_ids.contains(&event.thread_id) {
+                return false;
+            }
+        }
+        if let Some(event_types) = self.event_types.as_ref() {
+            let kind = &event.kind;
+            if !event_types.iter().any(|filter| filter.matches(kind)) {
+                return false;
+            }
+        }
+        if let Some(names) = self.function_names.as_ref() {
+            match event.kind.function_symbol() {
+                Some(symbol) => {
+                    if !names.iter().any(|candidate| candidate == symbol) {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum EventTypeFilter {
-    TraceStart,
-    TraceEnd,
     FunctionCall,
     FunctionReturn,
-    SignalDelivery,
     Unknown,
 }
 
 impl EventTypeFilter {
     fn matches(&self, kind: &ParsedEventKind) -> bool {
-        match (self, kind) {
-            (EventTypeFilter::TraceStart, ParsedEventKind::TraceStart) => true,
-            (EventTypeFilter::TraceEnd, ParsedEventKind::TraceEnd) => true,
-            (EventTypeFilter::FunctionCall, ParsedEventKind::FunctionCall { .. }) => true,
-            (EventTypeFilter::FunctionReturn, ParsedEventKind::FunctionReturn { .. }) => true,
-            (EventTypeFilter::SignalDelivery, ParsedEventKind::SignalDelivery { .. }) => true,
-            (EventTypeFilter::Unknown, ParsedEventKind::Unknown) => true,
-            _ => false,
-        }
+        matches!(
+            (self, kind),
+            (
+                EventTypeFilter::FunctionCall,
+                ParsedEventKind::FunctionCall { .. }
+            ) | (
+                EventTypeFilter::FunctionReturn,
+                ParsedEventKind::FunctionReturn { .. }
+            ) | (EventTypeFilter::Unknown, ParsedEventKind::Unknown)
+        )
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum EventOrderBy {
+    #[default]
     Timestamp,
     ThreadId,
 }
 
-impl Default for EventOrderBy {
-    fn default() -> Self {
-        EventOrderBy::Timestamp
-    }
-}
-
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct EventProjection {
     #[serde(rename = "timestampNs", default = "default_true")]
@@ -119,14 +333,18 @@ impl Default for EventProjection {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EventsGetResponse {
+    /// The page in order; empty when grouped by thread.
     pub events: Vec<EventResult>,
+    /// The page keyed by thread id, each group in the requested order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events_by_thread: Option<BTreeMap<u32, Vec<EventResult>>>,
     pub metadata: QueryMetadata,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryMetadata {
     pub total_count: u64,
@@ -134,10 +352,25 @@ pub struct QueryMetadata {
     pub offset: u64,
     pub limit: u64,
     pub has_more: bool,
+    /// Context events added around the page; `returnedCount` counts only
+    /// the page itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_count: Option<u64>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsCountResponse {
+    pub total_count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_type: Option<BTreeMap<String, u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_thread: Option<BTreeMap<u32, u64>>,
     pub execution_time_ms: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EventResult {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -148,16 +381,49 @@ pub struct EventResult {
     pub event_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function_name: Option<String>,
+    /// Present when requested; the inner value is null without an anchor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wall_clock: Option<Option<String>>,
+    /// Present when context was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<EventRole>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum EventRole {
+    /// The event is in the requested page.
+    Match,
+    /// The event only neighbours one that is.
+    Context,
 }
 
 #[derive(Clone)]
 pub struct EventsGetHandler {
     trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
 }
 
 impl EventsGetHandler {
     pub fn new(trace_root_dir: PathBuf) -> Self {
-        Self { trace_root_dir }
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
     }
 
     pub fn register(self, server: &crate::server::JsonRpcServer) {
@@ -167,68 +433,44 @@ impl EventsGetHandler {
     }
 
     fn validate_params(&self, params: &EventsGetParams) -> Result<(), JsonRpcError> {
-        if params.trace_id.trim().is_empty() {
-            return Err(JsonRpcError::invalid_params("traceId must not be empty"));
-        }
+        validate_trace_id(&params.trace_id)?;
         if params.limit > MAX_LIMIT {
             return Err(JsonRpcError::invalid_params("limit cannot exceed 10000"));
         }
-        if let (Some(start), Some(end)) = (params.filters.time_start_ns, params.filters.time_end_ns)
-        {
-            if start >= end {
-                return Err(JsonRpcError::invalid_params(
-                    "timeStartNs must be less than timeEndNs",
-                ));
-            }
+        if params.context > MAX_CONTEXT {
+            return Err(JsonRpcError::invalid_params("context cannot exceed 100"));
+        }
+        validate_filters(&params.filters)?;
+        if let Some(expr) = params.filter_expr.as_ref() {
+            expr.validate()?;
         }
         Ok(())
     }
 
     fn map_atf_error(err: AtfError) -> JsonRpcError {
         match err {
-            AtfError::TraceNotFound(_)
-            | AtfError::ManifestNotFound(_)
-            | AtfError::EventsNotFound(_) => JsonRpcError::trace_not_found(),
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
             other => JsonRpcError::internal(format!("failed to load trace: {other}")),
         }
     }
 
-    fn event_matches_filters(&self, event: &ParsedEvent, filters: &EventFilters) -> bool {
-        if let Some(start) = filters.time_start_ns {
-            if event.timestamp_ns < start {
-                return false;
-            }
-        }
-        if let Some(end) = filters.time_end_ns {
-            if event.timestamp_ns > end {
-                return false;
-            }
-        }
-        if let Some(thread_ids) = filters.thread_ids.as_ref() {
-            if !thread_ids.contains(&event.thread_id) {
-                return false;
-            }
-        }
-        if let Some(event_types) = filters.event_types.as_ref() {
-            let kind = &event.kind;
-            if !event_types.iter().any(|filter| filter.matches(kind)) {
-                return false;
-            }
-        }
-        if let Some(names) = filters.function_names.as_ref() {
-            match event.kind.function_symbol() {
-                Some(symbol) => {
-                    if !names.iter().any(|candidate| candidate == symbol) {
-                        return false;
-                    }
-                }
-                None => return false,
-            }
-        }
-        true
+    fn event_matches_filters(
+        &self,
+        event: &ParsedEvent,
+        filters: &EventFilters,
+        filter_expr: Option<&FilterExpr>,
+    ) -> bool {
+        filters.matches(event) && filter_expr.is_none_or(|expr| expr.matches(event))
     }
 
-    fn project_event(&self, event: &ParsedEvent, projection: &EventProjection) -> EventResult {
+    fn project_event(
+        &self,
+        event: &ParsedEvent,
+        projection: &EventProjection,
+        wall_clock: Option<Option<WallClockAnchor>>,
+    ) -> EventResult {
         let timestamp_ns = if projection.timestamp_ns {
             Some(event.timestamp_ns)
         } else {
@@ -250,43 +492,70 @@ impl EventsGetHandler {
             None
         };
 
+        let wall_clock =
+            wall_clock.map(|anchor| anchor.and_then(|anchor| anchor.iso8601(event.timestamp_ns)));
+
         EventResult {
             timestamp_ns,
             thread_id,
             event_type,
             function_name,
+            wall_clock,
+            role: None,
         }
     }
-}
-
-#[async_trait]
-impl JsonRpcHandler for EventsGetHandler {
-    async fn call(&self, params: Option<Value>) -> JsonRpcResult {
-        let params_value = params.unwrap_or_else(|| json!({}));
-        let params: EventsGetParams =
-            serde_json::from_value(params_value.clone()).map_err(|err| {
-                JsonRpcError::invalid_params(format!("invalid events.get params: {err}"))
-            })?;
 
+    pub async fn get_events(
+        &self,
+        params: EventsGetParams,
+    ) -> Result<EventsGetResponse, JsonRpcError> {
         self.validate_params(&params)?;
 
-        let trace_dir = self.trace_root_dir.join(params.trace_id.trim());
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
         let start_time = Instant::now();
 
-        let reader = AtfReader::open(&trace_dir).map_err(Self::map_atf_error)?;
-        let mut stream = reader.event_stream().map_err(Self::map_atf_error)?;
-
-        let mut matched_events = Vec::new();
-        while let Some(item) = stream.next() {
-            let event = item.map_err(Self::map_atf_error)?;
-            if self.event_matches_filters(&event, &params.filters) {
-                matched_events.push(event);
+        // Decoding is blocking file IO; keep it off the async worker threads so
+        // one large trace cannot stall every other request.
+        let handler = self.clone();
+        let filters = params.filters.clone();
+        let filter_expr = params.filter_expr.clone();
+        let include_wall_clock = params.include_wall_clock;
+        let context = params.context;
+        let (mut matched_events, timeline, wall_clock) = task::spawn_blocking(move || {
+            let wall_clock =
+                include_wall_clock.then(|| WallClockAnchor::read(&trace_dir.join("manifest.json")));
+            let source = handler.source.open(&trace_dir)?;
+            let mut matched = Vec::new();
+            // Context reaches events the filters reject, so every event is kept
+            let mut timeline = (context > 0).then(Vec::new);
+            for (position, item) in source.events()?.enumerate() {
+                let event = item?;
+                let is_match =
+                    handler.event_matches_filters(&event, &filters, filter_expr.as_ref());
+                match timeline.as_mut() {
+                    Some(timeline) => {
+                        if is_match {
+                            matched.push((position, event.clone()));
+                        }
+                        timeline.push(event);
+                    }
+                    None if is_match => matched.push((position, event)),
+                    None => {}
+                }
             }
-        }
+            Ok::<_, AtfError>((matched, timeline, wall_clock))
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("event scan task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
 
-        matched_events.sort_by(|a, b| match params.order_by {
-            EventOrderBy::Timestamp => a.timestamp_ns.cmp(&b.timestamp_ns),
-            EventOrderBy::ThreadId => a.thread_id.cmp(&b.thread_id),
+        matched_events.sort_by(|(a_pos, a), (b_pos, b)| {
+            compare_events(params.order_by, (*a_pos, a), (*b_pos, b))
         });
 
         if !params.ascending {
@@ -303,125 +572,291 @@ impl JsonRpcHandler for EventsGetHandler {
         let end_index = start_index.saturating_add(limit).min(matched_events.len());
         let slice = &matched_events[start_index..end_index];
 
-        let events: Vec<EventResult> = slice
+        // With context, the page and its neighbours are listed together in the
+        // requested order, each marked with its role
+        let page: Vec<(&ParsedEvent, Option<EventRole>)> = match timeline.as_ref() {
+            Some(timeline) => {
+                let context = params.context as usize;
+                let mut entries: Vec<(usize, EventRole)> =
+                    expand_context(timeline, slice, context, params.context_scope)
+                        .into_iter()
+                        .collect();
+                entries.sort_by(|(a_pos, _), (b_pos, _)| {
+                    compare_events(
+                        params.order_by,
+                        (*a_pos, &timeline[*a_pos]),
+                        (*b_pos, &timeline[*b_pos]),
+                    )
+                });
+                if !params.ascending {
+                    entries.reverse();
+                }
+                entries
+                    .into_iter()
+                    .map(|(position, role)| (&timeline[position], Some(role)))
+                    .collect()
+            }
+            None => slice.iter().map(|(_, event)| (event, None)).collect(),
+        };
+
+        let events: Vec<EventResult> = page
             .iter()
-            .map(|event| self.project_event(event, &params.projection))
+            .map(|(event, role)| EventResult {
+                role: *role,
+                ..self.project_event(event, &params.projection, wall_clock)
+            })
             .collect();
+        let returned_count = slice.len() as u64;
+        let context_count = timeline
+            .is_some()
+            .then(|| (page.len() - slice.len()) as u64);
+
+        // Grouping keeps each event's place in the page, so every group stays
+        // in the requested order; the projection may omit threadId
+        let (events, events_by_thread) = if params.group_by_thread {
+            let mut groups: BTreeMap<u32, Vec<EventResult>> = BTreeMap::new();
+            for ((event, _), result) in page.iter().zip(events) {
+                groups.entry(event.thread_id).or_default().push(result);
+            }
+            (Vec::new(), Some(groups))
+        } else {
+            (events, None)
+        };
 
-        let has_more = total_count > params.offset + events.len() as u64;
+        // Items remain only if the page stopped short of the end; an offset at or
+        // past the end yields an empty page with nothing more to fetch.
+        let has_more = end_index < matched_events.len();
         let metadata = QueryMetadata {
             total_count,
-            returned_count: events.len() as u64,
+            returned_count,
             offset: params.offset,
             limit: params.limit,
             has_more,
+            context_count,
             execution_time_ms: start_time.elapsed().as_millis() as u64,
         };
 
-        let response = EventsGetResponse { events, metadata };
-
-        serde_json::to_value(response)
-            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+        Ok(EventsGetResponse {
+            events,
+            events_by_thread,
+            metadata,
+        })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    #![allow(non_snake_case)]
-
-    use super::*;
-    use std::{fs::File, io::Write, path::PathBuf};
-    use prost::Message;
-    use serde_json::json;
-    use tempfile::TempDir;
-    use crate::atf::event::{event::Payload, Event, FunctionCall};
+/// Total order for paging: ties on the requested key fall back to the other
+/// key and finally to the event's position in the stream, so equal requests
+/// always page through identical sequences.
+fn compare_events(
+    order_by: EventOrderBy,
+    (a_pos, a): (usize, &ParsedEvent),
+    (b_pos, b): (usize, &ParsedEvent),
+) -> Ordering {
+    let primary = match order_by {
+        EventOrderBy::Timestamp => a
+            .timestamp_ns
+            .cmp(&b.timestamp_ns)
+            .then_with(|| a.thread_id.cmp(&b.thread_id)),
+        EventOrderBy::ThreadId => a
+            .thread_id
+            .cmp(&b.thread_id)
+            .then_with(|| a.timestamp_ns.cmp(&b.timestamp_ns)),
+    };
+    primary.then_with(|| a_pos.cmp(&b_pos))
+}
 
-    fn timestamp(ts: u64) -> prost_types::Timestamp {
-        prost_types::Timestamp {
-            seconds: (ts / 1_000_000_000) as i64,
-            nanos: (ts % 1_000_000_000) as i32,
+/// Stream positions of the `page` events and of up to `context` events on
+/// either side of each, neighbours being taken in timestamp order within
+/// `scope`. Overlapping windows are merged, and a page event stays a match
+/// when it also falls in another event's window.
+///
+/// `timeline` holds every event of the trace, indexed by stream position.
+fn expand_context(
+    timeline: &[ParsedEvent],
+    page: &[(usize, ParsedEvent)],
+    context: usize,
+    scope: ContextScope,
+) -> BTreeMap<usize, EventRole> {
+    let lane_of = |event: &ParsedEvent| (scope == ContextScope::Thread).then_some(event.thread_id);
+    let mut lanes: HashMap<Option<u32>, Vec<usize>> = HashMap::new();
+    for (position, event) in timeline.iter().enumerate() {
+        lanes.entry(lane_of(event)).or_default().push(position);
+    }
+    let mut rank = vec![0; timeline.len()];
+    for lane in lanes.values_mut() {
+        lane.sort_by(|&a, &b| {
+            compare_events(
+                EventOrderBy::Timestamp,
+                (a, &timeline[a]),
+                (b, &timeline[b]),
+            )
+        });
+        for (index, &position) in lane.iter().enumerate() {
+            rank[position] = index;
         }
     }
 
-    fn function_call_event(timestamp_ns: u64, thread_id: i32, symbol: &str) -> Event {
-        Event {
-            event_id: timestamp_ns,
-            thread_id,
-            timestamp: Some(timestamp(timestamp_ns)),
-            payload: Some(Payload::FunctionCall(FunctionCall {
-                symbol: symbol.to_string(),
-                address: 0,
-                argument_registers: Default::default(),
-                stack_shallow_copy: Vec::new(),
-            })),
+    let mut roles = BTreeMap::new();
+    for (position, event) in page {
+        let lane = &lanes[&lane_of(event)];
+        let index = rank[*position];
+        let window = index.saturating_sub(context)..(index + context + 1).min(lane.len());
+        for &neighbour in &lane[window] {
+            roles.entry(neighbour).or_insert(EventRole::Context);
         }
     }
-
-    struct TraceFixture {
-        root: TempDir,
-        trace_id: String,
+    for (position, _) in page {
+        roles.insert(*position, EventRole::Match);
     }
+    roles
+}
 
-    impl TraceFixture {
-        fn new(trace_id: impl Into<String>) -> Self {
-            let root = TempDir::new().expect("tempdir");
-            let trace_id = trace_id.into();
-            std::fs::create_dir_all(root.path().join(&trace_id)).expect("trace dir");
-            Self { root, trace_id }
+fn validate_filters(filters: &EventFilters) -> Result<(), JsonRpcError> {
+    if let (Some(start), Some(end)) = (filters.time_start_ns, filters.time_end_ns) {
+        if start >= end {
+            return Err(JsonRpcError::invalid_params(
+                "timeStartNs must be less than timeEndNs",
+            ));
         }
+    }
+    Ok(())
+}
 
-        fn trace_root(&self) -> PathBuf {
-            self.root.path().to_path_buf()
-        }
+/// Serves `events.count`: the `events.get` filters without collecting,
+/// sorting or projecting any events.
+#[derive(Clone)]
+pub struct EventsCountHandler {
+    events: EventsGetHandler,
+}
 
-        fn trace_dir(&self) -> PathBuf {
-            self.root.path().join(&self.trace_id)
+impl EventsCountHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            events: EventsGetHandler::new(trace_root_dir),
         }
+    }
 
-        fn manifest_path(&self) -> PathBuf {
-            self.trace_dir().join("trace.json")
-        }
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.events = self.events.with_allowed_roots(allowed_roots);
+        self
+    }
 
-        fn events_path(&self) -> PathBuf {
-            self.trace_dir().join("events.bin")
-        }
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.events = self.events.with_source(source);
+        self
+    }
 
-        fn write_manifest(&self, event_count: u64) {
-            let manifest = json!({
-                "os": "linux",
-                "arch": "x86_64",
-                "pid": 1,
-                "sessionId": 1,
-                "timeStartNs": 100,
-                "timeEndNs": 10_000,
-                "eventCount": event_count,
-                "bytesWritten": 1024,
-            });
-            std::fs::write(
-                self.manifest_path(),
-                serde_json::to_vec_pretty(&manifest).expect("serialize"),
-            )
-            .expect("write manifest");
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("events.count", self);
+    }
+
+    pub async fn count_events(
+        &self,
+        params: EventsCountParams,
+    ) -> Result<EventsCountResponse, JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+        validate_filters(&params.filters)?;
+        if let Some(expr) = params.filter_expr.as_ref() {
+            expr.validate()?;
         }
 
-        fn write_events(&self, events: &[Event]) {
-            let mut file = File::create(self.events_path()).expect("events file");
-            for event in events {
-                let mut buffer = Vec::new();
-                event
-                    .encode_length_delimited(&mut buffer)
-                    .expect("encode event");
-                file.write_all(&buffer).expect("write event");
+        let trace_root = resolve_trace_root(
+            &self.events.trace_root_dir,
+            &self.events.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        let handler = self.events.clone();
+        let mut response = task::spawn_blocking(move || {
+            let source = handler.source.open(&trace_dir)?;
+            let mut response = EventsCountResponse {
+                by_type: params.by_type.then(BTreeMap::new),
+                by_thread: params.by_thread.then(BTreeMap::new),
+                ..Default::default()
+            };
+            for item in source.events()? {
+                let event = item?;
+                if !handler.event_matches_filters(
+                    &event,
+                    &params.filters,
+                    params.filter_expr.as_ref(),
+                ) {
+                    continue;
+                }
+                response.total_count += 1;
+                if let Some(by_type) = response.by_type.as_mut() {
+                    match by_type.get_mut(event.kind.as_str()) {
+                        Some(count) => *count += 1,
+                        None => {
+                            by_type.insert(event.kind.as_str().to_string(), 1);
+                        }
+                    }
+                }
+                if let Some(by_thread) = response.by_thread.as_mut() {
+                    *by_thread.entry(event.thread_id).or_default() += 1;
+                }
             }
-            file.flush().expect("flush events");
-        }
+            Ok::<_, AtfError>(response)
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("event count task failed: {err}")))?
+        .map_err(EventsGetHandler::map_atf_error)?;
+
+        response.execution_time_ms = start_time.elapsed().as_millis() as u64;
+        Ok(response)
     }
+}
+
+#[async_trait]
+impl JsonRpcHandler for EventsCountHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: EventsCountParams =
+            EventsCountParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid events.count params: {err}"))
+            })?;
+
+        let response = self.count_events(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for EventsGetHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: EventsGetParams = EventsGetParams::deserialize(params.unwrap_or(&json!({})))
+            .map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid events.get params: {err}"))
+            })?;
+
+        let response = self.get_events(params).await?;
+
+        envelope_result(EVENTS_GET_SCHEMA_VERSION, response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::IndexEvent;
+    use crate::handlers::envelope::ResponseEnvelope;
+    use crate::handlers::test_support::{function_call_event, TraceFixture};
+    use serde_json::json;
 
     #[test]
     fn event_type_filter_matches_variants() {
         assert!(
             EventTypeFilter::FunctionCall.matches(&ParsedEventKind::FunctionCall {
-                symbol: Some("foo".into()),
+                symbol: Some("0xf00".into()),
             })
         );
         assert!(!EventTypeFilter::FunctionReturn
@@ -432,10 +867,7 @@ mod tests {
     async fn events_handler__limit_exceeds_max__then_returns_error() {
         // Test limit validation error
         let fixture = TraceFixture::new("trace_limit_error");
-        fixture.write_manifest(1);
-
-        let events = vec![function_call_event(100, 1, "test")];
-        fixture.write_events(&events);
+        fixture.write_events(&[function_call_event(100, 1, 0x1)]);
 
         let handler = EventsGetHandler::new(fixture.trace_root());
 
@@ -454,13 +886,10 @@ mod tests {
     async fn events_handler__basic_functionality__then_returns_events() {
         // Test basic handler functionality to ensure good path coverage
         let fixture = TraceFixture::new("trace_basic");
-        fixture.write_manifest(2);
-
-        let events = vec![
-            function_call_event(100, 1, "test_func_1"),
-            function_call_event(200, 1, "test_func_2"),
-        ];
-        fixture.write_events(&events);
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x1),
+            function_call_event(200, 1, 0x2),
+        ]);
 
         let handler = EventsGetHandler::new(fixture.trace_root());
 
@@ -471,7 +900,374 @@ mod tests {
         });
 
         let result = handler.call(Some(params)).await.expect("should succeed");
-        assert!(result.get("events").is_some());
-        assert!(result.get("metadata").is_some());
+        let envelope: ResponseEnvelope<EventsGetResponse> =
+            serde_json::from_value(result).expect("enveloped response");
+        assert_eq!(envelope.schema_version, EVENTS_GET_SCHEMA_VERSION);
+        assert!(!envelope.generated_at.is_empty());
+        assert_eq!(envelope.data.events.len(), 2);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn events_handler__concurrent_requests_on_single_thread__then_all_complete() {
+        let fixture = TraceFixture::new("trace_concurrent");
+        let events: Vec<IndexEvent> = (0..2_000)
+            .map(|i| function_call_event(100 + i, 1, 0x1))
+            .collect();
+        fixture.write_events(&events);
+
+        let handler = EventsGetHandler::new(fixture.trace_root());
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..8 {
+            let handler = handler.clone();
+            tasks.spawn(async move {
+                handler
+                    .call(Some(json!({ "traceId": "trace_concurrent", "limit": 1 })))
+                    .await
+            });
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            let value = result.expect("task").expect("events");
+            assert_eq!(value["metadata"]["totalCount"], 2_000);
+        }
+    }
+
+    #[tokio::test]
+    async fn events_handler__trace_root_override__then_reads_allowed_root_only() {
+        let default = TraceFixture::new("trace_default");
+        let other = TraceFixture::new("trace_other");
+        other.write_events(&[function_call_event(100, 1, 0x1)]);
+
+        let handler = EventsGetHandler::new(default.trace_root());
+        let params = json!({
+            "traceId": "trace_other",
+            "traceRoot": other.trace_root(),
+        });
+        let err = handler
+            .call(Some(params.clone()))
+            .await
+            .expect_err("root not allowed");
+        assert_eq!(err.code, -32602);
+
+        let handler = handler.with_allowed_roots(vec![other.trace_root()]);
+        let result = handler.call(Some(params)).await.expect("allowed root");
+        assert_eq!(result["metadata"]["totalCount"], 1);
+    }
+
+    #[tokio::test]
+    async fn events_count__filters_and_breakdowns__then_counts_without_events() {
+        let fixture = TraceFixture::new("trace_count");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0xa),
+            function_call_event(200, 2, 0xb),
+            function_call_event(300, 2, 0xa),
+            function_call_event(400, 3, 0xa),
+        ]);
+
+        let handler = EventsCountHandler::new(fixture.trace_root());
+        let result = handler
+            .call(Some(json!({
+                "traceId": "trace_count",
+                "filters": { "functionNames": ["0xa"], "timeEndNs": 350 },
+                "byType": true,
+                "byThread": true,
+            })))
+            .await
+            .expect("count");
+
+        assert_eq!(result["totalCount"], 2);
+        assert_eq!(result["byType"], json!({ "FunctionCall": 2 }));
+        assert_eq!(result["byThread"], json!({ "1": 1, "2": 1 }));
+        assert!(result.get("events").is_none());
+
+        let plain = handler
+            .call(Some(json!({ "traceId": "trace_count" })))
+            .await
+            .expect("count");
+        assert_eq!(plain["totalCount"], 4);
+        assert!(plain.get("byType").is_none());
+        assert!(plain.get("byThread").is_none());
+    }
+
+    #[tokio::test]
+    async fn events_handler__filter_expr__then_groups_anded_with_filters() {
+        let fixture = TraceFixture::new("trace_expr");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0xa),
+            function_call_event(200, 1, 0xb),
+            function_call_event(300, 2, 0xa),
+            function_call_event(400, 3, 0xa),
+            function_call_event(500, 2, 0xa),
+        ]);
+        let handler = EventsGetHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({
+                "traceId": "trace_expr",
+                "filters": { "timeEndNs": 450 },
+                "filterExpr": { "and": [
+                    { "or": [{ "threadIds": [1] }, { "threadIds": [2] }] },
+                    { "not": { "functionNames": ["0xb"] } },
+                ]},
+            })))
+            .await
+            .expect("events");
+        let timestamps: Vec<u64> = result["events"]
+            .as_array()
+            .expect("events")
+            .iter()
+            .map(|event| event["timestampNs"].as_u64().expect("timestamp"))
+            .collect();
+        assert_eq!(timestamps, vec![100, 300]);
+
+        let count = EventsCountHandler::new(fixture.trace_root())
+            .call(Some(json!({
+                "traceId": "trace_expr",
+                "filterExpr": { "or": [] },
+            })))
+            .await
+            .expect("count");
+        assert_eq!(count["totalCount"], 0);
+    }
+
+    #[tokio::test]
+    async fn events_handler__filter_expr_unknown_field__then_invalid_params() {
+        let fixture = TraceFixture::new("trace_expr_invalid");
+        let handler = EventsGetHandler::new(fixture.trace_root());
+
+        for expr in [
+            json!({ "or": [{ "thredIds": [1] }] }),
+            json!({ "not": { "timeStartNs": 500, "timeEndNs": 100 } }),
+        ] {
+            let err = handler
+                .call(Some(
+                    json!({ "traceId": "trace_expr_invalid", "filterExpr": expr }),
+                ))
+                .await
+                .expect_err("expected error");
+            assert_eq!(err.code, -32602, "{expr}");
+        }
+    }
+
+    #[tokio::test]
+    async fn events_handler__group_by_thread__then_global_page_split_by_thread() {
+        let fixture = TraceFixture::new("trace_grouped");
+        fixture.write_events(&[
+            function_call_event(100, 2, 0xa),
+            function_call_event(200, 1, 0xb),
+            function_call_event(300, 2, 0xc),
+            function_call_event(400, 1, 0xd),
+            function_call_event(500, 3, 0xe),
+        ]);
+        let handler = EventsGetHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({
+                "traceId": "trace_grouped",
+                "groupByThread": true,
+                "ascending": false,
+                "offset": 1,
+                "limit": 3,
+            })))
+            .await
+            .expect("events");
+
+        // The page is 400, 300, 200 before grouping; thread 3 falls outside it
+        let timestamps = |thread: &str| -> Vec<u64> {
+            result["eventsByThread"][thread]
+                .as_array()
+                .expect("group")
+                .iter()
+                .map(|event| event["timestampNs"].as_u64().expect("timestamp"))
+                .collect()
+        };
+        assert_eq!(timestamps("1"), vec![400, 200]);
+        assert_eq!(timestamps("2"), vec![300]);
+        assert!(result["eventsByThread"].get("3").is_none());
+        assert_eq!(result["events"], json!([]));
+        assert_eq!(result["metadata"]["returnedCount"], 3);
+        assert_eq!(result["metadata"]["hasMore"], true);
+
+        let flat = handler
+            .call(Some(json!({ "traceId": "trace_grouped" })))
+            .await
+            .expect("events");
+        assert!(flat.get("eventsByThread").is_none());
+    }
+
+    #[tokio::test]
+    async fn events_handler__include_wall_clock__then_iso8601_or_null() {
+        let fixture = TraceFixture::new("trace_wall_clock");
+        fixture.write_events(&[function_call_event(1_100, 1, 0xa)]);
+        fixture.edit_manifest(|manifest| {
+            manifest["monotonic_start_ns"] = json!(100);
+            manifest["wall_clock_start_ns"] = json!(1_714_564_800_000_000_000u64);
+        });
+        let handler = EventsGetHandler::new(fixture.trace_root());
+        let params = json!({ "traceId": "trace_wall_clock", "includeWallClock": true });
+
+        let result = handler.call(Some(params.clone())).await.expect("events");
+        assert_eq!(
+            result["events"][0]["wallClock"],
+            "2024-05-01T12:00:00.000001000Z"
+        );
+
+        let plain = handler
+            .call(Some(json!({ "traceId": "trace_wall_clock" })))
+            .await
+            .expect("events");
+        assert!(plain["events"][0].get("wallClock").is_none());
+
+        fixture.write_events(&[function_call_event(1_100, 1, 0xa)]);
+        let unanchored = handler.call(Some(params)).await.expect("events");
+        assert!(unanchored["events"][0]["wallClock"].is_null());
+        assert!(unanchored["events"][0].get("wallClock").is_some());
+    }
+
+    #[tokio::test]
+    async fn events_handler__context__then_neighbours_marked_and_windows_merged() {
+        let fixture = TraceFixture::new("trace_context");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0xa),
+            function_call_event(150, 2, 0x10),
+            function_call_event(200, 1, 0xb),
+            function_call_event(300, 1, 0xff),
+            function_call_event(400, 1, 0xc),
+            function_call_event(500, 1, 0xff),
+            function_call_event(600, 1, 0xd),
+            function_call_event(700, 1, 0xe),
+        ]);
+        let handler = EventsGetHandler::new(fixture.trace_root());
+
+        let roles = |value: &Value| -> Vec<(u64, String)> {
+            value["events"]
+                .as_array()
+                .expect("events")
+                .iter()
+                .map(|event| {
+                    (
+                        event["timestampNs"].as_u64().expect("timestamp"),
+                        event["role"].as_str().expect("role").to_string(),
+                    )
+                })
+                .collect()
+        };
+        let params = |scope: &str| {
+            json!({
+                "traceId": "trace_context",
+                "filters": { "functionNames": ["0xff"] },
+                "context": 1,
+                "contextScope": scope,
+            })
+        };
+
+        // The windows around 300 and 500 share 400, which is listed once;
+        // thread 2's event at 150 is not a neighbour on thread 1
+        let result = handler.call(Some(params("thread"))).await.expect("events");
+        assert_eq!(
+            roles(&result),
+            vec![
+                (200, "context".to_string()),
+                (300, "match".to_string()),
+                (400, "context".to_string()),
+                (500, "match".to_string()),
+                (600, "context".to_string()),
+            ]
+        );
+        assert_eq!(result["metadata"]["returnedCount"], 2);
+        assert_eq!(result["metadata"]["contextCount"], 3);
+
+        let global = handler.call(Some(params("global"))).await.expect("events");
+        assert_eq!(roles(&global)[0], (200, "context".to_string()));
+
+        let mut wide = params("global");
+        wide["context"] = json!(2);
+        let wide = handler.call(Some(wide)).await.expect("events");
+        assert_eq!(roles(&wide)[0], (150, "context".to_string()));
+
+        let plain = handler
+            .call(Some(json!({ "traceId": "trace_context" })))
+            .await
+            .expect("events");
+        assert!(plain["events"][0].get("role").is_none());
+        assert!(plain["metadata"].get("contextCount").is_none());
+    }
+
+    #[tokio::test]
+    async fn events_count__inverted_time_range__then_invalid_params() {
+        let fixture = TraceFixture::new("trace_count_invalid");
+        let handler = EventsCountHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({
+                "traceId": "trace_count_invalid",
+                "filters": { "timeStartNs": 500, "timeEndNs": 100 },
+            })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, -32602);
+    }
+
+    #[tokio::test]
+    async fn events_handler__pagination_offsets__then_has_more_only_before_end() {
+        let fixture = TraceFixture::new("trace_pages");
+        let events: Vec<IndexEvent> = (0..3)
+            .map(|i| function_call_event(100 + i, 1, 0x1))
+            .collect();
+        fixture.write_events(&events);
+        let handler = EventsGetHandler::new(fixture.trace_root());
+
+        for (offset, expected_returned, expected_has_more) in
+            [(0, 2, true), (1, 2, false), (3, 0, false), (10, 0, false)]
+        {
+            let result = handler
+                .call(Some(
+                    json!({ "traceId": "trace_pages", "offset": offset, "limit": 2 }),
+                ))
+                .await
+                .expect("events");
+            let metadata = &result["metadata"];
+            assert_eq!(metadata["totalCount"], 3, "offset {offset}");
+            assert_eq!(metadata["returnedCount"], expected_returned, "offset {offset}");
+            assert_eq!(metadata["hasMore"], expected_has_more, "offset {offset}");
+        }
+    }
+
+    #[tokio::test]
+    async fn events_handler__order_by_thread_ties__then_timestamp_breaks_ties() {
+        let fixture = TraceFixture::new("trace_ties");
+        fixture.write_events(&[
+            function_call_event(300, 1, 0xc),
+            function_call_event(100, 2, 0xa),
+            function_call_event(200, 1, 0xb),
+            function_call_event(100, 1, 0xa),
+        ]);
+        let handler = EventsGetHandler::new(fixture.trace_root());
+
+        let order = |value: Value| -> Vec<(u64, u64)> {
+            value["events"]
+                .as_array()
+                .expect("events")
+                .iter()
+                .map(|event| {
+                    (
+                        event["threadId"].as_u64().expect("thread"),
+                        event["timestampNs"].as_u64().expect("timestamp"),
+                    )
+                })
+                .collect()
+        };
+
+        let ascending = handler
+            .call(Some(json!({ "traceId": "trace_ties", "orderBy": "threadId" })))
+            .await
+            .expect("events");
+        assert_eq!(order(ascending), vec![(1, 100), (1, 200), (1, 300), (2, 100)]);
+
+        let by_time = handler
+            .call(Some(json!({ "traceId": "trace_ties", "ascending": false })))
+            .await
+            .expect("events");
+        assert_eq!(order(by_time), vec![(1, 300), (1, 200), (2, 100), (1, 100)]);
     }
 }
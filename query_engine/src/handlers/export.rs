@@ -0,0 +1,97 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tokio::task;
+
+use crate::{
+    atf::{is_remote_url, AtfV2Error},
+    handlers::paths::validate_trace_id,
+    server::types::JsonRpcError,
+};
+
+/// Runs `export` to build trace `output_trace_id` from `trace_id`, both under
+/// `trace_root`, as `create_trace` does.
+pub(crate) async fn run_export<T, F>(
+    trace_root: &Path,
+    trace_id: &str,
+    output_trace_id: &str,
+    export: F,
+) -> Result<T, JsonRpcError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Path, &Path) -> Result<T, AtfV2Error> + Send + 'static,
+{
+    let trace_id = validate_trace_id(trace_id)?;
+    let output = output_dir(trace_root, output_trace_id)?;
+    let source = trace_root.join(trace_id);
+    if !source.is_dir() {
+        return Err(JsonRpcError::trace_not_found());
+    }
+    build_trace(output, move |output| export(&source, output)).await
+}
+
+/// Runs `build` to write trace `output_trace_id` under `trace_root`. The
+/// output directory is created first and removed again if the build fails,
+/// so a partial session is never left behind.
+pub(crate) async fn create_trace<T, F>(
+    trace_root: &Path,
+    output_trace_id: &str,
+    build: F,
+) -> Result<T, JsonRpcError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Path) -> Result<T, AtfV2Error> + Send + 'static,
+{
+    let output = output_dir(trace_root, output_trace_id)?;
+    build_trace(output, build).await
+}
+
+/// Directory a new trace `output_trace_id` is written to, which must not
+/// exist yet
+fn output_dir(trace_root: &Path, output_trace_id: &str) -> Result<PathBuf, JsonRpcError> {
+    let output_trace_id = validate_trace_id(output_trace_id)?;
+    if trace_root.to_str().is_some_and(is_remote_url) {
+        return Err(JsonRpcError::invalid_params(
+            "exports need a local trace root",
+        ));
+    }
+
+    let output = trace_root.join(output_trace_id);
+    if output.exists() {
+        return Err(JsonRpcError::invalid_params(format!(
+            "trace {output_trace_id} already exists"
+        )));
+    }
+    Ok(output)
+}
+
+async fn build_trace<T, F>(output: PathBuf, build: F) -> Result<T, JsonRpcError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Path) -> Result<T, AtfV2Error> + Send + 'static,
+{
+    let result = task::spawn_blocking({
+        let output = output.clone();
+        move || {
+            // Only a directory this build created is removed on failure
+            fs::create_dir(&output).map_err(|err| (false, AtfV2Error::io(output.clone(), err)))?;
+            build(&output).map_err(|err| (true, err))
+        }
+    })
+    .await
+    .map_err(|err| JsonRpcError::internal(format!("export task failed: {err}")))?;
+
+    result.map_err(|(created, err)| {
+        if created {
+            let _ = fs::remove_dir_all(&output);
+        }
+        match err {
+            AtfV2Error::Io { ref source, .. } if source.kind() == io::ErrorKind::NotFound => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to export trace: {other}")),
+        }
+    })
+}
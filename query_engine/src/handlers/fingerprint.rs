@@ -0,0 +1,311 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task;
+
+use crate::{
+    atf::{AtfError, ParsedEvent},
+    handlers::{
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, EventSource, SourceProvider},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+    },
+};
+
+/// Mixed into every digest; bump it whenever what is hashed changes, so old
+/// and new fingerprints never compare equal by accident.
+const FINGERPRINT_VERSION: &[u8] = b"ada-fingerprint-v1";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFingerprintParams {
+    pub trace_id: String,
+    /// Hash each thread's sequence on its own, so the interleaving of
+    /// threads no longer matters.
+    #[serde(default)]
+    pub normalize_threads: bool,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFingerprintResponse {
+    /// Hex MD5 digest of the trace's control flow.
+    pub fingerprint: String,
+    pub normalize_threads: bool,
+    pub event_count: u64,
+    pub thread_count: u64,
+    pub execution_time_ms: u64,
+}
+
+/// Adds an event's (type, symbol) pair to `context`. Both are length
+/// prefixed so adjacent strings cannot run into each other, and a missing
+/// symbol is tagged apart from an empty one.
+fn consume_event(context: &mut md5::Context, event: &ParsedEvent) {
+    let event_type = event.kind.as_str();
+    context.consume((event_type.len() as u64).to_le_bytes());
+    context.consume(event_type.as_bytes());
+    match event.kind.function_symbol() {
+        Some(symbol) => {
+            context.consume([1]);
+            context.consume((symbol.len() as u64).to_le_bytes());
+            context.consume(symbol.as_bytes());
+        }
+        None => context.consume([0]),
+    }
+}
+
+/// Structural digest of the events in `source`.
+///
+/// Threads are identified by the order in which they first appear, never by
+/// their OS thread ids, and timestamps are not hashed at all. Without
+/// `normalize_threads`, the digest covers one sequence of
+/// (thread, type, symbol) in stream order, so it also reflects how threads
+/// interleave. With it, each thread's sequence of (type, symbol) is hashed
+/// separately and the sorted per-thread digests are combined, so only what
+/// each thread did counts, not when or on which thread id.
+pub(crate) fn fingerprint(
+    source: &dyn EventSource,
+    normalize_threads: bool,
+) -> Result<(String, u64, u64), AtfError> {
+    let mut thread_indices: HashMap<u32, u32> = HashMap::new();
+    let mut thread_contexts: Vec<md5::Context> = Vec::new();
+    let mut sequence = md5::Context::new();
+    sequence.consume(FINGERPRINT_VERSION);
+    let mut event_count = 0u64;
+
+    for item in source.events()? {
+        let event = item?;
+        event_count += 1;
+        let next_index = thread_indices.len() as u32;
+        let thread_index = *thread_indices.entry(event.thread_id).or_insert(next_index);
+        if normalize_threads {
+            if thread_index as usize == thread_contexts.len() {
+                thread_contexts.push(md5::Context::new());
+            }
+            consume_event(&mut thread_contexts[thread_index as usize], &event);
+        } else {
+            sequence.consume(thread_index.to_le_bytes());
+            consume_event(&mut sequence, &event);
+        }
+    }
+
+    let thread_count = thread_indices.len() as u64;
+    if normalize_threads {
+        let mut digests: Vec<[u8; 16]> = thread_contexts
+            .into_iter()
+            .map(|context| context.compute().0)
+            .collect();
+        digests.sort_unstable();
+        sequence.consume([1]);
+        for digest in digests {
+            sequence.consume(digest);
+        }
+    }
+    Ok((
+        format!("{:x}", sequence.compute()),
+        event_count,
+        thread_count,
+    ))
+}
+
+#[derive(Clone)]
+pub struct TraceFingerprintHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
+}
+
+impl TraceFingerprintHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.fingerprint", self);
+    }
+
+    fn map_atf_error(err: AtfError) -> JsonRpcError {
+        match err {
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to load trace: {other}")),
+        }
+    }
+
+    pub async fn get_fingerprint(
+        &self,
+        params: TraceFingerprintParams,
+    ) -> Result<TraceFingerprintResponse, JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        let source = Arc::clone(&self.source);
+        let normalize_threads = params.normalize_threads;
+        let (fingerprint, event_count, thread_count) = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            fingerprint(events.as_ref(), normalize_threads)
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("fingerprint task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
+
+        Ok(TraceFingerprintResponse {
+            fingerprint,
+            normalize_threads,
+            event_count,
+            thread_count,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TraceFingerprintHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TraceFingerprintParams =
+            TraceFingerprintParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid trace.fingerprint params: {err}"))
+            })?;
+
+        let response = self.get_fingerprint(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::ParsedEventKind;
+    use crate::handlers::source::{MemoryEventSource, MemorySourceProvider};
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+
+    fn call(timestamp_ns: u64, thread_id: u32, symbol: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: ParsedEventKind::FunctionCall {
+                symbol: Some(symbol.into()),
+            },
+        }
+    }
+
+    fn digest(events: Vec<ParsedEvent>, normalize_threads: bool) -> String {
+        fingerprint(&MemoryEventSource::new(events), normalize_threads)
+            .expect("fingerprint")
+            .0
+    }
+
+    #[test]
+    fn fingerprint__different_timestamps_and_thread_ids__then_same_digest() {
+        let run_a = vec![call(100, 7, "main"), call(200, 9, "work")];
+        let run_b = vec![call(5_000, 42, "main"), call(9_000, 43, "work")];
+
+        assert_eq!(digest(run_a.clone(), false), digest(run_b.clone(), false));
+        assert_eq!(digest(run_a, true), digest(run_b, true));
+    }
+
+    #[test]
+    fn fingerprint__interleaving_changes__then_only_normalized_digest_stable() {
+        let run_a = vec![call(1, 1, "a"), call(2, 2, "x"), call(3, 1, "b")];
+        let run_b = vec![call(1, 1, "a"), call(2, 1, "b"), call(3, 2, "x")];
+
+        assert_ne!(digest(run_a.clone(), false), digest(run_b.clone(), false));
+        assert_eq!(digest(run_a, true), digest(run_b, true));
+    }
+
+    #[test]
+    fn fingerprint__different_symbol__then_digest_changes() {
+        let base = digest(vec![call(1, 1, "ab"), call(2, 1, "c")], true);
+
+        assert_ne!(base, digest(vec![call(1, 1, "a"), call(2, 1, "bc")], true));
+        assert_ne!(base, digest(vec![call(1, 1, "ab")], true));
+    }
+
+    #[tokio::test]
+    async fn trace_fingerprint__memory_trace__then_counts_reported() {
+        let provider = MemorySourceProvider::new().with_trace(
+            "run",
+            MemoryEventSource::new(vec![call(1, 5, "a"), call(2, 6, "b"), call(3, 5, "c")]),
+        );
+        let handler =
+            TraceFingerprintHandler::new(PathBuf::from("/virtual")).with_source(Arc::new(provider));
+
+        let result = handler
+            .call(Some(json!({ "traceId": "run", "normalizeThreads": true })))
+            .await
+            .expect("fingerprint");
+
+        assert_eq!(result["eventCount"], 3);
+        assert_eq!(result["threadCount"], 2);
+        assert_eq!(result["normalizeThreads"], true);
+        assert_eq!(result["fingerprint"].as_str().expect("digest").len(), 32);
+    }
+
+    #[tokio::test]
+    async fn trace_fingerprint__v2_sessions_shifted_in_time__then_same_digest() {
+        let fingerprint_of = |fixture: TraceFixture| async move {
+            TraceFingerprintHandler::new(fixture.trace_root())
+                .call(Some(json!({ "traceId": fixture.trace_id() })))
+                .await
+                .expect("fingerprint")["fingerprint"]
+                .clone()
+        };
+        let run_a = TraceFixture::new("run_a");
+        run_a.write_events(&[
+            function_call_event(100, 1, 0x100),
+            function_return_event(200, 1, 0x100),
+        ]);
+        let run_b = TraceFixture::new("run_b");
+        run_b.write_events(&[
+            function_call_event(7_000, 9, 0x100),
+            function_return_event(9_000, 9, 0x100),
+        ]);
+        let run_c = TraceFixture::new("run_c");
+        run_c.write_events(&[
+            function_call_event(100, 1, 0x200),
+            function_return_event(200, 1, 0x200),
+        ]);
+
+        let digest_a = fingerprint_of(run_a).await;
+        assert_eq!(digest_a, fingerprint_of(run_b).await);
+        assert_ne!(digest_a, fingerprint_of(run_c).await);
+    }
+}
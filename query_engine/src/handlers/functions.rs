@@ -0,0 +1,339 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task;
+
+use crate::{
+    atf::AtfError,
+    handlers::{
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, SourceProvider},
+        spans::{for_each_nested, reconstruct_spans, SpanCandidate},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionsTimingParams {
+    pub trace_id: String,
+    #[serde(default)]
+    pub filters: FunctionTimingFilters,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+/// Restricts which calls are counted. As in `spans.list`, a call counts only
+/// if it starts and ends inside the time window.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionTimingFilters {
+    pub time_start_ns: Option<u64>,
+    pub time_end_ns: Option<u64>,
+    pub thread_ids: Option<Vec<u32>>,
+}
+
+impl FunctionTimingFilters {
+    pub(crate) fn matches(&self, span: &SpanCandidate) -> bool {
+        if let Some(thread_ids) = self.thread_ids.as_ref() {
+            if !thread_ids.contains(&span.thread_id) {
+                return false;
+            }
+        }
+        if self
+            .time_start_ns
+            .is_some_and(|start| span.start_time_ns < start)
+        {
+            return false;
+        }
+        if self.time_end_ns.is_some_and(|end| span.end_time_ns > end) {
+            return false;
+        }
+        true
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionsTimingResponse {
+    /// Functions by exclusive time, hottest first.
+    pub functions: Vec<FunctionTiming>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionTiming {
+    pub function_name: String,
+    pub call_count: u64,
+    /// Time inside the function including its callees. A recursive call
+    /// nested in another call of the same function adds nothing, so the
+    /// total never exceeds the wall time the function was on the stack.
+    pub inclusive_ns: u64,
+    /// Time inside the function excluding its callees.
+    pub exclusive_ns: u64,
+}
+
+/// Totals per function over `spans`, hottest exclusive time first.
+///
+/// Recursion is detected over all spans before `filters` apply, so a
+/// recursive call keeps that status when its outer call is filtered out.
+/// Spans without a function name are skipped.
+pub(crate) fn function_timings(
+    spans: &[SpanCandidate],
+    filters: &FunctionTimingFilters,
+) -> Vec<FunctionTiming> {
+    let mut totals: BTreeMap<&str, FunctionTiming> = BTreeMap::new();
+    for_each_nested(spans, |span, enclosing| {
+        let Some(name) = span.function_name.as_deref() else {
+            return;
+        };
+        if !filters.matches(span) {
+            return;
+        }
+        let recursive = enclosing
+            .iter()
+            .any(|outer| outer.function_name.as_deref() == Some(name));
+        let timing = totals.entry(name).or_insert_with(|| FunctionTiming {
+            function_name: name.to_string(),
+            call_count: 0,
+            inclusive_ns: 0,
+            exclusive_ns: 0,
+        });
+        timing.call_count += 1;
+        timing.exclusive_ns = timing.exclusive_ns.saturating_add(span.self_duration_ns);
+        if !recursive {
+            timing.inclusive_ns = timing.inclusive_ns.saturating_add(span.duration_ns);
+        }
+    });
+
+    // Ties keep name order from the map, so equal requests list identically.
+    let mut functions: Vec<FunctionTiming> = totals.into_values().collect();
+    functions.sort_by_key(|timing| std::cmp::Reverse(timing.exclusive_ns));
+    functions
+}
+
+#[derive(Clone)]
+pub struct FunctionsTimingHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
+}
+
+impl FunctionsTimingHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("functions.timing", self);
+    }
+
+    fn validate_params(params: &FunctionsTimingParams) -> Result<(), JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+        if let (Some(start), Some(end)) = (params.filters.time_start_ns, params.filters.time_end_ns)
+        {
+            if start >= end {
+                return Err(JsonRpcError::invalid_params(
+                    "timeStartNs must be less than timeEndNs",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn map_atf_error(err: AtfError) -> JsonRpcError {
+        match err {
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to load trace: {other}")),
+        }
+    }
+
+    pub async fn get_timings(
+        &self,
+        params: FunctionsTimingParams,
+    ) -> Result<FunctionsTimingResponse, JsonRpcError> {
+        Self::validate_params(&params)?;
+
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        // Span reconstruction walks the whole event stream with blocking IO.
+        let source = Arc::clone(&self.source);
+        let spans = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            reconstruct_spans(events.as_ref())
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("span reconstruction task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
+
+        Ok(FunctionsTimingResponse {
+            functions: function_timings(&spans, &params.filters),
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for FunctionsTimingHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: FunctionsTimingParams =
+            FunctionsTimingParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid functions.timing params: {err}"))
+            })?;
+
+        let response = self.get_timings(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+
+    const MAIN: u64 = 0x100;
+    const WORK: u64 = 0x200;
+
+    fn candidate(thread_id: u32, start: u64, end: u64, depth: u32, name: &str) -> SpanCandidate {
+        SpanCandidate {
+            span_id: format!("{thread_id}:{start}"),
+            function_name: Some(name.into()),
+            start_time_ns: start,
+            end_time_ns: end,
+            duration_ns: end - start,
+            self_duration_ns: end - start,
+            thread_id,
+            depth,
+            child_count: 0,
+            merged_count: 1,
+        }
+    }
+
+    #[test]
+    fn function_timings__recursive_calls__then_inclusive_counted_once() {
+        // fib(0..100) -> fib(10..60) -> fib(20..30)
+        let mut outer = candidate(1, 0, 100, 0, "fib");
+        outer.self_duration_ns = 50;
+        let mut middle = candidate(1, 10, 60, 1, "fib");
+        middle.self_duration_ns = 40;
+        let inner = candidate(1, 20, 30, 2, "fib");
+        // The same function on another thread is not nested in thread 1's calls
+        let other = candidate(2, 20, 30, 0, "fib");
+
+        let timings = function_timings(
+            &[inner, middle, outer, other],
+            &FunctionTimingFilters::default(),
+        );
+        assert_eq!(
+            timings,
+            vec![FunctionTiming {
+                function_name: "fib".into(),
+                call_count: 4,
+                inclusive_ns: 110,
+                exclusive_ns: 110,
+            }]
+        );
+    }
+
+    #[test]
+    fn function_timings__filters__then_only_matching_calls_counted() {
+        let spans = [
+            candidate(1, 0, 100, 0, "a"),
+            candidate(2, 0, 100, 0, "a"),
+            candidate(1, 200, 300, 0, "a"),
+        ];
+        let filters = FunctionTimingFilters {
+            time_end_ns: Some(150),
+            thread_ids: Some(vec![1]),
+            ..Default::default()
+        };
+
+        let timings = function_timings(&spans, &filters);
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].call_count, 1);
+        assert_eq!(timings[0].inclusive_ns, 100);
+    }
+
+    #[tokio::test]
+    async fn functions_timing__caller_of_hot_callee__then_inclusive_exceeds_exclusive() {
+        let fixture = TraceFixture::new("timing");
+        let events = vec![
+            function_call_event(100, 1, MAIN),
+            function_call_event(110, 1, WORK),
+            function_return_event(900, 1, WORK),
+            function_return_event(1_000, 1, MAIN),
+        ];
+        fixture.write_events(&events);
+        let handler = FunctionsTimingHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({ "traceId": "timing" })))
+            .await
+            .expect("timings");
+
+        // main is cheap itself but holds the expensive call to work
+        let timing = |index: usize| {
+            let function = &result["functions"][index];
+            (
+                function["functionName"].as_str().expect("name").to_string(),
+                function["inclusiveNs"].as_u64().expect("inclusive"),
+                function["exclusiveNs"].as_u64().expect("exclusive"),
+            )
+        };
+        assert_eq!(timing(0), ("0x200".to_string(), 790, 790));
+        assert_eq!(timing(1), ("0x100".to_string(), 900, 110));
+        assert_eq!(result["functions"][1]["callCount"], 1);
+    }
+
+    #[tokio::test]
+    async fn functions_timing__inverted_time_range__then_invalid_params() {
+        let fixture = TraceFixture::new("timing_invalid");
+        let handler = FunctionsTimingHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({
+                "traceId": "timing_invalid",
+                "filters": { "timeStartNs": 500, "timeEndNs": 100 },
+            })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, -32602);
+    }
+}
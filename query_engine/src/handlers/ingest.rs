@@ -0,0 +1,484 @@
+//! Converts line-based traces from other tools into V2 sessions.
+//!
+//! Each line is `<timestamp_ns> <thread_id> <enter|exit> <symbol>`, separated
+//! by whitespace; the symbol runs to the end of the line so demangled names
+//! with spaces survive. Blank lines and lines starting with `#` are ignored.
+//! `entry`/`->` and `return`/`<-` are accepted as DTrace flow-indent
+//! spellings of `enter` and `exit`.
+//!
+//! Lines parse into `ParsedEvent`s. A session stores function ids rather
+//! than names, so a `0x…` symbol is taken as the id itself and every other
+//! name gets an id of its own, with the names kept in the manifest's
+//! `symbols` map.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    atf::{
+        arch_name, os_name,
+        v2::{
+            write_manifest, IndexEvent, ThreadLayout, ThreadWriter, ATF_EVENT_KIND_CALL,
+            ATF_EVENT_KIND_RETURN, ATF_NO_DETAIL_SEQ,
+        },
+        AtfV2Error, ParsedEvent, ParsedEventKind,
+    },
+    handlers::export::create_trace,
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+/// Upper half of the function ids given to named symbols, a module id the
+/// tracer never assigns
+const INGESTED_MODULE_ID: u64 = 0xffff_ffff;
+
+/// Parses one line; `Ok(None)` for blank and comment lines.
+pub fn parse_line(line: &str) -> Result<Option<ParsedEvent>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut fields = line.splitn(4, char::is_whitespace);
+    let mut next = |name: &str| {
+        fields
+            .next()
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .ok_or_else(|| format!("missing {name}"))
+    };
+    let timestamp_ns = next("timestamp")?
+        .parse()
+        .map_err(|err| format!("invalid timestamp: {err}"))?;
+    let thread_id = next("thread id")?
+        .parse()
+        .map_err(|err| format!("invalid thread id: {err}"))?;
+    let direction = next("direction")?;
+    let symbol = Some(next("symbol")?.to_string());
+    let kind = match direction {
+        "enter" | "entry" | "->" => ParsedEventKind::FunctionCall { symbol },
+        "exit" | "return" | "<-" => ParsedEventKind::FunctionReturn { symbol },
+        other => return Err(format!("unknown direction {other:?}")),
+    };
+
+    Ok(Some(ParsedEvent {
+        timestamp_ns,
+        thread_id,
+        kind,
+    }))
+}
+
+/// Events read from a text trace, and the lines left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedText {
+    pub events: Vec<ParsedEvent>,
+    pub skipped_lines: u64,
+    /// 1-based number of the first skipped line and why it was rejected.
+    pub first_error: Option<String>,
+}
+
+/// Parses every line of `input`, skipping the malformed ones.
+pub fn parse_text(input: impl BufRead) -> std::io::Result<ParsedText> {
+    let mut parsed = ParsedText::default();
+    for (number, line) in input.lines().enumerate() {
+        match parse_line(&line?) {
+            Ok(Some(event)) => parsed.events.push(event),
+            Ok(None) => {}
+            Err(err) => {
+                parsed.skipped_lines += 1;
+                parsed
+                    .first_error
+                    .get_or_insert_with(|| format!("line {}: {err}", number + 1));
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Function ids handed out while writing a session
+#[derive(Debug, Default)]
+struct FunctionIds {
+    by_name: HashMap<String, u64>,
+    names: Map<String, Value>,
+}
+
+impl FunctionIds {
+    fn id_of(&mut self, symbol: &str) -> u64 {
+        if let Some(id) = symbol
+            .strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+        {
+            return id;
+        }
+        if let Some(&id) = self.by_name.get(symbol) {
+            return id;
+        }
+        let id = (INGESTED_MODULE_ID << 32) | self.by_name.len() as u64;
+        self.by_name.insert(symbol.to_string(), id);
+        self.names
+            .insert(format!("{id:#x}"), Value::String(symbol.to_string()));
+        id
+    }
+}
+
+/// Writes `events` as a session at `output`, one stream per thread in
+/// timestamp order. Call depths are recomputed from the calls and returns
+/// of each thread; events other than those are dropped.
+fn write_session(
+    output: &Path,
+    events: &[ParsedEvent],
+    os: u8,
+    arch: u8,
+) -> Result<TraceIngestResponse, AtfV2Error> {
+    let mut threads: BTreeMap<u32, Vec<&ParsedEvent>> = BTreeMap::new();
+    for event in events {
+        threads.entry(event.thread_id).or_default().push(event);
+    }
+
+    let mut function_ids = FunctionIds::default();
+    let mut written = Vec::new();
+    for (thread_id, mut events) in threads {
+        events.sort_by_key(|event| event.timestamp_ns);
+        let layout = ThreadLayout {
+            thread_id,
+            arch,
+            os,
+            clock_type: 0,
+        };
+        let mut writer = ThreadWriter::create(&output.join(format!("thread_{thread_id}")), layout)?;
+        let mut depth = 0u32;
+        for event in events {
+            let (event_kind, symbol) = match &event.kind {
+                ParsedEventKind::FunctionCall { symbol } => (ATF_EVENT_KIND_CALL, symbol),
+                ParsedEventKind::FunctionReturn { symbol } => (ATF_EVENT_KIND_RETURN, symbol),
+                ParsedEventKind::Unknown => continue,
+            };
+            if event_kind == ATF_EVENT_KIND_RETURN {
+                depth = depth.saturating_sub(1);
+            }
+            let index_event = IndexEvent {
+                timestamp_ns: event.timestamp_ns,
+                function_id: symbol
+                    .as_deref()
+                    .map_or(0, |symbol| function_ids.id_of(symbol)),
+                thread_id,
+                event_kind,
+                call_depth: depth,
+                detail_seq: ATF_NO_DETAIL_SEQ,
+            };
+            if event_kind == ATF_EVENT_KIND_CALL {
+                depth += 1;
+            }
+            writer.push(&index_event, None)?;
+        }
+        written.push(writer.finish()?);
+    }
+
+    let mut manifest = Map::new();
+    manifest.insert("format_version".to_string(), "2.0".into());
+    if !function_ids.names.is_empty() {
+        manifest.insert("symbols".to_string(), Value::Object(function_ids.names));
+    }
+    write_manifest(output, manifest, &written)?;
+
+    Ok(TraceIngestResponse {
+        trace_id: String::new(),
+        event_count: written.iter().map(|thread| thread.event_count).sum(),
+        thread_count: written.len() as u64,
+        ..TraceIngestResponse::default()
+    })
+}
+
+/// Index header code of the `name` an `os_name` or `arch_name` maps it to
+fn header_code(
+    name: Option<&str>,
+    names: fn(u8) -> &'static str,
+    param: &str,
+) -> Result<u8, JsonRpcError> {
+    match name {
+        None => Ok(0),
+        Some(name) => (1..=u8::MAX)
+            .find(|&code| names(code) == name)
+            .ok_or_else(|| JsonRpcError::invalid_params(format!("unknown {param} {name:?}"))),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceIngestParams {
+    /// Text trace to convert; relative to the trace root, which it must lie
+    /// inside.
+    pub input_path: PathBuf,
+    pub output_trace_id: String,
+    /// Platform the trace was taken on, as `trace.info` names it; unknown
+    /// when absent.
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub arch: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceIngestResponse {
+    pub trace_id: String,
+    pub event_count: u64,
+    pub thread_count: u64,
+    /// Lines that could not be parsed and were left out.
+    pub skipped_lines: u64,
+    /// 1-based number of the first skipped line and why it was rejected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct TraceIngestHandler {
+    trace_root_dir: PathBuf,
+}
+
+impl TraceIngestHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self { trace_root_dir }
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.ingest", self);
+    }
+
+    /// Canonical path of `input_path`, which must name a file inside the
+    /// trace root
+    fn resolve_input(&self, input_path: &Path) -> Result<PathBuf, JsonRpcError> {
+        let rejected = || JsonRpcError::invalid_params("inputPath is not a file in the trace root");
+        let root = self.trace_root_dir.canonicalize().map_err(|_| rejected())?;
+        let input = root
+            .join(input_path)
+            .canonicalize()
+            .map_err(|_| rejected())?;
+        if input.starts_with(&root) && input.is_file() {
+            Ok(input)
+        } else {
+            Err(rejected())
+        }
+    }
+
+    pub async fn ingest(
+        &self,
+        params: TraceIngestParams,
+    ) -> Result<TraceIngestResponse, JsonRpcError> {
+        let os = header_code(params.os.as_deref(), os_name, "os")?;
+        let arch = header_code(params.arch.as_deref(), arch_name, "arch")?;
+        let input = self.resolve_input(&params.input_path)?;
+
+        let response = create_trace(
+            &self.trace_root_dir,
+            &params.output_trace_id,
+            move |output| {
+                let file = File::open(&input).map_err(|err| AtfV2Error::io(&input, err))?;
+                let parsed =
+                    parse_text(BufReader::new(file)).map_err(|err| AtfV2Error::io(&input, err))?;
+                let response = write_session(output, &parsed.events, os, arch)?;
+                Ok(TraceIngestResponse {
+                    skipped_lines: parsed.skipped_lines,
+                    first_error: parsed.first_error,
+                    ..response
+                })
+            },
+        )
+        .await?;
+
+        Ok(TraceIngestResponse {
+            trace_id: params.output_trace_id.trim().to_string(),
+            ..response
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TraceIngestHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TraceIngestParams = match params {
+            Some(value) => TraceIngestParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid trace.ingest parameters: {err}"))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing trace.ingest parameters",
+                ))
+            }
+        };
+
+        let response = self.ingest(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::v2::SessionReader;
+    use crate::handlers::{test_support::TraceFixture, SpansListHandler};
+    use serde_json::json;
+
+    fn call(timestamp_ns: u64, thread_id: u32, symbol: &str) -> ParsedEvent {
+        ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: ParsedEventKind::FunctionCall {
+                symbol: Some(symbol.into()),
+            },
+        }
+    }
+
+    #[test]
+    fn parse_line__dtrace_spellings_and_spaced_symbol__then_parsed_event() {
+        assert_eq!(
+            parse_line("100 7 -> operator new(unsigned long)"),
+            Ok(Some(call(100, 7, "operator new(unsigned long)")))
+        );
+        assert_eq!(
+            parse_line("  200\t7 return main ").map(|event| event.map(|e| e.kind)),
+            Ok(Some(ParsedEventKind::FunctionReturn {
+                symbol: Some("main".into())
+            }))
+        );
+        assert_eq!(parse_line("# comment"), Ok(None));
+        assert_eq!(parse_line(""), Ok(None));
+    }
+
+    #[test]
+    fn parse_text__malformed_lines__then_skipped_and_first_reported() {
+        let input = "100 1 enter main\nbogus\n200 1 sideways main\n300 1 exit main\n";
+
+        let parsed = parse_text(input.as_bytes()).expect("parse");
+
+        assert_eq!(parsed.events.len(), 2);
+        assert_eq!(parsed.skipped_lines, 2);
+        assert_eq!(
+            parsed.first_error.as_deref(),
+            Some("line 2: invalid timestamp: invalid digit found in string")
+        );
+    }
+
+    #[tokio::test]
+    async fn trace_ingest__text_trace__then_session_queryable_by_handlers() {
+        let fixture = TraceFixture::new("unused");
+        std::fs::write(
+            fixture.trace_root().join("trace.txt"),
+            "# from dtrace\n\
+             300 2 enter 0x10\n\
+             100 1 enter main\n\
+             150 1 enter work\n\
+             500 1\n\
+             250 1 exit work\n\
+             400 1 exit main\n",
+        )
+        .expect("input");
+        let handler = TraceIngestHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({
+                "inputPath": "trace.txt",
+                "outputTraceId": "ingested",
+                "os": "linux",
+                "arch": "arm64",
+            })))
+            .await
+            .expect("ingest");
+
+        assert_eq!(
+            response,
+            json!({
+                "traceId": "ingested",
+                "eventCount": 5,
+                "threadCount": 2,
+                "skippedLines": 1,
+                "firstError": "line 5: missing direction",
+            })
+        );
+        let output = fixture.trace_root().join("ingested");
+        let session = SessionReader::open(&output).expect("open ingested");
+        assert!(session.verify().expect("verify").is_consistent());
+        let events: Vec<(u64, u32, u64)> = session
+            .merged_iter()
+            .map(|(_, event)| (event.timestamp_ns, event.call_depth, event.function_id))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (100, 0, 0xffff_ffff_0000_0000),
+                (150, 1, 0xffff_ffff_0000_0001),
+                (250, 1, 0xffff_ffff_0000_0001),
+                (300, 0, 0x10),
+                (400, 0, 0xffff_ffff_0000_0000),
+            ]
+        );
+        let fields = crate::atf::v2::read_manifest_fields(&output).expect("fields");
+        assert_eq!(
+            fields["symbols"],
+            json!({ "0xffffffff00000000": "main", "0xffffffff00000001": "work" })
+        );
+        assert_eq!(session.threads()[0].index.os(), 4);
+
+        let spans = SpansListHandler::new(fixture.trace_root())
+            .call(Some(json!({ "traceId": "ingested" })))
+            .await
+            .expect("spans");
+        assert_eq!(spans["spans"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[tokio::test]
+    async fn trace_ingest__input_outside_root__then_invalid_params() {
+        let fixture = TraceFixture::new("unused");
+        let outside = tempfile::NamedTempFile::new().expect("outside file");
+        let handler = TraceIngestHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({
+                "inputPath": outside.path(),
+                "outputTraceId": "ingested",
+            })))
+            .await
+            .expect_err("expected error");
+
+        assert_eq!(err.code, -32602);
+        assert!(!fixture.trace_root().join("ingested").exists());
+    }
+
+    #[tokio::test]
+    async fn trace_ingest__unknown_os__then_invalid_params() {
+        let fixture = TraceFixture::new("unused");
+        std::fs::write(fixture.trace_root().join("trace.txt"), "").expect("input");
+        let handler = TraceIngestHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({
+                "inputPath": "trace.txt",
+                "outputTraceId": "ingested",
+                "os": "plan9",
+            })))
+            .await
+            .expect_err("expected error");
+
+        assert_eq!(err.code, -32602);
+    }
+}
@@ -1,7 +1,56 @@
+pub mod anomalies;
+pub mod api;
+pub mod callgraph;
+pub mod cpu_time;
+pub mod decimate;
+pub mod envelope;
 pub mod events;
+pub(crate) mod export;
+pub mod fingerprint;
+pub mod functions;
+pub mod ingest;
+pub(crate) mod paths;
+pub mod quick_check;
+pub mod schema;
+pub mod session_verify;
+pub mod signals;
+pub mod slice;
+#[cfg(test)]
+mod snapshot_tests;
+pub mod source;
+pub mod span_index;
 pub mod spans;
+pub mod stacks;
+pub mod strip;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+pub mod timeline;
 pub mod trace_info;
+pub(crate) mod wall_clock;
 
-pub use events::EventsGetHandler;
-pub use spans::SpansListHandler;
+pub use anomalies::TraceAnomaliesHandler;
+pub use api::{QueryApi, QueryError};
+pub use callgraph::CallGraphHandler;
+pub use cpu_time::ThreadsCpuTimeHandler;
+pub use decimate::TraceDecimateHandler;
+pub use envelope::ResponseEnvelope;
+pub use events::{EventsCountHandler, EventsGetHandler};
+pub use fingerprint::TraceFingerprintHandler;
+pub use functions::FunctionsTimingHandler;
+pub use ingest::TraceIngestHandler;
+pub use quick_check::TraceQuickCheckHandler;
+pub use schema::SystemSchemaHandler;
+pub use session_verify::SessionVerifyHandler;
+pub use signals::SignalsSummaryHandler;
+pub use slice::TraceSliceHandler;
+pub use source::{
+    open_trace, AtfSourceProvider, EventSource, MemoryEventSource, MemorySourceProvider,
+    RemappedEventSource, RemappedSourceProvider, SourceProvider, ThreadIdMap, ThreadIdMapping,
+    V2EventSource,
+};
+pub use span_index::SpansAtTimeHandler;
+pub use spans::{SpansGetHandler, SpansListHandler};
+pub use stacks::{StacksGetHandler, SymbolResolver};
+pub use strip::TraceStripRegistersHandler;
+pub use timeline::TimelineHandler;
 pub use trace_info::TraceInfoHandler;
@@ -0,0 +1,151 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::server::types::JsonRpcError;
+
+/// Validates a client-supplied trace id and returns it trimmed.
+///
+/// Trace ids name a single directory under the trace root, so anything that
+/// could walk out of it (separators, `..`, absolute paths, NUL bytes) is
+/// rejected before the id is ever joined onto a path.
+pub(crate) fn validate_trace_id(trace_id: &str) -> Result<&str, JsonRpcError> {
+    let trace_id = trace_id.trim();
+    if trace_id.is_empty() {
+        return Err(JsonRpcError::invalid_params("traceId must not be empty"));
+    }
+
+    let has_forbidden_char = trace_id.chars().any(|c| c == '/' || c == '\\' || c == '\0');
+    let mut components = Path::new(trace_id).components();
+    let is_single_normal = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    );
+
+    if has_forbidden_char || !is_single_normal {
+        return Err(JsonRpcError::invalid_params("invalid traceId"));
+    }
+    Ok(trace_id)
+}
+
+/// Resolves the trace root a request should read from.
+///
+/// Without an override the handler's configured root is used. An override must
+/// canonicalize to the configured root or to one of `allowed_roots` (or a
+/// directory beneath them); anything else is rejected as invalid params.
+pub(crate) fn resolve_trace_root(
+    default_root: &Path,
+    allowed_roots: &[PathBuf],
+    requested: Option<&Path>,
+) -> Result<PathBuf, JsonRpcError> {
+    let requested = match requested {
+        Some(requested) => requested,
+        None => return Ok(default_root.to_path_buf()),
+    };
+
+    let rejected = || JsonRpcError::invalid_params("traceRoot is not an allowed trace root");
+    let canonical = requested.canonicalize().map_err(|_| rejected())?;
+    let permitted = std::iter::once(default_root)
+        .chain(allowed_roots.iter().map(PathBuf::as_path))
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| canonical.starts_with(root));
+
+    if permitted {
+        Ok(canonical)
+    } else {
+        Err(rejected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use tempfile::TempDir;
+
+    fn assert_invalid_trace_id(trace_id: &str) {
+        let err = validate_trace_id(trace_id).expect_err("expected rejection");
+        assert_eq!(err.code, -32602);
+        assert_eq!(
+            err.data,
+            Some(serde_json::Value::String("invalid traceId".into()))
+        );
+    }
+
+    #[test]
+    fn validate_trace_id__plain_name__then_trimmed_id() {
+        assert_eq!(
+            validate_trace_id("  trace-1.v2 ").expect("valid"),
+            "trace-1.v2"
+        );
+    }
+
+    #[test]
+    fn validate_trace_id__parent_traversal__then_rejected() {
+        assert_invalid_trace_id("..");
+        assert_invalid_trace_id("../../etc");
+        assert_invalid_trace_id("trace/../../etc");
+        assert_invalid_trace_id("..\\secrets");
+    }
+
+    #[test]
+    fn validate_trace_id__absolute_path__then_rejected() {
+        assert_invalid_trace_id("/etc/passwd");
+        assert_invalid_trace_id("/");
+    }
+
+    #[test]
+    fn validate_trace_id__embedded_null_byte__then_rejected() {
+        assert_invalid_trace_id("trace\0.bin");
+    }
+
+    #[test]
+    fn validate_trace_id__current_dir__then_rejected() {
+        assert_invalid_trace_id(".");
+    }
+
+    #[test]
+    fn resolve_trace_root__no_override__then_default_root() {
+        let root = resolve_trace_root(Path::new("/traces"), &[], None).expect("root");
+        assert_eq!(root, PathBuf::from("/traces"));
+    }
+
+    #[test]
+    fn resolve_trace_root__allowed_subdirectory__then_accepted() {
+        let default = TempDir::new().expect("tempdir");
+        let other = TempDir::new().expect("tempdir");
+        let nested = other.path().join("team");
+        std::fs::create_dir_all(&nested).expect("nested");
+
+        let root = resolve_trace_root(
+            default.path(),
+            &[other.path().to_path_buf()],
+            Some(nested.as_path()),
+        )
+        .expect("root");
+        assert_eq!(root, nested.canonicalize().expect("canonical"));
+    }
+
+    #[test]
+    fn resolve_trace_root__escapes_allowed_roots__then_invalid_params() {
+        let default = TempDir::new().expect("tempdir");
+        let outside = TempDir::new().expect("tempdir");
+        let escape = default
+            .path()
+            .join("..")
+            .join(outside.path().file_name().expect("tempdir name"));
+
+        let err = resolve_trace_root(default.path(), &[], Some(escape.as_path()))
+            .expect_err("expected rejection");
+        assert_eq!(err.code, -32602);
+    }
+
+    #[test]
+    fn resolve_trace_root__missing_directory__then_invalid_params() {
+        let default = TempDir::new().expect("tempdir");
+        let missing = default.path().join("missing");
+
+        let err = resolve_trace_root(default.path(), &[], Some(missing.as_path()))
+            .expect_err("expected rejection");
+        assert_eq!(err.code, -32602);
+    }
+}
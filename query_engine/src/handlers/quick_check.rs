@@ -0,0 +1,233 @@
+use std::{io, path::PathBuf, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::task;
+
+use crate::{
+    atf::{AtfV2Error, IndexEvent, QuickCheckThread, SessionReader, VerifyIssue},
+    handlers::{
+        paths::{resolve_trace_root, validate_trace_id},
+        source::parse_index_event,
+        trace_info::EventSample,
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceQuickCheckParams {
+    pub trace_id: String,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceQuickCheckResponse {
+    pub trace_id: String,
+    /// True when `issues` is empty.
+    pub consistent: bool,
+    pub threads_checked: usize,
+    pub event_count: u64,
+    pub threads: Vec<ThreadQuickCheck>,
+    pub issues: Vec<VerifyIssue>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadQuickCheck {
+    pub thread_id: u32,
+    pub event_count: u64,
+    pub first_events: Vec<EventSample>,
+    pub last_events: Vec<EventSample>,
+}
+
+fn samples(events: &[IndexEvent]) -> Vec<EventSample> {
+    events
+        .iter()
+        .map(|event| EventSample::from(&parse_index_event(event)))
+        .collect()
+}
+
+impl From<QuickCheckThread> for ThreadQuickCheck {
+    fn from(thread: QuickCheckThread) -> Self {
+        Self {
+            thread_id: thread.thread_id,
+            event_count: thread.event_count,
+            first_events: samples(&thread.first_events),
+            last_events: samples(&thread.last_events),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TraceQuickCheckHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+}
+
+impl TraceQuickCheckHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.quickCheck", self);
+    }
+
+    fn map_v2_error(err: AtfV2Error) -> JsonRpcError {
+        match err {
+            AtfV2Error::Io { ref source, .. } if source.kind() == io::ErrorKind::NotFound => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to check trace: {other}")),
+        }
+    }
+
+    pub async fn quick_check(
+        &self,
+        params: TraceQuickCheckParams,
+    ) -> Result<TraceQuickCheckResponse, JsonRpcError> {
+        let trace_id = validate_trace_id(&params.trace_id)?.to_string();
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let session_dir = trace_root.join(&trace_id);
+        if !session_dir.is_dir() {
+            return Err(JsonRpcError::trace_not_found());
+        }
+        let start_time = Instant::now();
+
+        let report = task::spawn_blocking(move || SessionReader::quick_check(&session_dir))
+            .await
+            .map_err(|err| JsonRpcError::internal(format!("quick check task failed: {err}")))?
+            .map_err(Self::map_v2_error)?;
+
+        Ok(TraceQuickCheckResponse {
+            trace_id,
+            consistent: report.is_consistent(),
+            threads_checked: report.threads_checked,
+            event_count: report.event_count,
+            threads: report.threads.into_iter().map(Into::into).collect(),
+            issues: report.issues,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TraceQuickCheckHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TraceQuickCheckParams = match params {
+            Some(value) => TraceQuickCheckParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid trace.quickCheck parameters: {err}"))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing trace.quickCheck parameters",
+                ))
+            }
+        };
+
+        let response = self.quick_check(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::handlers::test_support::{
+        exception_event, function_call_event, function_return_event, TraceFixture,
+    };
+    use serde_json::json;
+    use std::fs::OpenOptions;
+
+    #[tokio::test]
+    async fn trace_quick_check__fixture_session__then_consistent_with_samples() {
+        let fixture = TraceFixture::new("session");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x100),
+            exception_event(150, 1),
+            function_return_event(200, 1, 0x100),
+            function_call_event(300, 2, 0x200),
+        ]);
+        let handler = TraceQuickCheckHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({ "traceId": "session" })))
+            .await
+            .expect("quick check");
+
+        assert_eq!(response["consistent"], true, "{response}");
+        assert_eq!(response["threadsChecked"], 2);
+        assert_eq!(response["eventCount"], 4);
+        let first = &response["threads"][0]["firstEvents"];
+        assert_eq!(first[0]["timestampNs"], 100);
+        assert_eq!(first[0]["eventType"], "FunctionCall");
+        assert_eq!(first[1]["eventType"], "Unknown");
+        assert_eq!(response["threads"][0]["lastEvents"][2]["timestampNs"], 200);
+    }
+
+    #[tokio::test]
+    async fn trace_quick_check__footer_truncated__then_reported_inconsistent() {
+        let fixture = TraceFixture::new("session");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x100),
+            function_return_event(200, 1, 0x100),
+        ]);
+        OpenOptions::new()
+            .write(true)
+            .open(fixture.index_path(1))
+            .expect("index")
+            .set_len(64 + 2 * 32)
+            .expect("truncate");
+        let handler = TraceQuickCheckHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({ "traceId": "session" })))
+            .await
+            .expect("quick check");
+
+        assert_eq!(response["consistent"], false);
+        assert_eq!(response["eventCount"], 2);
+        assert_eq!(response["issues"][0]["kind"], "indexFooterMissing");
+        assert_eq!(response["issues"][0]["threadId"], 1);
+    }
+
+    #[tokio::test]
+    async fn trace_quick_check__unknown_trace__then_trace_not_found() {
+        let fixture = TraceFixture::new("session");
+        let handler = TraceQuickCheckHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({ "traceId": "absent" })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, JsonRpcError::trace_not_found().code);
+    }
+}
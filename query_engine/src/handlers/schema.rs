@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use schemars::{schema::RootSchema, schema_for};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    handlers::{
+        envelope::ResponseEnvelope,
+        events::{EventsCountParams, EventsCountResponse, EventsGetParams, EventsGetResponse},
+        spans::{SpansGetParams, SpansGetResponse, SpansListParams, SpansListResponse},
+        trace_info::{TraceInfoParams, TraceInfoResponse},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemSchemaParams {
+    /// Only describe this method; every method when absent.
+    #[serde(default)]
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemSchemaResponse {
+    pub methods: BTreeMap<&'static str, MethodSchema>,
+}
+
+/// JSON Schemas of one method's params and result.
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodSchema {
+    pub params: RootSchema,
+    pub result: RootSchema,
+}
+
+/// Schemas generated from the serde types of every described method.
+///
+/// Each schema has its own generator, so two methods may define types of the
+/// same name, such as the `QueryMetadata` of `events.get` and `spans.list`.
+/// Results of enveloped methods include `schemaVersion` and `generatedAt`.
+pub fn method_schemas() -> BTreeMap<&'static str, MethodSchema> {
+    BTreeMap::from([
+        (
+            "events.get",
+            MethodSchema {
+                params: schema_for!(EventsGetParams),
+                result: schema_for!(ResponseEnvelope<EventsGetResponse>),
+            },
+        ),
+        (
+            "events.count",
+            MethodSchema {
+                params: schema_for!(EventsCountParams),
+                result: schema_for!(EventsCountResponse),
+            },
+        ),
+        (
+            "spans.list",
+            MethodSchema {
+                params: schema_for!(SpansListParams),
+                result: schema_for!(ResponseEnvelope<SpansListResponse>),
+            },
+        ),
+        (
+            "spans.get",
+            MethodSchema {
+                params: schema_for!(SpansGetParams),
+                result: schema_for!(SpansGetResponse),
+            },
+        ),
+        (
+            "trace.info",
+            MethodSchema {
+                params: schema_for!(TraceInfoParams),
+                result: schema_for!(ResponseEnvelope<TraceInfoResponse>),
+            },
+        ),
+    ])
+}
+
+#[derive(Clone, Default)]
+pub struct SystemSchemaHandler;
+
+impl SystemSchemaHandler {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("system.schema", self);
+    }
+
+    pub fn get_schema(
+        &self,
+        params: SystemSchemaParams,
+    ) -> Result<SystemSchemaResponse, JsonRpcError> {
+        let mut methods = method_schemas();
+        if let Some(method) = params.method {
+            let Some((name, schema)) = methods.remove_entry(method.as_str()) else {
+                return Err(JsonRpcError::invalid_params(format!(
+                    "no schema for method {method}"
+                )));
+            };
+            methods = BTreeMap::from([(name, schema)]);
+        }
+        Ok(SystemSchemaResponse { methods })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for SystemSchemaHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: SystemSchemaParams =
+            SystemSchemaParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid system.schema params: {err}"))
+            })?;
+
+        let response = self.get_schema(params)?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+
+    fn required(schema: &Value) -> Vec<&str> {
+        schema["required"]
+            .as_array()
+            .map(|fields| fields.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn system_schema__events_get__then_camel_case_and_optional_fields() {
+        let result = SystemSchemaHandler::new()
+            .call(Some(json!({ "method": "events.get" })))
+            .await
+            .expect("schema");
+
+        let methods = result["methods"].as_object().expect("methods");
+        assert_eq!(methods.keys().collect::<Vec<_>>(), vec!["events.get"]);
+        let params = &result["methods"]["events.get"]["params"];
+        assert!(params["properties"]["traceId"].is_object());
+        assert!(params["properties"]["groupByThread"].is_object());
+        assert_eq!(params["properties"]["limit"]["default"], 1000);
+        assert_eq!(required(params), vec!["traceId"]);
+        assert!(params["definitions"]["FilterExpr"]["anyOf"].is_array());
+        assert!(params["definitions"]["EventFilters"]["properties"]["timeStartNs"].is_object());
+
+        let event = &result["methods"]["events.get"]["result"]["definitions"]["EventResult"];
+        assert!(event["properties"]["timestampNs"].is_object());
+        assert!(required(event).is_empty());
+    }
+
+    #[tokio::test]
+    async fn system_schema__trace_info__then_skipped_fields_not_required() {
+        let result = SystemSchemaHandler::new().call(None).await.expect("schema");
+
+        assert!(result["methods"]["spans.list"].is_object());
+        let info = &result["methods"]["trace.info"]["result"];
+        let required = required(info);
+        assert!(required.contains(&"traceId"));
+        assert!(required.contains(&"eventCount"));
+        assert!(required.contains(&"schemaVersion"));
+        assert!(required.contains(&"generatedAt"));
+        assert!(!required.contains(&"checksums"));
+        assert!(!required.contains(&"wallClock"));
+    }
+
+    #[tokio::test]
+    async fn system_schema__unknown_method__then_invalid_params() {
+        let err = SystemSchemaHandler::new()
+            .call(Some(json!({ "method": "nope.nothing" })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, -32602);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{io, path::PathBuf, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::task;
+
+use crate::{
+    atf::{AtfV2Error, SessionReader, VerifyIssue},
+    handlers::paths::{resolve_trace_root, validate_trace_id},
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionVerifyParams {
+    pub trace_id: String,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionVerifyResponse {
+    pub trace_id: String,
+    /// True when `issues` is empty.
+    pub consistent: bool,
+    pub threads_checked: usize,
+    pub events_checked: u64,
+    pub issues: Vec<VerifyIssue>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Clone)]
+pub struct SessionVerifyHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+}
+
+impl SessionVerifyHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("session.verify", self);
+    }
+
+    fn map_v2_error(err: AtfV2Error) -> JsonRpcError {
+        match err {
+            AtfV2Error::Io { ref source, .. } if source.kind() == io::ErrorKind::NotFound => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to verify session: {other}")),
+        }
+    }
+
+    pub async fn verify(
+        &self,
+        params: SessionVerifyParams,
+    ) -> Result<SessionVerifyResponse, JsonRpcError> {
+        let trace_id = validate_trace_id(&params.trace_id)?.to_string();
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let session_dir = trace_root.join(&trace_id);
+        if !session_dir.is_dir() {
+            return Err(JsonRpcError::trace_not_found());
+        }
+        let start_time = Instant::now();
+
+        let report = task::spawn_blocking(move || SessionReader::open(&session_dir)?.verify())
+            .await
+            .map_err(|err| JsonRpcError::internal(format!("session verify task failed: {err}")))?
+            .map_err(Self::map_v2_error)?;
+
+        Ok(SessionVerifyResponse {
+            trace_id,
+            consistent: report.is_consistent(),
+            threads_checked: report.threads_checked,
+            events_checked: report.events_checked,
+            issues: report.issues,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for SessionVerifyHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: SessionVerifyParams = match params {
+            Some(value) => SessionVerifyParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid session.verify parameters: {err}"))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing session.verify parameters",
+                ))
+            }
+        };
+
+        let response = self.verify(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn session_verify__fixture_session__then_consistent() {
+        let fixture = TraceFixture::new("session");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x100),
+            function_return_event(200, 1, 0x100),
+            function_call_event(150, 2, 0x200),
+        ]);
+        let handler = SessionVerifyHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({ "traceId": "session" })))
+            .await
+            .expect("verify");
+
+        assert_eq!(response["consistent"], true, "{response}");
+        assert_eq!(response["threadsChecked"], 2);
+        assert_eq!(response["eventsChecked"], 3);
+    }
+
+    #[tokio::test]
+    async fn session_verify__thread_missing__then_reported_inconsistent() {
+        let root = TempDir::new().expect("tempdir");
+        let session = root.path().join("session");
+        fs::create_dir_all(&session).expect("session dir");
+        fs::write(
+            session.join("manifest.json"),
+            json!({ "threads": [{ "id": 3, "has_detail": false }] }).to_string(),
+        )
+        .expect("manifest");
+        let handler = SessionVerifyHandler::new(root.path().to_path_buf());
+
+        let response = handler
+            .call(Some(json!({ "traceId": "session" })))
+            .await
+            .expect("verify");
+
+        assert_eq!(response["consistent"], false);
+        assert_eq!(response["threadsChecked"], 0);
+        assert_eq!(response["issues"][0]["kind"], "missingThread");
+        assert_eq!(response["issues"][0]["threadId"], 3);
+    }
+
+    #[tokio::test]
+    async fn session_verify__unknown_trace__then_trace_not_found() {
+        let root = TempDir::new().expect("tempdir");
+        let handler = SessionVerifyHandler::new(root.path().to_path_buf());
+
+        let err = handler
+            .call(Some(json!({ "traceId": "absent" })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, JsonRpcError::trace_not_found().code);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task;
+
+use crate::{
+    atf::{AtfError, ParsedEventKind},
+    handlers::{
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, EventSource, SourceProvider},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalsSummaryParams {
+    pub trace_id: String,
+    /// Inclusive window bounds; unbounded when absent.
+    #[serde(default)]
+    pub time_start_ns: Option<u64>,
+    #[serde(default)]
+    pub time_end_ns: Option<u64>,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalsSummaryResponse {
+    pub delivery_count: u64,
+    /// Earliest and latest delivery in the window; absent when there was
+    /// none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_ns: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_ns: Option<u64>,
+    /// Threads that received a delivery, most deliveries first.
+    pub threads: Vec<ThreadSignals>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadSignals {
+    pub thread_id: u32,
+    pub count: u64,
+    pub first_ns: u64,
+    pub last_ns: u64,
+}
+
+/// Counts the signal deliveries of each thread in `source` between
+/// `start_ns` and `end_ns`.
+///
+/// V2 sessions record a delivery as an exception event without the signal
+/// number, which reaches handlers as `ParsedEventKind::Unknown`, so every
+/// delivery is counted together.
+pub(crate) fn thread_signals(
+    source: &dyn EventSource,
+    start_ns: Option<u64>,
+    end_ns: Option<u64>,
+) -> Result<Vec<ThreadSignals>, AtfError> {
+    let mut threads: BTreeMap<u32, ThreadSignals> = BTreeMap::new();
+
+    for item in source.events()? {
+        let event = item?;
+        if !matches!(event.kind, ParsedEventKind::Unknown)
+            || start_ns.is_some_and(|start| event.timestamp_ns < start)
+            || end_ns.is_some_and(|end| event.timestamp_ns > end)
+        {
+            continue;
+        }
+        let thread = threads
+            .entry(event.thread_id)
+            .or_insert_with(|| ThreadSignals {
+                thread_id: event.thread_id,
+                count: 0,
+                first_ns: event.timestamp_ns,
+                last_ns: event.timestamp_ns,
+            });
+        thread.count += 1;
+        thread.first_ns = thread.first_ns.min(event.timestamp_ns);
+        thread.last_ns = thread.last_ns.max(event.timestamp_ns);
+    }
+
+    let mut threads: Vec<ThreadSignals> = threads.into_values().collect();
+    // Stable, so ties stay in thread id order.
+    threads.sort_by_key(|thread| std::cmp::Reverse(thread.count));
+    Ok(threads)
+}
+
+#[derive(Clone)]
+pub struct SignalsSummaryHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
+}
+
+impl SignalsSummaryHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("signals.summary", self);
+    }
+
+    fn map_atf_error(err: AtfError) -> JsonRpcError {
+        match err {
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
+            other => JsonRpcError::internal(format!("failed to load trace: {other}")),
+        }
+    }
+
+    pub async fn get_summary(
+        &self,
+        params: SignalsSummaryParams,
+    ) -> Result<SignalsSummaryResponse, JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+        if let (Some(start), Some(end)) = (params.time_start_ns, params.time_end_ns) {
+            if start > end {
+                return Err(JsonRpcError::invalid_params(
+                    "timeStartNs must not be greater than timeEndNs",
+                ));
+            }
+        }
+
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        let source = Arc::clone(&self.source);
+        let (start_ns, end_ns) = (params.time_start_ns, params.time_end_ns);
+        let threads = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            thread_signals(events.as_ref(), start_ns, end_ns)
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("signals task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
+
+        Ok(SignalsSummaryResponse {
+            delivery_count: threads.iter().map(|thread| thread.count).sum(),
+            first_ns: threads.iter().map(|thread| thread.first_ns).min(),
+            last_ns: threads.iter().map(|thread| thread.last_ns).max(),
+            threads,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for SignalsSummaryHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: SignalsSummaryParams =
+            SignalsSummaryParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid signals.summary params: {err}"))
+            })?;
+
+        let response = self.get_summary(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::handlers::test_support::{
+        exception_event, function_call_event, function_return_event, TraceFixture,
+    };
+
+    #[tokio::test]
+    async fn signals_summary__v2_exceptions__then_counted_per_thread() {
+        let fixture = TraceFixture::new("crashy");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0xa),
+            exception_event(150, 4),
+            exception_event(250, 4),
+            exception_event(200, 2),
+            exception_event(350, 4),
+            function_return_event(400, 1, 0xa),
+        ]);
+        let handler = SignalsSummaryHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({ "traceId": "crashy" })))
+            .await
+            .expect("signals");
+
+        assert_eq!(result["deliveryCount"], 4);
+        assert_eq!(result["firstNs"], 150);
+        assert_eq!(result["lastNs"], 350);
+        assert_eq!(
+            result["threads"],
+            json!([
+                { "threadId": 4, "count": 3, "firstNs": 150, "lastNs": 350 },
+                { "threadId": 2, "count": 1, "firstNs": 200, "lastNs": 200 },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn signals_summary__time_window__then_only_window_deliveries() {
+        let fixture = TraceFixture::new("crashy");
+        fixture.write_events(&[
+            exception_event(100, 1),
+            exception_event(200, 1),
+            exception_event(300, 2),
+        ]);
+        let handler = SignalsSummaryHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({
+                "traceId": "crashy",
+                "timeStartNs": 150,
+                "timeEndNs": 250,
+            })))
+            .await
+            .expect("signals");
+        assert_eq!(result["deliveryCount"], 1);
+        assert_eq!(result["threads"][0]["threadId"], 1);
+
+        let result = handler
+            .call(Some(json!({ "traceId": "crashy", "timeStartNs": 400 })))
+            .await
+            .expect("signals");
+        assert_eq!(result["deliveryCount"], 0);
+        assert!(result.get("firstNs").is_none());
+
+        let err = handler
+            .call(Some(json!({
+                "traceId": "crashy",
+                "timeStartNs": 300,
+                "timeEndNs": 200,
+            })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, -32602);
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    atf::{
+        v2::{read_manifest_fields, rewrite_threads},
+        AtfV2Error, SessionReader,
+    },
+    handlers::export::run_export,
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceSliceParams {
+    pub trace_id: String,
+    /// Trace id of the slice, created next to the source trace.
+    pub output_trace_id: String,
+    /// Inclusive window bounds in trace nanoseconds; unbounded when absent.
+    #[serde(default)]
+    pub start_ns: Option<u64>,
+    #[serde(default)]
+    pub end_ns: Option<u64>,
+    /// Threads to keep; every thread when absent.
+    #[serde(default)]
+    pub thread_ids: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceSliceResponse {
+    pub trace_id: String,
+    pub event_count: u64,
+    /// Range of the events kept; absent when the window holds none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_start_ns: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_end_ns: Option<u64>,
+    /// Requested thread ids the source session has no stream for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_thread_ids: Vec<u32>,
+}
+
+/// Which events of the source session a slice keeps.
+#[derive(Debug, Clone)]
+struct SliceSelection {
+    start_ns: Option<u64>,
+    end_ns: Option<u64>,
+    thread_ids: Option<BTreeSet<u32>>,
+}
+
+impl SliceSelection {
+    fn contains(&self, timestamp_ns: u64) -> bool {
+        self.start_ns.is_none_or(|start| timestamp_ns >= start)
+            && self.end_ns.is_none_or(|end| timestamp_ns <= end)
+    }
+
+    fn keeps_thread(&self, thread_id: u32) -> bool {
+        self.thread_ids
+            .as_ref()
+            .is_none_or(|thread_ids| thread_ids.contains(&thread_id))
+    }
+}
+
+/// Copies the events selected by `selection`, with their detail records,
+/// into a new session at `output`. Threads left out are dropped from the
+/// new manifest altogether.
+fn slice_session(
+    source: &Path,
+    output: &Path,
+    selection: &SliceSelection,
+) -> Result<TraceSliceResponse, AtfV2Error> {
+    let session = SessionReader::open(source)?;
+    let manifest = read_manifest_fields(source)?;
+    let threads = session
+        .threads()
+        .iter()
+        .filter(|thread| selection.keeps_thread(thread.thread_id()));
+    let written = rewrite_threads(threads, output, manifest, |thread, writer| {
+        for event in thread.index.iter() {
+            if selection.contains(event.timestamp_ns) {
+                writer.copy_event(thread, event)?;
+            }
+        }
+        Ok(())
+    })?;
+
+    let missing_thread_ids = selection
+        .thread_ids
+        .iter()
+        .flatten()
+        .filter(|&&thread_id| {
+            !session
+                .threads()
+                .iter()
+                .any(|thread| thread.thread_id() == thread_id)
+        })
+        .copied()
+        .collect();
+
+    let ranges = || written.iter().filter_map(|thread| thread.time_range);
+    Ok(TraceSliceResponse {
+        trace_id: String::new(),
+        event_count: written.iter().map(|thread| thread.event_count).sum(),
+        time_start_ns: ranges().map(|(start, _)| start).min(),
+        time_end_ns: ranges().map(|(_, end)| end).max(),
+        missing_thread_ids,
+    })
+}
+
+#[derive(Clone)]
+pub struct TraceSliceHandler {
+    trace_root_dir: PathBuf,
+}
+
+impl TraceSliceHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self { trace_root_dir }
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.slice", self);
+    }
+
+    pub async fn slice(
+        &self,
+        params: TraceSliceParams,
+    ) -> Result<TraceSliceResponse, JsonRpcError> {
+        if let (Some(start_ns), Some(end_ns)) = (params.start_ns, params.end_ns) {
+            if start_ns > end_ns {
+                return Err(JsonRpcError::invalid_params(
+                    "startNs must not be greater than endNs",
+                ));
+            }
+        }
+
+        if params.thread_ids.as_ref().is_some_and(Vec::is_empty) {
+            return Err(JsonRpcError::invalid_params("threadIds must not be empty"));
+        }
+
+        let selection = SliceSelection {
+            start_ns: params.start_ns,
+            end_ns: params.end_ns,
+            thread_ids: params.thread_ids.map(BTreeSet::from_iter),
+        };
+        let response = run_export(
+            &self.trace_root_dir,
+            &params.trace_id,
+            &params.output_trace_id,
+            move |source, output| slice_session(source, output, &selection),
+        )
+        .await?;
+
+        Ok(TraceSliceResponse {
+            trace_id: params.output_trace_id.trim().to_string(),
+            ..response
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TraceSliceHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TraceSliceParams = match params {
+            Some(value) => TraceSliceParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid trace.slice parameters: {err}"))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing trace.slice parameters",
+                ))
+            }
+        };
+
+        let response = self.slice(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::v2::{ATF_DETAIL_EVENT_FUNCTION_CALL, ATF_DETAIL_EVENT_FUNCTION_RETURN};
+    use crate::handlers::test_support::{
+        function_call_event, function_detail, function_return_event, TraceFixture,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn trace_slice__time_window__then_new_session_with_window_events() {
+        let fixture = TraceFixture::new("full");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x100),
+            function_return_event(200, 1, 0x100),
+            function_call_event(300, 1, 0x200),
+            function_return_event(400, 1, 0x200),
+            function_call_event(250, 2, 0x300),
+        ]);
+        fixture.edit_manifest(|manifest| manifest["clock_type"] = json!(1));
+        let handler = TraceSliceHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "slice",
+                "startNs": 200,
+                "endNs": 300,
+            })))
+            .await
+            .expect("slice");
+
+        assert_eq!(
+            response,
+            json!({
+                "traceId": "slice",
+                "eventCount": 3,
+                "timeStartNs": 200,
+                "timeEndNs": 300,
+            })
+        );
+        let session = SessionReader::open(&fixture.trace_root().join("slice")).expect("open slice");
+        assert_eq!(session.manifest().time_start_ns, 200);
+        assert_eq!(session.manifest().time_end_ns, 300);
+        let timestamps: Vec<u64> = session
+            .merged_iter()
+            .map(|(_, event)| event.timestamp_ns)
+            .collect();
+        assert_eq!(timestamps, vec![200, 250, 300]);
+        let fields = read_manifest_fields(&fixture.trace_root().join("slice")).expect("fields");
+        assert_eq!(fields["clock_type"], 1);
+    }
+
+    #[tokio::test]
+    async fn trace_slice__events_with_details__then_details_relinked() {
+        let fixture = TraceFixture::new("full");
+        let mut call = function_call_event(100, 1, 0x100);
+        call.detail_seq = 0;
+        let mut ret = function_return_event(200, 1, 0x100);
+        ret.detail_seq = 1;
+        fixture.write_events(&[call, ret]);
+        fixture.write_detail(
+            1,
+            &[
+                function_detail(ATF_DETAIL_EVENT_FUNCTION_CALL, 0, 100, 1, 0x100, &[1]),
+                function_detail(ATF_DETAIL_EVENT_FUNCTION_RETURN, 1, 200, 1, 0x100, &[2]),
+            ],
+        );
+        let handler = TraceSliceHandler::new(fixture.trace_root());
+
+        handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "slice",
+                "startNs": 150,
+            })))
+            .await
+            .expect("slice");
+
+        let session = SessionReader::open(&fixture.trace_root().join("slice")).expect("open slice");
+        assert!(session.verify().expect("verify").is_consistent());
+        let thread = &session.threads()[0];
+        let event = thread.index.get(0).expect("event");
+        let detail = thread.get_detail_for(event).expect("detail");
+        assert_eq!(detail.stack_snapshot(), Some(&[2u8][..]));
+    }
+
+    #[tokio::test]
+    async fn trace_slice__thread_ids__then_other_threads_dropped_and_missing_reported() {
+        let fixture = TraceFixture::new("full");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x100),
+            function_call_event(150, 2, 0x200),
+            function_return_event(200, 2, 0x200),
+            function_call_event(250, 3, 0x300),
+        ]);
+        let handler = TraceSliceHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "worker",
+                "threadIds": [2, 9],
+            })))
+            .await
+            .expect("slice");
+
+        assert_eq!(response["eventCount"], 2);
+        assert_eq!(response["missingThreadIds"], json!([9]));
+        let output = fixture.trace_root().join("worker");
+        let session = SessionReader::open(&output).expect("open slice");
+        let thread_ids: Vec<u32> = session.manifest().threads.iter().map(|t| t.id).collect();
+        assert_eq!(thread_ids, vec![2]);
+        assert!(!output.join("thread_1").exists());
+        assert_eq!(session.manifest().time_start_ns, 150);
+        assert_eq!(session.manifest().time_end_ns, 200);
+    }
+
+    #[tokio::test]
+    async fn trace_slice__empty_thread_ids__then_invalid_params() {
+        let fixture = TraceFixture::new("full");
+        let handler = TraceSliceHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "slice",
+                "threadIds": [],
+            })))
+            .await
+            .expect_err("expected error");
+
+        assert_eq!(err.code, -32602);
+    }
+
+    #[tokio::test]
+    async fn trace_slice__output_exists__then_invalid_params() {
+        let fixture = TraceFixture::new("full");
+        fixture.write_events(&[function_call_event(100, 1, 0x100)]);
+        std::fs::create_dir(fixture.trace_root().join("taken")).expect("taken dir");
+        let handler = TraceSliceHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({ "traceId": "full", "outputTraceId": "taken" })))
+            .await
+            .expect_err("expected error");
+
+        assert_eq!(err.code, -32602);
+        assert!(fixture.trace_root().join("taken").is_dir());
+    }
+
+    #[tokio::test]
+    async fn trace_slice__inverted_window__then_invalid_params() {
+        let fixture = TraceFixture::new("full");
+        let handler = TraceSliceHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "slice",
+                "startNs": 300,
+                "endNs": 200,
+            })))
+            .await
+            .expect_err("expected error");
+
+        assert_eq!(err.code, -32602);
+    }
+
+    #[tokio::test]
+    async fn trace_slice__unknown_trace__then_trace_not_found() {
+        let fixture = TraceFixture::new("full");
+        let handler = TraceSliceHandler::new(fixture.trace_root());
+
+        let err = handler
+            .call(Some(
+                json!({ "traceId": "absent", "outputTraceId": "slice" }),
+            ))
+            .await
+            .expect_err("expected error");
+
+        assert_eq!(err.code, JsonRpcError::trace_not_found().code);
+        assert!(!fixture.trace_root().join("slice").exists());
+    }
+}
@@ -0,0 +1,171 @@
+//! Snapshot tests for handler responses over a canonical trace.
+//!
+//! Each test serializes the JSON-RPC result (or error) exactly as a client
+//! receives it and compares it to `snapshots/<name>.json`. Timing fields, the
+//! `generatedAt` stamp of enveloped responses and the temporary trace root are
+//! redacted so the output is deterministic.
+//!
+//! After an intentional response change, regenerate with
+//! `UPDATE_SNAPSHOTS=1 cargo test handlers::snapshot_tests` and review the
+//! diff. A missing snapshot is written on first run and fails the test.
+
+#![allow(non_snake_case)]
+
+use std::{fs, path::Path};
+
+use serde_json::{json, Value};
+
+use crate::{
+    handlers::{
+        test_support::{function_call_event as call, function_return_event as ret, TraceFixture},
+        EventsGetHandler, SpansListHandler, TraceInfoHandler,
+    },
+    server::handler::{JsonRpcHandler, JsonRpcResult},
+};
+
+const TRACE_ID: &str = "canonical";
+const REDACTED_KEYS: [&str; 2] = ["executionTimeMs", "generatedAt"];
+
+const MAIN: u64 = 0x100;
+const PARSE_ARGS: u64 = 0x200;
+const WORKER: u64 = 0x300;
+
+/// Trace root holding one canonical trace: nested calls on thread 1 and an
+/// overlapping call on thread 2.
+fn canonical_trace() -> TraceFixture {
+    let trace = TraceFixture::new(TRACE_ID);
+    trace.write_events(&[
+        call(100, 1, MAIN),
+        call(150, 1, PARSE_ARGS),
+        call(200, 2, WORKER),
+        ret(250, 1, PARSE_ARGS),
+        ret(300, 2, WORKER),
+        ret(400, 1, MAIN),
+    ]);
+    trace
+}
+
+/// Replaces timing fields and the temporary trace root with placeholders
+fn redact(value: &mut Value, trace_root: &str) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if REDACTED_KEYS.contains(&key.as_str()) {
+                    *field = Value::String("[redacted]".into());
+                } else {
+                    redact(field, trace_root);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, trace_root)),
+        Value::String(text) if text.contains(trace_root) => {
+            *text = text.replace(trace_root, "[trace_root]");
+        }
+        _ => {}
+    }
+}
+
+/// Compares a handler outcome to `snapshots/<name>.json`
+fn assert_snapshot(name: &str, result: JsonRpcResult, trace_root: &Path) {
+    let mut actual = match result {
+        Ok(value) => json!({ "result": value }),
+        Err(err) => json!({ "error": err }),
+    };
+    redact(&mut actual, &trace_root.to_string_lossy());
+    let actual = format!(
+        "{}\n",
+        serde_json::to_string_pretty(&actual).expect("serialize snapshot")
+    );
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/handlers/snapshots")
+        .join(format!("{name}.json"));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    match fs::read_to_string(&path) {
+        Ok(expected) if !update => assert_eq!(
+            actual, expected,
+            "snapshot {name} changed; rerun with UPDATE_SNAPSHOTS=1 if intended"
+        ),
+        Ok(_) => fs::write(&path, actual).expect("update snapshot"),
+        Err(_) => {
+            fs::create_dir_all(path.parent().unwrap()).expect("snapshot dir");
+            fs::write(&path, actual).expect("write snapshot");
+            assert!(
+                update,
+                "new snapshot written to {}; review it",
+                path.display()
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn snapshot__events_get__then_matches() {
+    let trace = canonical_trace();
+    let handler = EventsGetHandler::new(trace.trace_root());
+
+    let result = handler.call(Some(json!({ "traceId": TRACE_ID }))).await;
+    assert_snapshot("events_get", result, &trace.trace_root());
+}
+
+#[tokio::test]
+async fn snapshot__events_get_filtered_page__then_matches() {
+    let trace = canonical_trace();
+    let handler = EventsGetHandler::new(trace.trace_root());
+
+    let params = json!({
+        "traceId": TRACE_ID,
+        "filters": { "threadIds": [1] },
+        "offset": 1,
+        "limit": 2,
+    });
+    let result = handler.call(Some(params)).await;
+    assert_snapshot("events_get_filtered_page", result, &trace.trace_root());
+}
+
+#[tokio::test]
+async fn snapshot__spans_list__then_matches() {
+    let trace = canonical_trace();
+    let handler = SpansListHandler::new(trace.trace_root());
+
+    let result = handler.call(Some(json!({ "traceId": TRACE_ID }))).await;
+    assert_snapshot("spans_list", result, &trace.trace_root());
+}
+
+#[tokio::test]
+async fn snapshot__trace_info__then_matches() {
+    let trace = canonical_trace();
+    let handler = TraceInfoHandler::new(trace.trace_root(), 10, std::time::Duration::from_secs(60));
+
+    let params = json!({
+        "traceId": TRACE_ID,
+        "include_checksums": true,
+        "include_samples": true,
+    });
+    let result = handler.call(Some(params)).await;
+    assert_snapshot("trace_info", result, &trace.trace_root());
+}
+
+#[tokio::test]
+async fn snapshot__missing_trace__then_error_matches() {
+    let trace = canonical_trace();
+    let handler = TraceInfoHandler::new(trace.trace_root(), 10, std::time::Duration::from_secs(60));
+
+    let result = handler.call(Some(json!({ "traceId": "missing" }))).await;
+    assert_snapshot("error_trace_not_found", result, &trace.trace_root());
+}
+
+#[tokio::test]
+async fn snapshot__invalid_params__then_error_matches() {
+    let trace = canonical_trace();
+    let events = EventsGetHandler::new(trace.trace_root());
+    let spans = SpansListHandler::new(trace.trace_root());
+
+    let result = events
+        .call(Some(json!({ "traceId": TRACE_ID, "limit": 50_000 })))
+        .await;
+    assert_snapshot("error_events_get_limit", result, &trace.trace_root());
+
+    let result = spans.call(Some(json!({ "traceId": "../escape" }))).await;
+    assert_snapshot("error_spans_list_trace_id", result, &trace.trace_root());
+}
@@ -0,0 +1,550 @@
+//! Pluggable event sources for handlers.
+//!
+//! Handlers read a trace's manifest and decoded events through
+//! [`EventSource`] and open traces through a [`SourceProvider`]. Production
+//! uses [`AtfSourceProvider`], which opens V2 sessions on disk or by URL
+//! through [`open_trace`]; tests and embedders can serve events from memory with
+//! [`MemorySourceProvider`].
+//! Either can be wrapped in a [`RemappedSourceProvider`] to read thread ids
+//! as compact sequential ids.
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atf::{
+    arch_name, os_name,
+    v2::{is_remote_url, ATF_EVENT_KIND_CALL, ATF_EVENT_KIND_RETURN},
+    AtfError, IndexEvent, ManifestInfo, ParsedEvent, ParsedEventKind, SessionReader,
+};
+
+/// Iterator over a trace's decoded events, in stream order
+pub type EventIter<'a> = Box<dyn Iterator<Item = Result<ParsedEvent, AtfError>> + 'a>;
+
+/// A trace's manifest and its decoded events
+pub trait EventSource {
+    fn manifest(&self) -> &ManifestInfo;
+
+    /// Starts a fresh pass over the events
+    fn events(&self) -> Result<EventIter<'_>, AtfError>;
+}
+
+/// Opens the event source for a trace directory
+pub trait SourceProvider: Send + Sync {
+    fn open(&self, trace_dir: &Path) -> Result<Box<dyn EventSource>, AtfError>;
+}
+
+/// Opens the V2 session in `trace_dir`, with its per-thread streams merged
+/// into global timestamp order.
+///
+/// A `trace_dir` spelling an `http://`, `https://` or `s3://` URL, as a
+/// trace id joined onto a URL trace root does, is fetched remotely.
+pub fn open_trace(trace_dir: &Path) -> Result<Box<dyn EventSource>, AtfError> {
+    Ok(Box::new(V2EventSource::open(trace_dir)?))
+}
+
+/// Opens traces from disk or by URL with [`open_trace`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AtfSourceProvider;
+
+impl SourceProvider for AtfSourceProvider {
+    fn open(&self, trace_dir: &Path) -> Result<Box<dyn EventSource>, AtfError> {
+        open_trace(trace_dir)
+    }
+}
+
+/// A V2 session read as a single event stream
+///
+/// Index events carry a function id rather than a name, so calls and
+/// returns are reported with the id in hex as their symbol, which keeps
+/// distinct functions apart for span and timing queries. Exception events
+/// decode as `ParsedEventKind::Unknown`.
+pub struct V2EventSource {
+    session: SessionReader,
+    manifest: ManifestInfo,
+}
+
+impl V2EventSource {
+    pub fn open(session_dir: &Path) -> Result<Self, AtfError> {
+        let session = match session_dir.to_str() {
+            Some(location) if is_remote_url(location) => SessionReader::open_url(location),
+            _ => SessionReader::open(session_dir),
+        }
+        .map_err(|err| AtfError::from_v2(session_dir, err))?;
+        Ok(Self::new(session))
+    }
+
+    /// Summarizes an open session; the platform comes from the first
+    /// thread's index header, as every thread records the same one.
+    pub fn new(session: SessionReader) -> Self {
+        let (time_start_ns, time_end_ns) = session.time_range();
+        let (os, arch) = session
+            .threads()
+            .first()
+            .map_or(("unknown", "unknown"), |thread| {
+                (os_name(thread.index.os()), arch_name(thread.index.arch()))
+            });
+        let manifest = ManifestInfo {
+            os: os.to_string(),
+            arch: arch.to_string(),
+            time_start_ns,
+            time_end_ns,
+            event_count: session.event_count(),
+        };
+        Self { session, manifest }
+    }
+
+    /// The session, for handlers that also read detail records
+    pub fn session(&self) -> &SessionReader {
+        &self.session
+    }
+}
+
+pub(crate) fn parse_index_event(event: &IndexEvent) -> ParsedEvent {
+    let symbol = Some(format!("{:#x}", { event.function_id }));
+    ParsedEvent {
+        timestamp_ns: event.timestamp_ns,
+        thread_id: event.thread_id,
+        kind: match event.event_kind {
+            ATF_EVENT_KIND_CALL => ParsedEventKind::FunctionCall { symbol },
+            ATF_EVENT_KIND_RETURN => ParsedEventKind::FunctionReturn { symbol },
+            _ => ParsedEventKind::Unknown,
+        },
+    }
+}
+
+impl EventSource for V2EventSource {
+    fn manifest(&self) -> &ManifestInfo {
+        &self.manifest
+    }
+
+    fn events(&self) -> Result<EventIter<'_>, AtfError> {
+        Ok(Box::new(
+            self.session
+                .merged_iter()
+                .map(|(_, event)| Ok(parse_index_event(event))),
+        ))
+    }
+}
+
+/// Events held in memory, with a manifest derived from them
+#[derive(Debug, Clone)]
+pub struct MemoryEventSource {
+    manifest: ManifestInfo,
+    events: Vec<ParsedEvent>,
+}
+
+impl MemoryEventSource {
+    /// Builds a source whose manifest covers `events` the way the tracer
+    /// would have written it: time bounds from the events and their count.
+    pub fn new(events: Vec<ParsedEvent>) -> Self {
+        let manifest = ManifestInfo {
+            os: "unknown".to_string(),
+            arch: "unknown".to_string(),
+            time_start_ns: events.iter().map(|e| e.timestamp_ns).min().unwrap_or(0),
+            time_end_ns: events.iter().map(|e| e.timestamp_ns).max().unwrap_or(0),
+            event_count: events.len() as u64,
+        };
+        Self { manifest, events }
+    }
+
+    pub fn with_manifest(manifest: ManifestInfo, events: Vec<ParsedEvent>) -> Self {
+        Self { manifest, events }
+    }
+}
+
+impl EventSource for MemoryEventSource {
+    fn manifest(&self) -> &ManifestInfo {
+        &self.manifest
+    }
+
+    fn events(&self) -> Result<EventIter<'_>, AtfError> {
+        Ok(Box::new(self.events.iter().cloned().map(Ok)))
+    }
+}
+
+/// Serves in-memory traces by trace id, without touching the filesystem
+#[derive(Debug, Clone, Default)]
+pub struct MemorySourceProvider {
+    traces: HashMap<String, Arc<MemoryEventSource>>,
+}
+
+impl MemorySourceProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_trace(mut self, trace_id: impl Into<String>, source: MemoryEventSource) -> Self {
+        self.traces.insert(trace_id.into(), Arc::new(source));
+        self
+    }
+}
+
+impl SourceProvider for MemorySourceProvider {
+    /// Looks the trace up by the last component of `trace_dir`, its trace id
+    fn open(&self, trace_dir: &Path) -> Result<Box<dyn EventSource>, AtfError> {
+        let source = trace_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|trace_id| self.traces.get(trace_id))
+            .ok_or_else(|| AtfError::TraceNotFound(trace_dir.to_path_buf()))?;
+        Ok(Box::new(Arc::clone(source)))
+    }
+}
+
+impl<T: EventSource + ?Sized> EventSource for Arc<T> {
+    fn manifest(&self) -> &ManifestInfo {
+        (**self).manifest()
+    }
+
+    fn events(&self) -> Result<EventIter<'_>, AtfError> {
+        (**self).events()
+    }
+}
+
+/// Compact thread ids assigned in the order threads first appear in a trace.
+///
+/// The first thread seen is 0, the next 1 and so on. The order only depends
+/// on the event stream, so the same trace always maps the same way.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadIdMap {
+    original_ids: Vec<u32>,
+    compact_ids: HashMap<u32, u32>,
+}
+
+/// One entry of a [`ThreadIdMap`], as reported to clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadIdMapping {
+    pub thread_id: u32,
+    pub original_thread_id: u32,
+}
+
+impl ThreadIdMap {
+    /// Assigns ids to the threads of `source` in one pass over its events.
+    pub fn scan(source: &dyn EventSource) -> Result<Self, AtfError> {
+        let mut map = Self::default();
+        for item in source.events()? {
+            map.assign(item?.thread_id);
+        }
+        Ok(map)
+    }
+
+    fn assign(&mut self, original_id: u32) -> u32 {
+        let next_id = self.original_ids.len() as u32;
+        *self.compact_ids.entry(original_id).or_insert_with(|| {
+            self.original_ids.push(original_id);
+            next_id
+        })
+    }
+
+    /// Compact id of a thread id as recorded in the trace
+    pub fn compact_id(&self, original_id: u32) -> Option<u32> {
+        self.compact_ids.get(&original_id).copied()
+    }
+
+    /// Thread id as recorded in the trace for a compact id
+    pub fn original_id(&self, compact_id: u32) -> Option<u32> {
+        self.original_ids.get(compact_id as usize).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.original_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.original_ids.is_empty()
+    }
+
+    /// Every thread, ordered by compact id
+    pub fn mappings(&self) -> Vec<ThreadIdMapping> {
+        self.original_ids
+            .iter()
+            .enumerate()
+            .map(|(thread_id, &original_thread_id)| ThreadIdMapping {
+                thread_id: thread_id as u32,
+                original_thread_id,
+            })
+            .collect()
+    }
+}
+
+/// Reads another source with its thread ids replaced by compact ids
+pub struct RemappedEventSource<S> {
+    inner: S,
+    map: ThreadIdMap,
+}
+
+impl<S: EventSource> RemappedEventSource<S> {
+    /// Scans `inner` once to assign the ids, so the mapping is known before
+    /// the first event is read.
+    pub fn new(inner: S) -> Result<Self, AtfError> {
+        let map = ThreadIdMap::scan(&inner)?;
+        Ok(Self { inner, map })
+    }
+
+    /// Translates compact ids back to the ids recorded in the trace
+    pub fn thread_id_map(&self) -> &ThreadIdMap {
+        &self.map
+    }
+}
+
+impl<S: EventSource> EventSource for RemappedEventSource<S> {
+    fn manifest(&self) -> &ManifestInfo {
+        self.inner.manifest()
+    }
+
+    fn events(&self) -> Result<EventIter<'_>, AtfError> {
+        let events = self.inner.events()?;
+        Ok(Box::new(events.map(move |item| {
+            let mut event = item?;
+            // Every id was assigned by the scan; a trace that changed on disk
+            // since then keeps the unknown id rather than failing mid-stream.
+            if let Some(thread_id) = self.map.compact_id(event.thread_id) {
+                event.thread_id = thread_id;
+            }
+            Ok(event)
+        })))
+    }
+}
+
+/// Opens traces through another provider with thread ids remapped
+pub struct RemappedSourceProvider<P> {
+    inner: P,
+}
+
+impl<P: SourceProvider> RemappedSourceProvider<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+
+    /// Opens the trace and returns it with its mapping, for callers that
+    /// report original thread ids alongside compact ones.
+    pub fn open_remapped(
+        &self,
+        trace_dir: &Path,
+    ) -> Result<RemappedEventSource<Box<dyn EventSource>>, AtfError> {
+        RemappedEventSource::new(self.inner.open(trace_dir)?)
+    }
+}
+
+impl<P: SourceProvider> SourceProvider for RemappedSourceProvider<P> {
+    fn open(&self, trace_dir: &Path) -> Result<Box<dyn EventSource>, AtfError> {
+        Ok(Box::new(self.open_remapped(trace_dir)?))
+    }
+}
+
+impl<T: EventSource + ?Sized> EventSource for Box<T> {
+    fn manifest(&self) -> &ManifestInfo {
+        (**self).manifest()
+    }
+
+    fn events(&self) -> Result<EventIter<'_>, AtfError> {
+        (**self).events()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::handlers::test_support::{function_call_event, index_event, TraceFixture};
+
+    fn call(timestamp_ns: u64, thread_id: u32) -> ParsedEvent {
+        ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: ParsedEventKind::FunctionCall {
+                symbol: Some("main".into()),
+            },
+        }
+    }
+
+    #[test]
+    fn memory_source__new__then_manifest_covers_events() {
+        let source = MemoryEventSource::new(vec![call(300, 1), call(100, 2), call(200, 1)]);
+
+        assert_eq!(source.manifest().time_start_ns, 100);
+        assert_eq!(source.manifest().time_end_ns, 300);
+        assert_eq!(source.manifest().event_count, 3);
+        let timestamps: Vec<u64> = source
+            .events()
+            .expect("events")
+            .map(|event| event.expect("event").timestamp_ns)
+            .collect();
+        assert_eq!(timestamps, vec![300, 100, 200]);
+    }
+
+    #[test]
+    fn memory_provider__open__then_found_by_trace_id() {
+        let provider = MemorySourceProvider::new()
+            .with_trace("trace_a", MemoryEventSource::new(vec![call(1, 1)]));
+
+        let source = provider
+            .open(Path::new("/virtual/root/trace_a"))
+            .expect("open");
+        assert_eq!(source.manifest().event_count, 1);
+
+        let err = provider
+            .open(Path::new("/virtual/root/trace_b"))
+            .err()
+            .expect("missing trace");
+        assert!(matches!(err, AtfError::TraceNotFound(_)));
+    }
+
+    #[test]
+    fn remapped_source__large_thread_ids__then_sequential_in_first_seen_order() {
+        let source = MemoryEventSource::new(vec![
+            call(1, 90_412),
+            call(2, 77_001),
+            call(3, 90_412),
+            call(4, 12),
+        ]);
+
+        let remapped = RemappedEventSource::new(source).expect("remap");
+
+        let thread_ids: Vec<u32> = remapped
+            .events()
+            .expect("events")
+            .map(|event| event.expect("event").thread_id)
+            .collect();
+        assert_eq!(thread_ids, vec![0, 1, 0, 2]);
+        let map = remapped.thread_id_map();
+        assert_eq!(map.original_id(1), Some(77_001));
+        assert_eq!(map.compact_id(12), Some(2));
+        assert_eq!(map.original_id(3), None);
+        assert_eq!(
+            map.mappings()[0],
+            ThreadIdMapping {
+                thread_id: 0,
+                original_thread_id: 90_412,
+            }
+        );
+    }
+
+    #[test]
+    fn remapped_provider__open_twice__then_same_mapping() {
+        let provider = RemappedSourceProvider::new(MemorySourceProvider::new().with_trace(
+            "trace_a",
+            MemoryEventSource::new(vec![call(1, 5), call(2, 3), call(3, 4)]),
+        ));
+        let trace_dir = Path::new("/virtual/root/trace_a");
+
+        let first = provider.open_remapped(trace_dir).expect("open");
+        let second = provider.open_remapped(trace_dir).expect("open");
+
+        assert_eq!(first.thread_id_map(), second.thread_id_map());
+        assert_eq!(first.thread_id_map().len(), 3);
+        let source = provider.open(trace_dir).expect("open");
+        assert_eq!(source.manifest().event_count, 3);
+    }
+
+    #[test]
+    fn open_trace__v2_session__then_threads_merged_in_timestamp_order() {
+        let fixture = TraceFixture::new("session");
+        fixture.write_v2_session(&[
+            (
+                7,
+                vec![
+                    index_event(100, 7, ATF_EVENT_KIND_CALL, 0x1_0000_0001),
+                    index_event(400, 7, ATF_EVENT_KIND_RETURN, 0x1_0000_0001),
+                ],
+            ),
+            (
+                9,
+                vec![
+                    index_event(200, 9, ATF_EVENT_KIND_CALL, 0x1_0000_0002),
+                    index_event(300, 9, 3, 0),
+                ],
+            ),
+        ]);
+
+        let source = AtfSourceProvider.open(&fixture.trace_dir()).expect("open");
+
+        assert_eq!(source.manifest().event_count, 4);
+        assert_eq!(source.manifest().time_start_ns, 100);
+        assert_eq!(source.manifest().time_end_ns, 400);
+        let events: Vec<ParsedEvent> = source
+            .events()
+            .expect("events")
+            .collect::<Result<_, _>>()
+            .expect("decode");
+        let order: Vec<(u64, u32)> = events
+            .iter()
+            .map(|event| (event.timestamp_ns, event.thread_id))
+            .collect();
+        assert_eq!(order, vec![(100, 7), (200, 9), (300, 9), (400, 7)]);
+        assert_eq!(
+            events[0].kind,
+            ParsedEventKind::FunctionCall {
+                symbol: Some("0x100000001".into()),
+            }
+        );
+        assert_eq!(events[2].kind, ParsedEventKind::Unknown);
+        assert_eq!(
+            events[3].kind,
+            ParsedEventKind::FunctionReturn {
+                symbol: Some("0x100000001".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn open_trace__v2_session__then_platform_from_index_header() {
+        let fixture = TraceFixture::new("platform");
+        fixture.write_events(&[function_call_event(500, 1, 0xa)]);
+
+        let source = open_trace(&fixture.trace_dir()).expect("open");
+
+        assert_eq!(source.manifest().os, "linux");
+        assert_eq!(source.manifest().arch, "x86_64");
+    }
+
+    #[tokio::test]
+    async fn open_trace__http_trace_root__then_handler_reads_remote_session() {
+        use crate::handlers::events::EventsCountHandler;
+        use crate::server::handler::JsonRpcHandler;
+        use serde_json::json;
+
+        let fixture = TraceFixture::new("remote");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0xa),
+            function_call_event(200, 2, 0xb),
+            index_event(300, 1, ATF_EVENT_KIND_RETURN, 0xa),
+        ]);
+        let base_url = fixture.serve_over_http();
+        let handler = EventsCountHandler::new(base_url.clone().into());
+
+        let result = handler
+            .call(Some(json!({ "traceId": "remote", "byThread": true })))
+            .await
+            .expect("count");
+        assert_eq!(result["totalCount"], 3);
+        assert_eq!(result["byThread"], json!({ "1": 2, "2": 1 }));
+
+        // The fetcher owns a runtime, which must not be dropped on this one.
+        let missing = Path::new(&format!("{base_url}/missing")).to_path_buf();
+        let err = tokio::task::spawn_blocking(move || open_trace(&missing).err())
+            .await
+            .expect("open task")
+            .expect("no remote trace");
+        assert!(
+            matches!(&err, AtfError::Fetch { url, .. } if url.ends_with("/missing/manifest.json")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn open_trace__missing_trace_or_manifest__then_not_found_errors() {
+        let fixture = TraceFixture::new("empty");
+
+        let err = open_trace(&fixture.trace_dir()).err().expect("no manifest");
+        assert!(matches!(err, AtfError::ManifestNotFound(_)));
+
+        let err = open_trace(&fixture.trace_root().join("missing"))
+            .err()
+            .expect("no trace");
+        assert!(matches!(err, AtfError::TraceNotFound(_)));
+    }
+}
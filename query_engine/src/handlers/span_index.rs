@@ -0,0 +1,513 @@
+use std::{
+    collections::BTreeMap,
+    fs, mem,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task;
+
+use crate::{
+    handlers::{
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, SourceProvider},
+        spans::{reconstruct_spans, SpanCandidate, SpanProjection, SpanResult, SpansListHandler},
+        trace_info::EventFiles,
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        memory::{BudgetedLru, CacheWeight, MemoryBudget},
+        types::JsonRpcError,
+    },
+};
+
+/// Static interval tree over one thread's spans.
+///
+/// Spans are sorted by start time and viewed as an implicit balanced tree:
+/// the root of `lo..hi` is its midpoint. `max_end[i]` holds the latest end
+/// time in the subtree rooted at `i`, which lets a query skip every subtree
+/// that finished before the queried time.
+#[derive(Debug)]
+struct IntervalTree {
+    spans: Vec<SpanCandidate>,
+    max_end: Vec<u64>,
+}
+
+impl IntervalTree {
+    fn new(mut spans: Vec<SpanCandidate>) -> Self {
+        spans.sort_by_key(|span| span.start_time_ns);
+        let mut max_end = vec![0; spans.len()];
+        Self::fill_max_end(&spans, &mut max_end, 0, spans.len());
+        Self { spans, max_end }
+    }
+
+    fn fill_max_end(spans: &[SpanCandidate], max_end: &mut [u64], lo: usize, hi: usize) -> u64 {
+        if lo >= hi {
+            return 0;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = Self::fill_max_end(spans, max_end, lo, mid);
+        let right = Self::fill_max_end(spans, max_end, mid + 1, hi);
+        max_end[mid] = spans[mid].end_time_ns.max(left).max(right);
+        max_end[mid]
+    }
+
+    fn visit_active<'a>(&'a self, timestamp_ns: u64, out: &mut Vec<&'a SpanCandidate>) {
+        self.visit_range(timestamp_ns, 0, self.spans.len(), out);
+    }
+
+    fn visit_range<'a>(
+        &'a self,
+        timestamp_ns: u64,
+        lo: usize,
+        hi: usize,
+        out: &mut Vec<&'a SpanCandidate>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] < timestamp_ns {
+            return;
+        }
+        self.visit_range(timestamp_ns, lo, mid, out);
+        let span = &self.spans[mid];
+        // Everything right of `mid` starts no earlier, so none of it can
+        // contain a time before this span's start.
+        if span.start_time_ns > timestamp_ns {
+            return;
+        }
+        if span.end_time_ns >= timestamp_ns {
+            out.push(span);
+        }
+        self.visit_range(timestamp_ns, mid + 1, hi, out);
+    }
+}
+
+/// Per-thread interval trees over a trace's reconstructed spans.
+#[derive(Debug)]
+pub(crate) struct SpanIndex {
+    threads: BTreeMap<u32, IntervalTree>,
+}
+
+impl SpanIndex {
+    pub(crate) fn new(spans: Vec<SpanCandidate>) -> Self {
+        let mut by_thread: BTreeMap<u32, Vec<SpanCandidate>> = BTreeMap::new();
+        for span in spans {
+            by_thread.entry(span.thread_id).or_default().push(span);
+        }
+        Self {
+            threads: by_thread
+                .into_iter()
+                .map(|(thread_id, spans)| (thread_id, IntervalTree::new(spans)))
+                .collect(),
+        }
+    }
+
+    /// Spans whose `[start, end]` contains `timestamp_ns`, outermost first.
+    /// Equal depths are ordered by thread, then start time.
+    pub(crate) fn active_at(
+        &self,
+        timestamp_ns: u64,
+        thread_ids: Option<&[u32]>,
+    ) -> Vec<&SpanCandidate> {
+        let mut active = Vec::new();
+        for (thread_id, tree) in &self.threads {
+            if thread_ids.is_some_and(|ids| !ids.contains(thread_id)) {
+                continue;
+            }
+            tree.visit_active(timestamp_ns, &mut active);
+        }
+        active.sort_by_key(|span| (span.depth, span.thread_id, span.start_time_ns));
+        active
+    }
+
+    /// Approximate heap bytes held by the index.
+    fn heap_bytes(&self) -> usize {
+        let string_bytes = |span: &SpanCandidate| {
+            span.span_id.capacity() + span.function_name.as_ref().map_or(0, String::capacity)
+        };
+        self.threads
+            .values()
+            .map(|tree| {
+                mem::size_of::<(u32, IntervalTree)>()
+                    + tree.spans.capacity() * mem::size_of::<SpanCandidate>()
+                    + tree.max_end.capacity() * mem::size_of::<u64>()
+                    + tree.spans.iter().map(string_bytes).sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpansAtTimeParams {
+    pub trace_id: String,
+    pub timestamp_ns: u64,
+    /// Only report spans on these threads.
+    #[serde(default)]
+    pub thread_ids: Option<Vec<u32>>,
+    #[serde(default)]
+    pub projection: SpanProjection,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpansAtTimeResponse {
+    /// Active spans, outermost first.
+    pub spans: Vec<SpanResult>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+struct CachedSpanIndex {
+    index: Arc<SpanIndex>,
+    cached_at: Instant,
+    manifest_mtime: Option<SystemTime>,
+    events_mtime: Option<SystemTime>,
+}
+
+impl CacheWeight for CachedSpanIndex {
+    fn weight_bytes(&self) -> usize {
+        mem::size_of::<Self>() + mem::size_of::<SpanIndex>() + self.index.heap_bytes()
+    }
+}
+
+#[derive(Clone)]
+pub struct SpansAtTimeHandler {
+    trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
+    cache_ttl: Duration,
+    cache: Option<Arc<BudgetedLru<PathBuf, CachedSpanIndex>>>,
+}
+
+impl SpansAtTimeHandler {
+    /// Like `TraceInfoHandler`, a zero capacity or TTL disables the cache and
+    /// every query reconstructs the trace's spans.
+    pub fn new(trace_root_dir: PathBuf, cache_capacity: usize, cache_ttl: Duration) -> Self {
+        let cache = if cache_capacity == 0 || cache_ttl.is_zero() {
+            None
+        } else {
+            let capacity = NonZeroUsize::new(cache_capacity).expect("validated non-zero");
+            Some(BudgetedLru::new(
+                "spans.atTime",
+                capacity,
+                Arc::new(MemoryBudget::unlimited()),
+            ))
+        };
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+            cache_ttl,
+            cache,
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Counts the cached indexes against `budget`, which evicts them
+    /// alongside other caches' entries. The cache starts empty.
+    pub fn with_memory_budget(mut self, budget: Arc<MemoryBudget>) -> Self {
+        self.cache = self
+            .cache
+            .map(|cache| BudgetedLru::new("spans.atTime", cache.capacity(), budget));
+        self
+    }
+
+    /// Also joins the server's memory budget.
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        let handler = self.with_memory_budget(server.memory_budget());
+        server
+            .handler_registry()
+            .register_handler("spans.atTime", handler);
+    }
+
+    /// Cached index for `trace_dir`, unless it expired or the trace's files
+    /// changed since it was built. Sources not backed by files are only
+    /// subject to the TTL.
+    fn fetch_from_cache(
+        &self,
+        trace_dir: &Path,
+        manifest_mtime: Option<SystemTime>,
+        events_mtime: Option<SystemTime>,
+    ) -> Option<Arc<SpanIndex>> {
+        let cache = self.cache.as_ref()?;
+        let entry = cache.get(trace_dir)?;
+
+        let stale = |current: Option<SystemTime>, stored: Option<SystemTime>| {
+            current
+                .zip(stored)
+                .is_some_and(|(current, stored)| current > stored)
+        };
+        if entry.cached_at.elapsed() > self.cache_ttl
+            || stale(manifest_mtime, entry.manifest_mtime)
+            || stale(events_mtime, entry.events_mtime)
+        {
+            cache.pop(trace_dir);
+            return None;
+        }
+        Some(entry.index)
+    }
+
+    async fn span_index(&self, trace_dir: PathBuf) -> Result<Arc<SpanIndex>, JsonRpcError> {
+        let manifest_mtime = fs::metadata(trace_dir.join("manifest.json"))
+            .and_then(|meta| meta.modified())
+            .ok();
+        let events_mtime = EventFiles::scan(&trace_dir)
+            .ok()
+            .and_then(|files| files.mtime);
+        if let Some(index) = self.fetch_from_cache(&trace_dir, manifest_mtime, events_mtime) {
+            return Ok(index);
+        }
+
+        // Span reconstruction walks the whole event stream with blocking IO.
+        let source = Arc::clone(&self.source);
+        let events_dir = trace_dir.clone();
+        let index = task::spawn_blocking(move || {
+            let events = source.open(&events_dir)?;
+            reconstruct_spans(events.as_ref()).map(SpanIndex::new)
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("span reconstruction task failed: {err}")))?
+        .map_err(SpansListHandler::map_atf_error)?;
+        let index = Arc::new(index);
+
+        if let Some(cache) = &self.cache {
+            cache.put(
+                trace_dir,
+                CachedSpanIndex {
+                    index: Arc::clone(&index),
+                    cached_at: Instant::now(),
+                    manifest_mtime,
+                    events_mtime,
+                },
+            );
+        }
+        Ok(index)
+    }
+
+    pub async fn spans_at_time(
+        &self,
+        params: SpansAtTimeParams,
+    ) -> Result<SpansAtTimeResponse, JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        let index = self.span_index(trace_dir).await?;
+        let spans = index
+            .active_at(params.timestamp_ns, params.thread_ids.as_deref())
+            .into_iter()
+            .map(|span| SpansListHandler::project_span(span, &params.projection))
+            .collect();
+
+        Ok(SpansAtTimeResponse {
+            spans,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for SpansAtTimeHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: SpansAtTimeParams =
+            SpansAtTimeParams::deserialize(params.unwrap_or(&json!({}))).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid spans.atTime params: {err}"))
+            })?;
+
+        let response = self.spans_at_time(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{
+        atf::AtfError,
+        handlers::{
+            source::EventSource,
+            test_support::{function_call_event, function_return_event, TraceFixture},
+        },
+    };
+
+    const MAIN: u64 = 0x100;
+    const WORK: u64 = 0x200;
+
+    fn candidate(thread_id: u32, start: u64, end: u64, depth: u32, name: &str) -> SpanCandidate {
+        SpanCandidate {
+            span_id: format!("{thread_id}:{start}"),
+            function_name: Some(name.into()),
+            start_time_ns: start,
+            end_time_ns: end,
+            duration_ns: end - start,
+            self_duration_ns: end - start,
+            thread_id,
+            depth,
+            child_count: 0,
+            merged_count: 1,
+        }
+    }
+
+    fn names(spans: &[&SpanCandidate]) -> Vec<String> {
+        spans
+            .iter()
+            .map(|span| span.function_name.clone().expect("name"))
+            .collect()
+    }
+
+    #[test]
+    fn span_index__active_at__then_matches_linear_scan() {
+        // Siblings, nesting and long spans that straddle many short ones.
+        let mut spans = Vec::new();
+        for i in 0..200u64 {
+            let start = (i * 37) % 1_000;
+            let end = start + (i * 13) % 150;
+            spans.push(candidate(
+                (i % 3) as u32,
+                start,
+                end,
+                (i % 5) as u32,
+                &i.to_string(),
+            ));
+        }
+        let index = SpanIndex::new(spans.clone());
+
+        for timestamp_ns in (0..1_200).step_by(7) {
+            let mut expected: Vec<&SpanCandidate> = spans
+                .iter()
+                .filter(|span| {
+                    span.start_time_ns <= timestamp_ns && timestamp_ns <= span.end_time_ns
+                })
+                .collect();
+            expected.sort_by_key(|span| (span.depth, span.thread_id, span.start_time_ns));
+            assert_eq!(
+                names(&index.active_at(timestamp_ns, None)),
+                names(&expected),
+                "at {timestamp_ns}"
+            );
+        }
+    }
+
+    #[test]
+    fn span_index__bounds_and_threads__then_inclusive_and_scoped() {
+        let index = SpanIndex::new(vec![
+            candidate(1, 10, 20, 1, "inner"),
+            candidate(1, 0, 100, 0, "outer"),
+            candidate(2, 20, 30, 0, "other"),
+        ]);
+
+        assert_eq!(
+            names(&index.active_at(20, None)),
+            ["outer", "other", "inner"]
+        );
+        assert_eq!(names(&index.active_at(20, Some(&[1]))), ["outer", "inner"]);
+        assert_eq!(names(&index.active_at(21, Some(&[2]))), ["other"]);
+        assert!(index.active_at(101, None).is_empty());
+    }
+
+    /// Counts how often traces are opened, to observe cache hits.
+    struct CountingProvider {
+        opens: AtomicUsize,
+    }
+
+    impl SourceProvider for CountingProvider {
+        fn open(&self, trace_dir: &Path) -> Result<Box<dyn EventSource>, AtfError> {
+            self.opens.fetch_add(1, Ordering::SeqCst);
+            AtfSourceProvider.open(trace_dir)
+        }
+    }
+
+    #[tokio::test]
+    async fn spans_at_time__repeated_queries__then_index_built_once() {
+        let fixture = TraceFixture::new("at_time");
+        let events = vec![
+            function_call_event(100, 1, MAIN),
+            function_call_event(200, 1, WORK),
+            function_return_event(300, 1, WORK),
+            function_return_event(1_000, 1, MAIN),
+        ];
+        fixture.write_events(&events);
+        let provider = Arc::new(CountingProvider {
+            opens: AtomicUsize::new(0),
+        });
+        let handler = SpansAtTimeHandler::new(fixture.trace_root(), 8, Duration::from_secs(60))
+            .with_source(provider.clone());
+
+        let result = handler
+            .call(Some(json!({ "traceId": "at_time", "timestampNs": 250 })))
+            .await
+            .expect("active spans");
+        assert_eq!(result["spans"][0]["functionName"], "0x100");
+        assert_eq!(result["spans"][1]["functionName"], "0x200");
+
+        let result = handler
+            .call(Some(json!({ "traceId": "at_time", "timestampNs": 500 })))
+            .await
+            .expect("active spans");
+        assert_eq!(result["spans"].as_array().expect("spans").len(), 1);
+        assert_eq!(provider.opens.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn spans_at_time__session_rewritten__then_index_rebuilt() {
+        let fixture = TraceFixture::new("at_time_stale");
+        fixture.write_events(&[
+            function_call_event(100, 1, MAIN),
+            function_return_event(1_000, 1, MAIN),
+        ]);
+        let provider = Arc::new(CountingProvider {
+            opens: AtomicUsize::new(0),
+        });
+        let handler = SpansAtTimeHandler::new(fixture.trace_root(), 8, Duration::from_secs(60))
+            .with_source(provider.clone());
+        let params = json!({ "traceId": "at_time_stale", "timestampNs": 500 });
+
+        handler
+            .call(Some(params.clone()))
+            .await
+            .expect("first query");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        fixture.write_events(&[
+            function_call_event(100, 1, WORK),
+            function_return_event(1_000, 1, WORK),
+        ]);
+
+        let result = handler.call(Some(params)).await.expect("second query");
+        assert_eq!(result["spans"][0]["functionName"], "0x200");
+        assert_eq!(provider.opens.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,11 +1,18 @@
-use std::{collections::HashMap, path::PathBuf, time::Instant};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Instant};
 
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use tokio::task;
 
 use crate::{
-    atf::{AtfError, AtfReader, ParsedEventKind},
+    atf::{AtfError, ParsedEventKind},
+    handlers::{
+        envelope::{envelope_result, SPANS_LIST_SCHEMA_VERSION},
+        paths::{resolve_trace_root, validate_trace_id},
+        source::{AtfSourceProvider, EventSource, SourceProvider},
+    },
     server::{
         handler::{JsonRpcHandler, JsonRpcResult},
         types::JsonRpcError,
@@ -23,7 +30,7 @@ fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SpansListParams {
     #[serde(rename = "traceId")]
@@ -36,11 +43,50 @@ pub struct SpansListParams {
     pub offset: u64,
     #[serde(default = "default_limit")]
     pub limit: u64,
+    #[serde(default)]
+    pub order_by: SpanOrderBy,
+    #[serde(default = "default_true")]
+    pub ascending: bool,
     #[serde(default = "default_true")]
     pub include_children: bool,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+    /// Coalesces runs of adjacent same-function siblings shorter than this
+    /// into one span. Unlike `filters.minDurationNs`, nothing is dropped.
+    #[serde(default)]
+    pub min_duration_ns: Option<u64>,
+    /// Discards spans shorter than this while reconstructing, before they are
+    /// stored or sorted. Parents still count the discarded calls in
+    /// `childCount` and exclude their time from `selfDurationNs`.
+    #[serde(default)]
+    pub min_reconstruct_duration_ns: Option<u64>,
+    #[serde(default)]
+    pub span_id_format: SpanIdFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SpanOrderBy {
+    #[default]
+    StartTime,
+    Duration,
+    Depth,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+/// How `spanId` values are derived.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SpanIdFormat {
+    /// `"{thread}:{start}:{sequence}"`, numbered in reconstruction order.
+    #[default]
+    Sequence,
+    /// Hex digest of the span's thread, start, end, depth and function name,
+    /// stable across runs over the same trace.
+    Hash,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SpanFilters {
     #[serde(rename = "timeStartNs")]
@@ -59,9 +105,12 @@ pub struct SpanFilters {
     pub min_depth: Option<u32>,
     #[serde(rename = "maxDepth")]
     pub max_depth: Option<u32>,
+    /// Drops spans whose call and return share a timestamp.
+    #[serde(rename = "excludeZeroDuration", default)]
+    pub exclude_zero_duration: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(default, rename_all = "camelCase")]
 pub struct SpanProjection {
     #[serde(rename = "spanId", default = "default_true")]
@@ -74,6 +123,9 @@ pub struct SpanProjection {
     pub end_time_ns: bool,
     #[serde(rename = "durationNs", default = "default_true")]
     pub duration_ns: bool,
+    /// Duration minus the time spent in direct children.
+    #[serde(rename = "selfDurationNs")]
+    pub self_duration_ns: bool,
     #[serde(rename = "threadId")]
     pub thread_id: bool,
     #[serde(rename = "moduleName")]
@@ -92,6 +144,7 @@ impl Default for SpanProjection {
             start_time_ns: true,
             end_time_ns: true,
             duration_ns: true,
+            self_duration_ns: false,
             thread_id: false,
             module_name: false,
             depth: false,
@@ -100,14 +153,14 @@ impl Default for SpanProjection {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SpansListResponse {
     pub spans: Vec<SpanResult>,
     pub metadata: QueryMetadata,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryMetadata {
     pub total_count: u64,
@@ -118,7 +171,7 @@ pub struct QueryMetadata {
     pub execution_time_ms: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SpanResult {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -132,6 +185,8 @@ pub struct SpanResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_ns: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_duration_ns: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub thread_id: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub module_name: Option<String>,
@@ -139,18 +194,52 @@ pub struct SpanResult {
     pub depth: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub child_count: Option<u32>,
+    /// Number of spans coalesced into this one; absent for a single span.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_count: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SpansGetParams {
+    pub trace_id: String,
+    /// Id in either format `spans.list` produces; children use the same one.
+    pub span_id: String,
+    #[serde(default)]
+    pub projection: SpanProjection,
+    /// Overrides the handler's trace root; must resolve inside an allowed root.
+    #[serde(default)]
+    pub trace_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SpanNode {
+    #[serde(flatten)]
+    pub span: SpanResult,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SpanNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SpansGetResponse {
+    pub span: SpanNode,
+    pub execution_time_ms: u64,
 }
 
 #[derive(Debug, Clone)]
-struct SpanCandidate {
-    span_id: String,
-    function_name: Option<String>,
-    start_time_ns: u64,
-    end_time_ns: u64,
-    duration_ns: u64,
-    thread_id: u32,
-    depth: u32,
-    child_count: u32,
+pub(crate) struct SpanCandidate {
+    pub(crate) span_id: String,
+    pub(crate) function_name: Option<String>,
+    pub(crate) start_time_ns: u64,
+    pub(crate) end_time_ns: u64,
+    pub(crate) duration_ns: u64,
+    pub(crate) self_duration_ns: u64,
+    pub(crate) thread_id: u32,
+    pub(crate) depth: u32,
+    pub(crate) child_count: u32,
+    pub(crate) merged_count: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -159,17 +248,36 @@ struct ActiveSpan {
     start_time_ns: u64,
     depth: u32,
     child_count: u32,
+    child_duration_ns: u64,
     span_sequence: u64,
 }
 
 #[derive(Clone)]
 pub struct SpansListHandler {
     trace_root_dir: PathBuf,
+    allowed_roots: Vec<PathBuf>,
+    source: Arc<dyn SourceProvider>,
 }
 
 impl SpansListHandler {
     pub fn new(trace_root_dir: PathBuf) -> Self {
-        Self { trace_root_dir }
+        Self {
+            trace_root_dir,
+            allowed_roots: Vec::new(),
+            source: Arc::new(AtfSourceProvider),
+        }
+    }
+
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = allowed_roots;
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.source = source;
+        self
     }
 
     pub fn register(self, server: &crate::server::JsonRpcServer) {
@@ -179,9 +287,7 @@ impl SpansListHandler {
     }
 
     fn validate_params(&self, params: &SpansListParams) -> Result<(), JsonRpcError> {
-        if params.trace_id.trim().is_empty() {
-            return Err(JsonRpcError::invalid_params("traceId must not be empty"));
-        }
+        validate_trace_id(&params.trace_id)?;
         if params.limit > MAX_LIMIT {
             return Err(JsonRpcError::invalid_params("limit cannot exceed 10000"));
         }
@@ -203,11 +309,11 @@ impl SpansListHandler {
         Ok(())
     }
 
-    fn map_atf_error(err: AtfError) -> JsonRpcError {
+    pub(crate) fn map_atf_error(err: AtfError) -> JsonRpcError {
         match err {
-            AtfError::TraceNotFound(_)
-            | AtfError::ManifestNotFound(_)
-            | AtfError::EventsNotFound(_) => JsonRpcError::trace_not_found(),
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
             other => JsonRpcError::internal(format!("failed to load trace: {other}")),
         }
     }
@@ -236,6 +342,9 @@ impl SpansListHandler {
                 return false;
             }
         }
+        if filters.exclude_zero_duration && span.duration_ns == 0 {
+            return false;
+        }
         if let Some(min_duration) = filters.min_duration_ns {
             if span.duration_ns < min_duration {
                 return false;
@@ -269,7 +378,7 @@ impl SpansListHandler {
         true
     }
 
-    fn project_span(&self, span: &SpanCandidate, projection: &SpanProjection) -> SpanResult {
+    pub(crate) fn project_span(span: &SpanCandidate, projection: &SpanProjection) -> SpanResult {
         SpanResult {
             span_id: if projection.span_id {
                 Some(span.span_id.clone())
@@ -296,6 +405,11 @@ impl SpansListHandler {
             } else {
                 None
             },
+            self_duration_ns: if projection.self_duration_ns {
+                Some(span.self_duration_ns)
+            } else {
+                None
+            },
             thread_id: if projection.thread_id {
                 Some(span.thread_id)
             } else {
@@ -312,81 +426,45 @@ impl SpansListHandler {
             } else {
                 None
             },
+            merged_count: (span.merged_count > 1).then_some(span.merged_count),
         }
     }
-}
-
-#[async_trait]
-impl JsonRpcHandler for SpansListHandler {
-    async fn call(&self, params: Option<Value>) -> JsonRpcResult {
-        let params_value = params.unwrap_or_else(|| json!({}));
-        let params: SpansListParams =
-            serde_json::from_value(params_value.clone()).map_err(|err| {
-                JsonRpcError::invalid_params(format!("invalid spans.list params: {err}"))
-            })?;
 
+    pub async fn list_spans(
+        &self,
+        params: SpansListParams,
+    ) -> Result<SpansListResponse, JsonRpcError> {
         self.validate_params(&params)?;
 
-        let trace_dir = self.trace_root_dir.join(params.trace_id.trim());
+        let trace_root = resolve_trace_root(
+            &self.trace_root_dir,
+            &self.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
         let start_time = Instant::now();
 
-        let reader = AtfReader::open(&trace_dir).map_err(Self::map_atf_error)?;
-        let mut stream = reader.event_stream().map_err(Self::map_atf_error)?;
-
-        let mut call_stacks: HashMap<u32, Vec<ActiveSpan>> = HashMap::new();
-        let mut spans = Vec::new();
-        let mut span_sequence: u64 = 0;
-
-        while let Some(item) = stream.next() {
-            let event = item.map_err(Self::map_atf_error)?;
-            match &event.kind {
-                ParsedEventKind::FunctionCall { symbol } => {
-                    let stack = call_stacks.entry(event.thread_id).or_default();
-                    let depth = stack.len() as u32;
-                    span_sequence = span_sequence.wrapping_add(1);
-                    stack.push(ActiveSpan {
-                        function_name: symbol.clone(),
-                        start_time_ns: event.timestamp_ns,
-                        depth,
-                        child_count: 0,
-                        span_sequence,
-                    });
-                }
-                ParsedEventKind::FunctionReturn { .. } => {
-                    if let Some(stack) = call_stacks.get_mut(&event.thread_id) {
-                        if let Some(frame) = stack.pop() {
-                            let duration = event.timestamp_ns.saturating_sub(frame.start_time_ns);
-                            let span_id = format!(
-                                "{}:{}:{}",
-                                event.thread_id, frame.start_time_ns, frame.span_sequence
-                            );
-                            spans.push(SpanCandidate {
-                                span_id,
-                                function_name: frame.function_name.clone(),
-                                start_time_ns: frame.start_time_ns,
-                                end_time_ns: event.timestamp_ns,
-                                duration_ns: duration,
-                                thread_id: event.thread_id,
-                                depth: frame.depth,
-                                child_count: frame.child_count,
-                            });
+        // Span reconstruction walks the whole event stream with blocking IO.
+        let source = Arc::clone(&self.source);
+        let min_reconstruct_duration_ns = params.min_reconstruct_duration_ns.unwrap_or(0);
+        let mut spans = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            reconstruct_spans_min_duration(events.as_ref(), min_reconstruct_duration_ns)
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("span reconstruction task failed: {err}")))?
+        .map_err(Self::map_atf_error)?;
 
-                            if let Some(parent) = stack.last_mut() {
-                                parent.child_count = parent.child_count.saturating_add(1);
-                            }
-                        }
-                    }
-                }
-                _ => {}
+        if let Some(min_duration_ns) = params.min_duration_ns {
+            spans = coalesce_short_spans(spans, min_duration_ns);
+        }
+        if params.span_id_format == SpanIdFormat::Hash {
+            for span in &mut spans {
+                span.span_id = content_span_id(span);
             }
         }
 
-        spans.sort_by(|a, b| {
-            a.start_time_ns
-                .cmp(&b.start_time_ns)
-                .then_with(|| a.thread_id.cmp(&b.thread_id))
-                .then_with(|| a.span_id.cmp(&b.span_id))
-        });
+        sort_spans(&mut spans, params.order_by, params.ascending);
 
         let filtered: Vec<SpanCandidate> = spans
             .into_iter()
@@ -407,10 +485,12 @@ impl JsonRpcHandler for SpansListHandler {
 
         let spans: Vec<SpanResult> = slice
             .iter()
-            .map(|span| self.project_span(span, &params.projection))
+            .map(|span| Self::project_span(span, &params.projection))
             .collect();
 
-        let has_more = total_count > params.offset + spans.len() as u64;
+        // Items remain only if the page stopped short of the end; an offset at or
+        // past the end yields an empty page with nothing more to fetch.
+        let has_more = end_index < filtered.len();
         let metadata = QueryMetadata {
             total_count,
             returned_count: spans.len() as u64,
@@ -420,90 +500,395 @@ impl JsonRpcHandler for SpansListHandler {
             execution_time_ms: start_time.elapsed().as_millis() as u64,
         };
 
-        let response = SpansListResponse { spans, metadata };
-
-        serde_json::to_value(response)
-            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+        Ok(SpansListResponse { spans, metadata })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    #![allow(non_snake_case)]
+#[async_trait]
+impl JsonRpcHandler for SpansListHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: SpansListParams = SpansListParams::deserialize(params.unwrap_or(&json!({})))
+            .map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid spans.list params: {err}"))
+            })?;
 
-    use super::*;
-    use std::{fs::File, io::Write, path::PathBuf};
-    use prost::Message;
-    use serde_json::json;
-    use tempfile::TempDir;
-    use crate::atf::event::{event::Payload, Event, FunctionCall, FunctionReturn};
+        let response = self.list_spans(params).await?;
+
+        envelope_result(SPANS_LIST_SCHEMA_VERSION, response)
+    }
+}
 
-    fn timestamp(ts: u64) -> prost_types::Timestamp {
-        prost_types::Timestamp {
-            seconds: (ts / 1_000_000_000) as i64,
-            nanos: (ts % 1_000_000_000) as i32,
+#[derive(Clone)]
+pub struct SpansGetHandler {
+    spans: SpansListHandler,
+}
+
+impl SpansGetHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            spans: SpansListHandler::new(trace_root_dir),
         }
     }
 
-    struct TraceFixture {
-        root: TempDir,
-        trace_id: String,
+    /// Additional roots that requests may select through `traceRoot`.
+    pub fn with_allowed_roots(mut self, allowed_roots: Vec<PathBuf>) -> Self {
+        self.spans = self.spans.with_allowed_roots(allowed_roots);
+        self
+    }
+
+    /// Reads traces through `source` instead of from disk.
+    pub fn with_source(mut self, source: Arc<dyn SourceProvider>) -> Self {
+        self.spans = self.spans.with_source(source);
+        self
+    }
+
+    pub fn register(self, server: &crate::server::JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("spans.get", self);
     }
 
-    impl TraceFixture {
-        fn new(trace_id: impl Into<String>) -> Self {
-            let root = TempDir::new().expect("tempdir");
-            let trace_id = trace_id.into();
-            std::fs::create_dir_all(root.path().join(&trace_id)).expect("trace dir");
-            Self { root, trace_id }
+    pub async fn get_span(&self, params: SpansGetParams) -> Result<SpansGetResponse, JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+
+        let trace_root = resolve_trace_root(
+            &self.spans.trace_root_dir,
+            &self.spans.allowed_roots,
+            params.trace_root.as_deref(),
+        )?;
+        let trace_dir = trace_root.join(params.trace_id.trim());
+        let start_time = Instant::now();
+
+        let span_id = params.span_id.trim().to_string();
+        let sequence_key = parse_sequence_span_id(&span_id);
+        let source = Arc::clone(&self.spans.source);
+        let spans = task::spawn_blocking(move || {
+            let events = source.open(&trace_dir)?;
+            match sequence_key {
+                Some((thread_id, _)) => reconstruct_thread_spans(events.as_ref(), thread_id),
+                None => reconstruct_spans(events.as_ref()),
+            }
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("span reconstruction task failed: {err}")))?
+        .map_err(SpansListHandler::map_atf_error)?;
+
+        // Sequence ids carry the thread and start time, so only that thread's
+        // spans were reconstructed; hashed ids must be recomputed everywhere.
+        let mut spans: Vec<SpanCandidate> = match sequence_key {
+            Some((_, start_time_ns)) => spans
+                .into_iter()
+                .filter(|span| span.start_time_ns >= start_time_ns)
+                .collect(),
+            None => spans
+                .into_iter()
+                .map(|mut span| {
+                    span.span_id = content_span_id(&span);
+                    span
+                })
+                .collect(),
+        };
+
+        let root_index = spans
+            .iter()
+            .position(|span| span.span_id == span_id)
+            .ok_or_else(|| JsonRpcError::span_not_found(&span_id))?;
+        let root = spans.swap_remove(root_index);
+
+        let mut descendants: Vec<SpanCandidate> = spans
+            .into_iter()
+            .filter(|span| {
+                span.thread_id == root.thread_id
+                    && span.depth > root.depth
+                    && span.start_time_ns >= root.start_time_ns
+                    && span.end_time_ns <= root.end_time_ns
+            })
+            .collect();
+        // (start, depth) order is a pre-order walk of the subtree.
+        descendants.sort_by(|a, b| {
+            a.start_time_ns
+                .cmp(&b.start_time_ns)
+                .then_with(|| a.depth.cmp(&b.depth))
+        });
+
+        let mut descendants = descendants.into_iter().peekable();
+        let span = self.build_node(root, &mut descendants, &params.projection);
+
+        Ok(SpansGetResponse {
+            span,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        })
+    }
+
+    fn build_node(
+        &self,
+        span: SpanCandidate,
+        descendants: &mut std::iter::Peekable<std::vec::IntoIter<SpanCandidate>>,
+        projection: &SpanProjection,
+    ) -> SpanNode {
+        let mut children = Vec::new();
+        while let Some(next) = descendants.peek() {
+            let nested = next.depth > span.depth
+                && next.start_time_ns >= span.start_time_ns
+                && next.end_time_ns <= span.end_time_ns;
+            if !nested {
+                break;
+            }
+            let child = descendants.next().expect("peeked span");
+            children.push(self.build_node(child, descendants, projection));
         }
 
-        fn trace_root(&self) -> PathBuf {
-            self.root.path().to_path_buf()
+        SpanNode {
+            span: SpansListHandler::project_span(&span, projection),
+            children,
         }
+    }
+}
 
-        fn trace_dir(&self) -> PathBuf {
-            self.root.path().join(&self.trace_id)
+#[async_trait]
+impl JsonRpcHandler for SpansGetHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: SpansGetParams = SpansGetParams::deserialize(params.unwrap_or(&json!({})))
+            .map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid spans.get params: {err}"))
+            })?;
+
+        let response = self.get_span(params).await?;
+
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("serialization failed: {err}")))
+    }
+}
+
+/// Thread and start time encoded in a `"{thread}:{start}:{sequence}"` id.
+fn parse_sequence_span_id(span_id: &str) -> Option<(u32, u64)> {
+    let mut parts = span_id.split(':');
+    let thread_id = parts.next()?.parse().ok()?;
+    let start_time_ns = parts.next()?.parse().ok()?;
+    let _sequence: u64 = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((thread_id, start_time_ns))
+}
+
+pub(crate) fn reconstruct_spans(source: &dyn EventSource) -> Result<Vec<SpanCandidate>, AtfError> {
+    reconstruct_spans_min_duration(source, 0)
+}
+
+/// Like `reconstruct_spans`, but never emits spans shorter than
+/// `min_duration_ns`. A discarded span still counts towards its parent's
+/// `child_count` and child time, so surviving parents report the same
+/// counts and self durations as without the threshold, and sequence span
+/// ids are numbered as if nothing had been discarded.
+pub(crate) fn reconstruct_spans_min_duration(
+    source: &dyn EventSource,
+    min_duration_ns: u64,
+) -> Result<Vec<SpanCandidate>, AtfError> {
+    reconstruct_spans_filtered(source, min_duration_ns, None)
+}
+
+/// Like `reconstruct_spans`, but only builds the spans of `thread_id`.
+/// Calls on other threads are still counted, so sequence span ids match
+/// those of a full reconstruction.
+pub(crate) fn reconstruct_thread_spans(
+    source: &dyn EventSource,
+    thread_id: u32,
+) -> Result<Vec<SpanCandidate>, AtfError> {
+    reconstruct_spans_filtered(source, 0, Some(thread_id))
+}
+
+fn reconstruct_spans_filtered(
+    source: &dyn EventSource,
+    min_duration_ns: u64,
+    thread_id: Option<u32>,
+) -> Result<Vec<SpanCandidate>, AtfError> {
+    let mut call_stacks: HashMap<u32, Vec<ActiveSpan>> = HashMap::new();
+    let mut spans = Vec::new();
+    let mut span_sequence: u64 = 0;
+
+    for item in source.events()? {
+        let event = item?;
+        if thread_id.is_some_and(|thread_id| thread_id != event.thread_id) {
+            if matches!(event.kind, ParsedEventKind::FunctionCall { .. }) {
+                span_sequence = span_sequence.wrapping_add(1);
+            }
+            continue;
         }
+        match &event.kind {
+            ParsedEventKind::FunctionCall { symbol } => {
+                let stack = call_stacks.entry(event.thread_id).or_default();
+                let depth = stack.len() as u32;
+                span_sequence = span_sequence.wrapping_add(1);
+                stack.push(ActiveSpan {
+                    function_name: symbol.clone(),
+                    start_time_ns: event.timestamp_ns,
+                    depth,
+                    child_count: 0,
+                    child_duration_ns: 0,
+                    span_sequence,
+                });
+            }
+            ParsedEventKind::FunctionReturn { .. } => {
+                if let Some(stack) = call_stacks.get_mut(&event.thread_id) {
+                    if let Some(frame) = stack.pop() {
+                        let duration = event.timestamp_ns.saturating_sub(frame.start_time_ns);
+                        if duration >= min_duration_ns {
+                            let span_id = format!(
+                                "{}:{}:{}",
+                                event.thread_id, frame.start_time_ns, frame.span_sequence
+                            );
+                            spans.push(SpanCandidate {
+                                span_id,
+                                function_name: frame.function_name,
+                                start_time_ns: frame.start_time_ns,
+                                end_time_ns: event.timestamp_ns,
+                                duration_ns: duration,
+                                // Children are nested inside the parent, but clamp in
+                                // case clock skew makes them appear to overlap it.
+                                self_duration_ns: duration.saturating_sub(frame.child_duration_ns),
+                                thread_id: event.thread_id,
+                                depth: frame.depth,
+                                child_count: frame.child_count,
+                                merged_count: 1,
+                            });
+                        }
 
-        fn manifest_path(&self) -> PathBuf {
-            self.trace_dir().join("trace.json")
+                        if let Some(parent) = stack.last_mut() {
+                            parent.child_count = parent.child_count.saturating_add(1);
+                            parent.child_duration_ns =
+                                parent.child_duration_ns.saturating_add(duration);
+                        }
+                    }
+                }
+            }
+            _ => {}
         }
+    }
+
+    Ok(spans)
+}
+
+/// Visits spans thread by thread in call order, each with the spans enclosing
+/// it, outermost first. The last enclosing span is the direct caller.
+///
+/// Nesting is derived from depth, so spans need not be in any order.
+pub(crate) fn for_each_nested<'a>(
+    spans: &'a [SpanCandidate],
+    mut visit: impl FnMut(&'a SpanCandidate, &[&'a SpanCandidate]),
+) {
+    let mut ordered: Vec<&SpanCandidate> = spans.iter().collect();
+    ordered.sort_by_key(|span| (span.thread_id, span.start_time_ns, span.depth));
 
-        fn events_path(&self) -> PathBuf {
-            self.trace_dir().join("events.bin")
+    let mut enclosing: Vec<&SpanCandidate> = Vec::new();
+    for span in ordered {
+        while enclosing
+            .last()
+            .is_some_and(|outer| outer.thread_id != span.thread_id || outer.depth >= span.depth)
+        {
+            enclosing.pop();
         }
+        visit(span, &enclosing);
+        enclosing.push(span);
+    }
+}
 
-        fn write_manifest(&self, event_count: u64) {
-            let manifest = json!({
-                "os": "linux",
-                "arch": "x86_64",
-                "pid": 1,
-                "sessionId": 1,
-                "timeStartNs": 100,
-                "timeEndNs": 10_000,
-                "eventCount": event_count,
-                "bytesWritten": 1024,
-            });
-            std::fs::write(
-                self.manifest_path(),
-                serde_json::to_vec_pretty(&manifest).expect("serialize"),
-            )
-            .expect("write manifest");
+/// Content-derived span id. Hashes the span's extent rather than its position
+/// in the event stream, so the id survives reordering and re-reconstruction.
+pub(crate) fn content_span_id(span: &SpanCandidate) -> String {
+    let mut context = md5::Context::new();
+    context.consume(span.thread_id.to_le_bytes());
+    context.consume(span.start_time_ns.to_le_bytes());
+    context.consume(span.end_time_ns.to_le_bytes());
+    context.consume(span.depth.to_le_bytes());
+    // Tag the name so an unnamed span never collides with an empty name.
+    match span.function_name.as_deref() {
+        Some(name) => {
+            context.consume([1]);
+            context.consume(name.as_bytes());
         }
+        None => context.consume([0]),
+    }
+    format!("{:x}", context.compute())
+}
+
+/// Merges runs of consecutive sibling spans that call the same function and
+/// are each shorter than `min_duration_ns`. Siblings share a thread and a
+/// parent, so runs never cross thread boundaries or their caller's return.
+pub(crate) fn coalesce_short_spans(
+    mut spans: Vec<SpanCandidate>,
+    min_duration_ns: u64,
+) -> Vec<SpanCandidate> {
+    // Within a thread, (start, depth) order is a pre-order walk of the call
+    // tree: the next sibling at depth d follows only descendants (depth > d).
+    spans.sort_by(|a, b| {
+        a.thread_id
+            .cmp(&b.thread_id)
+            .then_with(|| a.start_time_ns.cmp(&b.start_time_ns))
+            .then_with(|| a.depth.cmp(&b.depth))
+    });
 
-        fn write_events(&self, events: &[Event]) {
-            let mut file = File::create(self.events_path()).expect("events file");
-            for event in events {
-                let mut buffer = Vec::new();
-                event
-                    .encode_length_delimited(&mut buffer)
-                    .expect("encode event");
-                file.write_all(&buffer).expect("write event");
+    let mut coalesced: Vec<SpanCandidate> = Vec::with_capacity(spans.len());
+    // Index into `coalesced` of the open run at each depth of the current thread.
+    let mut runs: Vec<Option<usize>> = Vec::new();
+    let mut current_thread = None;
+
+    for span in spans {
+        if current_thread != Some(span.thread_id) {
+            current_thread = Some(span.thread_id);
+            runs.clear();
+        }
+        let depth = span.depth as usize;
+        runs.truncate(depth + 1);
+        runs.resize(depth + 1, None);
+
+        let short = span.duration_ns < min_duration_ns;
+        if let Some(index) = runs[depth] {
+            let run = &mut coalesced[index];
+            if short && run.function_name == span.function_name {
+                run.end_time_ns = run.end_time_ns.max(span.end_time_ns);
+                run.duration_ns = run.end_time_ns - run.start_time_ns;
+                run.self_duration_ns = run.self_duration_ns.saturating_add(span.self_duration_ns);
+                run.child_count = run.child_count.saturating_add(span.child_count);
+                run.merged_count = run.merged_count.saturating_add(span.merged_count);
+                continue;
             }
-            file.flush().expect("flush events");
         }
+
+        runs[depth] = short.then_some(coalesced.len());
+        coalesced.push(span);
+    }
+
+    coalesced
+}
+
+/// Sorts spans by `order_by`, breaking ties by start time, thread and span id
+/// so every ordering is total and pages are stable across requests.
+pub(crate) fn sort_spans(spans: &mut [SpanCandidate], order_by: SpanOrderBy, ascending: bool) {
+    spans.sort_by(|a, b| {
+        let primary = match order_by {
+            SpanOrderBy::StartTime => std::cmp::Ordering::Equal,
+            SpanOrderBy::Duration => a.duration_ns.cmp(&b.duration_ns),
+            SpanOrderBy::Depth => a.depth.cmp(&b.depth),
+        };
+        primary
+            .then_with(|| a.start_time_ns.cmp(&b.start_time_ns))
+            .then_with(|| a.thread_id.cmp(&b.thread_id))
+            .then_with(|| a.span_id.cmp(&b.span_id))
+    });
+
+    if !ascending {
+        spans.reverse();
     }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::IndexEvent;
+    use crate::handlers::envelope::ResponseEnvelope;
+    use crate::handlers::test_support::{function_call_event, function_return_event, TraceFixture};
+    use serde_json::json;
+    use std::path::PathBuf;
 
     #[test]
     fn span_matches_filters__depth_checks() {
@@ -514,9 +899,11 @@ mod tests {
             start_time_ns: 100,
             end_time_ns: 200,
             duration_ns: 100,
+            self_duration_ns: 100,
             thread_id: 1,
             depth: 2,
             child_count: 0,
+            merged_count: 1,
         };
         let filters = SpanFilters {
             min_depth: Some(1),
@@ -531,32 +918,10 @@ mod tests {
     async fn spans_handler__limit_exceeds_max__then_returns_error() {
         // Test limit validation error for spans handler
         let fixture = TraceFixture::new("spans_limit_error");
-        fixture.write_manifest(2);
-
-        let events = vec![
-            Event {
-                event_id: 100,
-                thread_id: 1,
-                timestamp: Some(timestamp(100)),
-                payload: Some(Payload::FunctionCall(FunctionCall {
-                    symbol: "test".to_string(),
-                    address: 0,
-                    argument_registers: Default::default(),
-                    stack_shallow_copy: Vec::new(),
-                })),
-            },
-            Event {
-                event_id: 200,
-                thread_id: 1,
-                timestamp: Some(timestamp(200)),
-                payload: Some(Payload::FunctionReturn(FunctionReturn {
-                    symbol: "test".to_string(),
-                    address: 0,
-                    return_registers: Default::default(),
-                })),
-            },
-        ];
-        fixture.write_events(&events);
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x1),
+            function_return_event(200, 1, 0x1),
+        ]);
 
         let handler = SpansListHandler::new(fixture.trace_root());
 
@@ -571,36 +936,164 @@ mod tests {
         assert_eq!(err.code, -32602); // Invalid params
     }
 
+    fn candidate(thread_id: u32, start: u64, end: u64, depth: u32, name: &str) -> SpanCandidate {
+        SpanCandidate {
+            span_id: format!("{thread_id}:{start}"),
+            function_name: Some(name.into()),
+            start_time_ns: start,
+            end_time_ns: end,
+            duration_ns: end - start,
+            self_duration_ns: end - start,
+            thread_id,
+            depth,
+            child_count: 0,
+            merged_count: 1,
+        }
+    }
+
+    #[test]
+    fn reconstruct_spans_min_duration__short_calls__then_dropped_and_still_counted() {
+        use crate::{atf::ParsedEvent, handlers::source::MemoryEventSource};
+
+        let call = |timestamp_ns, symbol: &str| ParsedEvent {
+            timestamp_ns,
+            thread_id: 1,
+            kind: ParsedEventKind::FunctionCall {
+                symbol: Some(symbol.into()),
+            },
+        };
+        let ret = |timestamp_ns, symbol: &str| ParsedEvent {
+            timestamp_ns,
+            thread_id: 1,
+            kind: ParsedEventKind::FunctionReturn {
+                symbol: Some(symbol.into()),
+            },
+        };
+        let source = MemoryEventSource::new(vec![
+            call(0, "main"),
+            call(10, "tick"),
+            ret(11, "tick"),
+            call(20, "work"),
+            call(30, "tick"),
+            ret(31, "tick"),
+            ret(520, "work"),
+            ret(1000, "main"),
+        ]);
+
+        let all = reconstruct_spans(&source).expect("reconstruct");
+        let kept = reconstruct_spans_min_duration(&source, 100).expect("reconstruct");
+
+        assert_eq!(all.len(), 4);
+        let names: Vec<&str> = kept
+            .iter()
+            .filter_map(|span| span.function_name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["work", "main"]);
+        let unfiltered = |name: &str| {
+            all.iter()
+                .find(|span| span.function_name.as_deref() == Some(name))
+                .expect("span")
+        };
+        for span in &kept {
+            let expected = unfiltered(span.function_name.as_deref().expect("name"));
+            assert_eq!(span.span_id, expected.span_id);
+            assert_eq!(span.child_count, expected.child_count);
+            assert_eq!(span.self_duration_ns, expected.self_duration_ns);
+        }
+        assert_eq!(kept[1].child_count, 2);
+        assert_eq!(kept[1].self_duration_ns, 1000 - 1 - 500);
+    }
+
+    #[test]
+    fn reconstruct_thread_spans__interleaved_threads__then_one_thread_with_full_ids() {
+        use crate::{atf::ParsedEvent, handlers::source::MemoryEventSource};
+
+        let event = |timestamp_ns, thread_id, call: bool| ParsedEvent {
+            timestamp_ns,
+            thread_id,
+            kind: if call {
+                ParsedEventKind::FunctionCall { symbol: None }
+            } else {
+                ParsedEventKind::FunctionReturn { symbol: None }
+            },
+        };
+        let source = MemoryEventSource::new(vec![
+            event(0, 1, true),
+            event(5, 2, true),
+            event(10, 1, true),
+            event(15, 2, false),
+            event(20, 1, false),
+            event(25, 2, true),
+            event(30, 2, false),
+            event(40, 1, false),
+        ]);
+
+        let all = reconstruct_spans(&source).expect("reconstruct");
+        let thread_two = reconstruct_thread_spans(&source, 2).expect("reconstruct");
+
+        let expected: Vec<&SpanCandidate> = all.iter().filter(|span| span.thread_id == 2).collect();
+        let ids: Vec<&str> = thread_two.iter().map(|span| span.span_id.as_str()).collect();
+        assert_eq!(ids, vec!["2:5:2", "2:25:4"]);
+        assert_eq!(
+            ids,
+            expected
+                .iter()
+                .map(|span| span.span_id.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn coalesce_short_spans__same_function_siblings__then_merged_with_count() {
+        let spans = vec![
+            candidate(1, 0, 1000, 0, "main"),
+            candidate(1, 10, 12, 1, "tick"),
+            candidate(1, 20, 22, 1, "tick"),
+            candidate(1, 30, 32, 1, "tick"),
+            candidate(1, 40, 42, 1, "tock"),
+            candidate(1, 50, 900, 1, "tick"),
+        ];
+
+        let coalesced = coalesce_short_spans(spans, 5);
+        let summary: Vec<(u64, u64, u32)> = coalesced
+            .iter()
+            .map(|span| (span.start_time_ns, span.end_time_ns, span.merged_count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(0, 1000, 1), (10, 32, 3), (40, 42, 1), (50, 900, 1)]
+        );
+    }
+
+    #[test]
+    fn coalesce_short_spans__different_parents_or_threads__then_kept_apart() {
+        let spans = vec![
+            candidate(1, 0, 10, 0, "a"),
+            candidate(1, 2, 4, 1, "tick"),
+            candidate(1, 20, 30, 0, "b"),
+            candidate(1, 22, 24, 1, "tick"),
+            candidate(2, 5, 7, 1, "tick"),
+        ];
+
+        let coalesced = coalesce_short_spans(spans, 100);
+        let ticks: Vec<u32> = coalesced
+            .iter()
+            .filter(|span| span.function_name.as_deref() == Some("tick"))
+            .map(|span| span.merged_count)
+            .collect();
+        assert_eq!(ticks, vec![1, 1, 1]);
+        // Short top-level siblings "a" and "b" differ by name, so both remain.
+        assert_eq!(coalesced.len(), 5);
+    }
+
     #[tokio::test]
     async fn spans_handler__basic_functionality__then_returns_spans() {
         // Test basic handler functionality to ensure good path coverage
         let fixture = TraceFixture::new("spans_basic");
-        fixture.write_manifest(2);
-
-        let events = vec![
-            Event {
-                event_id: 100,
-                thread_id: 1,
-                timestamp: Some(timestamp(100)),
-                payload: Some(Payload::FunctionCall(FunctionCall {
-                    symbol: "test_func".to_string(),
-                    address: 0,
-                    argument_registers: Default::default(),
-                    stack_shallow_copy: Vec::new(),
-                })),
-            },
-            Event {
-                event_id: 200,
-                thread_id: 1,
-                timestamp: Some(timestamp(200)),
-                payload: Some(Payload::FunctionReturn(FunctionReturn {
-                    symbol: "test_func".to_string(),
-                    address: 0,
-                    return_registers: Default::default(),
-                })),
-            },
-        ];
-        fixture.write_events(&events);
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x1),
+            function_return_event(200, 1, 0x1),
+        ]);
 
         let handler = SpansListHandler::new(fixture.trace_root());
 
@@ -611,7 +1104,381 @@ mod tests {
         });
 
         let result = handler.call(Some(params)).await.expect("should succeed");
-        assert!(result.get("spans").is_some());
-        assert!(result.get("metadata").is_some());
+        let envelope: ResponseEnvelope<SpansListResponse> =
+            serde_json::from_value(result).expect("enveloped response");
+        assert_eq!(envelope.schema_version, SPANS_LIST_SCHEMA_VERSION);
+        assert!(!envelope.generated_at.is_empty());
+        assert_eq!(envelope.data.spans.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn spans_handler__pagination_offsets__then_has_more_only_before_end() {
+        let fixture = TraceFixture::new("spans_pages");
+        let events: Vec<IndexEvent> = (0..3u64)
+            .flat_map(|i| {
+                let start = 100 + i * 100;
+                [
+                    function_call_event(start, 1, 0x1),
+                    function_return_event(start + 50, 1, 0x1),
+                ]
+            })
+            .collect();
+        fixture.write_events(&events);
+        let handler = SpansListHandler::new(fixture.trace_root());
+
+        for (offset, expected_returned, expected_has_more) in
+            [(0, 2, true), (1, 2, false), (3, 0, false), (10, 0, false)]
+        {
+            let result = handler
+                .call(Some(
+                    json!({ "traceId": "spans_pages", "offset": offset, "limit": 2 }),
+                ))
+                .await
+                .expect("spans");
+            let metadata = &result["metadata"];
+            assert_eq!(metadata["totalCount"], 3, "offset {offset}");
+            assert_eq!(metadata["returnedCount"], expected_returned, "offset {offset}");
+            assert_eq!(metadata["hasMore"], expected_has_more, "offset {offset}");
+        }
+    }
+
+    #[test]
+    fn sort_spans__duration_descending__then_longest_first_with_stable_ties() {
+        let mut spans = vec![
+            candidate(1, 0, 100, 0, "main"),
+            candidate(2, 50, 60, 1, "tick"),
+            candidate(1, 10, 20, 1, "tick"),
+            candidate(1, 30, 90, 1, "work"),
+        ];
+
+        sort_spans(&mut spans, SpanOrderBy::Duration, false);
+        let ids: Vec<&str> = spans.iter().map(|span| span.span_id.as_str()).collect();
+        assert_eq!(ids, vec!["1:0", "1:30", "2:50", "1:10"]);
+
+        sort_spans(&mut spans, SpanOrderBy::Depth, true);
+        let ids: Vec<&str> = spans.iter().map(|span| span.span_id.as_str()).collect();
+        assert_eq!(ids, vec!["1:0", "1:10", "1:30", "2:50"]);
+    }
+
+    #[tokio::test]
+    async fn spans_handler__order_by_duration__then_slowest_page_first() {
+        let fixture = TraceFixture::new("spans_by_duration");
+        let events: Vec<IndexEvent> = [(100u64, 10u64), (200, 80), (400, 30)]
+            .into_iter()
+            .flat_map(|(start, duration)| {
+                [
+                    function_call_event(start, 1, 0x1),
+                    function_return_event(start + duration, 1, 0x1),
+                ]
+            })
+            .collect();
+        fixture.write_events(&events);
+        let handler = SpansListHandler::new(fixture.trace_root());
+
+        let result = handler
+            .call(Some(json!({
+                "traceId": "spans_by_duration",
+                "orderBy": "duration",
+                "ascending": false,
+                "limit": 2
+            })))
+            .await
+            .expect("spans");
+        let durations: Vec<u64> = result["spans"]
+            .as_array()
+            .expect("spans")
+            .iter()
+            .map(|span| span["durationNs"].as_u64().expect("duration"))
+            .collect();
+        assert_eq!(durations, vec![80, 30]);
+        assert_eq!(result["metadata"]["hasMore"], true);
+    }
+
+    #[test]
+    fn content_span_id__same_extent__then_stable_and_name_sensitive() {
+        let span = candidate(1, 100, 200, 0, "main");
+        let mut renumbered = span.clone();
+        renumbered.span_id = "1:100:42".into();
+        assert_eq!(content_span_id(&span), content_span_id(&renumbered));
+        assert_eq!(content_span_id(&span).len(), 32);
+
+        let mut unnamed = span.clone();
+        unnamed.function_name = None;
+        let mut empty = span.clone();
+        empty.function_name = Some(String::new());
+        assert_ne!(content_span_id(&unnamed), content_span_id(&empty));
+        assert_ne!(content_span_id(&span), content_span_id(&candidate(2, 100, 200, 0, "main")));
+    }
+
+    #[tokio::test]
+    async fn spans_handler__span_id_format__then_sequence_by_default_and_hash_on_request() {
+        let fixture = TraceFixture::new("spans_id_format");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x1),
+            function_return_event(150, 1, 0x1),
+        ]);
+        let handler = SpansListHandler::new(fixture.trace_root());
+
+        let span_id = |value: Value| value["spans"][0]["spanId"].as_str().expect("id").to_string();
+        let sequence = handler
+            .call(Some(json!({ "traceId": "spans_id_format" })))
+            .await
+            .expect("spans");
+        assert_eq!(span_id(sequence), "1:100:1");
+
+        let hashed = handler
+            .call(Some(json!({ "traceId": "spans_id_format", "spanIdFormat": "hash" })))
+            .await
+            .expect("spans");
+        assert_eq!(
+            span_id(hashed),
+            content_span_id(&candidate(1, 100, 150, 0, "0x1"))
+        );
+    }
+
+    const MAIN: u64 = 0x100;
+    const PARSE: u64 = 0x200;
+    const LEX: u64 = 0x300;
+    const WORKER: u64 = 0x400;
+    const RUN: u64 = 0x500;
+    const EXIT: u64 = 0x600;
+
+    fn nested_trace_events() -> Vec<IndexEvent> {
+        let call = function_call_event;
+        let ret = function_return_event;
+        vec![
+            call(100, 1, MAIN),
+            call(110, 1, PARSE),
+            call(120, 1, LEX),
+            ret(130, 1, LEX),
+            ret(140, 1, PARSE),
+            call(145, 2, WORKER),
+            call(150, 1, RUN),
+            ret(180, 1, RUN),
+            ret(190, 2, WORKER),
+            ret(200, 1, MAIN),
+            call(210, 1, EXIT),
+            ret(220, 1, EXIT),
+        ]
+    }
+
+    fn child_names(node: &Value) -> Vec<&str> {
+        node["children"]
+            .as_array()
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|child| child["functionName"].as_str().expect("name"))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn spans_get__sequence_id__then_returns_subtree() {
+        let fixture = TraceFixture::new("spans_get");
+        fixture.write_events(&nested_trace_events());
+        let list = SpansListHandler::new(fixture.trace_root());
+        let handler = SpansGetHandler::new(fixture.trace_root());
+
+        let listed = list
+            .call(Some(json!({ "traceId": "spans_get", "filters": { "functionNames": ["0x100"] } })))
+            .await
+            .expect("spans");
+        let main_id = listed["spans"][0]["spanId"].as_str().expect("id");
+
+        let result = handler
+            .call(Some(json!({ "traceId": "spans_get", "spanId": main_id })))
+            .await
+            .expect("span");
+        let root = &result["span"];
+        assert_eq!(root["functionName"], "0x100");
+        assert_eq!(child_names(root), vec!["0x200", "0x500"]);
+        assert_eq!(child_names(&root["children"][0]), vec!["0x300"]);
+        assert!(child_names(&root["children"][1]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn spans_get__hashed_id__then_children_use_hashed_ids() {
+        let fixture = TraceFixture::new("spans_get_hash");
+        fixture.write_events(&nested_trace_events());
+        let handler = SpansGetHandler::new(fixture.trace_root());
+
+        let parse_id = content_span_id(&candidate(1, 110, 140, 1, "0x200"));
+        let result = handler
+            .call(Some(json!({ "traceId": "spans_get_hash", "spanId": parse_id })))
+            .await
+            .expect("span");
+        let root = &result["span"];
+        assert_eq!(root["spanId"], parse_id.as_str());
+        assert_eq!(child_names(root), vec!["0x300"]);
+        assert_eq!(
+            root["children"][0]["spanId"],
+            content_span_id(&candidate(1, 120, 130, 2, "0x300")).as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn spans_get__unknown_span__then_span_not_found() {
+        let fixture = TraceFixture::new("spans_get_missing");
+        fixture.write_events(&nested_trace_events());
+        let handler = SpansGetHandler::new(fixture.trace_root());
+
+        for span_id in ["1:100:99", "deadbeef"] {
+            let err = handler
+                .call(Some(json!({ "traceId": "spans_get_missing", "spanId": span_id })))
+                .await
+                .expect_err("expected error");
+            assert_eq!(err.code, -32000, "span {span_id}");
+            assert_eq!(err.message, "Span not found", "span {span_id}");
+        }
+    }
+
+    #[tokio::test]
+    async fn spans_handler__zero_duration_span__then_included_unless_excluded() {
+        let fixture = TraceFixture::new("spans_zero_duration");
+        fixture.write_events(&[
+            function_call_event(100, 1, 0x2),
+            function_return_event(100, 1, 0x2),
+        ]);
+        let handler = SpansListHandler::new(fixture.trace_root());
+
+        let included = handler
+            .call(Some(json!({ "traceId": "spans_zero_duration" })))
+            .await
+            .expect("spans");
+        assert_eq!(included["spans"][0]["durationNs"], 0);
+        assert_eq!(included["metadata"]["totalCount"], 1);
+
+        let excluded = handler
+            .call(Some(json!({
+                "traceId": "spans_zero_duration",
+                "filters": { "excludeZeroDuration": true }
+            })))
+            .await
+            .expect("spans");
+        assert_eq!(excluded["metadata"]["totalCount"], 0);
+    }
+
+    mod reconstruction_properties {
+        use super::*;
+        use crate::{atf::ParsedEvent, handlers::source::MemoryEventSource};
+        use proptest::prelude::*;
+
+        /// `(thread_id, is_call)` per event; timestamps increase with position
+        type Ops = Vec<(u32, bool)>;
+
+        fn ops() -> impl Strategy<Value = Ops> {
+            prop::collection::vec((1u32..4, any::<bool>()), 0..80)
+        }
+
+        /// Drops returns that have no open call and closes every open call
+        fn balance(ops: Ops) -> Ops {
+            let mut depths: HashMap<u32, usize> = HashMap::new();
+            let mut balanced = Vec::new();
+            for (thread_id, is_call) in ops {
+                let depth = depths.entry(thread_id).or_default();
+                if is_call {
+                    *depth += 1;
+                } else if *depth == 0 {
+                    continue;
+                } else {
+                    *depth -= 1;
+                }
+                balanced.push((thread_id, is_call));
+            }
+            let mut open: Vec<_> = depths.into_iter().collect();
+            open.sort();
+            for (thread_id, depth) in open {
+                balanced.extend(std::iter::repeat_n((thread_id, false), depth));
+            }
+            balanced
+        }
+
+        fn op_timestamp(position: usize) -> u64 {
+            100 + position as u64 * 10
+        }
+
+        fn reconstruct(ops: &[(u32, bool)]) -> Vec<SpanCandidate> {
+            let events = ops
+                .iter()
+                .enumerate()
+                .map(|(position, &(thread_id, is_call))| ParsedEvent {
+                    timestamp_ns: op_timestamp(position),
+                    thread_id,
+                    kind: if is_call {
+                        ParsedEventKind::FunctionCall {
+                            symbol: Some(format!("fn_{position}")),
+                        }
+                    } else {
+                        ParsedEventKind::FunctionReturn { symbol: None }
+                    },
+                })
+                .collect();
+
+            reconstruct_spans(&MemoryEventSource::new(events)).expect("reconstruct spans")
+        }
+
+        /// Stack depth at each call, keyed by `(thread_id, start_time_ns)`
+        fn call_depths(ops: &[(u32, bool)]) -> HashMap<(u32, u64), u32> {
+            let mut stacks: HashMap<u32, u32> = HashMap::new();
+            let mut depths = HashMap::new();
+            for (position, &(thread_id, is_call)) in ops.iter().enumerate() {
+                let depth = stacks.entry(thread_id).or_default();
+                if is_call {
+                    depths.insert((thread_id, op_timestamp(position)), *depth);
+                    *depth += 1;
+                } else {
+                    *depth = depth.saturating_sub(1);
+                }
+            }
+            depths
+        }
+
+        fn assert_span_invariants(ops: &[(u32, bool)], spans: &[SpanCandidate]) {
+            let depths = call_depths(ops);
+            for span in spans {
+                assert!(span.end_time_ns >= span.start_time_ns, "{span:?}");
+                assert_eq!(
+                    Some(&span.depth),
+                    depths.get(&(span.thread_id, span.start_time_ns)),
+                    "{span:?}"
+                );
+
+                let direct_children = spans
+                    .iter()
+                    .filter(|child| {
+                        child.thread_id == span.thread_id
+                            && child.depth == span.depth + 1
+                            && child.start_time_ns >= span.start_time_ns
+                            && child.end_time_ns <= span.end_time_ns
+                    })
+                    .count();
+                assert!(span.child_count as usize <= direct_children, "{span:?}");
+            }
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn reconstruct_spans__balanced_calls__then_one_span_per_call(ops in ops()) {
+                let ops = balance(ops);
+                let spans = reconstruct(&ops);
+
+                assert_span_invariants(&ops, &spans);
+                let calls = ops.iter().filter(|(_, is_call)| *is_call).count();
+                prop_assert_eq!(spans.len(), calls);
+            }
+
+            #[test]
+            fn reconstruct_spans__unbalanced_calls__then_invariants_hold(ops in ops()) {
+                let spans = reconstruct(&ops);
+
+                assert_span_invariants(&ops, &spans);
+                let calls = ops.iter().filter(|(_, is_call)| *is_call).count();
+                prop_assert!(spans.len() <= calls);
+            }
+        }
     }
 }
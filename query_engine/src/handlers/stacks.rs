@@ -0,0 +1,378 @@
+use std::{path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::task;
+
+use crate::{
+    atf::{v2::session::Endianness, AtfError},
+    handlers::{
+        paths::validate_trace_id,
+        source::{EventSource, V2EventSource},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+/// Maps a code address to a symbol name.
+pub trait SymbolResolver: Send + Sync {
+    fn resolve(&self, address: u64) -> Option<String>;
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StacksGetParams {
+    pub trace_id: String,
+    /// Thread and timestamp of the event, as `events.get` reports them
+    pub thread_id: u32,
+    pub timestamp_ns: u64,
+    /// Resolve each word through the configured symbol resolver.
+    #[serde(default)]
+    pub symbolicate: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StackWord {
+    /// Byte offset of the word within the stack snapshot.
+    pub offset: u64,
+    pub value: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StacksGetResponse {
+    pub trace_id: String,
+    pub thread_id: u32,
+    pub timestamp_ns: u64,
+    pub word_size: u32,
+    pub words: Vec<StackWord>,
+}
+
+/// Pointer width in bytes for an `arch` name. Unknown architectures
+/// are assumed to be 64-bit, which covers every platform the tracer targets.
+pub(crate) fn pointer_size(arch: &str) -> usize {
+    match arch.to_ascii_lowercase().as_str() {
+        "x86" | "i386" | "i686" | "arm" | "armv7" | "arm32" => 4,
+        _ => 8,
+    }
+}
+
+/// Splits a raw stack snapshot into words of `word_size` bytes in the traced
+/// process's byte order. A trailing partial word is dropped.
+pub(crate) fn decode_stack_words(
+    blob: &[u8],
+    word_size: usize,
+    endianness: Endianness,
+) -> Vec<u64> {
+    blob.chunks_exact(word_size)
+        .map(|chunk| read_uint(chunk, endianness))
+        .collect()
+}
+
+/// Reads an unsigned integer of up to eight bytes captured on the traced host.
+pub(crate) fn read_uint(bytes: &[u8], endianness: Endianness) -> u64 {
+    let mut buffer = [0u8; 8];
+    match endianness {
+        Endianness::Little => {
+            buffer[..bytes.len()].copy_from_slice(bytes);
+            u64::from_le_bytes(buffer)
+        }
+        Endianness::Big => {
+            buffer[8 - bytes.len()..].copy_from_slice(bytes);
+            u64::from_be_bytes(buffer)
+        }
+    }
+}
+
+/// Stack snapshot of the first event on `thread_id` at `timestamp_ns`, with
+/// the session's pointer width and byte order, or `None` when no such event
+/// exists. Events without a detail record have an empty stack.
+fn read_stack_snapshot(
+    source: &V2EventSource,
+    thread_id: u32,
+    timestamp_ns: u64,
+) -> Option<Vec<u8>> {
+    let thread = source
+        .session()
+        .threads()
+        .iter()
+        .find(|thread| thread.thread_id() == thread_id)?;
+    let event = thread
+        .index
+        .iter()
+        .find(|event| event.timestamp_ns == timestamp_ns)?;
+    Some(
+        thread
+            .get_detail_for(event)
+            .and_then(|detail| detail.stack_snapshot())
+            .map(<[u8]>::to_vec)
+            .unwrap_or_default(),
+    )
+}
+
+#[derive(Clone)]
+pub struct StacksGetHandler {
+    trace_root_dir: PathBuf,
+    resolver: Option<Arc<dyn SymbolResolver>>,
+}
+
+impl StacksGetHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self {
+            trace_root_dir,
+            resolver: None,
+        }
+    }
+
+    pub fn with_resolver(mut self, resolver: Arc<dyn SymbolResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("stacks.get", self);
+    }
+
+    pub async fn get_stack(
+        &self,
+        params: StacksGetParams,
+    ) -> Result<StacksGetResponse, JsonRpcError> {
+        let trace_id = validate_trace_id(&params.trace_id)?.to_string();
+
+        let trace_dir = self.trace_root_dir.join(&trace_id);
+        if !trace_dir.is_dir() {
+            return Err(JsonRpcError::trace_not_found());
+        }
+
+        let (thread_id, timestamp_ns) = (params.thread_id, params.timestamp_ns);
+        let (word_size, endianness, stack) = task::spawn_blocking(move || {
+            let source = V2EventSource::open(&trace_dir)?;
+            let word_size = pointer_size(&source.manifest().arch);
+            let endianness = source.session().manifest().endianness;
+            let stack = read_stack_snapshot(&source, thread_id, timestamp_ns);
+            Ok::<_, AtfError>((word_size, endianness, stack))
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("stack decode task failed: {err}")))?
+        .map_err(map_atf_error)?;
+
+        let stack = stack.ok_or_else(|| {
+            JsonRpcError::invalid_params(format!(
+                "no event at {timestamp_ns} on thread {thread_id}"
+            ))
+        })?;
+
+        let resolver = self.resolver.as_deref().filter(|_| params.symbolicate);
+        let words = decode_stack_words(&stack, word_size, endianness)
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| StackWord {
+                offset: (index * word_size) as u64,
+                value,
+                symbol: resolver.and_then(|resolver| resolver.resolve(value)),
+            })
+            .collect();
+
+        Ok(StacksGetResponse {
+            trace_id,
+            thread_id,
+            timestamp_ns,
+            word_size: word_size as u32,
+            words,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for StacksGetHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: StacksGetParams = match params {
+            Some(value) => StacksGetParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid stacks.get parameters: {err}"))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing stacks.get parameters",
+                ))
+            }
+        };
+
+        let response = self.get_stack(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+}
+
+fn map_atf_error(err: AtfError) -> JsonRpcError {
+    match err {
+        AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+            JsonRpcError::trace_not_found()
+        }
+        other => JsonRpcError::internal(format!("failed to read trace events: {other}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::v2::ATF_DETAIL_EVENT_FUNCTION_CALL;
+    use crate::handlers::test_support::{
+        function_call_event, function_detail, function_return_event, TraceFixture,
+    };
+    use serde_json::json;
+
+    const MAIN: u64 = 0x1000;
+
+    struct MapResolver;
+
+    impl SymbolResolver for MapResolver {
+        fn resolve(&self, address: u64) -> Option<String> {
+            (address == 0x1000).then(|| "main".to_string())
+        }
+    }
+
+    /// A call at 100 on thread 1 whose detail record holds `stack`,
+    /// returning at 200 without a detail record
+    fn write_trace(stack: &[u8]) -> TraceFixture {
+        let fixture = TraceFixture::new("trace");
+        let mut call = function_call_event(100, 1, MAIN);
+        call.detail_seq = 0;
+        fixture.write_events(&[call, function_return_event(200, 1, MAIN)]);
+        let detail = function_detail(ATF_DETAIL_EVENT_FUNCTION_CALL, 0, 100, 1, MAIN, stack);
+        fixture.write_detail(1, &[detail]);
+        fixture
+    }
+
+    fn params(timestamp_ns: u64, symbolicate: bool) -> StacksGetParams {
+        StacksGetParams {
+            trace_id: "trace".into(),
+            thread_id: 1,
+            timestamp_ns,
+            symbolicate,
+        }
+    }
+
+    #[test]
+    fn decode_stack_words__partial_tail__then_dropped() {
+        let blob = [1, 0, 0, 0, 2, 0, 0, 0, 3, 0];
+        assert_eq!(decode_stack_words(&blob, 4, Endianness::Little), vec![1, 2]);
+        assert_eq!(
+            decode_stack_words(&blob[..8], 8, Endianness::Little),
+            vec![0x0000_0002_0000_0001]
+        );
+    }
+
+    #[test]
+    fn decode_stack_words__big_endian_blob__then_swapped_on_little_host() {
+        let mut blob = 0x0000_7FFF_DEAD_BEEFu64.to_be_bytes().to_vec();
+        blob.extend_from_slice(&0x1000u64.to_be_bytes());
+        assert_eq!(
+            decode_stack_words(&blob, 8, Endianness::Big),
+            vec![0x0000_7FFF_DEAD_BEEF, 0x1000]
+        );
+        assert_eq!(
+            decode_stack_words(&[0x12, 0x34, 0x56, 0x78], 4, Endianness::Big),
+            vec![0x1234_5678]
+        );
+    }
+
+    #[test]
+    fn pointer_size__known_architectures__then_expected_width() {
+        assert_eq!(pointer_size("x86_64"), 8);
+        assert_eq!(pointer_size("arm64"), 8);
+        assert_eq!(pointer_size("ARMv7"), 4);
+        assert_eq!(pointer_size("i386"), 4);
+    }
+
+    #[tokio::test]
+    async fn stacks_get__symbolicate__then_resolved_words() {
+        let mut stack = 0x1000u64.to_le_bytes().to_vec();
+        stack.extend_from_slice(&0x2000u64.to_le_bytes());
+        let fixture = write_trace(&stack);
+        let handler =
+            StacksGetHandler::new(fixture.trace_root()).with_resolver(Arc::new(MapResolver));
+
+        let response = handler.get_stack(params(100, true)).await.expect("stack");
+        assert_eq!(response.word_size, 8);
+        assert_eq!(
+            response.words,
+            vec![
+                StackWord {
+                    offset: 0,
+                    value: 0x1000,
+                    symbol: Some("main".into()),
+                },
+                StackWord {
+                    offset: 8,
+                    value: 0x2000,
+                    symbol: None,
+                },
+            ]
+        );
+
+        let unresolved = handler.get_stack(params(100, false)).await.expect("stack");
+        assert!(unresolved.words.iter().all(|word| word.symbol.is_none()));
+    }
+
+    #[tokio::test]
+    async fn stacks_get__big_endian_manifest__then_words_decoded_in_trace_order() {
+        let fixture = write_trace(&0x1000u64.to_be_bytes());
+        fixture.edit_manifest(|manifest| manifest["endianness"] = json!("big"));
+        let handler =
+            StacksGetHandler::new(fixture.trace_root()).with_resolver(Arc::new(MapResolver));
+
+        let response = handler.get_stack(params(100, true)).await.expect("stack");
+        assert_eq!(response.words[0].value, 0x1000);
+        assert_eq!(response.words[0].symbol.as_deref(), Some("main"));
+    }
+
+    #[tokio::test]
+    async fn stacks_get__no_stack_snapshot__then_empty_words() {
+        let fixture = write_trace(&[]);
+        let handler = StacksGetHandler::new(fixture.trace_root());
+
+        for timestamp_ns in [100, 200] {
+            let response = handler
+                .get_stack(params(timestamp_ns, false))
+                .await
+                .expect("stack");
+            assert!(response.words.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn stacks_get__unknown_event__then_invalid_params() {
+        let fixture = write_trace(&[]);
+        let handler = StacksGetHandler::new(fixture.trace_root());
+
+        for params in [
+            json!({ "traceId": "trace", "threadId": 1, "timestampNs": 150 }),
+            json!({ "traceId": "trace", "threadId": 2, "timestampNs": 100 }),
+        ] {
+            let err = handler
+                .call(Some(params))
+                .await
+                .expect_err("expected error");
+            assert_eq!(err.code, -32602);
+        }
+    }
+
+    #[test]
+    fn stacks_get_handler_register__then_handler_present_in_registry() {
+        let server = JsonRpcServer::new();
+        StacksGetHandler::new(PathBuf::from("/tmp")).register(&server);
+
+        assert!(server.handler_registry().contains("stacks.get"));
+    }
+}
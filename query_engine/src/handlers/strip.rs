@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    atf::{
+        v2::{
+            read_manifest_fields, rewrite_threads, ATF_DETAIL_EVENT_FUNCTION_CALL,
+            ATF_DETAIL_EVENT_FUNCTION_RETURN, ATF_DETAIL_FUNCTION_PAYLOAD_SIZE,
+            ATF_DETAIL_FUNCTION_REGISTER_BYTES,
+        },
+        AtfV2Error, SessionReader,
+    },
+    handlers::export::run_export,
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+/// Offset of x0 in a function call or return payload, past the function id
+const REGISTERS_OFFSET: usize = 8;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceStripRegistersParams {
+    pub trace_id: String,
+    /// Trace id of the stripped copy, created next to the source trace.
+    pub output_trace_id: String,
+    /// Drop the stack snapshots too, and with them every function call and
+    /// return detail record.
+    #[serde(default)]
+    pub drop_stacks: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceStripRegistersResponse {
+    pub trace_id: String,
+    pub event_count: u64,
+    pub detail_count: u64,
+    pub source_detail_count: u64,
+}
+
+/// What a function call or return record keeps once stripped: its payload
+/// with the registers zeroed, or nothing when no stack snapshot is left to
+/// carry. Other records are kept as they are.
+fn strip_payload(event_type: u16, payload: &[u8], drop_stacks: bool) -> Option<Vec<u8>> {
+    let is_function = matches!(
+        event_type,
+        ATF_DETAIL_EVENT_FUNCTION_CALL | ATF_DETAIL_EVENT_FUNCTION_RETURN
+    );
+    if !is_function || payload.len() < ATF_DETAIL_FUNCTION_PAYLOAD_SIZE {
+        return Some(payload.to_vec());
+    }
+
+    let stack_size = u16::from_le_bytes([payload[96], payload[97]]);
+    if drop_stacks || stack_size == 0 {
+        return None;
+    }
+    let mut payload = payload.to_vec();
+    payload[REGISTERS_OFFSET..REGISTERS_OFFSET + ATF_DETAIL_FUNCTION_REGISTER_BYTES].fill(0);
+    Some(payload)
+}
+
+/// Copies every event into a new session at `output`, with the register
+/// values of function call and return records zeroed. Records left with
+/// nothing but the function id, which the index event already holds, are
+/// dropped.
+fn strip_session(
+    source: &Path,
+    output: &Path,
+    drop_stacks: bool,
+) -> Result<TraceStripRegistersResponse, AtfV2Error> {
+    let session = SessionReader::open(source)?;
+    let mut manifest = read_manifest_fields(source)?;
+    manifest.insert("registers_stripped".to_string(), true.into());
+
+    let written = rewrite_threads(session.threads(), output, manifest, |thread, writer| {
+        for event in thread.index.iter() {
+            let detail = thread.get_detail_for(event);
+            let stripped = detail.as_ref().and_then(|detail| {
+                let header = detail.header();
+                strip_payload(header.event_type, detail.payload(), drop_stacks)
+                    .map(|payload| (header, payload))
+            });
+            writer.push(
+                event,
+                stripped
+                    .as_ref()
+                    .map(|(header, payload)| (*header, payload.as_slice())),
+            )?;
+        }
+        Ok(())
+    })?;
+
+    Ok(TraceStripRegistersResponse {
+        trace_id: String::new(),
+        event_count: written.iter().map(|thread| thread.event_count).sum(),
+        detail_count: written.iter().map(|thread| thread.detail_count).sum(),
+        source_detail_count: session
+            .threads()
+            .iter()
+            .filter_map(|thread| thread.detail.as_ref())
+            .map(|detail| detail.len() as u64)
+            .sum(),
+    })
+}
+
+#[derive(Clone)]
+pub struct TraceStripRegistersHandler {
+    trace_root_dir: PathBuf,
+}
+
+impl TraceStripRegistersHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self { trace_root_dir }
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.stripRegisters", self);
+    }
+
+    pub async fn strip(
+        &self,
+        params: TraceStripRegistersParams,
+    ) -> Result<TraceStripRegistersResponse, JsonRpcError> {
+        let drop_stacks = params.drop_stacks;
+        let response = run_export(
+            &self.trace_root_dir,
+            &params.trace_id,
+            &params.output_trace_id,
+            move |source, output| strip_session(source, output, drop_stacks),
+        )
+        .await?;
+
+        Ok(TraceStripRegistersResponse {
+            trace_id: params.output_trace_id.trim().to_string(),
+            ..response
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TraceStripRegistersHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TraceStripRegistersParams = match params {
+            Some(value) => TraceStripRegistersParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!(
+                    "invalid trace.stripRegisters parameters: {err}"
+                ))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing trace.stripRegisters parameters",
+                ))
+            }
+        };
+
+        let response = self.strip(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::handlers::{
+        test_support::{function_call_event, function_detail, function_return_event, TraceFixture},
+        TraceInfoHandler,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    /// Three calls: the first with registers and a stack snapshot, the
+    /// second with registers only, the third without a detail record
+    fn write_trace(fixture: &TraceFixture) {
+        let mut first = function_call_event(100, 1, 0xa);
+        first.detail_seq = 0;
+        let mut second = function_call_event(200, 1, 0xb);
+        second.detail_seq = 1;
+        fixture.write_events(&[first, second, function_return_event(300, 1, 0xb)]);
+        let mut with_stack =
+            function_detail(ATF_DETAIL_EVENT_FUNCTION_CALL, 0, 100, 1, 0xa, &[7, 8]);
+        with_stack[24 + REGISTERS_OFFSET..24 + 96].fill(0xee);
+        let mut registers_only =
+            function_detail(ATF_DETAIL_EVENT_FUNCTION_CALL, 1, 200, 1, 0xb, &[]);
+        registers_only[24 + REGISTERS_OFFSET..24 + 96].fill(0xee);
+        fixture.write_detail(1, &[with_stack, registers_only]);
+    }
+
+    #[tokio::test]
+    async fn trace_strip_registers__stacks_kept__then_registers_zeroed_and_empty_records_dropped() {
+        let fixture = TraceFixture::new("full");
+        write_trace(&fixture);
+        let handler = TraceStripRegistersHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({ "traceId": "full", "outputTraceId": "lean" })))
+            .await
+            .expect("strip");
+
+        assert_eq!(
+            response,
+            json!({
+                "traceId": "lean",
+                "eventCount": 3,
+                "detailCount": 1,
+                "sourceDetailCount": 2,
+            })
+        );
+        let session = SessionReader::open(&fixture.trace_root().join("lean")).expect("open");
+        assert!(session.verify().expect("verify").is_consistent());
+        assert!(session.manifest().registers_stripped);
+        let thread = &session.threads()[0];
+        let detail = thread
+            .get_detail_for(thread.index.get(0).expect("first call"))
+            .expect("detail kept");
+        assert!(detail.payload()[REGISTERS_OFFSET..96]
+            .iter()
+            .all(|&b| b == 0));
+        assert_eq!(detail.payload()[..8], 0xau64.to_le_bytes());
+        assert_eq!(detail.stack_snapshot(), Some(&[7u8, 8][..]));
+        assert!(thread
+            .get_detail_for(thread.index.get(1).expect("second call"))
+            .is_none());
+
+        let info = TraceInfoHandler::new(fixture.trace_root(), 0, Duration::from_secs(60))
+            .call(Some(json!({ "traceId": "lean" })))
+            .await
+            .expect("trace info");
+        assert_eq!(info["registersStripped"], true);
+    }
+
+    #[tokio::test]
+    async fn trace_strip_registers__drop_stacks__then_index_only_session() {
+        let fixture = TraceFixture::new("full");
+        write_trace(&fixture);
+        let handler = TraceStripRegistersHandler::new(fixture.trace_root());
+
+        let response = handler
+            .call(Some(json!({
+                "traceId": "full",
+                "outputTraceId": "lean",
+                "dropStacks": true,
+            })))
+            .await
+            .expect("strip");
+
+        assert_eq!(response["detailCount"], 0);
+        let output = fixture.trace_root().join("lean");
+        let session = SessionReader::open(&output).expect("open");
+        assert!(!session.manifest().threads[0].has_detail);
+        assert!(!output.join("thread_1/detail.atf").exists());
+        assert_eq!(session.event_count(), 3);
+    }
+}
@@ -0,0 +1,306 @@
+//! Trace fixtures shared by handler unit tests and the integration tests.
+//!
+//! Compiled for this crate's own tests and, through the `test-support`
+//! feature, for the integration tests under `tests/`. Helpers panic on I/O
+//! failures since a broken fixture should fail the test that built it.
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+};
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use crate::atf::v2::{
+    AtfDetailFooter, AtfDetailHeader, AtfIndexFooter, AtfIndexHeader, IndexEvent,
+    ATF_DETAIL_FUNCTION_PAYLOAD_SIZE, ATF_EVENT_KIND_CALL, ATF_EVENT_KIND_EXCEPTION,
+    ATF_EVENT_KIND_RETURN, ATF_NO_DETAIL_SEQ,
+};
+
+/// A temporary trace root holding a single trace directory
+pub struct TraceFixture {
+    root: TempDir,
+    trace_id: String,
+}
+
+impl TraceFixture {
+    pub fn new(trace_id: impl Into<String>) -> Self {
+        let root = TempDir::new().expect("tempdir");
+        let trace_id = trace_id.into();
+        fs::create_dir_all(root.path().join(&trace_id)).expect("trace dir");
+        Self { root, trace_id }
+    }
+
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    pub fn trace_root(&self) -> PathBuf {
+        self.root.path().to_path_buf()
+    }
+
+    pub fn trace_dir(&self) -> PathBuf {
+        self.root.path().join(&self.trace_id)
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.trace_dir().join("manifest.json")
+    }
+
+    pub fn index_path(&self, thread_id: u32) -> PathBuf {
+        self.trace_dir()
+            .join(format!("thread_{thread_id}"))
+            .join("index.atf")
+    }
+
+    /// Replaces `manifest.json`
+    pub fn write_manifest_json(&self, manifest: Value) {
+        let bytes = serde_json::to_vec_pretty(&manifest).expect("serialize manifest");
+        fs::write(self.manifest_path(), bytes).expect("write manifest");
+    }
+
+    /// Rewrites `manifest.json` after `edit`, e.g. to add wall-clock anchors
+    pub fn edit_manifest(&self, edit: impl FnOnce(&mut Value)) {
+        let bytes = fs::read(self.manifest_path()).expect("read manifest");
+        let mut manifest: Value = serde_json::from_slice(&bytes).expect("parse manifest");
+        edit(&mut manifest);
+        self.write_manifest_json(manifest);
+    }
+
+    /// Writes `events` as a V2 session, one stream per thread in the order
+    /// the threads first appear, replacing any previous session. Each
+    /// stream is sorted by timestamp, as the tracer writes them.
+    pub fn write_events(&self, events: &[IndexEvent]) {
+        let mut threads: Vec<(u32, Vec<IndexEvent>)> = Vec::new();
+        for event in events {
+            let thread_id = event.thread_id;
+            match threads.iter_mut().find(|(id, _)| *id == thread_id) {
+                Some((_, stream)) => stream.push(*event),
+                None => threads.push((thread_id, vec![*event])),
+            }
+        }
+        for (_, stream) in &mut threads {
+            stream.sort_by_key(|event| event.timestamp_ns);
+        }
+        self.write_v2_session(&threads);
+    }
+
+    /// Writes the trace as an index-only V2 session with one stream per
+    /// entry of `threads`, each holding its events in timestamp order
+    pub fn write_v2_session(&self, threads: &[(u32, Vec<IndexEvent>)]) {
+        for entry in fs::read_dir(self.trace_dir()).expect("trace dir") {
+            let path = entry.expect("dir entry").path();
+            if path.is_dir() {
+                fs::remove_dir_all(path).expect("remove thread dir");
+            }
+        }
+
+        let timestamps = || {
+            threads
+                .iter()
+                .flat_map(|(_, events)| events.iter().map(|event| event.timestamp_ns))
+        };
+        self.write_manifest_json(json!({
+            "threads": threads
+                .iter()
+                .map(|(thread_id, _)| json!({ "id": thread_id, "has_detail": false }))
+                .collect::<Vec<_>>(),
+            "time_start_ns": timestamps().min().unwrap_or(0),
+            "time_end_ns": timestamps().max().unwrap_or(0),
+        }));
+
+        for (thread_id, events) in threads {
+            let thread_dir = self.trace_dir().join(format!("thread_{thread_id}"));
+            fs::create_dir_all(&thread_dir).expect("thread dir");
+            let event_count = events.len() as u64;
+            let time_start_ns = events.first().map_or(0, |event| event.timestamp_ns);
+            let time_end_ns = events.last().map_or(0, |event| event.timestamp_ns);
+            let header = AtfIndexHeader {
+                magic: *b"ATI2",
+                endian: 0x01,
+                version: 1,
+                arch: 1,
+                os: 4,
+                flags: 0,
+                thread_id: *thread_id,
+                clock_type: 3,
+                _reserved1: [0; 3],
+                _reserved2: 0,
+                event_size: 32,
+                event_count: event_count as u32,
+                events_offset: 64,
+                footer_offset: 64 + event_count * 32,
+                time_start_ns,
+                time_end_ns,
+            };
+            let footer = AtfIndexFooter {
+                magic: *b"2ITA",
+                checksum: 0,
+                event_count,
+                time_start_ns,
+                time_end_ns,
+                bytes_written: event_count * 32,
+                reserved: [0; 24],
+            };
+
+            let mut file = File::create(thread_dir.join("index.atf")).expect("index file");
+            file.write_all(as_bytes(&header)).expect("write header");
+            for event in events {
+                file.write_all(as_bytes(event)).expect("write index event");
+            }
+            file.write_all(as_bytes(&footer)).expect("write footer");
+            file.flush().expect("flush index");
+        }
+    }
+
+    /// Writes `records`, built with [`function_detail`], as the detail file
+    /// of `thread_id`, whose index events link them by `detail_seq`
+    pub fn write_detail(&self, thread_id: u32, records: &[Vec<u8>]) {
+        let thread_dir = self.trace_dir().join(format!("thread_{thread_id}"));
+        fs::create_dir_all(&thread_dir).expect("thread dir");
+        let bytes_length: usize = records.iter().map(Vec::len).sum();
+        let header = AtfDetailHeader {
+            magic: *b"ATD2",
+            endian: 0x01,
+            version: 1,
+            arch: 1,
+            os: 4,
+            flags: 0,
+            thread_id,
+            _reserved1: 0,
+            events_offset: 64,
+            event_count: records.len() as u64,
+            bytes_length: bytes_length as u64,
+            index_seq_start: 0,
+            index_seq_end: 0,
+            _reserved2: [0; 4],
+        };
+        let footer = AtfDetailFooter {
+            magic: *b"2DTA",
+            checksum: 0,
+            event_count: records.len() as u64,
+            bytes_length: bytes_length as u64,
+            time_start_ns: 0,
+            time_end_ns: 0,
+            reserved: [0; 24],
+        };
+
+        let mut file = File::create(thread_dir.join("detail.atf")).expect("detail file");
+        file.write_all(as_bytes(&header)).expect("write header");
+        for record in records {
+            file.write_all(record).expect("write detail record");
+        }
+        file.write_all(as_bytes(&footer)).expect("write footer");
+        file.flush().expect("flush detail");
+    }
+
+    /// Serves the trace root over HTTP from a background thread and returns
+    /// its base URL. Every file is sent whole, ignoring `Range`; missing
+    /// files are 404s. The server lives until the test process exits.
+    pub fn serve_over_http(&self) -> String {
+        use hyper::{
+            service::{make_service_fn, service_fn},
+            Body, Request, Response, Server, StatusCode,
+        };
+        use std::convert::Infallible;
+
+        let root = self.trace_root();
+        let (addr_tx, addr_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("runtime");
+            runtime.block_on(async move {
+                let make_service = make_service_fn(move |_| {
+                    let root = root.clone();
+                    async move {
+                        Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                            let path = root.join(request.uri().path().trim_start_matches('/'));
+                            async move {
+                                let response = match fs::read(path) {
+                                    Ok(contents) => Response::new(Body::from(contents)),
+                                    Err(_) => {
+                                        let mut response = Response::new(Body::empty());
+                                        *response.status_mut() = StatusCode::NOT_FOUND;
+                                        response
+                                    }
+                                };
+                                Ok::<_, Infallible>(response)
+                            }
+                        }))
+                    }
+                });
+                let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+                addr_tx.send(server.local_addr()).expect("send addr");
+                server.await.expect("serve trace root");
+            });
+        });
+        format!("http://{}", addr_rx.recv().expect("server addr"))
+    }
+}
+
+/// Raw bytes of a packed V2 record
+fn as_bytes<T: Copy>(record: &T) -> &[u8] {
+    // SAFETY: the V2 records are `repr(C, packed)` plain data
+    unsafe { std::slice::from_raw_parts(record as *const T as *const u8, std::mem::size_of::<T>()) }
+}
+
+/// A V2 index event without a detail record
+pub fn index_event(
+    timestamp_ns: u64,
+    thread_id: u32,
+    event_kind: u32,
+    function_id: u64,
+) -> IndexEvent {
+    IndexEvent {
+        timestamp_ns,
+        function_id,
+        thread_id,
+        event_kind,
+        call_depth: 0,
+        detail_seq: ATF_NO_DETAIL_SEQ,
+    }
+}
+
+/// A call of `function_id`, reported by handlers as its hex symbol
+pub fn function_call_event(timestamp_ns: u64, thread_id: u32, function_id: u64) -> IndexEvent {
+    index_event(timestamp_ns, thread_id, ATF_EVENT_KIND_CALL, function_id)
+}
+
+pub fn function_return_event(timestamp_ns: u64, thread_id: u32, function_id: u64) -> IndexEvent {
+    index_event(timestamp_ns, thread_id, ATF_EVENT_KIND_RETURN, function_id)
+}
+
+/// An exception event, decoded as `ParsedEventKind::Unknown`
+pub fn exception_event(timestamp_ns: u64, thread_id: u32) -> IndexEvent {
+    index_event(timestamp_ns, thread_id, ATF_EVENT_KIND_EXCEPTION, 0)
+}
+
+/// A function call or return detail record with zeroed registers and
+/// `stack` as its snapshot
+pub fn function_detail(
+    event_type: u16,
+    index_seq: u32,
+    timestamp_ns: u64,
+    thread_id: u32,
+    function_id: u64,
+    stack: &[u8],
+) -> Vec<u8> {
+    let total_length = 24 + ATF_DETAIL_FUNCTION_PAYLOAD_SIZE + stack.len();
+    let mut record = Vec::with_capacity(total_length);
+    record.extend_from_slice(&(total_length as u32).to_le_bytes());
+    record.extend_from_slice(&event_type.to_le_bytes());
+    record.extend_from_slice(&0u16.to_le_bytes());
+    record.extend_from_slice(&index_seq.to_le_bytes());
+    record.extend_from_slice(&thread_id.to_le_bytes());
+    record.extend_from_slice(&timestamp_ns.to_le_bytes());
+    record.extend_from_slice(&function_id.to_le_bytes());
+    record.resize(24 + 96, 0);
+    record.extend_from_slice(&(stack.len() as u16).to_le_bytes());
+    record.extend_from_slice(&0u16.to_le_bytes());
+    record.extend_from_slice(stack);
+    record
+}
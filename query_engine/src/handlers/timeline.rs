@@ -0,0 +1,456 @@
+use std::{collections::BTreeMap, path::PathBuf, time::Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::task;
+
+use crate::{
+    atf::AtfError,
+    handlers::{
+        paths::validate_trace_id,
+        source::open_trace,
+        spans::{coalesce_short_spans, reconstruct_spans, SpanCandidate},
+    },
+    server::{
+        handler::{JsonRpcHandler, JsonRpcResult},
+        types::JsonRpcError,
+        JsonRpcServer,
+    },
+};
+
+const DEFAULT_MAX_SPANS_PER_THREAD: u32 = 1000;
+const MAX_SPANS_PER_THREAD: u32 = 10_000;
+
+fn default_max_spans_per_thread() -> u32 {
+    DEFAULT_MAX_SPANS_PER_THREAD
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineParams {
+    pub trace_id: String,
+    /// Window start; defaults to the first top-level span.
+    #[serde(default)]
+    pub time_start_ns: Option<u64>,
+    /// Window end; defaults to the end of the last top-level span.
+    #[serde(default)]
+    pub time_end_ns: Option<u64>,
+    /// Adjacent spans separated by less than this gap, at least one of which
+    /// is shorter than it, are drawn as one block. 0 keeps every span.
+    #[serde(default)]
+    pub resolution: u64,
+    #[serde(default = "default_max_spans_per_thread")]
+    pub max_spans_per_thread: u32,
+    /// Coalesces runs of adjacent same-function spans shorter than this
+    /// before the lanes are shaped, keeping the function name on the block.
+    #[serde(default)]
+    pub min_duration_ns: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineResponse {
+    pub trace_id: String,
+    pub time_start_ns: u64,
+    pub time_end_ns: u64,
+    /// Swimlanes ordered by thread id.
+    pub threads: Vec<ThreadLane>,
+    pub execution_time_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadLane {
+    pub thread_id: u32,
+    /// Top-level spans in the window before any merging.
+    pub span_count: u64,
+    /// Non-overlapping blocks ordered by start time.
+    pub spans: Vec<TimelineSpan>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineSpan {
+    pub start_time_ns: u64,
+    pub duration_ns: u64,
+    /// Set when every span folded into this block called the same function.
+    pub function_name: Option<String>,
+    /// Number of top-level spans this block covers; 1 for an unmerged span.
+    pub merged_count: u32,
+}
+
+impl TimelineSpan {
+    fn end_time_ns(&self) -> u64 {
+        self.start_time_ns + self.duration_ns
+    }
+
+    fn absorb(&mut self, other: TimelineSpan) {
+        let end = self.end_time_ns().max(other.end_time_ns());
+        self.duration_ns = end - self.start_time_ns;
+        if self.function_name != other.function_name {
+            self.function_name = None;
+        }
+        self.merged_count = self.merged_count.saturating_add(other.merged_count);
+    }
+}
+
+impl From<&SpanCandidate> for TimelineSpan {
+    fn from(span: &SpanCandidate) -> Self {
+        Self {
+            start_time_ns: span.start_time_ns,
+            duration_ns: span.duration_ns,
+            function_name: span.function_name.clone(),
+            merged_count: span.merged_count,
+        }
+    }
+}
+
+/// Folds tiny neighbours together, then buckets the lane by time so it never
+/// exceeds `max_spans`. Input must be sorted and non-overlapping, which holds
+/// for depth-0 spans of a single thread; both passes preserve that.
+fn shape_lane(
+    spans: Vec<TimelineSpan>,
+    window: (u64, u64),
+    resolution: u64,
+    max_spans: usize,
+) -> Vec<TimelineSpan> {
+    let mut merged: Vec<TimelineSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        if let Some(last) = merged.last_mut() {
+            let gap = span.start_time_ns.saturating_sub(last.end_time_ns());
+            let tiny = last.duration_ns < resolution || span.duration_ns < resolution;
+            if gap < resolution && tiny {
+                last.absorb(span);
+                continue;
+            }
+        }
+        merged.push(span);
+    }
+
+    if merged.len() <= max_spans {
+        return merged;
+    }
+
+    let (start, end) = window;
+    let bucket_width = (end.saturating_sub(start) / max_spans as u64).max(1);
+    let mut bucketed: Vec<(u64, TimelineSpan)> = Vec::with_capacity(max_spans);
+    for span in merged {
+        let bucket =
+            (span.start_time_ns.saturating_sub(start) / bucket_width).min(max_spans as u64 - 1);
+        match bucketed.last_mut() {
+            Some((last_bucket, last)) if *last_bucket == bucket => last.absorb(span),
+            _ => bucketed.push((bucket, span)),
+        }
+    }
+    bucketed.into_iter().map(|(_, span)| span).collect()
+}
+
+#[derive(Clone)]
+pub struct TimelineHandler {
+    trace_root_dir: PathBuf,
+}
+
+impl TimelineHandler {
+    pub fn new(trace_root_dir: PathBuf) -> Self {
+        Self { trace_root_dir }
+    }
+
+    pub fn register(self, server: &JsonRpcServer) {
+        server
+            .handler_registry()
+            .register_handler("trace.timeline", self);
+    }
+
+    fn validate_params(params: &TimelineParams) -> Result<(), JsonRpcError> {
+        validate_trace_id(&params.trace_id)?;
+        if params.max_spans_per_thread == 0 || params.max_spans_per_thread > MAX_SPANS_PER_THREAD {
+            return Err(JsonRpcError::invalid_params(
+                "maxSpansPerThread must be between 1 and 10000",
+            ));
+        }
+        if let (Some(start), Some(end)) = (params.time_start_ns, params.time_end_ns) {
+            if start >= end {
+                return Err(JsonRpcError::invalid_params(
+                    "timeStartNs must be less than timeEndNs",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get_timeline(
+        &self,
+        params: TimelineParams,
+    ) -> Result<TimelineResponse, JsonRpcError> {
+        Self::validate_params(&params)?;
+
+        let trace_id = params.trace_id.trim().to_string();
+        let trace_dir = self.trace_root_dir.join(&trace_id);
+        let started = Instant::now();
+
+        let mut spans = task::spawn_blocking(move || {
+            let source = open_trace(&trace_dir)?;
+            reconstruct_spans(source.as_ref())
+        })
+        .await
+        .map_err(|err| JsonRpcError::internal(format!("span reconstruction task failed: {err}")))?
+        .map_err(map_atf_error)?;
+
+        if let Some(min_duration_ns) = params.min_duration_ns {
+            spans = coalesce_short_spans(spans, min_duration_ns);
+        }
+
+        let top_level: Vec<&SpanCandidate> = spans.iter().filter(|span| span.depth == 0).collect();
+        let window_start = params.time_start_ns.unwrap_or_else(|| {
+            top_level
+                .iter()
+                .map(|span| span.start_time_ns)
+                .min()
+                .unwrap_or(0)
+        });
+        let window_end = params.time_end_ns.unwrap_or_else(|| {
+            top_level
+                .iter()
+                .map(|span| span.end_time_ns)
+                .max()
+                .unwrap_or(window_start)
+        });
+
+        let mut lanes: BTreeMap<u32, Vec<TimelineSpan>> = BTreeMap::new();
+        for span in top_level {
+            if span.end_time_ns < window_start || span.start_time_ns > window_end {
+                continue;
+            }
+            lanes
+                .entry(span.thread_id)
+                .or_default()
+                .push(TimelineSpan::from(span));
+        }
+
+        let threads = lanes
+            .into_iter()
+            .map(|(thread_id, mut spans)| {
+                spans.sort_by_key(|span| span.start_time_ns);
+                let span_count = spans.len() as u64;
+                ThreadLane {
+                    thread_id,
+                    span_count,
+                    spans: shape_lane(
+                        spans,
+                        (window_start, window_end),
+                        params.resolution,
+                        params.max_spans_per_thread as usize,
+                    ),
+                }
+            })
+            .collect();
+
+        Ok(TimelineResponse {
+            trace_id,
+            time_start_ns: window_start,
+            time_end_ns: window_end,
+            threads,
+            execution_time_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcHandler for TimelineHandler {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
+        let params: TimelineParams = match params {
+            Some(value) => TimelineParams::deserialize(value).map_err(|err| {
+                JsonRpcError::invalid_params(format!("invalid trace.timeline parameters: {err}"))
+            })?,
+            None => {
+                return Err(JsonRpcError::invalid_params(
+                    "missing trace.timeline parameters",
+                ))
+            }
+        };
+
+        let response = self.get_timeline(params).await?;
+        serde_json::to_value(response)
+            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+    }
+}
+
+fn map_atf_error(err: AtfError) -> JsonRpcError {
+    match err {
+        AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+            JsonRpcError::trace_not_found()
+        }
+        other => JsonRpcError::internal(format!("failed to load trace: {other}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use crate::atf::IndexEvent;
+    use crate::handlers::test_support::{
+        function_call_event as call, function_return_event as ret, TraceFixture,
+    };
+    use serde_json::json;
+
+    const MAIN: u64 = 0x100;
+    const CHILD: u64 = 0x200;
+    const WORKER: u64 = 0x300;
+    const TAIL: u64 = 0x400;
+    const POLL: u64 = 0x500;
+    const RENDER: u64 = 0x600;
+
+    fn write_trace(events: &[IndexEvent]) -> TraceFixture {
+        let fixture = TraceFixture::new("trace");
+        fixture.write_events(events);
+        fixture
+    }
+
+    fn params(value: Value) -> TimelineParams {
+        serde_json::from_value(value).expect("params")
+    }
+
+    fn lane_span(start: u64, duration: u64, name: Option<&str>, merged: u32) -> TimelineSpan {
+        TimelineSpan {
+            start_time_ns: start,
+            duration_ns: duration,
+            function_name: name.map(str::to_string),
+            merged_count: merged,
+        }
+    }
+
+    #[tokio::test]
+    async fn timeline__two_threads__then_top_level_spans_grouped_and_sorted() {
+        let fixture = write_trace(&[
+            call(50, 2, WORKER),
+            call(100, 1, MAIN),
+            call(110, 1, CHILD),
+            ret(120, 1, CHILD),
+            ret(150, 2, WORKER),
+            ret(200, 1, MAIN),
+            call(300, 1, TAIL),
+            ret(350, 1, TAIL),
+        ]);
+        let handler = TimelineHandler::new(fixture.trace_root());
+
+        let response = handler
+            .get_timeline(params(json!({ "traceId": "trace" })))
+            .await
+            .expect("timeline");
+
+        assert_eq!((response.time_start_ns, response.time_end_ns), (50, 350));
+        let thread_ids: Vec<u32> = response.threads.iter().map(|lane| lane.thread_id).collect();
+        assert_eq!(thread_ids, vec![1, 2]);
+        assert_eq!(
+            response.threads[0].spans,
+            vec![
+                lane_span(100, 100, Some("0x100"), 1),
+                lane_span(300, 50, Some("0x400"), 1),
+            ]
+        );
+        assert_eq!(
+            response.threads[1].spans,
+            vec![lane_span(50, 100, Some("0x300"), 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn timeline__window__then_excludes_spans_outside() {
+        let fixture = write_trace(&[
+            call(100, 1, MAIN),
+            ret(200, 1, MAIN),
+            call(500, 1, TAIL),
+            ret(600, 1, TAIL),
+        ]);
+        let handler = TimelineHandler::new(fixture.trace_root());
+
+        let response = handler
+            .get_timeline(params(
+                json!({ "traceId": "trace", "timeStartNs": 400, "timeEndNs": 700 }),
+            ))
+            .await
+            .expect("timeline");
+
+        assert_eq!(response.threads[0].span_count, 1);
+        assert_eq!(
+            response.threads[0].spans[0].function_name.as_deref(),
+            Some("0x400")
+        );
+    }
+
+    #[test]
+    fn shape_lane__resolution__then_merges_tiny_neighbours_only() {
+        let spans = vec![
+            lane_span(0, 2, Some("tick"), 1),
+            lane_span(3, 2, Some("tick"), 1),
+            lane_span(6, 2, Some("tock"), 1),
+            lane_span(100, 50, Some("long"), 1),
+        ];
+
+        let shaped = shape_lane(spans, (0, 150), 5, 100);
+        assert_eq!(
+            shaped,
+            vec![
+                lane_span(0, 8, None, 3),
+                lane_span(100, 50, Some("long"), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn shape_lane__over_max_spans__then_bounded_and_non_overlapping() {
+        let spans: Vec<TimelineSpan> = (0..1000)
+            .map(|index| lane_span(index * 10, 5, Some("f"), 1))
+            .collect();
+
+        let shaped = shape_lane(spans, (0, 10_000), 0, 16);
+        assert!(shaped.len() <= 16);
+        assert_eq!(
+            shaped.iter().map(|span| span.merged_count).sum::<u32>(),
+            1000
+        );
+        for pair in shaped.windows(2) {
+            assert!(pair[0].end_time_ns() <= pair[1].start_time_ns);
+        }
+    }
+
+    #[tokio::test]
+    async fn timeline__min_duration_ns__then_same_function_runs_coalesced() {
+        let fixture = write_trace(&[
+            call(100, 1, POLL),
+            ret(101, 1, POLL),
+            call(110, 1, POLL),
+            ret(111, 1, POLL),
+            call(120, 1, RENDER),
+            ret(500, 1, RENDER),
+        ]);
+        let handler = TimelineHandler::new(fixture.trace_root());
+
+        let response = handler
+            .get_timeline(params(json!({ "traceId": "trace", "minDurationNs": 10 })))
+            .await
+            .expect("timeline");
+
+        assert_eq!(response.threads[0].span_count, 2);
+        assert_eq!(
+            response.threads[0].spans,
+            vec![
+                lane_span(100, 11, Some("0x500"), 2),
+                lane_span(120, 380, Some("0x600"), 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn timeline__zero_max_spans__then_invalid_params() {
+        let handler = TimelineHandler::new(PathBuf::from("/nonexistent"));
+        let err = handler
+            .call(Some(json!({ "traceId": "trace", "maxSpansPerThread": 0 })))
+            .await
+            .expect_err("expected error");
+        assert_eq!(err.code, -32602);
+    }
+}
@@ -1,24 +1,37 @@
 use std::{
-    collections::VecDeque,
-    fs::{self, Metadata},
+    collections::{BTreeMap, VecDeque},
+    fs,
     io::{self, Read},
+    mem,
     num::NonZeroUsize,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 
 use async_trait::async_trait;
-use lru::LruCache;
-use parking_lot::Mutex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::task;
 
 use crate::{
-    atf::{AtfError, AtfReader, ManifestInfo, ParsedEvent},
+    atf::{
+        v2::{
+            ATF_DETAIL_EVENT_FUNCTION_CALL, ATF_DETAIL_EVENT_FUNCTION_RETURN,
+            ATF_DETAIL_FUNCTION_REGISTER_BYTES, ATF_EVENT_KIND_CALL,
+        },
+        AtfError, ParsedEvent,
+    },
+    handlers::{
+        envelope::{envelope_result, TRACE_INFO_SCHEMA_VERSION},
+        paths::validate_trace_id,
+        source::{EventSource, V2EventSource},
+        wall_clock::WallClockAnchor,
+    },
     server::{
         handler::{JsonRpcHandler, JsonRpcResult},
+        memory::{BudgetedLru, CacheWeight, MemoryBudget},
         types::JsonRpcError,
         JsonRpcServer,
     },
@@ -30,7 +43,7 @@ const SAMPLE_COUNT: usize = 5;
 pub struct TraceInfoHandler {
     trace_root_dir: PathBuf,
     cache_ttl: Duration,
-    cache: Option<Arc<Mutex<LruCache<String, CachedTraceInfo>>>>,
+    cache: Option<Arc<BudgetedLru<String, CachedTraceInfo>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -38,22 +51,45 @@ struct CachedTraceInfo {
     base: TraceInfoResponse,
     checksums: Option<TraceChecksums>,
     samples: Option<TraceSamples>,
+    payload_stats: Option<PayloadStats>,
     cached_at: Instant,
     manifest_mtime: Option<SystemTime>,
     events_mtime: Option<SystemTime>,
 }
 
-#[derive(Debug, Deserialize)]
-struct TraceInfoParams {
+impl CacheWeight for CachedTraceInfo {
+    /// Serialized sizes stand in for the heap the response parts hold.
+    fn weight_bytes(&self) -> usize {
+        fn json_len<T: Serialize>(value: &T) -> usize {
+            serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+        }
+        mem::size_of::<Self>()
+            + json_len(&self.base)
+            + json_len(&self.checksums)
+            + json_len(&self.samples)
+            + json_len(&self.payload_stats)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct TraceInfoParams {
     #[serde(rename = "traceId")]
-    trace_id: String,
+    pub trace_id: String,
     #[serde(default)]
-    include_checksums: bool,
+    pub include_checksums: bool,
     #[serde(default)]
-    include_samples: bool,
+    pub include_samples: bool,
+    #[serde(
+        default,
+        rename = "includePayloadStats",
+        alias = "include_payload_stats"
+    )]
+    pub include_payload_stats: bool,
+    #[serde(default, rename = "includeWallClock", alias = "include_wall_clock")]
+    pub include_wall_clock: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct TraceInfoResponse {
     #[serde(rename = "traceId")]
     pub trace_id: String,
@@ -74,12 +110,36 @@ pub struct TraceInfoResponse {
     pub checksums: Option<TraceChecksums>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub samples: Option<TraceSamples>,
+    #[serde(rename = "payloadStats", skip_serializing_if = "Option::is_none")]
+    pub payload_stats: Option<PayloadStats>,
+    #[serde(rename = "wallClock", skip_serializing_if = "Option::is_none")]
+    pub wall_clock: Option<WallClockRange>,
+    /// One call/return pair in this many was kept when the trace was
+    /// decimated; absent for traces holding every call
+    #[serde(rename = "decimationFactor", skip_serializing_if = "Option::is_none")]
+    pub decimation_factor: Option<u32>,
+    /// Register values were zeroed by `trace.stripRegisters`
+    #[serde(
+        default,
+        rename = "registersStripped",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub registers_stripped: bool,
+}
+
+/// ISO-8601 wall-clock times of the trace bounds; null when the trace
+/// recorded no wall-clock anchor.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct WallClockRange {
+    pub start: Option<String>,
+    pub end: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct TraceFileInfo {
     #[serde(rename = "manifestSize")]
     pub manifest_size: u64,
+    /// Combined size of the per-thread index and detail files
     #[serde(rename = "eventsSize")]
     pub events_size: u64,
     #[serde(rename = "totalSize")]
@@ -88,15 +148,16 @@ pub struct TraceFileInfo {
     pub avg_event_size: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct TraceChecksums {
     #[serde(rename = "manifestMd5")]
     pub manifest_md5: String,
+    /// Digest of the per-thread index and detail files, read in path order
     #[serde(rename = "eventsMd5")]
     pub events_md5: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default, JsonSchema)]
 pub struct TraceSamples {
     #[serde(rename = "firstEvents")]
     pub first_events: Vec<EventSample>,
@@ -106,7 +167,31 @@ pub struct TraceSamples {
     pub random_events: Vec<EventSample>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Bytes in the session's detail records broken down by event type.
+///
+/// `envelopeBytes` covers the 24-byte record header every detail event
+/// carries regardless of payload.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default, JsonSchema)]
+pub struct PayloadStats {
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    #[serde(rename = "envelopeBytes")]
+    pub envelope_bytes: u64,
+    #[serde(rename = "byType")]
+    pub by_type: BTreeMap<String, PayloadTypeStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default, JsonSchema)]
+pub struct PayloadTypeStats {
+    pub count: u64,
+    pub bytes: u64,
+    /// Share of `bytes` taken by the `registers` (x0-x7, lr, fp, sp) and the
+    /// `stackSnapshot` of function call and return records.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct EventSample {
     #[serde(rename = "timestampNs")]
     pub timestamp_ns: u64,
@@ -124,6 +209,47 @@ struct CacheSnapshot {
     base: TraceInfoResponse,
     checksums: Option<TraceChecksums>,
     samples: Option<TraceSamples>,
+    payload_stats: Option<PayloadStats>,
+}
+
+/// The per-thread `index.atf` and `detail.atf` files of a V2 session
+pub(crate) struct EventFiles {
+    pub(crate) paths: Vec<PathBuf>,
+    pub(crate) size: u64,
+    /// Latest modification time across the files
+    pub(crate) mtime: Option<SystemTime>,
+}
+
+impl EventFiles {
+    pub(crate) fn scan(trace_dir: &Path) -> io::Result<Self> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(trace_dir)? {
+            let entry = entry?;
+            let is_thread_dir = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with("thread_"));
+            if !is_thread_dir || !entry.file_type()?.is_dir() {
+                continue;
+            }
+            for name in ["index.atf", "detail.atf"] {
+                let path = entry.path().join(name);
+                if path.is_file() {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort();
+
+        let mut size = 0;
+        let mut mtime = None;
+        for path in &paths {
+            let meta = fs::metadata(path)?;
+            size += meta.len();
+            mtime = mtime.max(meta.modified().ok());
+        }
+        Ok(Self { paths, size, mtime })
+    }
 }
 
 impl TraceInfoHandler {
@@ -132,7 +258,11 @@ impl TraceInfoHandler {
             None
         } else {
             let capacity = NonZeroUsize::new(cache_capacity).expect("validated non-zero");
-            Some(Arc::new(Mutex::new(LruCache::new(capacity))))
+            Some(BudgetedLru::new(
+                "trace.info",
+                capacity,
+                Arc::new(MemoryBudget::unlimited()),
+            ))
         };
         Self {
             trace_root_dir,
@@ -141,74 +271,104 @@ impl TraceInfoHandler {
         }
     }
 
+    /// Counts the cached responses against `budget`, which evicts them
+    /// alongside other caches' entries. The cache starts empty.
+    pub fn with_memory_budget(mut self, budget: Arc<MemoryBudget>) -> Self {
+        self.cache = self
+            .cache
+            .map(|cache| BudgetedLru::new("trace.info", cache.capacity(), budget));
+        self
+    }
+
+    /// Also joins the server's memory budget.
     pub fn register(self, server: &JsonRpcServer) {
+        let handler = self.with_memory_budget(server.memory_budget());
         server
             .handler_registry()
-            .register_handler("trace.info", self);
+            .register_handler("trace.info", handler);
     }
 
-    async fn get_trace_info(
+    pub async fn get_trace_info(
         &self,
         params: TraceInfoParams,
     ) -> Result<TraceInfoResponse, JsonRpcError> {
-        let trace_id = params.trace_id.trim();
-        if trace_id.is_empty() {
-            return Err(JsonRpcError::invalid_params("traceId must not be empty"));
-        }
+        let trace_id = validate_trace_id(&params.trace_id)?;
 
         let trace_dir = self.trace_root_dir.join(trace_id);
         if !trace_dir.exists() || !trace_dir.is_dir() {
             return Err(JsonRpcError::trace_not_found());
         }
 
-        let manifest_path = trace_dir.join("trace.json");
-        let events_path = trace_dir.join("events.bin");
-
+        let manifest_path = trace_dir.join("manifest.json");
         let manifest_meta =
             fs::metadata(&manifest_path).map_err(|err| map_metadata_error("manifest", err))?;
-        let events_meta =
-            fs::metadata(&events_path).map_err(|err| map_metadata_error("events", err))?;
+        let event_files =
+            EventFiles::scan(&trace_dir).map_err(|err| map_metadata_error("events", err))?;
 
         let manifest_mtime = manifest_meta.modified().ok();
-        let events_mtime = events_meta.modified().ok();
+        let events_mtime = event_files.mtime;
 
         if let Some(snapshot) = self.fetch_from_cache(trace_id, manifest_mtime, events_mtime) {
-            return self
-                .build_response_from_cache(trace_id, snapshot, &params, manifest_path, events_path)
-                .await;
+            let mut response = self
+                .build_response_from_cache(
+                    trace_id,
+                    snapshot,
+                    &params,
+                    manifest_path.clone(),
+                    event_files.paths,
+                )
+                .await?;
+            if params.include_wall_clock {
+                response.wall_clock = Some(wall_clock_range(&manifest_path, &response));
+            }
+            return Ok(response);
         }
 
-        let reader = AtfReader::open(&trace_dir).map_err(|err| self.map_atf_error(err))?;
+        let source = V2EventSource::open(&trace_dir).map_err(|err| self.map_atf_error(err))?;
         let base =
-            self.build_base_response(trace_id, reader.manifest(), &manifest_meta, &events_meta);
+            self.build_base_response(trace_id, &source, manifest_meta.len(), event_files.size);
 
         let mut response = base.clone();
         let mut cached_checksums = None;
         let mut cached_samples = None;
+        let mut cached_payload_stats = None;
 
         if params.include_checksums {
             let checksums = self
-                .compute_checksums(manifest_path.clone(), events_path.clone())
+                .compute_checksums(manifest_path.clone(), event_files.paths)
                 .await?;
             response.checksums = Some(checksums.clone());
             cached_checksums = Some(checksums);
         }
 
         if params.include_samples {
-            let samples = self.compute_samples(reader.clone()).await?;
+            let samples = self.compute_samples(trace_dir.clone()).await?;
             response.samples = Some(samples.clone());
             cached_samples = Some(samples);
         }
 
+        if params.include_payload_stats {
+            let payload_stats = self.compute_payload_stats(trace_dir).await?;
+            response.payload_stats = Some(payload_stats.clone());
+            cached_payload_stats = Some(payload_stats);
+        }
+
         self.cache_response(
             trace_id,
-            base,
+            CacheSnapshot {
+                base,
+                checksums: cached_checksums,
+                samples: cached_samples,
+                payload_stats: cached_payload_stats,
+            },
             manifest_mtime,
             events_mtime,
-            cached_checksums.clone(),
-            cached_samples.clone(),
         );
 
+        if params.include_wall_clock {
+            response.wall_clock = Some(wall_clock_range(&manifest_path, &response));
+        }
+
         Ok(response)
     }
 
@@ -219,32 +379,32 @@ impl TraceInfoHandler {
         events_mtime: Option<SystemTime>,
     ) -> Option<CacheSnapshot> {
         let cache = self.cache.as_ref()?;
-        let mut cache_lock = cache.lock();
-        let entry = cache_lock.get(trace_id)?;
+        let entry = cache.get(trace_id)?;
 
         if entry.cached_at.elapsed() > self.cache_ttl {
-            cache_lock.pop(trace_id);
+            cache.pop(trace_id);
             return None;
         }
 
         if let (Some(current), Some(stored)) = (manifest_mtime, entry.manifest_mtime) {
             if current > stored {
-                cache_lock.pop(trace_id);
+                cache.pop(trace_id);
                 return None;
             }
         }
 
         if let (Some(current), Some(stored)) = (events_mtime, entry.events_mtime) {
             if current > stored {
-                cache_lock.pop(trace_id);
+                cache.pop(trace_id);
                 return None;
             }
         }
 
         Some(CacheSnapshot {
-            base: entry.base.clone(),
-            checksums: entry.checksums.clone(),
-            samples: entry.samples.clone(),
+            base: entry.base,
+            checksums: entry.checksums,
+            samples: entry.samples,
+            payload_stats: entry.payload_stats,
         })
     }
 
@@ -254,19 +414,18 @@ impl TraceInfoHandler {
         snapshot: CacheSnapshot,
         params: &TraceInfoParams,
         manifest_path: PathBuf,
-        events_path: PathBuf,
+        event_paths: Vec<PathBuf>,
     ) -> Result<TraceInfoResponse, JsonRpcError> {
         let mut response = snapshot.base.clone();
         let mut new_checksums = None;
         let mut new_samples = None;
+        let mut new_payload_stats = None;
 
         if params.include_checksums {
             if let Some(checksums) = snapshot.checksums.clone() {
                 response.checksums = Some(checksums);
             } else {
-                let checksums = self
-                    .compute_checksums(manifest_path.clone(), events_path.clone())
-                    .await?;
+                let checksums = self.compute_checksums(manifest_path, event_paths).await?;
                 response.checksums = Some(checksums.clone());
                 new_checksums = Some(checksums);
             }
@@ -276,20 +435,35 @@ impl TraceInfoHandler {
             if let Some(samples) = snapshot.samples.clone() {
                 response.samples = Some(samples);
             } else {
-                let reader = AtfReader::open(self.trace_root_dir.join(trace_id))
-                    .map_err(|err| self.map_atf_error(err))?;
-                let samples = self.compute_samples(reader).await?;
+                let samples = self
+                    .compute_samples(self.trace_root_dir.join(trace_id))
+                    .await?;
                 response.samples = Some(samples.clone());
                 new_samples = Some(samples);
             }
         }
 
+        if params.include_payload_stats {
+            if let Some(payload_stats) = snapshot.payload_stats.clone() {
+                response.payload_stats = Some(payload_stats);
+            } else {
+                let payload_stats = self
+                    .compute_payload_stats(self.trace_root_dir.join(trace_id))
+                    .await?;
+                response.payload_stats = Some(payload_stats.clone());
+                new_payload_stats = Some(payload_stats);
+            }
+        }
+
         if let Some(checksums) = new_checksums {
             self.update_cached_checksums(trace_id, checksums);
         }
         if let Some(samples) = new_samples {
             self.update_cached_samples(trace_id, samples);
         }
+        if let Some(payload_stats) = new_payload_stats {
+            self.update_cached_payload_stats(trace_id, payload_stats);
+        }
 
         Ok(response)
     }
@@ -297,62 +471,66 @@ impl TraceInfoHandler {
     fn cache_response(
         &self,
         trace_id: &str,
-        mut base: TraceInfoResponse,
+        snapshot: CacheSnapshot,
         manifest_mtime: Option<SystemTime>,
         events_mtime: Option<SystemTime>,
-        checksums: Option<TraceChecksums>,
-        samples: Option<TraceSamples>,
     ) {
         let cache = match &self.cache {
             Some(cache) => cache,
             None => return,
         };
 
+        let CacheSnapshot {
+            mut base,
+            checksums,
+            samples,
+            payload_stats,
+        } = snapshot;
         base.checksums = None;
         base.samples = None;
+        base.payload_stats = None;
 
         let entry = CachedTraceInfo {
             base,
             checksums,
             samples,
+            payload_stats,
             cached_at: Instant::now(),
             manifest_mtime,
             events_mtime,
         };
 
-        cache.lock().put(trace_id.to_string(), entry);
+        cache.put(trace_id.to_string(), entry);
     }
 
     fn update_cached_checksums(&self, trace_id: &str, checksums: TraceChecksums) {
         if let Some(cache) = &self.cache {
-            if let Some(entry) = cache.lock().get_mut(trace_id) {
-                entry.checksums = Some(checksums);
-            }
+            cache.update(trace_id, |entry| entry.checksums = Some(checksums));
         }
     }
 
     fn update_cached_samples(&self, trace_id: &str, samples: TraceSamples) {
         if let Some(cache) = &self.cache {
-            if let Some(entry) = cache.lock().get_mut(trace_id) {
-                entry.samples = Some(samples);
-            }
+            cache.update(trace_id, |entry| entry.samples = Some(samples));
         }
     }
 
+    fn update_cached_payload_stats(&self, trace_id: &str, payload_stats: PayloadStats) {
+        if let Some(cache) = &self.cache {
+            cache.update(trace_id, |entry| entry.payload_stats = Some(payload_stats));
+        }
+    }
+
+    /// Spans are counted as the session's call events, one per span opened.
     fn build_base_response(
         &self,
         trace_id: &str,
-        manifest: &ManifestInfo,
-        manifest_meta: &Metadata,
-        events_meta: &Metadata,
+        source: &V2EventSource,
+        manifest_size: u64,
+        events_size: u64,
     ) -> TraceInfoResponse {
-        let manifest_size = manifest_meta.len();
-        let events_size = events_meta.len();
-        let avg_event_size = if manifest.event_count > 0 {
-            events_size / manifest.event_count
-        } else {
-            0
-        };
+        let manifest = source.manifest();
+        let avg_event_size = events_size.checked_div(manifest.event_count).unwrap_or(0);
 
         TraceInfoResponse {
             trace_id: trace_id.to_string(),
@@ -362,7 +540,10 @@ impl TraceInfoHandler {
             time_end_ns: manifest.time_end_ns,
             duration_ns: manifest.duration_ns(),
             event_count: manifest.event_count,
-            span_count: manifest.resolved_span_count(),
+            span_count: source
+                .session()
+                .merged_iter_of_kind(ATF_EVENT_KIND_CALL)
+                .count() as u64,
             files: TraceFileInfo {
                 manifest_size,
                 events_size,
@@ -371,17 +552,21 @@ impl TraceInfoHandler {
             },
             checksums: None,
             samples: None,
+            payload_stats: None,
+            wall_clock: None,
+            decimation_factor: source.session().manifest().decimation_factor,
+            registers_stripped: source.session().manifest().registers_stripped,
         }
     }
 
     async fn compute_checksums(
         &self,
         manifest_path: PathBuf,
-        events_path: PathBuf,
+        event_paths: Vec<PathBuf>,
     ) -> Result<TraceChecksums, JsonRpcError> {
         let (manifest_md5, events_md5) = tokio::try_join!(
-            compute_file_md5(manifest_path.clone()),
-            compute_file_md5(events_path.clone())
+            compute_file_md5(manifest_path),
+            compute_files_md5(event_paths)
         )?;
 
         Ok(TraceChecksums {
@@ -390,8 +575,12 @@ impl TraceInfoHandler {
         })
     }
 
-    async fn compute_samples(&self, reader: AtfReader) -> Result<TraceSamples, JsonRpcError> {
-        let result = task::spawn_blocking(move || sample_events(&reader)).await;
+    async fn compute_samples(&self, trace_dir: PathBuf) -> Result<TraceSamples, JsonRpcError> {
+        let result = task::spawn_blocking(move || {
+            let source = V2EventSource::open(&trace_dir)?;
+            sample_events(&source)
+        })
+        .await;
         match result {
             Ok(Ok(samples)) => Ok(samples),
             Ok(Err(err)) => Err(self.map_atf_error(err)),
@@ -401,11 +590,29 @@ impl TraceInfoHandler {
         }
     }
 
+    async fn compute_payload_stats(
+        &self,
+        trace_dir: PathBuf,
+    ) -> Result<PayloadStats, JsonRpcError> {
+        let result = task::spawn_blocking(move || {
+            let source = V2EventSource::open(&trace_dir)?;
+            Ok(tally_payload_stats(&source))
+        })
+        .await;
+        match result {
+            Ok(Ok(stats)) => Ok(stats),
+            Ok(Err(err)) => Err(self.map_atf_error(err)),
+            Err(err) => Err(JsonRpcError::internal(format!(
+                "payload stats task failed: {err}"
+            ))),
+        }
+    }
+
     fn map_atf_error(&self, err: AtfError) -> JsonRpcError {
         match err {
-            AtfError::TraceNotFound(_)
-            | AtfError::ManifestNotFound(_)
-            | AtfError::EventsNotFound(_) => JsonRpcError::trace_not_found(),
+            AtfError::TraceNotFound(_) | AtfError::ManifestNotFound(_) => {
+                JsonRpcError::trace_not_found()
+            }
             AtfError::Manifest(message) => {
                 JsonRpcError::internal(format!("failed to parse manifest: {message}"))
             }
@@ -415,6 +622,9 @@ impl TraceInfoHandler {
             AtfError::Io { path, source } => {
                 JsonRpcError::internal(format!("io error at {}: {}", path.display(), source))
             }
+            AtfError::Fetch { url, source } => {
+                JsonRpcError::internal(format!("failed to fetch {url}: {source}"))
+            }
             AtfError::Join(err) => JsonRpcError::internal(format!("blocking task failed: {err}")),
         }
     }
@@ -422,9 +632,9 @@ impl TraceInfoHandler {
 
 #[async_trait]
 impl JsonRpcHandler for TraceInfoHandler {
-    async fn call(&self, params: Option<Value>) -> JsonRpcResult {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
         let params: TraceInfoParams = match params {
-            Some(value) => serde_json::from_value(value).map_err(|err| {
+            Some(value) => TraceInfoParams::deserialize(value).map_err(|err| {
                 JsonRpcError::invalid_params(format!("invalid trace.info parameters: {err}"))
             })?,
             None => {
@@ -435,8 +645,7 @@ impl JsonRpcHandler for TraceInfoHandler {
         };
 
         let response = self.get_trace_info(params).await?;
-        serde_json::to_value(response)
-            .map_err(|err| JsonRpcError::internal(format!("failed to serialize response: {err}")))
+        envelope_result(TRACE_INFO_SCHEMA_VERSION, response)
     }
 }
 
@@ -452,21 +661,17 @@ impl From<&ParsedEvent> for EventSample {
     }
 }
 
-fn sample_events(reader: &AtfReader) -> Result<TraceSamples, AtfError> {
-    let mut stream = match reader.event_stream() {
-        Ok(stream) => stream,
-        Err(AtfError::EventsNotFound(_)) => return Ok(TraceSamples::default()),
-        Err(err) => return Err(err),
-    };
+fn sample_events(source: &dyn EventSource) -> Result<TraceSamples, AtfError> {
+    let stream = source.events()?;
 
-    let total_events = reader.manifest().event_count.max(1);
+    let total_events = source.manifest().event_count.max(1);
     let mut first_events = Vec::new();
     let mut random_events = Vec::new();
     let mut last_events: VecDeque<EventSample> = VecDeque::with_capacity(SAMPLE_COUNT);
     let mut count: u64 = 0;
     let sample_interval = std::cmp::max(1, total_events / 50);
 
-    while let Some(event) = stream.next() {
+    for event in stream {
         let parsed = event?;
         count += 1;
         let sample = EventSample::from(&parsed);
@@ -477,7 +682,7 @@ fn sample_events(reader: &AtfReader) -> Result<TraceSamples, AtfError> {
 
         if count > SAMPLE_COUNT as u64
             && random_events.len() < SAMPLE_COUNT
-            && count % sample_interval == 0
+            && count.is_multiple_of(sample_interval)
         {
             random_events.push(sample.clone());
         }
@@ -492,7 +697,6 @@ fn sample_events(reader: &AtfReader) -> Result<TraceSamples, AtfError> {
         return Ok(TraceSamples::default());
     }
 
-    let mut random_events = random_events;
     random_events.truncate(SAMPLE_COUNT);
 
     let last_events = if count <= SAMPLE_COUNT as u64 {
@@ -508,18 +712,55 @@ fn sample_events(reader: &AtfReader) -> Result<TraceSamples, AtfError> {
     })
 }
 
+fn tally_payload_stats(source: &V2EventSource) -> PayloadStats {
+    let mut stats = PayloadStats::default();
+    let details = source
+        .session()
+        .threads()
+        .iter()
+        .filter_map(|thread| thread.detail.as_ref());
+    for event in details.flat_map(|detail| detail.iter()) {
+        let header = event.header();
+        let record_bytes = header.total_length as u64;
+        let payload_bytes = event.payload().len() as u64;
+        stats.total_bytes += record_bytes;
+        stats.envelope_bytes += record_bytes - payload_bytes;
+
+        let (kind, function_stack) = match header.event_type {
+            ATF_DETAIL_EVENT_FUNCTION_CALL => ("FunctionCall", event.stack_snapshot()),
+            ATF_DETAIL_EVENT_FUNCTION_RETURN => ("FunctionReturn", event.stack_snapshot()),
+            _ => ("Unknown", None),
+        };
+        let entry = stats.by_type.entry(kind.to_string()).or_default();
+        entry.count += 1;
+        entry.bytes += payload_bytes;
+        if let Some(stack) = function_stack {
+            *entry.fields.entry("registers".to_string()).or_default() +=
+                ATF_DETAIL_FUNCTION_REGISTER_BYTES as u64;
+            *entry.fields.entry("stackSnapshot".to_string()).or_default() += stack.len() as u64;
+        }
+    }
+    stats
+}
+
 async fn compute_file_md5(path: PathBuf) -> Result<String, JsonRpcError> {
-    let display_path = path.display().to_string();
-    let result = task::spawn_blocking(move || -> io::Result<String> {
-        let mut file = std::fs::File::open(&path)?;
+    compute_files_md5(vec![path]).await
+}
+
+/// Digest of the files' contents read back to back
+async fn compute_files_md5(paths: Vec<PathBuf>) -> Result<String, JsonRpcError> {
+    let result = task::spawn_blocking(move || -> Result<String, (PathBuf, io::Error)> {
         let mut context = md5::Context::new();
         let mut buffer = [0u8; 8192];
-        loop {
-            let read = file.read(&mut buffer)?;
-            if read == 0 {
-                break;
+        for path in paths {
+            let mut file = std::fs::File::open(&path).map_err(|err| (path.clone(), err))?;
+            loop {
+                let read = file.read(&mut buffer).map_err(|err| (path.clone(), err))?;
+                if read == 0 {
+                    break;
+                }
+                context.consume(&buffer[..read]);
             }
-            context.consume(&buffer[..read]);
         }
         let digest = context.compute();
         Ok(format!("{:x}", digest))
@@ -527,8 +768,11 @@ async fn compute_file_md5(path: PathBuf) -> Result<String, JsonRpcError> {
     .await
     .map_err(|err| JsonRpcError::internal(format!("checksum task failed: {err}")))?;
 
-    result.map_err(|err| {
-        JsonRpcError::internal(format!("failed to read {display_path} for checksum: {err}"))
+    result.map_err(|(path, err)| {
+        JsonRpcError::internal(format!(
+            "failed to read {} for checksum: {err}",
+            path.display()
+        ))
     })
 }
 
@@ -540,153 +784,30 @@ fn map_metadata_error(kind: &str, err: io::Error) -> JsonRpcError {
     }
 }
 
+fn wall_clock_range(manifest_path: &Path, response: &TraceInfoResponse) -> WallClockRange {
+    let anchor = WallClockAnchor::read(manifest_path);
+    WallClockRange {
+        start: anchor.and_then(|anchor| anchor.iso8601(response.time_start_ns)),
+        end: anchor.and_then(|anchor| anchor.iso8601(response.time_end_ns)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(non_snake_case)]
 
     use super::*;
-    use crate::atf::{
-        event::{event::Payload, Event, FunctionCall, FunctionReturn, TraceEnd, TraceStart},
-        AtfReader,
+    use crate::atf::v2::{ATF_DETAIL_EVENT_FUNCTION_CALL, ATF_DETAIL_EVENT_FUNCTION_RETURN};
+    use crate::handlers::envelope::ResponseEnvelope;
+    use crate::handlers::test_support::{
+        function_call_event, function_detail, function_return_event, TraceFixture,
     };
     use crate::server::{server::JsonRpcServer, types::JsonRpcError};
-    use prost::Message;
     use serde_json::json;
-    use std::{
-        fs::{self, File},
-        io::{self, Write},
-        path::PathBuf,
-        time::Duration,
-    };
-    use tempfile::TempDir;
+    use std::{fs, io, path::PathBuf, time::Duration};
 
-    struct HandlerTestFixture {
-        temp: TempDir,
-        trace_id: String,
-    }
-
-    impl HandlerTestFixture {
-        fn new(trace_id: &str) -> io::Result<Self> {
-            let temp = TempDir::new()?;
-            let trace_id = trace_id.to_string();
-            fs::create_dir_all(temp.path().join(&trace_id))?;
-            Ok(Self { temp, trace_id })
-        }
-
-        fn trace_root(&self) -> PathBuf {
-            self.temp.path().to_path_buf()
-        }
-
-        fn trace_dir(&self) -> PathBuf {
-            self.temp.path().join(&self.trace_id)
-        }
-
-        fn manifest_path(&self) -> PathBuf {
-            self.trace_dir().join("trace.json")
-        }
-
-        fn events_path(&self) -> PathBuf {
-            self.trace_dir().join("events.bin")
-        }
-
-        fn write_manifest(&self, manifest: Value) -> io::Result<()> {
-            let bytes = serde_json::to_vec_pretty(&manifest)?;
-            fs::write(self.manifest_path(), bytes)
-        }
-
-        fn write_events(&self, events: &[Event]) -> io::Result<()> {
-            let mut file = File::create(self.events_path())?;
-            for event in events {
-                let mut buffer = Vec::new();
-                event
-                    .encode_length_delimited(&mut buffer)
-                    .expect("encode event");
-                file.write_all(&buffer)?;
-            }
-            file.flush()
-        }
-
-        fn overwrite_events_bytes(&self, bytes: &[u8]) -> io::Result<()> {
-            fs::write(self.events_path(), bytes)
-        }
-    }
-
-    fn sample_manifest(event_count: u64, span_count: Option<u64>) -> Value {
-        json!({
-            "os": "linux",
-            "arch": "x86_64",
-            "pid": 9000,
-            "sessionId": 1,
-            "timeStartNs": 100,
-            "timeEndNs": 2100,
-            "eventCount": event_count,
-            "bytesWritten": 4096,
-            "modules": ["mod"],
-            "spanCount": span_count,
-        })
-    }
-
-    fn function_call_event(timestamp_ns: u64, thread_id: i32, symbol: &str) -> Event {
-        Event {
-            event_id: timestamp_ns,
-            thread_id,
-            timestamp: Some(prost_types::Timestamp {
-                seconds: (timestamp_ns / 1_000_000_000) as i64,
-                nanos: (timestamp_ns % 1_000_000_000) as i32,
-            }),
-            payload: Some(Payload::FunctionCall(FunctionCall {
-                symbol: symbol.to_string(),
-                address: 0,
-                argument_registers: Default::default(),
-                stack_shallow_copy: Vec::new(),
-            })),
-        }
-    }
-
-    fn function_return_event(timestamp_ns: u64, thread_id: i32, symbol: &str) -> Event {
-        Event {
-            event_id: timestamp_ns,
-            thread_id,
-            timestamp: Some(prost_types::Timestamp {
-                seconds: (timestamp_ns / 1_000_000_000) as i64,
-                nanos: (timestamp_ns % 1_000_000_000) as i32,
-            }),
-            payload: Some(Payload::FunctionReturn(FunctionReturn {
-                symbol: symbol.to_string(),
-                address: 0,
-                return_registers: Default::default(),
-            })),
-        }
-    }
-
-    fn trace_start_event(timestamp_ns: u64, thread_id: i32) -> Event {
-        Event {
-            event_id: timestamp_ns,
-            thread_id,
-            timestamp: Some(prost_types::Timestamp {
-                seconds: (timestamp_ns / 1_000_000_000) as i64,
-                nanos: (timestamp_ns % 1_000_000_000) as i32,
-            }),
-            payload: Some(Payload::TraceStart(TraceStart {
-                executable_path: "/bin/test".into(),
-                args: vec!["--flag".into()],
-                operating_system: "linux".into(),
-                cpu_architecture: "x86_64".into(),
-            })),
-        }
-    }
-
-    fn trace_end_event(timestamp_ns: u64, thread_id: i32) -> Event {
-        Event {
-            event_id: timestamp_ns,
-            thread_id,
-            timestamp: Some(prost_types::Timestamp {
-                seconds: (timestamp_ns / 1_000_000_000) as i64,
-                nanos: (timestamp_ns % 1_000_000_000) as i32,
-            }),
-            payload: Some(Payload::TraceEnd(TraceEnd { exit_code: 0 })),
-        }
-    }
+    const FOO: u64 = 0xf00;
+    const BAR: u64 = 0xba5;
 
     fn dummy_response(trace_id: &str) -> TraceInfoResponse {
         TraceInfoResponse {
@@ -706,6 +827,19 @@ mod tests {
             },
             checksums: None,
             samples: None,
+            payload_stats: None,
+            wall_clock: None,
+            decimation_factor: None,
+            registers_stripped: false,
+        }
+    }
+
+    fn empty_snapshot(trace_id: &str) -> CacheSnapshot {
+        CacheSnapshot {
+            base: dummy_response(trace_id),
+            checksums: None,
+            samples: None,
+            payload_stats: None,
         }
     }
 
@@ -790,9 +924,6 @@ mod tests {
         let manifest_missing = handler.map_atf_error(AtfError::ManifestNotFound("/tmp".into()));
         assert_eq!(manifest_missing.code, JsonRpcError::trace_not_found().code);
 
-        let events_missing = handler.map_atf_error(AtfError::EventsNotFound("/tmp".into()));
-        assert_eq!(events_missing.code, JsonRpcError::trace_not_found().code);
-
         let manifest_err = handler.map_atf_error(AtfError::Manifest("bad".into()));
         assert_eq!(
             manifest_err.code,
@@ -825,6 +956,17 @@ mod tests {
             .expect("detail");
         assert!(io_detail.contains("io error"));
 
+        let fetch_err = handler.map_atf_error(AtfError::Fetch {
+            url: "http://host/trace/manifest.json".into(),
+            source: io::Error::new(io::ErrorKind::TimedOut, "request timed out"),
+        });
+        let fetch_detail = fetch_err
+            .data
+            .as_ref()
+            .and_then(|value| value.as_str())
+            .expect("detail");
+        assert!(fetch_detail.contains("failed to fetch http://host/trace/manifest.json"));
+
         let join_err = tokio::spawn(async { panic!("boom") }).await.unwrap_err();
         let mapped_join = handler.map_atf_error(AtfError::Join(join_err));
         assert_eq!(mapped_join.code, JsonRpcError::internal(String::new()).code);
@@ -839,45 +981,37 @@ mod tests {
     #[tokio::test]
     async fn build_response_from_cache__missing_optional_fields__then_populates_and_updates_cache()
     {
-        let fixture = HandlerTestFixture::new("lazy").expect("fixture");
-        let events = vec![
-            trace_start_event(100, 1),
-            function_call_event(200, 1, "foo"),
-            function_return_event(300, 1, "foo"),
-            trace_end_event(400, 1),
-        ];
-        fixture
-            .write_manifest(sample_manifest(events.len() as u64, Some(2)))
-            .expect("manifest");
-        fixture.write_events(&events).expect("events");
+        let fixture = TraceFixture::new("lazy");
+        fixture.write_events(&[
+            function_call_event(200, 1, FOO),
+            function_return_event(300, 1, FOO),
+        ]);
 
         let handler = TraceInfoHandler::new(fixture.trace_root(), 4, Duration::from_secs(60));
-        let trace_id = fixture.trace_id.clone();
+        let trace_id = fixture.trace_id().to_string();
 
         handler
             .get_trace_info(TraceInfoParams {
                 trace_id: trace_id.clone(),
                 include_checksums: false,
                 include_samples: false,
+                include_payload_stats: false,
+                include_wall_clock: false,
             })
             .await
             .expect("initial call");
 
         {
-            let cache = handler.cache.as_ref().expect("cache").lock();
+            let cache = handler.cache.as_ref().expect("cache");
             let entry = cache.peek(&trace_id).expect("entry");
             assert!(entry.checksums.is_none());
             assert!(entry.samples.is_none());
         }
 
         let manifest_meta = fs::metadata(fixture.manifest_path()).expect("meta");
-        let events_meta = fs::metadata(fixture.events_path()).expect("meta");
+        let event_files = EventFiles::scan(&fixture.trace_dir()).expect("event files");
         let snapshot = handler
-            .fetch_from_cache(
-                &trace_id,
-                manifest_meta.modified().ok(),
-                events_meta.modified().ok(),
-            )
+            .fetch_from_cache(&trace_id, manifest_meta.modified().ok(), event_files.mtime)
             .expect("snapshot");
 
         let response = handler
@@ -888,57 +1022,59 @@ mod tests {
                     trace_id: trace_id.clone(),
                     include_checksums: true,
                     include_samples: true,
+                    include_payload_stats: true,
+                    include_wall_clock: false,
                 },
                 fixture.manifest_path(),
-                fixture.events_path(),
+                event_files.paths,
             )
             .await
             .expect("response");
 
         assert!(response.checksums.is_some());
         assert!(response.samples.is_some());
+        assert!(response.payload_stats.is_some());
 
         {
-            let cache = handler.cache.as_ref().expect("cache").lock();
+            let cache = handler.cache.as_ref().expect("cache");
             let entry = cache.peek(&trace_id).expect("entry");
             assert!(entry.checksums.is_some());
             assert!(entry.samples.is_some());
+            assert!(entry.payload_stats.is_some());
         }
     }
 
     #[tokio::test]
     async fn fetch_from_cache__file_modified__then_entry_invalidated() {
-        let fixture = HandlerTestFixture::new("invalidate").expect("fixture");
-        let events = vec![function_call_event(100, 1, "foo")];
-        fixture
-            .write_manifest(sample_manifest(events.len() as u64, Some(1)))
-            .expect("manifest");
-        fixture.write_events(&events).expect("events");
+        let fixture = TraceFixture::new("invalidate");
+        fixture.write_events(&[function_call_event(100, 1, FOO)]);
 
         let handler = TraceInfoHandler::new(fixture.trace_root(), 4, Duration::from_secs(120));
-        let trace_id = fixture.trace_id.clone();
+        let trace_id = fixture.trace_id().to_string();
 
         handler
             .get_trace_info(TraceInfoParams {
                 trace_id: trace_id.clone(),
                 include_checksums: false,
                 include_samples: false,
+                include_payload_stats: false,
+                include_wall_clock: false,
             })
             .await
             .expect("initial call");
 
-        let original_events_meta = fs::metadata(fixture.events_path()).expect("meta");
+        let original_events_mtime = EventFiles::scan(&fixture.trace_dir())
+            .expect("event files")
+            .mtime;
 
         tokio::time::sleep(Duration::from_millis(20)).await;
-        fixture
-            .write_manifest(sample_manifest(42, Some(3)))
-            .expect("manifest update");
+        fixture.edit_manifest(|manifest| manifest["time_end_ns"] = json!(400));
 
         let manifest_meta = fs::metadata(fixture.manifest_path()).expect("meta");
         let snapshot = handler.fetch_from_cache(
             &trace_id,
             manifest_meta.modified().ok(),
-            original_events_meta.modified().ok(),
+            original_events_mtime,
         );
         assert!(
             snapshot.is_none(),
@@ -950,39 +1086,36 @@ mod tests {
                 trace_id: trace_id.clone(),
                 include_checksums: false,
                 include_samples: false,
+                include_payload_stats: false,
+                include_wall_clock: false,
             })
             .await
             .expect("repopulate");
 
         tokio::time::sleep(Duration::from_millis(20)).await;
-        fixture
-            .write_events(&[
-                function_call_event(100, 1, "foo"),
-                function_return_event(200, 1, "foo"),
-            ])
-            .expect("events update");
+        fixture.write_events(&[
+            function_call_event(100, 1, FOO),
+            function_return_event(200, 1, FOO),
+        ]);
 
         let manifest_meta = fs::metadata(fixture.manifest_path()).expect("meta");
-        let events_meta = fs::metadata(fixture.events_path()).expect("meta");
-        let snapshot = handler.fetch_from_cache(
-            &trace_id,
-            manifest_meta.modified().ok(),
-            events_meta.modified().ok(),
-        );
+        let event_files = EventFiles::scan(&fixture.trace_dir()).expect("event files");
+        let snapshot =
+            handler.fetch_from_cache(&trace_id, manifest_meta.modified().ok(), event_files.mtime);
         assert!(snapshot.is_none(), "events change should invalidate cache");
     }
 
     #[test]
     fn cache_response__cache_disabled__then_no_entry_created() {
         let handler = TraceInfoHandler::new(PathBuf::new(), 0, Duration::from_secs(60));
-        handler.cache_response("trace", dummy_response("trace"), None, None, None, None);
+        handler.cache_response("trace", empty_snapshot("trace"), None, None);
         assert!(handler.cache.is_none());
     }
 
     #[test]
     fn update_cached_entries__then_optional_fields_saved() {
         let handler = TraceInfoHandler::new(PathBuf::new(), 4, Duration::from_secs(60));
-        handler.cache_response("trace", dummy_response("trace"), None, None, None, None);
+        handler.cache_response("trace", empty_snapshot("trace"), None, None);
 
         let checksums = TraceChecksums {
             manifest_md5: "aa".repeat(16),
@@ -1003,21 +1136,185 @@ mod tests {
         };
         handler.update_cached_samples("trace", samples.clone());
 
-        let cache = handler.cache.as_ref().expect("cache").lock();
+        let cache = handler.cache.as_ref().expect("cache");
         let entry = cache.peek("trace").expect("entry");
         assert_eq!(entry.checksums.as_ref(), Some(&checksums));
         assert_eq!(entry.samples.as_ref(), Some(&samples));
     }
 
+    #[tokio::test]
+    async fn get_trace_info__call__then_fields_beside_envelope_version() {
+        let fixture = TraceFixture::new("enveloped");
+        fixture.write_events(&[
+            function_call_event(200, 1, FOO),
+            function_return_event(300, 1, FOO),
+            function_call_event(250, 2, BAR),
+        ]);
+
+        let handler = TraceInfoHandler::new(fixture.trace_root(), 0, Duration::from_secs(0));
+        let result = handler
+            .call(Some(json!({ "traceId": fixture.trace_id() })))
+            .await
+            .expect("response");
+        let envelope: ResponseEnvelope<TraceInfoResponse> =
+            serde_json::from_value(result).expect("enveloped response");
+
+        assert_eq!(envelope.schema_version, TRACE_INFO_SCHEMA_VERSION);
+        assert!(envelope.generated_at.ends_with('Z'));
+        assert_eq!(envelope.data.trace_id, "enveloped");
+        assert_eq!(envelope.data.event_count, 3);
+        assert_eq!(envelope.data.span_count, 2);
+        assert_eq!(envelope.data.os, "linux");
+        assert_eq!(envelope.data.arch, "x86_64");
+        assert_eq!(
+            (envelope.data.time_start_ns, envelope.data.time_end_ns),
+            (200, 300)
+        );
+        assert_eq!(envelope.data.files.events_size, 2 * (64 + 64) + 3 * 32);
+    }
+
+    #[tokio::test]
+    async fn get_trace_info__include_checksums__then_events_digest_covers_thread_files() {
+        let fixture = TraceFixture::new("checksums");
+        fixture.write_events(&[
+            function_call_event(100, 1, FOO),
+            function_call_event(150, 2, BAR),
+        ]);
+
+        let handler = TraceInfoHandler::new(fixture.trace_root(), 0, Duration::from_secs(0));
+        let response = handler
+            .get_trace_info(TraceInfoParams {
+                trace_id: fixture.trace_id().to_string(),
+                include_checksums: true,
+                ..Default::default()
+            })
+            .await
+            .expect("response");
+
+        let mut thread_bytes = fs::read(fixture.index_path(1)).expect("thread 1");
+        thread_bytes.extend(fs::read(fixture.index_path(2)).expect("thread 2"));
+        let checksums = response.checksums.expect("checksums");
+        assert_eq!(
+            checksums.events_md5,
+            format!("{:x}", md5::compute(&thread_bytes))
+        );
+        let manifest_bytes = fs::read(fixture.manifest_path()).expect("manifest");
+        assert_eq!(
+            checksums.manifest_md5,
+            format!("{:x}", md5::compute(&manifest_bytes))
+        );
+    }
+
+    #[tokio::test]
+    async fn get_trace_info__include_payload_stats__then_tallies_by_type() {
+        let fixture = TraceFixture::new("payload_stats");
+        let mut call = function_call_event(200, 1, FOO);
+        call.detail_seq = 0;
+        let mut ret = function_return_event(300, 1, FOO);
+        ret.detail_seq = 1;
+        fixture.write_events(&[call, ret, function_call_event(250, 2, BAR)]);
+        fixture.write_detail(
+            1,
+            &[
+                function_detail(ATF_DETAIL_EVENT_FUNCTION_CALL, 0, 200, 1, FOO, &[0xAB; 256]),
+                function_detail(ATF_DETAIL_EVENT_FUNCTION_RETURN, 1, 300, 1, FOO, &[]),
+            ],
+        );
+
+        let handler = TraceInfoHandler::new(fixture.trace_root(), 0, Duration::from_secs(0));
+        let response = handler
+            .get_trace_info(TraceInfoParams {
+                trace_id: fixture.trace_id().to_string(),
+                include_payload_stats: true,
+                ..Default::default()
+            })
+            .await
+            .expect("response");
+
+        let stats = response.payload_stats.expect("payload stats");
+        assert_eq!(stats.envelope_bytes, 2 * 24);
+        let type_bytes: u64 = stats.by_type.values().map(|entry| entry.bytes).sum();
+        assert_eq!(stats.envelope_bytes + type_bytes, stats.total_bytes);
+
+        let call_stats = &stats.by_type["FunctionCall"];
+        assert_eq!(call_stats.count, 1);
+        assert_eq!(call_stats.bytes, 100 + 256);
+        assert_eq!(call_stats.fields["stackSnapshot"], 256);
+        assert_eq!(call_stats.fields["registers"], 88);
+        let return_stats = &stats.by_type["FunctionReturn"];
+        assert_eq!(return_stats.count, 1);
+        assert_eq!(return_stats.fields["stackSnapshot"], 0);
+    }
+
+    #[tokio::test]
+    async fn get_trace_info__include_wall_clock__then_bounds_resolved_or_null() {
+        let fixture = TraceFixture::new("wall_clock");
+        fixture.write_events(&[
+            function_call_event(100, 1, FOO),
+            function_return_event(2_100, 1, FOO),
+        ]);
+        fixture.edit_manifest(|manifest| {
+            manifest["monotonic_start_ns"] = json!(100);
+            manifest["wall_clock_start_ns"] = json!(1_714_564_800_000_000_000u64);
+        });
+
+        let handler = TraceInfoHandler::new(fixture.trace_root(), 0, Duration::from_secs(0));
+        let params = TraceInfoParams {
+            trace_id: fixture.trace_id().to_string(),
+            include_wall_clock: true,
+            ..Default::default()
+        };
+        let response = handler
+            .get_trace_info(params.clone())
+            .await
+            .expect("response");
+        assert_eq!(
+            response.wall_clock,
+            Some(WallClockRange {
+                start: Some("2024-05-01T12:00:00.000000000Z".into()),
+                end: Some("2024-05-01T12:00:00.000002000Z".into()),
+            })
+        );
+
+        fixture.edit_manifest(|manifest| {
+            manifest
+                .as_object_mut()
+                .expect("manifest object")
+                .remove("wall_clock_start_ns");
+        });
+        let unanchored = handler.get_trace_info(params).await.expect("response");
+        assert_eq!(
+            unanchored.wall_clock,
+            Some(WallClockRange {
+                start: None,
+                end: None
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn get_trace_info__traversal_trace_id__then_invalid_params() {
+        let fixture = TraceFixture::new("inner");
+        fixture.write_events(&[]);
+
+        let handler =
+            TraceInfoHandler::new(fixture.trace_dir().join("sub"), 0, Duration::from_secs(0));
+        for trace_id in ["../inner", "/etc", "inner\0"] {
+            let err = handler
+                .call(Some(json!({ "traceId": trace_id })))
+                .await
+                .expect_err("expected rejection");
+            assert_eq!(err.code, -32602, "traceId {trace_id:?}");
+        }
+    }
+
     #[test]
-    fn sample_events__missing_events_file__then_returns_default_samples() {
-        let fixture = HandlerTestFixture::new("missing").expect("fixture");
-        fixture
-            .write_manifest(sample_manifest(10, Some(5)))
-            .expect("manifest");
-
-        let reader = AtfReader::open(fixture.trace_dir()).expect("reader");
-        let samples = sample_events(&reader).expect("samples");
+    fn sample_events__empty_session__then_returns_default_samples() {
+        let fixture = TraceFixture::new("empty");
+        fixture.write_events(&[]);
+
+        let source = V2EventSource::open(&fixture.trace_dir()).expect("source");
+        let samples = sample_events(&source).expect("samples");
         assert!(samples.first_events.is_empty());
         assert!(samples.last_events.is_empty());
         assert!(samples.random_events.is_empty());
@@ -1025,38 +1322,35 @@ mod tests {
 
     #[test]
     fn sample_events__limited_events__then_last_matches_first() {
-        let fixture = HandlerTestFixture::new("limited").expect("fixture");
-        let events = vec![
-            function_call_event(100, 1, "foo"),
-            function_return_event(200, 1, "foo"),
-            function_call_event(300, 1, "bar"),
-        ];
-        fixture
-            .write_manifest(sample_manifest(events.len() as u64, Some(2)))
-            .expect("manifest");
-        fixture.write_events(&events).expect("events");
-
-        let reader = AtfReader::open(fixture.trace_dir()).expect("reader");
-        let samples = sample_events(&reader).expect("samples");
+        let fixture = TraceFixture::new("limited");
+        fixture.write_events(&[
+            function_call_event(100, 1, FOO),
+            function_return_event(200, 1, FOO),
+            function_call_event(300, 1, BAR),
+        ]);
+
+        let source = V2EventSource::open(&fixture.trace_dir()).expect("source");
+        let samples = sample_events(&source).expect("samples");
         assert_eq!(samples.first_events.len(), 3);
         assert_eq!(samples.last_events, samples.first_events);
         assert!(samples.random_events.is_empty());
+        assert_eq!(samples.first_events[0].event_type, "FunctionCall");
+        assert_eq!(
+            samples.first_events[0].function_name.as_deref(),
+            Some("0xf00")
+        );
     }
 
     #[test]
     fn sample_events__many_events__then_collects_random_and_last_samples() {
-        let fixture = HandlerTestFixture::new("many").expect("fixture");
-        let mut events = Vec::new();
-        for i in 0..60 {
-            events.push(function_call_event(100 * (i + 1) as u64, 1, "foo"));
-        }
-        fixture
-            .write_manifest(sample_manifest(events.len() as u64, Some(30)))
-            .expect("manifest");
-        fixture.write_events(&events).expect("events");
-
-        let reader = AtfReader::open(fixture.trace_dir()).expect("reader");
-        let samples = sample_events(&reader).expect("samples");
+        let fixture = TraceFixture::new("many");
+        let events: Vec<_> = (0..60)
+            .map(|i| function_call_event(100 * (i + 1) as u64, 1, FOO))
+            .collect();
+        fixture.write_events(&events);
+
+        let source = V2EventSource::open(&fixture.trace_dir()).expect("source");
+        let samples = sample_events(&source).expect("samples");
         assert_eq!(samples.first_events.len(), SAMPLE_COUNT);
         assert_eq!(samples.random_events.len(), SAMPLE_COUNT);
         assert_eq!(samples.last_events.len(), SAMPLE_COUNT);
@@ -1067,38 +1361,15 @@ mod tests {
         );
     }
 
-    #[test]
-    fn sample_events__decode_failure__then_propagates_error() {
-        let fixture = HandlerTestFixture::new("decode").expect("fixture");
-        fixture
-            .write_manifest(sample_manifest(1, Some(0)))
-            .expect("manifest");
-        fixture
-            .overwrite_events_bytes(&[0xFF, 0xFF, 0xFF])
-            .expect("events");
-
-        let reader = AtfReader::open(fixture.trace_dir()).expect("reader");
-        let err = sample_events(&reader).expect_err("expected error");
-        match err {
-            AtfError::Decode(_) => {}
-            other => panic!("unexpected error: {other:?}"),
-        }
-    }
-
     #[tokio::test]
-    async fn compute_samples__decode_error__then_maps_to_json_rpc_error() {
-        let fixture = HandlerTestFixture::new("decode_map").expect("fixture");
-        fixture
-            .write_manifest(sample_manifest(1, Some(0)))
-            .expect("manifest");
-        fixture
-            .overwrite_events_bytes(&[0xAA, 0xBB, 0xCC])
-            .expect("events");
+    async fn compute_samples__corrupt_index__then_maps_to_decode_error() {
+        let fixture = TraceFixture::new("decode_map");
+        fixture.write_events(&[function_call_event(100, 1, FOO)]);
+        fs::write(fixture.index_path(1), [0xAA, 0xBB, 0xCC]).expect("corrupt index");
 
         let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
-        let reader = AtfReader::open(fixture.trace_dir()).expect("reader");
         let err = handler
-            .compute_samples(reader)
+            .compute_samples(fixture.trace_dir())
             .await
             .expect_err("expected error");
         assert_eq!(err.message, "Internal error");
@@ -1111,17 +1382,15 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn compute_samples__io_error__then_maps_to_internal_error() {
-        let fixture = HandlerTestFixture::new("io_map").expect("fixture");
-        fixture
-            .write_manifest(sample_manifest(1, Some(0)))
-            .expect("manifest");
-        fs::create_dir_all(fixture.events_path()).expect("events dir");
+    async fn compute_samples__unreadable_index__then_maps_to_internal_error() {
+        let fixture = TraceFixture::new("io_map");
+        fixture.write_events(&[function_call_event(100, 1, FOO)]);
+        fs::remove_file(fixture.index_path(1)).expect("remove index");
+        fs::create_dir_all(fixture.index_path(1)).expect("index dir");
 
         let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
-        let reader = AtfReader::open(fixture.trace_dir()).expect("reader");
         let err = handler
-            .compute_samples(reader)
+            .compute_samples(fixture.trace_dir())
             .await
             .expect_err("expected error");
         assert_eq!(err.message, "Internal error");
@@ -0,0 +1,79 @@
+use std::{fs, path::Path};
+
+use crate::atf::{format_wall_clock_ns, ClockMapping, Manifest};
+
+/// Ties the trace's event clock to the wall clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WallClockAnchor {
+    mapping: ClockMapping,
+}
+
+impl WallClockAnchor {
+    /// Anchor recorded in the session's `manifest.json`, if the tracer wrote
+    /// one.
+    ///
+    /// A manifest that cannot be read here is treated as carrying no anchor.
+    pub(crate) fn read(manifest_path: &Path) -> Option<Self> {
+        let bytes = fs::read(manifest_path).ok()?;
+        let manifest = Manifest::from_bytes(&bytes).ok()?;
+        Some(Self {
+            mapping: manifest.clock_mapping()?,
+        })
+    }
+
+    /// ISO-8601 UTC time of an event timestamp.
+    pub(crate) fn iso8601(&self, timestamp_ns: u64) -> Option<String> {
+        self.mapping
+            .to_realtime(timestamp_ns)
+            .map(format_wall_clock_ns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::test_support::{function_call_event, TraceFixture};
+    use serde_json::json;
+
+    #[test]
+    fn wall_clock_anchor__manifest_without_anchor__then_none() {
+        let fixture = TraceFixture::new("trace");
+        fixture.write_events(&[function_call_event(100, 1, 0xa)]);
+
+        assert_eq!(WallClockAnchor::read(&fixture.manifest_path()), None);
+    }
+
+    #[test]
+    fn wall_clock_anchor__start_reading__then_offset_resolved() {
+        let fixture = TraceFixture::new("trace");
+        fixture.write_manifest_json(json!({
+            "threads": [],
+            "monotonic_start_ns": 100,
+            "wall_clock_start_ns": 1_714_564_800_000_000_000u64,
+        }));
+
+        let anchor = WallClockAnchor::read(&fixture.manifest_path()).expect("anchor");
+        assert_eq!(
+            anchor.iso8601(1_500).as_deref(),
+            Some("2024-05-01T12:00:00.000001400Z")
+        );
+    }
+
+    #[test]
+    fn wall_clock_anchor__start_and_end__then_interpolated() {
+        let fixture = TraceFixture::new("trace");
+        fixture.write_manifest_json(json!({
+            "threads": [],
+            "monotonic_start_ns": 100,
+            "monotonic_end_ns": 1_100,
+            "wall_clock_start_ns": 1_714_564_800_000_000_000u64,
+            "wall_clock_end_ns": 1_714_564_800_000_002_000u64,
+        }));
+
+        let anchor = WallClockAnchor::read(&fixture.manifest_path()).expect("anchor");
+        assert_eq!(
+            anchor.iso8601(600).as_deref(),
+            Some("2024-05-01T12:00:00.000001000Z")
+        );
+    }
+}
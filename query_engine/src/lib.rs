@@ -1,7 +1,6 @@
 pub mod app;
 pub mod atf;
-// TODO: Update handlers to use ATF V2 API
-// pub mod handlers;
+pub mod handlers;
 pub mod server;
 
 /// Simple ping function for testing
@@ -0,0 +1,124 @@
+use std::{fmt, net::SocketAddr};
+
+use hyper::{header::AUTHORIZATION, HeaderMap};
+
+/// Transport-level credentials presented with an HTTP request.
+#[derive(Debug, Clone, Copy)]
+pub struct Credentials<'a> {
+    pub remote_addr: SocketAddr,
+    pub headers: &'a HeaderMap,
+}
+
+impl<'a> Credentials<'a> {
+    pub fn new(remote_addr: SocketAddr, headers: &'a HeaderMap) -> Self {
+        Self {
+            remote_addr,
+            headers,
+        }
+    }
+
+    /// Token from an `Authorization: Bearer <token>` header, if present.
+    pub fn bearer_token(&self) -> Option<&'a str> {
+        let value = self.headers.get(AUTHORIZATION)?.to_str().ok()?;
+        let (scheme, token) = value.split_once(' ')?;
+        if scheme.eq_ignore_ascii_case("bearer") {
+            Some(token.trim())
+        } else {
+            None
+        }
+    }
+}
+
+/// Decides whether an HTTP request may reach the handler registry.
+///
+/// Invoked once per request before dispatch. In-process callers using the
+/// registry directly are never authenticated.
+pub trait Authenticator: Send + Sync {
+    fn authenticate(&self, credentials: &Credentials<'_>) -> bool;
+}
+
+impl fmt::Debug for dyn Authenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Authenticator")
+    }
+}
+
+/// Accepts requests carrying a fixed bearer token.
+pub struct BearerTokenAuthenticator {
+    token: String,
+}
+
+impl BearerTokenAuthenticator {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+        }
+    }
+}
+
+impl Authenticator for BearerTokenAuthenticator {
+    fn authenticate(&self, credentials: &Credentials<'_>) -> bool {
+        match credentials.bearer_token() {
+            // Compare without short-circuiting so response timing does not
+            // reveal how much of the token matched.
+            Some(token) if token.len() == self.token.len() => {
+                token
+                    .bytes()
+                    .zip(self.token.bytes())
+                    .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+    use hyper::header::HeaderValue;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn remote_addr() -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080)
+    }
+
+    fn headers_with(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(value).expect("header"));
+        headers
+    }
+
+    #[test]
+    fn credentials_bearer_token__case_insensitive_scheme__then_token() {
+        let headers = headers_with("bearer secret");
+        assert_eq!(
+            Credentials::new(remote_addr(), &headers).bearer_token(),
+            Some("secret")
+        );
+    }
+
+    #[test]
+    fn credentials_bearer_token__basic_scheme__then_none() {
+        let headers = headers_with("Basic dXNlcjpwYXNz");
+        assert_eq!(
+            Credentials::new(remote_addr(), &headers).bearer_token(),
+            None
+        );
+    }
+
+    #[test]
+    fn bearer_token_authenticator__matching_and_mismatched_tokens__then_expected() {
+        let authenticator = BearerTokenAuthenticator::new("secret");
+
+        let good = headers_with("Bearer secret");
+        let wrong = headers_with("Bearer secreT");
+        let missing = HeaderMap::new();
+
+        assert!(authenticator.authenticate(&Credentials::new(remote_addr(), &good)));
+        assert!(!authenticator.authenticate(&Credentials::new(remote_addr(), &wrong)));
+        assert!(!authenticator.authenticate(&Credentials::new(remote_addr(), &missing)));
+    }
+}
@@ -19,6 +19,8 @@ pub enum JsonRpcServerError {
     ConnectionLimit,
     #[error("rate limit exceeded")]
     RateLimited,
+    #[error("request not authenticated")]
+    Unauthorized,
     #[error("method not found: {0}")]
     MethodNotFound(String),
     #[error("invalid request: {0}")]
@@ -32,6 +34,7 @@ impl From<JsonRpcServerError> for JsonRpcError {
         match value {
             JsonRpcServerError::ConnectionLimit => JsonRpcError::too_many_connections(),
             JsonRpcServerError::RateLimited => JsonRpcError::rate_limited(),
+            JsonRpcServerError::Unauthorized => JsonRpcError::unauthorized(),
             JsonRpcServerError::MethodNotFound(method) => JsonRpcError::method_not_found(&method),
             JsonRpcServerError::InvalidRequest(msg) => JsonRpcError::invalid_request(msg),
             JsonRpcServerError::Internal(msg) => JsonRpcError::internal(msg),
@@ -62,10 +65,15 @@ mod tests {
 
         let rate = JsonRpcServerError::RateLimited;
         let as_json: JsonRpcError = rate.clone().into();
-        assert_eq!(as_json.code, -32001);
-        assert_eq!(as_json.message, "Too many requests");
+        assert_eq!(as_json.code, -32000);
+        assert_eq!(as_json.message, "Rate limited");
         assert!(as_json.data.is_none());
 
+        let unauthorized = JsonRpcServerError::Unauthorized;
+        let as_json: JsonRpcError = unauthorized.clone().into();
+        assert_eq!(as_json.code, -32000);
+        assert_eq!(as_json.message, "Unauthorized");
+
         let method = JsonRpcServerError::MethodNotFound("trace.echo".into());
         let as_json: JsonRpcError = method.clone().into();
         assert_eq!(as_json.code, -32601);
@@ -1,8 +1,13 @@
-use std::{future::Future, sync::Arc};
+use std::{
+    future::{self, Future},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
 use serde_json::Value;
+use tracing::warn;
 
 use super::types::JsonRpcError;
 
@@ -10,7 +15,19 @@ pub type JsonRpcResult = Result<Value, JsonRpcError>;
 
 #[async_trait]
 pub trait JsonRpcHandler: Send + Sync {
-    async fn call(&self, params: Option<Value>) -> JsonRpcResult;
+    /// Handles a call with params borrowed from the caller, which keeps
+    /// them for logging once the call returns.
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult;
+
+    async fn call(&self, params: Option<Value>) -> JsonRpcResult {
+        self.handle(params.as_ref()).await
+    }
+
+    /// Whether the handler changes server or trace state. Mutating handlers
+    /// are refused when the server runs read-only.
+    fn is_mutating(&self) -> bool {
+        false
+    }
 }
 
 struct FnHandler<F>
@@ -23,10 +40,10 @@ where
 #[async_trait]
 impl<F, Fut> JsonRpcHandler for FnHandler<F>
 where
-    F: Fn(Option<Value>) -> Fut + Send + Sync,
+    F: Fn(Option<&Value>) -> Fut + Send + Sync,
     Fut: Future<Output = JsonRpcResult> + Send,
 {
-    async fn call(&self, params: Option<Value>) -> JsonRpcResult {
+    async fn handle(&self, params: Option<&Value>) -> JsonRpcResult {
         (self.func)(params).await
     }
 }
@@ -34,6 +51,7 @@ where
 #[derive(Clone, Default)]
 pub struct HandlerRegistry {
     handlers: Arc<DashMap<String, Arc<dyn JsonRpcHandler>>>,
+    slow_call_threshold: Option<Duration>,
 }
 
 impl HandlerRegistry {
@@ -41,6 +59,14 @@ impl HandlerRegistry {
         Self::default()
     }
 
+    /// Registry that logs calls taking at least `threshold` at warn level.
+    pub fn with_slow_call_threshold(threshold: Option<Duration>) -> Self {
+        Self {
+            slow_call_threshold: threshold,
+            ..Self::default()
+        }
+    }
+
     pub fn register_handler<H>(&self, method: impl Into<String>, handler: H)
     where
         H: JsonRpcHandler + 'static,
@@ -51,7 +77,7 @@ impl HandlerRegistry {
 
     pub fn register_async<F, Fut>(&self, method: impl Into<String>, func: F)
     where
-        F: Fn(Option<Value>) -> Fut + Send + Sync + 'static,
+        F: Fn(Option<&Value>) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = JsonRpcResult> + Send + 'static,
     {
         let handler = FnHandler {
@@ -62,25 +88,58 @@ impl HandlerRegistry {
 
     pub fn register_sync<F>(&self, method: impl Into<String>, func: F)
     where
-        F: Fn(Option<Value>) -> JsonRpcResult + Send + Sync + 'static,
+        F: Fn(Option<&Value>) -> JsonRpcResult + Send + Sync + 'static,
     {
-        let func = Arc::new(func);
-        self.register_async(method, move |params| {
-            let func = Arc::clone(&func);
-            async move { (*func)(params) }
-        });
+        self.register_async(method, move |params| future::ready(func(params)));
     }
 
     pub async fn call(&self, method: &str, params: Option<Value>) -> JsonRpcResult {
-        match self.handlers.get(method) {
-            Some(handler) => handler.call(params).await,
-            None => Err(JsonRpcError::method_not_found(method)),
+        let handler = match self.handlers.get(method) {
+            Some(handler) => Arc::clone(handler.value()),
+            None => return Err(JsonRpcError::method_not_found(method)),
+        };
+
+        let threshold = match self.slow_call_threshold {
+            Some(threshold) => threshold,
+            None => return handler.handle(params.as_ref()).await,
+        };
+
+        // The handler only borrows the params, so they are still here to
+        // digest if the call turns out to be slow. Fast calls never hash.
+        let started = Instant::now();
+        let result = handler.handle(params.as_ref()).await;
+        let elapsed = started.elapsed();
+        if elapsed >= threshold {
+            let params_digest = params_digest(params.as_ref());
+            warn!(
+                method,
+                params_digest = %format_args!("{params_digest:x}"),
+                elapsed_ms = elapsed.as_millis() as u64,
+                "slow query"
+            );
         }
+        result
     }
 
     pub fn contains(&self, method: &str) -> bool {
         self.handlers.contains_key(method)
     }
+
+    /// Returns true when `method` is registered and tagged as mutating.
+    pub fn is_mutating(&self, method: &str) -> bool {
+        self.handlers
+            .get(method)
+            .is_some_and(|handler| handler.is_mutating())
+    }
+}
+
+fn params_digest(params: Option<&Value>) -> md5::Digest {
+    let mut context = md5::Context::new();
+    if let Some(params) = params {
+        // Writing into an in-memory hasher cannot fail.
+        let _ = serde_json::to_writer(&mut context, params);
+    }
+    context.compute()
 }
 
 #[cfg(test)]
@@ -94,8 +153,9 @@ mod tests {
     #[tokio::test]
     async fn json_rpc_handler__async_registration__then_invokes_handler() {
         let registry = HandlerRegistry::new();
-        registry.register_async("trace.echo", |params| async move {
-            Ok(params.unwrap_or_else(|| json!({"default": true})))
+        registry.register_async("trace.echo", |params| {
+            let params = params.cloned();
+            async move { Ok(params.unwrap_or_else(|| json!({"default": true}))) }
         });
 
         let result = registry
@@ -161,4 +221,109 @@ mod tests {
         registry.register_sync("trace.echo", |_| Ok(json!(null)));
         assert!(registry.contains("trace.echo"));
     }
+
+    #[tokio::test]
+    async fn json_rpc_handler__slow_call_threshold__then_result_unchanged() {
+        let registry = HandlerRegistry::with_slow_call_threshold(Some(Duration::ZERO));
+        registry.register_sync("trace.echo", |params| {
+            Ok(params.cloned().unwrap_or_default())
+        });
+
+        let result = registry
+            .call("trace.echo", Some(json!({"value": 1})))
+            .await
+            .expect("handler should succeed");
+        assert_eq!(result, json!({"value": 1}));
+
+        let err = registry
+            .call("trace.missing", None)
+            .await
+            .expect_err("expected method not found error");
+        assert_eq!(err.code, -32601);
+    }
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<parking_lot::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn json_rpc_handler__fast_call__then_params_not_copied_or_digested() {
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let params = Value::String("x".repeat(64));
+        let params_ptr = params.as_str().expect("string params").as_ptr() as usize;
+        let registry = HandlerRegistry::with_slow_call_threshold(Some(Duration::from_secs(3600)));
+        registry.register_sync("trace.echo", move |params| {
+            let seen_ptr = params.and_then(Value::as_str).map(|s| s.as_ptr() as usize);
+            Ok(json!(seen_ptr == Some(params_ptr)))
+        });
+
+        let same_allocation = registry
+            .call("trace.echo", Some(params))
+            .await
+            .expect("handler should succeed");
+        assert_eq!(same_allocation, json!(true));
+        assert!(logs.0.lock().is_empty());
+
+        let registry = HandlerRegistry::with_slow_call_threshold(Some(Duration::ZERO));
+        registry.register_sync("trace.echo", |_| Ok(json!(null)));
+        registry
+            .call("trace.echo", Some(json!({"value": 1})))
+            .await
+            .expect("handler should succeed");
+        let logs = String::from_utf8(logs.0.lock().clone()).expect("utf-8 logs");
+        assert!(logs.contains("slow query"));
+        assert!(logs.contains("params_digest"));
+    }
+
+    #[test]
+    fn params_digest__same_params__then_stable_digest() {
+        let params = json!({"traceId": "t", "limit": 10});
+        assert_eq!(params_digest(Some(&params)), params_digest(Some(&params)));
+        assert_ne!(
+            params_digest(Some(&params)),
+            params_digest(Some(&json!({"traceId": "u", "limit": 10})))
+        );
+        assert_eq!(params_digest(None), md5::compute(b""));
+    }
+
+    struct DeleteHandler;
+
+    #[async_trait]
+    impl JsonRpcHandler for DeleteHandler {
+        async fn handle(&self, _params: Option<&Value>) -> JsonRpcResult {
+            Ok(json!({"deleted": true}))
+        }
+
+        fn is_mutating(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn json_rpc_handler__is_mutating__then_reflects_handler_tag() {
+        let registry = HandlerRegistry::new();
+        registry.register_sync("trace.echo", |_| Ok(json!(null)));
+        registry.register_handler("trace.delete", DeleteHandler);
+
+        assert!(!registry.is_mutating("trace.echo"));
+        assert!(registry.is_mutating("trace.delete"));
+        assert!(!registry.is_mutating("trace.missing"));
+    }
 }
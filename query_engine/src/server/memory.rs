@@ -0,0 +1,393 @@
+//! Server-wide memory budget shared by the handlers' caches.
+//!
+//! Every cache built as a [`BudgetedLru`] registers with one
+//! [`MemoryBudget`] and reports the bytes its entries hold. When an insert
+//! takes the total over the budget, the least recently used entry across
+//! *all* registered caches is evicted until the total fits again, so one busy
+//! cache cannot starve the others.
+
+use std::{
+    borrow::Borrow,
+    collections::BTreeMap,
+    hash::Hash,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    time::Instant,
+};
+
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// A cache whose entries count against a [`MemoryBudget`].
+pub trait BudgetedCache: Send + Sync {
+    /// Name the cache is reported under in `system.metrics`.
+    fn name(&self) -> &str;
+
+    fn used_bytes(&self) -> usize;
+
+    fn entry_count(&self) -> usize;
+
+    /// When the entry next in line for eviction was last used; `None` when
+    /// the cache is empty.
+    fn oldest_use(&self) -> Option<Instant>;
+
+    /// Evicts that entry and returns the bytes it held.
+    fn evict_oldest(&self) -> usize;
+}
+
+/// Byte limit shared by every cache registered with it.
+pub struct MemoryBudget {
+    limit_bytes: Option<usize>,
+    caches: Mutex<Vec<Weak<dyn BudgetedCache>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    /// `None` when the budget is unlimited.
+    pub budget_bytes: Option<usize>,
+    pub used_bytes: usize,
+    /// Usage per cache name; caches sharing a name are summed.
+    pub caches: BTreeMap<String, CacheUsage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+    pub used_bytes: usize,
+    pub entries: usize,
+}
+
+impl MemoryBudget {
+    /// `None` accounts for usage without ever evicting for it.
+    pub fn new(limit_bytes: Option<usize>) -> Self {
+        Self {
+            limit_bytes,
+            caches: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(None)
+    }
+
+    pub fn limit_bytes(&self) -> Option<usize> {
+        self.limit_bytes
+    }
+
+    /// Counts `cache` against the budget for as long as it is alive.
+    pub fn register(&self, cache: Weak<dyn BudgetedCache>) {
+        let mut caches = self.caches.lock();
+        caches.retain(|cache| cache.strong_count() > 0);
+        caches.push(cache);
+    }
+
+    fn live_caches(&self) -> Vec<Arc<dyn BudgetedCache>> {
+        self.caches
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.live_caches()
+            .iter()
+            .map(|cache| cache.used_bytes())
+            .sum()
+    }
+
+    pub fn usage(&self) -> MemoryUsage {
+        let mut caches: BTreeMap<String, CacheUsage> = BTreeMap::new();
+        for cache in self.live_caches() {
+            let usage = caches.entry(cache.name().to_string()).or_default();
+            usage.used_bytes += cache.used_bytes();
+            usage.entries += cache.entry_count();
+        }
+        MemoryUsage {
+            budget_bytes: self.limit_bytes,
+            used_bytes: caches.values().map(|usage| usage.used_bytes).sum(),
+            caches,
+        }
+    }
+
+    /// Evicts the least recently used entry across all caches until usage
+    /// fits the limit. An entry larger than the whole budget is evicted too,
+    /// right after it was inserted.
+    ///
+    /// Caches call this after inserting, without holding their own lock.
+    pub fn enforce(&self) {
+        let Some(limit) = self.limit_bytes else {
+            return;
+        };
+        let caches = self.live_caches();
+        let mut used: usize = caches.iter().map(|cache| cache.used_bytes()).sum();
+        while used > limit {
+            let oldest = caches
+                .iter()
+                .filter_map(|cache| cache.oldest_use().map(|used_at| (used_at, cache)))
+                .min_by_key(|(used_at, _)| *used_at);
+            let Some((_, cache)) = oldest else {
+                break;
+            };
+            used = used.saturating_sub(cache.evict_oldest());
+        }
+    }
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// Approximate bytes a cached value keeps alive, heap allocations included.
+pub trait CacheWeight {
+    fn weight_bytes(&self) -> usize;
+}
+
+struct Slot<V> {
+    value: V,
+    bytes: usize,
+    last_used: Instant,
+}
+
+/// An LRU cache that also counts against a [`MemoryBudget`].
+///
+/// Lookups return clones, so values are usually small records or hold their
+/// bulk behind an `Arc`.
+pub struct BudgetedLru<K: Hash + Eq, V> {
+    name: String,
+    entries: Mutex<LruCache<K, Slot<V>>>,
+    used_bytes: AtomicUsize,
+    budget: Arc<MemoryBudget>,
+}
+
+impl<K, V> BudgetedLru<K, V>
+where
+    K: Hash + Eq + Send + 'static,
+    V: CacheWeight + Clone + Send + 'static,
+{
+    /// A cache of at most `capacity` entries, registered with `budget`.
+    pub fn new(
+        name: impl Into<String>,
+        capacity: NonZeroUsize,
+        budget: Arc<MemoryBudget>,
+    ) -> Arc<Self> {
+        let cache = Arc::new(Self {
+            name: name.into(),
+            entries: Mutex::new(LruCache::new(capacity)),
+            used_bytes: AtomicUsize::new(0),
+            budget,
+        });
+        let weak: Weak<Self> = Arc::downgrade(&cache);
+        cache.budget.register(weak);
+        cache
+    }
+
+    pub fn capacity(&self) -> NonZeroUsize {
+        self.entries.lock().cap()
+    }
+
+    /// Returns the value and marks it used.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut entries = self.entries.lock();
+        let slot = entries.get_mut(key)?;
+        slot.last_used = Instant::now();
+        Some(slot.value.clone())
+    }
+
+    /// Returns the value without marking it used.
+    pub fn peek<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.lock().peek(key).map(|slot| slot.value.clone())
+    }
+
+    /// Inserts `value`, evicting across the budget's caches if needed.
+    pub fn put(&self, key: K, value: V) {
+        let bytes = value.weight_bytes();
+        {
+            let mut entries = self.entries.lock();
+            let slot = Slot {
+                value,
+                bytes,
+                last_used: Instant::now(),
+            };
+            // Returns the replaced entry, or the one evicted for capacity
+            if let Some((_, old)) = entries.push(key, slot) {
+                self.used_bytes.fetch_sub(old.bytes, Ordering::Relaxed);
+            }
+            self.used_bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+        self.budget.enforce();
+    }
+
+    /// Changes the value in place and weighs it again. Returns false when
+    /// `key` is not cached.
+    pub fn update<Q>(&self, key: &Q, change: impl FnOnce(&mut V)) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        {
+            let mut entries = self.entries.lock();
+            let Some(slot) = entries.get_mut(key) else {
+                return false;
+            };
+            change(&mut slot.value);
+            let bytes = slot.value.weight_bytes();
+            self.used_bytes.fetch_sub(slot.bytes, Ordering::Relaxed);
+            self.used_bytes.fetch_add(bytes, Ordering::Relaxed);
+            slot.bytes = bytes;
+            slot.last_used = Instant::now();
+        }
+        self.budget.enforce();
+        true
+    }
+
+    pub fn pop<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.entries.lock().pop(key)?;
+        self.used_bytes.fetch_sub(slot.bytes, Ordering::Relaxed);
+        Some(slot.value)
+    }
+}
+
+impl<K, V> BudgetedCache for BudgetedLru<K, V>
+where
+    K: Hash + Eq + Send,
+    V: Send,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn used_bytes(&self) -> usize {
+        self.used_bytes.load(Ordering::Relaxed)
+    }
+
+    fn entry_count(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    fn oldest_use(&self) -> Option<Instant> {
+        self.entries
+            .lock()
+            .peek_lru()
+            .map(|(_, slot)| slot.last_used)
+    }
+
+    fn evict_oldest(&self) -> usize {
+        match self.entries.lock().pop_lru() {
+            Some((_, slot)) => {
+                self.used_bytes.fetch_sub(slot.bytes, Ordering::Relaxed);
+                slot.bytes
+            }
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Blob(usize);
+
+    impl CacheWeight for Blob {
+        fn weight_bytes(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn cache(name: &str, budget: &Arc<MemoryBudget>) -> Arc<BudgetedLru<u32, Blob>> {
+        BudgetedLru::new(name, NonZeroUsize::new(8).unwrap(), Arc::clone(budget))
+    }
+
+    #[test]
+    fn memory_budget__over_limit__then_oldest_entry_across_caches_evicted() {
+        let budget = Arc::new(MemoryBudget::new(Some(100)));
+        let spans = cache("spans", &budget);
+        let info = cache("info", &budget);
+
+        spans.put(1, Blob(40));
+        info.put(1, Blob(40));
+        // Touching the span entry leaves the info entry least recently used
+        assert!(spans.get(&1).is_some());
+        spans.put(2, Blob(40));
+
+        assert!(info.peek(&1).is_none());
+        assert_eq!(spans.peek(&1), Some(Blob(40)));
+        assert_eq!(spans.peek(&2), Some(Blob(40)));
+        assert_eq!(budget.used_bytes(), 80);
+    }
+
+    #[test]
+    fn memory_budget__usage__then_reported_per_cache() {
+        let budget = Arc::new(MemoryBudget::unlimited());
+        let spans = cache("spans", &budget);
+        spans.put(1, Blob(10));
+        spans.put(2, Blob(20));
+        assert!(spans.update(&1, |blob| blob.0 = 15));
+        spans.put(2, Blob(5));
+        {
+            let other = cache("info", &budget);
+            other.put(1, Blob(1_000));
+        }
+
+        let usage = budget.usage();
+        assert_eq!(usage.budget_bytes, None);
+        assert_eq!(usage.used_bytes, 20);
+        assert_eq!(
+            usage.caches,
+            BTreeMap::from([(
+                "spans".to_string(),
+                CacheUsage {
+                    used_bytes: 20,
+                    entries: 2,
+                }
+            )])
+        );
+    }
+
+    #[test]
+    fn budgeted_lru__capacity_eviction_and_pop__then_bytes_released() {
+        let budget = Arc::new(MemoryBudget::unlimited());
+        let small = BudgetedLru::new("small", NonZeroUsize::new(1).unwrap(), Arc::clone(&budget));
+
+        small.put(1, Blob(10));
+        small.put(2, Blob(20));
+        assert_eq!(small.used_bytes(), 20);
+        assert_eq!(small.pop(&2), Some(Blob(20)));
+        assert_eq!(small.used_bytes(), 0);
+        assert!(!small.update(&2, |_| {}));
+    }
+
+    #[test]
+    fn memory_budget__entry_larger_than_budget__then_not_kept() {
+        let budget = Arc::new(MemoryBudget::new(Some(10)));
+        let spans = cache("spans", &budget);
+
+        spans.put(1, Blob(11));
+
+        assert!(spans.peek(&1).is_none());
+        assert_eq!(budget.used_bytes(), 0);
+    }
+}
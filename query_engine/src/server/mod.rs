@@ -1,15 +1,19 @@
+pub mod auth;
 pub mod connection;
 pub mod errors;
 pub mod handler;
+pub mod memory;
 pub mod rate_limit;
 pub mod server;
 pub mod types;
 
+pub use auth::{Authenticator, BearerTokenAuthenticator, Credentials};
 pub use connection::{
     ConnectionError, ConnectionGuard, ConnectionManager, ConnectionManagerConfig,
 };
 pub use errors::{JsonRpcServerError, ServerError};
 pub use handler::{HandlerRegistry, JsonRpcHandler};
+pub use memory::{BudgetedCache, BudgetedLru, CacheWeight, MemoryBudget, MemoryUsage};
 pub use rate_limit::RateLimiter;
 pub use server::{JsonRpcServer, JsonRpcServerConfig};
 pub use types::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
@@ -1,4 +1,7 @@
-use std::{net::IpAddr, time::Instant};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 use dashmap::DashMap;
 use parking_lot::Mutex;
@@ -17,13 +20,16 @@ impl TokenBucket {
         }
     }
 
-    fn try_acquire(&mut self, capacity: f64, refill_per_sec: f64) -> bool {
+    /// Takes a token, or returns how long until the next one is available.
+    fn try_acquire(&mut self, capacity: f64, refill_per_sec: f64) -> Result<(), Duration> {
         self.refill(capacity, refill_per_sec);
         if self.tokens >= 1.0 {
             self.tokens -= 1.0;
-            true
+            Ok(())
         } else {
-            false
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / refill_per_sec,
+            ))
         }
     }
 
@@ -39,16 +45,27 @@ impl TokenBucket {
     }
 }
 
-/// Simple per-IP token bucket rate limiter.
+/// Simple per-connection token bucket rate limiter.
+///
+/// Buckets are keyed by the connection's remote address, which is unique
+/// among open TCP connections. The transport calls [`forget`](Self::forget)
+/// when a connection closes; reconnecting starts a fresh bucket, so the
+/// per-IP connection limit is what bounds a client that reconnects.
 pub struct RateLimiter {
     capacity: f64,
     refill_per_sec: f64,
-    buckets: DashMap<IpAddr, Mutex<TokenBucket>>,
+    buckets: DashMap<SocketAddr, Mutex<TokenBucket>>,
     unlimited: bool,
 }
 
 impl RateLimiter {
     pub fn new(max_requests_per_second: u32) -> Self {
+        Self::with_burst(max_requests_per_second, 0)
+    }
+
+    /// Refills at `max_requests_per_second` and holds up to `burst` tokens;
+    /// a `burst` of 0 uses the per-second rate as the burst size.
+    pub fn with_burst(max_requests_per_second: u32, burst: u32) -> Self {
         if max_requests_per_second == 0 {
             return Self {
                 capacity: f64::INFINITY,
@@ -58,30 +75,46 @@ impl RateLimiter {
             };
         }
 
-        let capacity = max_requests_per_second as f64;
+        let refill_per_sec = max_requests_per_second as f64;
+        let capacity = if burst == 0 {
+            refill_per_sec
+        } else {
+            burst as f64
+        };
         Self {
             capacity,
-            refill_per_sec: capacity,
+            refill_per_sec,
             buckets: DashMap::new(),
             unlimited: false,
         }
     }
 
-    pub fn allow(&self, ip: IpAddr) -> bool {
+    pub fn allow(&self, connection: SocketAddr) -> bool {
+        self.check(connection).is_ok()
+    }
+
+    /// Like [`allow`](Self::allow), but reports the wait before the next
+    /// request on `connection` would be accepted.
+    pub fn check(&self, connection: SocketAddr) -> Result<(), Duration> {
         if self.unlimited {
-            return true;
+            return Ok(());
         }
 
         let entry = self
             .buckets
-            .entry(ip)
+            .entry(connection)
             .or_insert_with(|| Mutex::new(TokenBucket::new(self.capacity)));
         let mut bucket = entry.lock();
         bucket.try_acquire(self.capacity, self.refill_per_sec)
     }
 
+    /// Drops the bucket of a closed connection.
+    pub fn forget(&self, connection: SocketAddr) {
+        self.buckets.remove(&connection);
+    }
+
     #[cfg(test)]
-    pub fn tracked_ips(&self) -> usize {
+    pub fn tracked_connections(&self) -> usize {
         self.buckets.len()
     }
 
@@ -96,60 +129,89 @@ mod tests {
 
     use super::*;
     use std::{
-        net::{IpAddr, Ipv4Addr},
+        net::{Ipv4Addr, SocketAddr},
         thread,
         time::Duration,
     };
 
-    fn localhost() -> IpAddr {
-        IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))
+    fn connection(port: u16) -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::LOCALHOST, port))
     }
 
     #[test]
     fn json_rpc_rate_limit__rapid_successive_calls__then_triggers_zero_duration_check() {
         // This test specifically targets the zero-duration check on line 34
         let limiter = RateLimiter::new(2);
-        let ip = localhost();
+        let conn = connection(40000);
 
         // These rapid calls should trigger the zero-duration check in refill()
-        assert!(limiter.allow(ip));
-        assert!(limiter.allow(ip)); // Immediate call, likely zero duration
-        assert!(!limiter.allow(ip)); // Should fail, no time to refill
+        assert!(limiter.allow(conn));
+        assert!(limiter.allow(conn)); // Immediate call, likely zero duration
+        assert!(!limiter.allow(conn)); // Should fail, no time to refill
     }
 
     #[test]
     fn json_rpc_rate_limit__allow_within_capacity__then_succeeds() {
         let limiter = RateLimiter::new(2);
-        let ip = localhost();
+        let conn = connection(40000);
+
+        assert!(limiter.allow(conn));
+        assert!(limiter.allow(conn));
+        assert!(!limiter.allow(conn));
+        assert_eq!(limiter.tracked_connections(), 1);
+    }
 
-        assert!(limiter.allow(ip));
-        assert!(limiter.allow(ip));
-        assert!(!limiter.allow(ip));
-        assert_eq!(limiter.tracked_ips(), 1);
+    #[test]
+    fn json_rpc_rate_limit__separate_connections__then_separate_buckets() {
+        let limiter = RateLimiter::new(1);
+        let first = connection(40000);
+        let second = connection(40001);
+
+        assert!(limiter.allow(first));
+        assert!(!limiter.allow(first));
+        assert!(limiter.allow(second));
+
+        limiter.forget(first);
+        assert_eq!(limiter.tracked_connections(), 1);
+        assert!(limiter.allow(first));
     }
 
     #[test]
     fn json_rpc_rate_limit__refill_after_sleep__then_allows_again() {
         let limiter = RateLimiter::new(1);
-        let ip = localhost();
+        let conn = connection(40000);
 
-        assert!(limiter.allow(ip));
-        assert!(!limiter.allow(ip));
+        assert!(limiter.allow(conn));
+        assert!(!limiter.allow(conn));
 
         thread::sleep(Duration::from_millis(1100));
 
-        assert!(limiter.allow(ip));
+        assert!(limiter.allow(conn));
+    }
+
+    #[test]
+    fn json_rpc_rate_limit__burst_above_rate__then_allows_burst_then_retry_after() {
+        let limiter = RateLimiter::with_burst(2, 5);
+        let conn = connection(40000);
+
+        for _ in 0..5 {
+            assert!(limiter.check(conn).is_ok());
+        }
+        let retry_after = limiter.check(conn).expect_err("bucket exhausted");
+        assert!(retry_after > Duration::ZERO);
+        assert!(retry_after <= Duration::from_millis(500));
+        assert_eq!(limiter.capacity(), 5.0);
     }
 
     #[test]
     fn json_rpc_rate_limit__unlimited_configuration__then_always_allows() {
         let limiter = RateLimiter::new(0);
-        let ip = localhost();
+        let conn = connection(40000);
 
         for _ in 0..100 {
-            assert!(limiter.allow(ip));
+            assert!(limiter.allow(conn));
         }
-        assert_eq!(limiter.tracked_ips(), 0);
+        assert_eq!(limiter.tracked_connections(), 0);
         assert!(limiter.capacity().is_infinite());
     }
 }
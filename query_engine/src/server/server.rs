@@ -2,39 +2,65 @@ use std::{
     convert::Infallible,
     future::Future,
     net::{IpAddr, SocketAddr},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use hyper::server::{conn::AddrIncoming, conn::AddrStream, Builder};
 use hyper::{
     body,
-    header::CONTENT_TYPE,
+    header::{CONTENT_TYPE, RETRY_AFTER},
     http::StatusCode,
     service::{make_service_fn, service_fn},
     Body, Method, Request, Response,
 };
+use serde_json::{json, Value};
+use tracing::{debug, field, info_span, warn, Instrument};
 
 use super::{
+    auth::{Authenticator, Credentials},
     connection::{ConnectionError, ConnectionManager, ConnectionManagerConfig},
     errors::{JsonRpcServerError, ServerError},
     handler::HandlerRegistry,
+    memory::MemoryBudget,
     rate_limit::RateLimiter,
     types::{JsonRpcError, JsonRpcRequest, JsonRpcResponse},
 };
 
 #[derive(Clone, Debug)]
 pub struct JsonRpcServerConfig {
+    /// Sustained per-client request rate; 0 disables rate limiting.
     pub max_requests_per_second: u32,
+    /// Maximum burst per client; 0 uses `max_requests_per_second`.
+    pub rate_limit_burst: u32,
     pub max_concurrent_per_ip: usize,
     pub max_total_concurrent: usize,
+    /// Gate for HTTP requests; `None` leaves the server open.
+    pub authenticator: Option<Arc<dyn Authenticator>>,
+    /// Refuse handlers that report `is_mutating()`.
+    pub read_only: bool,
+    /// Handler calls at least this slow are logged at warn level; `None`
+    /// disables the slow-query log.
+    pub slow_query_threshold: Option<Duration>,
+    /// Bytes all handler caches may hold together; `None` leaves them bound
+    /// only by their entry counts.
+    pub memory_budget_bytes: Option<usize>,
 }
 
 impl Default for JsonRpcServerConfig {
     fn default() -> Self {
         Self {
             max_requests_per_second: 2_000,
+            rate_limit_burst: 0,
             max_concurrent_per_ip: 2_000,
             max_total_concurrent: 20_000,
+            authenticator: None,
+            read_only: false,
+            slow_query_threshold: Some(Duration::from_millis(500)),
+            memory_budget_bytes: None,
         }
     }
 }
@@ -49,6 +75,8 @@ struct JsonRpcServerInner {
     handlers: HandlerRegistry,
     connections: ConnectionManager,
     rate_limiter: RateLimiter,
+    memory_budget: Arc<MemoryBudget>,
+    next_request_id: AtomicU64,
 }
 
 impl JsonRpcServer {
@@ -62,14 +90,25 @@ impl JsonRpcServer {
             max_per_ip: config.max_concurrent_per_ip,
         };
 
-        Self {
+        let server = Self {
             inner: Arc::new(JsonRpcServerInner {
-                handlers: HandlerRegistry::new(),
+                handlers: HandlerRegistry::with_slow_call_threshold(config.slow_query_threshold),
                 connections: ConnectionManager::new(connection_config),
-                rate_limiter: RateLimiter::new(config.max_requests_per_second),
+                rate_limiter: RateLimiter::with_burst(
+                    config.max_requests_per_second,
+                    config.rate_limit_burst,
+                ),
+                memory_budget: Arc::new(MemoryBudget::new(config.memory_budget_bytes)),
+                next_request_id: AtomicU64::new(1),
                 config,
             }),
-        }
+        };
+
+        let memory_budget = Arc::clone(&server.inner.memory_budget);
+        server.register_sync("system.metrics", move |_| {
+            Ok(json!({ "memory": memory_budget.usage() }))
+        });
+        server
     }
 
     pub fn config(&self) -> &JsonRpcServerConfig {
@@ -80,9 +119,14 @@ impl JsonRpcServer {
         self.inner.handlers.clone()
     }
 
+    /// Budget that handler caches join when they register.
+    pub fn memory_budget(&self) -> Arc<MemoryBudget> {
+        Arc::clone(&self.inner.memory_budget)
+    }
+
     pub fn register_async<F, Fut>(&self, method: impl Into<String>, func: F)
     where
-        F: Fn(Option<serde_json::Value>) -> Fut + Send + Sync + 'static,
+        F: Fn(Option<&serde_json::Value>) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<serde_json::Value, JsonRpcError>> + Send + 'static,
     {
         self.inner.handlers.register_async(method, func);
@@ -90,7 +134,7 @@ impl JsonRpcServer {
 
     pub fn register_sync<F>(&self, method: impl Into<String>, func: F)
     where
-        F: Fn(Option<serde_json::Value>) -> Result<serde_json::Value, JsonRpcError>
+        F: Fn(Option<&serde_json::Value>) -> Result<serde_json::Value, JsonRpcError>
             + Send
             + Sync
             + 'static,
@@ -138,11 +182,14 @@ impl JsonRpcServer {
     {
         let server = self.clone();
         let make_service = make_service_fn(move |conn: &AddrStream| {
-            let remote_addr = conn.remote_addr();
-            let server = server.clone();
+            let connection = ConnectionScope {
+                server: server.clone(),
+                remote_addr: conn.remote_addr(),
+            };
             async move {
                 Ok::<_, Infallible>(service_fn(move |req| {
-                    let server = server.clone();
+                    let server = connection.server.clone();
+                    let remote_addr = connection.remote_addr;
                     async move { server.handle_http_request(req, remote_addr).await }
                 }))
             }
@@ -169,9 +216,26 @@ impl JsonRpcServer {
 
         let remote_ip = remote_addr.ip();
 
-        if !self.inner.rate_limiter.allow(remote_ip) {
-            let response = JsonRpcServerError::RateLimited.to_response(None);
-            return Ok(json_response(response));
+        // Only HTTP clients are limited, each connection on its own budget;
+        // in-process callers go through the handler registry directly and
+        // never reach this point.
+        if let Err(retry_after) = self.inner.rate_limiter.check(remote_addr) {
+            let error = JsonRpcError::rate_limited_retry_after(retry_after);
+            let mut response = json_response(JsonRpcResponse::error(None, error));
+            let retry_after_secs =
+                retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, retry_after_secs.max(1).into());
+            return Ok(response);
+        }
+
+        if let Some(authenticator) = &self.inner.config.authenticator {
+            let credentials = Credentials::new(remote_addr, req.headers());
+            if !authenticator.authenticate(&credentials) {
+                let response = JsonRpcServerError::Unauthorized.to_response(None);
+                return Ok(json_response(response));
+            }
         }
 
         let guard = match self.inner.connections.acquire(remote_ip) {
@@ -250,23 +314,63 @@ impl JsonRpcServer {
             id,
         } = request;
 
+        let request_id = self.inner.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let span = info_span!(
+            "rpc",
+            request_id,
+            method = %method,
+            trace_id = field::Empty,
+        );
+        if let Some(trace_id) = params
+            .as_ref()
+            .and_then(|params| params.get("traceId"))
+            .and_then(Value::as_str)
+        {
+            span.record("trace_id", trace_id);
+        }
+
+        let result = async {
+            if self.inner.config.read_only && self.inner.handlers.is_mutating(&method) {
+                return Err(JsonRpcError::read_only(&method));
+            }
+            debug!("dispatching request");
+            let result = self.inner.handlers.call(&method, params).await;
+            if let Err(err) = &result {
+                warn!(code = err.code, message = %err.message, "request failed");
+            }
+            result
+        }
+        .instrument(span)
+        .await;
+
         if id.is_none() {
-            let _ = self.inner.handlers.call(&method, params).await;
             return Response::builder()
                 .status(StatusCode::NO_CONTENT)
                 .body(Body::empty())
                 .expect("building notification response");
         }
 
-        let result = self.inner.handlers.call(&method, params).await;
         let response = match result {
-            Ok(value) => JsonRpcResponse::success(id.clone(), value),
-            Err(err) => JsonRpcResponse::error(id.clone(), err),
+            Ok(value) => JsonRpcResponse::success(id, value),
+            Err(err) => JsonRpcResponse::error(id, err.with_request_id(request_id)),
         };
         json_response(response)
     }
 }
 
+/// Per-connection state owned by the connection's service, which hyper
+/// drops when the connection closes
+struct ConnectionScope {
+    server: JsonRpcServer,
+    remote_addr: SocketAddr,
+}
+
+impl Drop for ConnectionScope {
+    fn drop(&mut self) {
+        self.server.inner.rate_limiter.forget(self.remote_addr);
+    }
+}
+
 fn json_response(response: JsonRpcResponse) -> Response<Body> {
     let payload = serde_json::to_vec(&response).expect("serializing JSON-RPC response");
     Response::builder()
@@ -281,6 +385,11 @@ mod tests {
     #![allow(non_snake_case)]
 
     use super::*;
+    use crate::server::{
+        auth::BearerTokenAuthenticator,
+        handler::{JsonRpcHandler, JsonRpcResult},
+    };
+    use async_trait::async_trait;
     use hyper::body::to_bytes;
     use serde_json::{json, Value};
     use std::{
@@ -303,6 +412,7 @@ mod tests {
             max_requests_per_second: 0,
             max_concurrent_per_ip: 10,
             max_total_concurrent: 10,
+            ..JsonRpcServerConfig::default()
         }
     }
 
@@ -328,6 +438,7 @@ mod tests {
             max_requests_per_second: 42,
             max_concurrent_per_ip: 24,
             max_total_concurrent: 100,
+            ..JsonRpcServerConfig::default()
         };
         let server = JsonRpcServer::with_config(config.clone());
 
@@ -346,6 +457,43 @@ mod tests {
         assert!(registry.contains("test_method"));
     }
 
+    #[tokio::test]
+    async fn json_rpc_server__system_metrics__then_reports_memory_budget() {
+        use crate::server::memory::{BudgetedLru, CacheWeight};
+        use std::num::NonZeroUsize;
+
+        #[derive(Clone)]
+        struct Blob;
+        impl CacheWeight for Blob {
+            fn weight_bytes(&self) -> usize {
+                64
+            }
+        }
+
+        let server = JsonRpcServer::with_config(JsonRpcServerConfig {
+            memory_budget_bytes: Some(1_024),
+            ..test_config()
+        });
+        let cache = BudgetedLru::new(
+            "blobs",
+            NonZeroUsize::new(4).unwrap(),
+            server.memory_budget(),
+        );
+        cache.put(1u32, Blob);
+
+        let metrics = server
+            .handler_registry()
+            .call("system.metrics", None)
+            .await
+            .expect("metrics");
+        assert_eq!(metrics["memory"]["budgetBytes"], 1_024);
+        assert_eq!(metrics["memory"]["usedBytes"], 64);
+        assert_eq!(
+            metrics["memory"]["caches"]["blobs"],
+            json!({ "usedBytes": 64, "entries": 1 })
+        );
+    }
+
     // Note: serve() method uses pending::<()>().await which would run forever
     // Coverage for lines 101-104 is achieved through serve_with_shutdown tests
 
@@ -459,6 +607,7 @@ mod tests {
             max_requests_per_second: 1,
             max_concurrent_per_ip: 10,
             max_total_concurrent: 10,
+            ..JsonRpcServerConfig::default()
         });
         let body = build_request(Body::from(
             r#"{"jsonrpc":"2.0","method":"trace.info","id":1}"#,
@@ -478,9 +627,98 @@ mod tests {
             .await
             .expect("second response");
 
+        assert_eq!(
+            second
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok()),
+            Some("1")
+        );
         let payload = parse_body(second).await;
-        assert_eq!(payload["error"]["code"], -32001);
-        assert_eq!(payload["error"]["message"], "Too many requests");
+        assert_eq!(payload["error"]["code"], -32000);
+        assert_eq!(payload["error"]["message"], "Rate limited");
+        assert!(payload["error"]["data"]["retryAfterMs"].as_u64().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn json_rpc_server__authenticator_configured__then_rejects_missing_token() {
+        let server = JsonRpcServer::with_config(JsonRpcServerConfig {
+            authenticator: Some(Arc::new(BearerTokenAuthenticator::new("secret"))),
+            ..test_config()
+        });
+        server.register_sync("ping", |_| Ok(json!("pong")));
+        let body = r#"{"jsonrpc":"2.0","method":"ping","id":1}"#;
+
+        let rejected = server
+            .handle_http_request(build_request(Body::from(body)), remote_addr())
+            .await
+            .expect("http response");
+        let payload = parse_body(rejected).await;
+        assert_eq!(payload["error"]["code"], -32000);
+        assert_eq!(payload["error"]["message"], "Unauthorized");
+
+        let mut authorized = build_request(Body::from(body));
+        authorized.headers_mut().insert(
+            hyper::header::AUTHORIZATION,
+            "Bearer secret".parse().expect("header"),
+        );
+        let accepted = server
+            .handle_http_request(authorized, remote_addr())
+            .await
+            .expect("http response");
+        let payload = parse_body(accepted).await;
+        assert_eq!(payload["result"], "pong");
+
+        // Direct registry access bypasses transport authentication.
+        let direct = server.handler_registry().call("ping", None).await;
+        assert_eq!(direct.expect("direct call"), json!("pong"));
+    }
+
+    struct PurgeHandler;
+
+    #[async_trait]
+    impl JsonRpcHandler for PurgeHandler {
+        async fn handle(&self, _params: Option<&Value>) -> JsonRpcResult {
+            Ok(json!({"purged": true}))
+        }
+
+        fn is_mutating(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn json_rpc_server__read_only__then_refuses_mutating_handlers() {
+        let server = JsonRpcServer::with_config(JsonRpcServerConfig {
+            read_only: true,
+            ..test_config()
+        });
+        server
+            .handler_registry()
+            .register_handler("trace.purge", PurgeHandler);
+        server.register_sync("ping", |_| Ok(json!("pong")));
+
+        let refused = server
+            .handle_http_request(
+                build_request(Body::from(
+                    r#"{"jsonrpc":"2.0","method":"trace.purge","id":1}"#,
+                )),
+                remote_addr(),
+            )
+            .await
+            .expect("http response");
+        let payload = parse_body(refused).await;
+        assert_eq!(payload["error"]["code"], -32005);
+        assert_eq!(payload["error"]["data"]["detail"], "trace.purge");
+
+        let allowed = server
+            .handle_http_request(
+                build_request(Body::from(r#"{"jsonrpc":"2.0","method":"ping","id":2}"#)),
+                remote_addr(),
+            )
+            .await
+            .expect("http response");
+        assert_eq!(parse_body(allowed).await["result"], "pong");
     }
 
     #[tokio::test]
@@ -489,6 +727,7 @@ mod tests {
             max_requests_per_second: 0,
             max_concurrent_per_ip: 1,
             max_total_concurrent: 1,
+            ..JsonRpcServerConfig::default()
         });
         let ip = localhost();
         let guard = server
@@ -617,7 +856,7 @@ mod tests {
     async fn json_rpc_server__method_dispatch_success__then_returns_result() {
         let server = JsonRpcServer::with_config(test_config());
         server.register_sync("trace.echo", |params| {
-            Ok(params.unwrap_or_else(|| json!({})))
+            Ok(params.cloned().unwrap_or_else(|| json!({})))
         });
 
         let response = server
@@ -673,7 +912,33 @@ mod tests {
 
         let payload = parse_body(response).await;
         assert_eq!(payload["error"]["code"], -32601);
-        assert_eq!(payload["error"]["data"], "trace.unknown");
+        assert_eq!(payload["error"]["data"]["detail"], "trace.unknown");
+        assert!(payload["error"]["data"]["requestId"].as_u64().is_some());
+    }
+
+    #[tokio::test]
+    async fn json_rpc_server__handler_errors__then_distinct_request_ids() {
+        let server = JsonRpcServer::with_config(test_config());
+        server.register_sync("trace.fail", |_| {
+            Err(JsonRpcError::new(
+                -32010,
+                "failed",
+                Some(json!({"hint": 1})),
+            ))
+        });
+        let body = r#"{"jsonrpc":"2.0","method":"trace.fail","params":{"traceId":"t"},"id":1}"#;
+
+        let mut request_ids = Vec::new();
+        for _ in 0..2 {
+            let response = server
+                .handle_http_request(build_request(Body::from(body)), remote_addr())
+                .await
+                .expect("http response");
+            let payload = parse_body(response).await;
+            assert_eq!(payload["error"]["data"]["hint"], 1);
+            request_ids.push(payload["error"]["data"]["requestId"].clone());
+        }
+        assert_ne!(request_ids[0], request_ids[1]);
     }
 
     #[tokio::test]
@@ -120,13 +120,59 @@ impl JsonRpcError {
         Self::new(-32000, "Trace not found", None)
     }
 
+    pub fn span_not_found(span_id: &str) -> Self {
+        Self::new(
+            -32000,
+            "Span not found",
+            Some(Value::String(span_id.to_string())),
+        )
+    }
+
     pub fn rate_limited() -> Self {
-        Self::new(-32001, "Too many requests", None)
+        Self::new(-32000, "Rate limited", None)
+    }
+
+    /// Rate-limit rejection carrying a `retryAfterMs` hint for the client.
+    pub fn rate_limited_retry_after(retry_after: std::time::Duration) -> Self {
+        let retry_after_ms = retry_after.as_millis().max(1) as u64;
+        Self::new(
+            -32000,
+            "Rate limited",
+            Some(serde_json::json!({ "retryAfterMs": retry_after_ms })),
+        )
     }
 
     pub fn too_many_connections() -> Self {
         Self::new(-32002, "Too many concurrent connections", None)
     }
+
+    pub fn unauthorized() -> Self {
+        Self::new(-32000, "Unauthorized", None)
+    }
+
+    /// Tags the error with the server-assigned request id so client reports
+    /// can be matched against server logs. Object `data` gains a `requestId`
+    /// field; any other `data` moves under `detail`.
+    pub fn with_request_id(mut self, request_id: u64) -> Self {
+        let data = match self.data.take() {
+            Some(Value::Object(mut map)) => {
+                map.insert("requestId".into(), request_id.into());
+                Value::Object(map)
+            }
+            Some(detail) => serde_json::json!({ "detail": detail, "requestId": request_id }),
+            None => serde_json::json!({ "requestId": request_id }),
+        };
+        self.data = Some(data);
+        self
+    }
+
+    pub fn read_only(method: &str) -> Self {
+        Self::new(
+            -32005,
+            "Server is read-only",
+            Some(Value::String(method.to_string())),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -246,11 +292,20 @@ mod tests {
         assert_eq!(trace_not_found.message, "Trace not found");
         assert!(trace_not_found.data.is_none());
 
+        let span_not_found = JsonRpcError::span_not_found("1:100:7");
+        assert_eq!(span_not_found.code, -32000);
+        assert_eq!(span_not_found.message, "Span not found");
+        assert_eq!(span_not_found.data, Some(Value::String("1:100:7".into())));
+
         let rate_limited = JsonRpcError::rate_limited();
-        assert_eq!(rate_limited.code, -32001);
-        assert_eq!(rate_limited.message, "Too many requests");
+        assert_eq!(rate_limited.code, -32000);
+        assert_eq!(rate_limited.message, "Rate limited");
         assert!(rate_limited.data.is_none());
 
+        let retry = JsonRpcError::rate_limited_retry_after(std::time::Duration::from_millis(250));
+        assert_eq!(retry.code, -32000);
+        assert_eq!(retry.data, Some(json!({ "retryAfterMs": 250 })));
+
         let too_many_connections = JsonRpcError::too_many_connections();
         assert_eq!(too_many_connections.code, -32002);
         assert_eq!(
@@ -258,6 +313,11 @@ mod tests {
             "Too many concurrent connections"
         );
         assert!(too_many_connections.data.is_none());
+
+        let unauthorized = JsonRpcError::unauthorized();
+        assert_eq!(unauthorized.code, -32000);
+        assert_eq!(unauthorized.message, "Unauthorized");
+        assert!(unauthorized.data.is_none());
     }
 
     #[test]
@@ -278,4 +338,23 @@ mod tests {
             serde_json::from_str(notification).expect("valid notification");
         assert!(parsed_notification.id.is_none());
     }
+
+    #[test]
+    fn json_rpc_error_with_request_id__each_data_shape__then_request_id_added() {
+        let string = JsonRpcError::invalid_params("bad").with_request_id(7);
+        assert_eq!(
+            string.data,
+            Some(json!({ "detail": "bad", "requestId": 7 }))
+        );
+
+        let object = JsonRpcError::rate_limited_retry_after(std::time::Duration::from_millis(5))
+            .with_request_id(8);
+        assert_eq!(
+            object.data,
+            Some(json!({ "retryAfterMs": 5, "requestId": 8 }))
+        );
+
+        let empty = JsonRpcError::trace_not_found().with_request_id(9);
+        assert_eq!(empty.data, Some(json!({ "requestId": 9 })));
+    }
 }
@@ -1,172 +1,37 @@
 #![allow(non_snake_case)]
 
-use std::{fs::File, io::Write, path::PathBuf};
+use std::path::PathBuf;
 
-use prost::Message;
 use query_engine::{
-    atf::event::{
-        event::Payload, Event, FunctionCall, FunctionReturn, SignalDelivery, TraceEnd, TraceStart,
-    },
+    atf::v2::IndexEvent,
     handlers::{
         events::{EventsGetHandler, EventsGetResponse},
         spans::{SpansListHandler, SpansListResponse},
+        test_support::{exception_event, function_call_event, function_return_event, TraceFixture},
     },
     server::{handler::JsonRpcHandler, JsonRpcServer},
 };
 use serde_json::json;
 use tempfile::TempDir;
 
-fn timestamp(ts: u64) -> prost_types::Timestamp {
-    prost_types::Timestamp {
-        seconds: (ts / 1_000_000_000) as i64,
-        nanos: (ts % 1_000_000_000) as i32,
-    }
-}
-
-fn trace_start_event(timestamp_ns: u64, thread_id: i32) -> Event {
-    Event {
-        event_id: timestamp_ns,
-        thread_id,
-        timestamp: Some(timestamp(timestamp_ns)),
-        payload: Some(Payload::TraceStart(TraceStart {
-            executable_path: "/bin/demo".into(),
-            args: vec!["--test".into()],
-            operating_system: "linux".into(),
-            cpu_architecture: "x86_64".into(),
-        })),
-    }
-}
-
-fn trace_end_event(timestamp_ns: u64, thread_id: i32) -> Event {
-    Event {
-        event_id: timestamp_ns,
-        thread_id,
-        timestamp: Some(timestamp(timestamp_ns)),
-        payload: Some(Payload::TraceEnd(TraceEnd { exit_code: 0 })),
-    }
-}
-
-fn function_call_event(timestamp_ns: u64, thread_id: i32, symbol: &str) -> Event {
-    Event {
-        event_id: timestamp_ns,
-        thread_id,
-        timestamp: Some(timestamp(timestamp_ns)),
-        payload: Some(Payload::FunctionCall(FunctionCall {
-            symbol: symbol.to_string(),
-            address: 0,
-            argument_registers: Default::default(),
-            stack_shallow_copy: Vec::new(),
-        })),
-    }
-}
-
-fn function_return_event(timestamp_ns: u64, thread_id: i32, symbol: &str) -> Event {
-    Event {
-        event_id: timestamp_ns,
-        thread_id,
-        timestamp: Some(timestamp(timestamp_ns)),
-        payload: Some(Payload::FunctionReturn(FunctionReturn {
-            symbol: symbol.to_string(),
-            address: 0,
-            return_registers: Default::default(),
-        })),
-    }
-}
-
-fn signal_delivery_event(timestamp_ns: u64, thread_id: i32, name: &str) -> Event {
-    Event {
-        event_id: timestamp_ns,
-        thread_id,
-        timestamp: Some(timestamp(timestamp_ns)),
-        payload: Some(Payload::SignalDelivery(SignalDelivery {
-            number: 9,
-            name: name.to_string(),
-            registers: Default::default(),
-        })),
-    }
-}
-
-fn unknown_event(timestamp_ns: u64, thread_id: i32) -> Event {
-    Event {
-        event_id: timestamp_ns,
-        thread_id,
-        timestamp: Some(timestamp(timestamp_ns)),
-        payload: None,
-    }
-}
-
-struct TraceFixture {
-    root: TempDir,
-    trace_id: String,
-}
-
-impl TraceFixture {
-    fn new(trace_id: impl Into<String>) -> Self {
-        let root = TempDir::new().expect("tempdir");
-        let trace_id = trace_id.into();
-        std::fs::create_dir_all(root.path().join(&trace_id)).expect("trace dir");
-        Self { root, trace_id }
-    }
-
-    fn trace_root(&self) -> PathBuf {
-        self.root.path().to_path_buf()
-    }
-
-    fn trace_dir(&self) -> PathBuf {
-        self.root.path().join(&self.trace_id)
-    }
-
-    fn manifest_path(&self) -> PathBuf {
-        self.trace_dir().join("trace.json")
-    }
-
-    fn events_path(&self) -> PathBuf {
-        self.trace_dir().join("events.bin")
-    }
-
-    fn write_manifest(&self, event_count: u64) {
-        let manifest = json!({
-            "os": "linux",
-            "arch": "x86_64",
-            "pid": 1,
-            "sessionId": 1,
-            "timeStartNs": 100,
-            "timeEndNs": 10_000,
-            "eventCount": event_count,
-            "bytesWritten": 1024,
-        });
-        std::fs::write(
-            self.manifest_path(),
-            serde_json::to_vec_pretty(&manifest).expect("serialize"),
-        )
-        .expect("write manifest");
-    }
+const FOO: u64 = 0x100;
+const BAR: u64 = 0x200;
+const QUX: u64 = 0x300;
+const LONELY: u64 = 0x400;
+const OPEN: u64 = 0x500;
 
-    fn write_events(&self, events: &[Event]) {
-        let mut file = File::create(self.events_path()).expect("events file");
-        for event in events {
-            let mut buffer = Vec::new();
-            event
-                .encode_length_delimited(&mut buffer)
-                .expect("encode event");
-            file.write_all(&buffer).expect("write event");
-        }
-        file.flush().expect("flush events");
-    }
-}
-
-fn standard_events() -> Vec<Event> {
+fn standard_events() -> Vec<IndexEvent> {
     vec![
-        trace_start_event(100, 1),
-        function_return_event(150, 3, "lonely"),
-        function_call_event(200, 1, "foo"),
-        function_call_event(250, 1, "bar"),
-        function_return_event(300, 1, "bar"),
-        function_return_event(400, 1, "foo"),
-        function_call_event(450, 2, "qux"),
-        function_return_event(650, 2, "qux"),
-        function_call_event(700, 1, ""),
-        trace_end_event(900, 1),
+        exception_event(100, 1),
+        function_return_event(150, 3, LONELY),
+        function_call_event(200, 1, FOO),
+        function_call_event(250, 1, BAR),
+        function_return_event(300, 1, BAR),
+        function_return_event(400, 1, FOO),
+        function_call_event(450, 2, QUX),
+        function_return_event(650, 2, QUX),
+        function_call_event(700, 1, OPEN),
+        exception_event(900, 1),
     ]
 }
 
@@ -174,7 +39,6 @@ fn standard_events() -> Vec<Event> {
 async fn events_handler__filters_and_projection__then_returns_expected_events() {
     let fixture = TraceFixture::new("trace_events");
     let events = standard_events();
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -185,7 +49,7 @@ async fn events_handler__filters_and_projection__then_returns_expected_events()
             "threadIds": [1],
             "timeStartNs": 150,
             "timeEndNs": 450,
-            "functionNames": ["foo"]
+            "functionNames": ["0x100"]
         },
         "projection": {
             "functionName": true
@@ -202,7 +66,7 @@ async fn events_handler__filters_and_projection__then_returns_expected_events()
     let response: EventsGetResponse = serde_json::from_value(value).expect("decode response");
     assert_eq!(response.events.len(), 1);
     let event = &response.events[0];
-    assert_eq!(event.function_name.as_deref(), Some("foo"));
+    assert_eq!(event.function_name.as_deref(), Some("0x100"));
     assert_eq!(event.event_type.as_deref(), Some("FunctionCall"));
     assert_eq!(event.thread_id, Some(1));
     assert_eq!(event.timestamp_ns, Some(200));
@@ -226,20 +90,17 @@ async fn events_handler__register__then_handler_available() {
 async fn events_handler__event_type_filters__then_selects_each_variant() {
     let fixture = TraceFixture::new("trace_event_types");
     let events = vec![
-        signal_delivery_event(500, 1, "SIGUSR1"),
-        trace_start_event(100, 1),
-        function_return_event(450, 1, "end_symbol"),
-        trace_end_event(900, 1),
+        exception_event(500, 1),
+        function_call_event(100, 1, FOO),
+        function_return_event(450, 1, FOO),
     ];
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
     let cases = [
-        ("traceStart", "TraceStart"),
-        ("traceEnd", "TraceEnd"),
+        ("functionCall", "FunctionCall"),
         ("functionReturn", "FunctionReturn"),
-        ("signalDelivery", "SignalDelivery"),
+        ("unknown", "Unknown"),
     ];
 
     for (event_type, expected_label) in cases {
@@ -266,11 +127,10 @@ async fn events_handler__event_type_filters__then_selects_each_variant() {
 async fn events_handler__order_by_timestamp__then_applies_sorting() {
     let fixture = TraceFixture::new("trace_order_timestamp");
     let events = vec![
-        function_call_event(500, 2, "later"),
-        function_call_event(150, 2, "earlier"),
-        function_call_event(300, 2, "middle"),
+        function_call_event(500, 2, 0x30),
+        function_call_event(150, 2, 0x10),
+        function_call_event(300, 2, 0x20),
     ];
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -295,14 +155,13 @@ async fn events_handler__order_by_timestamp__then_applies_sorting() {
         .iter()
         .map(|event| event.function_name.as_deref().expect("name"))
         .collect();
-    assert_eq!(sequence, ["earlier", "middle", "later"]);
+    assert_eq!(sequence, ["0x10", "0x20", "0x30"]);
 }
 
 #[tokio::test]
 async fn events_handler__projection_all_disabled__then_returns_empty_fields() {
     let fixture = TraceFixture::new("trace_projection_disabled");
-    let events = vec![function_call_event(200, 3, "visible")];
-    fixture.write_manifest(events.len() as u64);
+    let events = vec![function_call_event(200, 3, FOO)];
     fixture.write_events(&events);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -333,19 +192,14 @@ async fn events_handler__projection_all_disabled__then_returns_empty_fields() {
 #[tokio::test]
 async fn events_handler__function_names_filter_missing_symbol__then_excludes_event() {
     let fixture = TraceFixture::new("trace_function_filter");
-    let events = vec![
-        function_call_event(120, 4, ""),
-        function_return_event(220, 4, ""),
-    ];
-    fixture.write_manifest(events.len() as u64);
+    let events = vec![exception_event(120, 4), exception_event(220, 4)];
     fixture.write_events(&events);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
     let params = json!({
         "traceId": "trace_function_filter",
         "filters": {
-            "eventTypes": ["functionCall"],
-            "functionNames": ["foo"]
+            "functionNames": ["0x100"]
         },
         "projection": {
             "functionName": true
@@ -364,8 +218,7 @@ async fn events_handler__function_names_filter_missing_symbol__then_excludes_eve
 async fn events_handler__order_by_thread_descending__then_sorts_results() {
     let fixture = TraceFixture::new("trace_ordering");
     let mut events = standard_events();
-    events.push(function_call_event(500, 3, "zap"));
-    fixture.write_manifest(events.len() as u64);
+    events.push(function_call_event(500, 3, 0x700));
     fixture.write_events(&events);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -383,19 +236,24 @@ async fn events_handler__order_by_thread_descending__then_sorts_results() {
     let response: EventsGetResponse = serde_json::from_value(value).expect("decode");
 
     assert_eq!(response.events.len(), 2);
-    let thread_ids: Vec<_> = response
+    let order: Vec<_> = response
         .events
         .iter()
-        .map(|event| event.thread_id.expect("thread"))
+        .map(|event| {
+            (
+                event.thread_id.expect("thread"),
+                event.timestamp_ns.expect("timestamp"),
+            )
+        })
         .collect();
-    assert!(thread_ids[0] >= thread_ids[1]);
+    // Descending by thread, then by timestamp within a thread.
+    assert_eq!(order, vec![(3, 500), (2, 450)]);
     assert!(response.metadata.has_more);
 }
 
 #[tokio::test]
 async fn events_handler__invalid_event_type__then_error() {
     let fixture = TraceFixture::new("trace_invalid_type");
-    fixture.write_manifest(0);
     fixture.write_events(&[]);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -417,7 +275,6 @@ async fn events_handler__invalid_event_type__then_error() {
 #[tokio::test]
 async fn events_handler__limit_exceeds__then_error() {
     let fixture = TraceFixture::new("trace_limit");
-    fixture.write_manifest(0);
     fixture.write_events(&[]);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -436,7 +293,6 @@ async fn events_handler__limit_exceeds__then_error() {
 #[tokio::test]
 async fn events_handler__time_range_invalid__then_error() {
     let fixture = TraceFixture::new("trace_time");
-    fixture.write_manifest(0);
     fixture.write_events(&[]);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -473,8 +329,7 @@ async fn events_handler__missing_trace__then_not_found() {
 #[tokio::test]
 async fn events_handler__unknown_event_type_filter__then_returns_unknown_events() {
     let fixture = TraceFixture::new("trace_unknown_events");
-    let events = vec![unknown_event(1_000, 7)];
-    fixture.write_manifest(events.len() as u64);
+    let events = vec![exception_event(1_000, 7)];
     fixture.write_events(&events);
 
     let handler = EventsGetHandler::new(fixture.trace_root());
@@ -521,8 +376,8 @@ async fn events_handler__empty_trace_id__then_invalid_params() {
 #[tokio::test]
 async fn events_handler__event_decode_failure__then_internal_error() {
     let fixture = TraceFixture::new("trace_decode_failure");
-    fixture.write_manifest(1);
-    std::fs::write(fixture.events_path(), vec![0xFF, 0xFF]).expect("write invalid bytes");
+    fixture.write_events(&[function_call_event(100, 1, FOO)]);
+    std::fs::write(fixture.index_path(1), vec![0xFF, 0xFF]).expect("write invalid bytes");
 
     let handler = EventsGetHandler::new(fixture.trace_root());
     let params = json!({
@@ -543,7 +398,6 @@ async fn events_handler__event_decode_failure__then_internal_error() {
 async fn spans_handler__include_children_false__then_filters_nested() {
     let fixture = TraceFixture::new("trace_spans_children");
     let events = standard_events();
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -566,6 +420,36 @@ async fn spans_handler__include_children_false__then_filters_nested() {
     assert_eq!(response.spans[1].depth, Some(0));
 }
 
+#[tokio::test]
+async fn spans_handler__self_duration_projection__then_excludes_child_time() {
+    let fixture = TraceFixture::new("trace_spans_self_time");
+    let events = standard_events();
+    fixture.write_events(&events);
+
+    let handler = SpansListHandler::new(fixture.trace_root());
+    let params = json!({
+        "traceId": "trace_spans_self_time",
+        "filters": { "threadIds": [1] },
+        "projection": { "selfDurationNs": true }
+    });
+
+    let value = handler.call(Some(params)).await.expect("handler");
+    let response: SpansListResponse = serde_json::from_value(value).expect("decode");
+
+    let span = |name: &str| {
+        response
+            .spans
+            .iter()
+            .find(|span| span.function_name.as_deref() == Some(name))
+            .expect("span")
+    };
+    let (foo, bar) = (span("0x100"), span("0x200"));
+    assert_eq!(foo.duration_ns, Some(200));
+    assert_eq!(bar.duration_ns, Some(50));
+    assert_eq!(foo.self_duration_ns, Some(200 - 50));
+    assert_eq!(bar.self_duration_ns, bar.duration_ns);
+}
+
 #[tokio::test]
 async fn spans_handler__register__then_handler_available() {
     let fixture = TraceFixture::new("trace_spans_register");
@@ -581,7 +465,6 @@ async fn spans_handler__register__then_handler_available() {
 async fn spans_handler__filters_by_function_and_duration__then_returns_expected() {
     let fixture = TraceFixture::new("trace_spans_filter");
     let events = standard_events();
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -589,7 +472,7 @@ async fn spans_handler__filters_by_function_and_duration__then_returns_expected(
         "traceId": "trace_spans_filter",
         "filters": {
             "threadIds": [2],
-            "functionNames": ["qux"],
+            "functionNames": ["0x300"],
             "minDurationNs": 150,
             "maxDurationNs": 500,
             "minDepth": 0,
@@ -607,7 +490,7 @@ async fn spans_handler__filters_by_function_and_duration__then_returns_expected(
 
     assert_eq!(response.spans.len(), 1);
     let span = &response.spans[0];
-    assert_eq!(span.function_name.as_deref(), Some("qux"));
+    assert_eq!(span.function_name.as_deref(), Some("0x300"));
     assert_eq!(span.thread_id, Some(2));
     assert_eq!(span.duration_ns, Some(200));
     assert_eq!(span.depth, Some(0));
@@ -617,7 +500,6 @@ async fn spans_handler__filters_by_function_and_duration__then_returns_expected(
 async fn spans_handler__projection_all_disabled__then_returns_empty_fields() {
     let fixture = TraceFixture::new("trace_spans_projection_disabled");
     let events = standard_events();
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -654,7 +536,6 @@ async fn spans_handler__projection_all_disabled__then_returns_empty_fields() {
 async fn spans_handler__filter_thresholds__then_exclude_spans() {
     let fixture = TraceFixture::new("trace_spans_thresholds");
     let events = standard_events();
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -679,7 +560,7 @@ async fn spans_handler__filter_thresholds__then_exclude_spans() {
                     "functionName": true
                 }
             }),
-            vec!["bar"],
+            vec!["0x200"],
             "maxDurationNs",
         ),
         (
@@ -693,7 +574,7 @@ async fn spans_handler__filter_thresholds__then_exclude_spans() {
                     "depth": true
                 }
             }),
-            vec!["bar"],
+            vec!["0x200"],
             "minDepth",
         ),
         (
@@ -707,7 +588,7 @@ async fn spans_handler__filter_thresholds__then_exclude_spans() {
                     "depth": true
                 }
             }),
-            vec!["foo", "qux"],
+            vec!["0x100", "0x300"],
             "maxDepth",
         ),
     ];
@@ -729,7 +610,6 @@ async fn spans_handler__filter_thresholds__then_exclude_spans() {
 #[tokio::test]
 async fn spans_handler__time_range_invalid__then_error() {
     let fixture = TraceFixture::new("trace_spans_time");
-    fixture.write_manifest(0);
     fixture.write_events(&[]);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -751,7 +631,6 @@ async fn spans_handler__time_range_invalid__then_error() {
 #[tokio::test]
 async fn spans_handler__limit_exceeds__then_error() {
     let fixture = TraceFixture::new("trace_spans_limit");
-    fixture.write_manifest(0);
     fixture.write_events(&[]);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -770,7 +649,6 @@ async fn spans_handler__limit_exceeds__then_error() {
 #[tokio::test]
 async fn spans_handler__invalid_filters_type__then_error() {
     let fixture = TraceFixture::new("trace_spans_invalid_filters");
-    fixture.write_manifest(0);
     fixture.write_events(&[]);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -807,9 +685,8 @@ async fn spans_handler__missing_trace__then_not_found() {
 async fn spans_handler__pagination__then_returns_correct_slice() {
     let fixture = TraceFixture::new("trace_spans_pagination");
     let mut events = standard_events();
-    events.push(function_call_event(950, 1, "late"));
-    events.push(function_return_event(1_050, 1, "late"));
-    fixture.write_manifest(events.len() as u64);
+    events.push(function_call_event(950, 1, 0x600));
+    events.push(function_return_event(1_050, 1, 0x600));
     fixture.write_events(&events);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -853,7 +730,6 @@ async fn spans_handler__empty_trace_id__then_invalid_params() {
 #[tokio::test]
 async fn spans_handler__min_depth_exceeds_max__then_invalid_params() {
     let fixture = TraceFixture::new("trace_spans_depth_validation");
-    fixture.write_manifest(0);
     fixture.write_events(&[]);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -880,8 +756,8 @@ async fn spans_handler__min_depth_exceeds_max__then_invalid_params() {
 #[tokio::test]
 async fn spans_handler__event_decode_failure__then_internal_error() {
     let fixture = TraceFixture::new("trace_spans_decode_failure");
-    fixture.write_manifest(1);
-    std::fs::write(fixture.events_path(), vec![0xAA]).expect("write invalid bytes");
+    fixture.write_events(&[function_call_event(100, 1, FOO)]);
+    std::fs::write(fixture.index_path(1), vec![0xAA]).expect("write invalid bytes");
 
     let handler = SpansListHandler::new(fixture.trace_root());
     let params = json!({
@@ -902,7 +778,6 @@ async fn spans_handler__event_decode_failure__then_internal_error() {
 async fn spans_handler__time_filters__then_returns_spans_within_window() {
     let fixture = TraceFixture::new("trace_spans_time_window");
     let events = standard_events();
-    fixture.write_manifest(events.len() as u64);
     fixture.write_events(&events);
 
     let handler = SpansListHandler::new(fixture.trace_root());
@@ -924,25 +799,24 @@ async fn spans_handler__time_filters__then_returns_spans_within_window() {
 
     assert_eq!(response.spans.len(), 1);
     let span = &response.spans[0];
-    assert_eq!(span.function_name.as_deref(), Some("bar"));
+    assert_eq!(span.function_name.as_deref(), Some("0x200"));
     assert_eq!(span.start_time_ns, Some(250));
     assert_eq!(span.end_time_ns, Some(300));
 }
 
 #[tokio::test]
-async fn spans_handler__function_name_absent__then_excluded_by_filter() {
-    let fixture = TraceFixture::new("trace_spans_missing_function");
+async fn spans_handler__function_names_filter__then_excludes_other_functions() {
+    let fixture = TraceFixture::new("trace_spans_function_filter");
     let mut events = standard_events();
-    events.push(function_call_event(1_100, 4, ""));
-    events.push(function_return_event(1_200, 4, ""));
-    fixture.write_manifest(events.len() as u64);
+    events.push(function_call_event(1_100, 4, 0x800));
+    events.push(function_return_event(1_200, 4, 0x800));
     fixture.write_events(&events);
 
     let handler = SpansListHandler::new(fixture.trace_root());
     let params = json!({
-        "traceId": "trace_spans_missing_function",
+        "traceId": "trace_spans_function_filter",
         "filters": {
-            "functionNames": ["qux"]
+            "functionNames": ["0x300"]
         },
         "projection": {
             "functionName": true,
@@ -955,6 +829,6 @@ async fn spans_handler__function_name_absent__then_excluded_by_filter() {
 
     assert_eq!(response.spans.len(), 1);
     let span = &response.spans[0];
-    assert_eq!(span.function_name.as_deref(), Some("qux"));
+    assert_eq!(span.function_name.as_deref(), Some("0x300"));
     assert_eq!(span.thread_id, Some(2));
 }
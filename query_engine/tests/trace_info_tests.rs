@@ -0,0 +1,410 @@
+#![allow(non_snake_case)]
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use query_engine::{
+    atf::AtfError,
+    handlers::test_support::{function_call_event, function_return_event, TraceFixture},
+    handlers::{EventSource, TraceInfoHandler, V2EventSource},
+    server::{handler::JsonRpcHandler, JsonRpcServer, JsonRpcServerConfig},
+};
+use serde_json::json;
+use tokio::time::sleep;
+
+use query_engine::handlers::trace_info::TraceInfoResponse;
+use query_engine::server::types::JsonRpcError;
+
+const FOO: u64 = 0x100;
+const BAR: u64 = 0x200;
+
+#[tokio::test]
+async fn v2_event_source__valid_session__then_manifest_summarized() {
+    let fixture = TraceFixture::new("traceA");
+    fixture.write_events(&[
+        function_call_event(100, 1, FOO),
+        function_return_event(300, 1, FOO),
+        function_call_event(200, 2, BAR),
+        function_return_event(2_100, 2, BAR),
+    ]);
+
+    let source = V2EventSource::open(&fixture.trace_dir()).expect("source");
+    let manifest = source.manifest();
+
+    assert_eq!(manifest.os, "linux");
+    assert_eq!(manifest.arch, "x86_64");
+    assert_eq!(manifest.event_count, 4);
+    assert_eq!(manifest.duration_ns(), 2000);
+
+    let parsed_events = source.events().expect("events").count();
+    assert_eq!(parsed_events, 4);
+}
+
+#[tokio::test]
+async fn v2_event_source__missing_manifest__then_error() {
+    let fixture = TraceFixture::new("traceB");
+    let err = V2EventSource::open(&fixture.trace_dir())
+        .err()
+        .expect("expected error");
+    match err {
+        AtfError::ManifestNotFound(path) => assert!(path.ends_with("manifest.json")),
+        other => panic!("unexpected error variant: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn trace_info_handler__base_request__then_returns_metadata() {
+    let fixture = TraceFixture::new("traceC");
+    let events = vec![
+        function_call_event(100, 1, FOO),
+        function_return_event(200, 1, FOO),
+    ];
+    fixture.write_events(&events);
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 4, Duration::from_secs(60));
+    let response_value = handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect("handler response");
+
+    let response: TraceInfoResponse = serde_json::from_value(response_value).expect("deserialize");
+    assert_eq!(response.trace_id, fixture.trace_id());
+    assert_eq!(response.event_count, 2);
+    assert_eq!(response.span_count, 1);
+    assert_eq!(response.duration_ns, 100);
+    assert!(response.checksums.is_none());
+    assert!(response.samples.is_none());
+}
+
+#[tokio::test]
+async fn trace_info_handler__include_checksums_and_samples__then_returns_optional_fields() {
+    let fixture = TraceFixture::new("traceD");
+    let events = vec![
+        function_call_event(100, 1, FOO),
+        function_return_event(200, 1, FOO),
+        function_call_event(300, 2, BAR),
+        function_return_event(400, 2, BAR),
+    ];
+    fixture.write_events(&events);
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 4, Duration::from_secs(60));
+    let params = json!({
+        "traceId": fixture.trace_id(),
+        "include_checksums": true,
+        "include_samples": true,
+    });
+    let response_value = handler.call(Some(params)).await.expect("response");
+    let response: TraceInfoResponse = serde_json::from_value(response_value).expect("deserialize");
+
+    let checksums = response.checksums.expect("checksums present");
+    assert_eq!(checksums.manifest_md5.len(), 32);
+    assert_eq!(checksums.events_md5.len(), 32);
+
+    let samples = response.samples.expect("samples present");
+    assert!(!samples.first_events.is_empty());
+    assert!(!samples.last_events.is_empty());
+}
+
+#[tokio::test]
+async fn trace_info_handler__cache_hit__then_latency_under_target() {
+    let fixture = TraceFixture::new("traceE");
+    let events = vec![function_call_event(100, 1, FOO)];
+    fixture.write_events(&events);
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 1, Duration::from_secs(60));
+    handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect("priming call");
+
+    let start = Instant::now();
+    handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect("cached call");
+    let elapsed = start.elapsed();
+
+    assert!(elapsed < Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn trace_info_handler__ttl_expired__then_reloads_manifest() {
+    let fixture = TraceFixture::new("traceF");
+    fixture.write_events(&[
+        function_call_event(100, 1, FOO),
+        function_return_event(200, 1, FOO),
+    ]);
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_millis(10));
+    handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect("first call");
+
+    sleep(Duration::from_millis(15)).await;
+
+    fixture.write_events(&[
+        function_call_event(100, 1, FOO),
+        function_return_event(200, 1, FOO),
+        function_call_event(300, 2, BAR),
+        function_return_event(400, 2, BAR),
+    ]);
+
+    let response_value = handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect("reloaded");
+    let response: TraceInfoResponse = serde_json::from_value(response_value).expect("deserialize");
+    assert_eq!(response.event_count, 4);
+    assert_eq!(response.span_count, 2);
+}
+
+#[tokio::test]
+async fn trace_info_handler__missing_trace__then_error() {
+    let fixture = TraceFixture::new("traceG");
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 1, Duration::from_secs(60));
+    let err = handler
+        .call(Some(json!({"traceId": "unknown"})))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, JsonRpcError::trace_not_found().code);
+}
+
+#[tokio::test]
+async fn trace_info_handler__include_samples_without_events__then_returns_empty_samples() {
+    let fixture = TraceFixture::new("traceH");
+    fixture.write_events(&[]);
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
+    let value = handler
+        .call(Some(json!({
+            "traceId": fixture.trace_id(),
+            "include_samples": true
+        })))
+        .await
+        .expect("response");
+    let response: TraceInfoResponse = serde_json::from_value(value).expect("deserialize");
+    let samples = response.samples.expect("samples present");
+    assert!(samples.first_events.is_empty());
+    assert!(samples.last_events.is_empty());
+}
+
+#[tokio::test]
+async fn trace_info_handler__checksums_missing_events__then_internal_error() {
+    let fixture = TraceFixture::new("traceI");
+    fixture.write_events(&[function_call_event(100, 1, FOO)]);
+    fs::remove_file(fixture.index_path(1)).expect("remove index");
+    fs::create_dir(fixture.index_path(1)).expect("index directory");
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
+    let err = handler
+        .call(Some(json!({
+            "traceId": fixture.trace_id(),
+            "include_checksums": true
+        })))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, -32603);
+}
+
+#[tokio::test]
+async fn json_rpc_server__trace_info_handler_registered__then_serves_request() {
+    let fixture = TraceFixture::new("traceJ");
+    let events = vec![function_call_event(100, 1, FOO)];
+    fixture.write_events(&events);
+
+    let server = JsonRpcServer::with_config(JsonRpcServerConfig::default());
+    TraceInfoHandler::new(fixture.trace_root(), 4, Duration::from_secs(60)).register(&server);
+
+    let registry = server.handler_registry();
+    let response = registry
+        .call(
+            "trace.info",
+            Some(json!({
+                "traceId": fixture.trace_id(),
+                "include_samples": false
+            })),
+        )
+        .await
+        .expect("rpc response");
+
+    let parsed: TraceInfoResponse = serde_json::from_value(response).expect("deserialize");
+    assert_eq!(parsed.event_count, 1);
+}
+
+#[tokio::test]
+async fn trace_info_handler__cache_disabled__then_reloads_after_manifest_change() {
+    let fixture = TraceFixture::new("traceK");
+    fixture.write_events(&[function_call_event(100, 1, FOO)]);
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 0, Duration::from_secs(120));
+
+    let first: TraceInfoResponse = serde_json::from_value(
+        handler
+            .call(Some(json!({"traceId": fixture.trace_id()})))
+            .await
+            .expect("first response"),
+    )
+    .expect("deserialize");
+    assert_eq!(first.event_count, 1);
+
+    sleep(Duration::from_millis(20)).await;
+    fixture.write_events(&[
+        function_call_event(100, 1, FOO),
+        function_return_event(200, 1, FOO),
+        function_call_event(300, 2, BAR),
+        function_return_event(400, 2, BAR),
+    ]);
+
+    let second: TraceInfoResponse = serde_json::from_value(
+        handler
+            .call(Some(json!({"traceId": fixture.trace_id()})))
+            .await
+            .expect("second response"),
+    )
+    .expect("deserialize");
+    assert_eq!(second.event_count, 4);
+    assert_eq!(second.span_count, 2);
+}
+
+#[tokio::test]
+async fn trace_info_handler__blank_trace_id__then_invalid_params() {
+    let fixture = TraceFixture::new("traceL");
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 4, Duration::from_secs(60));
+
+    let err = handler
+        .call(Some(json!({"traceId": "   "})))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, JsonRpcError::invalid_params("_").code);
+    assert_eq!(err.message, "Invalid params");
+    let detail = err
+        .data
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .expect("detail");
+    assert!(detail.contains("traceId must not be empty"));
+}
+
+#[tokio::test]
+async fn trace_info_handler__invalid_params_type__then_invalid_params() {
+    let fixture = TraceFixture::new("traceM");
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 4, Duration::from_secs(60));
+
+    let err = handler
+        .call(Some(json!({"traceId": 42})))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, JsonRpcError::invalid_params("_").code);
+    assert_eq!(err.message, "Invalid params");
+    let detail = err
+        .data
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .expect("detail");
+    assert!(detail.contains("invalid trace.info parameters"));
+}
+
+#[tokio::test]
+async fn trace_info_handler__missing_manifest_metadata__then_trace_not_found() {
+    let fixture = TraceFixture::new("traceN");
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
+
+    let err = handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, JsonRpcError::trace_not_found().code);
+}
+
+#[tokio::test]
+async fn trace_info_handler__thread_stream_missing__then_counts_remaining_threads() {
+    let fixture = TraceFixture::new("traceO");
+    fixture.write_events(&[
+        function_call_event(100, 1, FOO),
+        function_return_event(200, 1, FOO),
+        function_call_event(300, 2, BAR),
+        function_return_event(400, 2, BAR),
+    ]);
+    fs::remove_dir_all(fixture.index_path(2).parent().unwrap()).expect("remove thread dir");
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
+    let value = handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect("response");
+    let response: TraceInfoResponse = serde_json::from_value(value).expect("deserialize");
+    assert_eq!(response.event_count, 2);
+    assert_eq!(response.span_count, 1);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn trace_info_handler__metadata_permission_denied__then_internal_error() {
+    let fixture = TraceFixture::new("traceP");
+    fixture.write_events(&[function_call_event(100, 1, FOO)]);
+
+    let trace_dir = fixture.trace_dir();
+    fs::set_permissions(&trace_dir, fs::Permissions::from_mode(0o000)).expect("chmod");
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
+    let err = handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, JsonRpcError::internal("_").code);
+    assert_eq!(err.message, "Internal error");
+    let detail = err
+        .data
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .expect("detail");
+    assert!(detail.contains("failed to read manifest metadata"));
+
+    fs::set_permissions(&trace_dir, fs::Permissions::from_mode(0o700)).expect("restore perms");
+}
+
+#[tokio::test]
+async fn trace_info_handler__invalid_manifest_payload__then_internal_error() {
+    let fixture = TraceFixture::new("traceQ");
+    fixture.write_events(&[function_call_event(100, 1, FOO)]);
+    fs::write(fixture.manifest_path(), b"").expect("invalid manifest");
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
+    let err = handler
+        .call(Some(json!({"traceId": fixture.trace_id()})))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, JsonRpcError::internal("_").code);
+    assert_eq!(err.message, "Internal error");
+    let detail = err
+        .data
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .expect("detail");
+    assert!(detail.contains("failed to parse manifest"));
+}
+
+#[tokio::test]
+async fn trace_info_handler__invalid_event_payload__then_decode_error() {
+    let fixture = TraceFixture::new("traceR");
+    fixture.write_events(&[function_call_event(100, 1, FOO)]);
+    fs::write(fixture.index_path(1), [0xAA, 0xBB, 0xCC]).expect("invalid events");
+
+    let handler = TraceInfoHandler::new(fixture.trace_root(), 2, Duration::from_secs(60));
+    let err = handler
+        .call(Some(json!({
+            "traceId": fixture.trace_id(),
+            "include_samples": true
+        })))
+        .await
+        .expect_err("expected error");
+    assert_eq!(err.code, JsonRpcError::internal("_").code);
+    assert_eq!(err.message, "Internal error");
+    let detail = err
+        .data
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .expect("detail");
+    assert!(detail.contains("failed to decode events"));
+}
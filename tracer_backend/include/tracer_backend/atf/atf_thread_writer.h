@@ -63,6 +63,32 @@ uint32_t atf_thread_writer_write_event(AtfThreadWriter* writer,
  */
 int atf_thread_writer_finalize(AtfThreadWriter* writer);
 
+/**
+ * Flush both files and fsync them to disk
+ *
+ * Events written so far become durable, but headers and footers are only
+ * completed by atf_thread_writer_finalize(), so readers of a live session
+ * must rely on the file length rather than the header counts.
+ *
+ * @param writer Pointer to writer
+ * @return 0 on success, negative errno on error
+ */
+int atf_thread_writer_sync(AtfThreadWriter* writer);
+
+/**
+ * Write finalized copies of both files into another session directory
+ *
+ * Creates <session_dir>/thread_<id>/index.atf, plus detail.atf when detail
+ * was recorded, holding every event written so far with the headers and
+ * footers finalize would produce. The live files are not modified, so the
+ * writer can keep appending afterwards.
+ *
+ * @param writer Pointer to writer
+ * @param session_dir Destination session directory
+ * @return 0 on success, negative errno on error
+ */
+int atf_thread_writer_snapshot(AtfThreadWriter* writer, const char* session_dir);
+
 /**
  * Close and free the thread writer
  *
@@ -13,6 +13,10 @@ typedef struct FridaController FridaController;
 
 // Controller lifecycle
 FridaController* frida_controller_create(const char* output_dir);
+// Like frida_controller_create, but with shared memory named after a session
+// id of its own instead of the process-wide one, so several controllers can
+// exist in one process (e.g. tests running in parallel)
+FridaController* frida_controller_create_isolated(const char* output_dir);
 void frida_controller_destroy(FridaController* controller);
 
 // Process management
@@ -25,6 +29,25 @@ int frida_controller_detach(FridaController* controller);
 int frida_controller_resume(FridaController* controller);
 int frida_controller_pause(FridaController* controller);
 
+/**
+ * @brief Return the controller to PROCESS_STATE_INITIALIZED for a new target
+ *
+ * @param controller the FridaController instance
+ * @param output_dir new output root for later sessions, or NULL to keep it
+ * @return int 0 on success, -1 on failure
+ *
+ * Allowed only in PROCESS_STATE_INITIALIZED (after a detach or the target's
+ * exit) and PROCESS_STATE_FAILED; any other state, including RUNNING,
+ * returns -1 and leaves the controller untouched. An open ATF session is
+ * finalized, the Frida session released, and the shared memory recreated
+ * under a new session id so an agent still loaded in the previous target
+ * cannot write into the next trace. Lane, stack capture, pre/post-roll and
+ * drop policy settings carry over; statistics restart from zero. If the
+ * shared memory cannot be recreated the controller is left in
+ * PROCESS_STATE_FAILED and should be destroyed.
+ */
+int frida_controller_reset(FridaController* controller, const char* output_dir);
+
 /**
  * @brief Install hooks into the target process
  * 
@@ -56,15 +79,56 @@ int frida_controller_arm_trigger(FridaController* controller,
 int frida_controller_fire_trigger(FridaController* controller);
 int frida_controller_disarm_trigger(FridaController* controller);
 int frida_controller_set_detail_enabled(FridaController* controller, uint32_t enabled);
+// Select how agents handle a lane with no free ring (see DropPolicy).
+// Takes effect on the next overflow and is recorded in the session manifest.
+int frida_controller_set_drop_policy(FridaController* controller, DropPolicy policy);
+// Enumerate the target's registered threads. Fills up to capacity entries of
+// out (NULL is allowed when capacity is 0) and stores the total number of
+// threads in *out_count, so callers can size a buffer and call again. Threads
+// that exited stay listed until their registry slot is reused.
+int frida_controller_list_threads(FridaController* controller,
+                                  TracedThreadInfo* out,
+                                  uint32_t capacity,
+                                  uint32_t* out_count);
+// Per-thread counters for the threads frida_controller_list_threads reports,
+// filled with the same sizing protocol. The process-wide view stays in
+// frida_controller_get_stats.
+int frida_controller_get_thread_stats(FridaController* controller,
+                                      TracedThreadStats* out,
+                                      uint32_t capacity,
+                                      uint32_t* out_count);
+// Trace child processes spawned or exec'd by the target. Each child gets a
+// sub-session under <session>/children/ that the parent manifest links; a
+// plain fork() without exec stays in the parent's session. Set before attach,
+// or while attached to start following from the next child.
+int frida_controller_set_follow_children(FridaController* controller, uint32_t enabled);
 int frida_controller_start_session(FridaController* controller);
 int frida_controller_stop_session(FridaController* controller);
+// Drain buffered events to the session files and fsync them, so a concurrent
+// reader sees everything captured so far. Blocks until the drain thread has
+// served the request; bytes_written in the stats then covers those events.
+int frida_controller_flush(FridaController* controller);
+// Flush, then copy everything captured so far into out_dir as a finalized,
+// self-contained trace with its own manifest. Capture continues untouched.
+// Fails if out_dir already holds a manifest or is the session directory.
+int frida_controller_snapshot(FridaController* controller, const char* out_dir);
 
 // Statistics
 TracerStats frida_controller_get_stats(FridaController* controller);
+// Restart events_captured, events_dropped and bytes_written from zero so
+// later reads cover only the window since the call. Instantaneous fields
+// (active_threads, hooks_installed) and per-thread stats are unaffected.
+int frida_controller_reset_stats(FridaController* controller);
 
 // State query
 ProcessState frida_controller_get_state(FridaController* controller);
 FlightRecorderState frida_controller_get_flight_state(FridaController* controller);
+// Copy the reason recorded when the controller entered PROCESS_STATE_FAILED
+// into out as a NUL-terminated string, truncated to capacity. Returns the
+// full length of the reason, so a return >= capacity means it was cut short;
+// 0 when not failed or no reason was recorded, -1 on invalid arguments.
+int frida_controller_get_failure_reason(FridaController* controller, char* out,
+                                        size_t capacity);
 
 #ifdef __cplusplus
 }
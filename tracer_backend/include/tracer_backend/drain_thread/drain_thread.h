@@ -95,9 +95,43 @@ void drain_thread_get_metrics(const DrainThread* drain, DrainMetrics* out_metric
 int drain_thread_update_config(DrainThread* drain, const DrainConfig* config);
 
 // Session management for ATF V2
+// Starts writing a session into session_dir. While the session is active the
+// drain holds an exclusive flock() on <session_dir>/.lock; readers probe it
+// with a shared lock to detect a capture in progress. Returns -EBUSY if
+// another writer already holds the lock.
 int drain_thread_start_session(DrainThread* drain, const char* session_dir);
+// Finalizes the session and writes <session_dir>/manifest.json. The manifest
+// is published atomically (written to manifest.json.tmp, then renamed), so a
+// concurrent reader never observes a partially written manifest. The session
+// lock is released once the manifest and all thread files are finalized.
+// The manifest's "drop_policy" is read from the control block, if one is set.
 int drain_thread_stop_session(DrainThread* drain);
 
+// Links a followed child's sub-session from the active session's manifest,
+// under "children" as {"pid": ..., "dir": ...}. relative_dir is relative to
+// the session directory (e.g. "children/pid_1234"). Returns -EINVAL without
+// an active session or for a directory longer than 255 bytes, -ENOMEM if the
+// list cannot grow.
+int drain_thread_add_child_session(DrainThread* drain, uint32_t pid, const char* relative_dir);
+
+// Drains every lane to the session's thread files and fsyncs them, so a
+// concurrent reader sees every event captured before the call. The running
+// worker serves the request and the caller waits for it; without a worker the
+// flush runs on the caller's thread. Returns 0 on success, -ECANCELED if the
+// worker is shutting down, -ETIMEDOUT if it does not respond within 5s, or
+// a negative errno from fsync. Header and footer counts are only written
+// when the session stops.
+int drain_thread_flush(DrainThread* drain);
+
+// Flushes like drain_thread_flush, then copies every event captured so far
+// into out_dir as a finalized session: thread files with complete headers
+// and footers plus a manifest.json, so the copy is a valid trace on its own.
+// The live session keeps capturing untouched. Children are not linked, as
+// their directories are not copied. Returns -EINVAL without an active
+// session or when out_dir is the session directory itself, -EEXIST if
+// out_dir already holds a manifest, or any error drain_thread_flush returns.
+int drain_thread_snapshot(DrainThread* drain, const char* out_dir);
+
 // Symbol table persistence for manifest (Phase 1)
 // Set the JSON string containing modules and symbols to be included in manifest.
 // The drain thread takes ownership of a copy of the string.
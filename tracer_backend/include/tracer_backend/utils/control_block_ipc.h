@@ -82,6 +82,14 @@ static inline uint64_t cb_get_fallback_events(ControlBlock* cb) {
     return __atomic_load_n(&cb->fallback_events, __ATOMIC_ACQUIRE);
 }
 
+static inline void cb_set_drop_policy(ControlBlock* cb, uint32_t policy) {
+    __atomic_store_n(&cb->drop_policy, policy, __ATOMIC_RELEASE);
+}
+
+static inline uint32_t cb_get_drop_policy(ControlBlock* cb) {
+    return __atomic_load_n(&cb->drop_policy, __ATOMIC_ACQUIRE);
+}
+
 #ifdef __cplusplus
 }
 #endif
@@ -24,8 +24,14 @@ void ring_pool_destroy(RingPool* pool);
 // Atomically swap out the active ring and submit it for draining.
 // On success, stores the old ring index into out_old_idx if non-NULL.
 // Returns true on success, false when no free ring is available (pool exhaustion).
+// Under DROP_POLICY_DROP_OLDEST an exhausted pool reclaims its oldest submitted
+// ring first; the other policies leave submitted rings for the drain.
 bool ring_pool_swap_active(RingPool* pool, uint32_t* out_old_idx);
 
+// Select how the pool behaves on exhaustion. Defaults to DROP_POLICY_DROP_OLDEST.
+void ring_pool_set_drop_policy(RingPool* pool, DropPolicy policy);
+DropPolicy ring_pool_get_drop_policy(RingPool* pool);
+
 // Get the header of the currently active ring for this pool.
 // Returns NULL on error.
 RingBufferHeader* ring_pool_get_active_header(RingPool* pool);
@@ -118,6 +118,12 @@ uint32_t thread_registry_get_active_count(ThreadRegistry* registry);
 // Returns: ThreadLaneSet pointer if active, NULL if inactive
 ThreadLaneSet* thread_registry_get_thread_at(ThreadRegistry* registry, uint32_t index);
 
+// Get thread lane set by slot, active or not (for listing exited threads)
+// registry: ThreadRegistry
+// index: slot index (0 to capacity-1)
+// Returns: ThreadLaneSet pointer, or NULL if the slot never held a thread
+ThreadLaneSet* thread_registry_get_slot(ThreadRegistry* registry, uint32_t index);
+
 // Get configured runtime capacity (pressure cap) for this registry
 uint32_t thread_registry_get_capacity(ThreadRegistry* registry);
 
@@ -147,6 +153,15 @@ ada_thread_metrics_t* thread_lanes_get_metrics(ThreadLaneSet* lanes);
 uint32_t thread_lanes_get_slot_index(ThreadLaneSet* lanes);
 uint64_t thread_lanes_get_thread_id(ThreadLaneSet* lanes);
 
+bool thread_lanes_is_active(ThreadLaneSet* lanes);
+
+// Record the thread's name, truncated to ADA_THREAD_NAME_MAX - 1 bytes.
+// Readers in another process may observe a name mid-update; best effort.
+void thread_lanes_set_name(ThreadLaneSet* lanes, const char* name);
+
+// Copy the recorded name into out (NUL-terminated, at most capacity bytes)
+void thread_lanes_get_name(ThreadLaneSet* lanes, char* out, size_t capacity);
+
 // Set active status for a thread lane set
 // lanes: ThreadLaneSet pointer
 // active: true if active, false if inactive
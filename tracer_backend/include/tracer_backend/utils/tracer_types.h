@@ -55,6 +55,13 @@ typedef enum {
     FLIGHT_RECORDER_POST_ROLL
 } FlightRecorderState;
 
+// What a producer does when its lane has no free ring to swap to
+typedef enum {
+    DROP_POLICY_DROP_OLDEST = 0,    // Reclaim the oldest submitted ring (default)
+    DROP_POLICY_DROP_NEWEST = 1,    // Keep submitted rings, drop the new event
+    DROP_POLICY_BLOCK_PRODUCER = 2  // Stall the traced thread until a ring frees up
+} DropPolicy;
+
 // Compact index event (32 bytes)
 typedef struct __attribute__((packed)) {
     uint64_t timestamp;      // Monotonic timestamp
@@ -149,6 +156,7 @@ typedef struct {
     uint32_t index_lane_enabled;
     uint32_t detail_lane_enabled;
     uint32_t capture_stack_snapshot;  // Enable 128-byte stack capture
+    uint32_t drop_policy;             // DropPolicy; access with atomic operations
     uint32_t hooks_ready;             // Agent sets to 1 when hooks are installed
     uint32_t actual_hook_count;       // Agent sets to actual hookable symbol count
 
@@ -179,6 +187,31 @@ typedef struct {
 } TracerStats;
 #endif
 
+// Live thread enumeration (see frida_controller_list_threads)
+#define ADA_THREAD_NAME_MAX 16
+
+typedef enum {
+    TRACED_THREAD_RUNNING = 0,  // Registered and writing to its lanes
+    TRACED_THREAD_EXITED  = 1   // Unregistered; listed until its slot is reused
+} TracedThreadState;
+
+typedef struct {
+    uint64_t thread_id;               // Registry thread id, as reported in events
+    uint32_t slot_index;              // Registry slot
+    uint32_t state;                   // TracedThreadState
+    char name[ADA_THREAD_NAME_MAX];   // NUL-terminated; empty if unnamed
+} TracedThreadInfo;
+
+// Per-thread counterpart of TracerStats (see frida_controller_get_thread_stats)
+typedef struct {
+    uint64_t thread_id;               // Registry thread id, as reported in events
+    uint32_t slot_index;              // Registry slot
+    uint32_t state;                   // TracedThreadState
+    uint64_t events_captured;         // Events the thread wrote to its rings
+    uint64_t events_dropped;          // Events lost to full rings or pools
+    uint64_t bytes_written;           // Bytes the thread wrote to its rings
+} TracedThreadStats;
+
 // ============================================================================
 // Thread Registry - Opaque types for public API
 // ============================================================================
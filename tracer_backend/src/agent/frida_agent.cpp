@@ -37,6 +37,7 @@ extern "C" {
 #include <tracer_backend/utils/shm_directory.h>
 #include <tracer_backend/metrics/thread_metrics.h>
 }
+#include <tracer_backend/utils/control_block_ipc.h>
 
 // Include C++ implementation headers
 #include "../utils/ring_buffer_private.h"
@@ -1376,6 +1377,33 @@ void parse_init_payload(const char* data, int data_size,
 // Hook Callbacks (C++ implementation)
 // ============================================================================
 
+// Longest a traced thread waits for the drain under DROP_POLICY_BLOCK_PRODUCER.
+// Bounded so a stalled or stopped drain cannot hang the target; the event is
+// dropped once the wait runs out.
+static constexpr uint64_t kBlockProducerMaxWaitUs = 100 * 1000;
+static constexpr uint64_t kBlockProducerPollUs = 50;
+
+// Recover from pool exhaustion according to the controller's drop policy.
+// Returns true once a fresh ring is active.
+static bool swap_on_exhaustion(::RingPool* pool, uint32_t policy, uint32_t* old_ring_idx) {
+    switch (policy) {
+        case DROP_POLICY_DROP_NEWEST:
+            return false;
+        case DROP_POLICY_BLOCK_PRODUCER:
+            for (uint64_t waited = 0; waited < kBlockProducerMaxWaitUs;
+                 waited += kBlockProducerPollUs) {
+                usleep(kBlockProducerPollUs);
+                if (ring_pool_swap_active(pool, old_ring_idx)) {
+                    return true;
+                }
+            }
+            return false;
+        default:
+            return ring_pool_handle_exhaustion(pool) &&
+                   ring_pool_swap_active(pool, old_ring_idx);
+    }
+}
+
 static void capture_index_event(AgentContext* ctx, HookData* hook,
                                ThreadLocalData* tls, EventKind kind) {
     if (!ctx->control_block()) {
@@ -1456,23 +1484,15 @@ static void capture_index_event(AgentContext* ctx, HookData* hook,
                     if (!wrote_pt) {
                         // Ring is full - swap to a new ring and retry
                         uint32_t old_ring_idx = UINT32_MAX;
-                        if (ring_pool_swap_active(index_pool, &old_ring_idx)) {
+                        uint32_t policy = cb_get_drop_policy(ctx->control_block());
+                        ring_pool_set_drop_policy(index_pool, (DropPolicy)policy);
+                        if (ring_pool_swap_active(index_pool, &old_ring_idx) ||
+                            swap_on_exhaustion(index_pool, policy, &old_ring_idx)) {
                             // Successfully swapped, get new active header and retry write
                             hdr = ring_pool_get_active_header(index_pool);
                             if (hdr) {
                                 wrote_pt = ring_buffer_write_raw(hdr, sizeof(IndexEvent), &event);
                             }
-                        } else {
-                            // Pool exhaustion - try to recover
-                            if (ring_pool_handle_exhaustion(index_pool)) {
-                                // Recovered, try swap again
-                                if (ring_pool_swap_active(index_pool, &old_ring_idx)) {
-                                    hdr = ring_pool_get_active_header(index_pool);
-                                    if (hdr) {
-                                        wrote_pt = ring_buffer_write_raw(hdr, sizeof(IndexEvent), &event);
-                                    }
-                                }
-                            }
                         }
                     }
 
@@ -72,6 +72,28 @@ int atf_detail_writer_write_event(AtfDetailWriter* writer,
  */
 int atf_detail_writer_finalize(AtfDetailWriter* writer);
 
+/**
+ * Flush buffered events and fsync the detail file
+ *
+ * The header is left as written at creation; only finalize updates it.
+ *
+ * @param writer Pointer to writer
+ * @return 0 on success, negative errno on error
+ */
+int atf_detail_writer_sync(AtfDetailWriter* writer);
+
+/**
+ * Write a finalized copy of the events written so far to another file
+ *
+ * The copy gets the header and footer finalize would write for the current
+ * event count. The live file is left untouched and writing may continue.
+ *
+ * @param writer Pointer to writer
+ * @param filepath Path of the copy (directories are created as needed)
+ * @return 0 on success, negative errno on error
+ */
+int atf_detail_writer_snapshot(AtfDetailWriter* writer, const char* filepath);
+
 /**
  * Close and free the detail writer
  *
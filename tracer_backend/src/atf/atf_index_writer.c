@@ -10,6 +10,7 @@
 #include <string.h>
 #include <errno.h>
 #include <sys/stat.h>
+#include <unistd.h>
 
 /* Platform detection */
 #if defined(__APPLE__)
@@ -67,6 +68,35 @@ static void get_directory(const char* filepath, char* dir, size_t dir_size) {
     }
 }
 
+/* Helper to fill in the footer for the events written so far */
+static void build_footer(const AtfIndexWriter* writer, AtfIndexFooter* footer) {
+    memset(footer, 0, sizeof(*footer));
+    memcpy(footer->magic, "2ITA", 4);
+    footer->checksum = 0;  /* TODO: Implement CRC32 */
+    footer->event_count = writer->event_count;
+    footer->time_start_ns = writer->time_start_ns;
+    footer->time_end_ns = writer->time_end_ns;
+    footer->bytes_written = writer->event_count * sizeof(IndexEvent);
+}
+
+/* Helper to copy length bytes at offset of fd to the end of out */
+static int copy_range(int fd, uint64_t offset, uint64_t length, FILE* out) {
+    char buffer[64 * 1024];
+    while (length > 0) {
+        size_t chunk = length < sizeof(buffer) ? (size_t)length : sizeof(buffer);
+        ssize_t n = pread(fd, buffer, chunk, (off_t)offset);
+        if (n <= 0) { // LCOV_EXCL_LINE
+            return n < 0 ? -errno : -EIO; // LCOV_EXCL_LINE
+        } // LCOV_EXCL_LINE
+        if (fwrite(buffer, (size_t)n, 1, out) != 1) { // LCOV_EXCL_LINE
+            return -EIO; // LCOV_EXCL_LINE
+        } // LCOV_EXCL_LINE
+        offset += (uint64_t)n;
+        length -= (uint64_t)n;
+    }
+    return 0;
+}
+
 AtfIndexWriter* atf_index_writer_create(const char* filepath,
                                         uint32_t thread_id,
                                         uint8_t clock_type) {
@@ -83,8 +113,8 @@ AtfIndexWriter* atf_index_writer_create(const char* filepath,
     AtfIndexWriter* writer = (AtfIndexWriter*)calloc(1, sizeof(AtfIndexWriter));
     if (!writer) return NULL; // LCOV_EXCL_LINE
 
-    /* Open file for writing */
-    writer->file = fopen(filepath, "wb");
+    /* Open for writing; snapshots read the events back */
+    writer->file = fopen(filepath, "w+b");
     if (!writer->file) {
         free(writer);
         return NULL;
@@ -157,13 +187,7 @@ int atf_index_writer_finalize(AtfIndexWriter* writer) {
 
     /* Write footer */
     AtfIndexFooter footer;
-    memset(&footer, 0, sizeof(footer));
-    memcpy(footer.magic, "2ITA", 4);
-    footer.checksum = 0;  /* TODO: Implement CRC32 */
-    footer.event_count = writer->event_count;
-    footer.time_start_ns = writer->time_start_ns;
-    footer.time_end_ns = writer->time_end_ns;
-    footer.bytes_written = writer->event_count * sizeof(IndexEvent);
+    build_footer(writer, &footer);
 
     if (fwrite(&footer, sizeof(footer), 1, writer->file) != 1) { // LCOV_EXCL_LINE
         return -EIO; // LCOV_EXCL_LINE
@@ -192,6 +216,68 @@ int atf_index_writer_finalize(AtfIndexWriter* writer) {
     return 0;
 }
 
+int atf_index_writer_sync(AtfIndexWriter* writer) {
+    if (!writer || !writer->file) return -EINVAL;
+
+    if (fflush(writer->file) != 0) { // LCOV_EXCL_LINE
+        return -EIO; // LCOV_EXCL_LINE
+    } // LCOV_EXCL_LINE
+
+    if (fsync(fileno(writer->file)) != 0) { // LCOV_EXCL_LINE
+        return -errno; // LCOV_EXCL_LINE
+    } // LCOV_EXCL_LINE
+
+    return 0;
+}
+
+int atf_index_writer_snapshot(AtfIndexWriter* writer, const char* filepath) {
+    if (!writer || !writer->file || !filepath) return -EINVAL;
+
+    /* Make the events written so far readable through the descriptor */
+    if (fflush(writer->file) != 0) { // LCOV_EXCL_LINE
+        return -EIO; // LCOV_EXCL_LINE
+    } // LCOV_EXCL_LINE
+
+    char dir[1024];
+    get_directory(filepath, dir, sizeof(dir));
+    if (dir[0] != '\0') {
+        mkdir_recursive(dir);
+    }
+
+    FILE* out = fopen(filepath, "wb");
+    if (!out) return -errno;
+
+    uint64_t events_length = (uint64_t)writer->event_count * sizeof(IndexEvent);
+
+    AtfIndexHeader header = writer->header;
+    header.event_count = writer->event_count;
+    header.footer_offset = header.events_offset + events_length;
+    header.time_start_ns = writer->time_start_ns;
+    header.time_end_ns = writer->time_end_ns;
+
+    AtfIndexFooter footer;
+    build_footer(writer, &footer);
+
+    int ret = 0;
+    if (fwrite(&header, sizeof(header), 1, out) != 1) { // LCOV_EXCL_LINE
+        ret = -EIO; // LCOV_EXCL_LINE
+    } // LCOV_EXCL_LINE
+    if (ret == 0) {
+        ret = copy_range(fileno(writer->file), header.events_offset, events_length, out);
+    }
+    if (ret == 0 && fwrite(&footer, sizeof(footer), 1, out) != 1) { // LCOV_EXCL_LINE
+        ret = -EIO; // LCOV_EXCL_LINE
+    } // LCOV_EXCL_LINE
+    if (fclose(out) != 0 && ret == 0) { // LCOV_EXCL_LINE
+        ret = -EIO; // LCOV_EXCL_LINE
+    } // LCOV_EXCL_LINE
+
+    if (ret != 0) {
+        unlink(filepath);
+    }
+    return ret;
+}
+
 void atf_index_writer_close(AtfIndexWriter* writer) {
     if (!writer) return;
 
@@ -60,6 +60,28 @@ int atf_index_writer_write_event(AtfIndexWriter* writer, const IndexEvent* event
  */
 int atf_index_writer_finalize(AtfIndexWriter* writer);
 
+/**
+ * Flush buffered events and fsync the index file
+ *
+ * The header is left as written at creation; only finalize updates it.
+ *
+ * @param writer Pointer to writer
+ * @return 0 on success, negative errno on error
+ */
+int atf_index_writer_sync(AtfIndexWriter* writer);
+
+/**
+ * Write a finalized copy of the events written so far to another file
+ *
+ * The copy gets the header and footer finalize would write for the current
+ * event count. The live file is left untouched and writing may continue.
+ *
+ * @param writer Pointer to writer
+ * @param filepath Path of the copy (directories are created as needed)
+ * @return 0 on success, negative errno on error
+ */
+int atf_index_writer_snapshot(AtfIndexWriter* writer, const char* filepath);
+
 /**
  * Close and free the index writer
  *
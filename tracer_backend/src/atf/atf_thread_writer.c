@@ -164,6 +164,44 @@ int atf_thread_writer_finalize(AtfThreadWriter* writer) {
     return ret;
 }
 
+int atf_thread_writer_sync(AtfThreadWriter* writer) {
+    if (!writer) return -EINVAL;
+
+    int ret = 0;
+
+    if (writer->index_writer) {
+        int rc = atf_index_writer_sync(writer->index_writer);
+        if (rc != 0) { // LCOV_EXCL_LINE
+            ret = rc; // LCOV_EXCL_LINE
+        } // LCOV_EXCL_LINE
+    }
+
+    if (writer->detail_writer) {
+        int rc = atf_detail_writer_sync(writer->detail_writer);
+        if (rc != 0) { // LCOV_EXCL_LINE
+            ret = rc; // LCOV_EXCL_LINE
+        } // LCOV_EXCL_LINE
+    }
+
+    return ret;
+}
+
+int atf_thread_writer_snapshot(AtfThreadWriter* writer, const char* session_dir) {
+    if (!writer || !writer->index_writer || !session_dir) return -EINVAL;
+
+    char path[1024];
+    snprintf(path, sizeof(path), "%s/thread_%u/index.atf", session_dir, writer->thread_id);
+    int rc = atf_index_writer_snapshot(writer->index_writer, path);
+    if (rc != 0) return rc;
+
+    if (writer->detail_writer) {
+        snprintf(path, sizeof(path), "%s/thread_%u/detail.atf", session_dir, writer->thread_id);
+        rc = atf_detail_writer_snapshot(writer->detail_writer, path);
+    }
+
+    return rc;
+}
+
 void atf_thread_writer_close(AtfThreadWriter* writer) {
     if (!writer) return;
 
@@ -157,18 +157,21 @@ fn main() -> Result<()> {
         if tick % 5 == 0 {
             let stats = controller.get_stats();
             println!(
-                "[Stats] Events: {}, Dropped: {}, Bytes: {}, Cycles: {}",
+                "[Stats] Events: {}, Dropped: {}, Bytes: {}, Threads: {}",
                 stats.events_captured,
                 stats.events_dropped,
                 stats.bytes_written,
-                stats.drain_cycles
+                stats.active_threads
             );
         }
 
         // Check if process is still running
         let state = controller.get_state();
         if state != ProcessState::Running && state != ProcessState::Attached {
-            println!("Process has terminated");
+            match controller.failure_reason() {
+                Some(reason) => println!("Tracing failed: {}", reason),
+                None => println!("Process has terminated"),
+            }
             break;
         }
     }
@@ -183,7 +186,7 @@ fn main() -> Result<()> {
     println!("Events captured: {}", final_stats.events_captured);
     println!("Events dropped:  {}", final_stats.events_dropped);
     println!("Bytes written:   {}", final_stats.bytes_written);
-    println!("Drain cycles:    {}", final_stats.drain_cycles);
+    println!("Threads:         {}", final_stats.active_threads);
 
     // Find and display session directory
     if let Ok(entries) = std::fs::read_dir(&output_dir) {
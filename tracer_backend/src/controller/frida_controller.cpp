@@ -16,6 +16,7 @@ extern "C" {
 #include <thread>
 #include <vector>
 #include <cctype>
+#include <algorithm>
 
 #ifdef __APPLE__
 #include <crt_externs.h>
@@ -30,6 +31,13 @@ using AttachSyncFn =
     FridaSession* (*)(FridaDevice*, guint, FridaSessionOptions*, GCancellable*, GError**);
 
 AttachSyncFn attach_sync_fn = frida_device_attach_sync;
+
+// Shared memory is named after the session id, so every controller in this
+// process after the first (followed children, reset controllers) needs its own
+uint32_t next_session_id(uint32_t base) {
+    static std::atomic<uint32_t> seq{0};
+    return base + (seq.fetch_add(1) + 1) * 0x9E3779B9u;
+}
 }
 
 extern "C" void frida_controller_test_set_attach_sync(AttachSyncFn fn) {
@@ -171,8 +179,8 @@ static gboolean on_script_load_timeout_cb(gpointer user_data) {
 // Constructor/Destructor
 // ============================================================================
 
-FridaController::FridaController(const std::string& output_dir)
-    : output_dir_(output_dir)
+FridaController::FridaController(const std::string& output_dir, uint32_t session_id)
+    : output_dir_(output_dir), session_id_(session_id)
 {
     // Initialize state
     state_ = PROCESS_STATE_INITIALIZED;
@@ -218,56 +226,18 @@ FridaController::FridaController(const std::string& output_dir)
         throw std::runtime_error("Failed to find local Frida device");
     }
     
-    // Initialize shared memory
-    if (!initialize_shared_memory()) {
+    std::string error_message = start_capture();
+    if (!error_message.empty()) {
         cleanup_frida_objects();
-        throw std::runtime_error("Failed to initialize shared memory");
+        throw std::runtime_error(error_message);
     }
-    
-    // Initialize ring buffers
-    if (!initialize_ring_buffers()) {
-        cleanup_frida_objects();
-        throw std::runtime_error("Failed to initialize ring buffers");
-    }
-    
-    // Create and start C-based drain thread (with ATF session management)
-    DrainConfig drain_config;
-    drain_config_default(&drain_config);
-    drain_ = drain_thread_create(registry_, &drain_config);
-    if (!drain_) {
-        cleanup_frida_objects();
-        throw std::runtime_error("Failed to create drain thread");
-    }
-    drain_thread_set_control_block(drain_, control_block_);
-
-    if (drain_thread_start(drain_) != 0) {
-        drain_thread_destroy(drain_);
-        drain_ = nullptr;
-        cleanup_frida_objects();
-        throw std::runtime_error("Failed to start drain thread");
-    }
-
-    start_registry_maintenance();
 }
 
 FridaController::~FridaController() {
-    stop_registry_maintenance();
-
-    // Stop ATF session first (finalizes files)
-    stop_atf_session();
-
-    // Stop and destroy C-based drain thread
-    if (drain_) {
-        drain_thread_stop(drain_);
-        drain_thread_destroy(drain_);
-        drain_ = nullptr;
-    }
+    // Children finalize their sub-sessions before the parent manifest is written
+    stop_children();
 
-    // Deinitialize thread registry (testing/runtime hygiene)
-    if (registry_) {
-        thread_registry_deinit(registry_);
-        registry_ = nullptr;
-    }
+    stop_capture();
 
     // Cleanup Frida objects
     cleanup_frida_objects();
@@ -327,6 +297,64 @@ void FridaController::registry_maintenance_loop() {
     }
 }
 
+std::string FridaController::start_capture() {
+    // Initialize shared memory
+    if (!initialize_shared_memory()) {
+        return "Failed to initialize shared memory";
+    }
+
+    // Initialize ring buffers
+    if (!initialize_ring_buffers()) {
+        return "Failed to initialize ring buffers";
+    }
+
+    // Create and start C-based drain thread (with ATF session management)
+    DrainConfig drain_config;
+    drain_config_default(&drain_config);
+    drain_ = drain_thread_create(registry_, &drain_config);
+    if (!drain_) {
+        return "Failed to create drain thread";
+    }
+    drain_thread_set_control_block(drain_, control_block_);
+
+    if (drain_thread_start(drain_) != 0) {
+        drain_thread_destroy(drain_);
+        drain_ = nullptr;
+        return "Failed to start drain thread";
+    }
+
+    start_registry_maintenance();
+    return std::string();
+}
+
+void FridaController::stop_capture() {
+    stop_registry_maintenance();
+
+    // Stop ATF session first (finalizes files)
+    stop_atf_session();
+
+    // Stop and destroy C-based drain thread
+    if (drain_) {
+        drain_thread_stop(drain_);
+        drain_thread_destroy(drain_);
+        drain_ = nullptr;
+    }
+
+    // Deinitialize thread registry (testing/runtime hygiene)
+    if (registry_) {
+        thread_registry_deinit(registry_);
+        registry_ = nullptr;
+    }
+
+    index_ring_.reset();
+    detail_ring_.reset();
+    control_block_ = nullptr;
+    shm_registry_.reset();
+    shm_detail_.reset();
+    shm_index_.reset();
+    shm_control_.reset();
+}
+
 // ============================================================================
 // ATF Session Management
 // ============================================================================
@@ -342,18 +370,23 @@ bool FridaController::start_atf_session() {
         return true;  // Already started
     }
 
-    // Build session directory path: output_dir/session_YYYYMMDD_HHMMSS/pid_XXXXX
-    char timestamp[64];
-    time_t now = time(nullptr);
-    struct tm* tm_info = localtime(&now);
-    strftime(timestamp, sizeof(timestamp), "%Y%m%d_%H%M%S", tm_info);
+    if (!fixed_session_dir_.empty()) {
+        // Followed child: the parent chose a directory inside its own session
+        session_dir_ = fixed_session_dir_;
+    } else {
+        // Build session directory path: output_dir/session_YYYYMMDD_HHMMSS/pid_XXXXX
+        char timestamp[64];
+        time_t now = time(nullptr);
+        struct tm* tm_info = localtime(&now);
+        strftime(timestamp, sizeof(timestamp), "%Y%m%d_%H%M%S", tm_info);
 
-    char session_path[1024];
-    snprintf(session_path, sizeof(session_path),
-             "%s/session_%s/pid_%u",
-             output_dir_.c_str(), timestamp, static_cast<unsigned int>(pid_));
+        char session_path[1024];
+        snprintf(session_path, sizeof(session_path),
+                 "%s/session_%s/pid_%u",
+                 output_dir_.c_str(), timestamp, static_cast<unsigned int>(pid_));
 
-    session_dir_ = session_path;
+        session_dir_ = session_path;
+    }
 
     // Create session directory hierarchy
     char mkdir_cmd[1100];
@@ -382,11 +415,14 @@ void FridaController::stop_atf_session() {
         return;  // Nothing to stop
     }
 
+    // Children finalize their sub-sessions before this manifest links them
+    stop_children();
+
     // Read symbol table from agent temp file (Phase 1: symbol resolution)
     {
         char symbols_path[256];
         uint32_t host_pid = shared_memory_get_pid();
-        uint32_t session_id = shared_memory_get_session_id();
+        uint32_t session_id = session_id_;
         snprintf(symbols_path, sizeof(symbols_path), "/tmp/ada_symbols_%u_%08x.json",
                  host_pid, session_id);
 
@@ -425,7 +461,7 @@ void FridaController::stop_atf_session() {
 
 std::string FridaController::build_shm_name(const char* role, pid_t pid_hint) {
     char name[256];
-    uint32_t sid = shared_memory_get_session_id();
+    uint32_t sid = session_id_;
     pid_t pid_part = (pid_hint > 0) ? pid_hint : getpid();
     snprintf(name, sizeof(name), "%s_%s_%d_%08x", 
              ADA_SHM_PREFIX, role, static_cast<int>(pid_part), 
@@ -436,7 +472,7 @@ std::string FridaController::build_shm_name(const char* role, pid_t pid_hint) {
 bool FridaController::initialize_shared_memory() {
     // Create shared memory with the controller's PID so agent can find it
     uint32_t controller_pid = shared_memory_get_pid();
-    uint32_t session_id = shared_memory_get_session_id();
+    uint32_t session_id = session_id_;
     
     g_debug("Creating shared memory with controller_pid: %u, session_id: %u\n", 
             controller_pid, session_id);
@@ -483,6 +519,7 @@ bool FridaController::initialize_shared_memory() {
     control_block_->detail_lane_enabled = 1;
     control_block_->pre_roll_ms = 1000;
     control_block_->post_roll_ms = 1000;
+    cb_set_drop_policy(control_block_, DROP_POLICY_DROP_OLDEST);
     // Init IPC fields to defaults
     cb_set_registry_ready(control_block_, 0);
     cb_set_registry_version(control_block_, 0);
@@ -566,6 +603,21 @@ bool FridaController::initialize_ring_buffers() {
 }
 
 void FridaController::cleanup_frida_objects() {
+    release_target();
+
+    if (device_) {
+        frida_unref(device_);
+        device_ = nullptr;
+    }
+    
+    if (manager_) {
+        frida_device_manager_close_sync(manager_, nullptr, nullptr);
+        frida_unref(manager_);
+        manager_ = nullptr;
+    }
+}
+
+void FridaController::release_target() {
     if (script_) {
         frida_script_unload_sync(script_, nullptr, nullptr);
         frida_unref(script_);
@@ -583,15 +635,9 @@ void FridaController::cleanup_frida_objects() {
         session_ = nullptr;
     }
     
-    if (device_) {
-        frida_unref(device_);
-        device_ = nullptr;
-    }
-    
-    if (manager_) {
-        frida_device_manager_close_sync(manager_, nullptr, nullptr);
-        frida_unref(manager_);
-        manager_ = nullptr;
+    if (device_ && child_added_handler_ != 0) {
+        g_signal_handler_disconnect(device_, child_added_handler_);
+        child_added_handler_ = 0;
     }
 }
 
@@ -615,7 +661,7 @@ void FridaController::wait_for_debugger_if_needed() const {
 int FridaController::spawn_suspended(const char* path, char* const argv[], uint32_t* out_pid) {
     printf("[Controller] Spawning process: %s\n", path);
     
-    if (!path) {
+    if (!path || !control_block_) {
         return -1;
     }
     
@@ -635,7 +681,7 @@ int FridaController::spawn_suspended(const char* path, char* const argv[], uint3
     frida_spawn_options_set_argv(options, const_cast<gchar**>(argv), argv_len);
     
     // Pass session id and host pid to spawned process
-    uint32_t sid = shared_memory_get_session_id();
+    uint32_t sid = session_id_;
     char sid_hex[16];
     snprintf(sid_hex, sizeof(sid_hex), "%08x", static_cast<unsigned int>(sid));
     pid_t host_pid = shared_memory_get_pid();
@@ -711,9 +757,8 @@ int FridaController::spawn_suspended(const char* path, char* const argv[], uint3
     
     if (error) {
         g_printerr("Failed to spawn: %s\n", error->message);
+        fail(std::string("spawn failed: ") + error->message);
         g_error_free(error);
-        state_ = PROCESS_STATE_FAILED;
-        control_block_->process_state = PROCESS_STATE_FAILED;
         return -1;
     }
     
@@ -730,6 +775,10 @@ int FridaController::spawn_suspended(const char* path, char* const argv[], uint3
 }
 
 int FridaController::attach(uint32_t pid) {
+    if (!control_block_) {
+        return -1;
+    }
+
     state_ = PROCESS_STATE_ATTACHING;
     control_block_->process_state = PROCESS_STATE_ATTACHING;
 
@@ -750,6 +799,7 @@ int FridaController::attach(uint32_t pid) {
                        error->code == FRIDA_ERROR_PROCESS_NOT_FOUND ||
                        error->code == FRIDA_ERROR_PROCESS_NOT_RESPONDING));
         g_printerr("Failed to attach (attempt %d/%d): %s\n", attempt, max_attempts, error->message);
+        std::string message = error->message;
         g_error_free(error);
         session_ = nullptr;
 
@@ -763,14 +813,12 @@ int FridaController::attach(uint32_t pid) {
             continue;
         }
 
-        state_ = PROCESS_STATE_FAILED;
-        control_block_->process_state = PROCESS_STATE_FAILED;
+        fail("attach to PID " + std::to_string(pid) + " failed: " + message);
         return -1;
     }
     
     if (!session_) {
-        state_ = PROCESS_STATE_FAILED;
-        control_block_->process_state = PROCESS_STATE_FAILED;
+        fail("attach to PID " + std::to_string(pid) + " failed: no session was created");
         return -1;
     }
     
@@ -779,6 +827,10 @@ int FridaController::attach(uint32_t pid) {
                      G_CALLBACK(on_detached_callback), this);
     
     pid_ = pid;
+
+    if (follow_children_ && !enable_child_gating()) {
+        g_printerr("[Controller] Warning: child processes of PID %u will not be traced\n", pid);
+    }
     state_ = PROCESS_STATE_ATTACHED;
     control_block_->process_state = PROCESS_STATE_ATTACHED;
     
@@ -819,8 +871,7 @@ int FridaController::resume() {
     }
     
     if (spawn_method_ != SpawnMethod::Frida || !device_ || pid_ == 0) {
-        state_ = PROCESS_STATE_FAILED;
-        control_block_->process_state = PROCESS_STATE_FAILED;
+        fail("resume failed: the process was not spawned by this controller");
         return -1;
     }
 
@@ -828,8 +879,7 @@ int FridaController::resume() {
     if (spawn_method_ == SpawnMethod::Frida && control_block_ && script_) {
         if (cb_get_hooks_ready(control_block_) == 0) {
             g_printerr("[Controller] Error: hooks_ready not set before resume\n");
-            state_ = PROCESS_STATE_FAILED;
-            control_block_->process_state = PROCESS_STATE_FAILED;
+            fail("resume failed: the agent has not reported its hooks ready");
             return -1;
         }
         g_debug("[Controller] Hooks ready confirmed; proceeding to resume\n");
@@ -846,9 +896,8 @@ int FridaController::resume() {
     frida_device_resume_sync(device_, pid_, nullptr, &error);
 
     if (error) {
+        fail(std::string("resume failed: ") + error->message);
         g_error_free(error);
-        state_ = PROCESS_STATE_FAILED;
-        control_block_->process_state = PROCESS_STATE_FAILED;
         return -1;
     }
 
@@ -863,6 +912,68 @@ int FridaController::pause() {
     return -1;
 }
 
+int FridaController::reset(const char* output_dir) {
+    // Only once the previous target is gone: detached, exited, or failed
+    ProcessState state = state_;
+    if (state != PROCESS_STATE_INITIALIZED && state != PROCESS_STATE_FAILED) {
+        g_printerr("[Controller] Cannot reset while attached (state %d); detach first\n",
+                   static_cast<int>(state));
+        return -1;
+    }
+
+    // The next target captures with the settings chosen for this one
+    bool has_settings = control_block_ != nullptr;
+    ControlBlock settings{};
+    if (has_settings) {
+        settings.index_lane_enabled = control_block_->index_lane_enabled;
+        settings.detail_lane_enabled = control_block_->detail_lane_enabled;
+        settings.capture_stack_snapshot = control_block_->capture_stack_snapshot;
+        settings.pre_roll_ms = control_block_->pre_roll_ms;
+        settings.post_roll_ms = control_block_->post_roll_ms;
+        settings.drop_policy = cb_get_drop_policy(control_block_);
+    }
+
+    // Finalizes the previous session, including any left open by an exit
+    stop_children();
+    stop_capture();
+    release_target();
+
+    // An agent left behind in the previous target must not find the new
+    // shared memory
+    session_id_ = next_session_id(session_id_);
+    if (output_dir && output_dir[0] != '\0') {
+        output_dir_ = output_dir;
+    }
+    pid_ = 0;
+    spawn_method_ = SpawnMethod::None;
+    {
+        std::lock_guard<std::mutex> lock(stats_mutex_);
+        stats_baseline_ = TracerStats{};
+    }
+
+    std::string error_message = start_capture();
+    if (!error_message.empty()) {
+        fail("reset failed: " + error_message);
+        return -1;
+    }
+
+    if (has_settings) {
+        control_block_->index_lane_enabled = settings.index_lane_enabled;
+        control_block_->detail_lane_enabled = settings.detail_lane_enabled;
+        control_block_->capture_stack_snapshot = settings.capture_stack_snapshot;
+        control_block_->pre_roll_ms = settings.pre_roll_ms;
+        control_block_->post_roll_ms = settings.post_roll_ms;
+        cb_set_drop_policy(control_block_, settings.drop_policy);
+    }
+
+    {
+        std::lock_guard<std::mutex> lock(failure_mutex_);
+        failure_reason_.clear();
+    }
+    state_ = PROCESS_STATE_INITIALIZED;
+    return 0;
+}
+
 // ============================================================================
 // Agent injection
 // ============================================================================
@@ -970,11 +1081,11 @@ int FridaController::install_hooks() {
         exclude_buf[n] = '\0';
         snprintf(init_payload, sizeof(init_payload),
                  "host_pid=%u;session_id=%08x;exclude=%s",
-                 shared_memory_get_pid(), shared_memory_get_session_id(), exclude_buf);
+                 shared_memory_get_pid(), session_id_, exclude_buf);
     } else {
         snprintf(init_payload, sizeof(init_payload),
                  "host_pid=%u;session_id=%08x",
-                 shared_memory_get_pid(), shared_memory_get_session_id());
+                 shared_memory_get_pid(), session_id_);
     }
 
     // --------------------------------------------------------------------
@@ -1198,6 +1309,10 @@ int FridaController::install_hooks() {
         g_printerr("Load duration: %lld ms\n", load_duration_ms);
         g_printerr("Timeout duration: %u ms\n", timeout_ms);
 
+        std::string reason = timeout_class
+            ? "hook installation failed: agent loader timed out after " +
+                  std::to_string(timeout_ms) + " ms"
+            : std::string("hook installation failed: ") + ctx.error->message;
         g_error_free(ctx.error);
 
         if (script_) {
@@ -1206,10 +1321,7 @@ int FridaController::install_hooks() {
             script_ = nullptr;
         }
 
-        state_ = PROCESS_STATE_FAILED;
-        if (control_block_) {
-            control_block_->process_state = PROCESS_STATE_FAILED;
-        }
+        fail(reason);
         return -1;
     }
 
@@ -1228,10 +1340,8 @@ int FridaController::install_hooks() {
             if (waited_ms >= max_wait_ms) {
                 g_printerr("[Controller] Timeout waiting for agent to set hooks_ready after %u ms\n",
                            max_wait_ms);
-                state_ = PROCESS_STATE_FAILED;
-                if (control_block_) {
-                    control_block_->process_state = PROCESS_STATE_FAILED;
-                }
+                fail("hook installation failed: agent did not report hooks ready within " +
+                     std::to_string(max_wait_ms) + " ms");
                 return -1;
             }
             std::this_thread::sleep_for(std::chrono::milliseconds(poll_ms));
@@ -1317,6 +1427,74 @@ int FridaController::set_detail_enabled(uint32_t enabled) {
     return 0;
 }
 
+int FridaController::set_drop_policy(DropPolicy policy) {
+    if (!control_block_) {
+        return -1;
+    }
+
+    switch (policy) {
+        case DROP_POLICY_DROP_OLDEST:
+        case DROP_POLICY_DROP_NEWEST:
+        case DROP_POLICY_BLOCK_PRODUCER:
+            break;
+        default:
+            return -1;
+    }
+
+    cb_set_drop_policy(control_block_, policy);
+
+    return 0;
+}
+
+int FridaController::set_follow_children(uint32_t enabled) {
+    follow_children_ = enabled != 0;
+    if (!session_) {
+        return 0;  // Applied when attaching
+    }
+
+    if (follow_children_) {
+        return enable_child_gating() ? 0 : -1;
+    }
+
+    GError* error = nullptr;
+    frida_session_disable_child_gating_sync(session_, nullptr, &error);
+    if (error) {
+        g_printerr("[Controller] Failed to disable child gating: %s\n", error->message);
+        g_error_free(error);
+        return -1;
+    }
+    return 0;
+}
+
+int FridaController::flush() {
+    if (!drain_) {
+        return -1;
+    }
+
+    int rc = drain_thread_flush(drain_);
+    if (rc != 0) {
+        g_printerr("[Controller] Failed to flush ATF session: %s\n", strerror(-rc));
+        return -1;
+    }
+
+    return 0;
+}
+
+int FridaController::snapshot(const char* out_dir) {
+    if (!drain_ || !out_dir) {
+        return -1;
+    }
+
+    int rc = drain_thread_snapshot(drain_, out_dir);
+    if (rc != 0) {
+        g_printerr("[Controller] Failed to snapshot ATF session to %s: %s\n",
+                   out_dir, strerror(-rc));
+        return -1;
+    }
+
+    return 0;
+}
+
 int FridaController::start_session() {
     if (!start_atf_session()) {
         return -1;
@@ -1338,6 +1516,46 @@ FlightRecorderState FridaController::get_flight_state() const {
 }
 
 TracerStats FridaController::get_stats() const {
+    TracerStats result = collect_stats();
+
+    // Cumulative counters count from the last reset; a drain restarted since
+    // then reads as zero rather than wrapping
+    std::lock_guard<std::mutex> lock(stats_mutex_);
+    auto since_reset = [](uint64_t total, uint64_t baseline) {
+        return total > baseline ? total - baseline : 0;
+    };
+    result.events_captured = since_reset(result.events_captured, stats_baseline_.events_captured);
+    result.events_dropped = since_reset(result.events_dropped, stats_baseline_.events_dropped);
+    result.bytes_written = since_reset(result.bytes_written, stats_baseline_.bytes_written);
+    return result;
+}
+
+int FridaController::reset_stats() {
+    TracerStats totals = collect_stats();
+
+    std::lock_guard<std::mutex> lock(stats_mutex_);
+    stats_baseline_ = totals;
+    return 0;
+}
+
+std::string FridaController::failure_reason() const {
+    std::lock_guard<std::mutex> lock(failure_mutex_);
+    return state_ == PROCESS_STATE_FAILED ? failure_reason_ : std::string();
+}
+
+void FridaController::fail(const std::string& reason) {
+    g_printerr("[Controller] Failed: %s\n", reason.c_str());
+    {
+        std::lock_guard<std::mutex> lock(failure_mutex_);
+        failure_reason_ = reason;
+    }
+    state_ = PROCESS_STATE_FAILED;
+    if (control_block_) {
+        control_block_->process_state = PROCESS_STATE_FAILED;
+    }
+}
+
+TracerStats FridaController::collect_stats() const {
     TracerStats result = {};
 
     if (drain_) {
@@ -1346,16 +1564,204 @@ TracerStats FridaController::get_stats() const {
 
         result.events_captured = dm.total_events_drained;
         result.bytes_written = dm.total_bytes_drained;
-        // Note: active_threads and hooks_installed would need additional tracking
+        // Note: hooks_installed would need additional tracking
+    }
+
+    if (registry_) {
+        result.active_threads = thread_registry_get_active_count(registry_);
     }
 
     return result;
 }
 
+int FridaController::list_threads(TracedThreadInfo* out, uint32_t capacity,
+                                  uint32_t* out_count) const {
+    if (!out_count || (capacity > 0 && !out)) {
+        return -1;
+    }
+    *out_count = 0;
+    if (!registry_) {
+        return -1;
+    }
+
+    uint32_t count = 0;
+    uint32_t slots = thread_registry_get_capacity(registry_);
+    for (uint32_t slot = 0; slot < slots; ++slot) {
+        ThreadLaneSet* lanes = thread_registry_get_slot(registry_, slot);
+        if (!lanes) {
+            continue;
+        }
+        if (count < capacity) {
+            TracedThreadInfo* info = &out[count];
+            info->thread_id = thread_lanes_get_thread_id(lanes);
+            info->slot_index = slot;
+            info->state = thread_lanes_is_active(lanes) ? TRACED_THREAD_RUNNING
+                                                        : TRACED_THREAD_EXITED;
+            thread_lanes_get_name(lanes, info->name, sizeof(info->name));
+        }
+        ++count;
+    }
+
+    *out_count = count;
+    return 0;
+}
+
+int FridaController::get_thread_stats(TracedThreadStats* out, uint32_t capacity,
+                                      uint32_t* out_count) const {
+    if (!out_count || (capacity > 0 && !out)) {
+        return -1;
+    }
+    *out_count = 0;
+    if (!registry_) {
+        return -1;
+    }
+
+    uint32_t count = 0;
+    uint32_t slots = thread_registry_get_capacity(registry_);
+    for (uint32_t slot = 0; slot < slots; ++slot) {
+        ThreadLaneSet* lanes = thread_registry_get_slot(registry_, slot);
+        if (!lanes) {
+            continue;
+        }
+        if (count < capacity) {
+            ada_thread_metrics_snapshot_t snapshot;
+            ada_thread_metrics_snapshot_capture(thread_lanes_get_metrics(lanes),
+                                                ada_metrics_now_ns(), &snapshot);
+
+            TracedThreadStats* stats = &out[count];
+            stats->thread_id = thread_lanes_get_thread_id(lanes);
+            stats->slot_index = slot;
+            stats->state = thread_lanes_is_active(lanes) ? TRACED_THREAD_RUNNING
+                                                         : TRACED_THREAD_EXITED;
+            stats->events_captured = snapshot.events_written;
+            stats->events_dropped = snapshot.events_dropped;
+            stats->bytes_written = snapshot.bytes_written;
+        }
+        ++count;
+    }
+
+    *out_count = count;
+    return 0;
+}
+
+// ============================================================================
+// Child process tracing
+// ============================================================================
+
+bool FridaController::enable_child_gating() {
+    if (!session_ || !device_) {
+        return false;
+    }
+
+    GError* error = nullptr;
+    frida_session_enable_child_gating_sync(session_, nullptr, &error);
+    if (error) {
+        g_printerr("[Controller] Failed to enable child gating: %s\n", error->message);
+        g_error_free(error);
+        return false;
+    }
+
+    if (child_added_handler_ == 0) {
+        child_added_handler_ = g_signal_connect(device_, "child-added",
+                                                G_CALLBACK(on_child_added_callback), this);
+    }
+    return true;
+}
+
+void FridaController::follow_child(FridaChild* child) {
+    guint child_pid = frida_child_get_pid(child);
+    FridaChildOrigin origin = frida_child_get_origin(child);
+
+    auto resume_untraced = [this, child_pid]() {
+        GError* error = nullptr;
+        frida_device_resume_sync(device_, child_pid, nullptr, &error);
+        if (error) {
+            g_printerr("[Controller] Failed to resume child %u: %s\n", child_pid, error->message);
+            g_error_free(error);
+        }
+    };
+
+    // A forked copy still runs this process's agent and keeps writing into
+    // this session; only a new image (spawn or exec) gets an agent of its own.
+    if (origin == FRIDA_CHILD_ORIGIN_FORK || session_dir_.empty() || !control_block_) {
+        resume_untraced();
+        return;
+    }
+
+    std::lock_guard<std::mutex> lock(children_mutex_);
+
+    // A process that execs repeatedly gets one sub-session per image
+    char base_dir[64];
+    snprintf(base_dir, sizeof(base_dir), "children/pid_%u", child_pid);
+    std::string rel_dir = base_dir;
+    for (unsigned n = 1; access((session_dir_ + "/" + rel_dir).c_str(), F_OK) == 0; ++n) {
+        rel_dir = std::string(base_dir) + "_" + std::to_string(n);
+    }
+
+    // Distinct shared memory per child; the agent learns the id at injection
+    uint32_t child_session_id = next_session_id(session_id_);
+
+    std::unique_ptr<FridaController> follower;
+    try {
+        follower = std::make_unique<FridaController>(output_dir_, child_session_id);
+    } catch (const std::exception& e) {
+        g_printerr("[Controller] Cannot trace child %u: %s\n", child_pid, e.what());
+        resume_untraced();
+        return;
+    }
+
+    // The child captures with the parent's settings and follows its own children
+    ControlBlock* child_cb = follower->control_block_;
+    child_cb->index_lane_enabled = control_block_->index_lane_enabled;
+    child_cb->detail_lane_enabled = control_block_->detail_lane_enabled;
+    child_cb->capture_stack_snapshot = control_block_->capture_stack_snapshot;
+    child_cb->pre_roll_ms = control_block_->pre_roll_ms;
+    child_cb->post_roll_ms = control_block_->post_roll_ms;
+    child_cb->flight_state = control_block_->flight_state;
+    cb_set_drop_policy(child_cb, cb_get_drop_policy(control_block_));
+    follower->fixed_session_dir_ = session_dir_ + "/" + rel_dir;
+    follower->follow_children_ = true;
+    follower->spawn_method_ = SpawnMethod::Frida;
+
+    if (follower->attach(child_pid) != 0 || follower->install_hooks() != 0 ||
+        follower->resume() != 0) {
+        g_printerr("[Controller] Failed to trace child %u; resuming it untraced\n", child_pid);
+        follower.reset();
+        resume_untraced();
+        return;
+    }
+
+    drain_thread_add_child_session(drain_, child_pid, rel_dir.c_str());
+    g_print("[Controller] Tracing child %u into %s\n", child_pid, rel_dir.c_str());
+    children_.push_back(std::move(follower));
+}
+
+void FridaController::stop_children() {
+    std::vector<std::unique_ptr<FridaController>> children;
+    {
+        std::lock_guard<std::mutex> lock(children_mutex_);
+        children.swap(children_);
+    }
+    // Newest first, so each child pops the GLib context it pushed
+    while (!children.empty()) {
+        children.pop_back();
+    }
+}
+
 // ============================================================================
 // Callbacks
 // ============================================================================
 
+void FridaController::on_child_added_callback(FridaDevice* device,
+                                              FridaChild* child,
+                                              gpointer user_data) {
+    (void)device;
+    // Each controller owns its device, so every child reported here descends
+    // from this controller's target (including exec after an untraced fork)
+    auto* controller = static_cast<FridaController*>(user_data);
+    controller->follow_child(child);
+}
+
 void FridaController::on_detached_callback(FridaSession* session,
                                            FridaSessionDetachReason reason,
                                            FridaCrash* crash,
@@ -1511,6 +1917,16 @@ FridaController* frida_controller_create(const char* output_dir) {
     }
 }
 
+FridaController* frida_controller_create_isolated(const char* output_dir) {
+    try {
+        return reinterpret_cast<FridaController*>(new ada::internal::FridaController(
+            output_dir, next_session_id(shared_memory_get_session_id())));
+    } catch (const std::exception& e) {
+        g_debug("Failed to create FridaController: %s\n", e.what());
+        return nullptr;
+    }
+}
+
 void frida_controller_destroy(FridaController* controller) {
     delete reinterpret_cast<ada::internal::FridaController*>(controller);
 }
@@ -1542,6 +1958,11 @@ int frida_controller_resume(FridaController* controller) {
         ->resume();
 }
 
+int frida_controller_reset(FridaController* controller, const char* output_dir) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)->reset(output_dir);
+}
+
 int frida_controller_pause(FridaController* controller) {
     if (!controller) return -1;
     return reinterpret_cast<ada::internal::FridaController*>(controller)
@@ -1586,6 +2007,61 @@ int frida_controller_set_detail_enabled(FridaController* controller, uint32_t en
         ->set_detail_enabled(enabled);
 }
 
+int frida_controller_set_drop_policy(FridaController* controller, DropPolicy policy) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)
+        ->set_drop_policy(policy);
+}
+
+int frida_controller_list_threads(FridaController* controller,
+                                  TracedThreadInfo* out,
+                                  uint32_t capacity,
+                                  uint32_t* out_count) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)
+        ->list_threads(out, capacity, out_count);
+}
+
+int frida_controller_get_thread_stats(FridaController* controller,
+                                      TracedThreadStats* out,
+                                      uint32_t capacity,
+                                      uint32_t* out_count) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)
+        ->get_thread_stats(out, capacity, out_count);
+}
+
+int frida_controller_set_follow_children(FridaController* controller, uint32_t enabled) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)
+        ->set_follow_children(enabled);
+}
+
+int frida_controller_flush(FridaController* controller) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)
+        ->flush();
+}
+
+int frida_controller_get_failure_reason(FridaController* controller, char* out,
+                                        size_t capacity) {
+    if (!controller || (capacity > 0 && !out)) return -1;
+    std::string reason =
+        reinterpret_cast<ada::internal::FridaController*>(controller)->failure_reason();
+    if (capacity > 0) {
+        size_t copied = std::min(reason.size(), capacity - 1);
+        memcpy(out, reason.data(), copied);
+        out[copied] = '\0';
+    }
+    return static_cast<int>(reason.size());
+}
+
+int frida_controller_snapshot(FridaController* controller, const char* out_dir) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)
+        ->snapshot(out_dir);
+}
+
 int frida_controller_start_session(FridaController* controller) {
     if (!controller) return -1;
     return reinterpret_cast<ada::internal::FridaController*>(controller)
@@ -1610,6 +2086,11 @@ FlightRecorderState frida_controller_get_flight_state(FridaController* controlle
         ->get_flight_state();
 }
 
+int frida_controller_reset_stats(FridaController* controller) {
+    if (!controller) return -1;
+    return reinterpret_cast<ada::internal::FridaController*>(controller)->reset_stats();
+}
+
 TracerStats frida_controller_get_stats(FridaController* controller) {
     if (!controller) {
         TracerStats empty = {0};
@@ -6,6 +6,8 @@
 #include <string>
 #include <cstdint>
 #include <thread>
+#include <mutex>
+#include <vector>
 
 extern "C" {
 #include <frida-core.h>
@@ -43,7 +45,9 @@ struct StartupTimeoutConfig {
 class FridaController {
 public:
     // Constructor/Destructor
-    explicit FridaController(const std::string& output_dir);
+    // session_id keys the shared memory the agent opens; children get their own
+    explicit FridaController(const std::string& output_dir,
+                             uint32_t session_id = shared_memory_get_session_id());
     ~FridaController();
     
     // Disable copy/move
@@ -58,6 +62,9 @@ public:
     int detach();
     int resume();
     int pause();
+    // Return to PROCESS_STATE_INITIALIZED with fresh shared memory so another
+    // target can be traced; output_dir replaces the output root when non-null
+    int reset(const char* output_dir);
     
     // Agent injection
     int install_hooks();
@@ -68,13 +75,22 @@ public:
     int fire_trigger();
     int disarm_trigger();
     int set_detail_enabled(uint32_t enabled);
+    int set_drop_policy(DropPolicy policy);
+    int set_follow_children(uint32_t enabled);
     int start_session();
     int stop_session();
+    int flush();
+    int snapshot(const char* out_dir);
+    int list_threads(TracedThreadInfo* out, uint32_t capacity, uint32_t* out_count) const;
+    int get_thread_stats(TracedThreadStats* out, uint32_t capacity, uint32_t* out_count) const;
     
     // State query
     ProcessState get_state() const { return state_; }
     FlightRecorderState get_flight_state() const;
     TracerStats get_stats() const;
+    int reset_stats();
+    // Why the controller entered PROCESS_STATE_FAILED; empty in other states
+    std::string failure_reason() const;
     
 private:
     // Spawn methods
@@ -103,12 +119,30 @@ private:
     bool initialize_shared_memory();
     bool initialize_ring_buffers();
     void cleanup_frida_objects();
+    // Drop the session and script of the current target, keeping the device
+    void release_target();
+
+    // Shared memory, ring buffers, drain and registry maintenance for one
+    // target; start_capture() returns why it failed, or an empty string
+    std::string start_capture();
+    void stop_capture();
     std::string build_shm_name(const char* role, pid_t pid_hint = 0);
 
     // ATF session management
     bool start_atf_session();
     void stop_atf_session();
 
+    // Counters since the controller started, before any reset
+    TracerStats collect_stats() const;
+
+    // Enter PROCESS_STATE_FAILED, recording why for failure_reason()
+    void fail(const std::string& reason);
+
+    // Child process tracing
+    bool enable_child_gating();
+    void follow_child(FridaChild* child);
+    void stop_children();
+
     // Controller maintenance
     void start_registry_maintenance();
     void stop_registry_maintenance();
@@ -123,6 +157,9 @@ private:
                                     const gchar* message,
                                     GBytes* data, 
                                     gpointer user_data);
+    static void on_child_added_callback(FridaDevice* device,
+                                        FridaChild* child,
+                                        gpointer user_data);
     
     // Instance callbacks
     void on_detached(FridaSessionDetachReason reason, FridaCrash* crash);
@@ -130,6 +167,7 @@ private:
     
     // Data members
     std::string output_dir_;
+    uint32_t session_id_;
     
     // Frida objects (raw pointers managed via RAII)
     FridaDeviceManager* manager_{nullptr};
@@ -157,9 +195,21 @@ private:
     // Drain thread (C-based with ATF session management)
     DrainThread* drain_{nullptr};
     std::string session_dir_;
+    std::string fixed_session_dir_;  // Set for children: <parent session>/children/pid_N
+
+    // Child process tracing: one controller and sub-session per traced child
+    bool follow_children_{false};
+    gulong child_added_handler_{0};
+    std::mutex children_mutex_;
+    std::vector<std::unique_ptr<FridaController>> children_;
     
-    // Statistics
-    mutable TracerStats stats_{};
+    // Statistics: cumulative counters as of the last reset_stats()
+    mutable std::mutex stats_mutex_;
+    TracerStats stats_baseline_{};
+
+    // Reason recorded by the last transition to PROCESS_STATE_FAILED
+    mutable std::mutex failure_mutex_;
+    std::string failure_reason_;
     
     // Event loop
     GMainLoop* main_loop_{nullptr};
@@ -1,11 +1,14 @@
 #include "drain_thread_private.h"
 
 #include <errno.h>
+#include <fcntl.h>
 #include <sched.h>
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
 #include <time.h>
+#include <sys/file.h>
+#include <sys/stat.h>
 #include <unistd.h>
 
 #include <tracer_backend/atf/atf_thread_writer.h>
@@ -150,9 +153,27 @@ static inline uint64_t monotonic_now_ns(void) {
     return ((uint64_t)ts.tv_sec * 1000000000ull) + (uint64_t)ts.tv_nsec;
 }
 
+static inline uint64_t realtime_now_ns(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_REALTIME, &ts);
+    return ((uint64_t)ts.tv_sec * 1000000000ull) + (uint64_t)ts.tv_nsec;
+}
+
 static const uint64_t kRegistryTickIntervalNs = 100000000ull; // 100ms
 static const uint32_t kRegistryWarmupTicks = 5;
 
+// Manifest spelling of a DropPolicy
+static const char* drain_drop_policy_name(uint32_t policy) {
+    switch (policy) {
+        case DROP_POLICY_DROP_NEWEST:
+            return "drop_newest";
+        case DROP_POLICY_BLOCK_PRODUCER:
+            return "block_producer";
+        default:
+            return "drop_oldest";
+    }
+}
+
 static void drain_update_control_block(DrainThread* drain) {
     if (!drain || !drain->control_block) {
         return;
@@ -1061,6 +1082,185 @@ static void drain_metrics_snapshot(const DrainThread* drain, DrainMetrics* out)
     }
 }
 
+// Upper bound on drain passes per flush, so producers that keep writing
+// cannot hold a flush open indefinitely. Each pass empties every lane.
+static const uint32_t kFlushMaxPasses = 16;
+static const uint64_t kFlushTimeoutNs = 5000000000ull; // 5s
+
+// Drain every lane, active rings included, then fsync the session's thread
+// files. Must run on the worker, or on the caller while no worker runs.
+static int drain_flush_now(DrainThread* drain) {
+    for (uint32_t pass = 0; pass < kFlushMaxPasses; ++pass) {
+        bool had_work;
+        if (drain->iterator_enabled && drain->iterator) {
+            had_work = drain_iteration(drain);
+        } else {
+            had_work = drain_cycle(drain, true);
+        }
+        if (!had_work) {
+            break;
+        }
+    }
+
+    int rc = 0;
+    for (uint32_t i = 0; i < MAX_THREADS; i++) {
+        if (drain->thread_writers[i]) {
+            int sync_rc = atf_thread_writer_sync(drain->thread_writers[i]);
+            if (sync_rc != 0 && rc == 0) {
+                rc = sync_rc;
+            }
+        }
+    }
+    return rc;
+}
+
+// Write <dir>/manifest.json listing the session's threads. It is written to a
+// temporary file and renamed into place so concurrent readers see either no
+// manifest or a complete one, never a truncated file. Children are linked
+// only when link_children is set. Call with lifecycle_lock held.
+static int drain_write_manifest(DrainThread* drain, const char* dir, bool link_children) {
+    char manifest_path[4096 + 16];
+    char manifest_tmp_path[4096 + 24];
+    snprintf(manifest_path, sizeof(manifest_path), "%s/manifest.json", dir);
+    snprintf(manifest_tmp_path, sizeof(manifest_tmp_path), "%s.tmp", manifest_path);
+
+    FILE* manifest = fopen(manifest_tmp_path, "w");
+    if (!manifest) {
+        return -errno;
+    }
+
+    fprintf(manifest, "{\n");
+    fprintf(manifest, "  \"threads\": [\n");
+
+    bool first = true;
+    for (uint32_t i = 0; i < MAX_THREADS; i++) {
+        if (drain->thread_writers[i]) {
+            if (!first) {
+                fprintf(manifest, ",\n");
+            }
+            fprintf(manifest, "    {\"id\": %u, \"has_detail\": true}", i);
+            first = false;
+        }
+    }
+
+    fprintf(manifest, "\n  ],\n");
+    fprintf(manifest, "  \"time_start_ns\": 0,\n");
+    fprintf(manifest, "  \"time_end_ns\": 0,\n");
+    fprintf(manifest, "  \"clock_type\": 1,\n");
+    if (drain->wall_clock_start_ns != 0) {
+        fprintf(manifest, "  \"wall_clock_start_ns\": %llu,\n",
+                (unsigned long long)drain->wall_clock_start_ns);
+        fprintf(manifest, "  \"monotonic_start_ns\": %llu,\n",
+                (unsigned long long)drain->monotonic_start_ns);
+    }
+    if (drain->wall_clock_end_ns != 0) {
+        fprintf(manifest, "  \"wall_clock_end_ns\": %llu,\n",
+                (unsigned long long)drain->wall_clock_end_ns);
+        fprintf(manifest, "  \"monotonic_end_ns\": %llu,\n",
+                (unsigned long long)drain->monotonic_end_ns);
+    }
+    fprintf(manifest, "  \"drop_policy\": \"%s\",\n",
+            drain_drop_policy_name(drain->control_block
+                                       ? cb_get_drop_policy(drain->control_block)
+                                       : DROP_POLICY_DROP_OLDEST));
+    if (link_children && drain->child_session_count > 0) {
+        fprintf(manifest, "  \"children\": [\n");
+        for (uint32_t i = 0; i < drain->child_session_count; i++) {
+            fprintf(manifest, "    {\"pid\": %u, \"dir\": \"%s\"}%s\n",
+                    drain->child_sessions[i].pid, drain->child_sessions[i].dir,
+                    i + 1 < drain->child_session_count ? "," : "");
+        }
+        fprintf(manifest, "  ],\n");
+    }
+#if defined(__BYTE_ORDER__) && __BYTE_ORDER__ == __ORDER_BIG_ENDIAN__
+    fprintf(manifest, "  \"endianness\": \"big\",\n");
+#else
+    fprintf(manifest, "  \"endianness\": \"little\",\n");
+#endif
+
+    // Include symbol table if available (Phase 1: symbol resolution)
+    if (drain->symbol_table_json && drain->symbol_table_json[0] != '\0') {
+        // symbol_table_json contains: "modules": [...], "symbols": [...]
+        fprintf(manifest, "  %s,\n", drain->symbol_table_json);
+        fprintf(manifest, "  \"format_version\": \"2.1\"\n");
+    } else {
+        fprintf(manifest, "  \"format_version\": \"2.0\"\n");
+    }
+    fprintf(manifest, "}\n");
+
+    bool write_failed = ferror(manifest) != 0 || fflush(manifest) != 0 ||
+                        fsync(fileno(manifest)) != 0;
+    write_failed = fclose(manifest) != 0 || write_failed;
+    if (write_failed || rename(manifest_tmp_path, manifest_path) != 0) {
+        unlink(manifest_tmp_path);
+        return -EIO;
+    }
+    return 0;
+}
+
+// Copy the session's thread files, finalized, and a manifest into out_dir.
+// Runs right after a flush, on the worker or on the caller while no worker
+// runs, with lifecycle_lock held.
+static int drain_write_snapshot(DrainThread* drain, const char* out_dir) {
+    if (!drain->session_active) {
+        return -EINVAL;
+    }
+    if (mkdir(out_dir, 0755) != 0 && errno != EEXIST) {
+        return -errno;
+    }
+
+    // Writing into the live session would truncate its thread files
+    struct stat out_stat;
+    struct stat session_stat;
+    if (stat(out_dir, &out_stat) != 0) {
+        return -errno;
+    }
+    if (stat(drain->session_dir, &session_stat) == 0 &&
+        out_stat.st_dev == session_stat.st_dev && out_stat.st_ino == session_stat.st_ino) {
+        return -EINVAL;
+    }
+
+    char manifest_path[4096 + 16];
+    snprintf(manifest_path, sizeof(manifest_path), "%s/manifest.json", out_dir);
+    if (access(manifest_path, F_OK) == 0) {
+        return -EEXIST;
+    }
+
+    for (uint32_t i = 0; i < MAX_THREADS; i++) {
+        if (drain->thread_writers[i]) {
+            int rc = atf_thread_writer_snapshot(drain->thread_writers[i], out_dir);
+            if (rc != 0) {
+                return rc;
+            }
+        }
+    }
+    return drain_write_manifest(drain, out_dir, false);
+}
+
+// Serve the latest pending flush request; earlier tickets are covered by it.
+static void drain_serve_flush(DrainThread* drain) {
+    uint64_t requested = atomic_load_explicit(&drain->flush_requested, memory_order_acquire);
+    if (requested == atomic_load_explicit(&drain->flush_completed, memory_order_relaxed)) {
+        return;
+    }
+    int rc = drain_flush_now(drain);
+    atomic_store_explicit(&drain->flush_result, rc, memory_order_relaxed);
+
+    // A snapshot requested with this ticket, or a later one, reads the flush
+    char* snapshot_dir = atomic_exchange_explicit(&drain->snapshot_request, NULL,
+                                                  memory_order_acquire);
+    if (snapshot_dir) {
+        if (rc == 0) {
+            pthread_mutex_lock(&drain->lifecycle_lock);
+            rc = drain_write_snapshot(drain, snapshot_dir);
+            pthread_mutex_unlock(&drain->lifecycle_lock);
+        }
+        free(snapshot_dir);
+        atomic_store_explicit(&drain->snapshot_result, rc, memory_order_relaxed);
+    }
+    atomic_store_explicit(&drain->flush_completed, requested, memory_order_release);
+}
+
 static void* drain_worker_thread(void* arg) {
     DrainThread* drain = (DrainThread*)arg;
     if (!drain) {
@@ -1075,6 +1275,7 @@ static void* drain_worker_thread(void* arg) {
 
     while (atomic_load_explicit(&drain->state, memory_order_acquire) == DRAIN_STATE_RUNNING) {
         drain_update_control_block(drain);
+        drain_serve_flush(drain);
         bool work = false;
 
         // Use per-thread drain iteration if available
@@ -1186,6 +1387,7 @@ DrainThread* drain_thread_create(ThreadRegistry* registry, const DrainConfig* co
     drain->warmup_ticks = 0;
     drain->session_dir[0] = '\0';
     drain->session_active = false;
+    drain->session_lock_fd = -1;
     memset(drain->thread_writers, 0, sizeof(drain->thread_writers));
     drain->symbol_table_json = NULL;  // Phase 1: symbol resolution
     drain->thread_started = false;
@@ -1203,11 +1405,21 @@ DrainThread* drain_thread_create(ThreadRegistry* registry, const DrainConfig* co
     atomic_init(&drain->state, DRAIN_STATE_INITIALIZED);
     atomic_init(&drain->rr_cursor, 0);
     atomic_init(&drain->last_cycle_ns, monotonic_now_ns());
+    atomic_init(&drain->flush_requested, 0);
+    atomic_init(&drain->flush_completed, 0);
+    atomic_init(&drain->flush_result, 0);
+    atomic_init(&drain->snapshot_request, NULL);
+    atomic_init(&drain->snapshot_result, 0);
 
     if (drain_thread_call_pthread_mutex_init(&drain->lifecycle_lock, NULL) != 0) {
         free(drain);
         return NULL;
     }
+    if (drain_thread_call_pthread_mutex_init(&drain->snapshot_lock, NULL) != 0) {
+        pthread_mutex_destroy(&drain->lifecycle_lock);
+        free(drain);
+        return NULL;
+    }
 
     // Initialize per-thread drain iterator only if explicitly enabled
     if (local_config.enable_fair_scheduling ||
@@ -1220,6 +1432,7 @@ DrainThread* drain_thread_create(ThreadRegistry* registry, const DrainConfig* co
 
         drain->iterator = drain_iterator_create(&local_config, max_threads);
         if (!drain->iterator) {
+            pthread_mutex_destroy(&drain->snapshot_lock);
             pthread_mutex_destroy(&drain->lifecycle_lock);
             free(drain);
             return NULL;
@@ -1353,12 +1566,26 @@ void drain_thread_destroy(DrainThread* drain) {
     }
     drain->iterator_enabled = false;
 
+    free(drain->child_sessions);
+    drain->child_sessions = NULL;
+    drain->child_session_count = 0;
+    drain->child_session_capacity = 0;
+
     // Clean up symbol table JSON (Phase 1: symbol resolution)
     if (drain->symbol_table_json) {
         free(drain->symbol_table_json);
         drain->symbol_table_json = NULL;
     }
 
+    if (drain->session_lock_fd >= 0) {
+        close(drain->session_lock_fd);
+        drain->session_lock_fd = -1;
+    }
+
+    // A request withdrawn too late for the worker to serve it
+    free(atomic_exchange_explicit(&drain->snapshot_request, NULL, memory_order_acquire));
+
+    pthread_mutex_destroy(&drain->snapshot_lock);
     pthread_mutex_destroy(&drain->lifecycle_lock);
     free(drain);
 }
@@ -1390,6 +1617,84 @@ int drain_thread_update_config(DrainThread* drain, const DrainConfig* config) {
     return 0;
 }
 
+// Flush, then snapshot into snapshot_dir unless it is NULL. Takes ownership
+// of snapshot_dir, which must be heap-allocated; the worker frees it once the
+// request is handed over.
+static int drain_request_flush(DrainThread* drain, char* snapshot_dir) {
+    pthread_mutex_lock(&drain->lifecycle_lock);
+    int state = atomic_load_explicit(&drain->state, memory_order_acquire);
+    if (state == DRAIN_STATE_INITIALIZED || state == DRAIN_STATE_STOPPED) {
+        // No worker competes for the lanes; flush on the caller's thread
+        int rc = drain_flush_now(drain);
+        if (rc == 0 && snapshot_dir) {
+            rc = drain_write_snapshot(drain, snapshot_dir);
+        }
+        pthread_mutex_unlock(&drain->lifecycle_lock);
+        free(snapshot_dir);
+        return rc;
+    }
+    if (state != DRAIN_STATE_RUNNING) {
+        pthread_mutex_unlock(&drain->lifecycle_lock);
+        free(snapshot_dir);
+        return -ECANCELED;
+    }
+    bool wants_snapshot = snapshot_dir != NULL;
+    if (wants_snapshot) {
+        atomic_store_explicit(&drain->snapshot_request, snapshot_dir, memory_order_release);
+    }
+    uint64_t ticket =
+        atomic_fetch_add_explicit(&drain->flush_requested, 1, memory_order_acq_rel) + 1;
+    pthread_mutex_unlock(&drain->lifecycle_lock);
+
+    int rc = 0;
+    const uint64_t deadline_ns = monotonic_now_ns() + kFlushTimeoutNs;
+    while (atomic_load_explicit(&drain->flush_completed, memory_order_acquire) < ticket) {
+        if (atomic_load_explicit(&drain->state, memory_order_acquire) != DRAIN_STATE_RUNNING) {
+            rc = -ECANCELED;
+            break;
+        }
+        if (monotonic_now_ns() >= deadline_ns) {
+            rc = -ETIMEDOUT;
+            break;
+        }
+        usleep(100);
+    }
+    if (rc != 0) {
+        // Withdraw the snapshot unless the worker already took it
+        if (wants_snapshot) {
+            free(atomic_exchange_explicit(&drain->snapshot_request, NULL, memory_order_acq_rel));
+        }
+        return rc;
+    }
+    return wants_snapshot
+        ? atomic_load_explicit(&drain->snapshot_result, memory_order_relaxed)
+        : atomic_load_explicit(&drain->flush_result, memory_order_relaxed);
+}
+
+int drain_thread_flush(DrainThread* drain) {
+    if (!drain) {
+        return -EINVAL;
+    }
+    return drain_request_flush(drain, NULL);
+}
+
+int drain_thread_snapshot(DrainThread* drain, const char* out_dir) {
+    if (!drain || !out_dir || out_dir[0] == '\0') {
+        return -EINVAL;
+    }
+
+    char* snapshot_dir = strdup(out_dir);
+    if (!snapshot_dir) {
+        return -ENOMEM;
+    }
+
+    // The worker holds a single request slot
+    pthread_mutex_lock(&drain->snapshot_lock);
+    int rc = drain_request_flush(drain, snapshot_dir);
+    pthread_mutex_unlock(&drain->snapshot_lock);
+    return rc;
+}
+
 int drain_thread_start_session(DrainThread* drain, const char* session_dir) {
     if (!drain || !session_dir) {
         return -EINVAL;
@@ -1402,8 +1707,29 @@ int drain_thread_start_session(DrainThread* drain, const char* session_dir) {
         return -EALREADY;
     }
 
+    // Hold an exclusive advisory lock on <session_dir>/.lock while the session
+    // is being written so readers can tell an in-progress capture from a
+    // finished one. Best effort: without a session directory there is nothing
+    // to read yet, and the writers create it on demand.
+    char lock_path[4096 + 8];
+    snprintf(lock_path, sizeof(lock_path), "%s/.lock", session_dir);
+    int lock_fd = open(lock_path, O_RDWR | O_CREAT | O_CLOEXEC, 0644);
+    if (lock_fd >= 0 && flock(lock_fd, LOCK_EX | LOCK_NB) != 0) {
+        int err = errno;
+        close(lock_fd);
+        pthread_mutex_unlock(&drain->lifecycle_lock);
+        return err == EWOULDBLOCK ? -EBUSY : -err;
+    }
+
     strncpy(drain->session_dir, session_dir, sizeof(drain->session_dir) - 1);
     drain->session_dir[sizeof(drain->session_dir) - 1] = '\0';
+    drain->session_lock_fd = lock_fd;
+    // Anchor event timestamps (CLOCK_MONOTONIC) to the wall clock so readers
+    // can report when events happened, not just their offsets.
+    drain->monotonic_start_ns = monotonic_now_ns();
+    drain->wall_clock_start_ns = realtime_now_ns();
+    drain->monotonic_end_ns = 0;
+    drain->wall_clock_end_ns = 0;
     drain->session_active = true;
 
     pthread_mutex_unlock(&drain->lifecycle_lock);
@@ -1422,42 +1748,11 @@ int drain_thread_stop_session(DrainThread* drain) {
         return 0;
     }
 
-    // Generate manifest.json with thread list and time range
-    char manifest_path[4096];
-    snprintf(manifest_path, sizeof(manifest_path), "%s/manifest.json", drain->session_dir);
-
-    FILE* manifest = fopen(manifest_path, "w");
-    if (manifest) {
-        fprintf(manifest, "{\n");
-        fprintf(manifest, "  \"threads\": [\n");
-
-        bool first = true;
-        for (uint32_t i = 0; i < MAX_THREADS; i++) {
-            if (drain->thread_writers[i]) {
-                if (!first) {
-                    fprintf(manifest, ",\n");
-                }
-                fprintf(manifest, "    {\"id\": %u, \"has_detail\": true}", i);
-                first = false;
-            }
-        }
-
-        fprintf(manifest, "\n  ],\n");
-        fprintf(manifest, "  \"time_start_ns\": 0,\n");
-        fprintf(manifest, "  \"time_end_ns\": 0,\n");
-        fprintf(manifest, "  \"clock_type\": 1,\n");
-
-        // Include symbol table if available (Phase 1: symbol resolution)
-        if (drain->symbol_table_json && drain->symbol_table_json[0] != '\0') {
-            // symbol_table_json contains: "modules": [...], "symbols": [...]
-            fprintf(manifest, "  %s,\n", drain->symbol_table_json);
-            fprintf(manifest, "  \"format_version\": \"2.1\"\n");
-        } else {
-            fprintf(manifest, "  \"format_version\": \"2.0\"\n");
-        }
-        fprintf(manifest, "}\n");
-        fclose(manifest);
-    }
+    // A second reading pair lets readers correct for the wall clock drifting
+    // against the event clock over the capture.
+    drain->monotonic_end_ns = monotonic_now_ns();
+    drain->wall_clock_end_ns = realtime_now_ns();
+    (void)drain_write_manifest(drain, drain->session_dir, true);
 
     // Finalize and close all thread writers
     for (uint32_t i = 0; i < MAX_THREADS; i++) {
@@ -1468,8 +1763,50 @@ int drain_thread_stop_session(DrainThread* drain) {
         }
     }
 
+    // Everything is on disk; let readers in
+    if (drain->session_lock_fd >= 0) {
+        flock(drain->session_lock_fd, LOCK_UN);
+        close(drain->session_lock_fd);
+        drain->session_lock_fd = -1;
+    }
+
     drain->session_active = false;
     drain->session_dir[0] = '\0';
+    drain->child_session_count = 0;
+
+    pthread_mutex_unlock(&drain->lifecycle_lock);
+    return 0;
+}
+
+int drain_thread_add_child_session(DrainThread* drain, uint32_t pid, const char* relative_dir) {
+    if (!drain || !relative_dir || relative_dir[0] == '\0' ||
+        strlen(relative_dir) >= sizeof(drain->child_sessions[0].dir)) {
+        return -EINVAL;
+    }
+
+    pthread_mutex_lock(&drain->lifecycle_lock);
+
+    if (!drain->session_active) {
+        pthread_mutex_unlock(&drain->lifecycle_lock);
+        return -EINVAL;
+    }
+
+    if (drain->child_session_count == drain->child_session_capacity) {
+        uint32_t capacity = drain->child_session_capacity ? drain->child_session_capacity * 2 : 4;
+        DrainChildSession* grown = (DrainChildSession*)realloc(
+            drain->child_sessions, capacity * sizeof(DrainChildSession));
+        if (!grown) {
+            pthread_mutex_unlock(&drain->lifecycle_lock);
+            return -ENOMEM;
+        }
+        drain->child_sessions = grown;
+        drain->child_session_capacity = capacity;
+    }
+
+    DrainChildSession* child = &drain->child_sessions[drain->child_session_count++];
+    child->pid = pid;
+    strncpy(child->dir, relative_dir, sizeof(child->dir) - 1);
+    child->dir[sizeof(child->dir) - 1] = '\0';
 
     pthread_mutex_unlock(&drain->lifecycle_lock);
     return 0;
@@ -40,6 +40,12 @@ typedef struct {
     // Note: fairness_index is not atomic as it requires calculation
 } DrainMetricsAtomic;
 
+// Sub-session of a followed child process (see drain_thread_add_child_session)
+typedef struct DrainChildSession {
+    uint32_t pid;
+    char     dir[256];  // relative to the parent session directory
+} DrainChildSession;
+
 // Per-thread drain state tracking
 typedef struct ThreadDrainState {
     uint32_t thread_id;
@@ -165,8 +171,18 @@ struct DrainThread {
     // ATF V2 session management
     char                session_dir[4096];
     bool                session_active;
+    int                 session_lock_fd;  // flock(LOCK_EX) on <session_dir>/.lock, or -1
+    uint64_t            wall_clock_start_ns;  // CLOCK_REALTIME at session start, 0 if unset
+    uint64_t            monotonic_start_ns;   // CLOCK_MONOTONIC sampled alongside it
+    uint64_t            wall_clock_end_ns;    // CLOCK_REALTIME at session stop, 0 until then
+    uint64_t            monotonic_end_ns;     // CLOCK_MONOTONIC sampled alongside it
     AtfThreadWriter*    thread_writers[MAX_THREADS]; // Per-thread writers
 
+    // Child sub-sessions linked from the manifest, reset per session
+    DrainChildSession*  child_sessions;      // Heap-allocated, freed on destroy
+    uint32_t            child_session_count;
+    uint32_t            child_session_capacity;
+
     // Symbol table JSON for manifest (Phase 1 - symbol resolution)
     char*               symbol_table_json;  // Heap-allocated, freed on destroy
 
@@ -177,6 +193,16 @@ struct DrainThread {
     atomic_uint         rr_cursor;           // round-robin start index
     atomic_uint_fast64_t last_cycle_ns;      // last cycle timestamp snapshot
 
+    // Flush requests served by the worker (see drain_thread_flush)
+    atomic_uint_fast64_t flush_requested;    // latest ticket handed to a caller
+    atomic_uint_fast64_t flush_completed;    // latest ticket the worker served
+    atomic_int          flush_result;        // result of the last served flush
+
+    // Snapshot requests ride on a flush (see drain_thread_snapshot)
+    pthread_mutex_t     snapshot_lock;       // one pending snapshot at a time
+    _Atomic(char*)      snapshot_request;    // heap copy of the target dir, or NULL
+    atomic_int          snapshot_result;     // result of the last served snapshot
+
     DrainMetricsAtomic  metrics;
 
     // Per-thread drain iteration
@@ -4,7 +4,7 @@
 //! components built with Frida.
 
 use std::ffi::CString;
-use std::os::raw::{c_char, c_uint};
+use std::os::raw::{c_char, c_int, c_uint};
 use std::path::Path;
 use std::ptr;
 
@@ -36,9 +36,33 @@ pub mod ffi {
             pub events_captured: u64,
             pub events_dropped: u64,
             pub bytes_written: u64,
-            pub drain_cycles: u64,
-            pub cpu_overhead_percent: f64,
-            pub memory_usage_mb: f64,
+            pub active_threads: u32,
+            pub hooks_installed: u32,
+        }
+
+        pub const ADA_THREAD_NAME_MAX: usize = 16;
+
+        /// Per-thread counters filled by `frida_controller_get_thread_stats`
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct TracedThreadStats {
+            pub thread_id: u64,
+            pub slot_index: u32,
+            pub state: u32,
+            pub events_captured: u64,
+            pub events_dropped: u64,
+            pub bytes_written: u64,
+        }
+
+        /// Registry entry filled by `frida_controller_list_threads`
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct TracedThreadInfo {
+            pub thread_id: u64,
+            pub slot_index: u32,
+            /// 0 = running, 1 = exited
+            pub state: u32,
+            pub name: [c_char; ADA_THREAD_NAME_MAX],
         }
 
         #[repr(C)]
@@ -65,8 +89,24 @@ pub mod ffi {
             PostRoll = 4,
         }
 
+        /// What the agent does when a lane has no free ring to swap to
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+        pub enum DropPolicy {
+            /// Reclaim the oldest undrained ring, losing its events
+            #[default]
+            DropOldest = 0,
+            /// Keep undrained rings and drop the incoming event
+            DropNewest = 1,
+            /// Stall the traced thread until the drain frees a ring
+            BlockProducer = 2,
+        }
+
         extern "C" {
             pub fn frida_controller_create(output_dir: *const c_char) -> *mut FridaController;
+            pub fn frida_controller_create_isolated(
+                output_dir: *const c_char,
+            ) -> *mut FridaController;
             pub fn frida_controller_destroy(controller: *mut FridaController);
             pub fn frida_controller_spawn_suspended(
                 controller: *mut FridaController,
@@ -77,6 +117,10 @@ pub mod ffi {
             pub fn frida_controller_attach(controller: *mut FridaController, pid: c_uint) -> c_int;
             pub fn frida_controller_detach(controller: *mut FridaController) -> c_int;
             pub fn frida_controller_resume(controller: *mut FridaController) -> c_int;
+            pub fn frida_controller_reset(
+                controller: *mut FridaController,
+                output_dir: *const c_char,
+            ) -> c_int;
             pub fn frida_controller_install_hooks(controller: *mut FridaController) -> c_int;
             pub fn frida_controller_arm_trigger(
                 controller: *mut FridaController,
@@ -89,10 +133,41 @@ pub mod ffi {
                 controller: *mut FridaController,
                 enabled: c_uint,
             ) -> c_int;
+            pub fn frida_controller_set_drop_policy(
+                controller: *mut FridaController,
+                policy: DropPolicy,
+            ) -> c_int;
+            pub fn frida_controller_list_threads(
+                controller: *mut FridaController,
+                out: *mut TracedThreadInfo,
+                capacity: c_uint,
+                out_count: *mut c_uint,
+            ) -> c_int;
+            pub fn frida_controller_get_thread_stats(
+                controller: *mut FridaController,
+                out: *mut TracedThreadStats,
+                capacity: c_uint,
+                out_count: *mut c_uint,
+            ) -> c_int;
+            pub fn frida_controller_set_follow_children(
+                controller: *mut FridaController,
+                enabled: c_uint,
+            ) -> c_int;
             pub fn frida_controller_start_session(controller: *mut FridaController) -> c_int;
             pub fn frida_controller_stop_session(controller: *mut FridaController) -> c_int;
+            pub fn frida_controller_flush(controller: *mut FridaController) -> c_int;
+            pub fn frida_controller_snapshot(
+                controller: *mut FridaController,
+                out_dir: *const c_char,
+            ) -> c_int;
             pub fn frida_controller_get_stats(controller: *mut FridaController) -> TracerStats;
+            pub fn frida_controller_reset_stats(controller: *mut FridaController) -> c_int;
             pub fn frida_controller_get_state(controller: *mut FridaController) -> ProcessState;
+            pub fn frida_controller_get_failure_reason(
+                controller: *mut FridaController,
+                out: *mut c_char,
+                capacity: usize,
+            ) -> c_int;
             pub fn frida_controller_get_flight_state(
                 controller: *mut FridaController,
             ) -> FlightRecorderState;
@@ -105,6 +180,120 @@ pub mod ffi {
 
 use ffi::*;
 
+/// Whether a registered thread is still running in the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    Running,
+    /// Unregistered; listed until its registry slot is reused
+    Exited,
+}
+
+/// A thread of the traced process, as recorded in the thread registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadInfo {
+    /// Registry thread id, as reported in events
+    pub id: u64,
+    pub slot: u32,
+    /// `None` when the thread was never named
+    pub name: Option<String>,
+    pub state: ThreadState,
+}
+
+/// Per-thread counterpart of `TracerStats`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadStats {
+    /// Registry thread id, as reported in events
+    pub id: u64,
+    pub slot: u32,
+    pub state: ThreadState,
+    pub events_captured: u64,
+    pub events_dropped: u64,
+    pub bytes_written: u64,
+}
+
+impl ThreadState {
+    fn from_raw(state: u32) -> Self {
+        if state == 0 {
+            ThreadState::Running
+        } else {
+            ThreadState::Exited
+        }
+    }
+}
+
+impl From<&TracedThreadStats> for ThreadStats {
+    fn from(raw: &TracedThreadStats) -> Self {
+        ThreadStats {
+            id: raw.thread_id,
+            slot: raw.slot_index,
+            state: ThreadState::from_raw(raw.state),
+            events_captured: raw.events_captured,
+            events_dropped: raw.events_dropped,
+            bytes_written: raw.bytes_written,
+        }
+    }
+}
+
+/// Fills a buffer through a backend call that reports how many entries exist
+///
+/// Threads may register between calls, so the buffer grows and the call is
+/// retried until every entry fits. Returns `None` if the call fails.
+fn collect_sized<T: Copy + Default>(
+    mut fill: impl FnMut(*mut T, c_uint, &mut c_uint) -> c_int,
+) -> Option<Vec<T>> {
+    let mut raw: Vec<T> = Vec::new();
+    loop {
+        let mut count: c_uint = 0;
+        if fill(raw.as_mut_ptr(), raw.len() as c_uint, &mut count) != 0 {
+            return None;
+        }
+        if count as usize <= raw.len() {
+            raw.truncate(count as usize);
+            return Some(raw);
+        }
+        raw.resize(count as usize, T::default());
+    }
+}
+
+/// Reads a string through a backend call that returns its full length
+///
+/// The call copies a truncated, NUL-terminated prefix into the buffer, so
+/// the buffer grows until the whole string fits. Returns `None` if the call
+/// fails or the string is empty.
+fn read_sized_string(mut fill: impl FnMut(*mut c_char, usize) -> c_int) -> Option<String> {
+    let mut raw: Vec<u8> = Vec::new();
+    loop {
+        let length = fill(raw.as_mut_ptr() as *mut c_char, raw.len());
+        if length <= 0 {
+            return None;
+        }
+        let length = length as usize;
+        if length < raw.len() {
+            raw.truncate(length);
+            return Some(String::from_utf8_lossy(&raw).into_owned());
+        }
+        raw.resize(length + 1, 0);
+    }
+}
+
+impl From<&TracedThreadInfo> for ThreadInfo {
+    fn from(raw: &TracedThreadInfo) -> Self {
+        let bytes: Vec<u8> = raw
+            .name
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .collect();
+        let name = (!bytes.is_empty()).then(|| String::from_utf8_lossy(&bytes).into_owned());
+        ThreadInfo {
+            id: raw.thread_id,
+            slot: raw.slot_index,
+            name,
+            state: ThreadState::from_raw(raw.state),
+        }
+    }
+}
+
 /// High-level Rust wrapper for the tracer controller
 pub struct TracerController {
     ptr: *mut ffi::FridaController,
@@ -112,11 +301,14 @@ pub struct TracerController {
 
 impl TracerController {
     /// Create a new tracer controller
+    ///
+    /// Each controller gets shared memory of its own, so several can exist
+    /// in one process without clobbering each other's segments.
     pub fn new<P: AsRef<Path>>(output_dir: P) -> anyhow::Result<Self> {
         let output_dir = output_dir.as_ref();
         let c_path = CString::new(output_dir.to_str().unwrap())?;
 
-        let ptr = unsafe { ffi::frida_controller_create(c_path.as_ptr()) };
+        let ptr = unsafe { ffi::frida_controller_create_isolated(c_path.as_ptr()) };
 
         if ptr.is_null() {
             anyhow::bail!("Failed to create tracer controller");
@@ -150,7 +342,7 @@ impl TracerController {
         };
 
         if result != 0 {
-            anyhow::bail!("Failed to spawn process");
+            return Err(self.failure("Failed to spawn process".to_string()));
         }
 
         Ok(pid)
@@ -161,7 +353,7 @@ impl TracerController {
         let result = unsafe { ffi::frida_controller_attach(self.ptr, pid) };
 
         if result != 0 {
-            anyhow::bail!("Failed to attach to process {}", pid);
+            return Err(self.failure(format!("Failed to attach to process {}", pid)));
         }
 
         Ok(())
@@ -172,7 +364,7 @@ impl TracerController {
         let result = unsafe { ffi::frida_controller_install_hooks(self.ptr) };
 
         if result != 0 {
-            anyhow::bail!("Failed to install hooks");
+            return Err(self.failure("Failed to install hooks".to_string()));
         }
 
         Ok(())
@@ -222,6 +414,32 @@ impl TracerController {
         Ok(())
     }
 
+    /// Choose how overflowing lanes are handled; recorded in the session manifest
+    pub fn set_drop_policy(&mut self, policy: DropPolicy) -> anyhow::Result<()> {
+        let result = unsafe { ffi::frida_controller_set_drop_policy(self.ptr, policy) };
+
+        if result != 0 {
+            anyhow::bail!("Failed to set drop policy {:?}", policy);
+        }
+
+        Ok(())
+    }
+
+    /// Trace child processes into sub-sessions linked from the manifest
+    ///
+    /// Spawned and exec'd children get their own session under
+    /// `children/pid_<pid>`; a fork without exec stays in this session.
+    pub fn set_follow_children(&mut self, follow: bool) -> anyhow::Result<()> {
+        let result =
+            unsafe { ffi::frida_controller_set_follow_children(self.ptr, follow as c_uint) };
+
+        if result != 0 {
+            anyhow::bail!("Failed to set follow_children to {}", follow);
+        }
+
+        Ok(())
+    }
+
     /// Start ATF session output without resuming the process
     pub fn start_session(&mut self) -> anyhow::Result<()> {
         let result = unsafe { ffi::frida_controller_start_session(self.ptr) };
@@ -244,12 +462,41 @@ impl TracerController {
         Ok(())
     }
 
+    /// Drain buffered events to the session files and fsync them
+    ///
+    /// Blocks until everything captured so far is on disk, so a concurrent
+    /// reader sees a consistent prefix and `bytes_written` covers it.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        let result = unsafe { ffi::frida_controller_flush(self.ptr) };
+
+        if result != 0 {
+            anyhow::bail!("Failed to flush ATF session");
+        }
+
+        Ok(())
+    }
+
+    /// Copy everything captured so far into `out_dir` as a complete trace
+    ///
+    /// Flushes first, then writes finalized thread files and a manifest, so
+    /// the copy can be queried like a stopped session while capture goes on.
+    pub fn snapshot<P: AsRef<Path>>(&mut self, out_dir: P) -> anyhow::Result<()> {
+        let out_dir = CString::new(out_dir.as_ref().to_str().unwrap())?;
+        let result = unsafe { ffi::frida_controller_snapshot(self.ptr, out_dir.as_ptr()) };
+
+        if result != 0 {
+            anyhow::bail!("Failed to snapshot ATF session");
+        }
+
+        Ok(())
+    }
+
     /// Resume a suspended process
     pub fn resume(&mut self) -> anyhow::Result<()> {
         let result = unsafe { ffi::frida_controller_resume(self.ptr) };
 
         if result != 0 {
-            anyhow::bail!("Failed to resume process");
+            return Err(self.failure("Failed to resume process".to_string()));
         }
 
         Ok(())
@@ -266,20 +513,104 @@ impl TracerController {
         Ok(())
     }
 
+    /// Return to `ProcessState::Initialized` so another target can be traced
+    ///
+    /// Only allowed once the previous target is gone (detached or exited) or
+    /// the controller has failed; resetting while a target is attached or
+    /// running is refused. Later sessions go under `output_dir` when given.
+    /// Capture settings carry over and statistics restart from zero.
+    pub fn reset(&mut self, output_dir: Option<&Path>) -> anyhow::Result<()> {
+        let output_dir = output_dir
+            .map(|dir| CString::new(dir.to_str().unwrap()))
+            .transpose()?;
+        let output_dir_ptr = output_dir.as_ref().map_or(ptr::null(), |dir| dir.as_ptr());
+        let result = unsafe { ffi::frida_controller_reset(self.ptr, output_dir_ptr) };
+
+        if result != 0 {
+            let state = self.get_state();
+            if state != ProcessState::Initialized && state != ProcessState::Failed {
+                anyhow::bail!(
+                    "Cannot reset tracer controller while {:?}; detach first",
+                    state
+                );
+            }
+            return Err(self.failure("Failed to reset tracer controller".to_string()));
+        }
+
+        Ok(())
+    }
+
     /// Get current statistics
     pub fn get_stats(&self) -> TracerStats {
         unsafe { ffi::frida_controller_get_stats(self.ptr) }
     }
 
+    /// Restart the cumulative counters in `get_stats` from zero
+    ///
+    /// Lets callers measure a phase of a long session. `active_threads` and
+    /// `hooks_installed` are point-in-time values and are unaffected.
+    pub fn reset_stats(&mut self) -> anyhow::Result<()> {
+        let result = unsafe { ffi::frida_controller_reset_stats(self.ptr) };
+
+        if result != 0 {
+            anyhow::bail!("Failed to reset statistics");
+        }
+
+        Ok(())
+    }
+
+    /// List the target's threads while it runs
+    ///
+    /// Backed by the shared thread registry, so only threads that have
+    /// emitted events (and thereby registered) appear.
+    pub fn list_threads(&self) -> anyhow::Result<Vec<ThreadInfo>> {
+        let Some(raw) = collect_sized(|out, capacity, count| unsafe {
+            ffi::frida_controller_list_threads(self.ptr, out, capacity, count)
+        }) else {
+            anyhow::bail!("Failed to list threads");
+        };
+
+        Ok(raw.iter().map(ThreadInfo::from).collect())
+    }
+
+    /// Get per-thread statistics, e.g. to find the thread flooding events
+    ///
+    /// Empty when the target has no thread registry. `get_stats` keeps the
+    /// process-wide view.
+    pub fn get_thread_stats(&self) -> Vec<ThreadStats> {
+        collect_sized(|out, capacity, count| unsafe {
+            ffi::frida_controller_get_thread_stats(self.ptr, out, capacity, count)
+        })
+        .map(|raw| raw.iter().map(ThreadStats::from).collect())
+        .unwrap_or_default()
+    }
+
     /// Get current process state
     pub fn get_state(&self) -> ProcessState {
         unsafe { ffi::frida_controller_get_state(self.ptr) }
     }
 
+    /// Why the process entered `ProcessState::Failed`
+    ///
+    /// `None` in any other state, or if the backend recorded no reason.
+    pub fn failure_reason(&self) -> Option<String> {
+        read_sized_string(|out, capacity| unsafe {
+            ffi::frida_controller_get_failure_reason(self.ptr, out, capacity)
+        })
+    }
+
     /// Get current flight recorder state
     pub fn get_flight_state(&self) -> FlightRecorderState {
         unsafe { ffi::frida_controller_get_flight_state(self.ptr) }
     }
+
+    /// An error for a failed call, carrying the failure reason if one was recorded
+    fn failure(&self, message: String) -> anyhow::Error {
+        match self.failure_reason() {
+            Some(reason) => anyhow::anyhow!("{}: {}", message, reason),
+            None => anyhow::anyhow!(message),
+        }
+    }
 }
 
 impl Drop for TracerController {
@@ -296,13 +627,272 @@ impl Drop for TracerController {
 unsafe impl Send for TracerController {}
 unsafe impl Sync for TracerController {}
 
+/// A target traced through a controller, from spawn or attach to detach
+///
+/// The constructors run the lifecycle in the order the backend requires
+/// (spawn suspended, attach, install hooks, resume). A failed step leaves
+/// the controller in `ProcessState::Failed`, where it refuses to resume, so
+/// `spawn` kills the suspended target itself rather than leave it frozen.
+/// Dropping the session, including during a panic, resumes a target that is
+/// still suspended (killing it if that is refused) and detaches.
+/// Cleanup on drop is best effort; call `finish` to see detach errors.
+pub struct TraceSession<'a> {
+    controller: &'a mut TracerController,
+    pid: u32,
+    attached: bool,
+    suspended: bool,
+}
+
+impl<'a> TraceSession<'a> {
+    /// Spawn `path` suspended, attach, install hooks and resume it
+    ///
+    /// `args` is the full argv, including the program name.
+    pub fn spawn<P: AsRef<Path>>(
+        controller: &'a mut TracerController,
+        path: P,
+        args: &[String],
+    ) -> anyhow::Result<Self> {
+        let pid = controller.spawn_suspended(path, args)?;
+        let mut session = TraceSession {
+            controller,
+            pid,
+            attached: false,
+            suspended: true,
+        };
+
+        if let Err(err) = session.start_suspended() {
+            session.suspended = false;
+            return Err(match kill_target(pid) {
+                Ok(()) => err,
+                Err(kill_err) => err.context(format!(
+                    "Failed to kill suspended process {}: {}",
+                    pid, kill_err
+                )),
+            });
+        }
+
+        Ok(session)
+    }
+
+    /// Attach to the suspended target, install hooks and resume it
+    fn start_suspended(&mut self) -> anyhow::Result<()> {
+        self.controller.attach(self.pid)?;
+        self.attached = true;
+        self.controller.install_hooks()?;
+        self.controller.resume()?;
+        self.suspended = false;
+
+        Ok(())
+    }
+
+    /// Attach to a running process and install hooks
+    pub fn attach(controller: &'a mut TracerController, pid: u32) -> anyhow::Result<Self> {
+        let mut session = TraceSession {
+            controller,
+            pid,
+            attached: false,
+            suspended: false,
+        };
+
+        session.controller.attach(pid)?;
+        session.attached = true;
+        session.controller.install_hooks()?;
+
+        Ok(session)
+    }
+
+    /// PID of the traced process
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Current statistics, as `TracerController::get_stats`
+    pub fn stats(&self) -> TracerStats {
+        self.controller.get_stats()
+    }
+
+    /// Current process state, as `TracerController::get_state`
+    pub fn state(&self) -> ProcessState {
+        self.controller.get_state()
+    }
+
+    /// The controller, e.g. to flush, snapshot or fire the trigger
+    pub fn controller(&mut self) -> &mut TracerController {
+        self.controller
+    }
+
+    /// Detach from the target, finalizing the session files
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        self.attached = false;
+        self.controller.detach()
+    }
+}
+
+impl Drop for TraceSession<'_> {
+    fn drop(&mut self) {
+        // Resume first: the backend can only resume a target it is attached to
+        if self.suspended && self.controller.resume().is_err() {
+            let _ = kill_target(self.pid);
+        }
+        if self.attached {
+            let _ = self.controller.detach();
+        }
+    }
+}
+
+/// Send SIGKILL to `pid` without going through the controller
+fn kill_target(pid: u32) -> std::io::Result<()> {
+    let result = unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) };
+
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::os::raw::c_void;
+
+    type AttachSyncFn = unsafe extern "C" fn(
+        *mut c_void,
+        c_uint,
+        *mut c_void,
+        *mut c_void,
+        *mut *mut c_void,
+    ) -> *mut c_void;
+
+    extern "C" {
+        fn frida_controller_test_set_attach_sync(attach: Option<AttachSyncFn>);
+        fn frida_controller_test_reset_attach_sync();
+    }
+
+    /// Stands in for `frida_device_attach_sync`, creating no session
+    unsafe extern "C" fn attach_sync_no_session(
+        _device: *mut c_void,
+        _pid: c_uint,
+        _options: *mut c_void,
+        _cancellable: *mut c_void,
+        _error: *mut *mut c_void,
+    ) -> *mut c_void {
+        ptr::null_mut()
+    }
+
     // Keep only a lightweight sanity test here; all C++ tests run via
     // generated wrappers in tests/tests.rs to avoid duplicate execution.
     #[test]
     fn test_controller_creation() {
         let _ = TracerController::new("./test_output");
     }
+
+    #[test]
+    fn test_controllers_coexist_in_one_process() {
+        let Ok(mut first) = TracerController::new("./test_output") else {
+            return;
+        };
+        first.arm_trigger(100, 100).unwrap();
+
+        // Sharing segments, the second controller would zero the first's
+        // control block and disarm it
+        let second = TracerController::new("./test_output").unwrap();
+        assert_eq!(first.get_flight_state(), FlightRecorderState::Armed);
+        assert_eq!(second.get_flight_state(), FlightRecorderState::Idle);
+    }
+
+    #[test]
+    fn test_trace_session_spawn_failure_leaves_controller_usable() {
+        let Ok(mut controller) = TracerController::new("./test_output") else {
+            return;
+        };
+
+        assert!(TraceSession::spawn(&mut controller, "/nonexistent/ada_target", &[]).is_err());
+        assert_eq!(controller.get_state(), ProcessState::Failed);
+        assert!(controller.failure_reason().is_some());
+        assert!(controller.reset(None).is_ok());
+    }
+
+    #[test]
+    fn test_trace_session_attach_failure_kills_suspended_target() {
+        let Ok(mut controller) = TracerController::new("./test_output") else {
+            return;
+        };
+
+        unsafe { frida_controller_test_set_attach_sync(Some(attach_sync_no_session)) };
+        let args = ["sleep".to_string(), "30".to_string()];
+        let result = TraceSession::spawn(&mut controller, "/bin/sleep", &args);
+        unsafe { frida_controller_test_reset_attach_sync() };
+
+        // Resume is refused once attach fails, so the target must be killed
+        let message = format!("{:#}", result.err().expect("attach should fail"));
+        assert!(message.contains("Failed to attach"), "{}", message);
+        assert!(!message.contains("Failed to kill"), "{}", message);
+        assert_eq!(controller.get_state(), ProcessState::Failed);
+        assert!(controller.reset(None).is_ok());
+    }
+
+    #[test]
+    fn test_collect_sized_growing_count() {
+        // A fourth thread registers between sizing the buffer and filling it
+        let mut calls = 0;
+        let collected = collect_sized(|out: *mut u32, capacity, count| {
+            calls += 1;
+            *count = if calls == 1 { 3 } else { 4 };
+            for i in 0..capacity.min(*count) {
+                unsafe { *out.add(i as usize) = i };
+            }
+            0
+        })
+        .unwrap();
+        assert_eq!(collected, vec![0, 1, 2, 3]);
+        assert_eq!(calls, 3);
+
+        assert!(collect_sized(|_: *mut u32, _, _| -1).is_none());
+    }
+
+    #[test]
+    fn test_read_sized_string() {
+        let reason = b"spawn failed: no such file";
+        let mut calls = 0;
+        let read = read_sized_string(|out, capacity| {
+            calls += 1;
+            if capacity > 0 {
+                let copied = reason.len().min(capacity - 1);
+                for (i, &byte) in reason[..copied].iter().enumerate() {
+                    unsafe { *out.add(i) = byte as c_char };
+                }
+                unsafe { *out.add(copied) = 0 };
+            }
+            reason.len() as c_int
+        });
+        assert_eq!(read.as_deref(), Some("spawn failed: no such file"));
+        assert_eq!(calls, 2);
+
+        assert!(read_sized_string(|_, _| 0).is_none());
+        assert!(read_sized_string(|_, _| -1).is_none());
+    }
+
+    #[test]
+    fn test_thread_info_from_raw() {
+        let mut raw = TracedThreadInfo {
+            thread_id: 42,
+            slot_index: 3,
+            state: 1,
+            ..Default::default()
+        };
+        for (dst, src) in raw.name.iter_mut().zip(b"worker") {
+            *dst = *src as c_char;
+        }
+
+        let info = ThreadInfo::from(&raw);
+        assert_eq!(info.id, 42);
+        assert_eq!(info.slot, 3);
+        assert_eq!(info.name.as_deref(), Some("worker"));
+        assert_eq!(info.state, ThreadState::Exited);
+
+        let unnamed = ThreadInfo::from(&TracedThreadInfo::default());
+        assert_eq!(unnamed.name, None);
+        assert_eq!(unnamed.state, ThreadState::Running);
+    }
 }
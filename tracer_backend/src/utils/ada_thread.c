@@ -1,3 +1,7 @@
+#if !defined(__APPLE__) && !defined(_GNU_SOURCE)
+#define _GNU_SOURCE  // pthread_getname_np
+#endif
+
 #include <tracer_backend/ada/thread.h>
 #include <tracer_backend/utils/ring_pool.h>
 
@@ -93,6 +97,12 @@ ThreadLaneSet* ada_register_current_thread(void) {
         return NULL;
     }
 
+    // Name the slot so the controller can list it while tracing
+    char name[ADA_THREAD_NAME_MAX];
+    if (pthread_getname_np(pthread_self(), name, sizeof(name)) == 0) {
+        thread_lanes_set_name(lanes, name);
+    }
+
     // Initialize TLS state
     g_tls_state.lanes = lanes;
     g_tls_state.metrics = thread_lanes_get_metrics(lanes);
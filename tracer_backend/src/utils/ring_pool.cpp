@@ -12,6 +12,7 @@ struct AdaRingPool {
     ::ThreadLaneSet* lanes;
     int lane_type; // 0 = index, 1 = detail
     ada_backpressure_state_t* backpressure;
+    DropPolicy drop_policy;
 };
 
 namespace {
@@ -83,7 +84,8 @@ RingPool* ring_pool_create(ThreadRegistry* registry, ThreadLaneSet* lanes, int l
     if (tls) {
         bp_state = &tls->backpressure[lane_type];
     }
-    auto* p = new (std::nothrow) AdaRingPool{registry, lanes, lane_type, bp_state};
+    auto* p = new (std::nothrow) AdaRingPool{registry, lanes, lane_type, bp_state,
+                                                  DROP_POLICY_DROP_OLDEST};
     if (!p) {
         return nullptr;
     }
@@ -123,11 +125,12 @@ bool ring_pool_swap_active(RingPool* pool, uint32_t* out_old_idx) {
         if (metrics) {
             ada_thread_metrics_record_ring_full(metrics);
         }
-        if (ring_pool_handle_exhaustion(pool)) {
+        bool reclaim = p->drop_policy == DROP_POLICY_DROP_OLDEST;
+        if (reclaim && ring_pool_handle_exhaustion(pool)) {
             new_idx = lane_get_free_ring(lane);
         }
         if (new_idx == UINT32_MAX) {
-            if (cpp_lane->ring_count > 1) {
+            if (reclaim && cpp_lane->ring_count > 1) {
                 uint32_t cur = cpp_lane->active_idx.load(std::memory_order_acquire);
                 new_idx = (cur + 1) % cpp_lane->ring_count;
             } else {
@@ -155,6 +158,26 @@ bool ring_pool_swap_active(RingPool* pool, uint32_t* out_old_idx) {
     return true;
 }
 
+void ring_pool_set_drop_policy(RingPool* pool, DropPolicy policy) {
+    if (!pool) return;
+    auto* p = reinterpret_cast<AdaRingPool*>(pool);
+    switch (policy) {
+        case DROP_POLICY_DROP_OLDEST:
+        case DROP_POLICY_DROP_NEWEST:
+        case DROP_POLICY_BLOCK_PRODUCER:
+            p->drop_policy = policy;
+            break;
+        default:
+            p->drop_policy = DROP_POLICY_DROP_OLDEST;
+            break;
+    }
+}
+
+DropPolicy ring_pool_get_drop_policy(RingPool* pool) {
+    if (!pool) return DROP_POLICY_DROP_OLDEST;
+    return reinterpret_cast<AdaRingPool*>(pool)->drop_policy;
+}
+
 RingBufferHeader* ring_pool_get_active_header(RingPool* pool) {
     if (!pool) return nullptr;
     auto* p = reinterpret_cast<AdaRingPool*>(pool);
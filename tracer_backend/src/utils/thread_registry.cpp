@@ -215,6 +215,16 @@ ThreadLaneSet* thread_registry_get_thread_at(ThreadRegistry* registry, uint32_t
     return reinterpret_cast<ThreadLaneSet*>(&lanes_base[index]);
 }
 
+ThreadLaneSet* thread_registry_get_slot(ThreadRegistry* registry, uint32_t index) {
+    auto* cpp_registry = reinterpret_cast<ada::internal::ThreadRegistry*>(registry);
+    if (!registry || index >= cpp_registry->get_capacity()) return nullptr;
+
+    auto* lanes_base = reinterpret_cast<ada::internal::ThreadLaneSet*>(reinterpret_cast<uint8_t*>(cpp_registry) + cpp_registry->lanes_off);
+    if (lanes_base[index].thread_id == 0) return nullptr;
+
+    return reinterpret_cast<ThreadLaneSet*>(&lanes_base[index]);
+}
+
 void thread_registry_stop_accepting(ThreadRegistry* registry) {
     if (!registry) return;
     auto* cpp_registry = reinterpret_cast<ada::internal::ThreadRegistry*>(registry);
@@ -518,6 +528,33 @@ uint64_t thread_lanes_get_thread_id(ThreadLaneSet* lanes) {
     return cpp_lanes->thread_id;
 }
 
+bool thread_lanes_is_active(ThreadLaneSet* lanes) {
+    if (!lanes) return false;
+    auto* cpp_lanes = reinterpret_cast<ada::internal::ThreadLaneSet*>(lanes);
+    return cpp_lanes->active.load(std::memory_order_acquire);
+}
+
+void thread_lanes_set_name(ThreadLaneSet* lanes, const char* name) {
+    if (!lanes) return;
+    auto* cpp_lanes = reinterpret_cast<ada::internal::ThreadLaneSet*>(lanes);
+    size_t len = name ? strnlen(name, sizeof(cpp_lanes->name) - 1) : 0;
+    if (len > 0) {
+        std::memcpy(cpp_lanes->name, name, len);
+    }
+    cpp_lanes->name[len] = '\0';
+}
+
+void thread_lanes_get_name(ThreadLaneSet* lanes, char* out, size_t capacity) {
+    if (!out || capacity == 0) return;
+    out[0] = '\0';
+    if (!lanes) return;
+    auto* cpp_lanes = reinterpret_cast<ada::internal::ThreadLaneSet*>(lanes);
+    size_t len = strnlen(cpp_lanes->name, sizeof(cpp_lanes->name));
+    if (len >= capacity) len = capacity - 1;
+    std::memcpy(out, cpp_lanes->name, len);
+    out[len] = '\0';
+}
+
 void thread_lanes_set_active(ThreadLaneSet* lanes, bool active) {
     if (!lanes) return;
     auto* cpp_lanes = reinterpret_cast<ada::internal::ThreadLaneSet*>(lanes);
@@ -144,6 +144,7 @@ public:
     uintptr_t thread_id{0};
     uint32_t slot_index{0};
     std::atomic<bool> active{false};
+    char name[ADA_THREAD_NAME_MAX]{};  // Set by the agent after registration
 
     // Offsets to lane memory layouts within the unified SHM pool (prototype for offsets-only SHM)
     // These are relative to the segment base (segments[seg_id-1].base_offset) and can be
@@ -167,6 +168,7 @@ public:
         if (needs_log_thread_registry_registry) printf("DEBUG: ThreadLaneSet::initialize start - tid=%lx, slot=%u\n", tid, slot);
         thread_id = tid;
         slot_index = slot;
+        name[0] = '\0';
         
         if (needs_log_thread_registry_registry) printf("DEBUG: Initializing index_lane\n");
         index_lane.initialize(index_memory, RINGS_PER_INDEX_LANE, 64 * 1024, sizeof(IndexEvent), QUEUE_COUNT_INDEX_LANE);
@@ -414,6 +416,7 @@ public:
             );
         } else {
             thread_lanes[slot].thread_id = thread_id;
+            thread_lanes[slot].name[0] = '\0';
             thread_lanes[slot].active.store(true, std::memory_order_release);
             ada_thread_metrics_init(&thread_lanes[slot].metrics, thread_id, slot);
         }
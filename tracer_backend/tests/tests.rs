@@ -2,6 +2,25 @@ use std::io;
 use std::process::{Command, Stdio};
 use std::time::Duration;
 
+// Overrides the per-test timeout below, in seconds
+const TIMEOUT_ENV: &str = "ADA_GTEST_TIMEOUT_SECS";
+
+fn gtest_timeout(filter: &str) -> Duration {
+    if let Some(secs) = std::env::var(TIMEOUT_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+    {
+        return Duration::from_secs(secs);
+    }
+
+    if filter.contains("integration") || filter.contains("Integration") {
+        Duration::from_secs(120) // 2 minutes for integration tests
+    } else {
+        Duration::from_secs(60) // 1 minute for unit tests
+    }
+}
+
 // Helper function for individual gtest execution with timeout
 fn run_gtest(bin: &str, filter: &str) -> io::Result<()> {
     use std::sync::mpsc;
@@ -15,6 +34,13 @@ fn run_gtest(bin: &str, filter: &str) -> io::Result<()> {
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
+    // Own process group, so a timeout can kill the test and anything it spawned
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
     // Use spawn() instead of output() to get a handle we can kill
     let mut child = cmd.spawn()?;
 
@@ -28,12 +54,7 @@ fn run_gtest(bin: &str, filter: &str) -> io::Result<()> {
         let _ = tx.send(output);
     });
 
-    // Determine timeout based on test type
-    let timeout = if filter.contains("integration") || filter.contains("Integration") {
-        Duration::from_secs(120) // 2 minutes for integration tests
-    } else {
-        Duration::from_secs(60) // 1 minute for unit tests
-    };
+    let timeout = gtest_timeout(filter);
 
     // Wait for either completion or timeout
     match rx.recv_timeout(timeout) {
@@ -57,7 +78,6 @@ fn run_gtest(bin: &str, filter: &str) -> io::Result<()> {
             // Try to kill the process on Unix systems
             #[cfg(unix)]
             {
-                use std::os::unix::process::CommandExt;
                 // Kill the entire process group
                 unsafe {
                     libc::killpg(child_id as i32, libc::SIGTERM);
@@ -74,7 +94,10 @@ fn run_gtest(bin: &str, filter: &str) -> io::Result<()> {
                     .output();
             }
 
-            panic!("Test timeout: {} :: {} exceeded {:?}", bin, filter, timeout);
+            panic!(
+                "Test timed out: {} :: {} exceeded {:?} (set {} to change)",
+                bin, filter, timeout, TIMEOUT_ENV
+            );
         }
     }
 }
@@ -426,3 +426,64 @@ TEST(AtfThreadWriter, Close_WithoutFinalize_NoCrash) {
 
     cleanup_temp_dir();
 }
+
+// Snapshot copies both files finalized while the writer keeps appending
+TEST(AtfThreadWriter, Snapshot_WhileWriting_FinalizedCopy) {
+    cleanup_temp_dir();
+    std::string session_dir = get_temp_dir() + "/live";
+    std::string snapshot_dir = get_temp_dir() + "/snapshot";
+
+    AtfThreadWriter* writer = atf_thread_writer_create(session_dir.c_str(), 1, ATF_CLOCK_MACH_CONTINUOUS);
+    ASSERT_NE(writer, nullptr);
+
+    uint8_t payload[16] = {0};
+    atf_thread_writer_write_event(writer, 1000, 0x100000001,
+                                  ATF_EVENT_KIND_CALL, 1,
+                                  payload, sizeof(payload));
+    atf_thread_writer_write_event(writer, 2000, 0x100000001,
+                                  ATF_EVENT_KIND_RETURN, 1,
+                                  NULL, 0);
+
+    EXPECT_EQ(atf_thread_writer_snapshot(writer, snapshot_dir.c_str()), 0);
+
+    AtfIndexHeader index_header;
+    FILE* index = fopen((snapshot_dir + "/thread_1/index.atf").c_str(), "rb");
+    ASSERT_NE(index, nullptr);
+    ASSERT_EQ(fread(&index_header, sizeof(index_header), 1, index), 1u);
+    EXPECT_EQ(index_header.event_count, 2u);
+    EXPECT_EQ(index_header.footer_offset, 64u + 2 * sizeof(IndexEvent));
+    EXPECT_EQ(index_header.time_start_ns, 1000u);
+    EXPECT_EQ(index_header.time_end_ns, 2000u);
+    EXPECT_NE(index_header.flags & ATF_INDEX_FLAG_HAS_DETAIL_FILE, 0u);
+    AtfIndexFooter index_footer;
+    ASSERT_EQ(fseek(index, (long)index_header.footer_offset, SEEK_SET), 0);
+    ASSERT_EQ(fread(&index_footer, sizeof(index_footer), 1, index), 1u);
+    EXPECT_EQ(memcmp(index_footer.magic, "2ITA", 4), 0);
+    EXPECT_EQ(index_footer.event_count, 2u);
+    fclose(index);
+
+    AtfDetailHeader detail_header;
+    FILE* detail = fopen((snapshot_dir + "/thread_1/detail.atf").c_str(), "rb");
+    ASSERT_NE(detail, nullptr);
+    ASSERT_EQ(fread(&detail_header, sizeof(detail_header), 1, detail), 1u);
+    fclose(detail);
+    EXPECT_EQ(detail_header.event_count, 1u);
+    EXPECT_EQ(detail_header.bytes_length, sizeof(DetailEventHeader) + sizeof(payload));
+
+    // The live writer is unaffected and still finalizes normally
+    atf_thread_writer_write_event(writer, 3000, 0x100000002,
+                                  ATF_EVENT_KIND_CALL, 1,
+                                  NULL, 0);
+    EXPECT_EQ(atf_thread_writer_finalize(writer), 0);
+    FILE* live = fopen((session_dir + "/thread_1/index.atf").c_str(), "rb");
+    ASSERT_NE(live, nullptr);
+    ASSERT_EQ(fread(&index_header, sizeof(index_header), 1, live), 1u);
+    fclose(live);
+    EXPECT_EQ(index_header.event_count, 3u);
+
+    EXPECT_EQ(atf_thread_writer_snapshot(NULL, snapshot_dir.c_str()), -EINVAL);
+    EXPECT_EQ(atf_thread_writer_snapshot(writer, NULL), -EINVAL);
+
+    atf_thread_writer_close(writer);
+    cleanup_temp_dir();
+}
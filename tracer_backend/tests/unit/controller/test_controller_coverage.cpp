@@ -172,6 +172,31 @@ TEST_F(ControllerCoverageTest,
     EXPECT_EQ(frida_controller_get_state(controller), PROCESS_STATE_FAILED);
 }
 
+TEST_F(ControllerCoverageTest,
+       controller__reset_after_failed_attach__then_initialized_for_next_target) {
+    AttachSyncOverride override(attach_sync_timeout_stub);
+
+    controller = frida_controller_create("/tmp");
+    ASSERT_NE(controller, nullptr);
+    ASSERT_EQ(frida_controller_attach(controller, 1234), -1);
+    char reason[128];
+    EXPECT_GT(frida_controller_get_failure_reason(controller, reason, sizeof(reason)), 0);
+
+    ASSERT_EQ(frida_controller_reset(controller, "/tmp"), 0);
+    EXPECT_EQ(frida_controller_get_state(controller), PROCESS_STATE_INITIALIZED);
+    EXPECT_EQ(frida_controller_get_failure_reason(controller, reason, sizeof(reason)), 0);
+    TracerStats stats = frida_controller_get_stats(controller);
+    EXPECT_EQ(stats.events_captured, 0u);
+
+    // The same controller takes the next target, and fails it independently
+    attach_calls = 0;
+    EXPECT_EQ(frida_controller_attach(controller, 5678), -1);
+    EXPECT_EQ(attach_calls, 5);
+    ASSERT_GT(frida_controller_get_failure_reason(controller, reason, sizeof(reason)), 0);
+    EXPECT_NE(strstr(reason, "5678"), nullptr);
+    EXPECT_EQ(frida_controller_reset(controller, nullptr), 0);
+}
+
 // Test: controller__registry_disabled__then_no_registry_init
 TEST_F(ControllerCoverageTest, DISABLED_controller__registry_disabled__then_no_registry_init) {
     // Set environment variable to disable registry
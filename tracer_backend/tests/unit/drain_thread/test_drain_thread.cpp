@@ -4,8 +4,13 @@
 #include <cstdint>
 #include <cstring>
 #include <errno.h>
+#include <fcntl.h>
 #include <cstdlib>
+#include <fstream>
 #include <memory>
+#include <sstream>
+#include <sys/file.h>
+#include <sys/stat.h>
 #include <thread>
 #include <unistd.h>
 #include <vector>
@@ -16,6 +21,7 @@ extern "C" {
 #include <tracer_backend/atf/atf_thread_writer.h>
 #include <tracer_backend/drain_thread/drain_thread.h>
 #include <tracer_backend/utils/control_block_ipc.h>
+#include <tracer_backend/utils/ring_buffer.h>
 #include <tracer_backend/utils/thread_registry.h>
 
 void drain_thread_test_set_state(DrainThread *drain, DrainState state);
@@ -1116,6 +1122,280 @@ TEST(DrainThreadUnit,
     fclose(manifest_file);
   }
 
+  // The manifest is renamed into place, leaving no temporary file behind
+  std::string manifest_tmp_path = manifest_path + ".tmp";
+  EXPECT_NE(access(manifest_tmp_path.c_str(), F_OK), 0);
+
+  drain_thread_destroy(drain);
+  system(("rm -rf " + std::string(session_dir)).c_str());
+}
+
+TEST(DrainThreadUnit,
+     drain_thread__stop_session_with_drop_policy__then_manifest_records_policy) {
+  HookScope guard;
+  RegistryHarness harness(2);
+  DrainThread *drain = create_drain(harness, nullptr);
+  ASSERT_NE(drain, nullptr);
+
+  ControlBlock cb{};
+  cb_set_drop_policy(&cb, DROP_POLICY_BLOCK_PRODUCER);
+  drain_thread_set_control_block(drain, &cb);
+
+  const char* session_dir = "/tmp/ada_test_session_drop_policy";
+  system(("rm -rf " + std::string(session_dir)).c_str());
+  system(("mkdir -p " + std::string(session_dir)).c_str());
+
+  ASSERT_EQ(drain_thread_start_session(drain, session_dir), 0);
+  EXPECT_EQ(drain_thread_stop_session(drain), 0);
+
+  std::string manifest_path = std::string(session_dir) + "/manifest.json";
+  std::ifstream manifest(manifest_path);
+  ASSERT_TRUE(manifest.is_open());
+  std::stringstream contents;
+  contents << manifest.rdbuf();
+  EXPECT_NE(contents.str().find("\"drop_policy\": \"block_producer\""),
+            std::string::npos);
+
+  drain_thread_set_control_block(drain, nullptr);
+  drain_thread_destroy(drain);
+  system(("rm -rf " + std::string(session_dir)).c_str());
+}
+
+TEST(DrainThreadUnit,
+     drain_thread__stop_session__then_manifest_records_clock_readings) {
+  HookScope guard;
+  RegistryHarness harness(2);
+  DrainThread *drain = create_drain(harness, nullptr);
+  ASSERT_NE(drain, nullptr);
+
+  const char* session_dir = "/tmp/ada_test_session_wall_clock";
+  system(("rm -rf " + std::string(session_dir)).c_str());
+  system(("mkdir -p " + std::string(session_dir)).c_str());
+
+  struct timespec before;
+  clock_gettime(CLOCK_REALTIME, &before);
+  ASSERT_EQ(drain_thread_start_session(drain, session_dir), 0);
+  EXPECT_EQ(drain_thread_stop_session(drain), 0);
+
+  std::ifstream manifest(std::string(session_dir) + "/manifest.json");
+  ASSERT_TRUE(manifest.is_open());
+  std::stringstream contents;
+  contents << manifest.rdbuf();
+  const std::string text = contents.str();
+
+  auto read_u64 = [&text](const char* name) -> uint64_t {
+    const std::string key = std::string("\"") + name + "\": ";
+    size_t pos = text.find(key);
+    EXPECT_NE(pos, std::string::npos) << name;
+    return pos == std::string::npos
+               ? 0
+               : std::strtoull(text.c_str() + pos + key.size(), nullptr, 10);
+  };
+  uint64_t before_ns =
+      (uint64_t)before.tv_sec * 1000000000ull + (uint64_t)before.tv_nsec;
+  EXPECT_GE(read_u64("wall_clock_start_ns"), before_ns);
+  EXPECT_GE(read_u64("wall_clock_end_ns"), read_u64("wall_clock_start_ns"));
+  EXPECT_GE(read_u64("monotonic_end_ns"), read_u64("monotonic_start_ns"));
+
+  drain_thread_destroy(drain);
+  system(("rm -rf " + std::string(session_dir)).c_str());
+}
+
+TEST(DrainThreadUnit,
+     drain_thread__stop_session_with_children__then_manifest_links_them) {
+  HookScope guard;
+  RegistryHarness harness(2);
+  DrainThread *drain = create_drain(harness, nullptr);
+  ASSERT_NE(drain, nullptr);
+
+  EXPECT_EQ(drain_thread_add_child_session(drain, 42, "children/pid_42"), -EINVAL);
+
+  const char* session_dir = "/tmp/ada_test_session_children";
+  system(("rm -rf " + std::string(session_dir)).c_str());
+  system(("mkdir -p " + std::string(session_dir)).c_str());
+
+  ASSERT_EQ(drain_thread_start_session(drain, session_dir), 0);
+  for (uint32_t pid = 100; pid < 106; ++pid) {
+    std::string dir = "children/pid_" + std::to_string(pid);
+    ASSERT_EQ(drain_thread_add_child_session(drain, pid, dir.c_str()), 0);
+  }
+  EXPECT_EQ(drain_thread_stop_session(drain), 0);
+
+  std::string manifest_path = std::string(session_dir) + "/manifest.json";
+  std::ifstream manifest(manifest_path);
+  ASSERT_TRUE(manifest.is_open());
+  std::stringstream contents;
+  contents << manifest.rdbuf();
+  EXPECT_NE(contents.str().find("{\"pid\": 100, \"dir\": \"children/pid_100\"},"),
+            std::string::npos);
+  EXPECT_NE(contents.str().find("{\"pid\": 105, \"dir\": \"children/pid_105\"}\n"),
+            std::string::npos);
+
+  // The next session starts without children
+  ASSERT_EQ(drain_thread_start_session(drain, session_dir), 0);
+  EXPECT_EQ(drain_thread_stop_session(drain), 0);
+  std::ifstream second(manifest_path);
+  std::stringstream second_contents;
+  second_contents << second.rdbuf();
+  EXPECT_EQ(second_contents.str().find("\"children\""), std::string::npos);
+
+  drain_thread_destroy(drain);
+  system(("rm -rf " + std::string(session_dir)).c_str());
+}
+
+TEST(DrainThreadUnit,
+     drain_thread__flush_while_running__then_active_ring_on_disk) {
+  HookScope guard;
+  RegistryHarness harness(2);
+  ThreadLaneSet *lanes = thread_registry_register(harness.registry, 0xF1F1);
+  ASSERT_NE(lanes, nullptr);
+  DrainThread *drain = create_drain(harness, nullptr);
+  ASSERT_NE(drain, nullptr);
+
+  const char* session_dir = "/tmp/ada_test_session_flush";
+  system(("rm -rf " + std::string(session_dir)).c_str());
+  system(("mkdir -p " + std::string(session_dir)).c_str());
+
+  ASSERT_EQ(drain_thread_start_session(drain, session_dir), 0);
+  ASSERT_EQ(drain_thread_start(drain), 0);
+
+  // Leave the event in the active ring; nothing submits it for draining
+  RingBufferHeader *hdr = thread_registry_get_active_ring_header(
+      harness.registry, thread_lanes_get_index_lane(lanes));
+  ASSERT_NE(hdr, nullptr);
+  IndexEvent event{};
+  event.timestamp = 42;
+  event.function_id = 0x100000001ull;
+  event.event_kind = EVENT_KIND_CALL;
+  ASSERT_TRUE(ring_buffer_write_raw(hdr, sizeof(IndexEvent), &event));
+
+  EXPECT_EQ(drain_thread_flush(drain), 0);
+
+  DrainMetrics metrics{};
+  drain_thread_get_metrics(drain, &metrics);
+  EXPECT_EQ(metrics.total_events_drained, 1u);
+  EXPECT_EQ(metrics.total_bytes_drained, sizeof(IndexEvent));
+
+  // 64-byte header plus the synced event, before any footer is written
+  struct stat st{};
+  std::string index_path = std::string(session_dir) + "/thread_0/index.atf";
+  ASSERT_EQ(stat(index_path.c_str(), &st), 0);
+  EXPECT_EQ(st.st_size, 64 + 32);
+
+  EXPECT_EQ(drain_thread_stop(drain), 0);
+  drain_thread_destroy(drain);
+  system(("rm -rf " + std::string(session_dir)).c_str());
+}
+
+TEST(DrainThreadUnit, drain_thread__flush_null__then_einval) {
+  EXPECT_EQ(drain_thread_flush(nullptr), -EINVAL);
+}
+
+TEST(DrainThreadUnit,
+     drain_thread__snapshot_while_running__then_finalized_copy) {
+  HookScope guard;
+  RegistryHarness harness(2);
+  ThreadLaneSet *lanes = thread_registry_register(harness.registry, 0xF2F2);
+  ASSERT_NE(lanes, nullptr);
+  DrainThread *drain = create_drain(harness, nullptr);
+  ASSERT_NE(drain, nullptr);
+
+  const std::string session_dir = "/tmp/ada_test_session_snapshot";
+  const std::string snapshot_dir = "/tmp/ada_test_session_snapshot_copy";
+  system(("rm -rf " + session_dir + " " + snapshot_dir).c_str());
+  system(("mkdir -p " + session_dir).c_str());
+
+  EXPECT_EQ(drain_thread_snapshot(drain, snapshot_dir.c_str()), -EINVAL);
+
+  ASSERT_EQ(drain_thread_start_session(drain, session_dir.c_str()), 0);
+  ASSERT_EQ(drain_thread_start(drain), 0);
+
+  RingBufferHeader *hdr = thread_registry_get_active_ring_header(
+      harness.registry, thread_lanes_get_index_lane(lanes));
+  ASSERT_NE(hdr, nullptr);
+  IndexEvent event{};
+  event.timestamp = 42;
+  event.function_id = 0x100000001ull;
+  event.event_kind = EVENT_KIND_CALL;
+  ASSERT_TRUE(ring_buffer_write_raw(hdr, sizeof(IndexEvent), &event));
+
+  EXPECT_EQ(drain_thread_snapshot(drain, snapshot_dir.c_str()), 0);
+
+  // The copy carries a finalized header and footer
+  std::string index_path = snapshot_dir + "/thread_0/index.atf";
+  struct stat st{};
+  ASSERT_EQ(stat(index_path.c_str(), &st), 0);
+  EXPECT_EQ(st.st_size, 64 + 32 + 64);
+  unsigned char header[64];
+  FILE *index = fopen(index_path.c_str(), "rb");
+  ASSERT_NE(index, nullptr);
+  ASSERT_EQ(fread(header, sizeof(header), 1, index), 1u);
+  fclose(index);
+  uint32_t event_count = 0;
+  uint64_t footer_offset = 0;
+  uint64_t time_start_ns = 0;
+  memcpy(&event_count, header + 28, sizeof(event_count));
+  memcpy(&footer_offset, header + 40, sizeof(footer_offset));
+  memcpy(&time_start_ns, header + 48, sizeof(time_start_ns));
+  EXPECT_EQ(event_count, 1u);
+  EXPECT_EQ(footer_offset, 64u + 32u);
+  EXPECT_EQ(time_start_ns, 42u);
+
+  std::ifstream manifest(snapshot_dir + "/manifest.json");
+  ASSERT_TRUE(manifest.is_open());
+  std::stringstream contents;
+  contents << manifest.rdbuf();
+  EXPECT_NE(contents.str().find("{\"id\": 0, \"has_detail\": true}"), std::string::npos);
+
+  // The live session is untouched and keeps capturing
+  std::string live_path = session_dir + "/thread_0/index.atf";
+  ASSERT_EQ(stat(live_path.c_str(), &st), 0);
+  EXPECT_EQ(st.st_size, 64 + 32);
+  EXPECT_EQ(access((session_dir + "/manifest.json").c_str(), F_OK), -1);
+  EXPECT_EQ(drain_thread_get_state(drain), DRAIN_STATE_RUNNING);
+
+  // Existing snapshots and the session itself are never overwritten
+  EXPECT_EQ(drain_thread_snapshot(drain, snapshot_dir.c_str()), -EEXIST);
+  EXPECT_EQ(drain_thread_snapshot(drain, session_dir.c_str()), -EINVAL);
+
+  EXPECT_EQ(drain_thread_stop(drain), 0);
+  drain_thread_destroy(drain);
+  system(("rm -rf " + session_dir + " " + snapshot_dir).c_str());
+}
+
+TEST(DrainThreadUnit, drain_thread__snapshot_null__then_einval) {
+  EXPECT_EQ(drain_thread_snapshot(nullptr, "/tmp/snapshot"), -EINVAL);
+}
+
+TEST(DrainThreadUnit,
+     drain_thread__active_session__then_holds_session_lock) {
+  HookScope guard;
+  RegistryHarness harness(2);
+  DrainThread *drain = create_drain(harness, nullptr);
+  DrainThread *other = create_drain(harness, nullptr);
+  ASSERT_NE(drain, nullptr);
+  ASSERT_NE(other, nullptr);
+
+  const char* session_dir = "/tmp/ada_test_session_lock";
+  system(("rm -rf " + std::string(session_dir)).c_str());
+  system(("mkdir -p " + std::string(session_dir)).c_str());
+
+  ASSERT_EQ(drain_thread_start_session(drain, session_dir), 0);
+
+  // A second writer is refused and a reader's shared lock would block
+  EXPECT_EQ(drain_thread_start_session(other, session_dir), -EBUSY);
+  std::string lock_path = std::string(session_dir) + "/.lock";
+  int reader_fd = open(lock_path.c_str(), O_RDONLY);
+  ASSERT_GE(reader_fd, 0);
+  EXPECT_NE(flock(reader_fd, LOCK_SH | LOCK_NB), 0);
+  EXPECT_EQ(errno, EWOULDBLOCK);
+
+  // Stopping the session releases the lock
+  EXPECT_EQ(drain_thread_stop_session(drain), 0);
+  EXPECT_EQ(flock(reader_fd, LOCK_SH | LOCK_NB), 0);
+  close(reader_fd);
+
+  drain_thread_destroy(other);
   drain_thread_destroy(drain);
   system(("rm -rf " + std::string(session_dir)).c_str());
 }
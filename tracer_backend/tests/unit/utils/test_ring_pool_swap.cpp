@@ -153,3 +153,33 @@ TEST(RingPoolSwap, ring_pool__exhaustion_no_oldest__then_handle_returns_false) {
 
     ring_pool_destroy(pool);
 }
+
+TEST(RingPoolSwap, ring_pool__drop_newest_exhausted__then_swap_fails_and_keeps_submitted) {
+    size_t size = 0; auto arena = alloc_registry(size);
+    auto* reg = thread_registry_init_with_capacity(arena.get(), size, 2);
+    ASSERT_NE(reg, nullptr);
+    ASSERT_NE(thread_registry_attach(reg), nullptr);
+    ThreadLaneSet* lanes = thread_registry_register(reg, 0xFFAA);
+    ASSERT_NE(lanes, nullptr);
+
+    RingPool* pool = ring_pool_create(reg, lanes, 0);
+    ASSERT_NE(pool, nullptr);
+    EXPECT_EQ(ring_pool_get_drop_policy(pool), DROP_POLICY_DROP_OLDEST);
+    ring_pool_set_drop_policy(pool, DROP_POLICY_DROP_NEWEST);
+    EXPECT_EQ(ring_pool_get_drop_policy(pool), DROP_POLICY_DROP_NEWEST);
+    Lane* idx_lane = thread_lanes_get_index_lane(lanes);
+    ASSERT_NE(idx_lane, nullptr);
+
+    // Swap until the free queue runs dry; nothing drains the submitted rings
+    uint32_t old = UINT32_MAX;
+    int swaps = 0;
+    while (ring_pool_swap_active(pool, &old)) {
+        ASSERT_LT(++swaps, 64);
+    }
+
+    // The pool must not have reclaimed a submitted ring to make room
+    EXPECT_EQ(lane_get_free_ring(idx_lane), UINT32_MAX);
+    EXPECT_NE(lane_take_ring(idx_lane), UINT32_MAX);
+
+    ring_pool_destroy(pool);
+}
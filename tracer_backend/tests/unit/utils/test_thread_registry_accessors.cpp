@@ -121,4 +121,43 @@ TEST_F(ThreadRegistryAccessors, ThreadLanesAccessors) {
 
     // Cleanup
     thread_registry_unregister(lanes);
-}
\ No newline at end of file
+}
+TEST_F(ThreadRegistryAccessors, thread_registry_get_slot__exited_thread__then_listed_inactive) {
+    EXPECT_EQ(thread_registry_get_slot(registry_, 0), nullptr);
+    EXPECT_EQ(thread_registry_get_slot(nullptr, 0), nullptr);
+
+    ThreadLaneSet* lanes = thread_registry_register(registry_, 7001);
+    ASSERT_NE(lanes, nullptr);
+    uint32_t slot = thread_lanes_get_slot_index(lanes);
+    EXPECT_EQ(thread_registry_get_slot(registry_, slot), lanes);
+    EXPECT_TRUE(thread_lanes_is_active(lanes));
+
+    thread_registry_unregister(lanes);
+    EXPECT_EQ(thread_registry_get_slot(registry_, slot), lanes);
+    EXPECT_FALSE(thread_lanes_is_active(lanes));
+    EXPECT_EQ(thread_registry_get_slot(registry_, thread_registry_get_capacity(registry_)),
+              nullptr);
+}
+
+TEST_F(ThreadRegistryAccessors, thread_lanes_set_name__long_name__then_truncated) {
+    ThreadLaneSet* lanes = thread_registry_register(registry_, 7002);
+    ASSERT_NE(lanes, nullptr);
+
+    char name[ADA_THREAD_NAME_MAX];
+    thread_lanes_get_name(lanes, name, sizeof(name));
+    EXPECT_STREQ(name, "");
+
+    thread_lanes_set_name(lanes, "com.example.worker-queue");
+    thread_lanes_get_name(lanes, name, sizeof(name));
+    EXPECT_STREQ(name, "com.example.wor");
+
+    char small[4];
+    thread_lanes_get_name(lanes, small, sizeof(small));
+    EXPECT_STREQ(small, "com");
+
+    thread_lanes_set_name(lanes, nullptr);
+    thread_lanes_get_name(lanes, name, sizeof(name));
+    EXPECT_STREQ(name, "");
+
+    thread_registry_unregister(lanes);
+}